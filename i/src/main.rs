@@ -19,13 +19,25 @@ pub struct Opt {
 fn main() {
     let opt = Opt::from_args();
     let ir = fs::read_to_string(opt.ir_file).expect("failed to load *.ll file");
-    let module = module::parse_assembly(ir.as_str()).expect("failed to parse LLVM Assembly");
+    let module = match module::parse_assembly(ir.as_str()) {
+        Ok(module) => module,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1)
+        }
+    };
     let main = module
         .find_function_by_name("main")
         .expect("failed to lookup 'main'");
     let ctx = interpreter::Context::new(&module)
         .with_libs(opt.libs)
         .expect("failed to load library");
-    let ret = interpreter::run_function(&ctx, main, vec![]);
-    process::exit(ret.expect("unknown error").sext_to_i64().unwrap_or(0) as i32)
+    let ret = match interpreter::run_function(&ctx, main, vec![]) {
+        Ok(ret) => ret,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1)
+        }
+    };
+    process::exit(ret.sext_to_i64().unwrap_or(0) as i32)
 }
@@ -21,7 +21,13 @@ pub struct Opt {
 fn main() {
     let opt = Opt::from_args();
     let ir = fs::read_to_string(opt.ir_file.as_str()).expect("failed to load *.ll file");
-    let module = module::parse_assembly(ir.as_str()).expect("failed to parse LLVM Assembly");
+    let module = match module::parse_assembly(ir.as_str()) {
+        Ok(module) => module,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1)
+        }
+    };
     let module = compile_module(X86_64, &module).expect("failed to compile module");
     let asm_file_name = unique_file_name("s");
     let mut output =
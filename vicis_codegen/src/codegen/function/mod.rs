@@ -6,12 +6,20 @@ pub mod slot;
 
 use super::{call_conv::CallConvKind, isa::TargetIsa};
 use crate::codegen::function::instruction::InstructionInfo;
+use basic_block::BasicBlock;
+use id_arena::Id;
 use instruction::InstructionId;
 use std::fmt;
-use vicis_ir::ir::{
-    function::Parameter,
-    module::{attributes::Attribute, preemption_specifier::PreemptionSpecifier},
-    types::{TypeId, Types},
+use vicis_ir::{
+    ir::{
+        function::Parameter,
+        module::{
+            attributes::Attribute, data_layout::DataLayout,
+            preemption_specifier::PreemptionSpecifier,
+        },
+        types::{TypeId, Types},
+    },
+    traits::basic_block::{BasicBlockData, BasicBlockLayout},
 };
 
 pub struct Function<T: TargetIsa> {
@@ -25,12 +33,18 @@ pub struct Function<T: TargetIsa> {
     pub layout: layout::Layout<<T::InstInfo as InstructionInfo>::Data>,
     pub slots: slot::Slots<T>,
     pub types: Types,
+    pub data_layout: DataLayout,
     pub is_prototype: bool,
     pub call_conv: CallConvKind,
     pub isa: T,
 }
 
 impl<T: TargetIsa> Function<T> {
+    /// Returns the size in bytes of a stack slot for `ty`.
+    pub fn type_size(&self, ty: TypeId) -> u32 {
+        self.data_layout.alloc_size_of(&self.types, ty) as u32
+    }
+
     pub fn remove_inst(
         &mut self,
         inst: InstructionId<<T::InstInfo as InstructionInfo>::Data>,
@@ -39,6 +53,18 @@ impl<T: TargetIsa> Function<T> {
     }
 }
 
+impl<T: TargetIsa> BasicBlockData<BasicBlock> for Function<T> {
+    fn get(&self, id: Id<BasicBlock>) -> &BasicBlock {
+        &self.data.basic_blocks[id]
+    }
+}
+
+impl<T: TargetIsa> BasicBlockLayout<BasicBlock> for Function<T> {
+    fn order(&self) -> Box<dyn Iterator<Item = Id<BasicBlock>> + '_> {
+        Box::new(self.layout.block_iter())
+    }
+}
+
 impl<T: TargetIsa> fmt::Debug for Function<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.is_prototype {
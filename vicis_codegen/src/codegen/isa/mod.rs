@@ -8,7 +8,7 @@ use crate::codegen::{
     register::{RegisterClass, RegisterInfo},
 };
 use anyhow::Result;
-use vicis_ir::ir::types::{TypeId, Types};
+use vicis_ir::ir::module::data_layout::DataLayout;
 
 pub trait TargetIsa: Copy {
     type InstInfo: InstructionInfo;
@@ -18,5 +18,6 @@ pub trait TargetIsa: Copy {
 
     fn module_pass_list() -> Vec<fn(&mut Module<Self>) -> Result<()>>;
     fn default_call_conv() -> CallConvKind;
-    fn type_size(types: &Types, ty: TypeId) -> u32;
+    /// The data layout to use for modules that don't specify one
+    fn default_data_layout() -> DataLayout;
 }
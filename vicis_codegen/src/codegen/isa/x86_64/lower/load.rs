@@ -5,14 +5,13 @@ use crate::codegen::{
         instruction::{InstructionData, Opcode, Operand as MOperand, OperandData},
         X86_64,
     },
-    isa::TargetIsa,
     lower::{LoweringContext, LoweringError},
 };
 use anyhow::Result;
 use vicis_ir::ir::{
     function::instruction::{InstructionId, Opcode as IrOpcode},
     types::{Type, TypeId},
-    value::{ConstantData, ConstantInt, Value, ValueId},
+    value::{ConstantData, Value, ValueId},
 };
 
 pub fn lower_load(
@@ -100,7 +99,7 @@ fn lower_load_gep(
     _align: u32,
     sext: Option<InstructionId>,
 ) -> Result<()> {
-    use {Constant as Const, ConstantData::Int, ConstantInt::Int64, Value::Constant};
+    use {Constant as Const, ConstantData::Int, Value::Constant};
 
     let gep = &ctx.ir_data.instructions[gep_id];
 
@@ -114,12 +113,14 @@ fn lower_load_gep(
     let mem;
 
     match &gep_args[..] {
-        [Value::Instruction(base_ptr), Const(Int(Int64(idx0))), Const(Int(Int64(idx1)))] => {
+        [Value::Instruction(base_ptr), Const(Int(idx0)), Const(Int(idx1))]
+            if idx0.bits() == 64 && idx1.bits() == 64 =>
+        {
+            let (idx0, idx1) = (idx0.as_i64().unwrap(), idx1.as_i64().unwrap());
             let base_ptr = ctx.inst_id_to_slot_id[base_ptr];
             let base_ty = gep.operand.types()[0];
-            let offset = idx0 * X86_64::type_size(ctx.types, base_ty) as i64
-                + idx1
-                    * X86_64::type_size(ctx.types, ctx.types.get_element(base_ty).unwrap()) as i64;
+            let offset = idx0 * ctx.type_size(base_ty) as i64
+                + idx1 * ctx.type_size(ctx.types.get_element(base_ty).unwrap()) as i64;
             // debug!(offset);
 
             mem = vec![
@@ -131,18 +132,21 @@ fn lower_load_gep(
                 MOperand::new(OperandData::None),
             ];
         }
-        [Value::Instruction(base_ptr), Const(Int(Int64(idx0))), Value::Instruction(idx1)] => {
+        [Value::Instruction(base_ptr), Const(Int(idx0)), Value::Instruction(idx1)]
+            if idx0.bits() == 64 =>
+        {
+            let idx0 = idx0.as_i64().unwrap();
             let base_ptr = ctx.inst_id_to_slot_id[base_ptr];
 
             let base_ty = gep.operand.types()[0];
-            let offset = idx0 * X86_64::type_size(ctx.types, base_ty) as i64;
+            let offset = idx0 * ctx.type_size(base_ty) as i64;
             // debug!(offset);
 
             let idx1_ty = gep.operand.types()[3];
             assert_eq!(*ctx.types.get(idx1_ty), Type::Int(64));
             let idx1 = get_or_generate_inst_output(ctx, idx1_ty, *idx1)?;
 
-            assert!(X86_64::type_size(ctx.types, ctx.types.get_element(base_ty).unwrap()) == 4);
+            assert!(ctx.type_size(ctx.types.get_element(base_ty).unwrap()) == 4);
 
             mem = vec![
                 MOperand::new(OperandData::MemStart),
@@ -150,10 +154,9 @@ fn lower_load_gep(
                 MOperand::new(OperandData::Int32(offset as i32)),
                 MOperand::input(OperandData::None),
                 MOperand::input(OperandData::VReg(idx1)),
-                MOperand::new(OperandData::Int32(X86_64::type_size(
-                    ctx.types,
-                    ctx.types.get_element(base_ty).unwrap(),
-                ) as i32)),
+                MOperand::new(OperandData::Int32(
+                    ctx.type_size(ctx.types.get_element(base_ty).unwrap()) as i32,
+                )),
             ];
         }
         _ => return Err(LoweringError::Todo.into()),
@@ -8,7 +8,6 @@ use crate::codegen::{
         register::{RegClass, RegInfo, GR32},
         X86_64,
     },
-    isa::TargetIsa,
     lower::{Lower as LowerTrait, LoweringContext, LoweringError},
     register::{Reg, RegisterClass, RegisterInfo, VReg},
 };
@@ -20,14 +19,15 @@ use vicis_ir::ir::{
         basic_block::BasicBlockId,
         data::Data as IrData,
         instruction::{
-            Alloca, Br, Call, Cast, CondBr, ICmp, ICmpCond, Instruction as IrInstruction,
-            InstructionId, IntBinary, Load, Opcode as IrOpcode, Operand, Phi, Ret, Store,
+            Alloca, AtomicOrdering, Br, Call, Cast, CondBr, ICmp, ICmpCond,
+            Instruction as IrInstruction, InstructionId, IntBinary, Load, Opcode as IrOpcode,
+            Operand, Phi, Ret, Store,
         },
         Parameter,
     },
     module::name::Name,
     types::{Type, TypeId},
-    value::{ConstantData, ConstantExpr, ConstantInt, Value, ValueId},
+    value::{ConstantData, ConstantExpr, Value, ValueId},
 };
 
 #[derive(Clone, Copy)]
@@ -83,16 +83,20 @@ fn lower(ctx: &mut LoweringContext<X86_64>, inst: &IrInstruction) -> Result<()>
             ref args,
             ref blocks,
         }) => lower_phi(ctx, inst.id.unwrap(), ty, args, blocks),
+        // Plain `mov`s already have acquire/release semantics on x86-64
         Operand::Load(Load {
             ref tys,
             addr,
             align,
+            ..
         }) => lower_load(ctx, inst.id.unwrap(), tys, addr, align),
         Operand::Store(Store {
             ref tys,
             ref args,
             align,
-        }) => lower_store(ctx, tys, args, align),
+            ordering,
+            ..
+        }) if ordering != AtomicOrdering::SeqCst => lower_store(ctx, tys, args, align),
         Operand::IntBinary(IntBinary { ty, ref args, .. }) => {
             lower_bin(ctx, inst.id.unwrap(), inst.opcode, ty, args)
         }
@@ -117,9 +121,8 @@ fn lower_alloca(
     _num_elements: &ConstantData,
     _align: u32,
 ) -> Result<()> {
-    let slot_id = ctx
-        .slots
-        .add_slot(tys[0], X86_64::type_size(ctx.types, tys[0]));
+    let size = ctx.type_size(tys[0]);
+    let slot_id = ctx.slots.add_slot(tys[0], size);
     ctx.inst_id_to_slot_id.insert(id, slot_id);
     Ok(())
 }
@@ -280,7 +283,8 @@ fn lower_condbr(
         let lhs = val_to_vreg(ctx, *ty, args[0])?;
         let rhs = ctx.ir_data.value_ref(args[1]);
         match rhs {
-            Value::Constant(ConstantData::Int(ConstantInt::Int32(rhs))) => {
+            Value::Constant(ConstantData::Int(rhs)) if rhs.bits() == 32 => {
+                let rhs = rhs.as_i32().unwrap();
                 ctx.inst_seq.push(MachInstruction::new(
                     InstructionData {
                         opcode: Opcode::CMPri32,
@@ -446,7 +450,9 @@ fn val_to_operand_data(
     match ctx.ir_data.values[val] {
         Value::Instruction(id) => Ok(get_or_generate_inst_output(ctx, ty, id)?.into()),
         Value::Argument(idx) => Ok(ctx.arg_idx_to_vreg[&idx].into()),
-        Value::Constant(ConstantData::Int(ConstantInt::Int32(i))) => Ok(OperandData::Int32(i)),
+        Value::Constant(ConstantData::Int(ref i)) if i.bits() == 32 => {
+            Ok(OperandData::Int32(i.as_i32().unwrap()))
+        }
         Value::Constant(ConstantData::Expr(ConstantExpr::GetElementPtr {
             inbounds: _,
             tys: _,
@@ -457,7 +463,7 @@ fn val_to_operand_data(
             assert!(matches!(args[0], ConstantData::GlobalRef(_)));
             let all_indices_0 = args[1..]
                 .iter()
-                .all(|arg| matches!(arg, ConstantData::Int(ConstantInt::Int64(0))));
+                .all(|arg| matches!(arg, ConstantData::Int(i) if i.as_i64() == Some(0)));
             assert!(all_indices_0);
             let src = OperandData::GlobalAddress(args[0].as_global_ref().as_string().clone());
             let dst = ctx.mach_data.vregs.add_vreg_data(ty);
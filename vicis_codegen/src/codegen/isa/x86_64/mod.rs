@@ -7,7 +7,11 @@ pub mod register;
 use super::TargetIsa;
 use crate::codegen::{call_conv::CallConvKind, isa::x86_64, module::Module, pass::regalloc};
 use anyhow::Result;
-use vicis_ir::ir::types::{ArrayType, Type, TypeId, Types};
+use vicis_ir::ir::module::data_layout::{self, DataLayout};
+
+/// The data layout of x86-64 System V targets.
+pub const DATA_LAYOUT_STR: &str =
+    "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128";
 
 #[derive(Copy, Clone)]
 pub struct X86_64;
@@ -32,18 +36,7 @@ impl TargetIsa for X86_64 {
         CallConvKind::SystemV
     }
 
-    fn type_size(types: &Types, ty: TypeId) -> u32 {
-        match &*types.get(ty) {
-            Type::Void => 0,
-            Type::Int(n) => *n / 8,
-            Type::Pointer(_) => 8,
-            Type::Array(ArrayType {
-                inner,
-                num_elements,
-            }) => Self::type_size(types, *inner) * num_elements,
-            Type::Function(_) => 0,
-            Type::Struct(_) => todo!(),
-            Type::Metadata => todo!(),
-        }
+    fn default_data_layout() -> DataLayout {
+        data_layout::parse(DATA_LAYOUT_STR).unwrap().1
     }
 }
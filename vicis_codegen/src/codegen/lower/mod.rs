@@ -15,7 +15,7 @@ use super::{
 use anyhow::Result;
 use id_arena::Arena;
 use rustc_hash::{FxHashMap, FxHashSet};
-use std::{error::Error, fmt, mem};
+use std::{error::Error, fmt, iter, mem};
 use vicis_ir::ir::{
     function::{
         basic_block::BasicBlockId as IrBasicBlockId,
@@ -23,8 +23,8 @@ use vicis_ir::ir::{
         instruction::{Instruction as IrInstruction, InstructionId as IrInstructionId, Opcode},
         Function as IrFunction, Parameter,
     },
-    module::Module as IrModule,
-    types::Types,
+    module::{data_layout::DataLayout, Module as IrModule},
+    types::{TypeId, Types},
 };
 
 pub trait Lower<T: TargetIsa> {
@@ -41,6 +41,7 @@ pub struct LoweringContext<'a, T: TargetIsa> {
     pub arg_idx_to_vreg: &'a mut FxHashMap<usize, VReg>,
     pub inst_seq: &'a mut Vec<MachInstruction<<T::InstInfo as II>::Data>>,
     pub types: &'a Types,
+    pub data_layout: &'a DataLayout,
     pub inst_id_to_vreg: &'a mut FxHashMap<IrInstructionId, VReg>,
     pub merged_inst: &'a mut FxHashSet<IrInstructionId>,
     pub block_map: &'a FxHashMap<IrBasicBlockId, MachBasicBlockId>,
@@ -51,13 +52,19 @@ pub struct LoweringContext<'a, T: TargetIsa> {
 #[derive(Debug)]
 pub enum LoweringError {
     Todo,
+    UnsupportedType(String),
 }
 
 pub fn compile_module<T: TargetIsa>(isa: T, module: &IrModule) -> Result<MachModule<T>> {
     let mut functions = Arena::new();
+    let data_layout = if module.target().datalayout().is_empty() {
+        T::default_data_layout()
+    } else {
+        module.data_layout().clone()
+    };
 
     for (_, function) in module.functions() {
-        functions.alloc(compile_function(isa, function)?);
+        functions.alloc(compile_function(isa, function, &data_layout)?);
     }
 
     let mut mach_module = MachModule {
@@ -78,7 +85,29 @@ pub fn compile_module<T: TargetIsa>(isa: T, module: &IrModule) -> Result<MachMod
     Ok(mach_module)
 }
 
-pub fn compile_function<T: TargetIsa>(isa: T, function: &IrFunction) -> Result<MachFunction<T>> {
+pub fn compile_function<T: TargetIsa>(
+    isa: T,
+    function: &IrFunction,
+    data_layout: &DataLayout,
+) -> Result<MachFunction<T>> {
+    // No ISA can lower vectors yet
+    let vector_ty = function
+        .params()
+        .iter()
+        .map(|param| param.ty)
+        .chain(iter::once(function.result_ty))
+        .chain(
+            function
+                .layout
+                .block_iter()
+                .flat_map(|block| function.layout.inst_iter(block))
+                .flat_map(|inst| function.data.inst_ref(inst).operand.types().iter().copied()),
+        )
+        .find(|&ty| function.types.is_vector(ty));
+    if let Some(ty) = vector_ty {
+        return Err(LoweringError::UnsupportedType(function.types.to_string(ty)).into());
+    }
+
     let mut slots = Slots::new(isa);
     let mut data = Data::new();
     let mut layout = Layout::new();
@@ -126,6 +155,7 @@ pub fn compile_function<T: TargetIsa>(isa: T, function: &IrFunction) -> Result<M
                     inst_seq: &mut prologue_seq,
                     arg_idx_to_vreg: &mut arg_idx_to_vreg,
                     types: &function.types,
+                    data_layout,
                     inst_id_to_vreg: &mut inst_id_to_vreg,
                     merged_inst: &mut merged_inst,
                     block_map: &block_map,
@@ -152,6 +182,7 @@ pub fn compile_function<T: TargetIsa>(isa: T, function: &IrFunction) -> Result<M
                     inst_seq: &mut prologue_seq,
                     arg_idx_to_vreg: &mut arg_idx_to_vreg,
                     types: &function.types,
+                    data_layout,
                     inst_id_to_vreg: &mut inst_id_to_vreg,
                     merged_inst: &mut merged_inst,
                     block_map: &block_map,
@@ -190,6 +221,7 @@ pub fn compile_function<T: TargetIsa>(isa: T, function: &IrFunction) -> Result<M
                     inst_seq: &mut inst_seq,
                     arg_idx_to_vreg: &mut arg_idx_to_vreg,
                     types: &function.types,
+                    data_layout,
                     inst_id_to_vreg: &mut inst_id_to_vreg,
                     merged_inst: &mut merged_inst,
                     block_map: &block_map,
@@ -223,6 +255,7 @@ pub fn compile_function<T: TargetIsa>(isa: T, function: &IrFunction) -> Result<M
         layout,
         slots,
         types: function.types.clone(),
+        data_layout: data_layout.clone(),
         is_prototype: function.is_prototype(),
         isa,
         call_conv,
@@ -241,6 +274,11 @@ impl<'a, T: TargetIsa> LoweringContext<'a, T> {
     pub fn is_merged(&self, inst: IrInstructionId) -> bool {
         self.merged_inst.contains(&inst)
     }
+
+    /// Returns the size in bytes `ty` takes in memory.
+    pub fn type_size(&self, ty: TypeId) -> u32 {
+        self.data_layout.alloc_size_of(self.types, ty) as u32
+    }
 }
 
 impl Error for LoweringError {}
@@ -249,6 +287,7 @@ impl fmt::Display for LoweringError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Todo => write!(f, "Todo"),
+            Self::UnsupportedType(ty) => write!(f, "Unsupported type: {}", ty),
         }
     }
 }
@@ -24,10 +24,8 @@ impl<'a, T: TargetIsa> Spiller<'a, T> {
     pub fn spill(&mut self, vreg: VReg, new_vregs: &mut Vec<VReg>) {
         let ty = self.function.data.vregs.type_for(vreg);
         assert!(*self.function.types.get(ty) == Type::Int(32));
-        let slot = self
-            .function
-            .slots
-            .add_slot(ty, T::type_size(&self.function.types, ty));
+        let size = self.function.type_size(ty);
+        let slot = self.function.slots.add_slot(ty, size);
 
         self.insert_spill(vreg, slot, new_vregs);
         self.insert_reload(vreg, slot, new_vregs);
@@ -40,3 +40,32 @@ fn compile_tests() {
 
     pb.finish();
 }
+
+#[test]
+fn unsupported_vector_type() {
+    let module = module::parse_assembly(
+        r#"
+define <4 x i32> @f(<4 x i32> %0) {
+  %2 = add <4 x i32> %0, %0
+  ret <4 x i32> %2
+}"#,
+    )
+    .unwrap();
+    let err = compile_module(X86_64, &module).err().unwrap();
+    assert_eq!(err.to_string(), "Unsupported type: <4 x i32>");
+}
+
+#[test]
+fn module_data_layout() {
+    let src = r#"
+define i32 @main() {
+  %1 = alloca i64, align 8
+  ret i32 0
+}"#;
+    for (datalayout, size) in [("", 8), ("target datalayout = \"e-i64:128\"", 16)] {
+        let module = module::parse_assembly(&format!("{}\n{}", datalayout, src)).unwrap();
+        let mach_module = compile_module(X86_64, &module).unwrap();
+        let (_, func) = mach_module.functions.iter().next().unwrap();
+        assert_eq!(func.slots.unaligned_size(), size, "{}", datalayout);
+    }
+}
@@ -0,0 +1,26 @@
+use vicis_codegen::codegen::{isa::x86_64::X86_64, lower::compile_module};
+use vicis_ir::{
+    ir::module,
+    pass::analysis::{dom_tree::DominatorTree, loop_info::LoopInfo},
+};
+
+#[test]
+fn machine_function_loops() {
+    let module = module::parse_assembly(include_str!("./codegen/sum.ll")).unwrap();
+    let mach_module = compile_module(X86_64, &module).unwrap();
+    let (_, func) = mach_module
+        .functions
+        .iter()
+        .find(|(_, f)| f.name == "main")
+        .unwrap();
+    let loop_info = LoopInfo::new(func, &DominatorTree::new(func));
+
+    assert_eq!(loop_info.loops().count(), 1);
+    let l = loop_info.get(loop_info.top_level_loops()[0]);
+    assert_eq!(l.blocks().len(), 3);
+    assert_eq!(l.latches().len(), 1);
+    assert_eq!(l.exit_blocks().len(), 1);
+    assert!(l.preheader().is_some());
+    assert_eq!(loop_info.depth_of(l.header()), 1);
+    assert_eq!(loop_info.depth_of(l.exit_blocks()[0]), 0);
+}
@@ -5,8 +5,11 @@ pub enum GenericValue {
     Void,
     Int1(bool),
     Int8(i8),
+    Int16(i16),
     Int32(i32),
     Int64(i64),
+    Float(f32),
+    Double(f64),
     Ptr(*mut u8),
     Id([u8; 16]),
 }
@@ -37,15 +40,17 @@ impl GenericValue {
         match self {
             Self::Int1(i) => Some(*i as i64),
             Self::Int8(i) => Some(*i as i64),
+            Self::Int16(i) => Some(*i as i64),
             Self::Int32(i) => Some(*i as i64),
             Self::Int64(i) => Some(*i),
             _ => None,
         }
     }
 
-    pub fn to_id<T>(&self) -> Option<&T> {
+    pub fn to_id<T: Copy>(&self) -> Option<T> {
         match self {
-            Self::Id(id) => Some(unsafe { &*(id.as_ptr() as *const T) }),
+            // `id` is not necessarily aligned for `T`
+            Self::Id(id) => Some(unsafe { (id.as_ptr() as *const T).read_unaligned() }),
             _ => None,
         }
     }
@@ -4,13 +4,14 @@ use super::Context;
 use crate::generic_value::GenericValue;
 use vicis_ir::ir::{
     function::{instruction::InstructionId, Function},
-    value::{ConstantData, ConstantExpr, ConstantInt, Value, ValueId},
+    value::{ConstantData, ConstantExpr, ConstantFloat, Value, ValueId},
 };
 
 pub struct StackFrame<'a> {
     pub ctx: &'a Context<'a>,
     pub func: &'a Function,
     val_map: FxHashMap<InstructionId, GenericValue>,
+    agg_map: FxHashMap<InstructionId, Vec<GenericValue>>,
     args: Vec<GenericValue>,
 }
 
@@ -20,6 +21,7 @@ impl<'a> StackFrame<'a> {
             ctx,
             func,
             val_map: FxHashMap::default(),
+            agg_map: FxHashMap::default(),
             args,
         }
     }
@@ -32,16 +34,37 @@ impl<'a> StackFrame<'a> {
         self.val_map.get(&id).copied()
     }
 
+    /// Records an aggregate (e.g. the `{ T, i1 }` result of `cmpxchg`) produced by `id`.
+    pub fn add_inst_aggregate(&mut self, id: InstructionId, elems: Vec<GenericValue>) {
+        self.agg_map.insert(id, elems);
+    }
+
+    pub fn get_inst_aggregate(&self, id: InstructionId) -> Option<&[GenericValue]> {
+        self.agg_map.get(&id).map(|elems| elems.as_slice())
+    }
+
     pub fn get_val(&self, id: ValueId) -> Option<GenericValue> {
         match self.func.data.value_ref(id) {
             Value::Instruction(id) => self.get_inst_val(*id),
-            Value::Constant(ConstantData::Int(ConstantInt::Int32(i))) => {
-                Some(GenericValue::Int32(*i))
-            }
-            Value::Constant(ConstantData::Int(ConstantInt::Int64(i))) => {
-                Some(GenericValue::Int64(*i))
-            }
-            Value::Constant(ConstantData::GlobalRef(name)) => {
+            Value::Argument(i) => self.args.get(*i).copied(),
+            Value::Constant(konst) => self.get_constant(konst),
+            _ => None,
+        }
+    }
+
+    pub fn get_constant(&self, konst: &ConstantData) -> Option<GenericValue> {
+        match konst {
+            ConstantData::Int(i) => match i.bits() {
+                1 => i.as_bool().map(GenericValue::Int1),
+                8 => i.as_i8().map(GenericValue::Int8),
+                16 => i.as_i16().map(GenericValue::Int16),
+                32 => i.as_i32().map(GenericValue::Int32),
+                64 => i.as_i64().map(GenericValue::Int64),
+                _ => None,
+            },
+            ConstantData::Float(ConstantFloat::Float(f)) => Some(GenericValue::Float(*f)),
+            ConstantData::Float(ConstantFloat::Double(f)) => Some(GenericValue::Double(*f)),
+            ConstantData::GlobalRef(name) => {
                 if let Some(f) = self
                     .ctx
                     .module
@@ -54,13 +77,10 @@ impl<'a> StackFrame<'a> {
                 }
                 None
             }
-            Value::Argument(i) => self.args.get(*i).copied(),
-            Value::Constant(ConstantData::Expr(ConstantExpr::GetElementPtr { args, .. })) => {
-                match args[0] {
-                    ConstantData::GlobalRef(ref name) => self.ctx.globals.get(&name).copied(),
-                    _ => todo!(),
-                }
-            }
+            ConstantData::Expr(ConstantExpr::GetElementPtr { args, .. }) => match args[0] {
+                ConstantData::GlobalRef(ref name) => self.ctx.globals.get(&name).copied(),
+                _ => todo!(),
+            },
             _ => None,
         }
     }
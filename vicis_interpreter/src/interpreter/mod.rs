@@ -6,20 +6,108 @@ extern crate libloading;
 use super::generic_value::GenericValue;
 use frame::StackFrame;
 use rustc_hash::FxHashMap;
-use std::{alloc, ffi, os::raw::c_void, ptr};
+use std::{
+    alloc, error, ffi, fmt,
+    os::raw::c_void,
+    ptr,
+    sync::atomic::{
+        self, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicPtr, AtomicU16, AtomicU32,
+        AtomicU64, AtomicU8,
+    },
+};
 use vicis_ir::ir::{
     function::{
         instruction::{
-            Alloca, Br, Call, Cast, CondBr, GetElementPtr, ICmp, ICmpCond, InstructionId,
-            IntBinary, Load, Opcode, Operand, Ret, Store,
+            Alloca, AtomicCmpXchg, AtomicOrdering, AtomicRmw, AtomicRmwBinOp, Br, Call, Cast,
+            CondBr, ExtractValue, Fence, Freeze, GetElementPtr, ICmp, ICmpCond, InstructionId,
+            IntBinary, Load, Opcode, Operand, Ret, Store, SyncScope,
         },
         Function, FunctionId,
     },
     module::{name::Name, Module},
-    types::{ArrayType, Type, TypeId, Types},
-    value::{ConstantArray, ConstantData, ValueId},
+    types::{Type, TypeId, Types},
+    value::{ConstantArray, ConstantData, ConstantStruct, Value, ValueId},
 };
 
+macro_rules! read_mem {
+    ($addr:expr, $ty:ty, $atomic:ty, $ordering:expr, $volatile:expr) => {
+        unsafe {
+            match $ordering {
+                Some(ordering) => (*($addr as *const $atomic)).load(ordering),
+                None if $volatile => ptr::read_volatile($addr as *const $ty),
+                None => *($addr as *const $ty),
+            }
+        }
+    };
+}
+
+macro_rules! write_mem {
+    ($addr:expr, $val:expr, $ty:ty, $atomic:ty, $ordering:expr, $volatile:expr) => {
+        unsafe {
+            match $ordering {
+                Some(ordering) => (*($addr as *const $atomic)).store($val, ordering),
+                None if $volatile => ptr::write_volatile($addr as *mut $ty, $val),
+                None => *($addr as *mut $ty) = $val,
+            }
+        }
+    };
+}
+
+/// Runs an integer `atomicrmw`, giving `None` for the floating-point operations.
+macro_rules! atomicrmw {
+    ($op:expr, $addr:expr, $val:expr, $ordering:expr, $i:ty, $u:ty, $ai:ty, $au:ty) => {{
+        let signed = unsafe { &*($addr as *const $ai) };
+        let unsigned = unsafe { &*($addr as *const $au) };
+        match $op {
+            AtomicRmwBinOp::Xchg => Some(signed.swap($val, $ordering)),
+            AtomicRmwBinOp::Add => Some(signed.fetch_add($val, $ordering)),
+            AtomicRmwBinOp::Sub => Some(signed.fetch_sub($val, $ordering)),
+            AtomicRmwBinOp::And => Some(signed.fetch_and($val, $ordering)),
+            AtomicRmwBinOp::Nand => Some(signed.fetch_nand($val, $ordering)),
+            AtomicRmwBinOp::Or => Some(signed.fetch_or($val, $ordering)),
+            AtomicRmwBinOp::Xor => Some(signed.fetch_xor($val, $ordering)),
+            AtomicRmwBinOp::Max => Some(signed.fetch_max($val, $ordering)),
+            AtomicRmwBinOp::Min => Some(signed.fetch_min($val, $ordering)),
+            AtomicRmwBinOp::UMax => Some(unsigned.fetch_max($val as $u, $ordering) as $i),
+            AtomicRmwBinOp::UMin => Some(unsigned.fetch_min($val as $u, $ordering) as $i),
+            AtomicRmwBinOp::FAdd
+            | AtomicRmwBinOp::FSub
+            | AtomicRmwBinOp::FMax
+            | AtomicRmwBinOp::FMin => None,
+        }
+    }};
+}
+
+/// Runs a floating-point `atomicrmw` on the bits of the value. Integer operations return
+/// an error from the enclosing function.
+macro_rules! atomicrmw_float {
+    ($op:expr, $addr:expr, $val:expr, $ordering:expr, $f:ty, $au:ty) => {{
+        let op: fn($f, $f) -> $f = match $op {
+            AtomicRmwBinOp::Xchg => |_, y| y,
+            AtomicRmwBinOp::FAdd => |x, y| x + y,
+            AtomicRmwBinOp::FSub => |x, y| x - y,
+            AtomicRmwBinOp::FMax => <$f>::max,
+            AtomicRmwBinOp::FMin => <$f>::min,
+            _ => return err(format!("atomicrmw {:?} on a floating-point value", $op)),
+        };
+        let bits = unsafe { &*($addr as *const $au) };
+        let old = bits
+            .fetch_update($ordering, load_ordering($ordering), |old| {
+                Some(op(<$f>::from_bits(old), $val).to_bits())
+            })
+            .unwrap();
+        <$f>::from_bits(old)
+    }};
+}
+
+/// An error from running IR that the interpreter doesn't support.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpreterError {
+    pub message: String,
+}
+
+pub type Result<T> = std::result::Result<T, InterpreterError>;
+
 pub struct Context<'a> {
     pub module: &'a Module,
     globals: FxHashMap<Name, GenericValue>,
@@ -30,15 +118,18 @@ pub fn run_function(
     ctx: &Context,
     func_id: FunctionId,
     args: Vec<GenericValue>,
-) -> Option<GenericValue> {
+) -> Result<GenericValue> {
     let func = &ctx.module.functions()[func_id];
 
     if func.is_prototype() {
-        return Some(call_external_func(ctx, func, &args));
+        return Ok(call_external_func(ctx, func, &args));
     }
 
     let mut frame = StackFrame::new(ctx, func, args);
-    let mut block = func.layout.first_block?;
+    let mut block = match func.layout.first_block {
+        Some(block) => block,
+        None => return err(format!("function '{}' has no blocks", func.name())),
+    };
 
     'main: loop {
         for (inst_id, inst) in func
@@ -53,11 +144,16 @@ pub fn run_function(
                     num_elements,
                     align,
                 }) => run_alloca(&mut frame, inst_id, tys, num_elements, *align),
-                Operand::Store(Store { tys, args, align }) => {
-                    run_store(&mut frame, tys, args, *align)
-                }
-                Operand::Load(Load { tys, addr, align }) => {
-                    run_load(&mut frame, inst_id, tys, *addr, *align)
+                Operand::Store(store) => run_store(&mut frame, store),
+                Operand::Load(load) => run_load(&mut frame, inst_id, load),
+                Operand::AtomicCmpXchg(cmpxchg) => run_cmpxchg(&mut frame, inst_id, cmpxchg)?,
+                Operand::AtomicRmw(rmw) => run_atomicrmw(&mut frame, inst_id, rmw)?,
+                Operand::Fence(Fence {
+                    ordering,
+                    syncscope,
+                }) => run_fence(*ordering, syncscope),
+                Operand::ExtractValue(ExtractValue { ty, args }) => {
+                    run_extractvalue(&mut frame, inst_id, *ty, args)?
                 }
                 Operand::IntBinary(IntBinary {
                     ty: _,
@@ -72,12 +168,13 @@ pub fn run_function(
                 Operand::Cast(Cast { tys, arg }) => {
                     run_cast(&mut frame, inst_id, inst.opcode, tys, *arg)
                 }
+                Operand::Freeze(Freeze { ty, arg }) => run_freeze(&mut frame, inst_id, *ty, *arg)?,
                 Operand::GetElementPtr(GetElementPtr {
                     inbounds: _,
                     tys,
                     args,
                 }) => run_gep(&mut frame, inst_id, tys, args),
-                Operand::Call(Call { tys, args, .. }) => run_call(&mut frame, inst_id, tys, args),
+                Operand::Call(Call { tys, args, .. }) => run_call(&mut frame, inst_id, tys, args)?,
                 Operand::CondBr(CondBr { arg, blocks }) => {
                     let arg = frame.get_val(*arg).unwrap();
                     block = blocks[if matches!(arg, GenericValue::Int1(true)) {
@@ -91,13 +188,13 @@ pub fn run_function(
                     block = *b;
                     continue 'main;
                 }
-                Operand::Ret(Ret { val, .. }) if val.is_none() => return Some(GenericValue::Void),
+                Operand::Ret(Ret { val, .. }) if val.is_none() => return Ok(GenericValue::Void),
                 Operand::Ret(Ret {
                     ty: _,
                     val: Some(val),
                 }) => {
                     let val = frame.get_val(*val).unwrap();
-                    return Some(val);
+                    return Ok(val);
                 }
                 _ => todo!("{:?}", inst.opcode),
             }
@@ -111,7 +208,7 @@ pub fn run_function(
         break;
     }
 
-    panic!("reached end of function without terminator");
+    err("reached end of function without terminator")
 }
 
 // Instructions
@@ -124,48 +221,274 @@ fn run_alloca(
     align: u32,
 ) {
     let alloc_ty = tys[0];
-    let alloc_sz = frame.func.types.size_of(alloc_ty) * num_elements.as_int().cast_to_usize();
-    let alloc_align = if align > 0 { align } else { 8 } as usize;
+    let dl = frame.ctx.module.data_layout();
+    let alloc_sz = dl.alloc_size_of(&frame.func.types, alloc_ty) as usize
+        * num_elements.as_int().cast_to_usize();
+    let alloc_align = if align > 0 {
+        align
+    } else {
+        dl.abi_align_of(&frame.func.types, alloc_ty)
+    } as usize;
     let ptr = unsafe {
         alloc::alloc(alloc::Layout::from_size_align(alloc_sz, alloc_align).expect("layout err"))
     };
     frame.add_inst_val(id, GenericValue::Ptr(ptr));
 }
 
-fn run_store(frame: &mut StackFrame, _tys: &[TypeId], args: &[ValueId], _align: u32) {
-    let src = args[0];
-    let dst = args[1];
-    let dst = frame.get_val(dst).unwrap();
-    let src = frame.get_val(src).unwrap();
+fn run_store(frame: &mut StackFrame, store: &Store) {
+    let dst = frame.get_val(store.dst_val()).unwrap().to_ptr().unwrap();
+    let src = frame.get_val(store.src_val()).unwrap();
+    let ordering = store.is_atomic().then(|| to_std_ordering(store.ordering));
     match src {
-        GenericValue::Int32(i) => unsafe {
-            *(dst.to_ptr().unwrap() as *mut i32) = i;
-        },
-        GenericValue::Ptr(p) => unsafe {
-            *(dst.to_ptr().unwrap() as *mut *mut u8) = p;
-        },
+        GenericValue::Int1(b) => write_mem!(dst, b as u8, u8, AtomicU8, ordering, store.volatile),
+        GenericValue::Int8(i) => write_mem!(dst, i, i8, AtomicI8, ordering, store.volatile),
+        GenericValue::Int16(i) => write_mem!(dst, i, i16, AtomicI16, ordering, store.volatile),
+        GenericValue::Int32(i) => write_mem!(dst, i, i32, AtomicI32, ordering, store.volatile),
+        GenericValue::Int64(i) => write_mem!(dst, i, i64, AtomicI64, ordering, store.volatile),
+        GenericValue::Float(f) => {
+            write_mem!(dst, f.to_bits(), u32, AtomicU32, ordering, store.volatile)
+        }
+        GenericValue::Double(f) => {
+            write_mem!(dst, f.to_bits(), u64, AtomicU64, ordering, store.volatile)
+        }
+        GenericValue::Ptr(p) => {
+            write_mem!(dst, p, *mut u8, AtomicPtr<u8>, ordering, store.volatile)
+        }
         t => todo!("{:?}", t),
     }
 }
 
-fn run_load(frame: &mut StackFrame, id: InstructionId, tys: &[TypeId], addr: ValueId, _align: u32) {
-    let ty = tys[0];
-    let addr = frame.get_val(addr).unwrap();
-    match &*frame.func.types.get(ty) {
-        Type::Int(8) => frame.add_inst_val(
-            id,
-            GenericValue::Int8(unsafe { *(addr.to_ptr().unwrap() as *const i8) }),
-        ),
-        Type::Int(32) => frame.add_inst_val(
-            id,
-            GenericValue::Int32(unsafe { *(addr.to_ptr().unwrap() as *const i32) }),
-        ),
-        Type::Pointer(_) => frame.add_inst_val(
-            id,
-            GenericValue::Ptr(unsafe { *(addr.to_ptr().unwrap() as *const *mut u8) }),
-        ),
+fn run_load(frame: &mut StackFrame, id: InstructionId, load: &Load) {
+    let addr = frame.get_val(load.addr).unwrap().to_ptr().unwrap();
+    let ordering = load.is_atomic().then(|| to_std_ordering(load.ordering));
+    let val = match &*frame.func.types.get(load.tys[0]) {
+        Type::Int(1) => {
+            GenericValue::Int1(read_mem!(addr, u8, AtomicU8, ordering, load.volatile) & 1 != 0)
+        }
+        Type::Int(8) => GenericValue::Int8(read_mem!(addr, i8, AtomicI8, ordering, load.volatile)),
+        Type::Int(16) => {
+            GenericValue::Int16(read_mem!(addr, i16, AtomicI16, ordering, load.volatile))
+        }
+        Type::Int(32) => {
+            GenericValue::Int32(read_mem!(addr, i32, AtomicI32, ordering, load.volatile))
+        }
+        Type::Int(64) => {
+            GenericValue::Int64(read_mem!(addr, i64, AtomicI64, ordering, load.volatile))
+        }
+        Type::Float => GenericValue::Float(f32::from_bits(read_mem!(
+            addr,
+            u32,
+            AtomicU32,
+            ordering,
+            load.volatile
+        ))),
+        Type::Double => GenericValue::Double(f64::from_bits(read_mem!(
+            addr,
+            u64,
+            AtomicU64,
+            ordering,
+            load.volatile
+        ))),
+        Type::Pointer(_) => GenericValue::Ptr(read_mem!(
+            addr,
+            *mut u8,
+            AtomicPtr<u8>,
+            ordering,
+            load.volatile
+        )),
         _ => todo!(),
     };
+    frame.add_inst_val(id, val);
+}
+
+fn run_cmpxchg(frame: &mut StackFrame, id: InstructionId, cmpxchg: &AtomicCmpXchg) -> Result<()> {
+    let addr = frame.get_val(cmpxchg.ptr()).unwrap().to_ptr().unwrap();
+    let cmp = frame.get_val(cmpxchg.cmp_val()).unwrap();
+    let new = frame.get_val(cmpxchg.new_val()).unwrap();
+    let success = to_std_ordering(cmpxchg.success_ordering);
+    let failure = to_std_ordering(cmpxchg.failure_ordering);
+    // A strong exchange is always a valid implementation of a weak one
+    let (old, ok) = match (cmp, new) {
+        (GenericValue::Int1(c), GenericValue::Int1(n)) => {
+            let res = unsafe { &*(addr as *const AtomicU8) }
+                .compare_exchange(c as u8, n as u8, success, failure);
+            (
+                GenericValue::Int1(res.unwrap_or_else(|v| v) & 1 != 0),
+                res.is_ok(),
+            )
+        }
+        (GenericValue::Int8(c), GenericValue::Int8(n)) => {
+            let res =
+                unsafe { &*(addr as *const AtomicI8) }.compare_exchange(c, n, success, failure);
+            (GenericValue::Int8(res.unwrap_or_else(|v| v)), res.is_ok())
+        }
+        (GenericValue::Int16(c), GenericValue::Int16(n)) => {
+            let res =
+                unsafe { &*(addr as *const AtomicI16) }.compare_exchange(c, n, success, failure);
+            (GenericValue::Int16(res.unwrap_or_else(|v| v)), res.is_ok())
+        }
+        (GenericValue::Int32(c), GenericValue::Int32(n)) => {
+            let res =
+                unsafe { &*(addr as *const AtomicI32) }.compare_exchange(c, n, success, failure);
+            (GenericValue::Int32(res.unwrap_or_else(|v| v)), res.is_ok())
+        }
+        (GenericValue::Int64(c), GenericValue::Int64(n)) => {
+            let res =
+                unsafe { &*(addr as *const AtomicI64) }.compare_exchange(c, n, success, failure);
+            (GenericValue::Int64(res.unwrap_or_else(|v| v)), res.is_ok())
+        }
+        (GenericValue::Ptr(c), GenericValue::Ptr(n)) => {
+            let res = unsafe { &*(addr as *const AtomicPtr<u8>) }
+                .compare_exchange(c, n, success, failure);
+            (GenericValue::Ptr(res.unwrap_or_else(|v| v)), res.is_ok())
+        }
+        (c, n) => return err(format!("cmpxchg of {:?} and {:?} is not supported", c, n)),
+    };
+    frame.add_inst_aggregate(id, vec![old, GenericValue::Int1(ok)]);
+    Ok(())
+}
+
+fn run_atomicrmw(frame: &mut StackFrame, id: InstructionId, rmw: &AtomicRmw) -> Result<()> {
+    let addr = frame.get_val(rmw.ptr()).unwrap().to_ptr().unwrap();
+    let val = frame.get_val(rmw.val()).unwrap();
+    let ordering = to_std_ordering(rmw.ordering);
+    let old = match val {
+        GenericValue::Int1(v) => atomicrmw_i1(rmw.op, addr, v, ordering).map(GenericValue::Int1),
+        GenericValue::Int8(v) => atomicrmw!(rmw.op, addr, v, ordering, i8, u8, AtomicI8, AtomicU8)
+            .map(GenericValue::Int8),
+        GenericValue::Int16(v) => {
+            atomicrmw!(rmw.op, addr, v, ordering, i16, u16, AtomicI16, AtomicU16)
+                .map(GenericValue::Int16)
+        }
+        GenericValue::Int32(v) => {
+            atomicrmw!(rmw.op, addr, v, ordering, i32, u32, AtomicI32, AtomicU32)
+                .map(GenericValue::Int32)
+        }
+        GenericValue::Int64(v) => {
+            atomicrmw!(rmw.op, addr, v, ordering, i64, u64, AtomicI64, AtomicU64)
+                .map(GenericValue::Int64)
+        }
+        GenericValue::Float(v) => Some(GenericValue::Float(atomicrmw_float!(
+            rmw.op, addr, v, ordering, f32, AtomicU32
+        ))),
+        GenericValue::Double(v) => Some(GenericValue::Double(atomicrmw_float!(
+            rmw.op, addr, v, ordering, f64, AtomicU64
+        ))),
+        GenericValue::Ptr(p) if rmw.op == AtomicRmwBinOp::Xchg => Some(GenericValue::Ptr(
+            unsafe { &*(addr as *const AtomicPtr<u8>) }.swap(p, ordering),
+        )),
+        _ => None,
+    };
+    match old {
+        Some(old) => frame.add_inst_val(id, old),
+        None => {
+            return err(format!(
+                "atomicrmw {:?} on {:?} is not supported",
+                rmw.op, val
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Runs `atomicrmw` on an `i1`, which is stored as a byte of 0 or 1. As a signed value,
+/// `true` is -1.
+fn atomicrmw_i1(
+    op: AtomicRmwBinOp,
+    addr: *mut u8,
+    val: bool,
+    ordering: atomic::Ordering,
+) -> Option<bool> {
+    let byte = unsafe { &*(addr as *const AtomicU8) };
+    let val = val as u8;
+    let old = match op {
+        AtomicRmwBinOp::Xchg => byte.swap(val, ordering),
+        AtomicRmwBinOp::Add | AtomicRmwBinOp::Sub | AtomicRmwBinOp::Xor => {
+            byte.fetch_xor(val, ordering)
+        }
+        AtomicRmwBinOp::And | AtomicRmwBinOp::Max | AtomicRmwBinOp::UMin => {
+            byte.fetch_and(val, ordering)
+        }
+        AtomicRmwBinOp::Or | AtomicRmwBinOp::Min | AtomicRmwBinOp::UMax => {
+            byte.fetch_or(val, ordering)
+        }
+        AtomicRmwBinOp::Nand => byte
+            .fetch_update(ordering, load_ordering(ordering), |old| {
+                Some(!(old & val) & 1)
+            })
+            .unwrap(),
+        _ => return None,
+    };
+    Some(old & 1 != 0)
+}
+
+fn run_fence(ordering: AtomicOrdering, syncscope: &SyncScope) {
+    match syncscope {
+        SyncScope::SingleThread => atomic::compiler_fence(to_std_ordering(ordering)),
+        _ => atomic::fence(to_std_ordering(ordering)),
+    }
+}
+
+fn run_extractvalue(
+    frame: &mut StackFrame,
+    id: InstructionId,
+    ty: TypeId,
+    args: &[ValueId],
+) -> Result<()> {
+    let indices = args[1..]
+        .iter()
+        .map(|&idx| match frame.func.data.value_ref(idx) {
+            Value::Constant(ConstantData::Int(idx)) => Ok(idx.cast_to_usize()),
+            _ => err("extractvalue index is not a constant"),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let val = match frame.func.data.value_ref(args[0]) {
+        // Only `cmpxchg` gives an aggregate here
+        Value::Instruction(agg) => match (frame.get_inst_aggregate(*agg), indices.as_slice()) {
+            (Some(elems), &[idx]) => elems.get(idx).copied(),
+            _ => None,
+        },
+        Value::Constant(konst) => {
+            let types = &frame.func.types;
+            let (mut konst, mut ty) = (konst, ty);
+            for &idx in &indices {
+                ty = match &*types.get(ty) {
+                    Type::Struct(strukt) => match strukt.elems.get(idx) {
+                        Some(&ty) => ty,
+                        None => return err("extractvalue index out of range"),
+                    },
+                    Type::Array(ary) => ary.inner,
+                    _ => return err("extractvalue of a non-aggregate"),
+                };
+                konst = match konst {
+                    ConstantData::Struct(ConstantStruct { elems, .. })
+                    | ConstantData::Array(ConstantArray { elems, .. }) => match elems.get(idx) {
+                        Some(elem) => elem,
+                        None => return err("extractvalue index out of range"),
+                    },
+                    // Every element of these is the same
+                    konst => konst,
+                };
+            }
+            match konst {
+                ConstantData::AggregateZero | ConstantData::Undef | ConstantData::Poison => {
+                    zero_of(types, ty)
+                }
+                konst => frame.get_constant(konst),
+            }
+        }
+        _ => None,
+    };
+    match val {
+        Some(val) => frame.add_inst_val(id, val),
+        None => {
+            return err(format!(
+                "extractvalue from {} is not supported",
+                frame.func.types.to_string(ty)
+            ))
+        }
+    }
+    Ok(())
 }
 
 fn run_int_binary(frame: &mut StackFrame, id: InstructionId, opcode: Opcode, args: &[ValueId]) {
@@ -176,7 +499,15 @@ fn run_int_binary(frame: &mut StackFrame, id: InstructionId, opcode: Opcode, arg
         Opcode::Sub => frame.add_inst_val(id, sub(x, y).unwrap()),
         Opcode::Mul => frame.add_inst_val(id, mul(x, y).unwrap()),
         Opcode::SDiv => frame.add_inst_val(id, sdiv(x, y).unwrap()),
+        Opcode::UDiv => frame.add_inst_val(id, udiv(x, y).unwrap()),
         Opcode::SRem => frame.add_inst_val(id, srem(x, y).unwrap()),
+        Opcode::URem => frame.add_inst_val(id, urem(x, y).unwrap()),
+        Opcode::And => frame.add_inst_val(id, and(x, y).unwrap()),
+        Opcode::Or => frame.add_inst_val(id, or(x, y).unwrap()),
+        Opcode::Xor => frame.add_inst_val(id, xor(x, y).unwrap()),
+        Opcode::Shl => frame.add_inst_val(id, shl(x, y).unwrap()),
+        Opcode::LShr => frame.add_inst_val(id, lshr(x, y).unwrap()),
+        Opcode::AShr => frame.add_inst_val(id, ashr(x, y).unwrap()),
         _ => todo!(),
     };
 }
@@ -211,48 +542,123 @@ fn run_cast(
                 _ => todo!(),
             }
         }
+        Opcode::Bitcast if matches!(arg, GenericValue::Ptr(_)) => arg,
         t => todo!("cast {:?}", t),
     };
     frame.add_inst_val(id, val)
 }
 
+fn run_freeze(frame: &mut StackFrame, id: InstructionId, ty: TypeId, arg: ValueId) -> Result<()> {
+    // Computed values are never poison here, but constant poison and undef are frozen to zero
+    let val = match frame.func.data.value_ref(arg) {
+        Value::Constant(ConstantData::Poison | ConstantData::Undef) => {
+            match zero_of(&frame.func.types, ty) {
+                Some(zero) => zero,
+                None => {
+                    return err(format!(
+                        "freeze of {} is not supported",
+                        frame.func.types.to_string(ty)
+                    ))
+                }
+            }
+        }
+        _ => frame.get_val(arg).unwrap(),
+    };
+    frame.add_inst_val(id, val);
+    Ok(())
+}
+
 fn run_gep(frame: &mut StackFrame, id: InstructionId, tys: &[TypeId], args: &[ValueId]) {
     let arg = frame.get_val(args[0]).unwrap().to_ptr().unwrap();
+    let types = &frame.func.types;
+    let dl = frame.ctx.module.data_layout();
     let mut total = 0;
     let mut cur_ty = tys[1];
     for &idx in &args[1..] {
-        if matches!(&*frame.func.types.get(cur_ty), Type::Struct(_)) {
+        let idx = match frame.get_val(idx).unwrap() {
+            GenericValue::Int32(idx) => idx as usize,
+            GenericValue::Int64(idx) => idx as usize,
+            _ => panic!(),
+        };
+        let strukt = match &*types.get(cur_ty) {
+            Type::Struct(strukt) => Some(strukt.clone()),
+            _ => None,
+        };
+        if let Some(strukt) = strukt {
+            total += dl.struct_layout(types, &strukt).offsets[idx] as usize;
+            cur_ty = strukt.elems[idx];
         } else {
-            let inner = frame.func.types.get_element(cur_ty).unwrap();
-            let idx = match frame.get_val(idx).unwrap() {
-                GenericValue::Int32(idx) => idx as usize,
-                GenericValue::Int64(idx) => idx as usize,
-                _ => panic!(),
-            };
-            total += frame.func.types.size_of(inner) * idx;
+            let inner = types.get_element(cur_ty).unwrap();
+            total += dl.alloc_size_of(types, inner) as usize * idx;
             cur_ty = inner;
         }
     }
     frame.add_inst_val(id, GenericValue::Ptr(unsafe { arg.add(total) }));
 }
 
-fn run_call(frame: &mut StackFrame, id: InstructionId, _tys: &[TypeId], args: &[ValueId]) {
+fn run_call(
+    frame: &mut StackFrame,
+    id: InstructionId,
+    _tys: &[TypeId],
+    args: &[ValueId],
+) -> Result<()> {
     let callee = frame.get_val(args[0]).unwrap();
     let args: Vec<GenericValue> = args[1..]
         .iter()
         .map(|&a| frame.get_val(a).unwrap())
         .collect();
     let func_id = callee.to_id::<FunctionId>().unwrap();
-    if let Some(ret) = run_function(frame.ctx, *func_id, args) {
-        match ret {
-            GenericValue::Void => {}
-            v => frame.add_inst_val(id, v),
-        }
+    match run_function(frame.ctx, func_id, args)? {
+        GenericValue::Void => {}
+        v => frame.add_inst_val(id, v),
     }
+    Ok(())
 }
 
 // Utils
 
+fn err<T>(message: impl Into<String>) -> Result<T> {
+    Err(InterpreterError {
+        message: message.into(),
+    })
+}
+
+fn to_std_ordering(ordering: AtomicOrdering) -> atomic::Ordering {
+    match ordering {
+        AtomicOrdering::NotAtomic | AtomicOrdering::Unordered | AtomicOrdering::Monotonic => {
+            atomic::Ordering::Relaxed
+        }
+        AtomicOrdering::Acquire => atomic::Ordering::Acquire,
+        AtomicOrdering::Release => atomic::Ordering::Release,
+        AtomicOrdering::AcqRel => atomic::Ordering::AcqRel,
+        AtomicOrdering::SeqCst => atomic::Ordering::SeqCst,
+    }
+}
+
+/// Returns the zero value of `ty`, if it is a scalar the interpreter supports.
+fn zero_of(types: &Types, ty: TypeId) -> Option<GenericValue> {
+    Some(match &*types.get(ty) {
+        Type::Int(1) => GenericValue::Int1(false),
+        Type::Int(8) => GenericValue::Int8(0),
+        Type::Int(16) => GenericValue::Int16(0),
+        Type::Int(32) => GenericValue::Int32(0),
+        Type::Int(64) => GenericValue::Int64(0),
+        Type::Float => GenericValue::Float(0.0),
+        Type::Double => GenericValue::Double(0.0),
+        Type::Pointer(_) => GenericValue::Ptr(ptr::null_mut()),
+        _ => return None,
+    })
+}
+
+/// Returns the strongest ordering a failed compare-exchange with `ordering` may use.
+fn load_ordering(ordering: atomic::Ordering) -> atomic::Ordering {
+    match ordering {
+        atomic::Ordering::Release => atomic::Ordering::Relaxed,
+        atomic::Ordering::AcqRel => atomic::Ordering::Acquire,
+        ordering => ordering,
+    }
+}
+
 fn add(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
     match (x, y) {
         (GenericValue::Int32(x), GenericValue::Int32(y)) => Some(GenericValue::Int32(x + y)),
@@ -288,6 +694,118 @@ fn srem(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
     }
 }
 
+fn udiv(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
+    match (x, y) {
+        // Dividing by `false` is undefined behavior, so `y` is `true`
+        (GenericValue::Int1(x), GenericValue::Int1(_)) => Some(GenericValue::Int1(x)),
+        (GenericValue::Int8(x), GenericValue::Int8(y)) => {
+            Some(GenericValue::Int8((x as u8 / y as u8) as i8))
+        }
+        (GenericValue::Int32(x), GenericValue::Int32(y)) => {
+            Some(GenericValue::Int32((x as u32 / y as u32) as i32))
+        }
+        (GenericValue::Int64(x), GenericValue::Int64(y)) => {
+            Some(GenericValue::Int64((x as u64 / y as u64) as i64))
+        }
+        _ => None,
+    }
+}
+
+fn urem(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
+    match (x, y) {
+        (GenericValue::Int1(_), GenericValue::Int1(_)) => Some(GenericValue::Int1(false)),
+        (GenericValue::Int8(x), GenericValue::Int8(y)) => {
+            Some(GenericValue::Int8((x as u8 % y as u8) as i8))
+        }
+        (GenericValue::Int32(x), GenericValue::Int32(y)) => {
+            Some(GenericValue::Int32((x as u32 % y as u32) as i32))
+        }
+        (GenericValue::Int64(x), GenericValue::Int64(y)) => {
+            Some(GenericValue::Int64((x as u64 % y as u64) as i64))
+        }
+        _ => None,
+    }
+}
+
+fn and(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
+    match (x, y) {
+        (GenericValue::Int1(x), GenericValue::Int1(y)) => Some(GenericValue::Int1(x & y)),
+        (GenericValue::Int8(x), GenericValue::Int8(y)) => Some(GenericValue::Int8(x & y)),
+        (GenericValue::Int32(x), GenericValue::Int32(y)) => Some(GenericValue::Int32(x & y)),
+        (GenericValue::Int64(x), GenericValue::Int64(y)) => Some(GenericValue::Int64(x & y)),
+        _ => None,
+    }
+}
+
+fn or(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
+    match (x, y) {
+        (GenericValue::Int1(x), GenericValue::Int1(y)) => Some(GenericValue::Int1(x | y)),
+        (GenericValue::Int8(x), GenericValue::Int8(y)) => Some(GenericValue::Int8(x | y)),
+        (GenericValue::Int32(x), GenericValue::Int32(y)) => Some(GenericValue::Int32(x | y)),
+        (GenericValue::Int64(x), GenericValue::Int64(y)) => Some(GenericValue::Int64(x | y)),
+        _ => None,
+    }
+}
+
+fn xor(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
+    match (x, y) {
+        (GenericValue::Int1(x), GenericValue::Int1(y)) => Some(GenericValue::Int1(x ^ y)),
+        (GenericValue::Int8(x), GenericValue::Int8(y)) => Some(GenericValue::Int8(x ^ y)),
+        (GenericValue::Int32(x), GenericValue::Int32(y)) => Some(GenericValue::Int32(x ^ y)),
+        (GenericValue::Int64(x), GenericValue::Int64(y)) => Some(GenericValue::Int64(x ^ y)),
+        _ => None,
+    }
+}
+
+fn shl(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
+    match (x, y) {
+        // Shifting an `i1` by one gives poison, so any value will do
+        (GenericValue::Int1(x), GenericValue::Int1(y)) => Some(GenericValue::Int1(x && !y)),
+        (GenericValue::Int8(x), GenericValue::Int8(y)) => {
+            Some(GenericValue::Int8(x.wrapping_shl(y as u32)))
+        }
+        (GenericValue::Int32(x), GenericValue::Int32(y)) => {
+            Some(GenericValue::Int32(x.wrapping_shl(y as u32)))
+        }
+        (GenericValue::Int64(x), GenericValue::Int64(y)) => {
+            Some(GenericValue::Int64(x.wrapping_shl(y as u32)))
+        }
+        _ => None,
+    }
+}
+
+fn lshr(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
+    match (x, y) {
+        (GenericValue::Int1(x), GenericValue::Int1(y)) => Some(GenericValue::Int1(x && !y)),
+        (GenericValue::Int8(x), GenericValue::Int8(y)) => {
+            Some(GenericValue::Int8((x as u8).wrapping_shr(y as u32) as i8))
+        }
+        (GenericValue::Int32(x), GenericValue::Int32(y)) => {
+            Some(GenericValue::Int32((x as u32).wrapping_shr(y as u32) as i32))
+        }
+        (GenericValue::Int64(x), GenericValue::Int64(y)) => {
+            Some(GenericValue::Int64((x as u64).wrapping_shr(y as u32) as i64))
+        }
+        _ => None,
+    }
+}
+
+fn ashr(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
+    match (x, y) {
+        (GenericValue::Int1(x), GenericValue::Int1(_)) => Some(GenericValue::Int1(x)),
+        (GenericValue::Int8(x), GenericValue::Int8(y)) => {
+            Some(GenericValue::Int8(x.wrapping_shr(y as u32)))
+        }
+        (GenericValue::Int32(x), GenericValue::Int32(y)) => {
+            Some(GenericValue::Int32(x.wrapping_shr(y as u32)))
+        }
+        (GenericValue::Int64(x), GenericValue::Int64(y)) => {
+            Some(GenericValue::Int64(x.wrapping_shr(y as u32)))
+        }
+        _ => None,
+    }
+}
+
 fn slt(x: GenericValue, y: GenericValue) -> Option<GenericValue> {
     match (x, y) {
         (GenericValue::Int32(x), GenericValue::Int32(y)) => Some(GenericValue::Int1(x < y)),
@@ -309,8 +827,12 @@ impl<'a> Context<'a> {
         let mut globals = FxHashMap::default();
 
         for (name, gv) in module.global_variables() {
-            let sz = module.types.size_of(gv.ty);
-            let align = if gv.align > 0 { gv.align } else { 8 } as usize;
+            let sz = module.data_layout().alloc_size_of(&module.types, gv.ty) as usize;
+            let align = if gv.align > 0 {
+                gv.align
+            } else {
+                module.data_layout().abi_align_of(&module.types, gv.ty)
+            } as usize;
             let ptr = unsafe {
                 alloc::alloc(alloc::Layout::from_size_align(sz, align).expect("layout err"))
             };
@@ -321,7 +843,10 @@ impl<'a> Context<'a> {
                         elems,
                         ..
                     }) => {
-                        let s: Vec<u8> = elems.iter().map(|e| *e.as_int().as_i8() as u8).collect();
+                        let s: Vec<u8> = elems
+                            .iter()
+                            .map(|e| e.as_int().as_i8().unwrap() as u8)
+                            .collect();
                         unsafe { ptr::copy_nonoverlapping(s.as_ptr(), ptr, s.len()) };
                     }
                     _ => todo!(),
@@ -352,32 +877,14 @@ impl<'a> Context<'a> {
     }
 }
 
-// dummy
-
-trait TypeSize {
-    fn size_of(&self, ty: TypeId) -> usize;
-}
-
-impl TypeSize for Types {
-    // Returns the size of the type in byte
-    fn size_of(&self, ty: TypeId) -> usize {
-        let ty = self.get(ty);
-        match &*ty {
-            Type::Void => 0,
-            Type::Int(1) => 1,
-            Type::Int(8) => 1,
-            Type::Int(16) => 2,
-            Type::Int(32) => 4,
-            Type::Array(ArrayType {
-                inner,
-                num_elements,
-            }) => self.size_of(*inner) * *num_elements as usize,
-            Type::Pointer(_) => 8,
-            _ => todo!(),
-        }
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error: {}", self.message)
     }
 }
 
+impl error::Error for InterpreterError {}
+
 fn call_external_func(ctx: &Context, func: &Function, args: &[GenericValue]) -> GenericValue {
     fn lookup<'a>(
         ctx: &'a Context,
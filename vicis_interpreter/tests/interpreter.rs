@@ -429,6 +429,14 @@ fn exec10() {
         (5, 23, 115, "mul"),
         (123, 23, 5, "sdiv"),
         (39, 30, 9, "srem"),
+        (-20, 3, 1431655758, "udiv"),
+        (-20, 7, 5, "urem"),
+        (12, 10, 8, "and"),
+        (12, 10, 14, "or"),
+        (12, 10, 6, "xor"),
+        (3, 4, 48, "shl"),
+        (-20, 28, 15, "lshr"),
+        (-20, 2, -5, "ashr"),
     ] {
         let asm = format!(
             "
@@ -454,3 +462,217 @@ fn exec10() {
         );
     }
 }
+
+#[test]
+fn exec11() {
+    let asm = r#"
+define dso_local i32 @f(i32 %0) {
+  %2 = alloca i32, align 4
+  store atomic i32 %0, i32* %2 seq_cst, align 4
+  %3 = atomicrmw add i32* %2, i32 5 seq_cst, align 4
+  %4 = atomicrmw umax i32* %2, i32 -1 acq_rel, align 4
+  %5 = cmpxchg i32* %2, i32 -1, i32 %3 seq_cst seq_cst, align 4
+  %6 = extractvalue { i32, i1 } %5, 0
+  %7 = cmpxchg weak i32* %2, i32 0, i32 100 seq_cst monotonic, align 4
+  fence syncscope("singlethread") seq_cst
+  %8 = load volatile i32, i32* %2, align 4
+  %9 = load atomic i32, i32* %2 acquire, align 4
+  %10 = add i32 %8, %9
+  %11 = add i32 %10, %6
+  ret i32 %11
+}"#;
+    let module = module::parse_assembly(asm).unwrap();
+    let ctx = interpreter::Context::new(&module)
+        .with_lib("/lib/x86_64-linux-gnu/libc.so.6")
+        .expect("failed to load libc");
+    let main = module.find_function_by_name("f").unwrap();
+    assert_eq!(
+        interpreter::run_function(&ctx, main, vec![GenericValue::Int32(3)]).unwrap(),
+        GenericValue::Int32(5)
+    );
+}
+
+#[test]
+fn exec12() {
+    let asm = r#"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+
+%struct.S = type { i8, i64, [2 x i32], i32 }
+
+define dso_local i32 @f(i32 %0) {
+  %2 = alloca %struct.S, align 8
+  %3 = getelementptr inbounds %struct.S, %struct.S* %2, i32 0, i32 2, i64 1
+  store i32 %0, i32* %3, align 4
+  %4 = getelementptr inbounds %struct.S, %struct.S* %2, i32 0, i32 3
+  store i32 10, i32* %4, align 4
+  %5 = bitcast %struct.S* %2 to i32*
+  %6 = getelementptr inbounds i32, i32* %5, i64 5
+  %7 = load i32, i32* %6, align 4
+  %8 = getelementptr inbounds i32, i32* %5, i64 6
+  %9 = load i32, i32* %8, align 4
+  %10 = add i32 %7, %9
+  ret i32 %10
+}"#;
+    let module = module::parse_assembly(asm).unwrap();
+    let ctx = interpreter::Context::new(&module)
+        .with_lib("/lib/x86_64-linux-gnu/libc.so.6")
+        .expect("failed to load libc");
+    let main = module.find_function_by_name("f").unwrap();
+    assert_eq!(
+        interpreter::run_function(&ctx, main, vec![GenericValue::Int32(32)]).unwrap(),
+        GenericValue::Int32(42)
+    );
+}
+
+#[test]
+fn exec13() {
+    for (x, y, z, op) in [
+        (-20, 3, 78, "udiv"),
+        (-20, 7, 5, "urem"),
+        (3, 4, 48, "shl"),
+        (-20, 4, 14, "lshr"),
+        (-20, 2, -5, "ashr"),
+    ] {
+        let asm = format!(
+            "
+    define dso_local i8 @f(i8 %0, i8 %1) {{
+      %3 = {} i8 %0, %1
+      ret i8 %3
+    }}",
+            op
+        );
+        let module = module::parse_assembly(asm.as_str()).unwrap();
+        let ctx = interpreter::Context::new(&module);
+        let main = module.find_function_by_name("f").unwrap();
+        assert_eq!(
+            interpreter::run_function(
+                &ctx,
+                main,
+                vec![GenericValue::Int8(x), GenericValue::Int8(y)]
+            )
+            .unwrap(),
+            GenericValue::Int8(z),
+            "{}",
+            op
+        );
+    }
+}
+
+#[test]
+fn exec14() {
+    let asm = r#"
+define dso_local i32 @f(i32 %0) {
+  %2 = freeze i32 poison
+  %3 = freeze i32 undef
+  %4 = freeze i32 %0
+  %5 = add i32 %2, %3
+  %6 = add i32 %5, %4
+  ret i32 %6
+}"#;
+    let module = module::parse_assembly(asm).unwrap();
+    let ctx = interpreter::Context::new(&module);
+    let main = module.find_function_by_name("f").unwrap();
+    assert_eq!(
+        interpreter::run_function(&ctx, main, vec![GenericValue::Int32(42)]).unwrap(),
+        GenericValue::Int32(42)
+    );
+}
+
+#[test]
+fn exec15() {
+    let asm = r#"
+define dso_local i16 @f() {
+  %1 = freeze i16 poison
+  ret i16 %1
+}
+
+define dso_local double @g() {
+  %1 = freeze double poison
+  ret double %1
+}
+
+define dso_local i128 @h() {
+  %1 = freeze i128 poison
+  ret i128 %1
+}"#;
+    let module = module::parse_assembly(asm).unwrap();
+    let ctx = interpreter::Context::new(&module);
+    let run =
+        |name| interpreter::run_function(&ctx, module.find_function_by_name(name).unwrap(), vec![]);
+    assert_eq!(run("f").unwrap(), GenericValue::Int16(0));
+    assert_eq!(run("g").unwrap(), GenericValue::Double(0.0));
+    assert_eq!(
+        run("h").unwrap_err().message,
+        "freeze of i128 is not supported"
+    );
+}
+
+#[test]
+fn exec16() {
+    let asm = r#"
+define dso_local i16 @rmw_i16() {
+  %1 = alloca i16, align 2
+  store i16 7, i16* %1, align 2
+  %2 = atomicrmw add i16* %1, i16 5 seq_cst, align 2
+  %3 = atomicrmw umax i16* %1, i16 -1 acq_rel, align 2
+  %4 = cmpxchg i16* %1, i16 -1, i16 3 seq_cst seq_cst, align 2
+  %5 = load i16, i16* %1, align 2
+  ret i16 %5
+}
+
+define dso_local i1 @rmw_i1() {
+  %1 = alloca i1, align 1
+  store i1 true, i1* %1, align 1
+  %2 = atomicrmw xor i1* %1, i1 true seq_cst, align 1
+  %3 = atomicrmw nand i1* %1, i1 true seq_cst, align 1
+  %4 = cmpxchg i1* %1, i1 true, i1 false seq_cst seq_cst, align 1
+  %5 = extractvalue { i1, i1 } %4, 1
+  ret i1 %5
+}
+
+define dso_local float @rmw_float() {
+  %1 = alloca float, align 4
+  store float 1.0, float* %1, align 4
+  %2 = atomicrmw fsub float* %1, float 0.25 seq_cst, align 4
+  %3 = atomicrmw fmin float* %1, float 0.5 seq_cst, align 4
+  %4 = load float, float* %1, align 4
+  ret float %4
+}
+
+define dso_local double @rmw_double() {
+  %1 = alloca double, align 8
+  store double 1.5, double* %1, align 8
+  %2 = atomicrmw fadd double* %1, double 2.0 seq_cst, align 8
+  %3 = atomicrmw fmax double* %1, double 3.0 release, align 8
+  ret double %3
+}
+
+define dso_local i32 @extract_const() {
+  %1 = extractvalue { i32, [2 x i32] } { i32 1, [2 x i32] [i32 2, i32 3] }, 1, 1
+  ret i32 %1
+}
+
+define dso_local double @extract_zero() {
+  %1 = extractvalue { i32, double } zeroinitializer, 1
+  ret double %1
+}
+
+define dso_local i32 @extract_wide() {
+  %1 = extractvalue { i128, i32 } { i128 1, i32 2 }, 0
+  ret i32 0
+}"#;
+    let module = module::parse_assembly(asm).unwrap();
+    let ctx = interpreter::Context::new(&module);
+    let run =
+        |name| interpreter::run_function(&ctx, module.find_function_by_name(name).unwrap(), vec![]);
+    assert_eq!(run("rmw_i16").unwrap(), GenericValue::Int16(3));
+    assert_eq!(run("rmw_i1").unwrap(), GenericValue::Int1(true));
+    assert_eq!(run("rmw_float").unwrap(), GenericValue::Float(0.5));
+    assert_eq!(run("rmw_double").unwrap(), GenericValue::Double(3.5));
+    assert_eq!(run("extract_const").unwrap(), GenericValue::Int32(3));
+    assert_eq!(run("extract_zero").unwrap(), GenericValue::Double(0.0));
+    assert_eq!(
+        run("extract_wide").unwrap_err().message,
+        "extractvalue from { i128, i32 } is not supported"
+    );
+}
@@ -0,0 +1,44 @@
+; ModuleID = 'alias.cc'
+source_filename = "alias.cc"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+%class.A = type { i32 }
+
+$_ZN1A3getEv = comdat any
+
+$_ZZ4mainE1x = comdat largest
+
+@x = global i32 1, align 4
+@y = internal unnamed_addr alias i32, i32* @x
+@z = weak_odr hidden alias i8, i8* bitcast (i32* @x to i8*)
+
+@_ZN1AC1Ei = dso_local unnamed_addr alias void (%class.A*, i32), void (%class.A*, i32)* @_ZN1AC2Ei
+
+@f = ifunc i32 (i32), i32 (i32)* ()* @f_resolver
+
+define dso_local void @_ZN1AC2Ei(%class.A* %this, i32 %x) unnamed_addr #0 {
+entry:
+  %0 = getelementptr inbounds %class.A, %class.A* %this, i32 0, i32 0
+  store i32 %x, i32* %0, align 4
+  ret void
+}
+
+define linkonce_odr dso_local i32 @_ZN1A3getEv(%class.A* %this) #0 comdat {
+entry:
+  %0 = getelementptr inbounds %class.A, %class.A* %this, i32 0, i32 0
+  %1 = load i32, i32* %0, align 4
+  ret i32 %1
+}
+
+define internal i32 @f_impl(i32 %x) {
+entry:
+  ret i32 %x
+}
+
+define internal i32 (i32)* @f_resolver() comdat($_ZZ4mainE1x) {
+entry:
+  ret i32 (i32)* @f_impl
+}
+
+attributes #0 = { noinline nounwind optnone uwtable }
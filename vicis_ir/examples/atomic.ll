@@ -0,0 +1,31 @@
+; ModuleID = 'atomic.c'
+source_filename = "atomic.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+@counter = global i32 0, align 4
+@flag = global i8 0, align 1
+
+define dso_local i32 @incr(i32 %0) {
+  %2 = atomicrmw add i32* @counter, i32 %0 seq_cst, align 4
+  %3 = atomicrmw volatile umax i32* @counter, i32 %2 syncscope("singlethread") monotonic, align 4
+  ret i32 %3
+}
+
+define dso_local i1 @try_lock() {
+  %1 = cmpxchg i8* @flag, i8 0, i8 1 acquire monotonic, align 1
+  %2 = extractvalue { i8, i1 } %1, 1
+  %3 = cmpxchg weak volatile i8* @flag, i8 1, i8 0 syncscope("agent") acq_rel acquire
+  ret i1 %2
+}
+
+define dso_local i32 @load_store(i32* %0) {
+  %2 = load atomic i32, i32* @counter acquire, align 4
+  %3 = load volatile i32, i32* %0, align 4
+  store atomic i32 %3, i32* @counter release, align 4
+  store volatile i32 %2, i32* %0, align 4
+  %4 = load atomic volatile i32, i32* %0 syncscope("singlethread") seq_cst, align 4
+  fence acquire
+  fence syncscope("singlethread") seq_cst
+  ret i32 %4
+}
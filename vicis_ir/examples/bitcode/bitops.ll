@@ -0,0 +1,26 @@
+; ModuleID = 'examples/bitcode/bitops.bc'
+source_filename = "c.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+; Function Attrs: noinline nounwind optnone uwtable
+define dso_local i32 @f(i32 %0, i32 %1) #0 {
+  %3 = udiv i32 %0, %1
+  %4 = udiv exact i32 %3, 2
+  %5 = urem i32 %4, %1
+  %6 = or i32 %5, 16
+  %7 = xor i32 %6, -1
+  %8 = shl nuw nsw i32 %7, 2
+  %9 = ashr exact i32 %8, 1
+  %10 = lshr i32 %9, %1
+  %11 = and i32 %10, 255
+  ret i32 %11
+}
+
+attributes #0 = { noinline nounwind optnone uwtable "frame-pointer"="all" "min-legal-vector-width"="0" "no-trapping-math"="false" "stack-protector-buffer-size"="8" "target-cpu"="x86-64" "target-features"="+fxsr,+mmx,+sse,+sse2,+x87" }
+
+!llvm.module.flags = !{!0}
+!llvm.ident = !{!1}
+
+!0 = !{i32 1, !"wchar_size", i32 4}
+!1 = !{!"clang version 10.0.0-4ubuntu1 "}
@@ -0,0 +1,37 @@
+; ModuleID = 'examples/bitcode/fp_arith.bc'
+source_filename = "c.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+; Function Attrs: noinline nounwind optnone uwtable
+define dso_local double @f(double %0, float %1, i32 %2) #0 {
+  %4 = fpext float %1 to double
+  %5 = fadd double %0, %4
+  %6 = fsub nnan double %5, 1.000000e+00
+  %7 = fmul fast double %6, %6
+  %8 = fdiv nsz arcp double %7, 2.000000e+00
+  %9 = frem double %8, 3.000000e+00
+  %10 = fneg double %9
+  %11 = sitofp i32 %2 to double
+  %12 = fcmp olt double %10, %11
+  br i1 %12, label %13, label %15
+
+13:                                               ; preds = %3
+  %14 = fptrunc double %10 to float
+  br label %15
+
+15:                                               ; preds = %13, %3
+  %16 = fcmp reassoc contract afn une double %11, 0.000000e+00
+  %17 = fptosi double %11 to i32
+  %18 = fptoui double %11 to i64
+  %19 = uitofp i64 %18 to float
+  ret double %11
+}
+
+attributes #0 = { noinline nounwind optnone uwtable }
+
+!llvm.module.flags = !{!0}
+!llvm.ident = !{!1}
+
+!0 = !{i32 1, !"wchar_size", i32 4}
+!1 = !{!"clang version 10.0.0-4ubuntu1 "}
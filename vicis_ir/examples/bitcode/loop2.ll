@@ -0,0 +1,16 @@
+; ModuleID = 'examples/bitcode/loop2.bc'
+source_filename = "examples/loop2.ll"
+
+define i32 @main() {
+entry:
+  br label %no_exit
+
+no_exit:                                          ; preds = %no_exit, %entry
+  %indvar = phi i32 [ 0, %entry ], [ %indvar.next, %no_exit ]
+  %indvar.next = add i32 %indvar, 1
+  %exitcond = icmp ne i32 %indvar.next, -2147483648
+  br i1 %exitcond, label %no_exit, label %loopexit
+
+loopexit:                                         ; preds = %no_exit
+  ret i32 0
+}
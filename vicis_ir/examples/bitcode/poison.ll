@@ -0,0 +1,21 @@
+; ModuleID = 'examples/bitcode/poison.bc'
+source_filename = "examples/poison.ll"
+
+define dso_local i32 @f(i32 %x, i1 %c) {
+entry:
+  %0 = freeze i32 %x
+  %1 = add nsw i32 %0, poison
+  %2 = select i1 %c, i32 %1, i32 poison
+  %3 = freeze <2 x i32> <i32 poison, i32 1>
+  %4 = extractelement <2 x i32> %3, i32 1
+  %5 = add i32 %2, %4
+  ret i32 %5
+}
+
+define dso_local i8* @g() {
+entry:
+  br label %target
+
+target:                                           ; preds = %entry
+  ret i8* blockaddress(@g, %target)
+}
@@ -0,0 +1,20 @@
+; ModuleID = 'examples/bitcode/struct.bc'
+source_filename = "a.7rcbfp3g-cgu.0"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+%a = type { i32, [123 x i64] }
+%b = type { i32, { i32 } }
+%c = type { %c*, i8 }
+%d = type { i8, %a }
+%"\E3\81\82\E3\81\84\E3\81\86\E3\81\88\E3\81\8A" = type { i32 }
+
+define dso_local i32 @main() {
+  %1 = alloca %a, align 8
+  %2 = alloca %b, align 8
+  %3 = alloca %c, align 8
+  %4 = alloca %d, align 8
+  %5 = alloca i64, align 8
+  %6 = alloca %"\E3\81\82\E3\81\84\E3\81\86\E3\81\88\E3\81\8A", align 8
+  ret i32 0
+}
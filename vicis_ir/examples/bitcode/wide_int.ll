@@ -0,0 +1,20 @@
+; ModuleID = 'examples/bitcode/wide_int.bc'
+source_filename = "wide_int"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-unknown-linux-gnu"
+
+@g16 = global i16 -32768, align 2
+@g128 = global i128 170141183460469231731687303715884105727, align 16
+
+define i24 @f(i3 %0, i16 %1, i128 %2) {
+  %4 = add i3 %0, -1
+  %5 = xor i16 %1, -1
+  %6 = mul i128 %2, -18446744073709551616
+  %7 = icmp ult i3 %4, 3
+  %8 = ashr i24 -8388608, 4
+  ret i24 %8
+}
+
+!wide = !{!0}
+
+!0 = !{i32 7, !"PIE Level", i16 2, i128 -1, i3 3}
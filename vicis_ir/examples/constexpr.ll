@@ -0,0 +1,19 @@
+; ModuleID = 'constexpr.c'
+source_filename = "constexpr.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+@buf = global [16 x i8] zeroinitializer, align 16
+@table = global [3 x i32] [i32 1, i32 2, i32 3], align 4
+@addr = global i64 ptrtoint ([16 x i8]* @buf to i64), align 8
+@end = global i8* inttoptr (i64 add (i64 ptrtoint ([16 x i8]* @buf to i64), i64 16) to i8*), align 8
+@len = global i64 sub (i64 ptrtoint (i8* getelementptr inbounds ([16 x i8], [16 x i8]* @buf, i64 0, i64 16) to i64), i64 ptrtoint ([16 x i8]* @buf to i64)), align 8
+@low = global i32 trunc (i64 ptrtoint ([16 x i8]* @buf to i64) to i32), align 4
+@is_null = global i1 icmp eq (i8* bitcast ([16 x i8]* @buf to i8*), i8* null), align 1
+@pick = global i32 select (i1 icmp ult (i32 1, i32 2), i32 10, i32 20), align 4
+@mask = global i32 and (i32 xor (i32 -1, i32 255), i32 shl nuw (i32 1, i32 12)), align 4
+
+define dso_local i64 @f() {
+  %1 = add i64 ptrtoint ([16 x i8]* @buf to i64), 1
+  ret i64 %1
+}
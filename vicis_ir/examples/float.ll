@@ -0,0 +1,28 @@
+; ModuleID = 'c.c'
+source_filename = "c.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+@f = internal global float 0x3FB99999A0000000, align 4
+@d = internal global double 2.500000e-01, align 8
+@h = internal global half 0xH3C00, align 2
+@ld = internal global x86_fp80 0xK3FFF8000000000000000, align 16
+@q = internal global fp128 0xL00000000000000003FFF000000000000, align 16
+
+; Function Attrs: noinline nounwind optnone uwtable
+define dso_local double @main() #0 {
+  %1 = alloca double, align 8
+  %2 = alloca float, align 4
+  store double 0x7FF0000000000000, double* %1, align 8
+  store float -1.500000e+00, float* %2, align 4
+  %3 = load double, double* %1, align 8
+  ret double %3
+}
+
+attributes #0 = { noinline nounwind optnone uwtable }
+
+!llvm.module.flags = !{!0}
+!llvm.ident = !{!1}
+
+!0 = !{i32 1, !"wchar_size", i32 4}
+!1 = !{!"clang version 10.0.0-4ubuntu1 "}
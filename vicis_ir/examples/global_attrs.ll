@@ -0,0 +1,24 @@
+source_filename = "global_attrs.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+$inl = comdat any
+
+@counter = dso_local thread_local global i32 0, align 4, !dbg !0
+@tls_ie = internal thread_local(initialexec) global i64 1, align 8
+@shared = dso_local addrspace(3) global [4 x float] zeroinitializer, align 16
+@ext = external externally_initialized global i32, align 4
+@hidden = hidden local_unnamed_addr constant i8 7, section ".rodata.hidden", partition "part", align 1
+@inl = linkonce_odr dso_local global i32 5, comdat, align 4
+@in_other = linkonce_odr dso_local global i32 6, comdat($inl), align 4
+@typed = dso_local global i32 0, align 4, !type !1, !type !2
+
+define dso_local i32 @get() {
+entry:
+  %0 = load i32, i32* @counter, align 4
+  ret i32 %0
+}
+
+!0 = !{!"counter"}
+!1 = !{i64 0, !"_ZTS1A"}
+!2 = !{i64 0, !"_ZTS1B"}
@@ -0,0 +1,33 @@
+; ModuleID = 'c.c'
+source_filename = "c.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+; Function Attrs: norecurse nounwind readnone uwtable
+define dso_local i32 @f(i32 %0) local_unnamed_addr #0 {
+  switch i32 %0, label %7 [
+    i32 0, label %2
+    i32 1, label %4
+    i32 5, label %4
+  ]
+
+2:                                                ; preds = %1
+  %3 = icmp sgt i32 %0, 10
+  br label %4
+
+4:                                                ; preds = %1, %1, %2
+  %5 = phi i32 [ 3, %2 ], [ 7, %1 ], [ 7, %1 ]
+  %6 = select i1 true, i32 %5, i32 %0
+  ret i32 %6
+
+7:                                                ; preds = %1
+  unreachable
+}
+
+attributes #0 = { norecurse nounwind readnone uwtable "frame-pointer"="none" "min-legal-vector-width"="0" "no-trapping-math"="false" "stack-protector-buffer-size"="8" "target-cpu"="x86-64" "target-features"="+cx8,+fxsr,+mmx,+sse,+sse2,+x87" }
+
+!llvm.module.flags = !{!0}
+!llvm.ident = !{!1}
+
+!0 = !{i32 1, !"wchar_size", i32 4}
+!1 = !{!"clang version 10.0.0-4ubuntu1 "}
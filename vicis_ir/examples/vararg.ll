@@ -0,0 +1,15 @@
+source_filename = "vararg.c"
+
+@.str = private unnamed_addr constant [4 x i8] c"%d\0A\00", align 1
+
+define dso_local i32 @main() {
+  %1 = call i32 (...) @any()
+  %2 = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @.str, i64 0, i64 0), i32 %1)
+  %3 = bitcast i32 (...)* @any to i32 (i32, ...)*
+  %4 = call i32 (i32, ...) %3(i32 1, i32 2)
+  ret i32 %4
+}
+
+declare dso_local i32 @any(...)
+
+declare dso_local i32 @printf(i8*, ...)
@@ -0,0 +1,37 @@
+; ModuleID = 'vector.c'
+source_filename = "vector.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+@v = global <4 x i32> <i32 1, i32 2, i32 3, i32 4>, align 16
+@z = global <2 x double> zeroinitializer, align 16
+
+; Function Attrs: norecurse nounwind readnone uwtable
+define dso_local <4 x i32> @f(<4 x i32> %0, <4 x i32> %1, i32 %2) local_unnamed_addr #0 {
+  %4 = add nsw <4 x i32> %0, <i32 1, i32 1, i32 1, i32 1>
+  %5 = mul <4 x i32> %4, %1
+  %6 = icmp sgt <4 x i32> %5, zeroinitializer
+  %7 = select <4 x i1> %6, <4 x i32> %5, <4 x i32> %0
+  %8 = insertelement <4 x i32> undef, i32 %2, i32 0
+  %9 = shufflevector <4 x i32> %8, <4 x i32> undef, <4 x i32> zeroinitializer
+  %10 = shufflevector <4 x i32> %7, <4 x i32> %9, <4 x i32> <i32 0, i32 5, i32 2, i32 7>
+  %11 = extractelement <4 x i32> %10, i64 3
+  %12 = insertelement <4 x i32> %10, i32 %11, i64 0
+  ret <4 x i32> %12
+}
+
+define dso_local <vscale x 4 x float> @g(<vscale x 4 x float> %0) local_unnamed_addr #0 {
+  %2 = insertelement <vscale x 4 x float> poison, float 1.000000e+00, i32 0
+  %3 = shufflevector <vscale x 4 x float> %2, <vscale x 4 x float> poison, <vscale x 4 x i32> zeroinitializer
+  %4 = fadd <vscale x 4 x float> %0, %3
+  %5 = fmul fast <vscale x 4 x float> %4, %4
+  ret <vscale x 4 x float> %5
+}
+
+attributes #0 = { norecurse nounwind readnone uwtable "frame-pointer"="none" "min-legal-vector-width"="128" "no-trapping-math"="false" "stack-protector-buffer-size"="8" "target-cpu"="x86-64" "target-features"="+cx8,+fxsr,+mmx,+sse,+sse2,+x87" }
+
+!llvm.module.flags = !{!0}
+!llvm.ident = !{!1}
+
+!0 = !{i32 1, !"wchar_size", i32 4}
+!1 = !{!"clang version 10.0.0-4ubuntu1 "}
@@ -1,36 +1,69 @@
 use crate::ir::value::{Value, ValueId};
 
-use super::{basic_block::BasicBlockId, instruction::builder::Builder as InstBuilder, Function};
+use super::{
+    basic_block::BasicBlockId,
+    instruction::{builder::Builder as InstBuilder, Instruction, InstructionId},
+    Function,
+};
 use rustc_hash::FxHashSet;
 
 pub struct Builder<'a> {
     ctx: Context,
     pub(super) func: &'a mut Function,
     pub(super) cur_block: Option<BasicBlockId>,
+    insert_point: InsertPoint,
 }
 
 struct Context {
     is_inserted: FxHashSet<BasicBlockId>,
 }
 
+/// Where in `cur_block` new instructions go
+#[derive(Clone, Copy)]
+enum InsertPoint {
+    End,
+    Before(InstructionId),
+    After(InstructionId),
+}
+
 impl<'a> Builder<'a> {
     pub fn new(func: &'a mut Function) -> Self {
         Self {
             ctx: Context::default(),
             func,
             cur_block: None,
+            insert_point: InsertPoint::End,
         }
     }
 
+    pub fn func(&self) -> &Function {
+        self.func
+    }
+
     pub fn create_block(&mut self) -> BasicBlockId {
         self.func.data.create_block()
     }
 
+    /// Makes new instructions go at the end of `block`.
     pub fn switch_to_block(&mut self, block: BasicBlockId) {
         self.cur_block = Some(block);
+        self.insert_point = InsertPoint::End;
         self.ensure_inserted_block(block);
     }
 
+    /// Makes new instructions go right before `inst`, which must be in the layout.
+    pub fn set_insert_point_before(&mut self, inst: InstructionId) {
+        self.cur_block = Some(self.func.layout.block_of(inst).unwrap());
+        self.insert_point = InsertPoint::Before(inst);
+    }
+
+    /// Makes new instructions go right after `inst`, which must be in the layout.
+    /// Instructions built one after another stay in the order they were built.
+    pub fn set_insert_point_after(&mut self, inst: InstructionId) {
+        self.cur_block = Some(self.func.layout.block_of(inst).unwrap());
+        self.insert_point = InsertPoint::After(inst);
+    }
+
     pub fn ensure_inserted_block(&mut self, block: BasicBlockId) {
         if self.ctx.is_inserted(block) {
             return;
@@ -46,6 +79,31 @@ impl<'a> Builder<'a> {
     pub fn value<T: Into<Value>>(&mut self, val: T) -> ValueId {
         self.func.data.create_value(val.into())
     }
+
+    /// Adds `inst` at the insertion point, and records the edges it makes if it's a
+    /// terminator.
+    pub(super) fn insert(&mut self, inst: Instruction) -> InstructionId {
+        let block = inst.parent;
+        let succs = if inst.opcode.is_terminator() {
+            inst.operand.blocks().to_vec()
+        } else {
+            vec![]
+        };
+        let id = self.func.data.create_inst(inst);
+        match self.insert_point {
+            InsertPoint::End => self.func.layout.append_inst(id, block),
+            InsertPoint::Before(before) => self.func.layout.insert_inst_before(id, before),
+            InsertPoint::After(after) => {
+                self.func.layout.insert_inst_after(id, after);
+                self.insert_point = InsertPoint::After(id);
+            }
+        }
+        for succ in succs {
+            self.func.data.block_ref_mut(block).succs_mut().insert(succ);
+            self.func.data.block_ref_mut(succ).preds_mut().insert(block);
+        }
+        id
+    }
 }
 
 impl Default for Context {
@@ -0,0 +1,227 @@
+//! Changes to the control flow graph of a `Function` that keep the layout, the predecessors
+//! and successors of blocks, and phis consistent.
+
+use super::{
+    basic_block::BasicBlockId,
+    instruction::{Br, InstructionId, Opcode, Operand},
+    Function,
+};
+use rustc_hash::FxHashSet;
+
+impl Function {
+    /// Splits the block of `inst` in two right before it, and returns the new block, which
+    /// starts with `inst` and follows the old one in the layout. The old block branches to
+    /// the new one, and the successors of the old block become those of the new one.
+    /// `inst` must not be a phi.
+    pub fn split_block_at(&mut self, inst: InstructionId) -> BasicBlockId {
+        let block = self.layout.block_of(inst).unwrap();
+        let new_block = self.data.create_block();
+        self.layout.insert_block_after(new_block, block);
+
+        let moved: Vec<InstructionId> = self
+            .layout
+            .inst_iter(block)
+            .skip_while(|&i| i != inst)
+            .collect();
+        for &i in &moved {
+            self.move_inst_to_end(i, new_block);
+        }
+        let br = self.data.create_inst(
+            Opcode::Br
+                .with_block(block)
+                .with_operand(Operand::Br(Br { block: new_block })),
+        );
+        self.layout.append_inst(br, block);
+
+        let succs = std::mem::take(self.data.block_ref_mut(block).succs_mut());
+        for &succ in &succs {
+            self.replace_pred(succ, block, new_block);
+        }
+        self.data.block_ref_mut(new_block).succs = succs;
+        self.data.block_ref_mut(block).succs_mut().insert(new_block);
+        self.data.block_ref_mut(new_block).preds_mut().insert(block);
+        new_block
+    }
+
+    /// Puts a new block on the edge from `from` to `to` and returns it. All the branches
+    /// from `from` to `to` go to the new block, which branches to `to` once, so phis in `to`
+    /// keep the first of their entries for `from`.
+    pub fn split_edge(&mut self, from: BasicBlockId, to: BasicBlockId) -> BasicBlockId {
+        let new_block = self.data.create_block();
+        self.layout.insert_block_after(new_block, from);
+        let br = self.data.create_inst(
+            Opcode::Br
+                .with_block(new_block)
+                .with_operand(Operand::Br(Br { block: to })),
+        );
+        self.layout.append_inst(br, new_block);
+
+        self.retarget_terminator(from, to, new_block);
+        self.data.remove_block_succ(from, to);
+        self.data.block_ref_mut(from).succs_mut().insert(new_block);
+        self.data.block_ref_mut(new_block).preds_mut().insert(from);
+        self.data.block_ref_mut(new_block).succs_mut().insert(to);
+        self.replace_pred(to, from, new_block);
+        for phi in self.phis_of(to) {
+            self.data.dedup_phi_incoming(phi, new_block);
+        }
+        new_block
+    }
+
+    /// Splits every edge from a block with several successors to a block with several
+    /// predecessors, and returns the new blocks. As in LLVM, edges to landing pads are left
+    /// alone since only an invoke may branch to one.
+    pub fn split_critical_edges(&mut self) -> Vec<BasicBlockId> {
+        let mut edges = vec![];
+        for from in self.layout.block_iter() {
+            let succs = self.data.block_ref(from).succs();
+            if succs.len() < 2 {
+                continue;
+            }
+            let mut succs: Vec<BasicBlockId> = succs.iter().copied().collect();
+            succs.sort_by_key(|b| b.index());
+            for to in succs {
+                if self.data.block_ref(to).preds().len() > 1 && !self.is_landing_pad(to) {
+                    edges.push((from, to));
+                }
+            }
+        }
+        edges
+            .into_iter()
+            .map(|(from, to)| self.split_edge(from, to))
+            .collect()
+    }
+
+    /// Makes `from` branch to `new_to` instead of `old_to`. Phis in `old_to` forget what
+    /// they took from `from`; adding incoming values to phis in `new_to` is up to the caller.
+    pub fn redirect_edge(
+        &mut self,
+        from: BasicBlockId,
+        old_to: BasicBlockId,
+        new_to: BasicBlockId,
+    ) {
+        self.retarget_terminator(from, old_to, new_to);
+        self.data.remove_block_succ(from, old_to);
+        self.data.remove_block_pred(old_to, from);
+        self.data.block_ref_mut(from).succs_mut().insert(new_to);
+        self.data.block_ref_mut(new_to).preds_mut().insert(from);
+        for phi in self.phis_of(old_to) {
+            self.data.remove_phi_incoming(phi, from);
+        }
+    }
+
+    /// Moves the instructions of `block` to the end of its only predecessor, if it is the
+    /// only successor of it, and removes `block`. Phis in `block` are replaced with their
+    /// only incoming values. Returns whether `block` was merged.
+    pub fn merge_into_single_pred(&mut self, block: BasicBlockId) -> bool {
+        let preds = self.data.block_ref(block).preds();
+        if preds.len() != 1 || self.layout.first_block == Some(block) {
+            return false;
+        }
+        let pred = *preds.iter().next().unwrap();
+        if pred == block || self.data.block_ref(pred).succs().len() != 1 {
+            return false;
+        }
+
+        for phi in self.phis_of(block) {
+            let val = self.data.inst_ref(phi).operand.args()[0];
+            self.data.replace_all_uses(phi, val);
+            self.remove_inst(phi);
+        }
+        if let Some(br) = *self.layout.block_node(pred).last_inst() {
+            self.remove_inst(br);
+        }
+        let insts: Vec<InstructionId> = self.layout.inst_iter(block).collect();
+        for inst in insts {
+            self.move_inst_to_end(inst, pred);
+        }
+
+        let succs = std::mem::take(self.data.block_ref_mut(block).succs_mut());
+        for &succ in &succs {
+            self.replace_pred(succ, block, pred);
+        }
+        self.data.block_ref_mut(pred).succs = succs;
+        self.data.block_ref_mut(block).preds_mut().clear();
+        self.layout.remove_block(block);
+        true
+    }
+
+    /// Removes the blocks that can't be reached from the entry, and returns them.
+    pub fn remove_unreachable_blocks(&mut self) -> Vec<BasicBlockId> {
+        let mut reachable = FxHashSet::default();
+        let mut worklist: Vec<BasicBlockId> = self.layout.first_block.into_iter().collect();
+        while let Some(block) = worklist.pop() {
+            if reachable.insert(block) {
+                worklist.extend(self.data.block_ref(block).succs().iter().copied());
+            }
+        }
+
+        let unreachable: Vec<BasicBlockId> = self
+            .layout
+            .block_iter()
+            .filter(|b| !reachable.contains(b))
+            .collect();
+        for &block in &unreachable {
+            let succs = std::mem::take(self.data.block_ref_mut(block).succs_mut());
+            for succ in succs {
+                self.data.remove_block_pred(succ, block);
+                if reachable.contains(&succ) {
+                    for phi in self.phis_of(succ) {
+                        self.data.remove_phi_incoming(phi, block);
+                    }
+                }
+            }
+            self.data.block_ref_mut(block).preds_mut().clear();
+        }
+        for &block in &unreachable {
+            let insts: Vec<InstructionId> = self.layout.inst_iter(block).collect();
+            for inst in insts {
+                self.data.remove_uses(inst);
+            }
+            self.layout.remove_block(block);
+        }
+        unreachable
+    }
+
+    /// Returns the phis at the start of `block`.
+    pub fn phis_of(&self, block: BasicBlockId) -> Vec<InstructionId> {
+        self.layout
+            .inst_iter(block)
+            .take_while(|&inst| self.data.inst_ref(inst).opcode == Opcode::Phi)
+            .collect()
+    }
+
+    fn is_landing_pad(&self, block: BasicBlockId) -> bool {
+        self.layout
+            .inst_iter(block)
+            .map(|inst| self.data.inst_ref(inst).opcode)
+            .find(|&opcode| opcode != Opcode::Phi)
+            == Some(Opcode::LandingPad)
+    }
+
+    fn move_inst_to_end(&mut self, inst: InstructionId, block: BasicBlockId) {
+        self.layout.remove_inst(inst);
+        self.layout.append_inst(inst, block);
+        self.data.inst_ref_mut(inst).parent = block;
+    }
+
+    /// Makes the terminator of `block` branch to `new` wherever it branches to `old`.
+    fn retarget_terminator(&mut self, block: BasicBlockId, old: BasicBlockId, new: BasicBlockId) {
+        let term = self.layout.block_node(block).last_inst().unwrap();
+        for b in self.data.inst_ref_mut(term).operand.blocks_mut() {
+            if *b == old {
+                *b = new;
+            }
+        }
+    }
+
+    /// Records that `block` is entered from `new` instead of `old`, in its predecessors and
+    /// its phis.
+    fn replace_pred(&mut self, block: BasicBlockId, old: BasicBlockId, new: BasicBlockId) {
+        self.data.remove_block_pred(block, old);
+        self.data.block_ref_mut(block).preds_mut().insert(new);
+        for phi in self.phis_of(block) {
+            self.data.replace_phi_incoming_block(phi, old, new);
+        }
+    }
+}
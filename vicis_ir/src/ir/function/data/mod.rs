@@ -3,6 +3,7 @@ use crate::ir::{
         basic_block::{BasicBlock, BasicBlockId},
         instruction::{Instruction, InstructionId},
     },
+    module::name::Name,
     value::{Value, ValueId},
 };
 use id_arena::Arena;
@@ -13,6 +14,21 @@ pub struct Data {
     pub instructions: Arena<Instruction>,
     pub basic_blocks: Arena<BasicBlock>,
     pub users_map: FxHashMap<InstructionId, FxHashSet<InstructionId>>,
+    /// Where each value is used as an operand
+    pub uses: FxHashMap<ValueId, FxHashSet<Use>>,
+    /// Metadata values that wrap each instruction (e.g. `metadata i32 %1`). They aren't
+    /// uses, so they don't keep the instruction alive.
+    pub metadata_refs: FxHashMap<InstructionId, FxHashSet<ValueId>>,
+    /// The value of each instruction and argument, so that all their uses share it
+    inst_values: FxHashMap<InstructionId, ValueId>,
+    arg_values: FxHashMap<usize, ValueId>,
+}
+
+/// An operand of an instruction: `operand.args()[index]` of `user`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Use {
+    pub user: InstructionId,
+    pub index: usize,
 }
 
 impl Default for Data {
@@ -22,6 +38,10 @@ impl Default for Data {
             instructions: Arena::new(),
             basic_blocks: Arena::new(),
             users_map: FxHashMap::default(),
+            uses: FxHashMap::default(),
+            metadata_refs: FxHashMap::default(),
+            inst_values: FxHashMap::default(),
+            arg_values: FxHashMap::default(),
         }
     }
 }
@@ -45,15 +65,94 @@ impl Data {
         id
     }
 
-    pub fn create_value(&mut self, inst: Value) -> ValueId {
-        self.values.alloc(inst)
+    /// Creates a value. An instruction or argument gets the same `ValueId` every time, so
+    /// that `uses_of` finds all of its uses.
+    pub fn create_value(&mut self, mut inst: Value) -> ValueId {
+        match inst {
+            Value::Instruction(id) => {
+                let values = &mut self.values;
+                return *self
+                    .inst_values
+                    .entry(id)
+                    .or_insert_with(|| values.alloc(inst));
+            }
+            Value::Argument(n) => {
+                let values = &mut self.values;
+                return *self
+                    .arg_values
+                    .entry(n)
+                    .or_insert_with(|| values.alloc(inst));
+            }
+            _ => {}
+        }
+        let mut refs = vec![];
+        if let Value::Metadata(meta) = &mut inst {
+            meta.for_each_value_mut(&mut |val| {
+                if let Value::Instruction(id) = val {
+                    refs.push(*id)
+                }
+            });
+        }
+        let id = self.values.alloc(inst);
+        for inst in refs {
+            self.metadata_refs.entry(inst).or_default().insert(id);
+        }
+        id
     }
 
     pub fn replace_inst(&mut self, from: InstructionId, to: Instruction) {
+        self.remove_uses(from);
         self.instructions[from].replace(to);
         self.validate_inst_uses(from)
     }
 
+    /// Makes `phi` take what it took from `from` when coming from `to` instead.
+    pub fn replace_phi_incoming_block(
+        &mut self,
+        phi: InstructionId,
+        from: BasicBlockId,
+        to: BasicBlockId,
+    ) {
+        let phi = self.instructions[phi].operand.as_phi_mut().unwrap();
+        for block in phi.blocks_mut() {
+            if *block == from {
+                *block = to;
+            }
+        }
+    }
+
+    /// Removes what `phi` takes when coming from `block`.
+    pub fn remove_phi_incoming(&mut self, phi_id: InstructionId, block: BasicBlockId) {
+        self.remove_uses(phi_id);
+        let phi = self.instructions[phi_id].operand.as_phi_mut().unwrap();
+        let (args, blocks) = phi
+            .args
+            .iter()
+            .zip(phi.blocks.iter())
+            .filter(|(_, &b)| b != block)
+            .unzip();
+        phi.args = args;
+        phi.blocks = blocks;
+        self.validate_inst_uses(phi_id);
+    }
+
+    /// Keeps only the first of what `phi` takes when coming from `block`, e.g. once several
+    /// edges from `block` have become one.
+    pub fn dedup_phi_incoming(&mut self, phi_id: InstructionId, block: BasicBlockId) {
+        self.remove_uses(phi_id);
+        let phi = self.instructions[phi_id].operand.as_phi_mut().unwrap();
+        let mut seen = false;
+        let (args, blocks) = phi
+            .args
+            .iter()
+            .zip(phi.blocks.iter())
+            .filter(|(_, &b)| b != block || !std::mem::replace(&mut seen, true))
+            .unzip();
+        phi.args = args;
+        phi.blocks = blocks;
+        self.validate_inst_uses(phi_id);
+    }
+
     pub fn block_ref(&self, id: BasicBlockId) -> &BasicBlock {
         &self.basic_blocks[id]
     }
@@ -99,46 +198,102 @@ impl Data {
         self.users_of(id).iter().next().copied()
     }
 
+    /// Returns where `id` is used. Constants are created for every use, so each has
+    /// one use at most; see `uses_of_global` for the uses of a global.
+    pub fn uses_of(&self, id: ValueId) -> impl Iterator<Item = Use> + '_ {
+        self.uses.get(&id).into_iter().flatten().copied()
+    }
+
+    /// Returns the operands that refer to the global `name`, including through constant
+    /// expressions.
+    pub fn uses_of_global<'a>(&'a self, name: &'a Name) -> impl Iterator<Item = Use> + 'a {
+        self.uses
+            .iter()
+            .filter(move |(&id, _)| {
+                matches!(&self.values[id], Value::Constant(konst) if konst.refers_to_global(name))
+            })
+            .flat_map(|(_, uses)| uses.iter().copied())
+    }
+
+    /// Makes the `index`th operand of `inst_id` `to`.
+    pub fn set_inst_arg(&mut self, inst_id: InstructionId, index: usize, to: ValueId) {
+        self.remove_uses(inst_id);
+        self.instructions[inst_id].operand.args_mut()[index] = to;
+        self.validate_inst_uses(inst_id);
+    }
+
     pub fn replace_inst_arg(&mut self, inst_id: InstructionId, from: InstructionId, to: ValueId) {
-        let inst = &mut self.instructions[inst_id];
-        let mut replaced = false;
-
-        // Replace `from` args with `to`
-        for arg_id in inst.operand.args_mut() {
-            let arg = &self.values[*arg_id];
-            if matches!(arg, Value::Instruction(i) if i == &from) {
-                *arg_id = to;
-                replaced = true;
-            }
-            continue;
-        }
+        let indices: Vec<usize> = self.instructions[inst_id]
+            .operand
+            .args()
+            .iter()
+            .enumerate()
+            .filter(|(_, &arg)| matches!(self.values[arg], Value::Instruction(i) if i == from))
+            .map(|(i, _)| i)
+            .collect();
 
         // Return if no args replaced
-        if !replaced {
+        if indices.is_empty() {
             return;
         }
 
-        // Update users map
-
-        if let Some(map) = self.users_map.get_mut(&from) {
-            assert!(map.remove(&inst_id));
-        }
-
-        if let Value::Instruction(to) = self.values[to] {
-            self.users_map
-                .entry(to)
-                .or_insert_with(FxHashSet::default)
-                .insert(inst_id);
+        self.remove_uses(inst_id);
+        for i in indices {
+            self.instructions[inst_id].operand.args_mut()[i] = to;
         }
+        self.validate_inst_uses(inst_id);
     }
 
+    /// Replaces every use of the instruction `inst_id`, whichever value refers to it.
     pub fn replace_all_uses(&mut self, inst_id: InstructionId, to: ValueId) {
         for user_id in self.users_map[&inst_id].clone() {
             self.replace_inst_arg(user_id, inst_id, to);
         }
+        let to = self.values[to].clone();
+        self.replace_metadata_refs(inst_id, to);
+    }
+
+    /// Makes metadata that wraps the instruction `inst_id` wrap `to` instead, so that it
+    /// doesn't refer to `inst_id` once it's removed.
+    pub fn replace_metadata_refs(&mut self, inst_id: InstructionId, to: Value) {
+        let refs = match self.metadata_refs.remove(&inst_id) {
+            Some(refs) => refs,
+            None => return,
+        };
+        for id in refs {
+            let mut meta = match std::mem::replace(&mut self.values[id], Value::undef()) {
+                Value::Metadata(meta) => meta,
+                _ => unreachable!(),
+            };
+            meta.for_each_value_mut(&mut |val| {
+                if matches!(val, Value::Instruction(i) if *i == inst_id) {
+                    *val = to.clone()
+                }
+            });
+            if let Value::Instruction(to) = to {
+                self.metadata_refs.entry(to).or_default().insert(id);
+            }
+            self.values[id] = Value::Metadata(meta);
+        }
+    }
+
+    /// Replaces every use of `from` with `to`.
+    pub fn replace_all_uses_with(&mut self, from: ValueId, to: ValueId) {
+        if from == to {
+            return;
+        }
+        for Use { user, index } in self.uses_of(from).collect::<Vec<_>>() {
+            self.set_inst_arg(user, index, to);
+        }
     }
 
     pub fn validate_inst_uses(&mut self, id: InstructionId) {
+        for (index, &arg) in self.instructions[id].operand.args().iter().enumerate() {
+            self.uses
+                .entry(arg)
+                .or_default()
+                .insert(Use { user: id, index });
+        }
         let args = self.instructions[id]
             .operand
             .args()
@@ -157,6 +312,11 @@ impl Data {
     }
 
     pub fn remove_uses(&mut self, id: InstructionId) {
+        for (index, arg) in self.instructions[id].operand.args().iter().enumerate() {
+            if let Some(uses) = self.uses.get_mut(arg) {
+                uses.remove(&Use { user: id, index });
+            }
+        }
         let args = self.instructions[id]
             .operand
             .args()
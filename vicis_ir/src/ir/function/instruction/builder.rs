@@ -1,24 +1,716 @@
-use super::{InstructionId, Opcode, Operand, Ret};
-use crate::ir::{function::builder::Builder as FuncBuilder, value::ValueId};
-// use rustc_hash::FxHashSet;
+//! Builds instructions at the insertion point of a function `Builder`.
+//!
+//! Constants are untyped, so each method takes the types that the instruction records.
+//! Operands whose types are known (arguments and instruction results) are checked
+//! against them, and a mismatch panics like a failed assertion in LLVM's `IRBuilder`.
+
+use super::{
+    Alloca, AtomicCmpXchg, AtomicOrdering, AtomicRmw, AtomicRmwBinOp, Br, Call, Cast, CondBr,
+    ExtractElement, ExtractValue, FCmp, FCmpCond, FastMathFlags, Fence, FloatBinary, FloatUnary,
+    Freeze, GetElementPtr, ICmp, ICmpCond, InsertElement, InsertValue, InstructionId, IntBinary,
+    Invoke, LandingPad, Load, Opcode, Operand, Phi, Resume, Ret, Select, ShuffleVector, Store,
+    Switch, SyncScope,
+};
+use crate::ir::{
+    function::{basic_block::BasicBlockId, builder::Builder as FuncBuilder},
+    types::{Type, TypeId},
+    value::{ConstantData, ConstantInt, ValueId},
+};
 
 pub struct Builder<'a: 'short, 'short> {
     func_builder: &'short mut FuncBuilder<'a>,
 }
 
+macro_rules! int_binary {
+    ($($name:ident => $opcode:ident),*) => {
+        $(
+            pub fn $name(&mut self, ty: TypeId, lhs: ValueId, rhs: ValueId) -> InstructionId {
+                self.int_binary(Opcode::$opcode, ty, lhs, rhs)
+            }
+        )*
+    };
+}
+
+macro_rules! float_binary {
+    ($($name:ident => $opcode:ident),*) => {
+        $(
+            pub fn $name(&mut self, ty: TypeId, lhs: ValueId, rhs: ValueId) -> InstructionId {
+                self.float_binary(Opcode::$opcode, ty, lhs, rhs)
+            }
+        )*
+    };
+}
+
+macro_rules! cast {
+    ($($name:ident => $opcode:ident),*) => {
+        $(
+            pub fn $name(&mut self, from: TypeId, arg: ValueId, to: TypeId) -> InstructionId {
+                self.cast(Opcode::$opcode, from, arg, to)
+            }
+        )*
+    };
+}
+
 impl<'a: 'short, 'short> Builder<'a, 'short> {
     pub fn new(func_builder: &'short mut FuncBuilder<'a>) -> Self {
         Self { func_builder }
     }
 
+    pub fn alloca(&mut self, ty: TypeId) -> InstructionId {
+        let i32 = self.func_builder.func.types.base().i32();
+        self.build(
+            Opcode::Alloca,
+            Operand::Alloca(Alloca {
+                tys: [ty, i32],
+                num_elements: ConstantData::Int(ConstantInt::new(32, 1)),
+                align: 0,
+            }),
+        )
+    }
+
+    pub fn load(&mut self, ty: TypeId, addr: ValueId) -> InstructionId {
+        let ptr_ty = self.pointer_to(ty, addr);
+        self.build(
+            Opcode::Load,
+            Operand::Load(Load {
+                tys: [ty, ptr_ty],
+                addr,
+                align: 0,
+                volatile: false,
+                ordering: AtomicOrdering::NotAtomic,
+                syncscope: SyncScope::System,
+            }),
+        )
+    }
+
+    pub fn store(&mut self, ty: TypeId, val: ValueId, addr: ValueId) -> InstructionId {
+        self.expect(val, ty);
+        let ptr_ty = self.pointer_to(ty, addr);
+        self.build(
+            Opcode::Store,
+            Operand::Store(Store {
+                tys: [ty, ptr_ty],
+                args: [val, addr],
+                align: 0,
+                volatile: false,
+                ordering: AtomicOrdering::NotAtomic,
+                syncscope: SyncScope::System,
+            }),
+        )
+    }
+
+    pub fn fence(&mut self, ordering: AtomicOrdering) -> InstructionId {
+        self.build(
+            Opcode::Fence,
+            Operand::Fence(Fence {
+                ordering,
+                syncscope: SyncScope::System,
+            }),
+        )
+    }
+
+    /// Builds `cmpxchg` that stores `new` to `addr` if it holds `cmp`.
+    pub fn cmpxchg(
+        &mut self,
+        ty: TypeId,
+        addr: ValueId,
+        cmp: ValueId,
+        new: ValueId,
+        success_ordering: AtomicOrdering,
+        failure_ordering: AtomicOrdering,
+    ) -> InstructionId {
+        self.expect(cmp, ty);
+        self.expect(new, ty);
+        let ptr_ty = self.pointer_to(ty, addr);
+        let result_ty = {
+            let mut types = self.func_builder.func.types.base_mut();
+            let i1 = types.i1();
+            types.anonymous_struct(vec![ty, i1], false)
+        };
+        self.build(
+            Opcode::AtomicCmpXchg,
+            Operand::AtomicCmpXchg(AtomicCmpXchg {
+                tys: [result_ty, ptr_ty, ty],
+                args: [addr, cmp, new],
+                align: 0,
+                weak: false,
+                volatile: false,
+                success_ordering,
+                failure_ordering,
+                syncscope: SyncScope::System,
+            }),
+        )
+    }
+
+    pub fn atomic_rmw(
+        &mut self,
+        op: AtomicRmwBinOp,
+        ty: TypeId,
+        addr: ValueId,
+        val: ValueId,
+        ordering: AtomicOrdering,
+    ) -> InstructionId {
+        self.expect(val, ty);
+        let ptr_ty = self.pointer_to(ty, addr);
+        self.build(
+            Opcode::AtomicRmw,
+            Operand::AtomicRmw(AtomicRmw {
+                op,
+                tys: [ptr_ty, ty],
+                args: [addr, val],
+                align: 0,
+                volatile: false,
+                ordering,
+                syncscope: SyncScope::System,
+            }),
+        )
+    }
+
+    int_binary!(
+        add => Add, sub => Sub, mul => Mul, sdiv => SDiv, udiv => UDiv, srem => SRem,
+        urem => URem, and => And, or => Or, xor => Xor, shl => Shl, lshr => LShr, ashr => AShr
+    );
+
+    pub fn int_binary(
+        &mut self,
+        opcode: Opcode,
+        ty: TypeId,
+        lhs: ValueId,
+        rhs: ValueId,
+    ) -> InstructionId {
+        assert!(self.is_int(ty), "{:?} needs integer operands", opcode);
+        self.expect(lhs, ty);
+        self.expect(rhs, ty);
+        self.build(
+            opcode,
+            Operand::IntBinary(IntBinary {
+                ty,
+                nsw: false,
+                nuw: false,
+                exact: false,
+                args: [lhs, rhs],
+            }),
+        )
+    }
+
+    float_binary!(fadd => FAdd, fsub => FSub, fmul => FMul, fdiv => FDiv, frem => FRem);
+
+    pub fn float_binary(
+        &mut self,
+        opcode: Opcode,
+        ty: TypeId,
+        lhs: ValueId,
+        rhs: ValueId,
+    ) -> InstructionId {
+        assert!(self.is_float(ty), "{:?} needs float operands", opcode);
+        self.expect(lhs, ty);
+        self.expect(rhs, ty);
+        self.build(
+            opcode,
+            Operand::FloatBinary(FloatBinary {
+                ty,
+                fast_math_flags: FastMathFlags::default(),
+                args: [lhs, rhs],
+            }),
+        )
+    }
+
+    pub fn fneg(&mut self, ty: TypeId, arg: ValueId) -> InstructionId {
+        assert!(self.is_float(ty), "fneg needs a float operand");
+        self.expect(arg, ty);
+        self.build(
+            Opcode::FNeg,
+            Operand::FloatUnary(FloatUnary {
+                ty,
+                fast_math_flags: FastMathFlags::default(),
+                arg,
+            }),
+        )
+    }
+
+    pub fn icmp(
+        &mut self,
+        cond: ICmpCond,
+        ty: TypeId,
+        lhs: ValueId,
+        rhs: ValueId,
+    ) -> InstructionId {
+        let scalar = self.scalar(ty);
+        let is_pointer = matches!(&*self.func_builder.func.types.get(scalar), Type::Pointer(_));
+        assert!(
+            self.is_int(ty) || is_pointer,
+            "icmp needs integer or pointer operands"
+        );
+        self.expect(lhs, ty);
+        self.expect(rhs, ty);
+        self.build(
+            Opcode::ICmp,
+            Operand::ICmp(ICmp {
+                ty,
+                args: [lhs, rhs],
+                cond,
+            }),
+        )
+    }
+
+    pub fn fcmp(
+        &mut self,
+        cond: FCmpCond,
+        ty: TypeId,
+        lhs: ValueId,
+        rhs: ValueId,
+    ) -> InstructionId {
+        assert!(self.is_float(ty), "fcmp needs float operands");
+        self.expect(lhs, ty);
+        self.expect(rhs, ty);
+        self.build(
+            Opcode::FCmp,
+            Operand::FCmp(FCmp {
+                ty,
+                fast_math_flags: FastMathFlags::default(),
+                args: [lhs, rhs],
+                cond,
+            }),
+        )
+    }
+
+    cast!(
+        trunc => Trunc, zext => Zext, sext => Sext, fptrunc => FPTrunc, fpext => FPExt,
+        fptoui => FPToUI, fptosi => FPToSI, uitofp => UIToFP, sitofp => SIToFP,
+        ptrtoint => PtrToInt, inttoptr => IntToPtr, bitcast => Bitcast,
+        addrspacecast => AddrSpaceCast
+    );
+
+    pub fn cast(
+        &mut self,
+        opcode: Opcode,
+        from: TypeId,
+        arg: ValueId,
+        to: TypeId,
+    ) -> InstructionId {
+        let types = &self.func_builder.func.types;
+        assert!(
+            opcode.is_valid_cast(from, to, types),
+            "{:?} can't convert {} to {}",
+            opcode,
+            types.to_string(from),
+            types.to_string(to)
+        );
+        self.expect(arg, from);
+        self.build(
+            opcode,
+            Operand::Cast(Cast {
+                tys: [from, to],
+                arg,
+            }),
+        )
+    }
+
+    /// Builds `getelementptr` that indexes `base`, a pointer to `ty`, with `indices`, each
+    /// of which is a pair of the type and the index.
+    pub fn gep(
+        &mut self,
+        ty: TypeId,
+        base: ValueId,
+        indices: &[(TypeId, ValueId)],
+        inbounds: bool,
+    ) -> InstructionId {
+        let ptr_ty = self.pointer_to(ty, base);
+        let mut tys = vec![ty, ptr_ty];
+        let mut args = vec![base];
+        for &(idx_ty, idx) in indices {
+            assert!(self.is_int(idx_ty), "getelementptr needs integer indices");
+            self.expect(idx, idx_ty);
+            tys.push(idx_ty);
+            args.push(idx);
+        }
+        self.build(
+            Opcode::GetElementPtr,
+            Operand::GetElementPtr(GetElementPtr {
+                inbounds,
+                tys,
+                args,
+            }),
+        )
+    }
+
+    pub fn select(
+        &mut self,
+        ty: TypeId,
+        cond: ValueId,
+        then: ValueId,
+        els: ValueId,
+    ) -> InstructionId {
+        let i1 = self.func_builder.func.types.base().i1();
+        self.expect(cond, i1);
+        self.expect(then, ty);
+        self.expect(els, ty);
+        self.build(
+            Opcode::Select,
+            Operand::Select(Select {
+                tys: [i1, ty],
+                args: [cond, then, els],
+            }),
+        )
+    }
+
+    pub fn freeze(&mut self, ty: TypeId, arg: ValueId) -> InstructionId {
+        self.expect(arg, ty);
+        self.build(Opcode::Freeze, Operand::Freeze(Freeze { ty, arg }))
+    }
+
+    /// Builds `extractvalue` that takes the element at `indices` of `aggre` of `ty`.
+    pub fn extract_value(&mut self, ty: TypeId, aggre: ValueId, indices: &[u32]) -> InstructionId {
+        self.expect(aggre, ty);
+        let mut args = vec![aggre];
+        args.extend(self.indices(ty, indices));
+        self.build(
+            Opcode::ExtractValue,
+            Operand::ExtractValue(ExtractValue { ty, args }),
+        )
+    }
+
+    /// Builds `insertvalue` that puts `val` of `val_ty` at `indices` of `aggre` of `ty`.
+    pub fn insert_value(
+        &mut self,
+        ty: TypeId,
+        aggre: ValueId,
+        val_ty: TypeId,
+        val: ValueId,
+        indices: &[u32],
+    ) -> InstructionId {
+        self.expect(aggre, ty);
+        self.expect(val, val_ty);
+        let mut args = vec![aggre, val];
+        args.extend(self.indices(ty, indices));
+        self.build(
+            Opcode::InsertValue,
+            Operand::InsertValue(InsertValue {
+                tys: [ty, val_ty],
+                args,
+            }),
+        )
+    }
+
+    pub fn extract_element(
+        &mut self,
+        vec_ty: TypeId,
+        vec: ValueId,
+        idx_ty: TypeId,
+        idx: ValueId,
+    ) -> InstructionId {
+        self.expect(vec, vec_ty);
+        self.expect(idx, idx_ty);
+        self.build(
+            Opcode::ExtractElement,
+            Operand::ExtractElement(ExtractElement {
+                tys: [vec_ty, idx_ty],
+                args: [vec, idx],
+            }),
+        )
+    }
+
+    pub fn insert_element(
+        &mut self,
+        vec_ty: TypeId,
+        vec: ValueId,
+        elem: ValueId,
+        idx_ty: TypeId,
+        idx: ValueId,
+    ) -> InstructionId {
+        let elem_ty = self.func_builder.func.types.get_element(vec_ty);
+        assert!(elem_ty.is_some(), "insertelement needs a vector");
+        self.expect(vec, vec_ty);
+        self.expect(elem, elem_ty.unwrap());
+        self.expect(idx, idx_ty);
+        self.build(
+            Opcode::InsertElement,
+            Operand::InsertElement(InsertElement {
+                tys: [vec_ty, elem_ty.unwrap(), idx_ty],
+                args: [vec, elem, idx],
+            }),
+        )
+    }
+
+    pub fn shuffle_vector(
+        &mut self,
+        vec_ty: TypeId,
+        lhs: ValueId,
+        rhs: ValueId,
+        mask_ty: TypeId,
+        mask: ValueId,
+    ) -> InstructionId {
+        self.expect(lhs, vec_ty);
+        self.expect(rhs, vec_ty);
+        self.expect(mask, mask_ty);
+        self.build(
+            Opcode::ShuffleVector,
+            Operand::ShuffleVector(ShuffleVector {
+                tys: [vec_ty, mask_ty],
+                args: [lhs, rhs, mask],
+            }),
+        )
+    }
+
+    /// Builds `call` of `callee` whose type is `fn_ty`, a function type.
+    pub fn call(&mut self, fn_ty: TypeId, callee: ValueId, args: &[ValueId]) -> InstructionId {
+        let (tys, args) = self.call_operands(fn_ty, callee, args);
+        self.build(
+            Opcode::Call,
+            Operand::Call(Call {
+                param_attrs: vec![vec![]; args.len() - 1],
+                args,
+                tys,
+                ret_attrs: vec![],
+                func_attrs: vec![],
+            }),
+        )
+    }
+
+    /// Builds `invoke` of `callee` that continues at `normal`, or at `unwind` if the
+    /// callee throws.
+    pub fn invoke(
+        &mut self,
+        fn_ty: TypeId,
+        callee: ValueId,
+        args: &[ValueId],
+        normal: BasicBlockId,
+        unwind: BasicBlockId,
+    ) -> InstructionId {
+        let (tys, args) = self.call_operands(fn_ty, callee, args);
+        self.build(
+            Opcode::Invoke,
+            Operand::Invoke(Invoke {
+                param_attrs: vec![vec![]; args.len() - 1],
+                args,
+                tys,
+                ret_attrs: vec![],
+                func_attrs: vec![],
+                blocks: vec![normal, unwind],
+            }),
+        )
+    }
+
+    pub fn landing_pad(&mut self, ty: TypeId) -> InstructionId {
+        self.build(Opcode::LandingPad, Operand::LandingPad(LandingPad { ty }))
+    }
+
+    pub fn resume(&mut self, ty: TypeId, arg: ValueId) -> InstructionId {
+        self.expect(arg, ty);
+        self.build(Opcode::Resume, Operand::Resume(Resume { ty, arg }))
+    }
+
+    /// Builds `phi` with no incoming values. Add them with `add_incoming`.
+    pub fn phi(&mut self, ty: TypeId) -> InstructionId {
+        self.build(
+            Opcode::Phi,
+            Operand::Phi(Phi {
+                ty,
+                args: vec![],
+                blocks: vec![],
+            }),
+        )
+    }
+
+    /// Adds to `phi` that it's `val` when coming from `block`.
+    pub fn add_incoming(&mut self, phi: InstructionId, val: ValueId, block: BasicBlockId) {
+        let data = &mut self.func_builder.func.data;
+        let ty = match data.inst_ref(phi).operand.as_phi() {
+            Some(phi) => phi.ty,
+            None => panic!("add_incoming needs a phi"),
+        };
+        self.expect(val, ty);
+        let data = &mut self.func_builder.func.data;
+        let operand = data.inst_ref_mut(phi).operand.as_phi_mut().unwrap();
+        operand.args.push(val);
+        operand.blocks.push(block);
+        data.validate_inst_uses(phi);
+    }
+
+    pub fn br(&mut self, block: BasicBlockId) -> InstructionId {
+        self.build(Opcode::Br, Operand::Br(Br { block }))
+    }
+
+    pub fn cond_br(
+        &mut self,
+        cond: ValueId,
+        then: BasicBlockId,
+        els: BasicBlockId,
+    ) -> InstructionId {
+        let i1 = self.func_builder.func.types.base().i1();
+        self.expect(cond, i1);
+        self.build(
+            Opcode::CondBr,
+            Operand::CondBr(CondBr {
+                arg: cond,
+                blocks: [then, els],
+            }),
+        )
+    }
+
+    /// Builds `switch` on `cond` of `ty` that jumps to the block of the case equal to
+    /// `cond`, or to `default`.
+    pub fn switch(
+        &mut self,
+        ty: TypeId,
+        cond: ValueId,
+        default: BasicBlockId,
+        cases: &[(ValueId, BasicBlockId)],
+    ) -> InstructionId {
+        assert!(
+            matches!(&*self.func_builder.func.types.get(ty), Type::Int(_)),
+            "switch needs an integer condition"
+        );
+        self.expect(cond, ty);
+        let mut tys = vec![ty];
+        let mut args = vec![cond];
+        let mut blocks = vec![default];
+        for &(val, block) in cases {
+            tys.push(ty);
+            args.push(val);
+            blocks.push(block);
+        }
+        self.build(
+            Opcode::Switch,
+            Operand::Switch(Switch { tys, args, blocks }),
+        )
+    }
+
     pub fn ret(&mut self, val: ValueId) -> InstructionId {
-        let cur_block = self.func_builder.cur_block.unwrap();
         let ty = self.func_builder.func.result_ty;
-        let inst = Opcode::Ret
-            .with_block(cur_block)
-            .with_operand(Operand::Ret(Ret { ty, val: Some(val) }));
-        let inst = self.func_builder.func.data.create_inst(inst);
-        self.func_builder.func.layout.append_inst(inst, cur_block);
-        inst
+        self.expect(val, ty);
+        self.build(Opcode::Ret, Operand::Ret(Ret { ty, val: Some(val) }))
+    }
+
+    pub fn ret_void(&mut self) -> InstructionId {
+        let ty = self.func_builder.func.result_ty;
+        assert!(
+            ty == self.func_builder.func.types.base().void(),
+            "ret void in a function returning a value"
+        );
+        self.build(Opcode::Ret, Operand::Ret(Ret { ty, val: None }))
+    }
+
+    pub fn unreachable(&mut self) -> InstructionId {
+        self.build(Opcode::Unreachable, Operand::Unreachable)
+    }
+
+    fn build(&mut self, opcode: Opcode, operand: Operand) -> InstructionId {
+        let inst = opcode
+            .with_block(self.func_builder.cur_block.unwrap())
+            .with_operand(operand);
+        self.func_builder.insert(inst)
+    }
+
+    /// Returns the types that `call` or `invoke` records, and the callee followed by `args`.
+    fn call_operands(
+        &self,
+        fn_ty: TypeId,
+        callee: ValueId,
+        args: &[ValueId],
+    ) -> (Vec<TypeId>, Vec<ValueId>) {
+        let func_ty = match &*self.func_builder.func.types.get(fn_ty) {
+            Type::Function(func_ty) => func_ty.clone(),
+            _ => panic!("callee's type must be a function type"),
+        };
+        assert!(
+            args.len() == func_ty.params.len()
+                || (func_ty.is_var_arg && args.len() > func_ty.params.len()),
+            "call with {} arguments doesn't match the callee's type",
+            args.len()
+        );
+        // The callee's type is written out only if it's variadic.
+        let mut tys = vec![if func_ty.is_var_arg {
+            fn_ty
+        } else {
+            func_ty.ret
+        }];
+        for (i, &arg) in args.iter().enumerate() {
+            let ty = match func_ty.params.get(i) {
+                Some(&ty) => ty,
+                None => self
+                    .func_builder
+                    .func
+                    .value_ty(arg)
+                    .expect("variadic argument must be typed"),
+            };
+            self.expect(arg, ty);
+            tys.push(ty);
+        }
+        let mut operands = vec![callee];
+        operands.extend(args);
+        (tys, operands)
+    }
+
+    /// Returns `i32` constants of `indices`, checking that they are in `ty`.
+    fn indices(&mut self, ty: TypeId, indices: &[u32]) -> Vec<ValueId> {
+        let mut cur = ty;
+        let mut args = vec![];
+        for &idx in indices {
+            cur = match self
+                .func_builder
+                .func
+                .types
+                .base()
+                .element_at(cur, idx as usize)
+            {
+                Some(elem) => elem,
+                None => panic!("index {} is out of the aggregate", idx),
+            };
+            args.push(self.func_builder.value(ConstantInt::new(32, idx as u64)));
+        }
+        args
+    }
+
+    /// Returns the type of `addr` that should point to `ty`.
+    fn pointer_to(&self, ty: TypeId, addr: ValueId) -> TypeId {
+        let types = &self.func_builder.func.types;
+        match self.func_builder.func.value_ty(addr) {
+            Some(ptr_ty) => {
+                let pointee = match &*types.get(ptr_ty) {
+                    Type::Pointer(ptr) => ptr.inner,
+                    _ => panic!("address must be a pointer"),
+                };
+                assert!(
+                    types.is_same(pointee, ty),
+                    "address points to {}, not {}",
+                    types.to_string(pointee),
+                    types.to_string(ty)
+                );
+                ptr_ty
+            }
+            None => types.base_mut().pointer(ty),
+        }
+    }
+
+    /// Panics if the type of `val` is known and is not `ty`.
+    fn expect(&self, val: ValueId, ty: TypeId) {
+        let types = &self.func_builder.func.types;
+        if let Some(actual) = self.func_builder.func.value_ty(val) {
+            assert!(
+                types.is_same(actual, ty),
+                "expected a value of {}, but got {}",
+                types.to_string(ty),
+                types.to_string(actual)
+            );
+        }
+    }
+
+    fn scalar(&self, ty: TypeId) -> TypeId {
+        match &*self.func_builder.func.types.get(ty) {
+            Type::Vector(v) => v.inner,
+            _ => ty,
+        }
+    }
+
+    fn is_int(&self, ty: TypeId) -> bool {
+        matches!(
+            &*self.func_builder.func.types.get(self.scalar(ty)),
+            Type::Int(_)
+        )
+    }
+
+    fn is_float(&self, ty: TypeId) -> bool {
+        self.func_builder.func.types.is_float(self.scalar(ty))
     }
 }
@@ -7,8 +7,12 @@ use rustc_hash::FxHashMap;
 use crate::ir::{
     function::{basic_block::BasicBlockId, data::Data, param_attrs::ParameterAttribute},
     module::{attributes::Attribute, metadata::Metadata, name::Name},
-    types::TypeId,
-    value::{ConstantData, ConstantInt, Value, ValueId},
+    types::{Type, TypeId, Types},
+    util::escape,
+    value::{
+        fold::{fold_cast, fold_extractelement, fold_freeze, fold_icmp, fold_int_binary},
+        ConstantData, Value, ValueId,
+    },
 };
 use id_arena::Id;
 use std::{fmt, slice};
@@ -30,29 +34,60 @@ pub enum Opcode {
     Phi,
     Load,
     Store,
+    AtomicCmpXchg,
+    AtomicRmw,
+    Fence,
     InsertValue,
     ExtractValue,
+    ExtractElement,
+    InsertElement,
+    ShuffleVector,
     Add,
     Sub,
     Mul,
     SDiv,
+    UDiv,
     SRem,
+    URem,
     And,
+    Or,
+    Xor,
+    Shl,
     LShr,
+    AShr,
+    FNeg,
+    FAdd,
+    FSub,
+    FMul,
+    FDiv,
+    FRem,
     ICmp,
+    FCmp,
     Sext,
     Zext,
     Bitcast,
     Trunc,
     IntToPtr,
+    PtrToInt,
+    AddrSpaceCast,
+    FPToUI,
+    FPToSI,
+    UIToFP,
+    SIToFP,
+    FPTrunc,
+    FPExt,
     GetElementPtr,
+    Select,
+    Freeze,
     Call,
     Invoke,
     LandingPad,
     Resume,
     Br,
     CondBr,
+    Switch,
     Ret,
+    Unreachable,
     Invalid,
 }
 
@@ -70,6 +105,75 @@ pub enum ICmpCond {
     Sle,
 }
 
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum FCmpCond {
+    False,
+    Oeq,
+    Ogt,
+    Oge,
+    Olt,
+    Ole,
+    One,
+    Ord,
+    Ueq,
+    Ugt,
+    Uge,
+    Ult,
+    Ule,
+    Une,
+    Uno,
+    True,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum AtomicOrdering {
+    NotAtomic,
+    Unordered,
+    Monotonic,
+    Acquire,
+    Release,
+    AcqRel,
+    SeqCst,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub enum SyncScope {
+    #[default]
+    System,
+    SingleThread,
+    Target(String),
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum AtomicRmwBinOp {
+    Xchg,
+    Add,
+    Sub,
+    And,
+    Nand,
+    Or,
+    Xor,
+    Max,
+    Min,
+    UMax,
+    UMin,
+    FAdd,
+    FSub,
+    FMax,
+    FMin,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct FastMathFlags {
+    pub nnan: bool,
+    pub ninf: bool,
+    pub nsz: bool,
+    pub arcp: bool,
+    pub contract: bool,
+    pub afn: bool,
+    pub reassoc: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct Alloca {
     pub tys: [TypeId; 2],
@@ -89,6 +193,9 @@ pub struct Load {
     pub tys: [TypeId; 2],
     pub addr: ValueId,
     pub align: u32,
+    pub volatile: bool,
+    pub ordering: AtomicOrdering,
+    pub syncscope: SyncScope,
 }
 
 #[derive(Debug, Clone)]
@@ -100,11 +207,57 @@ pub struct IntBinary {
     pub args: [ValueId; 2],
 }
 
+#[derive(Debug, Clone)]
+pub struct FloatBinary {
+    pub ty: TypeId,
+    pub fast_math_flags: FastMathFlags,
+    pub args: [ValueId; 2],
+}
+
+#[derive(Debug, Clone)]
+pub struct FloatUnary {
+    pub ty: TypeId,
+    pub fast_math_flags: FastMathFlags,
+    pub arg: ValueId,
+}
+
 #[derive(Debug, Clone)]
 pub struct Store {
     pub tys: [TypeId; 2],
     pub args: [ValueId; 2],
     pub align: u32,
+    pub volatile: bool,
+    pub ordering: AtomicOrdering,
+    pub syncscope: SyncScope,
+}
+
+#[derive(Debug, Clone)]
+pub struct AtomicCmpXchg {
+    pub tys: [TypeId; 3],   // result type ({ty, i1}), pointer type, value type
+    pub args: [ValueId; 3], // pointer, compare value, new value
+    pub align: u32,
+    pub weak: bool,
+    pub volatile: bool,
+    pub success_ordering: AtomicOrdering,
+    pub failure_ordering: AtomicOrdering,
+    pub syncscope: SyncScope,
+}
+
+#[derive(Debug, Clone)]
+pub struct AtomicRmw {
+    pub op: AtomicRmwBinOp,
+    pub tys: [TypeId; 2],   // pointer type, value type
+    pub args: [ValueId; 2], // pointer, value
+    pub align: u32,
+    pub volatile: bool,
+    pub ordering: AtomicOrdering,
+    pub syncscope: SyncScope,
+}
+
+#[derive(Debug, Clone)]
+pub struct Fence {
+    pub ordering: AtomicOrdering,
+    pub syncscope: SyncScope,
 }
 
 #[derive(Debug, Clone)]
@@ -119,6 +272,24 @@ pub struct ExtractValue {
     pub args: Vec<ValueId>,
 }
 
+#[derive(Debug, Clone)]
+pub struct ExtractElement {
+    pub tys: [TypeId; 2],   // vector type, index type
+    pub args: [ValueId; 2], // vector, index
+}
+
+#[derive(Debug, Clone)]
+pub struct InsertElement {
+    pub tys: [TypeId; 3],   // vector type, element type, index type
+    pub args: [ValueId; 3], // vector, element, index
+}
+
+#[derive(Debug, Clone)]
+pub struct ShuffleVector {
+    pub tys: [TypeId; 2],   // operand vector type, mask type
+    pub args: [ValueId; 3], // first vector, second vector, mask
+}
+
 #[derive(Debug, Clone)]
 pub struct ICmp {
     pub ty: TypeId,
@@ -126,6 +297,14 @@ pub struct ICmp {
     pub cond: ICmpCond,
 }
 
+#[derive(Debug, Clone)]
+pub struct FCmp {
+    pub ty: TypeId,
+    pub fast_math_flags: FastMathFlags,
+    pub args: [ValueId; 2],
+    pub cond: FCmpCond,
+}
+
 #[derive(Debug, Clone)]
 pub struct Cast {
     pub tys: [TypeId; 2], // from, to
@@ -139,6 +318,18 @@ pub struct GetElementPtr {
     pub args: Vec<ValueId>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Select {
+    pub tys: [TypeId; 2],   // condition type, value type
+    pub args: [ValueId; 3], // condition, true value, false value
+}
+
+#[derive(Debug, Clone)]
+pub struct Freeze {
+    pub ty: TypeId,
+    pub arg: ValueId,
+}
+
 #[derive(Debug, Clone)]
 pub struct Call {
     pub args: Vec<ValueId>, // args[0] = callee, args[1..] = arguments
@@ -180,6 +371,13 @@ pub struct CondBr {
     pub blocks: [BasicBlockId; 2], // iftrue, iffalse
 }
 
+#[derive(Debug, Clone)]
+pub struct Switch {
+    pub tys: Vec<TypeId>,   // tys[0] = condition type, tys[1..] = case value types
+    pub args: Vec<ValueId>, // args[0] = condition, args[1..] = case values
+    pub blocks: Vec<BasicBlockId>, // blocks[0] = default destination, blocks[1..] = case destinations
+}
+
 #[derive(Debug, Clone)]
 pub struct Ret {
     pub ty: TypeId,
@@ -192,19 +390,32 @@ pub enum Operand {
     Phi(Phi),
     Load(Load),
     IntBinary(IntBinary),
+    FloatBinary(FloatBinary),
+    FloatUnary(FloatUnary),
     Store(Store),
+    AtomicCmpXchg(AtomicCmpXchg),
+    AtomicRmw(AtomicRmw),
+    Fence(Fence),
     InsertValue(InsertValue),
     ExtractValue(ExtractValue),
+    ExtractElement(ExtractElement),
+    InsertElement(InsertElement),
+    ShuffleVector(ShuffleVector),
     ICmp(ICmp),
+    FCmp(FCmp),
     Cast(Cast),
     GetElementPtr(GetElementPtr),
+    Select(Select),
+    Freeze(Freeze),
     Call(Call),
     Invoke(Invoke),
     LandingPad(LandingPad),
     Resume(Resume),
     Br(Br),
     CondBr(CondBr),
+    Switch(Switch),
     Ret(Ret),
+    Unreachable,
     Invalid,
 }
 
@@ -232,44 +443,121 @@ impl Instruction {
         self
     }
 
-    pub fn fold_consts(&self, data: &Data) -> Option<ConstantData> {
+    /// Returns the type of the value this instruction produces, or `None` if it produces
+    /// nothing or its type can't be told (e.g. a `getelementptr` over a vector of pointers).
+    pub fn result_ty(&self, data: &Data, types: &Types) -> Option<TypeId> {
+        // Struct field numbers in `getelementptr` and `extractvalue` are constants.
+        let index = |arg: &ValueId| match data.value_ref(*arg) {
+            Value::Constant(ConstantData::Int(i)) => Some(i.cast_to_usize()),
+            _ => None,
+        };
+        let ty = match &self.operand {
+            Operand::Alloca(a) => types.base_mut().pointer(a.tys[0]),
+            Operand::Phi(p) => p.ty,
+            Operand::Load(l) => l.tys[0],
+            Operand::IntBinary(b) => b.ty,
+            Operand::FloatBinary(b) => b.ty,
+            Operand::FloatUnary(u) => u.ty,
+            Operand::ICmp(ICmp { ty, .. }) | Operand::FCmp(FCmp { ty, .. }) => {
+                let i1 = types.base().i1();
+                let vector = match &*types.get(*ty) {
+                    Type::Vector(v) => Some((v.num_elements, v.is_scalable)),
+                    _ => None,
+                };
+                match vector {
+                    Some((n, is_scalable)) => types.base_mut().vector(i1, n, is_scalable),
+                    None => i1,
+                }
+            }
+            Operand::Cast(c) => c.tys[1],
+            Operand::GetElementPtr(g) => {
+                let addr_space = match &*types.get(*g.tys.get(1)?) {
+                    Type::Pointer(p) => p.addr_space,
+                    _ => return None,
+                };
+                let mut ty = g.tys[0];
+                for arg in g.args.iter().skip(2) {
+                    ty = types.base().element_at(ty, index(arg).unwrap_or(0))?;
+                }
+                types.base_mut().pointer_in_addr_space(ty, addr_space)
+            }
+            Operand::Select(s) => s.tys[1],
+            Operand::Freeze(f) => f.ty,
+            Operand::InsertValue(v) => v.tys[0],
+            Operand::ExtractValue(v) => {
+                let mut ty = v.ty;
+                for arg in &v.args[1..] {
+                    ty = types.base().element_at(ty, index(arg)?)?;
+                }
+                ty
+            }
+            Operand::InsertElement(e) => e.tys[0],
+            Operand::ExtractElement(e) => types.get_element(e.tys[0])?,
+            Operand::ShuffleVector(s) => {
+                let (num_elements, is_scalable) = match &*types.get(s.tys[1]) {
+                    Type::Vector(mask) => (mask.num_elements, mask.is_scalable),
+                    _ => return None,
+                };
+                let elem = types.get_element(s.tys[0])?;
+                types.base_mut().vector(elem, num_elements, is_scalable)
+            }
+            Operand::AtomicCmpXchg(c) => c.tys[0],
+            Operand::AtomicRmw(r) => r.tys[1],
+            Operand::LandingPad(l) => l.ty,
+            Operand::Call(Call { tys, .. }) | Operand::Invoke(Invoke { tys, .. }) => {
+                match &*types.get(tys[0]) {
+                    Type::Function(f) => f.ret,
+                    _ => tys[0],
+                }
+            }
+            _ => return None,
+        };
+        if ty == types.base().void() {
+            return None;
+        }
+        Some(ty)
+    }
+
+    pub fn fold_consts(&self, data: &Data, types: &Types) -> Option<ConstantData> {
         match self.operand {
             Operand::IntBinary(ref i) => {
-                let args: Vec<&Value> = i.args.iter().map(|&id| data.value_ref(id)).collect();
-                match args.as_slice() {
-                    [Value::Constant(ConstantData::Int(ConstantInt::Int32(x))), Value::Constant(ConstantData::Int(ConstantInt::Int32(y)))] => {
-                        match self.opcode {
-                            Opcode::Add => Some(ConstantData::Int(ConstantInt::Int32(x + y))),
-                            Opcode::Sub => Some(ConstantData::Int(ConstantInt::Int32(x - y))),
-                            Opcode::Mul => Some(ConstantData::Int(ConstantInt::Int32(x * y))),
-                            Opcode::SRem => Some(ConstantData::Int(ConstantInt::Int32(x % y))),
-                            _ => None,
-                        }
-                    }
+                match [data.value_ref(i.args[0]), data.value_ref(i.args[1])] {
+                    [Value::Constant(x), Value::Constant(y)] => fold_int_binary(self.opcode, x, y),
                     _ => None,
                 }
             }
-            Operand::ICmp(ref i) => {
-                let args: Vec<&Value> = i.args.iter().map(|&id| data.value_ref(id)).collect();
-                match args.as_slice() {
-                    [Value::Constant(ConstantData::Int(ConstantInt::Int32(x))), Value::Constant(ConstantData::Int(ConstantInt::Int32(y)))] => {
-                        match i.cond {
-                            ICmpCond::Eq => Some(ConstantData::Int(ConstantInt::Int1(x == y))),
-                            ICmpCond::Ne => Some(ConstantData::Int(ConstantInt::Int1(x != y))),
-                            ICmpCond::Ugt => Some(ConstantData::Int(ConstantInt::Int1(x > y))),
-                            ICmpCond::Uge => Some(ConstantData::Int(ConstantInt::Int1(x >= y))),
-                            ICmpCond::Ult => Some(ConstantData::Int(ConstantInt::Int1(x < y))),
-                            ICmpCond::Ule => Some(ConstantData::Int(ConstantInt::Int1(x <= y))),
-                            ICmpCond::Sgt => Some(ConstantData::Int(ConstantInt::Int1(x > y))),
-                            ICmpCond::Sge => Some(ConstantData::Int(ConstantInt::Int1(x >= y))),
-                            ICmpCond::Slt => Some(ConstantData::Int(ConstantInt::Int1(x < y))),
-                            ICmpCond::Sle => Some(ConstantData::Int(ConstantInt::Int1(x <= y))),
-                        }
+            Operand::ICmp(ref i) => match [data.value_ref(i.args[0]), data.value_ref(i.args[1])] {
+                [Value::Constant(x), Value::Constant(y)] => {
+                    fold_icmp(i.cond, x, y, types.base().i1())
+                }
+                _ => None,
+            },
+            Operand::ExtractElement(ref e) => {
+                match [data.value_ref(e.args[0]), data.value_ref(e.args[1])] {
+                    [Value::Constant(v), Value::Constant(i)] => {
+                        fold_extractelement(v, i, e.tys[0], types)
                     }
                     _ => None,
                 }
             }
-            Operand::Cast(_) => todo!(),
+            Operand::Select(ref s) => match data.value_ref(s.cond()) {
+                Value::Constant(ConstantData::Int(cond)) => {
+                    match data.value_ref(s.args[if cond.is_zero() { 2 } else { 1 }]) {
+                        Value::Constant(konst) => Some(konst.clone()),
+                        _ => None,
+                    }
+                }
+                Value::Constant(ConstantData::Poison) => Some(ConstantData::Poison),
+                _ => None,
+            },
+            Operand::Freeze(ref f) => match data.value_ref(f.arg) {
+                Value::Constant(konst) => fold_freeze(konst, f.ty, types),
+                _ => None,
+            },
+            Operand::Cast(ref c) => match data.value_ref(c.arg) {
+                Value::Constant(konst) => fold_cast(self.opcode, konst, c.tys[1], types),
+                _ => None,
+            },
             _ => None,
         }
     }
@@ -287,13 +575,63 @@ impl Opcode {
         }
     }
 
+    /// Returns true if the integer binary operator accepts `nuw` and `nsw`.
+    pub fn has_wrap_flags(&self) -> bool {
+        matches!(self, Self::Add | Self::Sub | Self::Mul | Self::Shl)
+    }
+
+    /// Returns true if the integer binary operator accepts `exact`.
+    pub fn has_exact_flag(&self) -> bool {
+        matches!(self, Self::SDiv | Self::UDiv | Self::LShr | Self::AShr)
+    }
+
+    /// Returns true if the integer binary operator divides by its second operand.
+    pub fn is_division(&self) -> bool {
+        matches!(self, Self::SDiv | Self::UDiv | Self::SRem | Self::URem)
+    }
+
     pub fn is_terminator(&self) -> bool {
         matches!(
             self,
-            Self::Ret | Self::Br | Self::CondBr | Self::Invoke | Self::Resume
+            Self::Ret
+                | Self::Br
+                | Self::CondBr
+                | Self::Switch
+                | Self::Invoke
+                | Self::Resume
+                | Self::Unreachable
         )
     }
 
+    /// Returns true if this cast opcode can convert `from` to `to`. The sizes of
+    /// `bitcast` operands are not checked.
+    pub fn is_valid_cast(&self, from: TypeId, to: TypeId, types: &Types) -> bool {
+        let scalar = |ty: TypeId| match &*types.get(ty) {
+            Type::Vector(v) => v.inner,
+            _ => ty,
+        };
+        let (from, to) = (scalar(from), scalar(to));
+        let bits = |ty: TypeId| match &*types.get(ty) {
+            Type::Int(bits) => Some(*bits),
+            _ => None,
+        };
+        let is_pointer = |ty: TypeId| matches!(&*types.get(ty), Type::Pointer(_));
+        match self {
+            Self::Trunc => matches!((bits(from), bits(to)), (Some(f), Some(t)) if f > t),
+            Self::Zext | Self::Sext => {
+                matches!((bits(from), bits(to)), (Some(f), Some(t)) if f < t)
+            }
+            Self::FPTrunc | Self::FPExt => types.is_float(from) && types.is_float(to) && from != to,
+            Self::FPToUI | Self::FPToSI => types.is_float(from) && bits(to).is_some(),
+            Self::UIToFP | Self::SIToFP => bits(from).is_some() && types.is_float(to),
+            Self::PtrToInt => is_pointer(from) && bits(to).is_some(),
+            Self::IntToPtr => bits(from).is_some() && is_pointer(to),
+            Self::AddrSpaceCast => is_pointer(from) && is_pointer(to),
+            Self::Bitcast => true,
+            _ => false,
+        }
+    }
+
     pub fn is_load(&self) -> bool {
         self == &Self::Load
     }
@@ -302,6 +640,10 @@ impl Opcode {
         self == &Self::Store
     }
 
+    pub fn is_atomic(&self) -> bool {
+        matches!(self, Self::AtomicCmpXchg | Self::AtomicRmw | Self::Fence)
+    }
+
     pub fn is_alloca(&self) -> bool {
         self == &Self::Alloca
     }
@@ -321,6 +663,7 @@ impl Opcode {
     pub fn has_side_effects(&self) -> bool {
         self.is_load()
             || self.is_store()
+            || self.is_atomic()
             || self.is_alloca()
             || self.is_phi()
             || self.is_call()
@@ -357,17 +700,30 @@ impl Operand {
             Self::Ret(Ret { val, .. }) => slice::from_ref(val.as_ref().unwrap()),
             Self::Load(Load { addr, .. }) => slice::from_ref(addr),
             Self::Store(Store { args, .. }) => args,
+            Self::AtomicCmpXchg(AtomicCmpXchg { args, .. }) => args,
+            Self::AtomicRmw(AtomicRmw { args, .. }) => args,
+            Self::Fence(_) => &[],
             Self::InsertValue(InsertValue { args, .. }) => args,
             Self::ExtractValue(ExtractValue { args, .. }) => args,
+            Self::ExtractElement(ExtractElement { args, .. }) => args,
+            Self::InsertElement(InsertElement { args, .. }) => args,
+            Self::ShuffleVector(ShuffleVector { args, .. }) => args,
             Self::IntBinary(IntBinary { args, .. }) => args,
+            Self::FloatBinary(FloatBinary { args, .. }) => args,
+            Self::FloatUnary(FloatUnary { arg, .. }) => slice::from_ref(arg),
             Self::ICmp(ICmp { args, .. }) => args,
+            Self::FCmp(FCmp { args, .. }) => args,
             Self::Cast(Cast { arg, .. }) => slice::from_ref(arg),
             Self::GetElementPtr(GetElementPtr { args, .. }) => args.as_slice(),
+            Self::Select(Select { args, .. }) => args,
+            Self::Freeze(Freeze { arg, .. }) => slice::from_ref(arg),
             Self::Call(Call { args, .. }) | Self::Invoke(Invoke { args, .. }) => args.as_slice(),
             Self::LandingPad(LandingPad { .. }) => &[],
             Self::Resume(Resume { arg, .. }) => slice::from_ref(arg),
             Self::Br(Br { .. }) => &[],
             Self::CondBr(CondBr { arg, .. }) => slice::from_ref(arg),
+            Self::Switch(Switch { args, .. }) => args.as_slice(),
+            Self::Unreachable => &[],
             Self::Invalid => &[],
         }
     }
@@ -380,17 +736,30 @@ impl Operand {
             Self::Ret(Ret { val, .. }) => slice::from_mut(val.as_mut().unwrap()),
             Self::Load(Load { addr, .. }) => slice::from_mut(addr),
             Self::Store(Store { args, .. }) => args,
+            Self::AtomicCmpXchg(AtomicCmpXchg { args, .. }) => args,
+            Self::AtomicRmw(AtomicRmw { args, .. }) => args,
+            Self::Fence(_) => &mut [],
             Self::InsertValue(InsertValue { args, .. }) => args,
             Self::ExtractValue(ExtractValue { args, .. }) => args,
+            Self::ExtractElement(ExtractElement { args, .. }) => args,
+            Self::InsertElement(InsertElement { args, .. }) => args,
+            Self::ShuffleVector(ShuffleVector { args, .. }) => args,
             Self::IntBinary(IntBinary { args, .. }) => args,
+            Self::FloatBinary(FloatBinary { args, .. }) => args,
+            Self::FloatUnary(FloatUnary { arg, .. }) => slice::from_mut(arg),
             Self::ICmp(ICmp { args, .. }) => args,
+            Self::FCmp(FCmp { args, .. }) => args,
             Self::Cast(Cast { arg, .. }) => slice::from_mut(arg),
             Self::GetElementPtr(GetElementPtr { args, .. }) => args.as_mut_slice(),
+            Self::Select(Select { args, .. }) => args,
+            Self::Freeze(Freeze { arg, .. }) => slice::from_mut(arg),
             Self::Call(Call { args, .. }) | Self::Invoke(Invoke { args, .. }) => args.as_mut(),
             Self::LandingPad(LandingPad { .. }) => &mut [],
             Self::Resume(Resume { arg, .. }) => slice::from_mut(arg),
             Self::Br(Br { .. }) => &mut [],
             Self::CondBr(CondBr { arg, .. }) => slice::from_mut(arg),
+            Self::Switch(Switch { args, .. }) => args.as_mut_slice(),
+            Self::Unreachable => &mut [],
             Self::Invalid => &mut [],
         }
     }
@@ -402,17 +771,30 @@ impl Operand {
             Self::Ret(Ret { ty, .. }) => slice::from_ref(ty),
             Self::Load(Load { tys, .. }) => tys,
             Self::Store(Store { .. }) => &[],
+            Self::AtomicCmpXchg(AtomicCmpXchg { tys, .. }) => tys,
+            Self::AtomicRmw(AtomicRmw { tys, .. }) => tys,
+            Self::Fence(_) => &[],
             Self::InsertValue(InsertValue { tys, .. }) => tys,
             Self::ExtractValue(ExtractValue { ty, .. }) => slice::from_ref(ty),
+            Self::ExtractElement(ExtractElement { tys, .. }) => tys,
+            Self::InsertElement(InsertElement { tys, .. }) => tys,
+            Self::ShuffleVector(ShuffleVector { tys, .. }) => tys,
             Self::IntBinary(IntBinary { ty, .. }) => slice::from_ref(ty),
+            Self::FloatBinary(FloatBinary { ty, .. }) => slice::from_ref(ty),
+            Self::FloatUnary(FloatUnary { ty, .. }) => slice::from_ref(ty),
             Self::ICmp(ICmp { ty, .. }) => slice::from_ref(ty),
+            Self::FCmp(FCmp { ty, .. }) => slice::from_ref(ty),
             Self::Cast(Cast { tys, .. }) => tys,
             Self::GetElementPtr(GetElementPtr { tys, .. }) => tys.as_slice(),
+            Self::Select(Select { tys, .. }) => tys,
+            Self::Freeze(Freeze { ty, .. }) => slice::from_ref(ty),
             Self::Call(Call { tys, .. }) | Self::Invoke(Invoke { tys, .. }) => tys.as_slice(),
             Self::LandingPad(LandingPad { ty }) => slice::from_ref(ty),
             Self::Resume(Resume { ty, .. }) => slice::from_ref(ty),
             Self::Br(Br { .. }) => &[],
             Self::CondBr(CondBr { .. }) => &[],
+            Self::Switch(Switch { tys, .. }) => tys.as_slice(),
+            Self::Unreachable => &[],
             Self::Invalid => &[],
         }
     }
@@ -422,11 +804,23 @@ impl Operand {
             Self::Phi(Phi { blocks, .. }) => blocks,
             Self::Br(Br { block }) => slice::from_ref(block),
             Self::CondBr(CondBr { blocks, .. }) => blocks,
+            Self::Switch(Switch { blocks, .. }) => blocks,
             Self::Invoke(Invoke { blocks, .. }) => blocks,
             _ => &[],
         }
     }
 
+    pub fn blocks_mut(&mut self) -> &mut [BasicBlockId] {
+        match self {
+            Self::Phi(Phi { blocks, .. }) => blocks,
+            Self::Br(Br { block }) => slice::from_mut(block),
+            Self::CondBr(CondBr { blocks, .. }) => blocks,
+            Self::Switch(Switch { blocks, .. }) => blocks,
+            Self::Invoke(Invoke { blocks, .. }) => blocks,
+            _ => &mut [],
+        }
+    }
+
     pub fn call_result_ty(&self) -> Option<TypeId> {
         match self {
             Self::Call(Call { tys, .. }) | Self::Invoke(Invoke { tys, .. }) => Some(tys[0]),
@@ -437,9 +831,14 @@ impl Operand {
     as_inst!(as_alloca, Alloca);
     as_inst!(as_store, Store);
     as_inst!(as_load, Load);
+    as_inst!(as_cmpxchg, AtomicCmpXchg);
+    as_inst!(as_atomicrmw, AtomicRmw);
+    as_inst!(as_fence, Fence);
     as_inst!(as_phi, Phi);
     as_inst!(mut as_phi_mut, Phi);
     as_inst!(as_condbr, CondBr);
+    as_inst!(as_switch, Switch);
+    as_inst!(as_select, Select);
 }
 
 impl Alloca {
@@ -462,6 +861,68 @@ impl Load {
     pub fn src_val(&self) -> ValueId {
         self.addr
     }
+
+    pub fn is_atomic(&self) -> bool {
+        self.ordering != AtomicOrdering::NotAtomic
+    }
+}
+
+impl Store {
+    pub fn is_atomic(&self) -> bool {
+        self.ordering != AtomicOrdering::NotAtomic
+    }
+}
+
+impl AtomicCmpXchg {
+    pub fn ptr(&self) -> ValueId {
+        self.args[0]
+    }
+
+    pub fn cmp_val(&self) -> ValueId {
+        self.args[1]
+    }
+
+    pub fn new_val(&self) -> ValueId {
+        self.args[2]
+    }
+}
+
+impl AtomicRmw {
+    pub fn ptr(&self) -> ValueId {
+        self.args[0]
+    }
+
+    pub fn val(&self) -> ValueId {
+        self.args[1]
+    }
+}
+
+impl Select {
+    pub fn cond(&self) -> ValueId {
+        self.args[0]
+    }
+}
+
+impl Switch {
+    pub fn cond(&self) -> ValueId {
+        self.args[0]
+    }
+
+    pub fn cond_ty(&self) -> TypeId {
+        self.tys[0]
+    }
+
+    pub fn default_block(&self) -> BasicBlockId {
+        self.blocks[0]
+    }
+
+    /// Returns an iterator over pairs of a case value and its destination.
+    pub fn cases(&self) -> impl Iterator<Item = (ValueId, BasicBlockId)> + '_ {
+        self.args[1..]
+            .iter()
+            .copied()
+            .zip(self.blocks[1..].iter().copied())
+    }
 }
 
 impl Phi {
@@ -484,28 +945,59 @@ impl fmt::Debug for Opcode {
                 Opcode::Phi => "phi",
                 Opcode::Load => "load",
                 Opcode::Store => "store",
+                Opcode::AtomicCmpXchg => "cmpxchg",
+                Opcode::AtomicRmw => "atomicrmw",
+                Opcode::Fence => "fence",
                 Opcode::InsertValue => "insertvalue",
                 Opcode::ExtractValue => "extractvalue",
+                Opcode::ExtractElement => "extractelement",
+                Opcode::InsertElement => "insertelement",
+                Opcode::ShuffleVector => "shufflevector",
                 Opcode::Add => "add",
                 Opcode::Sub => "sub",
                 Opcode::Mul => "mul",
                 Opcode::SDiv => "sdiv",
+                Opcode::UDiv => "udiv",
                 Opcode::SRem => "srem",
+                Opcode::URem => "urem",
                 Opcode::And => "and",
+                Opcode::Or => "or",
+                Opcode::Xor => "xor",
+                Opcode::Shl => "shl",
                 Opcode::LShr => "lshr",
+                Opcode::AShr => "ashr",
+                Opcode::FNeg => "fneg",
+                Opcode::FAdd => "fadd",
+                Opcode::FSub => "fsub",
+                Opcode::FMul => "fmul",
+                Opcode::FDiv => "fdiv",
+                Opcode::FRem => "frem",
                 Opcode::ICmp => "icmp",
+                Opcode::FCmp => "fcmp",
                 Opcode::Sext => "sext",
                 Opcode::Zext => "zext",
                 Opcode::Bitcast => "bitcast",
                 Opcode::Trunc => "trunc",
                 Opcode::IntToPtr => "inttoptr",
+                Opcode::PtrToInt => "ptrtoint",
+                Opcode::AddrSpaceCast => "addrspacecast",
+                Opcode::FPToUI => "fptoui",
+                Opcode::FPToSI => "fptosi",
+                Opcode::UIToFP => "uitofp",
+                Opcode::SIToFP => "sitofp",
+                Opcode::FPTrunc => "fptrunc",
+                Opcode::FPExt => "fpext",
                 Opcode::GetElementPtr => "getelementptr",
+                Opcode::Select => "select",
+                Opcode::Freeze => "freeze",
                 Opcode::Call => "call",
                 Opcode::Invoke => "invoke",
                 Opcode::LandingPad => "landingpad",
                 Opcode::Resume => "resume",
                 Opcode::Br | Opcode::CondBr => "br",
+                Opcode::Switch => "switch",
                 Opcode::Ret => "ret",
+                Opcode::Unreachable => "unreachable",
                 Opcode::Invalid => "INVALID",
             }
         )
@@ -532,3 +1024,126 @@ impl fmt::Debug for ICmpCond {
         )
     }
 }
+
+impl fmt::Debug for FCmpCond {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::False => "false",
+                Self::Oeq => "oeq",
+                Self::Ogt => "ogt",
+                Self::Oge => "oge",
+                Self::Olt => "olt",
+                Self::Ole => "ole",
+                Self::One => "one",
+                Self::Ord => "ord",
+                Self::Ueq => "ueq",
+                Self::Ugt => "ugt",
+                Self::Uge => "uge",
+                Self::Ult => "ult",
+                Self::Ule => "ule",
+                Self::Une => "une",
+                Self::Uno => "uno",
+                Self::True => "true",
+            }
+        )
+    }
+}
+
+impl fmt::Debug for AtomicOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::NotAtomic => "notatomic",
+                Self::Unordered => "unordered",
+                Self::Monotonic => "monotonic",
+                Self::Acquire => "acquire",
+                Self::Release => "release",
+                Self::AcqRel => "acq_rel",
+                Self::SeqCst => "seq_cst",
+            }
+        )
+    }
+}
+
+impl fmt::Debug for AtomicRmwBinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Xchg => "xchg",
+                Self::Add => "add",
+                Self::Sub => "sub",
+                Self::And => "and",
+                Self::Nand => "nand",
+                Self::Or => "or",
+                Self::Xor => "xor",
+                Self::Max => "max",
+                Self::Min => "min",
+                Self::UMax => "umax",
+                Self::UMin => "umin",
+                Self::FAdd => "fadd",
+                Self::FSub => "fsub",
+                Self::FMax => "fmax",
+                Self::FMin => "fmin",
+            }
+        )
+    }
+}
+
+impl fmt::Display for SyncScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Preceded by a space unless it's the default (system) scope
+        match self {
+            Self::System => Ok(()),
+            Self::SingleThread => write!(f, " syncscope(\"singlethread\")"),
+            Self::Target(name) => write!(f, " syncscope(\"{}\")", escape(name)),
+        }
+    }
+}
+
+impl FastMathFlags {
+    pub fn fast() -> Self {
+        Self {
+            nnan: true,
+            ninf: true,
+            nsz: true,
+            arcp: true,
+            contract: true,
+            afn: true,
+            reassoc: true,
+        }
+    }
+
+    pub fn is_fast(&self) -> bool {
+        self == &Self::fast()
+    }
+}
+
+impl fmt::Display for FastMathFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Each flag is preceded by a space
+        if self.is_fast() {
+            return write!(f, " fast");
+        }
+        for (flag, name) in [
+            (self.reassoc, "reassoc"),
+            (self.nnan, "nnan"),
+            (self.ninf, "ninf"),
+            (self.nsz, "nsz"),
+            (self.arcp, "arcp"),
+            (self.contract, "contract"),
+            (self.afn, "afn"),
+        ] {
+            if flag {
+                write!(f, " {}", name)?
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
 use super::{
-    Alloca, Br, Call, Cast, CondBr, GetElementPtr, ICmp, ICmpCond, Instruction, InstructionId,
-    IntBinary, Invoke, LandingPad, Load, Opcode, Operand, Phi, Resume, Ret, Store,
+    Alloca, AtomicCmpXchg, AtomicOrdering, AtomicRmw, AtomicRmwBinOp, Br, Call, Cast, CondBr, FCmp,
+    FCmpCond, FastMathFlags, Fence, FloatBinary, FloatUnary, Freeze, GetElementPtr, ICmp, ICmpCond,
+    Instruction, InstructionId, IntBinary, Invoke, LandingPad, Load, Opcode, Operand, Phi, Resume,
+    Ret, Select, Store, Switch, SyncScope,
 };
 use crate::ir::{
     function::{
-        instruction::{ExtractValue, InsertValue},
+        instruction::{ExtractElement, ExtractValue, InsertElement, InsertValue, ShuffleVector},
         param_attrs::{parser::parse_param_attrs, ParameterAttribute},
         parser::ParserContext,
     },
@@ -12,7 +14,7 @@ use crate::ir::{
         attributes::parser::parse_attributes, metadata, metadata::Metadata,
         name::parser::identifier,
     },
-    util::string_literal,
+    util::{error::furthest, string_literal},
 };
 use crate::ir::{module::name, types, util::spaces, value};
 use nom::{
@@ -20,8 +22,8 @@ use nom::{
     bytes::complete::tag,
     character::complete::{char, digit1},
     combinator::{map, opt},
-    error::VerboseError,
-    sequence::{preceded, tuple},
+    error::{ContextError, ErrorKind, ParseError as _, VerboseError},
+    sequence::{delimited, preceded, tuple},
     Err::Error,
     IResult,
 };
@@ -33,6 +35,12 @@ pub fn parse_alloca<'a, 'b>(
 ) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
     let (source, _) = preceded(spaces, tag("alloca"))(source)?;
     let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, num_elements) = opt(|source| {
+        let (source, _) = preceded(spaces, char(','))(source)?;
+        let (source, num_ty) = types::parse(source, ctx.types)?;
+        let (source, num) = value::parser::parse_constant(source, ctx.types, num_ty)?;
+        Ok((source, (num_ty, num)))
+    })(source)?;
     let (source, align) = opt(preceded(
         spaces,
         preceded(
@@ -40,12 +48,16 @@ pub fn parse_alloca<'a, 'b>(
             preceded(spaces, preceded(tag("align"), preceded(spaces, digit1))),
         ),
     ))(source)?;
-    // TODO: Implement parser for num_elements
-    let num_elements = value::ConstantData::Int(value::ConstantInt::Int32(1));
+    let (num_ty, num_elements) = num_elements.unwrap_or_else(|| {
+        (
+            ctx.types.base().i32(),
+            value::ConstantData::Int(value::ConstantInt::from(1i32)),
+        )
+    });
     let inst = Opcode::Alloca
         .with_block(ctx.cur_block)
         .with_operand(Operand::Alloca(Alloca {
-            tys: [ty, ctx.types.base().i32()],
+            tys: [ty, num_ty],
             num_elements,
             align: align.map_or(0, |align| align.parse::<u32>().unwrap_or(0)),
         }));
@@ -85,17 +97,18 @@ pub fn parse_load<'a, 'b>(
     ctx: &mut ParserContext<'b>,
 ) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
     let (source, _) = preceded(spaces, tag("load"))(source)?;
+    let (source, atomic) = opt(preceded(spaces, tag("atomic")))(source)?;
+    let (source, volatile) = opt(preceded(spaces, tag("volatile")))(source)?;
     let (source, ty) = types::parse(source, ctx.types)?;
     let (source, _) = preceded(spaces, char(','))(source)?;
     let (source, addr_ty) = types::parse(source, ctx.types)?;
     let (source, addr) = value::parse(source, ctx, addr_ty)?;
-    let (source, align) = opt(preceded(
-        spaces,
-        preceded(
-            char(','),
-            preceded(spaces, preceded(tag("align"), preceded(spaces, digit1))),
-        ),
-    ))(source)?;
+    let (source, (syncscope, ordering)) = if atomic.is_some() {
+        tuple((parse_syncscope, parse_ordering))(source)?
+    } else {
+        (source, (SyncScope::System, AtomicOrdering::NotAtomic))
+    };
+    let (source, align) = parse_align(source)?;
     let (source, _) = opt(parse_metadata("!nonnull"))(source)?; // TODO: FIXME: don't ignore !nonnull
     let (source, _) = opt(parse_metadata("!range"))(source)?; // TODO: FIXME: don't ignore !range
     let inst = Opcode::Load
@@ -103,7 +116,10 @@ pub fn parse_load<'a, 'b>(
         .with_operand(Operand::Load(Load {
             tys: [ty, addr_ty],
             addr,
-            align: align.map_or(0, |align| align.parse::<u32>().unwrap_or(0)),
+            align,
+            volatile: volatile.is_some(),
+            ordering,
+            syncscope,
         }));
     Ok((source, inst))
 }
@@ -113,11 +129,175 @@ pub fn parse_store<'a, 'b>(
     ctx: &mut ParserContext<'b>,
 ) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
     let (source, _) = preceded(spaces, preceded(tag("store"), spaces))(source)?;
+    let (source, atomic) = opt(preceded(spaces, tag("atomic")))(source)?;
+    let (source, volatile) = opt(preceded(spaces, tag("volatile")))(source)?;
     let (source, src_ty) = types::parse(source, ctx.types)?;
     let (source, src) = value::parse(source, ctx, src_ty)?;
     let (source, _) = preceded(spaces, char(','))(source)?;
     let (source, dst_ty) = types::parse(source, ctx.types)?;
     let (source, dst) = value::parse(source, ctx, dst_ty)?;
+    let (source, (syncscope, ordering)) = if atomic.is_some() {
+        tuple((parse_syncscope, parse_ordering))(source)?
+    } else {
+        (source, (SyncScope::System, AtomicOrdering::NotAtomic))
+    };
+    let (source, align) = parse_align(source)?;
+    Ok((
+        source,
+        Opcode::Store
+            .with_block(ctx.cur_block)
+            .with_operand(Operand::Store(Store {
+                tys: [src_ty, dst_ty],
+                args: [src, dst],
+                align,
+                volatile: volatile.is_some(),
+                ordering,
+                syncscope,
+            })),
+    ))
+}
+
+pub fn parse_cmpxchg<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("cmpxchg"))(source)?;
+    let (source, weak) = opt(preceded(spaces, tag("weak")))(source)?;
+    let (source, volatile) = opt(preceded(spaces, tag("volatile")))(source)?;
+    let (source, ptr_ty) = types::parse(source, ctx.types)?;
+    let (source, ptr) = value::parse(source, ctx, ptr_ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, cmp) = value::parse(source, ctx, ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, _) = types::parse(source, ctx.types)?;
+    let (source, new) = value::parse(source, ctx, ty)?;
+    let (source, syncscope) = parse_syncscope(source)?;
+    let (source, success_ordering) = parse_ordering(source)?;
+    let (source, failure_ordering) = parse_ordering(source)?;
+    let (source, align) = parse_align(source)?;
+    let i1 = ctx.types.base().i1();
+    let result_ty = ctx.types.base_mut().anonymous_struct(vec![ty, i1], false);
+    let inst = Opcode::AtomicCmpXchg
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::AtomicCmpXchg(AtomicCmpXchg {
+            tys: [result_ty, ptr_ty, ty],
+            args: [ptr, cmp, new],
+            align,
+            weak: weak.is_some(),
+            volatile: volatile.is_some(),
+            success_ordering,
+            failure_ordering,
+            syncscope,
+        }));
+    Ok((source, inst))
+}
+
+pub fn parse_atomicrmw<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    pub fn atomicrmw_op(source: &str) -> IResult<&str, AtomicRmwBinOp, VerboseError<&str>> {
+        alt((
+            map(tag("xchg"), |_| AtomicRmwBinOp::Xchg),
+            map(tag("add"), |_| AtomicRmwBinOp::Add),
+            map(tag("sub"), |_| AtomicRmwBinOp::Sub),
+            map(tag("and"), |_| AtomicRmwBinOp::And),
+            map(tag("nand"), |_| AtomicRmwBinOp::Nand),
+            map(tag("or"), |_| AtomicRmwBinOp::Or),
+            map(tag("xor"), |_| AtomicRmwBinOp::Xor),
+            map(tag("max"), |_| AtomicRmwBinOp::Max),
+            map(tag("min"), |_| AtomicRmwBinOp::Min),
+            map(tag("umax"), |_| AtomicRmwBinOp::UMax),
+            map(tag("umin"), |_| AtomicRmwBinOp::UMin),
+            map(tag("fadd"), |_| AtomicRmwBinOp::FAdd),
+            map(tag("fsub"), |_| AtomicRmwBinOp::FSub),
+            map(tag("fmax"), |_| AtomicRmwBinOp::FMax),
+            map(tag("fmin"), |_| AtomicRmwBinOp::FMin),
+        ))(source)
+    }
+
+    let (source, _) = preceded(spaces, tag("atomicrmw"))(source)?;
+    let (source, volatile) = opt(preceded(spaces, tag("volatile")))(source)?;
+    let (source, op) = preceded(spaces, atomicrmw_op)(source)?;
+    let (source, ptr_ty) = types::parse(source, ctx.types)?;
+    let (source, ptr) = value::parse(source, ctx, ptr_ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, val) = value::parse(source, ctx, ty)?;
+    let (source, syncscope) = parse_syncscope(source)?;
+    let (source, ordering) = parse_ordering(source)?;
+    let (source, align) = parse_align(source)?;
+    let inst = Opcode::AtomicRmw
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::AtomicRmw(AtomicRmw {
+            op,
+            tys: [ptr_ty, ty],
+            args: [ptr, val],
+            align,
+            volatile: volatile.is_some(),
+            ordering,
+            syncscope,
+        }));
+    Ok((source, inst))
+}
+
+pub fn parse_fence<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("fence"))(source)?;
+    let (source, syncscope) = parse_syncscope(source)?;
+    let (source, ordering) = parse_ordering(source)?;
+    let inst = Opcode::Fence
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::Fence(Fence {
+            ordering,
+            syncscope,
+        }));
+    Ok((source, inst))
+}
+
+pub fn parse_ordering(source: &str) -> IResult<&str, AtomicOrdering, VerboseError<&str>> {
+    preceded(
+        spaces,
+        alt((
+            map(tag("unordered"), |_| AtomicOrdering::Unordered),
+            map(tag("monotonic"), |_| AtomicOrdering::Monotonic),
+            map(tag("acquire"), |_| AtomicOrdering::Acquire),
+            map(tag("release"), |_| AtomicOrdering::Release),
+            map(tag("acq_rel"), |_| AtomicOrdering::AcqRel),
+            map(tag("seq_cst"), |_| AtomicOrdering::SeqCst),
+        )),
+    )(source)
+}
+
+pub fn parse_syncscope(source: &str) -> IResult<&str, SyncScope, VerboseError<&str>> {
+    let (source, scope) = opt(preceded(
+        spaces,
+        preceded(
+            tag("syncscope"),
+            preceded(
+                spaces,
+                delimited(
+                    char('('),
+                    preceded(spaces, string_literal),
+                    preceded(spaces, char(')')),
+                ),
+            ),
+        ),
+    ))(source)?;
+    Ok((
+        source,
+        match scope {
+            None => SyncScope::System,
+            Some(scope) if scope == "singlethread" => SyncScope::SingleThread,
+            Some(scope) => SyncScope::Target(scope),
+        },
+    ))
+}
+
+fn parse_align(source: &str) -> IResult<&str, u32, VerboseError<&str>> {
     let (source, align) = opt(preceded(
         spaces,
         preceded(
@@ -127,13 +307,7 @@ pub fn parse_store<'a, 'b>(
     ))(source)?;
     Ok((
         source,
-        Opcode::Store
-            .with_block(ctx.cur_block)
-            .with_operand(Operand::Store(Store {
-                tys: [src_ty, dst_ty],
-                args: [src, dst],
-                align: align.map_or(0, |align| align.parse::<u32>().unwrap_or(0)),
-            })),
+        align.map_or(0, |align| align.parse::<u32>().unwrap_or(0)),
     ))
 }
 
@@ -197,63 +371,231 @@ pub fn parse_extractvalue<'a, 'b>(
     }
 }
 
+pub fn parse_extractelement<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("extractelement"))(source)?;
+    let (source, vec_ty) = types::parse(source, ctx.types)?;
+    let (source, vec) = value::parse(source, ctx, vec_ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, idx_ty) = types::parse(source, ctx.types)?;
+    let (source, idx) = value::parse(source, ctx, idx_ty)?;
+    Ok((
+        source,
+        Opcode::ExtractElement
+            .with_block(ctx.cur_block)
+            .with_operand(Operand::ExtractElement(ExtractElement {
+                tys: [vec_ty, idx_ty],
+                args: [vec, idx],
+            })),
+    ))
+}
+
+pub fn parse_insertelement<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("insertelement"))(source)?;
+    let (source, vec_ty) = types::parse(source, ctx.types)?;
+    let (source, vec) = value::parse(source, ctx, vec_ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, elem_ty) = types::parse(source, ctx.types)?;
+    let (source, elem) = value::parse(source, ctx, elem_ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, idx_ty) = types::parse(source, ctx.types)?;
+    let (source, idx) = value::parse(source, ctx, idx_ty)?;
+    Ok((
+        source,
+        Opcode::InsertElement
+            .with_block(ctx.cur_block)
+            .with_operand(Operand::InsertElement(InsertElement {
+                tys: [vec_ty, elem_ty, idx_ty],
+                args: [vec, elem, idx],
+            })),
+    ))
+}
+
+pub fn parse_shufflevector<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("shufflevector"))(source)?;
+    let (source, vec_ty) = types::parse(source, ctx.types)?;
+    let (source, v1) = value::parse(source, ctx, vec_ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, _) = types::parse(source, ctx.types)?;
+    let (source, v2) = value::parse(source, ctx, vec_ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, mask_ty) = types::parse(source, ctx.types)?;
+    let (source, mask) = value::parse(source, ctx, mask_ty)?;
+    Ok((
+        source,
+        Opcode::ShuffleVector
+            .with_block(ctx.cur_block)
+            .with_operand(Operand::ShuffleVector(ShuffleVector {
+                tys: [vec_ty, mask_ty],
+                args: [v1, v2, mask],
+            })),
+    ))
+}
+
 pub fn parse_add_sub_mul<'a, 'b>(
     source: &'a str,
     ctx: &mut ParserContext<'b>,
 ) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
-    let (source, opcode) = preceded(
+    let (source, opcode) = preceded(spaces, parse_int_binary_opcode)(source)?;
+    let (source, (nuw, nsw, exact)) = parse_int_binary_flags(source, opcode)?;
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, lhs) = value::parse(source, ctx, ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, rhs) = value::parse(source, ctx, ty)?;
+    let inst = opcode
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::IntBinary(IntBinary {
+            ty,
+            args: [lhs, rhs],
+            nuw,
+            nsw,
+            exact,
+        }));
+    Ok((source, inst))
+}
+
+pub fn parse_int_binary_opcode(source: &str) -> IResult<&str, Opcode, VerboseError<&str>> {
+    alt((
+        map(tag("add"), |_| Opcode::Add),
+        map(tag("sub"), |_| Opcode::Sub),
+        map(tag("mul"), |_| Opcode::Mul),
+        map(tag("sdiv"), |_| Opcode::SDiv),
+        map(tag("udiv"), |_| Opcode::UDiv),
+        map(tag("srem"), |_| Opcode::SRem),
+        map(tag("urem"), |_| Opcode::URem),
+        map(tag("and"), |_| Opcode::And),
+        map(tag("or"), |_| Opcode::Or),
+        map(tag("xor"), |_| Opcode::Xor),
+        map(tag("shl"), |_| Opcode::Shl),
+        map(tag("lshr"), |_| Opcode::LShr),
+        map(tag("ashr"), |_| Opcode::AShr),
+    ))(source)
+}
+
+/// Parses `nuw`, `nsw` and `exact` that `opcode` accepts, in any order.
+pub fn parse_int_binary_flags(
+    source: &str,
+    opcode: Opcode,
+) -> IResult<&str, (bool, bool, bool), VerboseError<&str>> {
+    let (mut source, mut nuw, mut nsw, mut exact) = (source, false, false, false);
+    loop {
+        if opcode.has_wrap_flags() {
+            if let Ok((source_, _)) = preceded(spaces, tag("nuw"))(source) {
+                nuw = true;
+                source = source_;
+                continue;
+            }
+            if let Ok((source_, _)) = preceded(spaces, tag("nsw"))(source) {
+                nsw = true;
+                source = source_;
+                continue;
+            }
+        }
+        if opcode.has_exact_flag() {
+            if let Ok((source_, _)) = preceded(spaces, tag("exact"))(source) {
+                exact = true;
+                source = source_;
+                continue;
+            }
+        }
+        return Ok((source, (nuw, nsw, exact)));
+    }
+}
+
+pub fn parse_fast_math_flags(source: &str) -> IResult<&str, FastMathFlags, VerboseError<&str>> {
+    let mut flags = FastMathFlags::default();
+    let mut source = source;
+    while let Ok((source_, flag)) = preceded(
         spaces,
         alt((
-            map(tag("add"), |_| Opcode::Add),
-            map(tag("sub"), |_| Opcode::Sub),
-            map(tag("mul"), |_| Opcode::Mul),
-            map(tag("sdiv"), |_| Opcode::SDiv),
-            map(tag("srem"), |_| Opcode::SRem),
-            map(tag("and"), |_| Opcode::And),
-            map(tag("lshr"), |_| Opcode::LShr),
+            tag("nnan"),
+            tag("ninf"),
+            tag("nsz"),
+            tag("arcp"),
+            tag("contract"),
+            tag("afn"),
+            tag("reassoc"),
+            tag("fast"),
         )),
-    )(source)?;
-    // TODO: `and` doesn't need nuw/nsw/exact keywords. We had better show error when they appear.
-    let (source, nuw) = opt(preceded(spaces, tag("nuw")))(source)?;
-    let (source, nsw) = opt(preceded(spaces, tag("nsw")))(source)?;
-    let (source, exact) = opt(preceded(spaces, tag("exact")))(source)?;
+    )(source)
+    {
+        match flag {
+            "nnan" => flags.nnan = true,
+            "ninf" => flags.ninf = true,
+            "nsz" => flags.nsz = true,
+            "arcp" => flags.arcp = true,
+            "contract" => flags.contract = true,
+            "afn" => flags.afn = true,
+            "reassoc" => flags.reassoc = true,
+            _ => flags = FastMathFlags::fast(),
+        }
+        source = source_;
+    }
+    Ok((source, flags))
+}
+
+pub fn parse_float_binary<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, opcode) = preceded(spaces, parse_float_binary_opcode)(source)?;
+    let (source, fast_math_flags) = parse_fast_math_flags(source)?;
     let (source, ty) = types::parse(source, ctx.types)?;
     let (source, lhs) = value::parse(source, ctx, ty)?;
     let (source, _) = preceded(spaces, char(','))(source)?;
     let (source, rhs) = value::parse(source, ctx, ty)?;
     let inst = opcode
         .with_block(ctx.cur_block)
-        .with_operand(Operand::IntBinary(IntBinary {
+        .with_operand(Operand::FloatBinary(FloatBinary {
             ty,
+            fast_math_flags,
             args: [lhs, rhs],
-            nuw: nuw.map_or(false, |_| true),
-            nsw: nsw.map_or(false, |_| true),
-            exact: exact.map_or(false, |_| true),
         }));
     Ok((source, inst))
 }
 
-pub fn parse_icmp<'a, 'b>(
+pub fn parse_float_binary_opcode(source: &str) -> IResult<&str, Opcode, VerboseError<&str>> {
+    alt((
+        map(tag("fadd"), |_| Opcode::FAdd),
+        map(tag("fsub"), |_| Opcode::FSub),
+        map(tag("fmul"), |_| Opcode::FMul),
+        map(tag("fdiv"), |_| Opcode::FDiv),
+        map(tag("frem"), |_| Opcode::FRem),
+    ))(source)
+}
+
+pub fn parse_fneg<'a, 'b>(
     source: &'a str,
     ctx: &mut ParserContext<'b>,
 ) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
-    pub fn icmp_cond(source: &str) -> IResult<&str, ICmpCond, VerboseError<&str>> {
-        alt((
-            map(tag("eq"), |_| ICmpCond::Eq),
-            map(tag("ne"), |_| ICmpCond::Ne),
-            map(tag("ugt"), |_| ICmpCond::Ugt),
-            map(tag("uge"), |_| ICmpCond::Uge),
-            map(tag("ult"), |_| ICmpCond::Ult),
-            map(tag("ule"), |_| ICmpCond::Ule),
-            map(tag("sgt"), |_| ICmpCond::Sgt),
-            map(tag("sge"), |_| ICmpCond::Sge),
-            map(tag("slt"), |_| ICmpCond::Slt),
-            map(tag("sle"), |_| ICmpCond::Sle),
-        ))(source)
-    }
+    let (source, _) = preceded(spaces, tag("fneg"))(source)?;
+    let (source, fast_math_flags) = parse_fast_math_flags(source)?;
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, arg) = value::parse(source, ctx, ty)?;
+    let inst = Opcode::FNeg
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::FloatUnary(FloatUnary {
+            ty,
+            fast_math_flags,
+            arg,
+        }));
+    Ok((source, inst))
+}
 
+pub fn parse_icmp<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
     let (source, _) = preceded(spaces, tag("icmp"))(source)?;
-    let (source, cond) = preceded(spaces, icmp_cond)(source)?;
+    let (source, cond) = preceded(spaces, parse_icmp_cond)(source)?;
     let (source, ty) = types::parse(source, ctx.types)?;
     let (source, lhs) = value::parse(source, ctx, ty)?;
     let (source, _) = preceded(spaces, char(','))(source)?;
@@ -268,20 +610,69 @@ pub fn parse_icmp<'a, 'b>(
     Ok((source, inst))
 }
 
+pub fn parse_icmp_cond(source: &str) -> IResult<&str, ICmpCond, VerboseError<&str>> {
+    alt((
+        map(tag("eq"), |_| ICmpCond::Eq),
+        map(tag("ne"), |_| ICmpCond::Ne),
+        map(tag("ugt"), |_| ICmpCond::Ugt),
+        map(tag("uge"), |_| ICmpCond::Uge),
+        map(tag("ult"), |_| ICmpCond::Ult),
+        map(tag("ule"), |_| ICmpCond::Ule),
+        map(tag("sgt"), |_| ICmpCond::Sgt),
+        map(tag("sge"), |_| ICmpCond::Sge),
+        map(tag("slt"), |_| ICmpCond::Slt),
+        map(tag("sle"), |_| ICmpCond::Sle),
+    ))(source)
+}
+
+pub fn parse_fcmp<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("fcmp"))(source)?;
+    let (source, fast_math_flags) = parse_fast_math_flags(source)?;
+    let (source, cond) = preceded(spaces, parse_fcmp_cond)(source)?;
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, lhs) = value::parse(source, ctx, ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, rhs) = value::parse(source, ctx, ty)?;
+    let inst = Opcode::FCmp
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::FCmp(FCmp {
+            ty,
+            fast_math_flags,
+            args: [lhs, rhs],
+            cond,
+        }));
+    Ok((source, inst))
+}
+
+pub fn parse_fcmp_cond(source: &str) -> IResult<&str, FCmpCond, VerboseError<&str>> {
+    alt((
+        map(tag("false"), |_| FCmpCond::False),
+        map(tag("oeq"), |_| FCmpCond::Oeq),
+        map(tag("ogt"), |_| FCmpCond::Ogt),
+        map(tag("oge"), |_| FCmpCond::Oge),
+        map(tag("olt"), |_| FCmpCond::Olt),
+        map(tag("ole"), |_| FCmpCond::Ole),
+        map(tag("one"), |_| FCmpCond::One),
+        map(tag("ord"), |_| FCmpCond::Ord),
+        map(tag("ueq"), |_| FCmpCond::Ueq),
+        map(tag("ugt"), |_| FCmpCond::Ugt),
+        map(tag("uge"), |_| FCmpCond::Uge),
+        map(tag("ult"), |_| FCmpCond::Ult),
+        map(tag("ule"), |_| FCmpCond::Ule),
+        map(tag("une"), |_| FCmpCond::Une),
+        map(tag("uno"), |_| FCmpCond::Uno),
+        map(tag("true"), |_| FCmpCond::True),
+    ))(source)
+}
+
 pub fn parse_cast<'a, 'b>(
     source: &'a str,
     ctx: &mut ParserContext<'b>,
 ) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
-    let (source, opcode) = preceded(
-        spaces,
-        alt((
-            map(tag("sext"), |_| Opcode::Sext),
-            map(tag("zext"), |_| Opcode::Zext),
-            map(tag("bitcast"), |_| Opcode::Bitcast),
-            map(tag("trunc"), |_| Opcode::Trunc),
-            map(tag("inttoptr"), |_| Opcode::IntToPtr),
-        )),
-    )(source)?;
+    let (source, opcode) = preceded(spaces, parse_cast_opcode)(source)?;
     let (source, from) = types::parse(source, ctx.types)?;
     let (source, arg) = value::parse(source, ctx, from)?;
     let (source, _) = preceded(spaces, tag("to"))(source)?;
@@ -295,6 +686,24 @@ pub fn parse_cast<'a, 'b>(
     Ok((source, inst))
 }
 
+pub fn parse_cast_opcode(source: &str) -> IResult<&str, Opcode, VerboseError<&str>> {
+    alt((
+        map(tag("sext"), |_| Opcode::Sext),
+        map(tag("zext"), |_| Opcode::Zext),
+        map(tag("bitcast"), |_| Opcode::Bitcast),
+        map(tag("trunc"), |_| Opcode::Trunc),
+        map(tag("inttoptr"), |_| Opcode::IntToPtr),
+        map(tag("ptrtoint"), |_| Opcode::PtrToInt),
+        map(tag("addrspacecast"), |_| Opcode::AddrSpaceCast),
+        map(tag("fptoui"), |_| Opcode::FPToUI),
+        map(tag("fptosi"), |_| Opcode::FPToSI),
+        map(tag("uitofp"), |_| Opcode::UIToFP),
+        map(tag("sitofp"), |_| Opcode::SIToFP),
+        map(tag("fptrunc"), |_| Opcode::FPTrunc),
+        map(tag("fpext"), |_| Opcode::FPExt),
+    ))(source)
+}
+
 pub fn parse_call_args<'a, 'b>(
     source: &'a str,
     ctx: &mut ParserContext<'b>,
@@ -319,9 +728,8 @@ pub fn parse_call_args<'a, 'b>(
             source = source_;
             continue;
         }
-        if let Ok((source, _)) = preceded(spaces, char(')'))(source_) {
-            return Ok((source, args));
-        }
+        let (source, _) = preceded(spaces, char(')'))(source_)?;
+        return Ok((source, args));
     }
 }
 
@@ -536,6 +944,97 @@ pub fn parse_br<'a, 'b>(
     }
 }
 
+pub fn parse_switch<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("switch"))(source)?;
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, cond) = value::parse(source, ctx, ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, default) = preceded(
+        spaces,
+        preceded(
+            tag("label"),
+            preceded(spaces, preceded(char('%'), name::parse)),
+        ),
+    )(source)?;
+    let (mut source, _) = preceded(spaces, char('['))(source)?;
+    let mut tys = vec![ty];
+    let mut args = vec![cond];
+    let mut blocks = vec![ctx.get_or_create_named_block(default)];
+    loop {
+        if let Ok((source_, _)) = preceded(spaces, char(']'))(source) {
+            source = source_;
+            break;
+        }
+        let (source_, ty) = types::parse(source, ctx.types)?;
+        let (source_, val) = value::parse(source_, ctx, ty)?;
+        let (source_, _) = preceded(spaces, char(','))(source_)?;
+        let (source_, label) = preceded(
+            spaces,
+            preceded(
+                tag("label"),
+                preceded(spaces, preceded(char('%'), name::parse)),
+            ),
+        )(source_)?;
+        tys.push(ty);
+        args.push(val);
+        blocks.push(ctx.get_or_create_named_block(label));
+        source = source_
+    }
+    let inst = Opcode::Switch
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::Switch(Switch { tys, args, blocks }));
+    Ok((source, inst))
+}
+
+pub fn parse_select<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("select"))(source)?;
+    let (source, cond_ty) = types::parse(source, ctx.types)?;
+    let (source, cond) = value::parse(source, ctx, cond_ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, lhs) = value::parse(source, ctx, ty)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, _) = types::parse(source, ctx.types)?;
+    let (source, rhs) = value::parse(source, ctx, ty)?;
+    let inst = Opcode::Select
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::Select(Select {
+            tys: [cond_ty, ty],
+            args: [cond, lhs, rhs],
+        }));
+    Ok((source, inst))
+}
+
+pub fn parse_freeze<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("freeze"))(source)?;
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, arg) = value::parse(source, ctx, ty)?;
+    let inst = Opcode::Freeze
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::Freeze(Freeze { ty, arg }));
+    Ok((source, inst))
+}
+
+pub fn parse_unreachable<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, Instruction, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("unreachable"))(source)?;
+    let inst = Opcode::Unreachable
+        .with_block(ctx.cur_block)
+        .with_operand(Operand::Unreachable);
+    Ok((source, inst))
+}
+
 pub fn parse_ret<'a, 'b>(
     source: &'a str,
     ctx: &mut ParserContext<'b>,
@@ -598,46 +1097,65 @@ pub fn parse<'a, 'b>(
 ) -> IResult<&'a str, InstructionId, VerboseError<&'a str>> {
     let (source, name) = opt(tuple((spaces, char('%'), name::parse, spaces, char('='))))(source)?;
     let name = name.map(|(_, _, name, _, _)| name);
+    let mut error = Error(VerboseError::from_error_kind(source, ErrorKind::Alt));
     for f in [
         parse_alloca,
         parse_phi,
         parse_load,
         parse_store,
+        parse_cmpxchg,
+        parse_atomicrmw,
+        parse_fence,
         parse_insertvalue,
         parse_extractvalue,
+        parse_extractelement,
+        parse_insertelement,
+        parse_shufflevector,
         parse_add_sub_mul,
+        parse_float_binary,
+        parse_fneg,
         parse_icmp,
+        parse_fcmp,
         parse_cast,
+        parse_select,
+        parse_freeze,
         parse_getelementptr,
         parse_call,
         parse_invoke,
         parse_landingpad,
         parse_resume,
         parse_br,
+        parse_switch,
         parse_ret,
+        parse_unreachable,
     ]
     .iter()
     {
-        if let Ok((source, mut inst)) = f(source, ctx) {
-            let (source, metadata) = parse_metadata_if_any(ctx.types)(source)?;
-            inst = inst.with_metadata(metadata);
-
-            if let Some(name) = name {
-                if let Some(inner) = ctx.name_to_value.get(&name) {
-                    if let value::Value::Instruction(id) = ctx.data.values[*inner] {
-                        ctx.data.replace_inst(id, inst.with_dest(name));
-                        return Ok((source, id));
-                    }
-                }
+        let (source, inst) = match f(source, ctx) {
+            Ok(ok) => ok,
+            Err(e) => {
+                error = furthest(error, e);
+                continue;
+            }
+        };
+        let (source, metadata) = parse_metadata_if_any(ctx.types)(source)?;
+        let inst = inst.with_metadata(metadata);
 
-                let id = ctx.data.create_inst(inst.with_dest(name.clone()));
-                ctx.name_to_value
-                    .insert(name, ctx.data.create_value(value::Value::Instruction(id)));
-                return Ok((source, id));
+        if let Some(name) = name {
+            if let Some(inner) = ctx.name_to_value.get(&name) {
+                if let value::Value::Instruction(id) = ctx.data.values[*inner] {
+                    ctx.data.replace_inst(id, inst.with_dest(name));
+                    return Ok((source, id));
+                }
             }
 
-            return Ok((source, ctx.data.create_inst(inst)));
+            let id = ctx.data.create_inst(inst.with_dest(name.clone()));
+            ctx.name_to_value
+                .insert(name, ctx.data.create_value(value::Value::Instruction(id)));
+            return Ok((source, id));
         }
+
+        return Ok((source, ctx.data.create_inst(inst)));
     }
-    Err(Error(VerboseError { errors: vec![] }))
+    Err(error.map(|e| VerboseError::add_context(source, "instruction", e)))
 }
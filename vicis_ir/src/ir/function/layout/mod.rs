@@ -75,6 +75,11 @@ impl Layout {
         }
     }
 
+    /// Returns the block `inst` is in, or `None` if it's not in the layout.
+    pub fn block_of(&self, inst: InstructionId) -> Option<BasicBlockId> {
+        self.instructions.get(&inst)?.block
+    }
+
     pub fn next_block_of(&self, block: BasicBlockId) -> Option<BasicBlockId> {
         self.basic_blocks[&block].next
     }
@@ -99,6 +104,43 @@ impl Layout {
         }
     }
 
+    /// Inserts `block` right after `after`, which must be in the layout.
+    pub fn insert_block_after(&mut self, block: BasicBlockId, after: BasicBlockId) {
+        let next = self.basic_blocks[&after].next;
+        self.basic_blocks.insert(
+            block,
+            BasicBlockNode {
+                prev: Some(after),
+                next,
+                first_inst: None,
+                last_inst: None,
+            },
+        );
+        self.basic_blocks.get_mut(&after).unwrap().next = Some(block);
+        match next {
+            Some(next) => self.basic_blocks.get_mut(&next).unwrap().prev = Some(block),
+            None => self.last_block = Some(block),
+        }
+    }
+
+    /// Removes `block` and the instructions left in it from the layout.
+    pub fn remove_block(&mut self, block: BasicBlockId) -> Option<()> {
+        let insts: Vec<InstructionId> = self.inst_iter(block).collect();
+        for inst in insts {
+            self.instructions.remove(&inst);
+        }
+        let node = self.basic_blocks.remove(&block)?;
+        match node.prev {
+            Some(prev) => self.basic_blocks.get_mut(&prev)?.next = node.next,
+            None => self.first_block = node.next,
+        }
+        match node.next {
+            Some(next) => self.basic_blocks.get_mut(&next)?.prev = node.prev,
+            None => self.last_block = node.prev,
+        }
+        Some(())
+    }
+
     pub fn append_inst(&mut self, inst: InstructionId, block: BasicBlockId) {
         self.instructions
             .entry(inst)
@@ -143,6 +185,44 @@ impl Layout {
         }
     }
 
+    /// Inserts `inst` right before `before`, which must be in the layout.
+    pub fn insert_inst_before(&mut self, inst: InstructionId, before: InstructionId) {
+        let block = self.instructions[&before].block.unwrap();
+        let prev = self.instructions[&before].prev;
+        self.instructions.insert(
+            inst,
+            InstructionNode {
+                block: Some(block),
+                prev,
+                next: Some(before),
+            },
+        );
+        self.instructions.get_mut(&before).unwrap().prev = Some(inst);
+        match prev {
+            Some(prev) => self.instructions.get_mut(&prev).unwrap().next = Some(inst),
+            None => self.basic_blocks.get_mut(&block).unwrap().first_inst = Some(inst),
+        }
+    }
+
+    /// Inserts `inst` right after `after`, which must be in the layout.
+    pub fn insert_inst_after(&mut self, inst: InstructionId, after: InstructionId) {
+        let block = self.instructions[&after].block.unwrap();
+        let next = self.instructions[&after].next;
+        self.instructions.insert(
+            inst,
+            InstructionNode {
+                block: Some(block),
+                prev: Some(after),
+                next,
+            },
+        );
+        self.instructions.get_mut(&after).unwrap().next = Some(inst);
+        match next {
+            Some(next) => self.instructions.get_mut(&next).unwrap().prev = Some(inst),
+            None => self.basic_blocks.get_mut(&block).unwrap().last_inst = Some(inst),
+        }
+    }
+
     pub fn remove_inst(&mut self, inst: InstructionId) -> Option<()> {
         let block = self.instructions[&inst].block?;
         let prev;
@@ -1,5 +1,6 @@
 pub mod basic_block;
 pub mod builder;
+pub mod cfg;
 pub mod data;
 pub mod instruction;
 pub mod layout;
@@ -11,12 +12,12 @@ pub use parser::parse;
 
 use super::{
     module::{
-        attributes::Attribute, linkage::Linkage, name::Name,
+        attributes::Attribute, linkage::Linkage, metadata::Metadata, name::Name,
         preemption_specifier::PreemptionSpecifier, unnamed_addr::UnnamedAddr,
         visibility::Visibility,
     },
     types::{TypeId, Types},
-    value::ConstantData,
+    value::{ConstantData, Value, ValueId},
 };
 use crate::traits::basic_block::{BasicBlockData, BasicBlockLayout};
 use basic_block::BasicBlock;
@@ -40,7 +41,10 @@ pub struct Function {
     pub unnamed_addr: Option<UnnamedAddr>,
     pub func_attrs: Vec<Attribute>,
     pub ret_attrs: Vec<param_attrs::ParameterAttribute>,
+    pub comdat: Option<Name>,
     pub personality: Option<PersonalityFunc>,
+    /// Attachments in the order they were written. A kind such as `!type` may repeat.
+    pub metadata: Vec<(String, Metadata)>,
     pub data: data::Data,
     pub layout: layout::Layout,
     pub types: Types,
@@ -73,7 +77,9 @@ impl Function {
             unnamed_addr: None,
             func_attrs: vec![],
             ret_attrs: vec![],
+            comdat: None,
             personality: None,
+            metadata: vec![],
             data: data::Data::default(),
             layout: layout::Layout::default(),
             types,
@@ -96,8 +102,21 @@ impl Function {
         self.layout.is_empty()
     }
 
+    /// Returns the type of `val` if it's an argument or an instruction with a result.
+    /// Constants are untyped, so this returns `None` for them.
+    pub fn value_ty(&self, val: ValueId) -> Option<TypeId> {
+        match self.data.value_ref(val) {
+            Value::Instruction(id) => self.data.inst_ref(*id).result_ty(&self.data, &self.types),
+            Value::Argument(i) => self.params.get(*i).map(|param| param.ty),
+            _ => None,
+        }
+    }
+
+    /// Removes `inst`. Metadata that still wraps it (e.g. the operand of `llvm.dbg.value`)
+    /// becomes `undef`, as debug info must not keep an instruction alive.
     pub fn remove_inst(&mut self, inst: InstructionId) -> Option<()> {
         self.data.remove_uses(inst);
+        self.data.replace_metadata_refs(inst, Value::undef());
         self.layout.remove_inst(inst)
     }
 }
@@ -10,15 +10,15 @@ use super::{
             Function, Parameter, PersonalityFunc,
         },
         module::{
-            attributes, global_variable, linkage, name, preemption_specifier, unnamed_addr,
-            visibility,
+            attributes, comdat, global_variable, linkage, metadata, metadata::Metadata, name,
+            preemption_specifier, unnamed_addr, visibility,
         },
         types,
         types::Types,
-        util::spaces,
+        util::{error::furthest, spaces},
         value::{Value, ValueId},
     },
-    instruction::{Br, CondBr},
+    instruction::{Br, CondBr, Switch},
 };
 use nom::{
     branch::alt,
@@ -129,26 +129,29 @@ pub fn parse_body<'a, 'b>(
         ctx.layout.append_block(block);
         ctx.cur_block = block;
 
-        while let Ok((source_, inst)) = instruction::parse(source, ctx) {
-            ctx.layout.append_inst(inst, ctx.cur_block);
-            source = source_
-        }
+        let error = loop {
+            match instruction::parse(source, ctx) {
+                Ok((source_, inst)) => {
+                    ctx.layout.append_inst(inst, ctx.cur_block);
+                    source = source_
+                }
+                Err(e) => break e,
+            }
+        };
 
         if let Ok((source, _)) = tuple((spaces, char('}')))(source) {
-            ctx.set_blocks_info();
+            set_blocks_info(ctx.data, ctx.layout);
             return Ok((source, ()));
         }
 
         // Parse label
-        if let Ok((source_, label_)) =
-            preceded(spaces, terminated(name::parse, preceded(spaces, char(':'))))(source)
-        {
-            label = label_;
-            source = source_;
-            continue;
+        match preceded(spaces, terminated(name::parse, preceded(spaces, char(':'))))(source) {
+            Ok((source_, label_)) => {
+                label = label_;
+                source = source_;
+            }
+            Err(e) => return Err(furthest(error, e)),
         }
-
-        todo!("unsupported syntax at {:?}", source)
     }
 }
 
@@ -179,7 +182,12 @@ pub fn parse(source: &str, types: Types) -> IResult<&str, Function, VerboseError
     let (source, (params, is_var_arg)) = parse_argument_list(source, &types)?;
     let (source, unnamed_addr) = opt(preceded(spaces, unnamed_addr::parse))(source)?;
     let (source, func_attrs) = attributes::parser::parse_attributes(source)?;
-    let (mut source, personality) = parse_personality(source, &types)?;
+    let (source, comdat) =
+        opt(|source| comdat::parser::parse_comdat_ref(source, &name::Name::Name(name.clone())))(
+            source,
+        )?;
+    let (source, personality) = parse_personality(source, &types)?;
+    let (mut source, metadata) = parse_attached_metadata(source, &types)?;
 
     let mut data = Data::new();
     let mut layout = Layout::new();
@@ -221,16 +229,34 @@ pub fn parse(source: &str, types: Types) -> IResult<&str, Function, VerboseError
             unnamed_addr,
             ret_attrs,
             func_attrs,
+            comdat,
             params,
             data,
             layout,
             types,
             // is_prototype,
             personality,
+            metadata,
         },
     ))
 }
 
+// (!name !N)*
+fn parse_attached_metadata<'a>(
+    mut source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, Vec<(String, Metadata)>, VerboseError<&'a str>> {
+    let mut metadata = vec![];
+    while let Ok((src, kind)) =
+        preceded(spaces, preceded(char('!'), name::parser::identifier))(source)
+    {
+        let (src, meta) = metadata::parse_operand(types)(src)?;
+        metadata.push((kind.to_owned(), meta));
+        source = src;
+    }
+    Ok((source, metadata))
+}
+
 impl<'a> ParserContext<'a> {
     pub fn get_or_create_named_value(&mut self, name: name::Name) -> ValueId {
         if let Some(value) = self.name_to_value.get(&name) {
@@ -253,32 +279,28 @@ impl<'a> ParserContext<'a> {
         self.name_to_block.insert(name, block);
         block
     }
+}
 
-    fn set_blocks_info(&mut self) {
-        for block_id in self.layout.block_iter() {
-            let maybe_br = self.layout.block_node(block_id).last_inst();
-            let maybe_br = match maybe_br {
-                Some(maybe_br) => *maybe_br,
-                None => continue,
-            };
-            let maybe_br = &self.data.instructions[maybe_br];
-            if !maybe_br.opcode.is_terminator() {
-                continue;
-            }
-            let br = maybe_br;
-            match br.operand {
-                Operand::Br(Br { block }) => {
-                    self.data.basic_blocks[br.parent].succs.insert(block);
-                    self.data.basic_blocks[block].preds.insert(br.parent);
-                }
-                Operand::CondBr(CondBr { blocks, .. }) => {
-                    for &block in blocks.iter() {
-                        self.data.basic_blocks[br.parent].succs.insert(block);
-                        self.data.basic_blocks[block].preds.insert(br.parent);
-                    }
-                }
-                _ => continue,
-            }
+/// Fills in the predecessors and successors of every block from the terminators.
+pub(crate) fn set_blocks_info(data: &mut Data, layout: &Layout) {
+    for block_id in layout.block_iter() {
+        let br = match layout.block_node(block_id).last_inst() {
+            Some(br) => &data.instructions[*br],
+            None => continue,
+        };
+        if !br.opcode.is_terminator() {
+            continue;
+        }
+        let parent = br.parent;
+        let succs: Vec<BasicBlockId> = match br.operand {
+            Operand::Br(Br { block }) => vec![block],
+            Operand::CondBr(CondBr { blocks, .. }) => blocks.to_vec(),
+            Operand::Switch(Switch { ref blocks, .. }) => blocks.clone(),
+            _ => continue,
+        };
+        for block in succs {
+            data.basic_blocks[parent].succs.insert(block);
+            data.basic_blocks[block].preds.insert(parent);
         }
     }
 }
@@ -1,17 +1,19 @@
 use super::{
-    super::module::name::Name,
+    super::module::{metadata::Metadata, name::Name},
     super::types::Types,
     super::value::{InlineAsm, Value},
     basic_block::BasicBlockId,
     data::Data,
     instruction::{
-        Alloca, Cast, GetElementPtr, ICmp, Instruction, InstructionId, IntBinary, Load, Opcode,
-        Operand, Phi, Store,
+        Alloca, AtomicCmpXchg, AtomicRmw, Cast, FCmp, Fence, FloatBinary, FloatUnary, Freeze,
+        GetElementPtr, ICmp, Instruction, InstructionId, IntBinary, Load, Opcode, Operand, Phi,
+        Store,
     },
     Function,
 };
 use crate::ir::function::instruction::{
-    Br, Call, CondBr, ExtractValue, InsertValue, Invoke, LandingPad, Resume, Ret,
+    Br, Call, CondBr, ExtractElement, ExtractValue, InsertElement, InsertValue, Invoke, LandingPad,
+    Resume, Ret, Select, ShuffleVector, Switch,
 };
 use rustc_hash::FxHashMap;
 use std::fmt;
@@ -55,7 +57,7 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
             write!(self.fmt, "{} ", attr.to_string(&f.types))?
         }
         write!(self.fmt, "{} ", f.types.to_string(f.result_ty))?;
-        write!(self.fmt, "@{}(", f.name)?;
+        write!(self.fmt, "@{}(", Name::Name(f.name.clone()))?;
 
         for (i, param) in f.params.iter().enumerate() {
             write!(self.fmt, "{} ", f.types.to_string(param.ty))?;
@@ -64,7 +66,7 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
             }
             match param.name.to_string() {
                 Some(name) => {
-                    write!(self.fmt, "%{}", name)?;
+                    write!(self.fmt, "%{}", param.name)?;
                     self.indexes.insert(Ids::Arg(i), Name::Name(name.clone()));
                 }
                 None => {
@@ -80,7 +82,10 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
         }
 
         if f.is_var_arg {
-            write!(self.fmt, ", ...")?;
+            if !f.params.is_empty() {
+                write!(self.fmt, ", ")?;
+            }
+            write!(self.fmt, "...")?;
         }
 
         write!(self.fmt, ") ")?;
@@ -93,6 +98,13 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
             write!(self.fmt, "{:?} ", attr)?
         }
 
+        if let Some(comdat) = &f.comdat {
+            match comdat.to_string() {
+                Some(name) if name == &f.name => write!(self.fmt, "comdat ")?,
+                _ => write!(self.fmt, "comdat(${}) ", comdat)?,
+            }
+        }
+
         if let Some((ty, func)) = &f.personality {
             write!(
                 self.fmt,
@@ -102,6 +114,10 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
             )?
         }
 
+        for (kind, meta) in &f.metadata {
+            write!(self.fmt, "!{} {} ", kind, meta.to_string(&f.types))?;
+        }
+
         if f.is_prototype() {
             return writeln!(self.fmt);
         }
@@ -127,7 +143,14 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                 let inst = f.data.inst_ref(inst_id);
                 if matches!(
                     inst.opcode,
-                    Opcode::Store | Opcode::Br | Opcode::CondBr | Opcode::Ret | Opcode::Resume
+                    Opcode::Store
+                        | Opcode::Fence
+                        | Opcode::Br
+                        | Opcode::CondBr
+                        | Opcode::Switch
+                        | Opcode::Ret
+                        | Opcode::Resume
+                        | Opcode::Unreachable
                 ) || (inst.operand.call_result_ty() == Some(f.types.base().void()))
                 {
                     continue;
@@ -211,14 +234,30 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                         .trim_end_matches(", ")
                 )
             }
-            Operand::Load(Load { tys, addr, align }) => {
+            Operand::Load(
+                load @ Load {
+                    tys,
+                    addr,
+                    align,
+                    volatile,
+                    ordering,
+                    syncscope,
+                },
+            ) => {
                 write!(
                     self.fmt,
-                    "%{:?} = load {}, {} {}{}",
+                    "%{:?} = load {}{}{}, {} {}{}{}",
                     dest,
+                    if load.is_atomic() { "atomic " } else { "" },
+                    if *volatile { "volatile " } else { "" },
                     types.to_string(tys[0]),
                     types.to_string(tys[1]),
                     self.value_to_string(data.value_ref(*addr), types),
+                    if load.is_atomic() {
+                        format!("{} {:?}", syncscope, ordering)
+                    } else {
+                        "".to_string()
+                    },
                     if *align == 0 {
                         "".to_string()
                     } else {
@@ -226,14 +265,30 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                     }
                 )
             }
-            Operand::Store(Store { tys, args, align }) => {
+            Operand::Store(
+                store @ Store {
+                    tys,
+                    args,
+                    align,
+                    volatile,
+                    ordering,
+                    syncscope,
+                },
+            ) => {
                 write!(
                     self.fmt,
-                    "store {} {}, {} {}{}",
+                    "store {}{}{} {}, {} {}{}{}",
+                    if store.is_atomic() { "atomic " } else { "" },
+                    if *volatile { "volatile " } else { "" },
                     types.to_string(tys[0]),
                     self.value_to_string(data.value_ref(args[0]), types),
                     types.to_string(tys[1]),
                     self.value_to_string(data.value_ref(args[1]), types),
+                    if store.is_atomic() {
+                        format!("{} {:?}", syncscope, ordering)
+                    } else {
+                        "".to_string()
+                    },
                     if *align == 0 {
                         "".to_string()
                     } else {
@@ -241,6 +296,70 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                     }
                 )
             }
+            Operand::AtomicCmpXchg(AtomicCmpXchg {
+                tys,
+                args,
+                align,
+                weak,
+                volatile,
+                success_ordering,
+                failure_ordering,
+                syncscope,
+            }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = cmpxchg {}{}{} {}, {} {}, {} {}{} {:?} {:?}{}",
+                    dest,
+                    if *weak { "weak " } else { "" },
+                    if *volatile { "volatile " } else { "" },
+                    types.to_string(tys[1]),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    types.to_string(tys[2]),
+                    self.value_to_string(data.value_ref(args[1]), types),
+                    types.to_string(tys[2]),
+                    self.value_to_string(data.value_ref(args[2]), types),
+                    syncscope,
+                    success_ordering,
+                    failure_ordering,
+                    if *align == 0 {
+                        "".to_string()
+                    } else {
+                        format!(", align {}", align)
+                    }
+                )
+            }
+            Operand::AtomicRmw(AtomicRmw {
+                op,
+                tys,
+                args,
+                align,
+                volatile,
+                ordering,
+                syncscope,
+            }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = atomicrmw {}{:?} {} {}, {} {}{} {:?}{}",
+                    dest,
+                    if *volatile { "volatile " } else { "" },
+                    op,
+                    types.to_string(tys[0]),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    types.to_string(tys[1]),
+                    self.value_to_string(data.value_ref(args[1]), types),
+                    syncscope,
+                    ordering,
+                    if *align == 0 {
+                        "".to_string()
+                    } else {
+                        format!(", align {}", align)
+                    }
+                )
+            }
+            Operand::Fence(Fence {
+                ordering,
+                syncscope,
+            }) => write!(self.fmt, "fence{} {:?}", syncscope, ordering),
             Operand::InsertValue(InsertValue { tys, args }) => {
                 write!(
                     self.fmt,
@@ -281,6 +400,43 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                         .trim_end_matches(", ")
                 )
             }
+            Operand::ExtractElement(ExtractElement { tys, args }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = extractelement {} {}, {} {}",
+                    dest,
+                    types.to_string(tys[0]),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    types.to_string(tys[1]),
+                    self.value_to_string(data.value_ref(args[1]), types),
+                )
+            }
+            Operand::InsertElement(InsertElement { tys, args }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = insertelement {} {}, {} {}, {} {}",
+                    dest,
+                    types.to_string(tys[0]),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    types.to_string(tys[1]),
+                    self.value_to_string(data.value_ref(args[1]), types),
+                    types.to_string(tys[2]),
+                    self.value_to_string(data.value_ref(args[2]), types),
+                )
+            }
+            Operand::ShuffleVector(ShuffleVector { tys, args }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = shufflevector {} {}, {} {}, {} {}",
+                    dest,
+                    types.to_string(tys[0]),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    types.to_string(tys[0]),
+                    self.value_to_string(data.value_ref(args[1]), types),
+                    types.to_string(tys[1]),
+                    self.value_to_string(data.value_ref(args[2]), types),
+                )
+            }
             Operand::IntBinary(IntBinary {
                 ty,
                 nuw,
@@ -301,6 +457,54 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                     self.value_to_string(data.value_ref(args[1]), types),
                 )
             }
+            Operand::FloatBinary(FloatBinary {
+                ty,
+                fast_math_flags,
+                args,
+            }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = {:?}{} {} {}, {}",
+                    dest,
+                    inst.opcode,
+                    fast_math_flags,
+                    types.to_string(*ty),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    self.value_to_string(data.value_ref(args[1]), types),
+                )
+            }
+            Operand::FloatUnary(FloatUnary {
+                ty,
+                fast_math_flags,
+                arg,
+            }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = {:?}{} {} {}",
+                    dest,
+                    inst.opcode,
+                    fast_math_flags,
+                    types.to_string(*ty),
+                    self.value_to_string(data.value_ref(*arg), types),
+                )
+            }
+            Operand::FCmp(FCmp {
+                ty,
+                fast_math_flags,
+                args,
+                cond,
+            }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = fcmp{} {:?} {} {}, {}",
+                    dest,
+                    fast_math_flags,
+                    cond,
+                    types.to_string(*ty),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    self.value_to_string(data.value_ref(args[1]), types)
+                )
+            }
             Operand::ICmp(ICmp { ty, args, cond }) => {
                 write!(
                     self.fmt,
@@ -312,6 +516,28 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                     self.value_to_string(data.value_ref(args[1]), types)
                 )
             }
+            Operand::Select(Select { tys, args }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = select {} {}, {} {}, {} {}",
+                    dest,
+                    types.to_string(tys[0]),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    types.to_string(tys[1]),
+                    self.value_to_string(data.value_ref(args[1]), types),
+                    types.to_string(tys[1]),
+                    self.value_to_string(data.value_ref(args[2]), types),
+                )
+            }
+            Operand::Freeze(Freeze { ty, arg }) => {
+                write!(
+                    self.fmt,
+                    "%{:?} = freeze {} {}",
+                    dest,
+                    types.to_string(*ty),
+                    self.value_to_string(data.value_ref(*arg), types),
+                )
+            }
             Operand::Cast(Cast { tys, arg }) => {
                 write!(
                     self.fmt,
@@ -476,6 +702,25 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                     self.indexes[&Ids::Block(blocks[1])],
                 )
             }
+            Operand::Switch(Switch { tys, args, blocks }) => {
+                write!(
+                    self.fmt,
+                    "switch {} {}, label %{:?} [",
+                    types.to_string(tys[0]),
+                    self.value_to_string(data.value_ref(args[0]), types),
+                    self.indexes[&Ids::Block(blocks[0])],
+                )?;
+                for ((ty, arg), block) in tys[1..].iter().zip(&args[1..]).zip(&blocks[1..]) {
+                    write!(
+                        self.fmt,
+                        "\n        {} {}, label %{:?}",
+                        types.to_string(*ty),
+                        self.value_to_string(data.value_ref(*arg), types),
+                        self.indexes[&Ids::Block(*block)],
+                    )?;
+                }
+                write!(self.fmt, "\n    ]")
+            }
             Operand::Ret(Ret { val: None, .. }) => write!(self.fmt, "ret void"),
             Operand::Ret(Ret { val: Some(val), ty }) => {
                 write!(
@@ -485,11 +730,14 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                     self.value_to_string(data.value_ref(*val), types),
                 )
             }
+            Operand::Unreachable => write!(self.fmt, "unreachable"),
             Operand::Invalid => panic!(),
         }?;
 
-        for (kind, meta) in &inst.metadata {
-            write!(self.fmt, ", !{} {:?}", kind, meta)?;
+        let mut metadata = inst.metadata.iter().collect::<Vec<_>>();
+        metadata.sort_by_key(|(kind, _)| *kind);
+        for (kind, meta) in metadata {
+            write!(self.fmt, ", !{} {}", kind, meta.to_string(types))?;
         }
 
         Ok(())
@@ -514,6 +762,14 @@ impl<'a, 'b: 'a> FunctionAsmPrinter<'a, 'b> {
                     body
                 )
             }
+            Value::Metadata(Metadata::Value(ty, val)) => {
+                format!(
+                    "{} {}",
+                    types.to_string(*ty),
+                    self.value_to_string(val, types)
+                )
+            }
+            Value::Metadata(meta) => meta.to_string(types),
         }
     }
 
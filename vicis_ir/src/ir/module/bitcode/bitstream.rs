@@ -0,0 +1,559 @@
+//! The bitstream container: a stream of nested blocks holding records, whose encoding
+//! is described by abbreviations. See <https://llvm.org/docs/BitCodeFormat.html>.
+
+use super::{codes::abbrev_id, codes::block, codes::blockinfo, BitcodeError};
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+/// The widest fixed or VBR field a stream may declare
+const MAX_WIDTH: u64 = 32;
+
+/// How deep blocks may nest. LLVM itself nests them a few levels deep at most.
+const MAX_DEPTH: usize = 64;
+
+/// A block with the records and sub-blocks in it, in stream order.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub id: u32,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Entry {
+    Record(Record),
+    Block(Block),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub code: u32,
+    pub ops: Vec<u64>,
+    pub blob: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbbrevOp {
+    Literal(u64),
+    Fixed(u32),
+    Vbr(u32),
+    /// An array of the operand that follows
+    Array,
+    Char6,
+    Blob,
+}
+
+pub type Abbrev = Vec<AbbrevOp>;
+
+/// Writes a bitstream. Blocks get their length filled in when they end.
+#[derive(Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    /// Position in bits
+    pos: usize,
+    /// The abbreviation ID width of the current block
+    width: u32,
+    /// Open blocks: the byte offset of the length word, the width of the outer block and
+    /// the number of abbreviations defined so far
+    blocks: Vec<(usize, u32, u64)>,
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    /// Position in bits
+    pos: usize,
+    /// The number of blocks the cursor is in
+    depth: usize,
+}
+
+impl Block {
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        self.entries.iter().filter_map(|e| match e {
+            Entry::Record(r) => Some(r),
+            Entry::Block(_) => None,
+        })
+    }
+
+    pub fn blocks(&self, id: u32) -> impl Iterator<Item = &Block> {
+        self.entries.iter().filter_map(move |e| match e {
+            Entry::Block(b) if b.id == id => Some(b),
+            _ => None,
+        })
+    }
+}
+
+impl Record {
+    /// Reads operands from `i` on as characters, e.g. a name.
+    pub fn string(&self, i: usize) -> String {
+        let bytes: Vec<u8> = self
+            .ops
+            .get(i..)
+            .unwrap_or(&[])
+            .iter()
+            .map(|&c| c as u8)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    pub fn op(&self, i: usize) -> Result<u64, BitcodeError> {
+        self.ops.get(i).copied().ok_or_else(|| {
+            BitcodeError::new(format!(
+                "record {} has no operand #{} (it has {})",
+                self.code,
+                i,
+                self.ops.len()
+            ))
+        })
+    }
+}
+
+impl BitWriter {
+    /// Starts a bitcode file.
+    pub fn new() -> Self {
+        let mut w = Self {
+            width: 2,
+            ..Self::default()
+        };
+        w.bytes.extend_from_slice(b"BC\xC0\xDE");
+        w.pos = 32;
+        w
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        debug_assert!(self.blocks.is_empty());
+        self.bytes
+    }
+
+    pub fn emit(&mut self, val: u64, width: u32) {
+        let mut done = 0;
+        while done < width {
+            let bit = (self.pos % 8) as u32;
+            if bit == 0 {
+                self.bytes.push(0);
+            }
+            let take = (8 - bit).min(width - done);
+            let chunk = (val >> done) as u8 & ((1u16 << take) - 1) as u8;
+            *self.bytes.last_mut().unwrap() |= chunk << bit;
+            done += take;
+            self.pos += take as usize;
+        }
+    }
+
+    pub fn emit_vbr(&mut self, mut val: u64, width: u32) {
+        let hi = 1 << (width - 1);
+        while val >= hi {
+            self.emit((val & (hi - 1)) | hi, width);
+            val >>= width - 1;
+        }
+        self.emit(val, width)
+    }
+
+    pub fn align32(&mut self) {
+        let pad = (32 - self.pos % 32) % 32;
+        self.emit(0, pad as u32);
+    }
+
+    pub fn enter_block(&mut self, id: u32, width: u32) {
+        self.emit(abbrev_id::ENTER_SUBBLOCK, self.width);
+        self.emit_vbr(id as u64, 8);
+        self.emit_vbr(width as u64, 4);
+        self.align32();
+        // The number of words in the block, filled in by `end_block`
+        self.blocks.push((self.bytes.len(), self.width, 0));
+        self.emit(0, 32);
+        self.width = width;
+    }
+
+    pub fn end_block(&mut self) {
+        self.emit(abbrev_id::END_BLOCK, self.width);
+        self.align32();
+        let (offset, width, _) = self.blocks.pop().expect("no block to end");
+        let num_words = ((self.bytes.len() - offset - 4) / 4) as u32;
+        self.bytes[offset..offset + 4].copy_from_slice(&num_words.to_le_bytes());
+        self.width = width;
+    }
+
+    /// Writes a record. A record with a blob is written with an abbreviation defined for it.
+    pub fn record(&mut self, r: &Record) {
+        match &r.blob {
+            Some(blob) => {
+                let mut abbrev = vec![AbbrevOp::Literal(r.code as u64)];
+                abbrev.extend(r.ops.iter().map(|_| AbbrevOp::Vbr(6)));
+                abbrev.push(AbbrevOp::Blob);
+                let id = self.define_abbrev(&abbrev);
+                self.emit(id, self.width);
+                for op in &r.ops {
+                    self.emit_vbr(*op, 6);
+                }
+                self.emit_vbr(blob.len() as u64, 6);
+                self.align32();
+                for b in blob {
+                    self.emit(*b as u64, 8);
+                }
+                self.align32();
+            }
+            None => {
+                self.emit(abbrev_id::UNABBREV_RECORD, self.width);
+                self.emit_vbr(r.code as u64, 6);
+                self.emit_vbr(r.ops.len() as u64, 6);
+                for op in &r.ops {
+                    self.emit_vbr(*op, 6);
+                }
+            }
+        }
+    }
+
+    /// Defines an abbreviation in the current block and returns its ID.
+    fn define_abbrev(&mut self, abbrev: &[AbbrevOp]) -> u64 {
+        self.emit(abbrev_id::DEFINE_ABBREV, self.width);
+        self.emit_vbr(abbrev.len() as u64, 5);
+        for op in abbrev {
+            match *op {
+                AbbrevOp::Literal(v) => {
+                    self.emit(1, 1);
+                    self.emit_vbr(v, 8);
+                }
+                AbbrevOp::Fixed(width) | AbbrevOp::Vbr(width) => {
+                    self.emit(0, 1);
+                    self.emit(
+                        if matches!(op, AbbrevOp::Fixed(_)) {
+                            1
+                        } else {
+                            2
+                        },
+                        3,
+                    );
+                    self.emit_vbr(width as u64, 5);
+                }
+                AbbrevOp::Array | AbbrevOp::Char6 | AbbrevOp::Blob => {
+                    self.emit(0, 1);
+                    self.emit(
+                        match op {
+                            AbbrevOp::Array => 3,
+                            AbbrevOp::Char6 => 4,
+                            _ => 5,
+                        },
+                        3,
+                    );
+                }
+            }
+        }
+        let (_, _, num_abbrevs) = self
+            .blocks
+            .last_mut()
+            .expect("abbreviation outside a block");
+        *num_abbrevs += 1;
+        abbrev_id::FIRST_APPLICATION_ABBREV + *num_abbrevs - 1
+    }
+}
+
+/// Splits a bitcode file into its top-level blocks.
+pub fn read(bytes: &[u8]) -> Result<Vec<Block>, BitcodeError> {
+    let bytes = strip_wrapper(bytes)?;
+    if !bytes.starts_with(b"BC\xC0\xDE") {
+        return Err(BitcodeError::new("not a bitcode file (bad magic)"));
+    }
+    let mut cursor = Cursor {
+        bytes,
+        pos: 32,
+        depth: 0,
+    };
+    let mut blockinfo = FxHashMap::default();
+    let mut blocks = vec![];
+    // The stream is padded to a multiple of four bytes
+    while cursor.pos + 32 <= bytes.len() * 8 {
+        match cursor.read(2)? {
+            abbrev_id::ENTER_SUBBLOCK => {}
+            id => return Err(cursor.error(format!("unexpected abbrev id {} at top level", id))),
+        }
+        let id = cursor.read_vbr(8)? as u32;
+        if let Some(block) = cursor.read_block(id, &mut blockinfo)? {
+            blocks.push(block)
+        }
+    }
+    Ok(blocks)
+}
+
+/// Returns the bitcode in a Darwin-style wrapper, or `bytes` if there is none.
+fn strip_wrapper(bytes: &[u8]) -> Result<&[u8], BitcodeError> {
+    if !bytes.starts_with(&[0xde, 0xc0, 0x17, 0x0b]) {
+        return Ok(bytes);
+    }
+    let field = |i: usize| {
+        bytes
+            .get(i * 4..i * 4 + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    match (field(2), field(3)) {
+        (Some(offset), Some(size)) if offset + size <= bytes.len() => {
+            Ok(&bytes[offset..offset + size])
+        }
+        _ => Err(BitcodeError::new("truncated bitcode wrapper")),
+    }
+}
+
+impl<'a> Cursor<'a> {
+    fn error(&self, message: impl Into<String>) -> BitcodeError {
+        BitcodeError::new(message).at(self.pos / 8)
+    }
+
+    fn read(&mut self, width: u32) -> Result<u64, BitcodeError> {
+        if self.pos + width as usize > self.bytes.len() * 8 {
+            return Err(self.error("unexpected end of bitcode"));
+        }
+        let mut val = 0u64;
+        let mut done = 0;
+        while done < width {
+            let bit = (self.pos % 8) as u32;
+            let take = (8 - bit).min(width - done);
+            let chunk = (self.bytes[self.pos / 8] >> bit) as u64 & ((1 << take) - 1);
+            val |= chunk << done;
+            done += take;
+            self.pos += take as usize;
+        }
+        Ok(val)
+    }
+
+    fn read_vbr(&mut self, width: u32) -> Result<u64, BitcodeError> {
+        if width == 0 {
+            return Ok(0);
+        }
+        let hi = 1 << (width - 1);
+        let mut val = 0u64;
+        let mut shift = 0;
+        loop {
+            let piece = self.read(width)?;
+            if shift >= 64 {
+                return Err(self.error("VBR value is too large"));
+            }
+            val |= (piece & (hi - 1)) << shift;
+            if piece & hi == 0 {
+                return Ok(val);
+            }
+            shift += width - 1;
+        }
+    }
+
+    fn align32(&mut self) {
+        self.pos = self.pos.div_ceil(32) * 32;
+    }
+
+    /// Reads the body of a block following its ID. Returns `None` for the BLOCKINFO
+    /// block, whose abbreviations are kept in `blockinfo` instead.
+    fn read_block(
+        &mut self,
+        id: u32,
+        blockinfo: &mut FxHashMap<u32, Vec<Rc<Abbrev>>>,
+    ) -> Result<Option<Block>, BitcodeError> {
+        let width = match self.read_vbr(4)? {
+            width if width <= MAX_WIDTH => width as u32,
+            width => return Err(self.error(format!("invalid abbrev id width {}", width))),
+        };
+        self.align32();
+        let _num_words = self.read(32)?;
+        let mut abbrevs = blockinfo.get(&id).cloned().unwrap_or_default();
+        let mut entries = vec![];
+        // The block whose abbreviations BLOCKINFO is defining
+        let mut cur_bid = None;
+        loop {
+            match self.read(width)? {
+                abbrev_id::END_BLOCK => {
+                    self.align32();
+                    return Ok(if id == block::BLOCKINFO {
+                        None
+                    } else {
+                        Some(Block { id, entries })
+                    });
+                }
+                abbrev_id::ENTER_SUBBLOCK => {
+                    let sub_id = self.read_vbr(8)? as u32;
+                    if self.depth == MAX_DEPTH {
+                        return Err(self.error("blocks are nested too deep"));
+                    }
+                    self.depth += 1;
+                    let sub = self.read_block(sub_id, blockinfo)?;
+                    self.depth -= 1;
+                    if let Some(sub) = sub {
+                        entries.push(Entry::Block(sub))
+                    }
+                }
+                abbrev_id::DEFINE_ABBREV => {
+                    let abbrev = Rc::new(self.read_abbrev()?);
+                    if id == block::BLOCKINFO {
+                        let bid = cur_bid
+                            .ok_or_else(|| self.error("abbreviation defined before SETBID"))?;
+                        blockinfo.entry(bid).or_default().push(abbrev)
+                    } else {
+                        abbrevs.push(abbrev)
+                    }
+                }
+                abbrev_id::UNABBREV_RECORD => {
+                    let code = self.read_vbr(6)? as u32;
+                    let num_ops = self.read_count()?;
+                    let ops = (0..num_ops)
+                        .map(|_| self.read_vbr(6))
+                        .collect::<Result<_, _>>()?;
+                    let record = Record {
+                        code,
+                        ops,
+                        blob: None,
+                    };
+                    if id == block::BLOCKINFO && record.code == blockinfo::SETBID {
+                        cur_bid = Some(record.op(0)? as u32);
+                    }
+                    entries.push(Entry::Record(record))
+                }
+                abbrev => {
+                    let abbrev = abbrevs
+                        .get((abbrev - abbrev_id::FIRST_APPLICATION_ABBREV) as usize)
+                        .cloned()
+                        .ok_or_else(|| self.error(format!("undefined abbrev id {}", abbrev)))?;
+                    entries.push(Entry::Record(self.read_abbreviated_record(&abbrev)?))
+                }
+            }
+        }
+    }
+
+    fn read_abbrev(&mut self) -> Result<Abbrev, BitcodeError> {
+        let num_ops = self.read_vbr(5)?;
+        let mut ops = vec![];
+        for _ in 0..num_ops {
+            if self.read(1)? == 1 {
+                ops.push(AbbrevOp::Literal(self.read_vbr(8)?));
+                continue;
+            }
+            ops.push(match self.read(3)? {
+                1 => AbbrevOp::Fixed(self.read_width()?),
+                2 => AbbrevOp::Vbr(self.read_width()?),
+                3 => AbbrevOp::Array,
+                4 => AbbrevOp::Char6,
+                5 => AbbrevOp::Blob,
+                e => return Err(self.error(format!("unknown abbrev encoding {}", e))),
+            })
+        }
+        Ok(ops)
+    }
+
+    /// Reads the number of operands or array elements that follow. Each takes a bit at
+    /// least unless it's a literal, so a count above the bits left can't be right.
+    fn read_count(&mut self) -> Result<u64, BitcodeError> {
+        let count = self.read_vbr(6)?;
+        if count > (self.bytes.len() * 8 - self.pos) as u64 {
+            return Err(self.error(format!("implausible operand count {}", count)));
+        }
+        Ok(count)
+    }
+
+    /// Reads the width of a fixed or VBR operand, which is 32 bits at most as in LLVM.
+    fn read_width(&mut self) -> Result<u32, BitcodeError> {
+        match self.read_vbr(5)? {
+            width if width <= MAX_WIDTH => Ok(width as u32),
+            width => Err(self.error(format!("operand width {} is too large", width))),
+        }
+    }
+
+    fn read_scalar(&mut self, op: &AbbrevOp) -> Result<u64, BitcodeError> {
+        match *op {
+            AbbrevOp::Literal(v) => Ok(v),
+            AbbrevOp::Fixed(width) => self.read(width),
+            AbbrevOp::Vbr(width) => self.read_vbr(width),
+            AbbrevOp::Char6 => Ok(decode_char6(self.read(6)? as u8) as u64),
+            AbbrevOp::Array | AbbrevOp::Blob => Err(self.error("array or blob as array element")),
+        }
+    }
+
+    fn read_abbreviated_record(&mut self, abbrev: &[AbbrevOp]) -> Result<Record, BitcodeError> {
+        let mut vals = vec![];
+        let mut blob = None;
+        let mut i = 0;
+        while i < abbrev.len() {
+            match &abbrev[i] {
+                AbbrevOp::Array => {
+                    let elem = abbrev
+                        .get(i + 1)
+                        .ok_or_else(|| self.error("array without element type"))?;
+                    let len = self.read_count()?;
+                    for _ in 0..len {
+                        vals.push(self.read_scalar(elem)?)
+                    }
+                    i += 1;
+                }
+                AbbrevOp::Blob => {
+                    let len = self.read_vbr(6)? as usize;
+                    self.align32();
+                    let start = self.pos / 8;
+                    let bytes = self
+                        .bytes
+                        .get(start..)
+                        .and_then(|rest| rest.get(..len))
+                        .ok_or_else(|| self.error("unexpected end of bitcode in blob"))?;
+                    blob = Some(bytes.to_vec());
+                    self.pos += len * 8;
+                    self.align32();
+                }
+                op => vals.push(self.read_scalar(op)?),
+            }
+            i += 1;
+        }
+        if vals.is_empty() {
+            return Err(self.error("abbreviated record without a code"));
+        }
+        Ok(Record {
+            code: vals[0] as u32,
+            ops: vals.split_off(1),
+            blob,
+        })
+    }
+}
+
+fn decode_char6(c: u8) -> char {
+    match c {
+        0..=25 => (b'a' + c) as char,
+        26..=51 => (b'A' + c - 26) as char,
+        52..=61 => (b'0' + c - 52) as char,
+        62 => '.',
+        _ => '_',
+    }
+}
+
+/// Decodes a value emitted as a signed VBR: the sign is in the lowest bit.
+pub fn decode_signed(v: u64) -> i64 {
+    if v & 1 == 0 {
+        (v >> 1) as i64
+    } else if v != 1 {
+        -((v >> 1) as i64)
+    } else {
+        // `-0` encodes the minimum value
+        i64::MIN
+    }
+}
+
+/// Encodes a value as a signed VBR, the inverse of `decode_signed`.
+pub fn encode_signed(v: i64) -> u64 {
+    if v >= 0 {
+        (v as u64) << 1
+    } else {
+        ((v as u64).wrapping_neg() << 1) | 1
+    }
+}
+
+/// Reads `count` VBR6 values packed at the start of `bytes`, e.g. the string lengths of
+/// a METADATA_STRINGS blob.
+pub fn read_vbr6s(bytes: &[u8], count: usize) -> Result<Vec<u64>, BitcodeError> {
+    let mut cursor = Cursor {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+    (0..count).map(|_| cursor.read_vbr(6)).collect()
+}
+
+/// Packs VBR6 values padded to a multiple of four bytes, e.g. the string lengths of a
+/// METADATA_STRINGS blob.
+pub fn write_vbr6s(vals: &[u64]) -> Vec<u8> {
+    let mut w = BitWriter::default();
+    for val in vals {
+        w.emit_vbr(*val, 6);
+    }
+    w.align32();
+    w.bytes
+}
@@ -0,0 +1,197 @@
+//! Block IDs and record codes of LLVM bitcode, as in LLVM's `LLVMBitCodes.h`.
+
+#![allow(dead_code)]
+
+/// Abbreviation IDs with a fixed meaning in every block.
+pub mod abbrev_id {
+    pub const END_BLOCK: u64 = 0;
+    pub const ENTER_SUBBLOCK: u64 = 1;
+    pub const DEFINE_ABBREV: u64 = 2;
+    pub const UNABBREV_RECORD: u64 = 3;
+    /// The first ID of abbreviations defined by the stream.
+    pub const FIRST_APPLICATION_ABBREV: u64 = 4;
+}
+
+pub mod block {
+    pub const BLOCKINFO: u32 = 0;
+    pub const MODULE: u32 = 8;
+    pub const PARAMATTR: u32 = 9;
+    pub const PARAMATTR_GROUP: u32 = 10;
+    pub const CONSTANTS: u32 = 11;
+    pub const FUNCTION: u32 = 12;
+    pub const IDENTIFICATION: u32 = 13;
+    pub const VALUE_SYMTAB: u32 = 14;
+    pub const METADATA: u32 = 15;
+    pub const METADATA_ATTACHMENT: u32 = 16;
+    pub const TYPE: u32 = 17;
+    pub const METADATA_KIND: u32 = 22;
+    pub const STRTAB: u32 = 23;
+    pub const SYNC_SCOPE_NAMES: u32 = 26;
+}
+
+pub mod blockinfo {
+    pub const SETBID: u32 = 1;
+}
+
+pub mod identification {
+    pub const STRING: u32 = 1;
+    pub const EPOCH: u32 = 2;
+}
+
+pub mod module {
+    pub const VERSION: u32 = 1;
+    pub const TRIPLE: u32 = 2;
+    pub const DATALAYOUT: u32 = 3;
+    pub const SECTIONNAME: u32 = 5;
+    pub const GLOBALVAR: u32 = 7;
+    pub const FUNCTION: u32 = 8;
+    pub const GCNAME: u32 = 11;
+    pub const COMDAT: u32 = 12;
+    pub const VSTOFFSET: u32 = 13;
+    pub const ALIAS: u32 = 14;
+    pub const SOURCE_FILENAME: u32 = 16;
+    pub const IFUNC: u32 = 18;
+}
+
+pub mod paramattr {
+    pub const ENTRY: u32 = 2;
+    pub const GRP_ENTRY: u32 = 3;
+}
+
+pub mod types {
+    pub const NUMENTRY: u32 = 1;
+    pub const VOID: u32 = 2;
+    pub const FLOAT: u32 = 3;
+    pub const DOUBLE: u32 = 4;
+    pub const LABEL: u32 = 5;
+    pub const OPAQUE: u32 = 6;
+    pub const INTEGER: u32 = 7;
+    pub const POINTER: u32 = 8;
+    pub const HALF: u32 = 10;
+    pub const ARRAY: u32 = 11;
+    pub const VECTOR: u32 = 12;
+    pub const X86_FP80: u32 = 13;
+    pub const FP128: u32 = 14;
+    pub const METADATA: u32 = 16;
+    pub const STRUCT_ANON: u32 = 18;
+    pub const STRUCT_NAME: u32 = 19;
+    pub const STRUCT_NAMED: u32 = 20;
+    pub const FUNCTION: u32 = 21;
+    pub const TOKEN: u32 = 22;
+    pub const OPAQUE_POINTER: u32 = 25;
+}
+
+pub mod constants {
+    pub const SETTYPE: u32 = 1;
+    pub const NULL: u32 = 2;
+    pub const UNDEF: u32 = 3;
+    pub const INTEGER: u32 = 4;
+    pub const WIDE_INTEGER: u32 = 5;
+    pub const FLOAT: u32 = 6;
+    pub const AGGREGATE: u32 = 7;
+    pub const STRING: u32 = 8;
+    pub const CSTRING: u32 = 9;
+    pub const CE_BINOP: u32 = 10;
+    pub const CE_CAST: u32 = 11;
+    pub const CE_GEP: u32 = 12;
+    pub const CE_SELECT: u32 = 13;
+    pub const CE_EXTRACTELT: u32 = 14;
+    pub const CE_INSERTELT: u32 = 15;
+    pub const CE_SHUFFLEVEC: u32 = 16;
+    pub const CE_CMP: u32 = 17;
+    pub const CE_INBOUNDS_GEP: u32 = 20;
+    pub const BLOCKADDRESS: u32 = 21;
+    pub const DATA: u32 = 22;
+    pub const INLINEASM_OLD2: u32 = 23;
+    pub const CE_GEP_WITH_INRANGE_INDEX: u32 = 24;
+    pub const CE_UNOP: u32 = 25;
+    pub const POISON: u32 = 26;
+    pub const INLINEASM_OLD3: u32 = 28;
+    pub const INLINEASM: u32 = 30;
+}
+
+pub mod function {
+    pub const DECLAREBLOCKS: u32 = 1;
+    pub const INST_BINOP: u32 = 2;
+    pub const INST_CAST: u32 = 3;
+    pub const INST_EXTRACTELT: u32 = 6;
+    pub const INST_INSERTELT: u32 = 7;
+    pub const INST_SHUFFLEVEC: u32 = 8;
+    pub const INST_CMP: u32 = 9;
+    pub const INST_RET: u32 = 10;
+    pub const INST_BR: u32 = 11;
+    pub const INST_SWITCH: u32 = 12;
+    pub const INST_INVOKE: u32 = 13;
+    pub const INST_UNREACHABLE: u32 = 15;
+    pub const INST_PHI: u32 = 16;
+    pub const INST_ALLOCA: u32 = 19;
+    pub const INST_LOAD: u32 = 20;
+    pub const INST_EXTRACTVAL: u32 = 26;
+    pub const INST_INSERTVAL: u32 = 27;
+    pub const INST_CMP2: u32 = 28;
+    pub const INST_VSELECT: u32 = 29;
+    pub const DEBUG_LOC_AGAIN: u32 = 33;
+    pub const INST_CALL: u32 = 34;
+    pub const DEBUG_LOC: u32 = 35;
+    pub const INST_FENCE: u32 = 36;
+    pub const INST_ATOMICRMW_OLD: u32 = 38;
+    pub const INST_RESUME: u32 = 39;
+    pub const INST_LOADATOMIC: u32 = 41;
+    pub const INST_GEP: u32 = 43;
+    pub const INST_STORE: u32 = 44;
+    pub const INST_STOREATOMIC: u32 = 45;
+    pub const INST_CMPXCHG: u32 = 46;
+    pub const INST_LANDINGPAD: u32 = 47;
+    pub const OPERAND_BUNDLE: u32 = 55;
+    pub const INST_UNOP: u32 = 56;
+    pub const INST_FREEZE: u32 = 58;
+    pub const INST_ATOMICRMW: u32 = 59;
+}
+
+pub mod value_symtab {
+    pub const ENTRY: u32 = 1;
+    pub const BBENTRY: u32 = 2;
+}
+
+pub mod metadata {
+    pub const STRING_OLD: u32 = 1;
+    pub const VALUE: u32 = 2;
+    pub const NODE: u32 = 3;
+    pub const NAME: u32 = 4;
+    pub const DISTINCT_NODE: u32 = 5;
+    pub const KIND: u32 = 6;
+    pub const LOCATION: u32 = 7;
+    pub const NAMED_NODE: u32 = 10;
+    pub const ATTACHMENT: u32 = 11;
+    pub const SUBRANGE: u32 = 13;
+    pub const ENUMERATOR: u32 = 14;
+    pub const BASIC_TYPE: u32 = 15;
+    pub const FILE: u32 = 16;
+    pub const DERIVED_TYPE: u32 = 17;
+    pub const COMPOSITE_TYPE: u32 = 18;
+    pub const SUBROUTINE_TYPE: u32 = 19;
+    pub const COMPILE_UNIT: u32 = 20;
+    pub const SUBPROGRAM: u32 = 21;
+    pub const LEXICAL_BLOCK: u32 = 22;
+    pub const LEXICAL_BLOCK_FILE: u32 = 23;
+    pub const NAMESPACE: u32 = 24;
+    pub const GLOBAL_VAR: u32 = 27;
+    pub const LOCAL_VAR: u32 = 28;
+    pub const EXPRESSION: u32 = 29;
+    pub const IMPORTED_ENTITY: u32 = 31;
+    pub const STRINGS: u32 = 35;
+    pub const GLOBAL_DECL_ATTACHMENT: u32 = 36;
+    pub const GLOBAL_VAR_EXPR: u32 = 37;
+    pub const INDEX_OFFSET: u32 = 38;
+    pub const INDEX: u32 = 39;
+    pub const LABEL: u32 = 40;
+    pub const ARG_LIST: u32 = 46;
+}
+
+pub mod strtab {
+    pub const BLOB: u32 = 1;
+}
+
+pub mod sync_scope {
+    pub const NAME: u32 = 1;
+}
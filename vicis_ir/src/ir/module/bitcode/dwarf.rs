@@ -0,0 +1,401 @@
+//! DWARF constants and debug info flags by the names `!DI*` nodes print them with. The
+//! reader turns numbers into names and the writer names into numbers.
+
+pub const TAGS: &[(u64, &str)] = &[
+    (0x01, "DW_TAG_array_type"),
+    (0x02, "DW_TAG_class_type"),
+    (0x03, "DW_TAG_entry_point"),
+    (0x04, "DW_TAG_enumeration_type"),
+    (0x05, "DW_TAG_formal_parameter"),
+    (0x08, "DW_TAG_imported_declaration"),
+    (0x0a, "DW_TAG_label"),
+    (0x0b, "DW_TAG_lexical_block"),
+    (0x0d, "DW_TAG_member"),
+    (0x0f, "DW_TAG_pointer_type"),
+    (0x10, "DW_TAG_reference_type"),
+    (0x11, "DW_TAG_compile_unit"),
+    (0x12, "DW_TAG_string_type"),
+    (0x13, "DW_TAG_structure_type"),
+    (0x15, "DW_TAG_subroutine_type"),
+    (0x16, "DW_TAG_typedef"),
+    (0x17, "DW_TAG_union_type"),
+    (0x18, "DW_TAG_unspecified_parameters"),
+    (0x19, "DW_TAG_variant"),
+    (0x1a, "DW_TAG_common_block"),
+    (0x1b, "DW_TAG_common_inclusion"),
+    (0x1c, "DW_TAG_inheritance"),
+    (0x1d, "DW_TAG_inlined_subroutine"),
+    (0x1e, "DW_TAG_module"),
+    (0x1f, "DW_TAG_ptr_to_member_type"),
+    (0x20, "DW_TAG_set_type"),
+    (0x21, "DW_TAG_subrange_type"),
+    (0x22, "DW_TAG_with_stmt"),
+    (0x23, "DW_TAG_access_declaration"),
+    (0x24, "DW_TAG_base_type"),
+    (0x25, "DW_TAG_catch_block"),
+    (0x26, "DW_TAG_const_type"),
+    (0x27, "DW_TAG_constant"),
+    (0x28, "DW_TAG_enumerator"),
+    (0x29, "DW_TAG_file_type"),
+    (0x2a, "DW_TAG_friend"),
+    (0x2b, "DW_TAG_namelist"),
+    (0x2c, "DW_TAG_namelist_item"),
+    (0x2d, "DW_TAG_packed_type"),
+    (0x2e, "DW_TAG_subprogram"),
+    (0x2f, "DW_TAG_template_type_parameter"),
+    (0x30, "DW_TAG_template_value_parameter"),
+    (0x31, "DW_TAG_thrown_type"),
+    (0x32, "DW_TAG_try_block"),
+    (0x33, "DW_TAG_variant_part"),
+    (0x34, "DW_TAG_variable"),
+    (0x35, "DW_TAG_volatile_type"),
+    (0x36, "DW_TAG_dwarf_procedure"),
+    (0x37, "DW_TAG_restrict_type"),
+    (0x38, "DW_TAG_interface_type"),
+    (0x39, "DW_TAG_namespace"),
+    (0x3a, "DW_TAG_imported_module"),
+    (0x3b, "DW_TAG_unspecified_type"),
+    (0x3c, "DW_TAG_partial_unit"),
+    (0x3d, "DW_TAG_imported_unit"),
+    (0x3f, "DW_TAG_condition"),
+    (0x40, "DW_TAG_shared_type"),
+    (0x41, "DW_TAG_type_unit"),
+    (0x42, "DW_TAG_rvalue_reference_type"),
+    (0x43, "DW_TAG_template_alias"),
+    (0x44, "DW_TAG_coarray_type"),
+    (0x45, "DW_TAG_generic_subrange"),
+    (0x46, "DW_TAG_dynamic_type"),
+    (0x47, "DW_TAG_atomic_type"),
+    (0x48, "DW_TAG_call_site"),
+    (0x49, "DW_TAG_call_site_parameter"),
+    (0x4a, "DW_TAG_skeleton_unit"),
+    (0x4b, "DW_TAG_immutable_type"),
+    (0x4106, "DW_TAG_GNU_template_template_param"),
+    (0x4107, "DW_TAG_GNU_template_parameter_pack"),
+    (0x4200, "DW_TAG_APPLE_property"),
+];
+
+pub const ATES: &[(u64, &str)] = &[
+    (0x01, "DW_ATE_address"),
+    (0x02, "DW_ATE_boolean"),
+    (0x03, "DW_ATE_complex_float"),
+    (0x04, "DW_ATE_float"),
+    (0x05, "DW_ATE_signed"),
+    (0x06, "DW_ATE_signed_char"),
+    (0x07, "DW_ATE_unsigned"),
+    (0x08, "DW_ATE_unsigned_char"),
+    (0x09, "DW_ATE_imaginary_float"),
+    (0x0a, "DW_ATE_packed_decimal"),
+    (0x0b, "DW_ATE_numeric_string"),
+    (0x0c, "DW_ATE_edited"),
+    (0x0d, "DW_ATE_signed_fixed"),
+    (0x0e, "DW_ATE_unsigned_fixed"),
+    (0x0f, "DW_ATE_decimal_float"),
+    (0x10, "DW_ATE_UTF"),
+    (0x11, "DW_ATE_UCS"),
+    (0x12, "DW_ATE_ASCII"),
+];
+
+pub const LANGS: &[(u64, &str)] = &[
+    (0x01, "DW_LANG_C89"),
+    (0x02, "DW_LANG_C"),
+    (0x03, "DW_LANG_Ada83"),
+    (0x04, "DW_LANG_C_plus_plus"),
+    (0x05, "DW_LANG_Cobol74"),
+    (0x06, "DW_LANG_Cobol85"),
+    (0x07, "DW_LANG_Fortran77"),
+    (0x08, "DW_LANG_Fortran90"),
+    (0x09, "DW_LANG_Pascal83"),
+    (0x0a, "DW_LANG_Modula2"),
+    (0x0b, "DW_LANG_Java"),
+    (0x0c, "DW_LANG_C99"),
+    (0x0d, "DW_LANG_Ada95"),
+    (0x0e, "DW_LANG_Fortran95"),
+    (0x0f, "DW_LANG_PLI"),
+    (0x10, "DW_LANG_ObjC"),
+    (0x11, "DW_LANG_ObjC_plus_plus"),
+    (0x12, "DW_LANG_UPC"),
+    (0x13, "DW_LANG_D"),
+    (0x14, "DW_LANG_Python"),
+    (0x15, "DW_LANG_OpenCL"),
+    (0x16, "DW_LANG_Go"),
+    (0x17, "DW_LANG_Modula3"),
+    (0x18, "DW_LANG_Haskell"),
+    (0x19, "DW_LANG_C_plus_plus_03"),
+    (0x1a, "DW_LANG_C_plus_plus_11"),
+    (0x1b, "DW_LANG_OCaml"),
+    (0x1c, "DW_LANG_Rust"),
+    (0x1d, "DW_LANG_C11"),
+    (0x1e, "DW_LANG_Swift"),
+    (0x1f, "DW_LANG_Julia"),
+    (0x20, "DW_LANG_Dylan"),
+    (0x21, "DW_LANG_C_plus_plus_14"),
+    (0x22, "DW_LANG_Fortran03"),
+    (0x23, "DW_LANG_Fortran08"),
+    (0x24, "DW_LANG_RenderScript"),
+    (0x25, "DW_LANG_BLISS"),
+    (0x8001, "DW_LANG_Mips_Assembler"),
+    (0x8e57, "DW_LANG_GOOGLE_RenderScript"),
+    (0xb000, "DW_LANG_BORLAND_Delphi"),
+];
+
+pub const CCS: &[(u64, &str)] = &[
+    (0x01, "DW_CC_normal"),
+    (0x02, "DW_CC_program"),
+    (0x03, "DW_CC_nocall"),
+    (0x04, "DW_CC_pass_by_reference"),
+    (0x05, "DW_CC_pass_by_value"),
+    (0xb0, "DW_CC_LLVM_vectorcall"),
+];
+
+/// `DW_OP_*` operations with the number of arguments they take, except the numbered
+/// `DW_OP_lit*`, `DW_OP_reg*` and `DW_OP_breg*`.
+const OPS: &[(u64, &str, usize)] = &[
+    (0x06, "DW_OP_deref", 0),
+    (0x10, "DW_OP_constu", 1),
+    (0x11, "DW_OP_consts", 1),
+    (0x12, "DW_OP_dup", 0),
+    (0x14, "DW_OP_over", 0),
+    (0x16, "DW_OP_swap", 0),
+    (0x1a, "DW_OP_and", 0),
+    (0x1b, "DW_OP_div", 0),
+    (0x1c, "DW_OP_minus", 0),
+    (0x1d, "DW_OP_mod", 0),
+    (0x1e, "DW_OP_mul", 0),
+    (0x1f, "DW_OP_neg", 0),
+    (0x20, "DW_OP_not", 0),
+    (0x21, "DW_OP_or", 0),
+    (0x22, "DW_OP_plus", 0),
+    (0x23, "DW_OP_plus_uconst", 1),
+    (0x24, "DW_OP_shl", 0),
+    (0x25, "DW_OP_shr", 0),
+    (0x26, "DW_OP_shra", 0),
+    (0x27, "DW_OP_xor", 0),
+    (0x29, "DW_OP_eq", 0),
+    (0x2a, "DW_OP_ge", 0),
+    (0x2b, "DW_OP_gt", 0),
+    (0x2c, "DW_OP_le", 0),
+    (0x2d, "DW_OP_lt", 0),
+    (0x2e, "DW_OP_ne", 0),
+    (0x90, "DW_OP_regx", 1),
+    (0x92, "DW_OP_bregx", 2),
+    (0x93, "DW_OP_piece", 1),
+    (0x94, "DW_OP_deref_size", 1),
+    (0x96, "DW_OP_nop", 0),
+    (0x9f, "DW_OP_stack_value", 0),
+    (0xa3, "DW_OP_entry_value", 1),
+    (0xa8, "DW_OP_convert", 1),
+    (0xe0, "DW_OP_GNU_push_tls_address", 0),
+    (0xf3, "DW_OP_GNU_entry_value", 1),
+    (0x1000, "DW_OP_LLVM_fragment", 2),
+    (0x1001, "DW_OP_LLVM_convert", 2),
+    (0x1002, "DW_OP_LLVM_tag_offset", 1),
+    (0x1003, "DW_OP_LLVM_entry_value", 1),
+    (0x1004, "DW_OP_LLVM_implicit_pointer", 0),
+    (0x1005, "DW_OP_LLVM_arg", 1),
+];
+
+const DI_FLAGS: &[(u64, &str)] = &[
+    (1 << 2, "DIFlagFwdDecl"),
+    (1 << 3, "DIFlagAppleBlock"),
+    (1 << 4, "DIFlagReservedBit4"),
+    (1 << 5, "DIFlagVirtual"),
+    (1 << 6, "DIFlagArtificial"),
+    (1 << 7, "DIFlagExplicit"),
+    (1 << 8, "DIFlagPrototyped"),
+    (1 << 9, "DIFlagObjcClassComplete"),
+    (1 << 10, "DIFlagObjectPointer"),
+    (1 << 11, "DIFlagVector"),
+    (1 << 12, "DIFlagStaticMember"),
+    (1 << 13, "DIFlagLValueReference"),
+    (1 << 14, "DIFlagRValueReference"),
+    (1 << 15, "DIFlagExportSymbols"),
+    (1 << 18, "DIFlagIntroducedVirtual"),
+    (1 << 19, "DIFlagBitField"),
+    (1 << 20, "DIFlagNoReturn"),
+    (1 << 22, "DIFlagTypePassByValue"),
+    (1 << 23, "DIFlagTypePassByReference"),
+    (1 << 24, "DIFlagEnumClass"),
+    (1 << 25, "DIFlagThunk"),
+    (1 << 26, "DIFlagNonTrivial"),
+    (1 << 27, "DIFlagBigEndian"),
+    (1 << 28, "DIFlagLittleEndian"),
+    (1 << 29, "DIFlagAllCallsDescribed"),
+];
+
+/// The accessibility and the inheritance are two-bit fields at these shifts.
+const DI_FLAG_FIELDS: [(u64, [&str; 4]); 2] = [
+    (0, ["", "DIFlagPrivate", "DIFlagProtected", "DIFlagPublic"]),
+    (
+        16,
+        [
+            "",
+            "DIFlagSingleInheritance",
+            "DIFlagMultipleInheritance",
+            "DIFlagVirtualInheritance",
+        ],
+    ),
+];
+
+const DI_FLAG_INDIRECT_VIRTUAL_BASE: u64 = (1 << 2) | (1 << 5);
+
+const SP_FLAGS: &[(u64, &str)] = &[
+    (1, "DISPFlagVirtual"),
+    (1 << 1, "DISPFlagPureVirtual"),
+    (1 << 2, "DISPFlagLocalToUnit"),
+    (1 << 3, "DISPFlagDefinition"),
+    (1 << 4, "DISPFlagOptimized"),
+    (1 << 5, "DISPFlagPure"),
+    (1 << 6, "DISPFlagElemental"),
+    (1 << 7, "DISPFlagRecursive"),
+    (1 << 8, "DISPFlagMainSubprogram"),
+    (1 << 9, "DISPFlagDeleted"),
+    (1 << 11, "DISPFlagObjCDirect"),
+];
+
+pub fn name(table: &[(u64, &'static str)], value: u64) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(v, _)| *v == value)
+        .map(|(_, name)| *name)
+}
+
+pub fn value(table: &[(u64, &str)], name: &str) -> Option<u64> {
+    table.iter().find(|(_, n)| *n == name).map(|(v, _)| *v)
+}
+
+/// The name and the number of arguments of a `DW_OP_*` operation.
+pub fn op(op: u64) -> Option<(&'static str, usize)> {
+    Some(match op {
+        0x30..=0x4f => (DW_OP_LIT[(op - 0x30) as usize], 0),
+        0x50..=0x6f => (DW_OP_REG[(op - 0x50) as usize], 0),
+        0x70..=0x8f => (DW_OP_BREG[(op - 0x70) as usize], 1),
+        _ => OPS
+            .iter()
+            .find(|(code, _, _)| *code == op)
+            .map(|(_, name, num_args)| (*name, *num_args))?,
+    })
+}
+
+/// The code and the number of arguments of a `DW_OP_*` operation by its name.
+pub fn op_code(name: &str) -> Option<(u64, usize)> {
+    if let Some((code, _, num_args)) = OPS.iter().find(|(_, n, _)| *n == name) {
+        return Some((*code, *num_args));
+    }
+    for (prefix, base, num_args) in [
+        ("DW_OP_lit", 0x30, 0),
+        ("DW_OP_reg", 0x50, 0),
+        ("DW_OP_breg", 0x70, 1),
+    ] {
+        if let Some(n) = name
+            .strip_prefix(prefix)
+            .and_then(|n| n.parse::<u64>().ok())
+        {
+            return (n < 32).then_some((base + n, num_args));
+        }
+    }
+    None
+}
+
+/// `DIFlag*` names of `flags`, with the bits that have no name as a number.
+pub fn di_flags(mut flags: u64) -> Vec<String> {
+    let mut names = vec![];
+    for (shift, field) in DI_FLAG_FIELDS {
+        let bits = (flags >> shift) & 3;
+        if bits != 0 {
+            names.push(field[bits as usize].to_string());
+            flags &= !(3 << shift);
+        }
+    }
+    if flags & DI_FLAG_INDIRECT_VIRTUAL_BASE == DI_FLAG_INDIRECT_VIRTUAL_BASE {
+        names.push("DIFlagIndirectVirtualBase".to_string());
+        flags &= !DI_FLAG_INDIRECT_VIRTUAL_BASE;
+    }
+    named_bits(DI_FLAGS, flags, names)
+}
+
+/// `DISPFlag*` names of `flags`, with the bits that have no name as a number.
+pub fn di_sp_flags(flags: u64) -> Vec<String> {
+    named_bits(SP_FLAGS, flags, vec![])
+}
+
+fn named_bits(table: &[(u64, &str)], mut flags: u64, mut names: Vec<String>) -> Vec<String> {
+    for (bits, name) in table {
+        if flags & bits != 0 {
+            names.push(name.to_string());
+            flags &= !bits;
+        }
+    }
+    if flags != 0 {
+        names.push(flags.to_string());
+    }
+    names
+}
+
+/// The bits of a `DIFlag*` name or a number.
+pub fn di_flag(name: &str) -> Option<u64> {
+    if name == "DIFlagIndirectVirtualBase" {
+        return Some(DI_FLAG_INDIRECT_VIRTUAL_BASE);
+    }
+    for (shift, field) in DI_FLAG_FIELDS {
+        if let Some(i) = field.iter().skip(1).position(|n| *n == name) {
+            return Some((i as u64 + 1) << shift);
+        }
+    }
+    flag(DI_FLAGS, "DIFlagZero", name)
+}
+
+/// The bits of a `DISPFlag*` name or a number.
+pub fn di_sp_flag(name: &str) -> Option<u64> {
+    flag(SP_FLAGS, "DISPFlagZero", name)
+}
+
+fn flag(table: &[(u64, &str)], zero: &str, name: &str) -> Option<u64> {
+    if name == zero {
+        return Some(0);
+    }
+    value(table, name).or_else(|| name.parse().ok())
+}
+
+macro_rules! numbered_ops {
+    ($name:ident, $prefix:literal) => {
+        const $name: [&str; 32] = [
+            concat!($prefix, "0"),
+            concat!($prefix, "1"),
+            concat!($prefix, "2"),
+            concat!($prefix, "3"),
+            concat!($prefix, "4"),
+            concat!($prefix, "5"),
+            concat!($prefix, "6"),
+            concat!($prefix, "7"),
+            concat!($prefix, "8"),
+            concat!($prefix, "9"),
+            concat!($prefix, "10"),
+            concat!($prefix, "11"),
+            concat!($prefix, "12"),
+            concat!($prefix, "13"),
+            concat!($prefix, "14"),
+            concat!($prefix, "15"),
+            concat!($prefix, "16"),
+            concat!($prefix, "17"),
+            concat!($prefix, "18"),
+            concat!($prefix, "19"),
+            concat!($prefix, "20"),
+            concat!($prefix, "21"),
+            concat!($prefix, "22"),
+            concat!($prefix, "23"),
+            concat!($prefix, "24"),
+            concat!($prefix, "25"),
+            concat!($prefix, "26"),
+            concat!($prefix, "27"),
+            concat!($prefix, "28"),
+            concat!($prefix, "29"),
+            concat!($prefix, "30"),
+            concat!($prefix, "31"),
+        ];
+    };
+}
+
+numbered_ops!(DW_OP_LIT, "DW_OP_lit");
+numbered_ops!(DW_OP_REG, "DW_OP_reg");
+numbered_ops!(DW_OP_BREG, "DW_OP_breg");
@@ -0,0 +1,1064 @@
+use super::{
+    bitstream::{decode_signed, Record},
+    codes::{block, function as fc, value_symtab},
+    metadata::{Attached, Md, MdUse},
+    reader::{
+        binop, cast, decode_align, err, fcmp_cond, icmp_cond, int_binop_flags, Body, Reader,
+        Result, Slot,
+    },
+    BitcodeError,
+};
+use crate::ir::{
+    function::{
+        basic_block::BasicBlockId,
+        data::Data,
+        instruction::{
+            Alloca, AtomicCmpXchg, AtomicOrdering, AtomicRmw, AtomicRmwBinOp, Br, Call, Cast,
+            CondBr, ExtractElement, ExtractValue, FCmp, FastMathFlags, Fence, FloatBinary,
+            FloatUnary, Freeze, GetElementPtr, ICmp, InsertElement, InsertValue, Instruction,
+            InstructionId, Invoke, LandingPad, Load, Opcode, Operand, Phi, Resume, Ret, Select,
+            ShuffleVector, Store, Switch, SyncScope,
+        },
+        layout::Layout,
+        parser::set_blocks_info,
+    },
+    module::{
+        metadata::{Field, Metadata, SpecializedKind, SpecializedNode},
+        name::Name,
+    },
+    types::{Type, TypeId},
+    value::{ConstantData, ConstantInt, Value, ValueId},
+};
+use rustc_hash::FxHashMap;
+use std::mem;
+
+/// The state of the function body being read.
+struct State {
+    data: Data,
+    layout: Layout,
+    blocks: Vec<BasicBlockId>,
+    cur_block: usize,
+    /// Placeholders for values used before they are defined, keyed by value index
+    forward_refs: FxHashMap<usize, ValueId>,
+    /// Every instruction in order, as metadata attachments refer to them by index
+    insts: Vec<InstructionId>,
+    value_names: FxHashMap<usize, String>,
+    next_number: usize,
+    /// Metadata nodes passed to calls, with the index of the call
+    md_args: Vec<(usize, ValueId, usize)>,
+    /// Debug locations with the index of the instruction they belong to
+    debug_locs: Vec<(usize, usize)>,
+    last_loc: Option<usize>,
+}
+
+impl State {
+    fn cur_block(&self) -> Result<BasicBlockId> {
+        self.blocks
+            .get(self.cur_block)
+            .copied()
+            .ok_or_else(|| BitcodeError::new("instruction after the last block"))
+    }
+
+    fn block(&self, id: u64) -> Result<BasicBlockId> {
+        self.blocks
+            .get(id as usize)
+            .copied()
+            .ok_or_else(|| BitcodeError::new(format!("invalid block id {}", id)))
+    }
+}
+
+/// Converts a value ID relative to `inst_num` into an absolute one.
+fn relative(inst_num: usize, id: u64) -> usize {
+    (inst_num as u32).wrapping_sub(id as u32) as usize
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn read_function_body(&mut self, body: Body<'a>) -> Result<()> {
+        let num_module_values = self.values.len();
+        self.md.begin_function();
+
+        let func = &mut self.module.functions[body.func];
+        let mut state = State {
+            data: mem::take(&mut func.data),
+            layout: mem::take(&mut func.layout),
+            blocks: vec![],
+            cur_block: 0,
+            forward_refs: FxHashMap::default(),
+            insts: vec![],
+            value_names: FxHashMap::default(),
+            next_number: 0,
+            md_args: vec![],
+            debug_locs: vec![],
+            last_loc: None,
+        };
+        let param_tys: Vec<TypeId> = func.params.iter().map(|p| p.ty).collect();
+
+        // Names are needed when values are created, so read the symbol table first.
+        let mut block_names = FxHashMap::default();
+        for r in body
+            .block
+            .blocks(block::VALUE_SYMTAB)
+            .flat_map(|b| b.records())
+        {
+            match r.code {
+                value_symtab::ENTRY => {
+                    state.value_names.insert(r.op(0)? as usize, r.string(1));
+                }
+                value_symtab::BBENTRY => {
+                    block_names.insert(r.op(0)? as usize, r.string(1));
+                }
+                _ => {}
+            }
+        }
+
+        for (i, ty) in param_tys.into_iter().enumerate() {
+            let idx = self.values.len();
+            let name = match state.value_names.get(&idx) {
+                Some(name) => Name::Name(name.clone()),
+                None => {
+                    state.next_number += 1;
+                    Name::Number(state.next_number - 1)
+                }
+            };
+            self.module.functions[body.func].params[i].name = name;
+            let arg = state.data.create_value(Value::Argument(i));
+            self.values.push(Slot::Local(arg));
+            self.value_tys.push(ty);
+        }
+
+        let mut attachments = (vec![], vec![]);
+        for entry in &body.block.entries {
+            use super::bitstream::Entry;
+            match entry {
+                Entry::Block(b) => match b.id {
+                    block::CONSTANTS => self.read_constants(b)?,
+                    block::METADATA => self.read_metadata(b)?,
+                    block::METADATA_ATTACHMENT => {
+                        attachments = self.md.read_attachments(b, state.insts.len())?
+                    }
+                    _ => {}
+                },
+                Entry::Record(r) if r.code == fc::DECLAREBLOCKS => {
+                    // Every block ends with a terminator record.
+                    let num_blocks = r.op(0)? as usize;
+                    if num_blocks > body.block.entries.len() {
+                        return err(format!("{} blocks declared", num_blocks));
+                    }
+                    for i in 0..num_blocks {
+                        let block = state.data.create_block();
+                        state.data.block_ref_mut(block).name = Some(match block_names.get(&i) {
+                            Some(name) => Name::Name(name.clone()),
+                            None => {
+                                state.next_number += 1;
+                                Name::Number(state.next_number - 1)
+                            }
+                        });
+                        state.layout.append_block(block);
+                        state.blocks.push(block);
+                    }
+                }
+                Entry::Record(r) => self.read_instruction(&mut state, r)?,
+            }
+        }
+
+        if let Some((idx, _)) = state.forward_refs.iter().next() {
+            return err(format!(
+                "value {} is used but never defined in @{}",
+                idx, self.module.functions[body.func].name
+            ));
+        }
+
+        // Record metadata uses in the order slots are given to them.
+        let (func_attachments, inst_attachments) = attachments;
+        let mut uses = vec![MdUse::Attachments(
+            Attached::Function(body.func),
+            func_attachments,
+        )];
+        let mut inst_attachments = inst_attachments.into_iter().peekable();
+        let mut md_args = state.md_args.iter().peekable();
+        let mut debug_locs = state.debug_locs.iter().peekable();
+        for (i, inst) in state.insts.iter().enumerate() {
+            while let Some((_, value, md)) = md_args.next_if(|(j, _, _)| *j == i) {
+                uses.push(MdUse::CallArg(body.func, *value, *md));
+            }
+            let mut attached = vec![];
+            if let Some((_, loc)) = debug_locs.next_if(|(j, _)| *j == i) {
+                attached.push((0, *loc));
+            }
+            while let Some((_, mds)) = inst_attachments.next_if(|(j, _)| *j == i) {
+                attached.extend(mds);
+            }
+            uses.push(MdUse::Attachments(
+                Attached::Instruction(body.func, *inst),
+                attached,
+            ));
+        }
+        self.md.function_uses.insert(body.func, uses);
+
+        set_blocks_info(&mut state.data, &state.layout);
+        let func = &mut self.module.functions[body.func];
+        func.data = state.data;
+        func.layout = state.layout;
+        self.values.truncate(num_module_values);
+        self.value_tys.truncate(num_module_values);
+        Ok(())
+    }
+
+    /// Returns the value at `idx`, which may be defined later.
+    fn value(&mut self, state: &mut State, idx: usize) -> Result<ValueId> {
+        if idx >= self.values.len() {
+            let data = &mut state.data;
+            let parent = state
+                .blocks
+                .first()
+                .copied()
+                .unwrap_or_else(|| data.create_block());
+            return Ok(*state.forward_refs.entry(idx).or_insert_with(|| {
+                let dummy = data.create_inst(Opcode::Invalid.with_block(parent));
+                data.create_value(Value::Instruction(dummy))
+            }));
+        }
+        let value = match &self.values[idx] {
+            Slot::Local(v) => return Ok(*v),
+            Slot::Asm(asm) => Value::InlineAsm(asm.clone()),
+            _ => Value::Constant(self.constant(idx)?),
+        };
+        Ok(state.data.create_value(value))
+    }
+
+    /// Reads a relative value ID at `ops[*i]`, followed by its type if the value is
+    /// defined later.
+    fn value_and_type(
+        &mut self,
+        state: &mut State,
+        r: &Record,
+        i: &mut usize,
+    ) -> Result<(ValueId, TypeId)> {
+        let idx = relative(self.values.len(), r.op(*i)?);
+        *i += 1;
+        let ty = if idx >= self.values.len() {
+            *i += 1;
+            self.ty(r.op(*i - 1)?)?
+        } else {
+            self.value_ty(idx)?
+        };
+        Ok((self.value(state, idx)?, ty))
+    }
+
+    /// Reads a relative value ID at `ops[*i]` whose type is known from the context.
+    fn relative_value(&mut self, state: &mut State, r: &Record, i: &mut usize) -> Result<ValueId> {
+        let idx = relative(self.values.len(), r.op(*i)?);
+        *i += 1;
+        self.value(state, idx)
+    }
+
+    fn i32_value(&self, state: &mut State, n: u64) -> ValueId {
+        state
+            .data
+            .create_value(Value::Constant(ConstantData::Int(ConstantInt::from(
+                n as i32,
+            ))))
+    }
+
+    fn sync_scope(&self, id: u64) -> Result<SyncScope> {
+        self.sync_scopes
+            .get(id as usize)
+            .cloned()
+            .ok_or_else(|| BitcodeError::new(format!("unknown sync scope {}", id)))
+    }
+
+    fn read_instruction(&mut self, state: &mut State, r: &Record) -> Result<()> {
+        // Records that don't make an instruction may come after the last terminator.
+        match r.code {
+            fc::DEBUG_LOC => {
+                // [line, column, scope, inlined at, is implicit code]
+                let scope = self.md.node_or_null(r.op(2)?);
+                let inlined_at = self.md.node_or_null(r.op(3)?);
+                let implicit = r.ops.get(4).copied().unwrap_or(0) != 0;
+                let loc = self
+                    .md
+                    .location(r.op(0)?, r.op(1)?, scope, inlined_at, implicit);
+                state.last_loc = Some(loc);
+                return self.attach_debug_loc(state, loc);
+            }
+            fc::DEBUG_LOC_AGAIN => {
+                let loc = state
+                    .last_loc
+                    .ok_or_else(|| BitcodeError::new("no debug location to repeat"))?;
+                return self.attach_debug_loc(state, loc);
+            }
+            fc::OPERAND_BUNDLE => return Ok(()),
+            _ => {}
+        }
+
+        let types = self.module.types.clone();
+        let parent = state.cur_block()?;
+        let mut i = 0;
+        // The instruction and the type of its result, if any
+        let (inst, ty): (Instruction, Option<TypeId>) = match r.code {
+            fc::INST_BINOP => {
+                // [lhs, rhs, opcode, flags]
+                let (lhs, ty) = self.value_and_type(state, r, &mut i)?;
+                let rhs = self.relative_value(state, r, &mut i)?;
+                let opcode = binop(r.op(i)?, types.is_float(scalar(&types, ty)))?;
+                let flags = r.ops.get(i + 1).copied().unwrap_or(0);
+                let operand = match opcode {
+                    Opcode::FAdd | Opcode::FSub | Opcode::FMul | Opcode::FDiv | Opcode::FRem => {
+                        Operand::FloatBinary(FloatBinary {
+                            ty,
+                            fast_math_flags: fast_math_flags(flags),
+                            args: [lhs, rhs],
+                        })
+                    }
+                    _ => {
+                        let (nuw, nsw, exact) = int_binop_flags(opcode, flags);
+                        Operand::IntBinary(crate::ir::function::instruction::IntBinary {
+                            ty,
+                            nsw,
+                            nuw,
+                            exact,
+                            args: [lhs, rhs],
+                        })
+                    }
+                };
+                (opcode.with_block(parent).with_operand(operand), Some(ty))
+            }
+            fc::INST_UNOP => {
+                // [operand, opcode, flags]
+                let (arg, ty) = self.value_and_type(state, r, &mut i)?;
+                if r.op(i)? != 0 {
+                    return err(format!("unknown unary opcode {}", r.op(i)?));
+                }
+                let fast_math_flags = fast_math_flags(r.ops.get(i + 1).copied().unwrap_or(0));
+                (
+                    Opcode::FNeg
+                        .with_block(parent)
+                        .with_operand(Operand::FloatUnary(FloatUnary {
+                            ty,
+                            fast_math_flags,
+                            arg,
+                        })),
+                    Some(ty),
+                )
+            }
+            fc::INST_CAST => {
+                // [operand, dest type, opcode]
+                let (arg, from) = self.value_and_type(state, r, &mut i)?;
+                let to = self.ty(r.op(i)?)?;
+                (
+                    cast(r.op(i + 1)?)?
+                        .with_block(parent)
+                        .with_operand(Operand::Cast(Cast {
+                            tys: [from, to],
+                            arg,
+                        })),
+                    Some(to),
+                )
+            }
+            fc::INST_FREEZE => {
+                let (arg, ty) = self.value_and_type(state, r, &mut i)?;
+                (
+                    Opcode::Freeze
+                        .with_block(parent)
+                        .with_operand(Operand::Freeze(Freeze { ty, arg })),
+                    Some(ty),
+                )
+            }
+            fc::INST_GEP => {
+                // [inbounds, source element type, (operand)*]
+                let inbounds = r.op(0)? != 0;
+                let src_ty = self.ty(r.op(1)?)?;
+                i = 2;
+                let mut tys = vec![src_ty];
+                let mut args = vec![];
+                while i < r.ops.len() {
+                    let (arg, ty) = self.value_and_type(state, r, &mut i)?;
+                    tys.push(ty);
+                    args.push(arg);
+                }
+                if args.is_empty() {
+                    return err("getelementptr without a pointer");
+                }
+                let ty = self.gep_result_ty(state, &tys, &args)?;
+                (
+                    Opcode::GetElementPtr
+                        .with_block(parent)
+                        .with_operand(Operand::GetElementPtr(GetElementPtr {
+                            inbounds,
+                            tys,
+                            args,
+                        })),
+                    Some(ty),
+                )
+            }
+            fc::INST_EXTRACTVAL => {
+                // [aggregate, index*]
+                let (agg, agg_ty) = self.value_and_type(state, r, &mut i)?;
+                let mut args = vec![agg];
+                let mut ty = agg_ty;
+                for idx in &r.ops[i..] {
+                    ty = types
+                        .base()
+                        .element_at(ty, *idx as usize)
+                        .ok_or_else(|| BitcodeError::new("invalid extractvalue index"))?;
+                    args.push(self.i32_value(state, *idx));
+                }
+                (
+                    Opcode::ExtractValue
+                        .with_block(parent)
+                        .with_operand(Operand::ExtractValue(ExtractValue { ty: agg_ty, args })),
+                    Some(ty),
+                )
+            }
+            fc::INST_INSERTVAL => {
+                // [aggregate, value, index*]
+                let (agg, agg_ty) = self.value_and_type(state, r, &mut i)?;
+                let (val, val_ty) = self.value_and_type(state, r, &mut i)?;
+                let mut args = vec![agg, val];
+                for idx in &r.ops[i..] {
+                    args.push(self.i32_value(state, *idx));
+                }
+                (
+                    Opcode::InsertValue
+                        .with_block(parent)
+                        .with_operand(Operand::InsertValue(InsertValue {
+                            tys: [agg_ty, val_ty],
+                            args,
+                        })),
+                    Some(agg_ty),
+                )
+            }
+            fc::INST_VSELECT => {
+                // [true value, false value, condition]
+                let (t, ty) = self.value_and_type(state, r, &mut i)?;
+                let f = self.relative_value(state, r, &mut i)?;
+                let (cond, cond_ty) = self.value_and_type(state, r, &mut i)?;
+                (
+                    Opcode::Select
+                        .with_block(parent)
+                        .with_operand(Operand::Select(Select {
+                            tys: [cond_ty, ty],
+                            args: [cond, t, f],
+                        })),
+                    Some(ty),
+                )
+            }
+            fc::INST_EXTRACTELT => {
+                // [vector, index]
+                let (vec, vec_ty) = self.value_and_type(state, r, &mut i)?;
+                let (idx, idx_ty) = self.value_and_type(state, r, &mut i)?;
+                (
+                    Opcode::ExtractElement.with_block(parent).with_operand(
+                        Operand::ExtractElement(ExtractElement {
+                            tys: [vec_ty, idx_ty],
+                            args: [vec, idx],
+                        }),
+                    ),
+                    Some(element(&types, vec_ty)?),
+                )
+            }
+            fc::INST_INSERTELT => {
+                // [vector, element, index]
+                let (vec, vec_ty) = self.value_and_type(state, r, &mut i)?;
+                let elem = self.relative_value(state, r, &mut i)?;
+                let (idx, idx_ty) = self.value_and_type(state, r, &mut i)?;
+                (
+                    Opcode::InsertElement
+                        .with_block(parent)
+                        .with_operand(Operand::InsertElement(InsertElement {
+                            tys: [vec_ty, element(&types, vec_ty)?, idx_ty],
+                            args: [vec, elem, idx],
+                        })),
+                    Some(vec_ty),
+                )
+            }
+            fc::INST_SHUFFLEVEC => {
+                // [vector, vector, mask]
+                let (v1, vec_ty) = self.value_and_type(state, r, &mut i)?;
+                let v2 = self.relative_value(state, r, &mut i)?;
+                let (mask, mask_ty) = self.value_and_type(state, r, &mut i)?;
+                let ty = {
+                    let (num_elements, is_scalable) = match &*types.get(mask_ty) {
+                        Type::Vector(mask) => (mask.num_elements, mask.is_scalable),
+                        _ => return err("shufflevector mask is not a vector"),
+                    };
+                    let elem = element(&types, vec_ty)?;
+                    types.base_mut().vector(elem, num_elements, is_scalable)
+                };
+                (
+                    Opcode::ShuffleVector
+                        .with_block(parent)
+                        .with_operand(Operand::ShuffleVector(ShuffleVector {
+                            tys: [vec_ty, mask_ty],
+                            args: [v1, v2, mask],
+                        })),
+                    Some(ty),
+                )
+            }
+            fc::INST_CMP | fc::INST_CMP2 => {
+                // [lhs, rhs, predicate, flags]
+                let (lhs, ty) = self.value_and_type(state, r, &mut i)?;
+                let rhs = self.relative_value(state, r, &mut i)?;
+                let pred = r.op(i)?;
+                let result_ty = {
+                    let i1 = types.base().i1();
+                    let vector = match &*types.get(ty) {
+                        Type::Vector(v) => Some((v.num_elements, v.is_scalable)),
+                        _ => None,
+                    };
+                    match vector {
+                        Some((num_elements, is_scalable)) => {
+                            types.base_mut().vector(i1, num_elements, is_scalable)
+                        }
+                        None => i1,
+                    }
+                };
+                let inst = if pred < 32 {
+                    Opcode::FCmp
+                        .with_block(parent)
+                        .with_operand(Operand::FCmp(FCmp {
+                            ty,
+                            fast_math_flags: fast_math_flags(
+                                r.ops.get(i + 1).copied().unwrap_or(0),
+                            ),
+                            args: [lhs, rhs],
+                            cond: fcmp_cond(pred)?,
+                        }))
+                } else {
+                    Opcode::ICmp
+                        .with_block(parent)
+                        .with_operand(Operand::ICmp(ICmp {
+                            ty,
+                            args: [lhs, rhs],
+                            cond: icmp_cond(pred)?,
+                        }))
+                };
+                (inst, Some(result_ty))
+            }
+            fc::INST_RET => {
+                let (ty, val) = if r.ops.is_empty() {
+                    (types.base().void(), None)
+                } else {
+                    let (val, ty) = self.value_and_type(state, r, &mut i)?;
+                    (ty, Some(val))
+                };
+                (
+                    Opcode::Ret
+                        .with_block(parent)
+                        .with_operand(Operand::Ret(Ret { ty, val })),
+                    None,
+                )
+            }
+            fc::INST_BR => {
+                // [true block, false block, condition] or [block]
+                let inst = if r.ops.len() == 1 {
+                    Opcode::Br.with_block(parent).with_operand(Operand::Br(Br {
+                        block: state.block(r.op(0)?)?,
+                    }))
+                } else {
+                    i = 2;
+                    let arg = self.relative_value(state, r, &mut i)?;
+                    Opcode::CondBr
+                        .with_block(parent)
+                        .with_operand(Operand::CondBr(CondBr {
+                            arg,
+                            blocks: [state.block(r.op(0)?)?, state.block(r.op(1)?)?],
+                        }))
+                };
+                (inst, None)
+            }
+            fc::INST_SWITCH => {
+                // [condition type, condition, default block, (case value, block)*]
+                let ty = self.ty(r.op(0)?)?;
+                i = 1;
+                let cond = self.relative_value(state, r, &mut i)?;
+                let mut tys = vec![ty];
+                let mut args = vec![cond];
+                let mut blocks = vec![state.block(r.op(2)?)?];
+                for case in r.ops[3..].chunks(2) {
+                    if case.len() != 2 {
+                        return err("invalid switch record");
+                    }
+                    tys.push(ty);
+                    args.push(self.value(state, case[0] as usize)?);
+                    blocks.push(state.block(case[1])?);
+                }
+                (
+                    Opcode::Switch
+                        .with_block(parent)
+                        .with_operand(Operand::Switch(Switch { tys, args, blocks })),
+                    None,
+                )
+            }
+            fc::INST_UNREACHABLE => (
+                Opcode::Unreachable
+                    .with_block(parent)
+                    .with_operand(Operand::Unreachable),
+                None,
+            ),
+            fc::INST_RESUME => {
+                let (arg, ty) = self.value_and_type(state, r, &mut i)?;
+                (
+                    Opcode::Resume
+                        .with_block(parent)
+                        .with_operand(Operand::Resume(Resume { ty, arg })),
+                    None,
+                )
+            }
+            fc::INST_LANDINGPAD => {
+                // [type, is cleanup, number of clauses, (clause type, value)*]
+                let ty = self.ty(r.op(0)?)?;
+                (
+                    Opcode::LandingPad
+                        .with_block(parent)
+                        .with_operand(Operand::LandingPad(LandingPad { ty })),
+                    Some(ty),
+                )
+            }
+            fc::INST_PHI => {
+                // [type, (value, block)*, flags]
+                let ty = self.ty(r.op(0)?)?;
+                let mut args = vec![];
+                let mut blocks = vec![];
+                for pair in r.ops[1..].chunks_exact(2) {
+                    let idx = match (self.values.len() as i64).checked_sub(decode_signed(pair[0])) {
+                        Some(idx) => idx,
+                        None => return err("invalid phi operand"),
+                    };
+                    args.push(self.value(state, idx as usize)?);
+                    blocks.push(state.block(pair[1])?);
+                }
+                (
+                    Opcode::Phi
+                        .with_block(parent)
+                        .with_operand(Operand::Phi(Phi { ty, args, blocks })),
+                    Some(ty),
+                )
+            }
+            fc::INST_ALLOCA => {
+                // [allocated type, size type, size, alignment]
+                let rec = r.op(3)?;
+                let ty = self.ty(r.op(0)?)?;
+                let ty = if rec & (1 << 6) != 0 {
+                    ty
+                } else {
+                    element(&types, ty)?
+                };
+                let num_ty = self.ty(r.op(1)?)?;
+                let num_elements = self.constant(r.op(2)? as usize)?;
+                let align = decode_align((rec & 0x1f) | ((rec >> 8) << 5))?;
+                (
+                    Opcode::Alloca
+                        .with_block(parent)
+                        .with_operand(Operand::Alloca(Alloca {
+                            tys: [ty, num_ty],
+                            num_elements,
+                            align,
+                        })),
+                    Some(types.base_mut().pointer(ty)),
+                )
+            }
+            fc::INST_LOAD | fc::INST_LOADATOMIC => {
+                // [pointer, type, alignment, volatile, (ordering, sync scope)]
+                let (addr, ptr_ty) = self.value_and_type(state, r, &mut i)?;
+                let rest = if r.code == fc::INST_LOAD { 3 } else { 5 };
+                let ty = if i + rest == r.ops.len() {
+                    i += 1;
+                    self.ty(r.op(i - 1)?)?
+                } else {
+                    element(&types, ptr_ty)?
+                };
+                let (ordering, syncscope) = if r.code == fc::INST_LOAD {
+                    (AtomicOrdering::NotAtomic, SyncScope::System)
+                } else {
+                    (ordering(r.op(i + 2)?)?, self.sync_scope(r.op(i + 3)?)?)
+                };
+                (
+                    Opcode::Load
+                        .with_block(parent)
+                        .with_operand(Operand::Load(Load {
+                            tys: [ty, ptr_ty],
+                            addr,
+                            align: decode_align(r.op(i)?)?,
+                            volatile: r.op(i + 1)? != 0,
+                            ordering,
+                            syncscope,
+                        })),
+                    Some(ty),
+                )
+            }
+            fc::INST_STORE | fc::INST_STOREATOMIC => {
+                // [pointer, value, alignment, volatile, (ordering, sync scope)]
+                let (ptr, ptr_ty) = self.value_and_type(state, r, &mut i)?;
+                let (val, val_ty) = self.value_and_type(state, r, &mut i)?;
+                let (ordering, syncscope) = if r.code == fc::INST_STORE {
+                    (AtomicOrdering::NotAtomic, SyncScope::System)
+                } else {
+                    (ordering(r.op(i + 2)?)?, self.sync_scope(r.op(i + 3)?)?)
+                };
+                (
+                    Opcode::Store
+                        .with_block(parent)
+                        .with_operand(Operand::Store(Store {
+                            tys: [val_ty, ptr_ty],
+                            args: [val, ptr],
+                            align: decode_align(r.op(i)?)?,
+                            volatile: r.op(i + 1)? != 0,
+                            ordering,
+                            syncscope,
+                        })),
+                    None,
+                )
+            }
+            fc::INST_CMPXCHG => {
+                // [pointer, compare, new value, volatile, success ordering, sync scope,
+                //  failure ordering, weak, alignment]
+                let (ptr, ptr_ty) = self.value_and_type(state, r, &mut i)?;
+                let (cmp, ty) = self.value_and_type(state, r, &mut i)?;
+                let new = self.relative_value(state, r, &mut i)?;
+                let result_ty = {
+                    let i1 = types.base().i1();
+                    types.base_mut().anonymous_struct(vec![ty, i1], false)
+                };
+                (
+                    Opcode::AtomicCmpXchg
+                        .with_block(parent)
+                        .with_operand(Operand::AtomicCmpXchg(AtomicCmpXchg {
+                            tys: [result_ty, ptr_ty, ty],
+                            args: [ptr, cmp, new],
+                            align: decode_align(r.ops.get(i + 5).copied().unwrap_or(0))?,
+                            weak: r.ops.get(i + 4).copied().unwrap_or(0) != 0,
+                            volatile: r.op(i)? != 0,
+                            success_ordering: ordering(r.op(i + 1)?)?,
+                            failure_ordering: ordering(r.op(i + 3)?)?,
+                            syncscope: self.sync_scope(r.op(i + 2)?)?,
+                        })),
+                    Some(result_ty),
+                )
+            }
+            fc::INST_ATOMICRMW | fc::INST_ATOMICRMW_OLD => {
+                // [pointer, value, operation, volatile, ordering, sync scope, alignment]
+                let (ptr, ptr_ty) = self.value_and_type(state, r, &mut i)?;
+                let (val, ty) = if r.code == fc::INST_ATOMICRMW {
+                    self.value_and_type(state, r, &mut i)?
+                } else {
+                    (
+                        self.relative_value(state, r, &mut i)?,
+                        element(&types, ptr_ty)?,
+                    )
+                };
+                (
+                    Opcode::AtomicRmw
+                        .with_block(parent)
+                        .with_operand(Operand::AtomicRmw(AtomicRmw {
+                            op: rmw_op(r.op(i)?)?,
+                            tys: [ptr_ty, ty],
+                            args: [ptr, val],
+                            align: decode_align(r.ops.get(i + 4).copied().unwrap_or(0))?,
+                            volatile: r.op(i + 1)? != 0,
+                            ordering: ordering(r.op(i + 2)?)?,
+                            syncscope: self.sync_scope(r.op(i + 3)?)?,
+                        })),
+                    Some(ty),
+                )
+            }
+            fc::INST_FENCE => (
+                Opcode::Fence
+                    .with_block(parent)
+                    .with_operand(Operand::Fence(Fence {
+                        ordering: ordering(r.op(0)?)?,
+                        syncscope: self.sync_scope(r.op(1)?)?,
+                    })),
+                None,
+            ),
+            fc::INST_CALL => {
+                // [attributes, calling convention, fast math flags, function type, callee,
+                //  argument*]
+                let attrs = match r.op(0)? {
+                    0 => Default::default(),
+                    n => self.attr_list(n)?,
+                };
+                let cc = r.op(1)?;
+                i = 2;
+                if cc & (1 << 17) != 0 {
+                    i += 1;
+                }
+                let explicit_ty = if cc & (1 << 15) != 0 {
+                    i += 1;
+                    Some(self.ty(r.op(i - 1)?)?)
+                } else {
+                    None
+                };
+                let (call, ret) = self.read_call(state, r, i, explicit_ty, attrs)?;
+                (
+                    Opcode::Call
+                        .with_block(parent)
+                        .with_operand(Operand::Call(call)),
+                    Some(ret),
+                )
+            }
+            fc::INST_INVOKE => {
+                // [attributes, calling convention, normal block, unwind block,
+                //  function type, callee, argument*]
+                let attrs = match r.op(0)? {
+                    0 => Default::default(),
+                    n => self.attr_list(n)?,
+                };
+                let blocks = vec![state.block(r.op(2)?)?, state.block(r.op(3)?)?];
+                i = 4;
+                let explicit_ty = if r.op(1)? & (1 << 13) != 0 {
+                    i += 1;
+                    Some(self.ty(r.op(4)?)?)
+                } else {
+                    None
+                };
+                let (call, ret) = self.read_call(state, r, i, explicit_ty, attrs)?;
+                (
+                    Opcode::Invoke
+                        .with_block(parent)
+                        .with_operand(Operand::Invoke(Invoke {
+                            args: call.args,
+                            tys: call.tys,
+                            param_attrs: call.param_attrs,
+                            ret_attrs: call.ret_attrs,
+                            func_attrs: call.func_attrs,
+                            blocks,
+                        })),
+                    Some(ret),
+                )
+            }
+            code => return err(format!("unsupported instruction code {}", code)),
+        };
+
+        let ty = ty.filter(|ty| *ty != types.base().void());
+        let is_terminator = inst.opcode.is_terminator();
+        let id = match ty {
+            Some(ty) => {
+                let idx = self.values.len();
+                let name = match state.value_names.get(&idx) {
+                    Some(name) => Name::Name(name.clone()),
+                    None => {
+                        state.next_number += 1;
+                        Name::Number(state.next_number - 1)
+                    }
+                };
+                let inst = inst.with_dest(name);
+                let (id, value) = match state.forward_refs.remove(&idx) {
+                    Some(value) => {
+                        let id = *state.data.value_ref(value).as_inst();
+                        state.data.replace_inst(id, inst);
+                        (id, value)
+                    }
+                    None => {
+                        let id = state.data.create_inst(inst);
+                        (id, state.data.create_value(Value::Instruction(id)))
+                    }
+                };
+                self.values.push(Slot::Local(value));
+                self.value_tys.push(ty);
+                id
+            }
+            None => state.data.create_inst(inst),
+        };
+        state.layout.append_inst(id, parent);
+        state.insts.push(id);
+        if is_terminator {
+            state.cur_block += 1;
+        }
+        Ok(())
+    }
+
+    fn attach_debug_loc(&mut self, state: &mut State, loc: usize) -> Result<()> {
+        if state.insts.is_empty() {
+            return err("debug location before any instruction");
+        }
+        state.debug_locs.push((state.insts.len() - 1, loc));
+        Ok(())
+    }
+
+    /// Reads the callee and the arguments of a call or an invoke from `ops[i]` on.
+    fn read_call(
+        &mut self,
+        state: &mut State,
+        r: &Record,
+        mut i: usize,
+        explicit_ty: Option<TypeId>,
+        attrs: super::reader::AttrList,
+    ) -> Result<(Call, TypeId)> {
+        let types = self.module.types.clone();
+        let (callee, callee_ty) = self.value_and_type(state, r, &mut i)?;
+        let fn_ty = match explicit_ty {
+            Some(ty) => ty,
+            None => element(&types, callee_ty)?,
+        };
+        let (ret, params, is_var_arg) = match &*types.get(fn_ty) {
+            Type::Function(f) => (f.ret, f.params.clone(), f.is_var_arg),
+            _ => return err("call of a non-function type"),
+        };
+        let mut args = vec![callee];
+        let mut tys = vec![if is_var_arg { fn_ty } else { ret }];
+        for ty in params {
+            let op = r.op(i)?;
+            i += 1;
+            let arg = match &*types.get(ty) {
+                Type::Metadata => {
+                    let id = relative(self.values.len(), op);
+                    let md = self
+                        .md
+                        .lookup(id as u64)
+                        .ok_or_else(|| BitcodeError::new("invalid metadata argument"))?;
+                    match self.local_metadata(state, md)? {
+                        Some(meta) => state.data.create_value(Value::Metadata(meta)),
+                        None => {
+                            let value = state.data.create_value(Value::Metadata(Metadata::Null));
+                            state.md_args.push((state.insts.len(), value, md));
+                            value
+                        }
+                    }
+                }
+                _ => self.value(state, relative(self.values.len(), op))?,
+            };
+            args.push(arg);
+            tys.push(ty);
+        }
+        if is_var_arg {
+            while i < r.ops.len() {
+                let (arg, ty) = self.value_and_type(state, r, &mut i)?;
+                args.push(arg);
+                tys.push(ty);
+            }
+        }
+        let param_attrs = (0..args.len() - 1).map(|i| attrs.param(i)).collect();
+        let func_attrs = self.attr_set(attrs.func);
+        Ok((
+            Call {
+                args,
+                tys,
+                param_attrs,
+                ret_attrs: attrs.ret,
+                func_attrs,
+            },
+            ret,
+        ))
+    }
+
+    /// Returns a metadata argument that refers to values of this function, which is
+    /// printed inline rather than numbered.
+    fn local_metadata(&mut self, state: &mut State, md: usize) -> Result<Option<Metadata>> {
+        Ok(Some(match self.md.get(md) {
+            Md::LocalValue(ty, idx) => {
+                let (ty, idx) = (*ty, *idx);
+                let value = self.value(state, idx)?;
+                Metadata::Value(ty, Box::new(state.data.value_ref(value).clone()))
+            }
+            Md::ArgList(args) => {
+                let mut fields = vec![];
+                for arg in args.clone() {
+                    let meta = match self.local_metadata(state, arg)? {
+                        Some(meta) => meta,
+                        None => self.md.constant_operand(arg),
+                    };
+                    fields.push((None, Field::Metadata(meta)));
+                }
+                Metadata::Specialized(SpecializedNode {
+                    kind: SpecializedKind::DIArgList,
+                    fields,
+                })
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    fn gep_result_ty(&self, state: &State, tys: &[TypeId], args: &[ValueId]) -> Result<TypeId> {
+        let types = &self.module.types;
+        let mut ty = tys[0];
+        for idx in &args[2.min(args.len())..] {
+            let i = match state.data.value_ref(*idx) {
+                Value::Constant(ConstantData::Int(i)) => i.cast_to_usize(),
+                _ => 0,
+            };
+            ty = types
+                .base()
+                .element_at(ty, i)
+                .ok_or_else(|| BitcodeError::new("invalid getelementptr index"))?;
+        }
+        let addr_space = match &*types.get(scalar(types, tys[1])) {
+            Type::Pointer(p) => p.addr_space,
+            _ => return err("getelementptr on a non-pointer"),
+        };
+        let ptr = types.base_mut().pointer_in_addr_space(ty, addr_space);
+        // A vector of pointers, or a vector index, gives a vector of pointers.
+        let vector = tys[1..].iter().find_map(|ty| match &*types.get(*ty) {
+            Type::Vector(v) => Some((v.num_elements, v.is_scalable)),
+            _ => None,
+        });
+        Ok(match vector {
+            Some((n, scalable)) => types.base_mut().vector(ptr, n, scalable),
+            None => ptr,
+        })
+    }
+}
+
+/// Returns the element type of a pointer or vector type.
+fn element(types: &crate::ir::types::Types, ty: TypeId) -> Result<TypeId> {
+    types
+        .get_element(ty)
+        .ok_or_else(|| BitcodeError::new(format!("{} has no element type", types.to_string(ty))))
+}
+
+/// Returns the element type of a vector type, or `ty` itself.
+fn scalar(types: &crate::ir::types::Types, ty: TypeId) -> TypeId {
+    match &*types.get(ty) {
+        Type::Vector(v) => v.inner,
+        _ => ty,
+    }
+}
+
+fn fast_math_flags(flags: u64) -> FastMathFlags {
+    if flags & 1 != 0 {
+        return FastMathFlags::fast();
+    }
+    FastMathFlags {
+        nnan: flags & 2 != 0,
+        ninf: flags & 4 != 0,
+        nsz: flags & 8 != 0,
+        arcp: flags & 16 != 0,
+        contract: flags & 32 != 0,
+        afn: flags & 64 != 0,
+        reassoc: flags & 128 != 0,
+    }
+}
+
+fn ordering(code: u64) -> Result<AtomicOrdering> {
+    Ok(match code {
+        0 => AtomicOrdering::NotAtomic,
+        1 => AtomicOrdering::Unordered,
+        2 => AtomicOrdering::Monotonic,
+        3 => AtomicOrdering::Acquire,
+        4 => AtomicOrdering::Release,
+        5 => AtomicOrdering::AcqRel,
+        6 => AtomicOrdering::SeqCst,
+        _ => return err(format!("unknown atomic ordering {}", code)),
+    })
+}
+
+fn rmw_op(code: u64) -> Result<AtomicRmwBinOp> {
+    Ok(match code {
+        0 => AtomicRmwBinOp::Xchg,
+        1 => AtomicRmwBinOp::Add,
+        2 => AtomicRmwBinOp::Sub,
+        3 => AtomicRmwBinOp::And,
+        4 => AtomicRmwBinOp::Nand,
+        5 => AtomicRmwBinOp::Or,
+        6 => AtomicRmwBinOp::Xor,
+        7 => AtomicRmwBinOp::Max,
+        8 => AtomicRmwBinOp::Min,
+        9 => AtomicRmwBinOp::UMax,
+        10 => AtomicRmwBinOp::UMin,
+        11 => AtomicRmwBinOp::FAdd,
+        12 => AtomicRmwBinOp::FSub,
+        13 => AtomicRmwBinOp::FMax,
+        14 => AtomicRmwBinOp::FMin,
+        _ => return err(format!("unknown atomicrmw operation {}", code)),
+    })
+}
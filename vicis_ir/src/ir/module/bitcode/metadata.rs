@@ -0,0 +1,1075 @@
+use super::{
+    bitstream::{decode_signed, read_vbr6s, Block, Record},
+    codes::metadata as mdc,
+    dwarf,
+    reader::{err, Reader, Result, Slot},
+    BitcodeError,
+};
+use crate::ir::{
+    function::{instruction::InstructionId, FunctionId},
+    module::{
+        metadata::{Field, Metadata, SpecializedKind, SpecializedNode},
+        name::Name,
+    },
+    types::{TypeId, MAX_INT_BITS},
+    value::{ConstantData, ConstantInt, Value, ValueId},
+};
+use rustc_hash::FxHashMap;
+
+/// A metadata entry as it is in the bitcode. Nodes refer to other entries by their
+/// index in the table, and get their `!N` only after the whole module has been read.
+pub(super) enum Md {
+    String(String),
+    Value(TypeId, ConstantData),
+    /// A function-local value such as an argument or an instruction (value index)
+    LocalValue(TypeId, usize),
+    Node {
+        distinct: bool,
+        ops: Vec<Option<usize>>,
+    },
+    Di(DiNode),
+    ArgList(Vec<usize>),
+    Null,
+}
+
+/// A specialized node such as `!DILocation`.
+pub(super) struct DiNode {
+    distinct: bool,
+    kind: SpecializedKind,
+    fields: Vec<(Option<&'static str>, RawField)>,
+    /// Operands in the order LLVM visits them when numbering nodes
+    children: Vec<Option<usize>>,
+}
+
+pub(super) enum RawField {
+    Known(Field),
+    /// An `MDString`. The field is left out if it is empty and the flag is set
+    Str(Option<usize>, bool),
+    /// A node. The field is left out if it is null and the flag is set
+    Ref(Option<usize>, bool),
+    /// A bound of a subrange, printed as an integer if it is a constant
+    Bound(Option<usize>),
+}
+
+/// Something metadata is attached to.
+pub(super) enum Attached {
+    GlobalVariable(Name),
+    Function(FunctionId),
+    Instruction(FunctionId, InstructionId),
+}
+
+/// A use of metadata in a function, in the order LLVM numbers nodes.
+pub(super) enum MdUse {
+    /// `(kind, entry)` pairs
+    Attachments(Attached, Vec<(u64, usize)>),
+    /// A node passed to an intrinsic, which is a placeholder value until numbered
+    CallArg(FunctionId, ValueId, usize),
+}
+
+/// The line, the column, the scope, the inlined-at location and whether it's implicit code.
+type LocationKey = (u64, u64, Option<usize>, Option<usize>, bool);
+
+#[derive(Default)]
+pub(super) struct MetadataTable {
+    arena: Vec<Md>,
+    /// Metadata IDs to entries
+    ids: Vec<usize>,
+    /// The number of module-level IDs. Function-level IDs come after them
+    module_len: Option<usize>,
+    kinds: FxHashMap<u64, String>,
+    named: Vec<(String, Vec<usize>)>,
+    locations: FxHashMap<LocationKey, usize>,
+    /// Attachments of global variables and function declarations by value index
+    decl_attachments: FxHashMap<usize, Vec<(u64, usize)>>,
+    pub function_uses: FxHashMap<FunctionId, Vec<MdUse>>,
+}
+
+impl MetadataTable {
+    pub fn read_kinds(&mut self, b: &Block) {
+        for r in b.records().filter(|r| r.code == mdc::KIND) {
+            if let Some(id) = r.ops.first() {
+                self.kinds.insert(*id, r.string(1));
+            }
+        }
+    }
+
+    /// Drops the IDs of the previous function.
+    pub fn begin_function(&mut self) {
+        let len = *self.module_len.get_or_insert(self.ids.len());
+        self.ids.truncate(len);
+    }
+
+    pub fn lookup(&self, id: u64) -> Option<usize> {
+        self.ids.get(id as usize).copied()
+    }
+
+    /// Looks up an ID that is 0 for null and the ID plus one otherwise.
+    pub fn node_or_null(&self, id: u64) -> Option<usize> {
+        id.checked_sub(1).and_then(|id| self.lookup(id))
+    }
+
+    /// Returns a `!DILocation` with the given fields, sharing equal ones.
+    pub fn location(
+        &mut self,
+        line: u64,
+        column: u64,
+        scope: Option<usize>,
+        inlined_at: Option<usize>,
+        implicit: bool,
+    ) -> usize {
+        let key = (line, column, scope, inlined_at, implicit);
+        if let Some(loc) = self.locations.get(&key) {
+            return *loc;
+        }
+        let loc = self.arena.len();
+        self.arena
+            .push(location(false, line, column, scope, inlined_at, implicit));
+        self.locations.insert(key, loc);
+        loc
+    }
+
+    /// Reads a METADATA_ATTACHMENT block, returning the attachments of the function and
+    /// those of its instructions by instruction index.
+    #[allow(clippy::type_complexity)]
+    pub fn read_attachments(
+        &self,
+        b: &Block,
+        num_insts: usize,
+    ) -> Result<(Vec<(u64, usize)>, Vec<(usize, Vec<(u64, usize)>)>)> {
+        let mut func = vec![];
+        let mut insts = vec![];
+        for r in b.records().filter(|r| r.code == mdc::ATTACHMENT) {
+            let (inst, pairs) = if r.ops.len() % 2 == 0 {
+                (None, &r.ops[..])
+            } else {
+                let inst = r.ops[0] as usize;
+                if inst >= num_insts {
+                    return err(format!("metadata attached to unknown instruction {}", inst));
+                }
+                (Some(inst), &r.ops[1..])
+            };
+            let attached = self.pairs(pairs)?;
+            match inst {
+                Some(inst) => insts.push((inst, attached)),
+                None => func.extend(attached),
+            }
+        }
+        insts.sort_by_key(|(inst, _)| *inst);
+        Ok((func, insts))
+    }
+
+    /// Reads `(kind, metadata ID)*`.
+    fn pairs(&self, ops: &[u64]) -> Result<Vec<(u64, usize)>> {
+        ops.chunks_exact(2)
+            .map(|pair| match self.lookup(pair[1]) {
+                Some(md) => Ok((pair[0], md)),
+                None => err(format!("invalid metadata ID {}", pair[1])),
+            })
+            .collect()
+    }
+
+    pub fn get(&self, md: usize) -> &Md {
+        &self.arena[md]
+    }
+
+    /// Returns a string or a constant as an operand.
+    pub fn constant_operand(&self, md: usize) -> Metadata {
+        self.operand(md, &[])
+    }
+
+    fn push_id(&mut self, md: Md) -> usize {
+        let idx = self.arena.len();
+        self.arena.push(md);
+        self.ids.push(idx);
+        idx
+    }
+
+    /// Gives `!N` to the nodes reachable from `root` in pre-order, as LLVM does.
+    fn number(&self, root: usize, slots: &mut [Option<usize>], next: &mut usize) {
+        let mut stack = vec![root];
+        while let Some(md) = stack.pop() {
+            let children = match &self.arena[md] {
+                Md::Node { ops, .. } => ops,
+                Md::Di(d) if d.kind != SpecializedKind::DIExpression => &d.children,
+                _ => continue,
+            };
+            if slots[md].is_some() {
+                continue;
+            }
+            slots[md] = Some(*next);
+            *next += 1;
+            stack.extend(children.iter().rev().flatten());
+        }
+    }
+
+    /// Returns `md` as an operand: a reference to a numbered node, or the value itself.
+    fn operand(&self, md: usize, slots: &[Option<usize>]) -> Metadata {
+        match &self.arena[md] {
+            Md::String(s) => Metadata::String(s.clone()),
+            Md::Value(_, ConstantData::Int(i)) => Metadata::Int(i.clone()),
+            Md::Value(ty, konst) => Metadata::Value(*ty, Box::new(Value::Constant(konst.clone()))),
+            Md::LocalValue(..) | Md::Null => Metadata::Null,
+            Md::Node { .. } | Md::Di(_) | Md::ArgList(_) => match slots[md] {
+                Some(slot) => Metadata::Name(Name::Number(slot)),
+                None => self.definition(md, slots),
+            },
+        }
+    }
+
+    /// Returns the contents of the node `md`.
+    fn definition(&self, md: usize, slots: &[Option<usize>]) -> Metadata {
+        let distinct = |distinct: bool, meta: Metadata| {
+            if distinct {
+                Metadata::Distinct(Box::new(meta))
+            } else {
+                meta
+            }
+        };
+        match &self.arena[md] {
+            Md::Node { distinct: d, ops } => distinct(
+                *d,
+                Metadata::Node(
+                    ops.iter()
+                        .map(|op| op.map_or(Metadata::Null, |op| self.operand(op, slots)))
+                        .collect(),
+                ),
+            ),
+            Md::Di(node) => distinct(
+                node.distinct,
+                Metadata::Specialized(SpecializedNode {
+                    kind: node.kind,
+                    fields: node
+                        .fields
+                        .iter()
+                        .filter_map(|(key, field)| {
+                            Some((key.map(String::from), self.field(field, slots)?))
+                        })
+                        .collect(),
+                }),
+            ),
+            Md::ArgList(args) => Metadata::Specialized(SpecializedNode {
+                kind: SpecializedKind::DIArgList,
+                fields: args
+                    .iter()
+                    .map(|arg| (None, Field::Metadata(self.operand(*arg, slots))))
+                    .collect(),
+            }),
+            _ => self.operand(md, slots),
+        }
+    }
+
+    fn field(&self, field: &RawField, slots: &[Option<usize>]) -> Option<Field> {
+        match field {
+            RawField::Known(field) => Some(field.clone()),
+            RawField::Str(s, skip_empty) => {
+                let s = match s.map(|s| &self.arena[s]) {
+                    Some(Md::String(s)) => s.clone(),
+                    _ => String::new(),
+                };
+                (!(*skip_empty && s.is_empty())).then_some(Field::String(s))
+            }
+            RawField::Ref(None, true) | RawField::Bound(None) => None,
+            RawField::Ref(None, false) => Some(Field::Metadata(Metadata::Null)),
+            RawField::Bound(Some(md)) => match &self.arena[*md] {
+                Md::Value(_, ConstantData::Int(i)) => Some(Field::Int(i.sext_value()? as i128)),
+                _ => Some(Field::Metadata(self.operand(*md, slots))),
+            },
+            RawField::Ref(Some(md), _) => Some(Field::Metadata(self.operand(*md, slots))),
+        }
+    }
+}
+
+fn location(
+    distinct: bool,
+    line: u64,
+    column: u64,
+    scope: Option<usize>,
+    inlined_at: Option<usize>,
+    implicit: bool,
+) -> Md {
+    let mut fields = vec![
+        (Some("line"), RawField::Known(Field::Int(line as i128))),
+        (Some("column"), RawField::Known(Field::Int(column as i128))),
+        (Some("scope"), RawField::Ref(scope, false)),
+        (Some("inlinedAt"), RawField::Ref(inlined_at, true)),
+    ];
+    if column == 0 {
+        fields.remove(1);
+    }
+    if implicit {
+        fields.push((Some("isImplicitCode"), RawField::Known(Field::Bool(true))));
+    }
+    Md::Di(DiNode {
+        distinct,
+        kind: SpecializedKind::DILocation,
+        fields,
+        children: vec![scope, inlined_at],
+    })
+}
+
+/// Builds a specialized node from a record, field by field in the order LLVM prints them.
+struct Di<'a> {
+    ids: &'a [usize],
+    ops: &'a [u64],
+    node: DiNode,
+}
+
+impl<'a> Di<'a> {
+    fn new(ids: &'a [usize], r: &'a Record, kind: SpecializedKind) -> Self {
+        Self {
+            ids,
+            ops: &r.ops,
+            node: DiNode {
+                distinct: r.ops.first().is_some_and(|op| op & 1 != 0),
+                kind,
+                fields: vec![],
+                children: vec![],
+            },
+        }
+    }
+
+    fn op(&self, i: usize) -> u64 {
+        self.ops.get(i).copied().unwrap_or(0)
+    }
+
+    /// The node referred to by `ops[i]`, which holds an ID plus one or 0 for null.
+    fn md(&self, i: usize) -> Option<usize> {
+        self.op(i)
+            .checked_sub(1)
+            .and_then(|id| self.ids.get(id as usize).copied())
+    }
+
+    fn push(mut self, key: &'static str, field: RawField) -> Self {
+        self.node.fields.push((Some(key), field));
+        self
+    }
+
+    fn int_value(self, key: &'static str, value: i128, skip_zero: bool) -> Self {
+        if skip_zero && value == 0 {
+            return self;
+        }
+        self.push(key, RawField::Known(Field::Int(value)))
+    }
+
+    fn int(self, key: &'static str, i: usize, skip_zero: bool) -> Self {
+        let value = self.op(i) as i128;
+        self.int_value(key, value, skip_zero)
+    }
+
+    fn bool_value(self, key: &'static str, value: bool, default: Option<bool>) -> Self {
+        if default == Some(value) {
+            return self;
+        }
+        self.push(key, RawField::Known(Field::Bool(value)))
+    }
+
+    fn bool(self, key: &'static str, i: usize, default: Option<bool>) -> Self {
+        let value = self.op(i) != 0;
+        self.bool_value(key, value, default)
+    }
+
+    fn string(self, key: &'static str, i: usize, skip_empty: bool) -> Self {
+        let s = self.md(i);
+        self.push(key, RawField::Str(s, skip_empty))
+    }
+
+    fn node(self, key: &'static str, i: usize, skip_null: bool) -> Self {
+        let md = self.md(i);
+        self.push(key, RawField::Ref(md, skip_null))
+    }
+
+    fn bound(self, key: &'static str, i: usize) -> Self {
+        let md = self.md(i);
+        self.push(key, RawField::Bound(md))
+    }
+
+    /// An enumerator such as `DW_TAG_member`, or its number if it has no name.
+    fn dwarf(self, key: &'static str, value: u64, name: Option<&str>, skip_zero: bool) -> Self {
+        if skip_zero && value == 0 {
+            return self;
+        }
+        match name {
+            Some(name) => self.push(key, RawField::Known(Field::Ident(name.to_string()))),
+            None => self.int_value(key, value as i128, false),
+        }
+    }
+
+    fn tag(self, i: usize) -> Self {
+        let tag = self.op(i);
+        self.dwarf("tag", tag, dwarf::name(dwarf::TAGS, tag), false)
+    }
+
+    fn flags(self, key: &'static str, names: Vec<String>) -> Self {
+        match names.len() {
+            0 => self,
+            1 if !names[0].starts_with("DIFlag") => {
+                let name = names.into_iter().next().unwrap();
+                self.push(key, RawField::Known(Field::Ident(name)))
+            }
+            _ => self.push(key, RawField::Known(Field::Flags(names))),
+        }
+    }
+
+    fn children(mut self, ops: &[usize]) -> Self {
+        self.node.children = ops.iter().map(|i| self.md(*i)).collect();
+        self
+    }
+
+    fn build(self) -> Md {
+        Md::Di(self.node)
+    }
+}
+
+impl<'a> Reader<'a> {
+    /// Reads a METADATA block at the module or function level.
+    pub(super) fn read_metadata(&mut self, b: &Block) -> Result<()> {
+        // Nodes may refer to nodes that come after them, so give every record its ID first.
+        let mut defined = vec![];
+        for r in b.records() {
+            match r.code {
+                mdc::STRINGS => {
+                    let count = r.op(0)? as usize;
+                    let offset = r.op(1)? as usize;
+                    let blob = r.blob.as_deref().unwrap_or_default();
+                    let lengths = read_vbr6s(blob, count)?;
+                    let mut pos = offset;
+                    for len in lengths {
+                        let bytes = blob
+                            .get(pos..)
+                            .and_then(|rest| rest.get(..len as usize))
+                            .ok_or_else(|| BitcodeError::new("invalid metadata string"))?;
+                        pos += len as usize;
+                        let s = String::from_utf8_lossy(bytes).into_owned();
+                        self.md.push_id(Md::String(s));
+                    }
+                }
+                mdc::NAME
+                | mdc::KIND
+                | mdc::NAMED_NODE
+                | mdc::ATTACHMENT
+                | mdc::GLOBAL_DECL_ATTACHMENT
+                | mdc::INDEX_OFFSET
+                | mdc::INDEX => {}
+                _ => defined.push(self.md.push_id(Md::Null)),
+            }
+        }
+
+        let mut defined = defined.into_iter();
+        let mut name = None;
+        for r in b.records() {
+            match r.code {
+                mdc::NAME => name = Some(r.string(0)),
+                mdc::NAMED_NODE => {
+                    let name = name
+                        .take()
+                        .ok_or_else(|| BitcodeError::new("named metadata without a name"))?;
+                    let ops = r
+                        .ops
+                        .iter()
+                        .map(|id| {
+                            self.md.lookup(*id).ok_or_else(|| {
+                                BitcodeError::new(format!("invalid metadata ID {}", id))
+                            })
+                        })
+                        .collect::<Result<_>>()?;
+                    self.md.named.push((name, ops));
+                }
+                mdc::KIND => {
+                    if let Some(id) = r.ops.first() {
+                        self.md.kinds.insert(*id, r.string(1));
+                    }
+                }
+                mdc::GLOBAL_DECL_ATTACHMENT => {
+                    let value = r.op(0)? as usize;
+                    let pairs = self.md.pairs(&r.ops[1..])?;
+                    self.md.decl_attachments.insert(value, pairs);
+                }
+                mdc::STRINGS | mdc::ATTACHMENT | mdc::INDEX_OFFSET | mdc::INDEX => {}
+                _ => {
+                    let idx = defined.next().unwrap();
+                    let md = self.read_metadata_record(r)?;
+                    self.md.arena[idx] = md;
+                    // Debug locations of instructions with the same fields are this node.
+                    if r.code == mdc::LOCATION && r.op(0)? == 0 {
+                        let key = (
+                            r.op(1)?,
+                            r.op(2)?,
+                            self.md.lookup(r.op(3)?),
+                            self.md.node_or_null(r.ops.get(4).copied().unwrap_or(0)),
+                            r.ops.get(5).copied().unwrap_or(0) != 0,
+                        );
+                        self.md.locations.entry(key).or_insert(idx);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_metadata_record(&mut self, r: &Record) -> Result<Md> {
+        Ok(match r.code {
+            mdc::STRING_OLD => Md::String(r.string(0)),
+            mdc::VALUE => {
+                // [type, value]
+                let ty = self.ty(r.op(0)?)?;
+                let idx = r.op(1)? as usize;
+                match self.values.get(idx) {
+                    None | Some(Slot::Local(_)) => Md::LocalValue(ty, idx),
+                    Some(Slot::Asm(_)) => Md::Null,
+                    Some(_) => Md::Value(ty, self.constant(idx)?),
+                }
+            }
+            mdc::NODE | mdc::DISTINCT_NODE => Md::Node {
+                distinct: r.code == mdc::DISTINCT_NODE,
+                ops: r
+                    .ops
+                    .iter()
+                    .map(|op| op.checked_sub(1).and_then(|id| self.md.lookup(id)))
+                    .collect(),
+            },
+            mdc::LOCATION => {
+                // [distinct, line, column, scope, inlined at, is implicit code]
+                let scope = self.md.lookup(r.op(3)?);
+                let inlined_at = self.md.node_or_null(r.ops.get(4).copied().unwrap_or(0));
+                location(
+                    r.op(0)? != 0,
+                    r.op(1)?,
+                    r.op(2)?,
+                    scope,
+                    inlined_at,
+                    r.ops.get(5).copied().unwrap_or(0) != 0,
+                )
+            }
+            mdc::ARG_LIST => {
+                Md::ArgList(r.ops.iter().filter_map(|id| self.md.lookup(*id)).collect())
+            }
+            _ => di_node(&self.md.ids, r)?,
+        })
+    }
+}
+
+/// Reads a specialized node such as `!DISubprogram`.
+fn di_node(ids: &[usize], r: &Record) -> Result<Md> {
+    use SpecializedKind::*;
+    Ok(match r.code {
+        mdc::SUBRANGE => {
+            // [distinct | version << 1, count, lower bound, upper bound, stride]
+            if r.op(0)? >> 1 != 2 {
+                return Ok(Md::Null);
+            }
+            Di::new(ids, r, DISubrange)
+                .bound("count", 1)
+                .bound("lowerBound", 2)
+                .bound("upperBound", 3)
+                .bound("stride", 4)
+                .children(&[1, 2, 3, 4])
+                .build()
+        }
+        mdc::ENUMERATOR => {
+            // [distinct | is unsigned << 1 | is big int << 2, (bit width,) name, value*]
+            let flags = r.op(0)?;
+            let is_unsigned = flags & 2 != 0;
+            let (name, value) = if flags & 4 != 0 {
+                let bits = match r.op(1)? {
+                    bits if (1..=MAX_INT_BITS as u64).contains(&bits) => bits as u32,
+                    bits => return err(format!("invalid enumerator width {}", bits)),
+                };
+                let words: Vec<u64> = r
+                    .ops
+                    .get(3..)
+                    .unwrap_or(&[])
+                    .iter()
+                    .map(|w| decode_signed(*w) as u64)
+                    .collect();
+                (2, ConstantInt::from_words(bits, &words))
+            } else {
+                (2, ConstantInt::from_i64(64, decode_signed(r.op(1)?)))
+            };
+            let value = if is_unsigned {
+                value.zext_value().map(|v| v as i128)
+            } else {
+                value.sext_value().map(|v| v as i128)
+            };
+            let di = Di::new(ids, r, DIEnumerator)
+                .string("name", name, false)
+                .int_value("value", value.unwrap_or(0), false);
+            if is_unsigned {
+                di.bool_value("isUnsigned", true, None).build()
+            } else {
+                di.build()
+            }
+        }
+        mdc::BASIC_TYPE => {
+            // [distinct, tag, name, size, align, encoding, flags]
+            let di = Di::new(ids, r, DIBasicType);
+            let di = if di.op(1) != 0x24 { di.tag(1) } else { di };
+            let encoding = di.op(5);
+            let flags = dwarf::di_flags(di.op(6));
+            di.string("name", 2, true)
+                .int("size", 3, true)
+                .int("align", 4, true)
+                .dwarf(
+                    "encoding",
+                    encoding,
+                    dwarf::name(dwarf::ATES, encoding),
+                    true,
+                )
+                .flags("flags", flags)
+                .build()
+        }
+        mdc::FILE => {
+            // [distinct, filename, directory, checksum kind, checksum, source]
+            let di =
+                Di::new(ids, r, DIFile)
+                    .string("filename", 1, false)
+                    .string("directory", 2, false);
+            let kind = match di.op(3) {
+                1 => Some("CSK_MD5"),
+                2 => Some("CSK_SHA1"),
+                3 => Some("CSK_SHA256"),
+                _ => None,
+            };
+            let di = match kind {
+                Some(kind) => di
+                    .push(
+                        "checksumkind",
+                        RawField::Known(Field::Ident(kind.to_string())),
+                    )
+                    .string("checksum", 4, false),
+                None => di,
+            };
+            let di = if di.op(5) != 0 {
+                di.string("source", 5, true)
+            } else {
+                di
+            };
+            di.build()
+        }
+        mdc::DERIVED_TYPE => {
+            // [distinct, tag, name, file, line, scope, base type, size, align, offset,
+            //  flags, extra data, dwarf address space + 1, annotations]
+            let flags = dwarf::di_flags(r.ops.get(10).copied().unwrap_or(0));
+            let di = Di::new(ids, r, DIDerivedType)
+                .tag(1)
+                .string("name", 2, true)
+                .node("scope", 5, true)
+                .node("file", 3, true)
+                .int("line", 4, true)
+                .node("baseType", 6, false)
+                .int("size", 7, true)
+                .int("align", 8, true)
+                .int("offset", 9, true)
+                .flags("flags", flags)
+                .node("extraData", 11, true);
+            let di = match di.op(12) {
+                0 => di,
+                n => di.int_value("dwarfAddressSpace", n as i128 - 1, false),
+            };
+            di.node("annotations", 13, true)
+                .children(&[3, 5, 2, 6, 11, 13])
+                .build()
+        }
+        mdc::COMPOSITE_TYPE => {
+            // [distinct, tag, name, file, line, scope, base type, size, align, offset,
+            //  flags, elements, runtime language, vtable holder, template parameters,
+            //  identifier, discriminator, data location, associated, allocated, rank,
+            //  annotations]
+            let flags = dwarf::di_flags(r.ops.get(10).copied().unwrap_or(0));
+            let lang = r.ops.get(12).copied().unwrap_or(0);
+            Di::new(ids, r, DICompositeType)
+                .tag(1)
+                .string("name", 2, true)
+                .node("scope", 5, true)
+                .node("file", 3, true)
+                .int("line", 4, true)
+                .node("baseType", 6, true)
+                .int("size", 7, true)
+                .int("align", 8, true)
+                .int("offset", 9, true)
+                .flags("flags", flags)
+                .node("elements", 11, true)
+                .dwarf("runtimeLang", lang, dwarf::name(dwarf::LANGS, lang), true)
+                .node("vtableHolder", 13, true)
+                .node("templateParams", 14, true)
+                .string("identifier", 15, true)
+                .node("discriminator", 16, true)
+                .node("dataLocation", 17, true)
+                .node("associated", 18, true)
+                .node("allocated", 19, true)
+                .bound("rank", 20)
+                .node("annotations", 21, true)
+                .children(&[3, 5, 2, 6, 11, 13, 14, 15, 16, 17, 18, 19, 20, 21])
+                .build()
+        }
+        mdc::SUBROUTINE_TYPE => {
+            // [distinct, flags, types, calling convention]
+            let flags = dwarf::di_flags(r.op(1)?);
+            let cc = r.ops.get(3).copied().unwrap_or(0);
+            Di::new(ids, r, DISubroutineType)
+                .flags("flags", flags)
+                .dwarf("cc", cc, dwarf::name(dwarf::CCS, cc), true)
+                .node("types", 2, false)
+                .children(&[2])
+                .build()
+        }
+        mdc::COMPILE_UNIT => {
+            // [distinct, language, file, producer, is optimized, flags, runtime version,
+            //  split debug filename, emission kind, enums, retained types, subprograms,
+            //  globals, imports, dwo id, macros, split debug inlining,
+            //  debug info for profiling, name table kind, ranges base address, sysroot,
+            //  sdk]
+            let lang = r.op(1)?;
+            let emission = match r.op(8)? {
+                0 => None,
+                1 => Some("FullDebug"),
+                2 => Some("LineTablesOnly"),
+                3 => Some("DebugDirectivesOnly"),
+                _ => None,
+            };
+            let name_table = match r.ops.get(18).copied().unwrap_or(0) {
+                1 => Some("GNU"),
+                2 => Some("None"),
+                3 => Some("Apple"),
+                _ => None,
+            };
+            let di = Di::new(ids, r, DICompileUnit)
+                .dwarf("language", lang, dwarf::name(dwarf::LANGS, lang), false)
+                .node("file", 2, false)
+                .string("producer", 3, true)
+                .bool("isOptimized", 4, None)
+                .string("flags", 5, true)
+                .int("runtimeVersion", 6, false)
+                .string("splitDebugFilename", 7, true);
+            let di = match emission {
+                Some(kind) => di.push(
+                    "emissionKind",
+                    RawField::Known(Field::Ident(kind.to_string())),
+                ),
+                None => di,
+            };
+            let di = di
+                .node("enums", 9, true)
+                .node("retainedTypes", 10, true)
+                .node("globals", 12, true)
+                .node("imports", 13, true)
+                .node("macros", 15, true)
+                .int("dwoId", 14, true)
+                .bool_value(
+                    "splitDebugInlining",
+                    r.ops.get(16).is_none_or(|v| *v != 0),
+                    Some(true),
+                )
+                .bool("debugInfoForProfiling", 17, Some(false));
+            let di = match name_table {
+                Some(kind) => di.push(
+                    "nameTableKind",
+                    RawField::Known(Field::Ident(kind.to_string())),
+                ),
+                None => di,
+            };
+            di.bool("rangesBaseAddress", 19, Some(false))
+                .string("sysroot", 20, true)
+                .string("sdk", 21, true)
+                .children(&[2, 3, 5, 7, 9, 10, 12, 13, 15, 20, 21])
+                .build()
+        }
+        mdc::SUBPROGRAM => {
+            // [distinct | has unit << 1 | has sp flags << 2, scope, name, linkage name,
+            //  file, line, type, scope line, containing type, sp flags, virtual index,
+            //  flags, unit, template parameters, declaration, retained nodes,
+            //  this adjustment, thrown types, annotations]
+            if r.op(0)? & 4 == 0 {
+                return Ok(Md::Null);
+            }
+            let sp_flags = r.op(9)?;
+            let flags = dwarf::di_flags(r.op(11)?);
+            let di = Di::new(ids, r, DISubprogram)
+                .string("name", 2, true)
+                .string("linkageName", 3, true)
+                .node("scope", 1, false)
+                .node("file", 4, true)
+                .int("line", 5, true)
+                .node("type", 6, true)
+                .int("scopeLine", 7, true)
+                .node("containingType", 8, true);
+            let di = if sp_flags & 3 != 0 || r.op(10)? != 0 {
+                di.int("virtualIndex", 10, false)
+            } else {
+                di
+            };
+            let this_adjustment = r.ops.get(16).map_or(0, |v| *v as i64 as i128);
+            di.int_value("thisAdjustment", this_adjustment, true)
+                .flags("flags", flags)
+                .flags("spFlags", dwarf::di_sp_flags(sp_flags))
+                .node("unit", 12, true)
+                .node("templateParams", 13, true)
+                .node("declaration", 14, true)
+                .node("retainedNodes", 15, true)
+                .node("thrownTypes", 17, true)
+                .node("annotations", 18, true)
+                .children(&[4, 1, 2, 3, 6, 12, 14, 15, 8, 13, 17, 18])
+                .build()
+        }
+        mdc::LEXICAL_BLOCK => {
+            // [distinct, scope, file, line, column]
+            Di::new(ids, r, DILexicalBlock)
+                .node("scope", 1, false)
+                .node("file", 2, true)
+                .int("line", 3, true)
+                .int("column", 4, true)
+                .children(&[2, 1])
+                .build()
+        }
+        mdc::LEXICAL_BLOCK_FILE => {
+            // [distinct, scope, file, discriminator]
+            Di::new(ids, r, DILexicalBlockFile)
+                .node("scope", 1, false)
+                .node("file", 2, true)
+                .int("discriminator", 3, false)
+                .children(&[2, 1])
+                .build()
+        }
+        mdc::NAMESPACE => {
+            // [distinct | export symbols << 1, scope, name]
+            let export_symbols = r.op(0)? & 2 != 0;
+            Di::new(ids, r, DINamespace)
+                .string("name", 2, true)
+                .node("scope", 1, false)
+                .bool_value("exportSymbols", export_symbols, Some(false))
+                .children(&[1, 2])
+                .build()
+        }
+        mdc::GLOBAL_VAR => {
+            // [distinct | version << 1, scope, name, linkage name, file, line, type,
+            //  is local, is definition, static data member declaration,
+            //  template parameters, align, annotations]
+            if r.op(0)? >> 1 != 2 {
+                return Ok(Md::Null);
+            }
+            Di::new(ids, r, DIGlobalVariable)
+                .string("name", 2, true)
+                .string("linkageName", 3, true)
+                .node("scope", 1, false)
+                .node("file", 4, true)
+                .int("line", 5, true)
+                .node("type", 6, true)
+                .bool("isLocal", 7, None)
+                .bool("isDefinition", 8, None)
+                .node("declaration", 9, true)
+                .node("templateParams", 10, true)
+                .int("align", 11, true)
+                .node("annotations", 12, true)
+                .children(&[1, 2, 4, 6, 2, 3, 9, 10, 12])
+                .build()
+        }
+        mdc::LOCAL_VAR => {
+            // [distinct | has alignment << 1, scope, name, file, line, type, arg, flags,
+            //  align, annotations]
+            if r.op(0)? & 2 == 0 {
+                return Ok(Md::Null);
+            }
+            let flags = dwarf::di_flags(r.op(7)?);
+            Di::new(ids, r, DILocalVariable)
+                .string("name", 2, true)
+                .int("arg", 6, true)
+                .node("scope", 1, false)
+                .node("file", 3, true)
+                .int("line", 4, true)
+                .node("type", 5, true)
+                .flags("flags", flags)
+                .int("align", 8, true)
+                .node("annotations", 9, true)
+                .children(&[1, 2, 3, 5, 9])
+                .build()
+        }
+        mdc::LABEL => {
+            // [distinct, scope, name, file, line]
+            Di::new(ids, r, DILabel)
+                .node("scope", 1, false)
+                .string("name", 2, true)
+                .node("file", 3, true)
+                .int("line", 4, true)
+                .children(&[1, 2, 3])
+                .build()
+        }
+        mdc::EXPRESSION => {
+            // [distinct | version << 1, element*]
+            if r.op(0)? >> 1 != 3 {
+                return Ok(Md::Null);
+            }
+            let mut node = Di::new(ids, r, DIExpression).node;
+            node.fields = expression(&r.ops[1..]);
+            Md::Di(node)
+        }
+        mdc::GLOBAL_VAR_EXPR => {
+            // [distinct, variable, expression]
+            Di::new(ids, r, DIGlobalVariableExpression)
+                .node("var", 1, false)
+                .node("expr", 2, false)
+                .children(&[1, 2])
+                .build()
+        }
+        mdc::IMPORTED_ENTITY => {
+            // [distinct, tag, scope, entity, line, name, file, elements]
+            Di::new(ids, r, DIImportedEntity)
+                .tag(1)
+                .string("name", 5, true)
+                .node("scope", 2, false)
+                .node("entity", 3, true)
+                .node("file", 6, true)
+                .int("line", 4, true)
+                .node("elements", 7, true)
+                .children(&[2, 3, 5, 6, 7])
+                .build()
+        }
+        // Nodes this reader doesn't know are read as null.
+        _ => Md::Null,
+    })
+}
+
+impl<'a> Reader<'a> {
+    /// Gives every node its `!N` and stores metadata into the module, once all the
+    /// functions have been read.
+    pub(super) fn finish_metadata(&mut self) -> Result<()> {
+        let md = &mut self.md;
+        let mut uses = vec![];
+        for (idx, name) in &self.global_variables {
+            if let Some(attached) = md.decl_attachments.get(idx) {
+                uses.push(MdUse::Attachments(
+                    Attached::GlobalVariable(name.clone()),
+                    attached.clone(),
+                ));
+            }
+        }
+        let named_uses = uses.len();
+        for (func, idx) in &self.functions {
+            match md.function_uses.remove(func) {
+                Some(func_uses) => uses.extend(func_uses),
+                None => {
+                    if let Some(attached) = md.decl_attachments.get(idx) {
+                        uses.push(MdUse::Attachments(
+                            Attached::Function(*func),
+                            attached.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        for u in &mut uses {
+            if let MdUse::Attachments(_, attached) = u {
+                attached.sort_by_key(|(kind, _)| *kind);
+            }
+        }
+
+        let md = &self.md;
+        let mut slots = vec![None; md.arena.len()];
+        let mut next = 0;
+        let number_uses = |uses: &[MdUse], slots: &mut [Option<usize>], next: &mut usize| {
+            for u in uses {
+                match u {
+                    MdUse::Attachments(_, attached) => {
+                        for (_, node) in attached {
+                            md.number(*node, slots, next)
+                        }
+                    }
+                    MdUse::CallArg(_, _, node) => md.number(*node, slots, next),
+                }
+            }
+        };
+        number_uses(&uses[..named_uses], &mut slots, &mut next);
+        for (_, ops) in &md.named {
+            for op in ops {
+                md.number(*op, &mut slots, &mut next);
+            }
+        }
+        number_uses(&uses[named_uses..], &mut slots, &mut next);
+
+        for (idx, slot) in slots.iter().enumerate() {
+            if let Some(slot) = slot {
+                self.module
+                    .metas
+                    .insert(Name::Number(*slot), md.definition(idx, &slots));
+            }
+        }
+        self.module.named_metas = md
+            .named
+            .iter()
+            .map(|(name, ops)| {
+                (
+                    name.clone(),
+                    ops.iter().map(|op| md.operand(*op, &slots)).collect(),
+                )
+            })
+            .collect();
+
+        for u in uses {
+            match u {
+                MdUse::Attachments(target, attached) => {
+                    for (kind, node) in attached {
+                        let kind = match md.kinds.get(&kind) {
+                            Some(kind) => kind.clone(),
+                            None if kind == 0 => "dbg".to_string(),
+                            None => return err(format!("unknown metadata kind {}", kind)),
+                        };
+                        let meta = md.operand(node, &slots);
+                        match &target {
+                            Attached::GlobalVariable(name) => {
+                                if let Some(gv) = self.module.global_variables.get_mut(name) {
+                                    gv.metadata.push((kind, meta))
+                                }
+                            }
+                            Attached::Function(func) => {
+                                self.module.functions[*func].metadata.push((kind, meta))
+                            }
+                            Attached::Instruction(func, inst) => {
+                                self.module.functions[*func]
+                                    .data
+                                    .inst_ref_mut(*inst)
+                                    .metadata
+                                    .insert(kind, meta);
+                            }
+                        }
+                    }
+                }
+                MdUse::CallArg(func, value, node) => {
+                    let meta = md.operand(node, &slots);
+                    self.module.functions[func].data.values[value] = Value::Metadata(meta);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the elements of a `!DIExpression`.
+fn expression(ops: &[u64]) -> Vec<(Option<&'static str>, RawField)> {
+    let mut fields = vec![];
+    let int = |v: u64| (None, RawField::Known(Field::Int(v as i128)));
+    let mut i = 0;
+    while i < ops.len() {
+        let op = ops[i];
+        let (name, num_args) = match dwarf::op(op) {
+            Some(op) => op,
+            None => return ops.iter().map(|v| int(*v)).collect(),
+        };
+        if i + 1 + num_args > ops.len() {
+            return ops.iter().map(|v| int(*v)).collect();
+        }
+        fields.push((None, RawField::Known(Field::Ident(name.to_string()))));
+        if op == 0x1001 {
+            // DW_OP_LLVM_convert, bit size, encoding
+            fields.push(int(ops[i + 1]));
+            fields.push((
+                None,
+                match dwarf::name(dwarf::ATES, ops[i + 2]) {
+                    Some(ate) => RawField::Known(Field::Ident(ate.to_string())),
+                    None => RawField::Known(Field::Int(ops[i + 2] as i128)),
+                },
+            ));
+        } else {
+            fields.extend(ops[i + 1..i + 1 + num_args].iter().map(|v| int(*v)));
+        }
+        i += 1 + num_args;
+    }
+    fields
+}
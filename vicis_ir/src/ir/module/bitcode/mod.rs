@@ -0,0 +1,287 @@
+//! Reads LLVM bitcode (`.bc` files) into a `Module`, which is the same as the text parser
+//! would build from the disassembly, and writes a `Module` back as bitcode.
+
+mod bitstream;
+mod codes;
+mod dwarf;
+mod function;
+mod metadata;
+mod reader;
+mod writer;
+
+use super::Module;
+use std::{error::Error, fmt};
+
+/// An error found while reading or writing bitcode. `offset` is the byte offset in the input if known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitcodeError {
+    pub offset: Option<usize>,
+    pub message: String,
+}
+
+impl BitcodeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            offset: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+impl fmt::Display for BitcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error: {}", self.message)?;
+        if let Some(offset) = self.offset {
+            write!(f, " (at byte {})", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for BitcodeError {}
+
+/// Parses LLVM bitcode.
+pub fn parse(bytes: &[u8]) -> Result<Module, BitcodeError> {
+    reader::read_module(&bitstream::read(bytes)?)
+}
+
+/// Writes `module` as LLVM bitcode.
+pub fn write(module: &Module) -> Result<Vec<u8>, BitcodeError> {
+    writer::write_module(module)
+}
+
+/// Returns true if `bytes` look like LLVM bitcode rather than assembly.
+pub fn is_bitcode(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"BC\xC0\xDE") || bytes.starts_with(&[0xde, 0xc0, 0x17, 0x0b])
+}
+
+macro_rules! generate_test {
+    ($fname:ident, $name:literal) => {
+        #[test]
+        fn $fname() {
+            use std::fs;
+            let bitcode = fs::read(concat!("./examples/bitcode/", $name, ".bc")).unwrap();
+            assert!(is_bitcode(&bitcode));
+            let module = match parse(&bitcode) {
+                Ok(ok) => ok,
+                Err(e) => panic!("{}", e),
+            };
+
+            // The disassembly of the same bitcode must give the same module
+            let source = fs::read_to_string(concat!("./examples/bitcode/", $name, ".ll")).unwrap();
+            let expected = match super::parse_assembly(&source) {
+                Ok(ok) => ok,
+                Err(e) => panic!("{}", e),
+            };
+            assert_eq!(module.to_string(), expected.to_string());
+        }
+    };
+}
+
+generate_test!(read_example_addsubmul, "addsubmul");
+generate_test!(read_example_ary, "ary");
+generate_test!(read_example_atomic, "atomic");
+generate_test!(read_example_bitops, "bitops");
+generate_test!(read_example_br, "br");
+generate_test!(read_example_call, "call");
+generate_test!(read_example_cast, "cast");
+generate_test!(read_example_cgep, "cgep");
+generate_test!(read_example_constexpr, "constexpr");
+generate_test!(read_example_dce, "dce");
+generate_test!(read_example_debug_info, "debug_info");
+generate_test!(read_example_float, "float");
+generate_test!(read_example_fp_arith, "fp_arith");
+generate_test!(read_example_gblvar, "gblvar");
+generate_test!(read_example_global_attrs, "global_attrs");
+generate_test!(read_example_icmp, "icmp");
+generate_test!(read_example_load, "load");
+generate_test!(read_example_loop, "loop");
+generate_test!(read_example_loop2, "loop2");
+generate_test!(read_example_manyargs, "manyargs");
+generate_test!(read_example_metadata, "metadata");
+generate_test!(read_example_node, "node");
+generate_test!(read_example_phi, "phi");
+generate_test!(read_example_phi_loop, "phi_loop");
+generate_test!(read_example_poison, "poison");
+generate_test!(read_example_printf, "printf");
+generate_test!(read_example_puts, "puts");
+generate_test!(read_example_ret42, "ret42");
+generate_test!(read_example_struct, "struct");
+generate_test!(read_example_switch, "switch");
+generate_test!(read_example_vector, "vector");
+generate_test!(read_example_wide_int, "wide_int");
+
+macro_rules! generate_write_test {
+    ($fname:ident, $name:literal) => {
+        #[test]
+        fn $fname() {
+            use std::fs;
+            let source = fs::read_to_string(concat!("./examples/bitcode/", $name, ".ll")).unwrap();
+            let module = match super::parse_assembly(&source) {
+                Ok(ok) => ok,
+                Err(e) => panic!("{}", e),
+            };
+
+            // Reading the written bitcode must give the same module
+            let bitcode = match write(&module) {
+                Ok(ok) => ok,
+                Err(e) => panic!("{}", e),
+            };
+            assert!(is_bitcode(&bitcode));
+            let written = match parse(&bitcode) {
+                Ok(ok) => ok,
+                Err(e) => panic!("{}", e),
+            };
+            assert_eq!(written.to_string(), module.to_string());
+        }
+    };
+}
+
+generate_write_test!(write_example_addsubmul, "addsubmul");
+generate_write_test!(write_example_ary, "ary");
+generate_write_test!(write_example_atomic, "atomic");
+generate_write_test!(write_example_bitops, "bitops");
+generate_write_test!(write_example_br, "br");
+generate_write_test!(write_example_call, "call");
+generate_write_test!(write_example_cast, "cast");
+generate_write_test!(write_example_cgep, "cgep");
+generate_write_test!(write_example_constexpr, "constexpr");
+generate_write_test!(write_example_dce, "dce");
+generate_write_test!(write_example_debug_info, "debug_info");
+generate_write_test!(write_example_float, "float");
+generate_write_test!(write_example_fp_arith, "fp_arith");
+generate_write_test!(write_example_gblvar, "gblvar");
+generate_write_test!(write_example_global_attrs, "global_attrs");
+generate_write_test!(write_example_icmp, "icmp");
+generate_write_test!(write_example_load, "load");
+generate_write_test!(write_example_loop, "loop");
+generate_write_test!(write_example_loop2, "loop2");
+generate_write_test!(write_example_manyargs, "manyargs");
+generate_write_test!(write_example_metadata, "metadata");
+generate_write_test!(write_example_node, "node");
+generate_write_test!(write_example_phi, "phi");
+generate_write_test!(write_example_phi_loop, "phi_loop");
+generate_write_test!(write_example_poison, "poison");
+generate_write_test!(write_example_printf, "printf");
+generate_write_test!(write_example_puts, "puts");
+generate_write_test!(write_example_ret42, "ret42");
+generate_write_test!(write_example_struct, "struct");
+generate_write_test!(write_example_switch, "switch");
+generate_write_test!(write_example_vector, "vector");
+generate_write_test!(write_example_wide_int, "wide_int");
+
+#[test]
+fn write_named_types() {
+    use codes::{block, types as ty};
+    use std::fs;
+
+    // LLVM gives a pending STRUCT_NAME to the next struct, so `%e = type i64` must not
+    // have one
+    let source = fs::read_to_string("./examples/struct.ll").unwrap();
+    let module = super::parse_assembly(&source).unwrap();
+    let blocks = bitstream::read(&write(&module).unwrap()).unwrap();
+    let types = blocks
+        .iter()
+        .flat_map(|b| b.blocks(block::TYPE))
+        .next()
+        .unwrap();
+    let records = types.records().collect::<Vec<_>>();
+    let names = records
+        .windows(2)
+        .filter(|w| w[0].code == ty::STRUCT_NAME)
+        .map(|w| {
+            assert!(matches!(w[1].code, ty::STRUCT_NAMED | ty::OPAQUE));
+            w[0].string(0)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["d", "a", "b", "c", "あいうえお"]);
+}
+
+#[test]
+fn read_debug_info() {
+    use super::metadata::DebugLocation;
+    use std::fs;
+
+    let module = parse(&fs::read("./examples/bitcode/debug_info.bc").unwrap()).unwrap();
+    assert_eq!(module.module_flags()[1].key, "Debug Info Version");
+
+    let func = &module.functions()[module.find_function_by_name("square").unwrap()];
+    let locs = func
+        .layout
+        .block_iter()
+        .flat_map(|block| func.layout.inst_iter(block))
+        .filter_map(|inst| module.debug_location(func.data.inst_ref(inst)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        locs.last(),
+        Some(&DebugLocation {
+            filename: "dbg.c".to_string(),
+            directory: "/home/user/src".to_string(),
+            line: 2,
+            column: 3,
+        })
+    );
+    assert_eq!(locs.len(), 4);
+}
+
+#[test]
+fn read_error() {
+    assert!(!is_bitcode(b"define void @f() {}"));
+    assert!(parse(b"BC\xC0\xDE\x35\x14").is_err());
+    let err = parse(b"not bitcode").unwrap_err();
+    assert!(err.to_string().starts_with("error: "));
+}
+
+#[test]
+fn read_truncated() {
+    use std::fs;
+
+    // Cutting the file anywhere must give an error, or a module if only trailing blocks
+    // are lost, but never a panic.
+    let bitcode = fs::read("./examples/bitcode/debug_info.bc").unwrap();
+    for len in 0..bitcode.len() {
+        let _ = parse(&bitcode[..len]);
+    }
+}
+
+#[test]
+fn read_malformed() {
+    use bitstream::BitWriter;
+    use codes::{abbrev_id, block};
+
+    // An abbreviation with a 40-bit fixed operand
+    let mut w = BitWriter::new();
+    w.enter_block(block::MODULE, 3);
+    w.emit(abbrev_id::DEFINE_ABBREV, 3);
+    w.emit_vbr(1, 5);
+    w.emit(0, 1);
+    w.emit(1, 3);
+    w.emit_vbr(40, 5);
+    w.end_block();
+    let err = parse(&w.into_bytes()).unwrap_err();
+    assert_eq!(err.message, "operand width 40 is too large");
+
+    // A phi operand whose relative value ID is -2^63
+    let mut blocks = bitstream::read(&std::fs::read("./examples/bitcode/phi.bc").unwrap()).unwrap();
+    let phi = blocks
+        .iter_mut()
+        .flat_map(|b| b.entries.iter_mut())
+        .filter_map(|e| match e {
+            bitstream::Entry::Block(b) if b.id == block::FUNCTION => Some(b),
+            _ => None,
+        })
+        .flat_map(|b| b.entries.iter_mut())
+        .find_map(|e| match e {
+            bitstream::Entry::Record(r) if r.code == codes::function::INST_PHI => Some(r),
+            _ => None,
+        })
+        .unwrap();
+    phi.ops[1] = 1;
+    let err = reader::read_module(&blocks).unwrap_err();
+    assert_eq!(err.message, "invalid phi operand");
+}
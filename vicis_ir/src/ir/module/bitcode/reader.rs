@@ -0,0 +1,1398 @@
+use super::{
+    bitstream::{decode_signed, Block, Record},
+    codes::{block, constants as cst, module as modc, paramattr, strtab, sync_scope, types as ty},
+    metadata::MetadataTable,
+    BitcodeError,
+};
+use crate::ir::{
+    function::{
+        instruction::{FCmpCond, ICmpCond, Opcode, SyncScope},
+        param_attrs::ParameterAttribute,
+        Function, FunctionId, Parameter,
+    },
+    module::{
+        attributes::Attribute,
+        comdat::{Comdat, SelectionKind},
+        data_layout,
+        global_alias::GlobalAlias,
+        global_ifunc::GlobalIFunc,
+        global_variable::{GlobalVariable, ThreadLocalMode},
+        linkage::Linkage,
+        name::Name,
+        preemption_specifier::PreemptionSpecifier,
+        unnamed_addr::UnnamedAddr,
+        visibility::Visibility,
+        Module,
+    },
+    types::{Type, TypeId, MAX_INT_BITS},
+    value::{
+        BlockAddress, ConstantArray, ConstantData, ConstantExpr, ConstantFloat, ConstantInt,
+        ConstantStruct, ConstantVector, InlineAsm, Value, ValueId,
+    },
+};
+use rustc_hash::FxHashMap;
+use std::{convert::TryFrom, mem, str};
+
+/// The most parameters an attribute list may refer to, so that a malformed index can't
+/// make the reader allocate without bound
+const MAX_PARAMS: usize = 1 << 16;
+
+pub(super) type Result<T> = std::result::Result<T, BitcodeError>;
+
+pub(super) fn err<T>(message: impl Into<String>) -> Result<T> {
+    Err(BitcodeError::new(message))
+}
+
+/// An entry of the value table. Module-level entries stay for the whole module, and
+/// function-level entries are dropped at the end of each function body.
+pub(super) enum Slot {
+    Global(Name),
+    /// A constant not built yet, since it may refer to values that come after it
+    Pending(Record),
+    /// A constant being built. Seeing this again means the constant refers to itself
+    Resolving,
+    Const(ConstantData),
+    Asm(InlineAsm),
+    Local(ValueId),
+}
+
+/// A group in the PARAMATTR_GROUP block: attributes for one function, return value or
+/// parameter (`index` is `u32::MAX`, 0 or the parameter number plus one respectively).
+struct AttrGroup {
+    index: u64,
+    func: Vec<Attribute>,
+    param: Vec<ParameterAttribute>,
+}
+
+/// The attributes of a function or a call site, built from a PARAMATTR entry.
+#[derive(Clone, Default)]
+pub(super) struct AttrList {
+    pub func: Vec<Attribute>,
+    pub ret: Vec<ParameterAttribute>,
+    pub params: Vec<Vec<ParameterAttribute>>,
+}
+
+impl AttrList {
+    pub fn param(&self, i: usize) -> Vec<ParameterAttribute> {
+        self.params.get(i).cloned().unwrap_or_default()
+    }
+}
+
+/// A function that has a body in the bitcode.
+pub(super) struct Body<'a> {
+    pub func: FunctionId,
+    pub block: &'a Block,
+}
+
+pub(super) struct Reader<'a> {
+    pub module: Module,
+    strtab: &'a [u8],
+    types: Vec<TypeId>,
+    groups: FxHashMap<u64, AttrGroup>,
+    attr_lists: Vec<AttrList>,
+    /// Function attribute sets in the order they get their `#N`
+    attr_sets: Vec<Vec<Attribute>>,
+    pub values: Vec<Slot>,
+    pub value_tys: Vec<TypeId>,
+    sections: Vec<String>,
+    comdats: Vec<Name>,
+    pub sync_scopes: Vec<SyncScope>,
+    pub md: MetadataTable,
+    /// Every function with the index of its value, in the order of the records
+    pub functions: Vec<(FunctionId, usize)>,
+    /// Global variables with the index of their values
+    pub global_variables: Vec<(usize, Name)>,
+    inits: Vec<(Name, usize)>,
+    aliasees: Vec<(Name, usize)>,
+    resolvers: Vec<(Name, usize)>,
+    personalities: Vec<(FunctionId, usize)>,
+    /// True if a `blockaddress` refers to a block by its index, which is replaced with
+    /// the name of the block once function bodies are read
+    has_block_addresses: bool,
+}
+
+pub fn read_module(blocks: &[Block]) -> Result<Module> {
+    let module = blocks
+        .iter()
+        .find(|b| b.id == block::MODULE)
+        .ok_or_else(|| BitcodeError::new("no module block"))?;
+    let strtab = blocks
+        .iter()
+        .filter(|b| b.id == block::STRTAB)
+        .flat_map(|b| b.records())
+        .find(|r| r.code == strtab::BLOB)
+        .and_then(|r| r.blob.as_deref())
+        .unwrap_or_default();
+
+    let mut reader = Reader {
+        module: Module::new(),
+        strtab,
+        types: vec![],
+        groups: FxHashMap::default(),
+        attr_lists: vec![],
+        attr_sets: vec![],
+        values: vec![],
+        value_tys: vec![],
+        sections: vec![],
+        comdats: vec![],
+        sync_scopes: vec![SyncScope::SingleThread, SyncScope::System],
+        md: MetadataTable::default(),
+        functions: vec![],
+        global_variables: vec![],
+        inits: vec![],
+        aliasees: vec![],
+        resolvers: vec![],
+        personalities: vec![],
+        has_block_addresses: false,
+    };
+    let bodies = reader.read_module_block(module)?;
+    reader.resolve_globals()?;
+    for body in bodies {
+        reader.read_function_body(body)?;
+    }
+    if reader.has_block_addresses {
+        reader.name_block_addresses();
+    }
+    reader.finish_metadata()?;
+    for (i, attrs) in mem::take(&mut reader.attr_sets).into_iter().enumerate() {
+        reader.module.attributes.insert(i as u32, attrs);
+    }
+    Ok(reader.module)
+}
+
+impl<'a> Reader<'a> {
+    /// Replaces block indices in `blockaddress` constants with the names of the blocks.
+    fn name_block_addresses(&mut self) {
+        let module = &mut self.module;
+        let blocks: FxHashMap<Name, Vec<Name>> = module
+            .functions
+            .iter()
+            .map(|(_, f)| {
+                let names = f
+                    .layout
+                    .block_iter()
+                    .map(|b| f.data.block_ref(b).name.clone().unwrap_or(Name::Number(0)))
+                    .collect();
+                (Name::Name(f.name.clone()), names)
+            })
+            .collect();
+        let name = |c: &mut ConstantData| name_block_address(c, &blocks);
+        module
+            .global_variables
+            .values_mut()
+            .for_each(|gv| gv.init.iter_mut().for_each(name));
+        module
+            .global_aliases
+            .values_mut()
+            .for_each(|a| name(&mut a.aliasee));
+        module
+            .global_ifuncs
+            .values_mut()
+            .for_each(|i| name(&mut i.resolver));
+        for (_, func) in module.functions.iter_mut() {
+            if let Some((_, personality)) = &mut func.personality {
+                name(personality);
+            }
+            for (_, value) in func.data.values.iter_mut() {
+                if let Value::Constant(c) = value {
+                    name(c);
+                }
+            }
+        }
+    }
+
+    fn read_module_block(&mut self, module: &'a Block) -> Result<Vec<Body<'a>>> {
+        use super::bitstream::Entry;
+
+        let mut bodies = vec![];
+        let mut defined = vec![];
+        for entry in &module.entries {
+            match entry {
+                Entry::Block(b) => match b.id {
+                    block::TYPE => self.read_types(b)?,
+                    block::PARAMATTR_GROUP => self.read_attr_groups(b)?,
+                    block::PARAMATTR => self.read_attr_lists(b)?,
+                    block::CONSTANTS => self.read_constants(b)?,
+                    block::METADATA_KIND => self.md.read_kinds(b),
+                    block::METADATA => self.read_metadata(b)?,
+                    block::SYNC_SCOPE_NAMES => {
+                        self.sync_scopes = b
+                            .records()
+                            .filter(|r| r.code == sync_scope::NAME)
+                            .map(|r| match r.string(0).as_str() {
+                                "" => SyncScope::System,
+                                "singlethread" => SyncScope::SingleThread,
+                                name => SyncScope::Target(name.to_string()),
+                            })
+                            .collect()
+                    }
+                    block::FUNCTION => bodies.push(b),
+                    _ => {}
+                },
+                Entry::Record(r) => {
+                    if let Some(func) = self.read_module_record(r)? {
+                        defined.push(func)
+                    }
+                }
+            }
+        }
+        if bodies.len() != defined.len() {
+            return err(format!(
+                "{} function bodies for {} function definitions",
+                bodies.len(),
+                defined.len()
+            ));
+        }
+        Ok(defined
+            .into_iter()
+            .zip(bodies)
+            .map(|(func, block)| Body { func, block })
+            .collect())
+    }
+
+    /// Reads a record directly in the MODULE block. Returns the function if the record
+    /// defines one with a body.
+    fn read_module_record(&mut self, r: &Record) -> Result<Option<FunctionId>> {
+        match r.code {
+            modc::TRIPLE => self.module.target.triple = r.string(0),
+            modc::DATALAYOUT => {
+                let datalayout = r.string(0);
+                self.module.target.data_layout = data_layout::parse(&datalayout)
+                    .map_err(|_| BitcodeError::new("invalid data layout"))?
+                    .1;
+                self.module.target.datalayout = datalayout;
+            }
+            modc::SOURCE_FILENAME => self.module.source_filename = r.string(0),
+            modc::SECTIONNAME => self.sections.push(r.string(0)),
+            modc::COMDAT => {
+                let name = Name::Name(self.strtab_name(r, 0)?);
+                let kind = match r.op(2)? {
+                    1 => SelectionKind::Any,
+                    2 => SelectionKind::ExactMatch,
+                    3 => SelectionKind::Largest,
+                    4 => SelectionKind::NoDeduplicate,
+                    5 => SelectionKind::SameSize,
+                    k => return err(format!("unknown comdat selection kind {}", k)),
+                };
+                self.module.comdats.insert(
+                    name.clone(),
+                    Comdat {
+                        name: name.clone(),
+                        kind,
+                    },
+                );
+                self.comdats.push(name);
+            }
+            modc::GLOBALVAR => self.read_global_variable(r)?,
+            modc::FUNCTION => return self.read_function_record(r),
+            modc::ALIAS => self.read_alias(r)?,
+            modc::IFUNC => self.read_ifunc(r)?,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn strtab_name(&self, r: &Record, i: usize) -> Result<String> {
+        let (offset, size) = (r.op(i)? as usize, r.op(i + 1)? as usize);
+        if self.strtab.is_empty() {
+            return err("bitcode without a string table is not supported");
+        }
+        self.strtab
+            .get(offset..)
+            .and_then(|rest| rest.get(..size))
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .ok_or_else(|| BitcodeError::new("name out of the string table"))
+    }
+
+    /// Reads an optional partition name at `ops[i]` and `ops[i + 1]`.
+    fn partition(&self, r: &Record, i: usize) -> Result<Option<String>> {
+        match r.ops.get(i + 1) {
+            Some(size) if *size > 0 => self.strtab_name(r, i).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn ty(&self, id: u64) -> Result<TypeId> {
+        self.types
+            .get(id as usize)
+            .copied()
+            .ok_or_else(|| BitcodeError::new(format!("invalid type id {}", id)))
+    }
+
+    fn push_global(&mut self, name: &Name, value_ty: TypeId, addr_space: u32) {
+        let ptr = self
+            .module
+            .types
+            .base_mut()
+            .pointer_in_addr_space(value_ty, addr_space);
+        self.values.push(Slot::Global(name.clone()));
+        self.value_tys.push(ptr);
+    }
+
+    /// Returns the value type of a global, given the type in its record. Old bitcode
+    /// stores the pointer type instead unless `explicit`.
+    fn value_type(&self, ty: TypeId, explicit: bool) -> Result<TypeId> {
+        if explicit {
+            return Ok(ty);
+        }
+        self.module
+            .types
+            .get_element(ty)
+            .ok_or_else(|| BitcodeError::new("global of a non-pointer type"))
+    }
+
+    fn comdat(&self, r: &Record, i: usize) -> Result<Option<Name>> {
+        match r.ops.get(i) {
+            None | Some(0) => Ok(None),
+            Some(n) => self
+                .comdats
+                .get(*n as usize - 1)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| BitcodeError::new("invalid comdat")),
+        }
+    }
+
+    // [strtab offset, strtab size, type, isconst | explicit type << 1 | addrspace << 2,
+    //  initid, linkage, alignment, section, visibility, threadlocal, unnamed_addr,
+    //  externally_initialized, dllstorageclass, comdat, attributes, dso_local,
+    //  partition offset, partition size]
+    fn read_global_variable(&mut self, r: &Record) -> Result<()> {
+        let name = Name::Name(self.strtab_name(r, 0)?);
+        let flags = r.op(3)?;
+        let ty = self.value_type(self.ty(r.op(2)?)?, flags & 2 != 0)?;
+        let addr_space = addr_space(flags >> 2)?;
+        let init = r.op(4)?;
+        let linkage = linkage(r.op(5)?)?;
+        let get = |i: usize| r.ops.get(i).copied().unwrap_or(0);
+        let gv = GlobalVariable {
+            name: name.clone(),
+            linkage: match linkage {
+                Linkage::External if init != 0 => None,
+                linkage => Some(linkage),
+            },
+            preemption_specifier: dso_local(get(15), linkage, visibility(get(8))?)
+                .then_some(PreemptionSpecifier::DsoLocal),
+            visibility: match visibility(get(8))? {
+                Visibility::Default => None,
+                v => Some(v),
+            },
+            thread_local: match get(9) {
+                0 => None,
+                1 => Some(ThreadLocalMode::GeneralDynamic),
+                2 => Some(ThreadLocalMode::LocalDynamic),
+                3 => Some(ThreadLocalMode::InitialExec),
+                4 => Some(ThreadLocalMode::LocalExec),
+                m => return err(format!("unknown thread local mode {}", m)),
+            },
+            unnamed_addr: unnamed_addr(get(10))?,
+            addr_space,
+            externally_initialized: get(11) != 0,
+            is_constant: flags & 1 != 0,
+            ty,
+            init: None,
+            section: match get(7) {
+                0 => None,
+                n => Some(
+                    self.sections
+                        .get(n as usize - 1)
+                        .cloned()
+                        .ok_or_else(|| BitcodeError::new("invalid section"))?,
+                ),
+            },
+            partition: self.partition(r, 16)?,
+            comdat: self.comdat(r, 13)?,
+            align: decode_align(r.op(6)?)?,
+            metadata: vec![],
+        };
+        if init != 0 {
+            self.inits.push((name.clone(), init as usize - 1));
+        }
+        self.global_variables
+            .push((self.values.len(), name.clone()));
+        self.push_global(&name, ty, addr_space);
+        self.module.global_order.push(name.clone());
+        self.module.global_variables.insert(name, gv);
+        Ok(())
+    }
+
+    // [strtab offset, strtab size, type, callingconv, isproto, linkage, paramattr,
+    //  alignment, section, visibility, gc, unnamed_addr, prologuedata, dllstorageclass,
+    //  comdat, prefixdata, personalityfn, dso_local, addrspace]
+    fn read_function_record(&mut self, r: &Record) -> Result<Option<FunctionId>> {
+        let name = self.strtab_name(r, 0)?;
+        let fn_ty = self.ty(r.op(2)?)?;
+        let fn_ty = match &*self.module.types.get(fn_ty) {
+            Type::Pointer(p) => p.inner,
+            _ => fn_ty,
+        };
+        let (result_ty, param_tys, is_var_arg) = match &*self.module.types.get(fn_ty) {
+            Type::Function(f) => (f.ret, f.params.clone(), f.is_var_arg),
+            _ => return err(format!("function @{} of a non-function type", name)),
+        };
+        let is_prototype = r.op(4)? != 0;
+        let attrs = match r.op(6)? {
+            0 => AttrList::default(),
+            n => self.attr_list(n)?,
+        };
+        let get = |i: usize| r.ops.get(i).copied().unwrap_or(0);
+        let params = param_tys
+            .into_iter()
+            .enumerate()
+            .map(|(i, ty)| Parameter {
+                name: Name::Number(i),
+                ty,
+                attrs: attrs.param(i),
+            })
+            .collect();
+        let mut func = Function::new(
+            &name,
+            result_ty,
+            params,
+            is_var_arg,
+            self.module.types.clone(),
+        );
+        func.linkage = linkage(r.op(5)?)?;
+        func.visibility = visibility(get(9))?;
+        func.preemption_specifier = if dso_local(get(17), func.linkage, func.visibility) {
+            PreemptionSpecifier::DsoLocal
+        } else {
+            PreemptionSpecifier::DsoPreemptable
+        };
+        func.unnamed_addr = unnamed_addr(get(11))?;
+        func.ret_attrs = attrs.ret.clone();
+        func.func_attrs = self.attr_set(attrs.func);
+        func.comdat = self.comdat(r, 14)?;
+        let id = self.module.functions.alloc(func);
+        if get(16) != 0 {
+            self.personalities.push((id, get(16) as usize - 1));
+        }
+        self.functions.push((id, self.values.len()));
+        self.push_global(&Name::Name(name), fn_ty, addr_space(get(18))?);
+        Ok(if is_prototype { None } else { Some(id) })
+    }
+
+    // [strtab offset, strtab size, value type, addrspace, aliasee, linkage, visibility,
+    //  dllstorageclass, threadlocal, unnamed_addr, dso_local, partition offset, partition size]
+    fn read_alias(&mut self, r: &Record) -> Result<()> {
+        let name = Name::Name(self.strtab_name(r, 0)?);
+        let ty = self.ty(r.op(2)?)?;
+        let get = |i: usize| r.ops.get(i).copied().unwrap_or(0);
+        let alias = GlobalAlias {
+            name: name.clone(),
+            linkage: match linkage(r.op(5)?)? {
+                Linkage::External => None,
+                l => Some(l),
+            },
+            preemption_specifier: dso_local(get(10), linkage(r.op(5)?)?, visibility(get(6))?)
+                .then_some(PreemptionSpecifier::DsoLocal),
+            visibility: match visibility(get(6))? {
+                Visibility::Default => None,
+                v => Some(v),
+            },
+            unnamed_addr: unnamed_addr(get(9))?,
+            ty,
+            aliasee_ty: ty,
+            aliasee: ConstantData::Undef,
+        };
+        self.aliasees.push((name.clone(), r.op(4)? as usize));
+        self.push_global(&name, ty, addr_space(r.op(3)?)?);
+        self.module.global_order.push(name.clone());
+        self.module.global_aliases.insert(name, alias);
+        Ok(())
+    }
+
+    // [strtab offset, strtab size, value type, addrspace, resolver, linkage, visibility,
+    //  dso_local, partition offset, partition size]
+    fn read_ifunc(&mut self, r: &Record) -> Result<()> {
+        let name = Name::Name(self.strtab_name(r, 0)?);
+        let ty = self.ty(r.op(2)?)?;
+        let get = |i: usize| r.ops.get(i).copied().unwrap_or(0);
+        let ifunc = GlobalIFunc {
+            name: name.clone(),
+            linkage: match linkage(r.op(5)?)? {
+                Linkage::External => None,
+                l => Some(l),
+            },
+            preemption_specifier: dso_local(get(7), linkage(r.op(5)?)?, visibility(get(6))?)
+                .then_some(PreemptionSpecifier::DsoLocal),
+            visibility: match visibility(get(6))? {
+                Visibility::Default => None,
+                v => Some(v),
+            },
+            ty,
+            resolver_ty: ty,
+            resolver: ConstantData::Undef,
+        };
+        self.resolvers.push((name.clone(), r.op(4)? as usize));
+        self.push_global(&name, ty, addr_space(r.op(3)?)?);
+        self.module.global_order.push(name.clone());
+        self.module.global_ifuncs.insert(name, ifunc);
+        Ok(())
+    }
+
+    /// Fills in what globals refer to, now that all module-level values are known.
+    fn resolve_globals(&mut self) -> Result<()> {
+        for (name, init) in mem::take(&mut self.inits) {
+            let init = self.constant(init)?;
+            self.module.global_variables.get_mut(&name).unwrap().init = Some(init);
+        }
+        for (name, aliasee) in mem::take(&mut self.aliasees) {
+            let aliasee_ty = self.value_ty(aliasee)?;
+            let aliasee = self.constant(aliasee)?;
+            let alias = self.module.global_aliases.get_mut(&name).unwrap();
+            alias.aliasee_ty = aliasee_ty;
+            alias.aliasee = aliasee;
+        }
+        for (name, resolver) in mem::take(&mut self.resolvers) {
+            let resolver_ty = self.value_ty(resolver)?;
+            let resolver = self.constant(resolver)?;
+            let ifunc = self.module.global_ifuncs.get_mut(&name).unwrap();
+            ifunc.resolver_ty = resolver_ty;
+            ifunc.resolver = resolver;
+        }
+        for (func, personality) in mem::take(&mut self.personalities) {
+            let ty = self.value_ty(personality)?;
+            let personality = self.constant(personality)?;
+            self.module.functions[func].personality = Some((ty, personality));
+        }
+        Ok(())
+    }
+
+    pub fn value_ty(&self, idx: usize) -> Result<TypeId> {
+        self.value_tys
+            .get(idx)
+            .copied()
+            .ok_or_else(|| BitcodeError::new(format!("invalid value id {}", idx)))
+    }
+
+    // Types
+
+    fn read_types(&mut self, b: &Block) -> Result<()> {
+        let types = self.module.types.clone();
+
+        // Named structs get their types first since any type may refer to them.
+        let mut named = vec![];
+        let mut struct_name = None;
+        let mut num_unnamed = 0;
+        for r in b.records() {
+            match r.code {
+                ty::NUMENTRY => continue,
+                ty::STRUCT_NAME => {
+                    struct_name = Some(r.string(0));
+                    continue;
+                }
+                ty::STRUCT_NAMED | ty::OPAQUE => {
+                    let name = match struct_name.take() {
+                        Some(name) => Name::Name(name),
+                        None => {
+                            num_unnamed += 1;
+                            Name::Number(num_unnamed - 1)
+                        }
+                    };
+                    named.push(Some((types.base_mut().named_type(name.clone()), name)))
+                }
+                _ => named.push(None),
+            }
+        }
+
+        let forward: Vec<Option<TypeId>> = named.iter().map(|n| n.as_ref().map(|n| n.0)).collect();
+        let mut records = b
+            .records()
+            .filter(|r| !matches!(r.code, ty::NUMENTRY | ty::STRUCT_NAME));
+        for named in named {
+            let r = records.next().unwrap();
+            let get = |i: usize, this: &Self| -> Result<TypeId> {
+                match r.op(i)? as usize {
+                    id if id < this.types.len() => Ok(this.types[id]),
+                    id => forward.get(id).copied().flatten().ok_or_else(|| {
+                        BitcodeError::new(format!("type {} is used before defined", id))
+                    }),
+                }
+            };
+            let mut base = types.base_mut();
+            let id = match r.code {
+                ty::VOID => base.void(),
+                ty::HALF => base.half(),
+                ty::FLOAT => base.float(),
+                ty::DOUBLE => base.double(),
+                ty::X86_FP80 => base.x86_fp80(),
+                ty::FP128 => base.fp128(),
+                ty::METADATA => base.metadata(),
+                // Neither labels nor tokens have a value of their own in this IR.
+                ty::LABEL | ty::TOKEN => base.void(),
+                ty::INTEGER => match r.op(0)? {
+                    bits if (1..=MAX_INT_BITS as u64).contains(&bits) => base.int(bits as u32),
+                    bits => return err(format!("invalid integer width {}", bits)),
+                },
+                ty::POINTER => {
+                    let inner = get(0, self)?;
+                    let addr_space = addr_space(r.ops.get(1).copied().unwrap_or(0))?;
+                    base.pointer_in_addr_space(inner, addr_space)
+                }
+                ty::OPAQUE_POINTER => {
+                    let i8 = base.i8();
+                    base.pointer_in_addr_space(i8, addr_space(r.op(0)?)?)
+                }
+                ty::ARRAY => {
+                    let inner = get(1, self)?;
+                    base.array(inner, num_elements(r.op(0)?)?)
+                }
+                ty::VECTOR => {
+                    let inner = get(1, self)?;
+                    base.vector(inner, num_elements(r.op(0)?)?, r.op(2).unwrap_or(0) != 0)
+                }
+                ty::FUNCTION => {
+                    let ret = get(1, self)?;
+                    let params = (2..r.ops.len())
+                        .map(|i| get(i, self))
+                        .collect::<Result<_>>()?;
+                    base.function(ret, params, r.op(0)? != 0)
+                }
+                ty::STRUCT_ANON | ty::STRUCT_NAMED | ty::OPAQUE => {
+                    let elems = (1..r.ops.len())
+                        .map(|i| get(i, self))
+                        .collect::<Result<_>>()?;
+                    let strukt = base.anonymous_struct(elems, r.op(0).unwrap_or(0) != 0);
+                    match named {
+                        Some((id, name)) => {
+                            base.change_to_named_type(strukt, name);
+                            id
+                        }
+                        None => strukt,
+                    }
+                }
+                code => return err(format!("unsupported type code {}", code)),
+            };
+            self.types.push(id);
+        }
+        Ok(())
+    }
+
+    // Attributes
+
+    fn read_attr_groups(&mut self, b: &Block) -> Result<()> {
+        for r in b.records().filter(|r| r.code == paramattr::GRP_ENTRY) {
+            let index = r.op(1)?;
+            let is_func = index == u32::MAX as u64;
+            let mut group = AttrGroup {
+                index,
+                func: vec![],
+                param: vec![],
+            };
+            let mut i = 2;
+            while i < r.ops.len() {
+                let kind = r.ops[i];
+                i += 1;
+                match kind {
+                    0 | 1 | 5 | 6 => {
+                        let attr = r.op(i)?;
+                        i += 1;
+                        // 1 is an integer attribute, and 6 a type attribute with its type.
+                        let val = if kind == 1 || kind == 6 {
+                            i += 1;
+                            Some(r.op(i - 1)?)
+                        } else {
+                            None
+                        };
+                        if is_func {
+                            group.func.extend(func_attr(attr));
+                        } else {
+                            let ty = match (kind, val) {
+                                (6, Some(ty)) => Some(self.ty(ty)?),
+                                _ => None,
+                            };
+                            group.param.extend(param_attr(attr, val, ty));
+                        }
+                    }
+                    3 | 4 => {
+                        let key = string_until_nul(&r.ops, &mut i);
+                        let value = if kind == 4 {
+                            string_until_nul(&r.ops, &mut i)
+                        } else {
+                            "".to_string()
+                        };
+                        if is_func {
+                            group
+                                .func
+                                .push(Attribute::StringAttribute { kind: key, value });
+                        } else {
+                            group
+                                .param
+                                .push(ParameterAttribute::StringAttribute { kind: key, value });
+                        }
+                    }
+                    k => return err(format!("unknown attribute encoding {}", k)),
+                }
+            }
+            self.groups.insert(r.op(0)?, group);
+        }
+        Ok(())
+    }
+
+    fn read_attr_lists(&mut self, b: &Block) -> Result<()> {
+        for r in b.records().filter(|r| r.code == paramattr::ENTRY) {
+            let mut list = AttrList::default();
+            for id in &r.ops {
+                let group = self
+                    .groups
+                    .get(id)
+                    .ok_or_else(|| BitcodeError::new(format!("unknown attribute group {}", id)))?;
+                match group.index {
+                    0 => list.ret.extend(group.param.iter().cloned()),
+                    i if i == u32::MAX as u64 => list.func.extend(group.func.iter().cloned()),
+                    i => {
+                        let i = i as usize - 1;
+                        if i >= MAX_PARAMS {
+                            return err(format!("attribute group for parameter #{}", i));
+                        }
+                        if list.params.len() <= i {
+                            list.params.resize(i + 1, vec![]);
+                        }
+                        list.params[i].extend(group.param.iter().cloned())
+                    }
+                }
+            }
+            self.attr_lists.push(list);
+        }
+        Ok(())
+    }
+
+    /// Returns the attribute list referred to by `id`, which starts from 1.
+    pub fn attr_list(&self, id: u64) -> Result<AttrList> {
+        self.attr_lists
+            .get((id as usize).wrapping_sub(1))
+            .cloned()
+            .ok_or_else(|| BitcodeError::new(format!("unknown attribute list {}", id)))
+    }
+
+    /// Gives `attrs` a number shared by every identical set and returns a reference to it.
+    pub fn attr_set(&mut self, attrs: Vec<Attribute>) -> Vec<Attribute> {
+        if attrs.is_empty() {
+            return vec![];
+        }
+        let n = match self.attr_sets.iter().position(|s| *s == attrs) {
+            Some(n) => n,
+            None => {
+                self.attr_sets.push(attrs);
+                self.attr_sets.len() - 1
+            }
+        };
+        vec![Attribute::Ref(n as u32)]
+    }
+
+    // Constants
+
+    pub fn read_constants(&mut self, b: &Block) -> Result<()> {
+        let mut cur_ty = self.module.types.base().i32();
+        for r in b.records() {
+            if r.code == cst::SETTYPE {
+                cur_ty = self.ty(r.op(0)?)?;
+                continue;
+            }
+            let slot = match r.code {
+                cst::INLINEASM | cst::INLINEASM_OLD3 => {
+                    // [fnty,] flags, asmstrsize, asmstr..., constraintsize, constraints...
+                    let mut i = if r.code == cst::INLINEASM { 1 } else { 0 };
+                    let sideeffect = r.op(i)? & 1 != 0;
+                    i += 1;
+                    let asm = sized_string(&r.ops, &mut i)?;
+                    let constraints = sized_string(&r.ops, &mut i)?;
+                    // The text parser keeps the asm string in `constraints` and the
+                    // constraint string in `body`, so do the same here.
+                    Slot::Asm(InlineAsm {
+                        body: constraints,
+                        constraints: asm,
+                        sideeffect,
+                    })
+                }
+                _ => Slot::Pending(r.clone()),
+            };
+            self.values.push(slot);
+            self.value_tys.push(cur_ty);
+        }
+        Ok(())
+    }
+
+    /// Returns the constant at `idx` in the value table, building it if needed.
+    pub fn constant(&mut self, idx: usize) -> Result<ConstantData> {
+        match self.values.get(idx) {
+            Some(Slot::Const(c)) => return Ok(c.clone()),
+            Some(Slot::Global(name)) => return Ok(ConstantData::GlobalRef(name.clone())),
+            Some(Slot::Pending(_)) => {}
+            Some(Slot::Resolving) => return err("constant refers to itself"),
+            _ => return err(format!("value {} is not a constant", idx)),
+        }
+        let r = match mem::replace(&mut self.values[idx], Slot::Resolving) {
+            Slot::Pending(r) => r,
+            _ => unreachable!(),
+        };
+        let konst = self.build_constant(&r, self.value_tys[idx])?;
+        self.values[idx] = Slot::Const(konst.clone());
+        Ok(konst)
+    }
+
+    fn build_constant(&mut self, r: &Record, ty: TypeId) -> Result<ConstantData> {
+        let types = self.module.types.clone();
+        let int_bits = |ty: TypeId| match &*types.get(ty) {
+            Type::Int(bits) => Ok(*bits),
+            _ => err("integer constant of a non-integer type"),
+        };
+        Ok(match r.code {
+            cst::NULL => null_value(&types.get(ty)),
+            cst::UNDEF => ConstantData::Undef,
+            cst::POISON => ConstantData::Poison,
+            cst::INTEGER => ConstantData::Int(ConstantInt::from_i64(
+                int_bits(ty)?,
+                decode_signed(r.op(0)?),
+            )),
+            cst::WIDE_INTEGER => {
+                let words: Vec<u64> = r.ops.iter().map(|w| decode_signed(*w) as u64).collect();
+                ConstantData::Int(ConstantInt::from_words(int_bits(ty)?, &words))
+            }
+            cst::FLOAT => ConstantData::Float(float(&types.get(ty), &r.ops)?),
+            cst::AGGREGATE => {
+                let elems = r
+                    .ops
+                    .iter()
+                    .map(|v| self.constant(*v as usize))
+                    .collect::<Result<Vec<_>>>()?;
+                match &*types.get(ty) {
+                    Type::Struct(s) => ConstantData::Struct(ConstantStruct {
+                        elems_ty: s.elems.clone(),
+                        elems,
+                        is_packed: s.is_packed,
+                    }),
+                    Type::Array(a) => ConstantData::Array(ConstantArray {
+                        elem_ty: a.inner,
+                        elems,
+                        is_string: false,
+                    }),
+                    Type::Vector(v) => ConstantData::Vector(ConstantVector::new(v.inner, elems)),
+                    _ => return err("aggregate constant of a non-aggregate type"),
+                }
+            }
+            cst::STRING | cst::CSTRING => {
+                let mut bytes: Vec<u8> = r.ops.iter().map(|c| *c as u8).collect();
+                if r.code == cst::CSTRING {
+                    bytes.push(0);
+                }
+                ConstantData::Array(ConstantArray {
+                    elem_ty: types.base().i8(),
+                    is_string: str::from_utf8(&bytes).is_ok(),
+                    elems: bytes
+                        .into_iter()
+                        .map(|c| ConstantData::Int(ConstantInt::from(c as i8)))
+                        .collect(),
+                })
+            }
+            cst::DATA => {
+                let (elem_ty, is_vector) = match &*types.get(ty) {
+                    Type::Array(a) => (a.inner, false),
+                    Type::Vector(v) => (v.inner, true),
+                    _ => return err("data constant of a non-sequential type"),
+                };
+                let elem = types.get(elem_ty).clone();
+                let elems = r
+                    .ops
+                    .iter()
+                    .map(|v| match elem {
+                        Type::Int(bits) => Ok(ConstantData::Int(ConstantInt::new(bits, *v))),
+                        _ => float(&elem, &[*v]).map(ConstantData::Float),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if is_vector {
+                    ConstantData::Vector(ConstantVector::new(elem_ty, elems))
+                } else {
+                    ConstantData::Array(ConstantArray {
+                        elem_ty,
+                        elems,
+                        is_string: false,
+                    })
+                }
+            }
+            cst::CE_BINOP => {
+                // [opcode, lhs, rhs, flags]
+                let lhs_ty = self.value_ty(r.op(1)? as usize)?;
+                let args = Box::new([
+                    self.constant(r.op(1)? as usize)?,
+                    self.constant(r.op(2)? as usize)?,
+                ]);
+                let opcode = binop(r.op(0)?, types.is_float(lhs_ty))?;
+                let flags = r.ops.get(3).copied().unwrap_or(0);
+                match opcode {
+                    Opcode::FAdd | Opcode::FSub | Opcode::FMul | Opcode::FDiv | Opcode::FRem => {
+                        ConstantData::Expr(ConstantExpr::FloatBinary {
+                            opcode,
+                            ty: lhs_ty,
+                            args,
+                        })
+                    }
+                    _ => {
+                        let (nuw, nsw, exact) = int_binop_flags(opcode, flags);
+                        ConstantData::Expr(ConstantExpr::IntBinary {
+                            opcode,
+                            ty: lhs_ty,
+                            nuw,
+                            nsw,
+                            exact,
+                            args,
+                        })
+                    }
+                }
+            }
+            cst::CE_UNOP => {
+                // [opcode, operand]
+                let arg = r.op(1)? as usize;
+                ConstantData::Expr(ConstantExpr::FNeg {
+                    ty: self.value_ty(arg)?,
+                    arg: Box::new(self.constant(arg)?),
+                })
+            }
+            cst::CE_CAST => {
+                // [opcode, opty, operand]
+                ConstantData::Expr(ConstantExpr::Cast {
+                    opcode: cast(r.op(0)?)?,
+                    tys: [self.ty(r.op(1)?)?, ty],
+                    arg: Box::new(self.constant(r.op(2)? as usize)?),
+                })
+            }
+            cst::CE_GEP | cst::CE_INBOUNDS_GEP | cst::CE_GEP_WITH_INRANGE_INDEX => {
+                // [pointee type,] [flags,] (type, value)*
+                let mut i = 0;
+                let src_ty = if r.ops.len() % 2 == 1 || r.code == cst::CE_GEP_WITH_INRANGE_INDEX {
+                    i += 1;
+                    Some(self.ty(r.op(0)?)?)
+                } else {
+                    None
+                };
+                let mut inbounds = r.code == cst::CE_INBOUNDS_GEP;
+                if r.code == cst::CE_GEP_WITH_INRANGE_INDEX {
+                    inbounds = r.op(1)? & 1 != 0;
+                    i += 1;
+                }
+                let mut tys = vec![];
+                let mut args = vec![];
+                while i + 1 < r.ops.len() {
+                    tys.push(self.ty(r.ops[i])?);
+                    args.push(self.constant(r.ops[i + 1] as usize)?);
+                    i += 2;
+                }
+                let src_ty = match src_ty {
+                    Some(ty) => ty,
+                    None => tys
+                        .first()
+                        .and_then(|ty| types.get_element(*ty))
+                        .ok_or_else(|| BitcodeError::new("getelementptr on a non-pointer"))?,
+                };
+                tys.insert(0, src_ty);
+                ConstantData::Expr(ConstantExpr::GetElementPtr {
+                    inbounds,
+                    tys,
+                    args,
+                })
+            }
+            cst::CE_SELECT => {
+                // [cond, true value, false value]
+                ConstantData::Expr(ConstantExpr::Select {
+                    tys: [self.value_ty(r.op(0)? as usize)?, ty],
+                    args: Box::new([
+                        self.constant(r.op(0)? as usize)?,
+                        self.constant(r.op(1)? as usize)?,
+                        self.constant(r.op(2)? as usize)?,
+                    ]),
+                })
+            }
+            cst::CE_EXTRACTELT => {
+                // [vector type, vector, index type, index]
+                ConstantData::Expr(ConstantExpr::ExtractElement {
+                    tys: [self.ty(r.op(0)?)?, self.ty(r.op(2)?)?],
+                    args: Box::new([
+                        self.constant(r.op(1)? as usize)?,
+                        self.constant(r.op(3)? as usize)?,
+                    ]),
+                })
+            }
+            cst::CE_INSERTELT => {
+                // [vector, element, index type, index]
+                let elem = r.op(1)? as usize;
+                ConstantData::Expr(ConstantExpr::InsertElement {
+                    tys: [ty, self.value_ty(elem)?, self.ty(r.op(2)?)?],
+                    args: Box::new([
+                        self.constant(r.op(0)? as usize)?,
+                        self.constant(elem)?,
+                        self.constant(r.op(3)? as usize)?,
+                    ]),
+                })
+            }
+            cst::CE_SHUFFLEVEC => {
+                // [vector, vector, mask]
+                let vec = r.op(0)? as usize;
+                let mask = r.op(2)? as usize;
+                ConstantData::Expr(ConstantExpr::ShuffleVector {
+                    tys: [self.value_ty(vec)?, self.value_ty(mask)?],
+                    args: Box::new([
+                        self.constant(vec)?,
+                        self.constant(r.op(1)? as usize)?,
+                        self.constant(mask)?,
+                    ]),
+                })
+            }
+            cst::CE_CMP => {
+                // [operand type, lhs, rhs, predicate]
+                let op_ty = self.ty(r.op(0)?)?;
+                let args = Box::new([
+                    self.constant(r.op(1)? as usize)?,
+                    self.constant(r.op(2)? as usize)?,
+                ]);
+                match r.op(3)? {
+                    p if p < 16 => ConstantData::Expr(ConstantExpr::FCmp {
+                        cond: fcmp_cond(p)?,
+                        ty: op_ty,
+                        args,
+                    }),
+                    p => ConstantData::Expr(ConstantExpr::ICmp {
+                        cond: icmp_cond(p)?,
+                        ty: op_ty,
+                        args,
+                    }),
+                }
+            }
+            cst::BLOCKADDRESS => {
+                // [function type, function, block index]
+                let func = match self.constant(r.op(1)? as usize)? {
+                    ConstantData::GlobalRef(name) => name,
+                    _ => return err("blockaddress of a non-function"),
+                };
+                self.has_block_addresses = true;
+                ConstantData::BlockAddress(BlockAddress {
+                    func,
+                    block: Name::Number(r.op(2)? as usize),
+                })
+            }
+            code => return err(format!("unsupported constant code {}", code)),
+        })
+    }
+}
+
+/// Reads a string stored as its length followed by one character per operand.
+fn sized_string(ops: &[u64], i: &mut usize) -> Result<String> {
+    let len = *ops
+        .get(*i)
+        .ok_or_else(|| BitcodeError::new("truncated string"))? as usize;
+    let chars = ops
+        .get(*i + 1..)
+        .and_then(|rest| rest.get(..len))
+        .ok_or_else(|| BitcodeError::new("truncated string"))?;
+    *i += 1 + len;
+    Ok(String::from_utf8_lossy(&chars.iter().map(|c| *c as u8).collect::<Vec<_>>()).into_owned())
+}
+
+/// Reads a string ending with a zero.
+fn name_block_address(c: &mut ConstantData, blocks: &FxHashMap<Name, Vec<Name>>) {
+    let recurse = |c: &mut ConstantData| name_block_address(c, blocks);
+    match c {
+        ConstantData::BlockAddress(BlockAddress { func, block }) => {
+            if let (Name::Number(i), Some(names)) = (&*block, blocks.get(func)) {
+                if let Some(name) = names.get(*i) {
+                    *block = name.clone();
+                }
+            }
+        }
+        ConstantData::Array(ConstantArray { elems, .. })
+        | ConstantData::Vector(ConstantVector { elems, .. })
+        | ConstantData::Struct(ConstantStruct { elems, .. }) => elems.iter_mut().for_each(recurse),
+        ConstantData::Expr(expr) => match expr {
+            ConstantExpr::GetElementPtr { args, .. } => args.iter_mut().for_each(recurse),
+            ConstantExpr::Cast { arg, .. }
+            | ConstantExpr::FNeg { arg, .. }
+            | ConstantExpr::ExtractValue { arg, .. } => recurse(arg),
+            ConstantExpr::IntBinary { args, .. }
+            | ConstantExpr::FloatBinary { args, .. }
+            | ConstantExpr::ICmp { args, .. }
+            | ConstantExpr::FCmp { args, .. }
+            | ConstantExpr::ExtractElement { args, .. }
+            | ConstantExpr::InsertValue { args, .. } => args.iter_mut().for_each(recurse),
+            ConstantExpr::Select { args, .. }
+            | ConstantExpr::InsertElement { args, .. }
+            | ConstantExpr::ShuffleVector { args, .. } => args.iter_mut().for_each(recurse),
+        },
+        _ => {}
+    }
+}
+
+fn string_until_nul(ops: &[u64], i: &mut usize) -> String {
+    let mut bytes = vec![];
+    while let Some(c) = ops.get(*i) {
+        *i += 1;
+        if *c == 0 {
+            break;
+        }
+        bytes.push(*c as u8);
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Decodes an alignment stored as its log2 plus one, or 0 if there is none.
+pub(super) fn decode_align(n: u64) -> Result<u32> {
+    match n {
+        0 => Ok(0),
+        1..=32 => Ok(1 << (n - 1)),
+        n => err(format!("invalid alignment exponent {}", n - 1)),
+    }
+}
+
+fn addr_space(n: u64) -> Result<u32> {
+    u32::try_from(n).map_err(|_| BitcodeError::new(format!("invalid address space {}", n)))
+}
+
+fn num_elements(n: u64) -> Result<u32> {
+    u32::try_from(n).map_err(|_| BitcodeError::new(format!("too many elements: {}", n)))
+}
+
+pub(super) fn null_value(ty: &Type) -> ConstantData {
+    match ty {
+        Type::Int(bits) => ConstantData::Int(ConstantInt::zero(*bits)),
+        Type::Half => ConstantData::Float(ConstantFloat::Half(0)),
+        Type::Float => ConstantData::Float(ConstantFloat::Float(0.0)),
+        Type::Double => ConstantData::Float(ConstantFloat::Double(0.0)),
+        Type::X86Fp80 => ConstantData::Float(ConstantFloat::X86Fp80(0)),
+        Type::Fp128 => ConstantData::Float(ConstantFloat::Fp128(0)),
+        Type::Pointer(_) => ConstantData::Null,
+        _ => ConstantData::AggregateZero,
+    }
+}
+
+fn float(ty: &Type, ops: &[u64]) -> Result<ConstantFloat> {
+    let op = |i: usize| {
+        ops.get(i)
+            .copied()
+            .ok_or_else(|| BitcodeError::new("truncated float constant"))
+    };
+    Ok(match ty {
+        Type::Half => ConstantFloat::Half(op(0)? as u16),
+        Type::Float => ConstantFloat::Float(f32::from_bits(op(0)? as u32)),
+        Type::Double => ConstantFloat::Double(f64::from_bits(op(0)?)),
+        Type::X86Fp80 => {
+            // The 16-bit exponent comes last in the first word.
+            let (hi, lo) = (op(0)?, op(1)?);
+            let low_word = (hi << 16) | (lo & 0xffff);
+            ConstantFloat::X86Fp80(low_word as u128 | ((hi >> 48) as u128) << 64)
+        }
+        Type::Fp128 => ConstantFloat::Fp128(op(0)? as u128 | (op(1)? as u128) << 64),
+        _ => return err("float constant of a non-float type"),
+    })
+}
+
+pub(super) fn binop(code: u64, is_float: bool) -> Result<Opcode> {
+    Ok(match (code, is_float) {
+        (0, false) => Opcode::Add,
+        (0, true) => Opcode::FAdd,
+        (1, false) => Opcode::Sub,
+        (1, true) => Opcode::FSub,
+        (2, false) => Opcode::Mul,
+        (2, true) => Opcode::FMul,
+        (3, false) => Opcode::UDiv,
+        (4, false) => Opcode::SDiv,
+        (4, true) => Opcode::FDiv,
+        (5, false) => Opcode::URem,
+        (6, false) => Opcode::SRem,
+        (6, true) => Opcode::FRem,
+        (7, false) => Opcode::Shl,
+        (8, false) => Opcode::LShr,
+        (9, false) => Opcode::AShr,
+        (10, false) => Opcode::And,
+        (11, false) => Opcode::Or,
+        (12, false) => Opcode::Xor,
+        _ => return err(format!("unknown binary opcode {}", code)),
+    })
+}
+
+/// Decodes the optional flags of an integer binary operation into `(nuw, nsw, exact)`.
+pub(super) fn int_binop_flags(opcode: Opcode, flags: u64) -> (bool, bool, bool) {
+    match opcode {
+        Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Shl => {
+            (flags & 1 != 0, flags & 2 != 0, false)
+        }
+        Opcode::UDiv | Opcode::SDiv | Opcode::LShr | Opcode::AShr => (false, false, flags & 1 != 0),
+        _ => (false, false, false),
+    }
+}
+
+pub(super) fn cast(code: u64) -> Result<Opcode> {
+    Ok(match code {
+        0 => Opcode::Trunc,
+        1 => Opcode::Zext,
+        2 => Opcode::Sext,
+        3 => Opcode::FPToUI,
+        4 => Opcode::FPToSI,
+        5 => Opcode::UIToFP,
+        6 => Opcode::SIToFP,
+        7 => Opcode::FPTrunc,
+        8 => Opcode::FPExt,
+        9 => Opcode::PtrToInt,
+        10 => Opcode::IntToPtr,
+        11 => Opcode::Bitcast,
+        12 => Opcode::AddrSpaceCast,
+        _ => return err(format!("unknown cast opcode {}", code)),
+    })
+}
+
+pub(super) fn fcmp_cond(code: u64) -> Result<FCmpCond> {
+    Ok(match code {
+        0 => FCmpCond::False,
+        1 => FCmpCond::Oeq,
+        2 => FCmpCond::Ogt,
+        3 => FCmpCond::Oge,
+        4 => FCmpCond::Olt,
+        5 => FCmpCond::Ole,
+        6 => FCmpCond::One,
+        7 => FCmpCond::Ord,
+        8 => FCmpCond::Uno,
+        9 => FCmpCond::Ueq,
+        10 => FCmpCond::Ugt,
+        11 => FCmpCond::Uge,
+        12 => FCmpCond::Ult,
+        13 => FCmpCond::Ule,
+        14 => FCmpCond::Une,
+        15 => FCmpCond::True,
+        _ => return err(format!("unknown fcmp predicate {}", code)),
+    })
+}
+
+pub(super) fn icmp_cond(code: u64) -> Result<ICmpCond> {
+    Ok(match code {
+        32 => ICmpCond::Eq,
+        33 => ICmpCond::Ne,
+        34 => ICmpCond::Ugt,
+        35 => ICmpCond::Uge,
+        36 => ICmpCond::Ult,
+        37 => ICmpCond::Ule,
+        38 => ICmpCond::Sgt,
+        39 => ICmpCond::Sge,
+        40 => ICmpCond::Slt,
+        41 => ICmpCond::Sle,
+        _ => return err(format!("unknown icmp predicate {}", code)),
+    })
+}
+
+fn linkage(code: u64) -> Result<Linkage> {
+    Ok(match code {
+        0 | 5 | 6 | 15 => Linkage::External,
+        2 => Linkage::Appending,
+        3 => Linkage::Internal,
+        7 => Linkage::ExternalWeak,
+        8 => Linkage::Common,
+        9 | 13 | 14 => Linkage::Private,
+        12 => Linkage::AvailableExternally,
+        1 | 16 => Linkage::WeakAny,
+        4 | 18 => Linkage::LinkOnceAny,
+        10 | 17 => Linkage::WeakODR,
+        11 | 19 => Linkage::LinkOnceODR,
+        _ => return err(format!("unknown linkage {}", code)),
+    })
+}
+
+/// Returns true if `dso_local` should be written for a global. It is implied by local
+/// linkage and by non-default visibility, so LLVM doesn't print it then.
+fn dso_local(flag: u64, linkage: Linkage, visibility: Visibility) -> bool {
+    let implicit = matches!(linkage, Linkage::Private | Linkage::Internal)
+        || (visibility != Visibility::Default && linkage != Linkage::ExternalWeak);
+    flag != 0 && !implicit
+}
+
+fn visibility(code: u64) -> Result<Visibility> {
+    Ok(match code {
+        0 => Visibility::Default,
+        1 => Visibility::Hidden,
+        2 => Visibility::Protected,
+        _ => return err(format!("unknown visibility {}", code)),
+    })
+}
+
+fn unnamed_addr(code: u64) -> Result<Option<UnnamedAddr>> {
+    Ok(match code {
+        0 => None,
+        1 => Some(UnnamedAddr::Global),
+        2 => Some(UnnamedAddr::Local),
+        _ => return err(format!("unknown unnamed_addr {}", code)),
+    })
+}
+
+/// Function attributes this IR has no way to represent are dropped.
+fn func_attr(kind: u64) -> Option<Attribute> {
+    Some(match kind {
+        2 => Attribute::AlwaysInline,
+        4 => Attribute::InlineHint,
+        6 => Attribute::MinimizeSize,
+        7 => Attribute::Naked,
+        10 => Attribute::NoBuiltin,
+        12 => Attribute::NoDuplicate,
+        13 => Attribute::NoImplicitFloat,
+        14 => Attribute::NoInline,
+        15 => Attribute::NonLazyBind,
+        16 => Attribute::NoRedZone,
+        17 => Attribute::NoReturn,
+        18 => Attribute::NoUnwind,
+        19 => Attribute::OptSize,
+        20 => Attribute::ReadNone,
+        21 => Attribute::ReadOnly,
+        23 => Attribute::ReturnsTwice,
+        26 => Attribute::StackProtect,
+        27 => Attribute::StackProtectReq,
+        28 => Attribute::StackProtectStrong,
+        30 => Attribute::SanitizeAddress,
+        31 => Attribute::SanitizeThread,
+        32 => Attribute::SanitizeMemory,
+        33 => Attribute::UWTable,
+        35 => Attribute::Builtin,
+        36 => Attribute::Cold,
+        37 => Attribute::OptNone,
+        40 => Attribute::JumpTable,
+        43 => Attribute::Convergent,
+        44 => Attribute::SafeStack,
+        45 => Attribute::ArgMemOnly,
+        48 => Attribute::NoRecurse,
+        49 => Attribute::InaccessibleMemOnly,
+        50 => Attribute::InaccessibleMemOrArgMemOnly,
+        52 => Attribute::WriteOnly,
+        53 => Attribute::Speculatable,
+        54 => Attribute::StrictFP,
+        55 => Attribute::SanitizeHWAddress,
+        56 => Attribute::NoCFCheck,
+        57 => Attribute::OptForFuzzing,
+        58 => Attribute::ShadowCallStack,
+        59 => Attribute::SpeculativeLoadHardening,
+        61 => Attribute::WillReturn,
+        62 => Attribute::NoFree,
+        63 => Attribute::NoSync,
+        64 => Attribute::SanitizeMemTag,
+        _ => return None,
+    })
+}
+
+/// Parameter attributes this IR has no way to represent are dropped.
+fn param_attr(kind: u64, val: Option<u64>, ty: Option<TypeId>) -> Option<ParameterAttribute> {
+    Some(match (kind, val) {
+        (1, Some(align)) => ParameterAttribute::Alignment(align),
+        (3, _) => ParameterAttribute::ByVal,
+        (5, _) => ParameterAttribute::InReg,
+        (8, _) => ParameterAttribute::Nest,
+        (9, _) => ParameterAttribute::NoAlias,
+        (11, _) => ParameterAttribute::NoCapture,
+        (21, _) => ParameterAttribute::ReadOnly,
+        (22, _) => ParameterAttribute::Returned,
+        (24, _) => ParameterAttribute::SignExt,
+        (29, _) => ParameterAttribute::SRet(ty),
+        (34, _) => ParameterAttribute::ZeroExt,
+        (38, _) => ParameterAttribute::InAlloca,
+        (39, _) => ParameterAttribute::NonNull,
+        (41, Some(n)) => ParameterAttribute::Dereferenceable(n),
+        (42, Some(n)) => ParameterAttribute::DereferenceableOrNull(n),
+        (46, _) => ParameterAttribute::SwiftSelf,
+        (47, _) => ParameterAttribute::SwiftError,
+        (60, _) => ParameterAttribute::ImmArg,
+        (62, _) => ParameterAttribute::NoFree,
+        _ => return None,
+    })
+}
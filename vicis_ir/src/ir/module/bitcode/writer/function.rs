@@ -0,0 +1,617 @@
+use super::{
+    super::{
+        bitstream::BitWriter,
+        codes::{block, function as fc, metadata as mdc, value_symtab},
+        reader::{err, Result},
+        BitcodeError,
+    },
+    binop, binop_flags, cast, chars, encode_align, fcmp_cond, icmp_cond, Ids, MdRef, Op, Rec,
+    ValueRef, Writer,
+};
+use crate::ir::{
+    function::{
+        basic_block::BasicBlockId,
+        instruction::{
+            AtomicOrdering, AtomicRmwBinOp, Call, FastMathFlags, Instruction, InstructionId,
+            Invoke, Operand,
+        },
+        Function,
+    },
+    module::{
+        metadata::{Field, Metadata, SpecializedKind},
+        name::Name,
+    },
+    types::{Type, TypeId},
+    value::{ConstantData, Value, ValueId},
+};
+use rustc_hash::FxHashMap;
+
+/// The records of a function body.
+#[derive(Default)]
+pub(super) struct Body {
+    num_blocks: u64,
+    /// Function-level metadata: values of the function passed as metadata and lists of them
+    metadata: Vec<Rec>,
+    /// Instruction and debug location records with the number of values before them
+    insts: Vec<(usize, Rec)>,
+    symtab: Vec<Rec>,
+    attachments: Vec<Rec>,
+}
+
+impl Body {
+    pub fn records(&self) -> impl Iterator<Item = &Rec> {
+        self.metadata
+            .iter()
+            .chain(self.insts.iter().map(|(_, rec)| rec))
+    }
+
+    pub fn emit(&self, w: &mut BitWriter, ids: &Ids) {
+        w.enter_block(block::FUNCTION, 4);
+        w.record(&ids.resolve(
+            &Rec::new(fc::DECLAREBLOCKS, vec![Op::Lit(self.num_blocks)]),
+            0,
+        ));
+        if !self.metadata.is_empty() {
+            w.enter_block(block::METADATA, 3);
+            for rec in &self.metadata {
+                w.record(&ids.resolve(rec, 0));
+            }
+            w.end_block();
+        }
+        for (num_values, rec) in &self.insts {
+            w.record(&ids.resolve(rec, ids.num_module_values + *num_values as u64));
+        }
+        if !self.symtab.is_empty() {
+            w.enter_block(block::VALUE_SYMTAB, 4);
+            for rec in &self.symtab {
+                w.record(&ids.resolve(rec, 0));
+            }
+            w.end_block();
+        }
+        if !self.attachments.is_empty() {
+            w.enter_block(block::METADATA_ATTACHMENT, 3);
+            for rec in &self.attachments {
+                w.record(&ids.resolve(rec, 0));
+            }
+            w.end_block();
+        }
+        w.end_block();
+    }
+}
+
+/// The function whose body is being built.
+struct State<'f> {
+    func: &'f Function,
+    /// Numbers of arguments and instructions with a result. Arguments come first
+    locals: FxHashMap<InstructionId, usize>,
+    blocks: FxHashMap<BasicBlockId, u64>,
+    body: Body,
+}
+
+impl<'f> State<'f> {
+    fn block(&self, block: BasicBlockId) -> Result<Op> {
+        match self.blocks.get(&block) {
+            Some(i) => Ok(Op::Lit(*i)),
+            None => err("branch to a block not in the function"),
+        }
+    }
+}
+
+impl<'a> Writer<'a> {
+    pub(super) fn function_body(&mut self, func: &'a Function) -> Result<Body> {
+        let mut s = State {
+            func,
+            locals: FxHashMap::default(),
+            blocks: FxHashMap::default(),
+            body: Body::default(),
+        };
+        let mut num_values = func.params.len();
+        for (i, block) in func.layout.block_iter().enumerate() {
+            s.blocks.insert(block, i as u64);
+            for id in func.layout.inst_iter(block) {
+                if self.has_result(func.data.inst_ref(id)) {
+                    s.locals.insert(id, num_values);
+                    num_values += 1;
+                }
+            }
+        }
+        s.body.num_blocks = s.blocks.len() as u64;
+
+        let attached =
+            self.attachments(func.metadata.iter().map(|(kind, meta)| (kind, meta)), true)?;
+        if !attached.is_empty() {
+            s.body.attachments.push(Rec::new(mdc::ATTACHMENT, attached));
+        }
+
+        let mut num_values = func.params.len();
+        let mut idx = 0;
+        for block in func.layout.block_iter() {
+            for id in func.layout.inst_iter(block) {
+                let inst = func.data.inst_ref(id);
+                let rec = self.instruction(&mut s, inst)?;
+                s.body.insts.push((num_values, rec));
+                if s.locals.contains_key(&id) {
+                    num_values += 1;
+                }
+                if let Some(loc) = inst.metadata.get("dbg") {
+                    let rec = self.debug_loc(loc)?;
+                    s.body.insts.push((num_values, rec));
+                }
+                let attached = self.attachments(&inst.metadata, false)?;
+                if !attached.is_empty() {
+                    let mut ops = vec![Op::Lit(idx)];
+                    ops.extend(attached);
+                    s.body.attachments.push(Rec::new(mdc::ATTACHMENT, ops));
+                }
+                idx += 1;
+            }
+        }
+
+        for (i, param) in func.params.iter().enumerate() {
+            if let Name::Name(name) = &param.name {
+                let mut ops = vec![Op::Value(ValueRef::Local(i))];
+                ops.extend(chars(name));
+                s.body.symtab.push(Rec::new(value_symtab::ENTRY, ops));
+            }
+        }
+        for block in func.layout.block_iter() {
+            if let Some(Name::Name(name)) = &func.data.block_ref(block).name {
+                let mut ops = vec![s.block(block)?];
+                ops.extend(chars(name));
+                s.body.symtab.push(Rec::new(value_symtab::BBENTRY, ops));
+            }
+            for id in func.layout.inst_iter(block) {
+                let inst = func.data.inst_ref(id);
+                if let (Some(Name::Name(name)), Some(n)) = (&inst.dest, s.locals.get(&id)) {
+                    let mut ops = vec![Op::Value(ValueRef::Local(*n))];
+                    ops.extend(chars(name));
+                    s.body.symtab.push(Rec::new(value_symtab::ENTRY, ops));
+                }
+            }
+        }
+        Ok(s.body)
+    }
+
+    fn has_result(&self, inst: &Instruction) -> bool {
+        match &inst.operand {
+            Operand::Store(_)
+            | Operand::Fence(_)
+            | Operand::Br(_)
+            | Operand::CondBr(_)
+            | Operand::Switch(_)
+            | Operand::Ret(_)
+            | Operand::Resume(_)
+            | Operand::Unreachable
+            | Operand::Invalid => false,
+            Operand::Call(Call { tys, .. }) | Operand::Invoke(Invoke { tys, .. }) => {
+                self.fn_type(tys).0 != self.types.base().void()
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the return type and the type of the callee of a call with `tys`, whose first
+    /// element is either of them.
+    fn fn_type(&self, tys: &[TypeId]) -> (TypeId, TypeId) {
+        if let Type::Function(f) = &*self.types.get(tys[0]) {
+            return (f.ret, tys[0]);
+        }
+        let fn_ty = self
+            .types
+            .base_mut()
+            .function(tys[0], tys[1..].to_vec(), false);
+        (tys[0], fn_ty)
+    }
+
+    fn value(&mut self, s: &State, v: ValueId, ty: TypeId) -> Result<ValueRef> {
+        match s.func.data.value_ref(v) {
+            Value::Instruction(id) => match s.locals.get(id) {
+                Some(n) => Ok(ValueRef::Local(*n)),
+                None => err("use of an instruction without a result"),
+            },
+            Value::Argument(i) => Ok(ValueRef::Local(*i)),
+            Value::Constant(konst) => self.constant(ty, konst),
+            Value::InlineAsm(_) => err("inline assembly used as a value"),
+            Value::Metadata(_) => err("metadata used as a value"),
+        }
+    }
+
+    fn rel(&mut self, s: &State, v: ValueId, ty: TypeId) -> Result<Op> {
+        Ok(Op::Rel(self.value(s, v, ty)?))
+    }
+
+    fn rel_ty(&mut self, s: &State, v: ValueId, ty: TypeId) -> Result<Op> {
+        Ok(Op::RelTy(self.value(s, v, ty)?, ty))
+    }
+
+    fn instruction(&mut self, s: &mut State, inst: &Instruction) -> Result<Rec> {
+        let i1 = self.types.base().i1();
+        let (code, ops) = match &inst.operand {
+            Operand::Alloca(a) => {
+                let num_elements = self.constant(a.tys[1], &a.num_elements)?;
+                let align = encode_align(a.align);
+                (
+                    fc::INST_ALLOCA,
+                    vec![
+                        Op::Ty(a.tys[0]),
+                        Op::Ty(a.tys[1]),
+                        Op::Value(num_elements),
+                        // The low bits of the alignment, explicit type, the high bits
+                        Op::Lit((align & 0x1f) | 1 << 6 | (align >> 5) << 8),
+                    ],
+                )
+            }
+            Operand::Phi(p) => {
+                let mut ops = vec![Op::Ty(p.ty)];
+                for (arg, block) in p.args.iter().zip(&p.blocks) {
+                    ops.push(Op::Signed(self.value(s, *arg, p.ty)?));
+                    ops.push(s.block(*block)?);
+                }
+                (fc::INST_PHI, ops)
+            }
+            Operand::Load(l) => {
+                let mut ops = vec![
+                    self.rel_ty(s, l.addr, l.tys[1])?,
+                    Op::Ty(l.tys[0]),
+                    Op::Lit(encode_align(l.align)),
+                    Op::Lit(l.volatile as u64),
+                ];
+                if l.ordering == AtomicOrdering::NotAtomic {
+                    (fc::INST_LOAD, ops)
+                } else {
+                    ops.push(Op::Lit(ordering(l.ordering)));
+                    ops.push(Op::Lit(self.sync_scope(&l.syncscope)));
+                    (fc::INST_LOADATOMIC, ops)
+                }
+            }
+            Operand::Store(st) => {
+                let mut ops = vec![
+                    self.rel_ty(s, st.args[1], st.tys[1])?,
+                    self.rel_ty(s, st.args[0], st.tys[0])?,
+                    Op::Lit(encode_align(st.align)),
+                    Op::Lit(st.volatile as u64),
+                ];
+                if st.ordering == AtomicOrdering::NotAtomic {
+                    (fc::INST_STORE, ops)
+                } else {
+                    ops.push(Op::Lit(ordering(st.ordering)));
+                    ops.push(Op::Lit(self.sync_scope(&st.syncscope)));
+                    (fc::INST_STOREATOMIC, ops)
+                }
+            }
+            Operand::AtomicCmpXchg(c) => (
+                fc::INST_CMPXCHG,
+                vec![
+                    self.rel_ty(s, c.args[0], c.tys[1])?,
+                    self.rel_ty(s, c.args[1], c.tys[2])?,
+                    self.rel(s, c.args[2], c.tys[2])?,
+                    Op::Lit(c.volatile as u64),
+                    Op::Lit(ordering(c.success_ordering)),
+                    Op::Lit(self.sync_scope(&c.syncscope)),
+                    Op::Lit(ordering(c.failure_ordering)),
+                    Op::Lit(c.weak as u64),
+                    Op::Lit(encode_align(c.align)),
+                ],
+            ),
+            Operand::AtomicRmw(r) => (
+                fc::INST_ATOMICRMW,
+                vec![
+                    self.rel_ty(s, r.args[0], r.tys[0])?,
+                    self.rel_ty(s, r.args[1], r.tys[1])?,
+                    Op::Lit(rmw_op(r.op)),
+                    Op::Lit(r.volatile as u64),
+                    Op::Lit(ordering(r.ordering)),
+                    Op::Lit(self.sync_scope(&r.syncscope)),
+                    Op::Lit(encode_align(r.align)),
+                ],
+            ),
+            Operand::Fence(f) => (
+                fc::INST_FENCE,
+                vec![
+                    Op::Lit(ordering(f.ordering)),
+                    Op::Lit(self.sync_scope(&f.syncscope)),
+                ],
+            ),
+            Operand::InsertValue(v) => {
+                let mut ops = vec![
+                    self.rel_ty(s, v.args[0], v.tys[0])?,
+                    self.rel_ty(s, v.args[1], v.tys[1])?,
+                ];
+                for idx in &v.args[2..] {
+                    ops.push(index(s, *idx)?);
+                }
+                (fc::INST_INSERTVAL, ops)
+            }
+            Operand::ExtractValue(v) => {
+                let mut ops = vec![self.rel_ty(s, v.args[0], v.ty)?];
+                for idx in &v.args[1..] {
+                    ops.push(index(s, *idx)?);
+                }
+                (fc::INST_EXTRACTVAL, ops)
+            }
+            Operand::ExtractElement(e) => (
+                fc::INST_EXTRACTELT,
+                vec![
+                    self.rel_ty(s, e.args[0], e.tys[0])?,
+                    self.rel_ty(s, e.args[1], e.tys[1])?,
+                ],
+            ),
+            Operand::InsertElement(e) => (
+                fc::INST_INSERTELT,
+                vec![
+                    self.rel_ty(s, e.args[0], e.tys[0])?,
+                    self.rel(s, e.args[1], e.tys[1])?,
+                    self.rel_ty(s, e.args[2], e.tys[2])?,
+                ],
+            ),
+            Operand::ShuffleVector(v) => (
+                fc::INST_SHUFFLEVEC,
+                vec![
+                    self.rel_ty(s, v.args[0], v.tys[0])?,
+                    self.rel(s, v.args[1], v.tys[0])?,
+                    self.rel_ty(s, v.args[2], v.tys[1])?,
+                ],
+            ),
+            Operand::IntBinary(b) => {
+                let mut ops = vec![
+                    self.rel_ty(s, b.args[0], b.ty)?,
+                    self.rel(s, b.args[1], b.ty)?,
+                    Op::Lit(binop(inst.opcode)?),
+                ];
+                let flags = binop_flags(inst.opcode, b.nuw, b.nsw, b.exact);
+                if flags != 0 {
+                    ops.push(Op::Lit(flags));
+                }
+                (fc::INST_BINOP, ops)
+            }
+            Operand::FloatBinary(b) => {
+                let mut ops = vec![
+                    self.rel_ty(s, b.args[0], b.ty)?,
+                    self.rel(s, b.args[1], b.ty)?,
+                    Op::Lit(binop(inst.opcode)?),
+                ];
+                push_fast_math_flags(&mut ops, &b.fast_math_flags);
+                (fc::INST_BINOP, ops)
+            }
+            Operand::FloatUnary(u) => {
+                let mut ops = vec![self.rel_ty(s, u.arg, u.ty)?, Op::Lit(0)];
+                push_fast_math_flags(&mut ops, &u.fast_math_flags);
+                (fc::INST_UNOP, ops)
+            }
+            Operand::ICmp(c) => (
+                fc::INST_CMP2,
+                vec![
+                    self.rel_ty(s, c.args[0], c.ty)?,
+                    self.rel(s, c.args[1], c.ty)?,
+                    Op::Lit(icmp_cond(c.cond)),
+                ],
+            ),
+            Operand::FCmp(c) => {
+                let mut ops = vec![
+                    self.rel_ty(s, c.args[0], c.ty)?,
+                    self.rel(s, c.args[1], c.ty)?,
+                    Op::Lit(fcmp_cond(c.cond)),
+                ];
+                push_fast_math_flags(&mut ops, &c.fast_math_flags);
+                (fc::INST_CMP2, ops)
+            }
+            Operand::Cast(c) => (
+                fc::INST_CAST,
+                vec![
+                    self.rel_ty(s, c.arg, c.tys[0])?,
+                    Op::Ty(c.tys[1]),
+                    Op::Lit(cast(inst.opcode)?),
+                ],
+            ),
+            Operand::GetElementPtr(g) => {
+                let mut ops = vec![Op::Lit(g.inbounds as u64), Op::Ty(g.tys[0])];
+                for (arg, ty) in g.args.iter().zip(&g.tys[1..]) {
+                    ops.push(self.rel_ty(s, *arg, *ty)?);
+                }
+                (fc::INST_GEP, ops)
+            }
+            Operand::Select(v) => (
+                fc::INST_VSELECT,
+                vec![
+                    self.rel_ty(s, v.args[1], v.tys[1])?,
+                    self.rel(s, v.args[2], v.tys[1])?,
+                    self.rel_ty(s, v.args[0], v.tys[0])?,
+                ],
+            ),
+            Operand::Freeze(f) => (fc::INST_FREEZE, vec![self.rel_ty(s, f.arg, f.ty)?]),
+            Operand::Call(c) => {
+                let attrs = self.attr_list(&c.func_attrs, &c.ret_attrs, &c.param_attrs)?;
+                let (fn_ty, callee) = self.callee_and_args(s, &c.args, &c.tys)?;
+                // [attributes, calling convention | explicit type, function type, callee,
+                //  argument*]
+                let mut ops = vec![Op::Lit(attrs), Op::Lit(1 << 15), Op::Ty(fn_ty)];
+                ops.extend(callee);
+                (fc::INST_CALL, ops)
+            }
+            Operand::Invoke(c) => {
+                let attrs = self.attr_list(&c.func_attrs, &c.ret_attrs, &c.param_attrs)?;
+                let (fn_ty, callee) = self.callee_and_args(s, &c.args, &c.tys)?;
+                // [attributes, calling convention | explicit type, normal block,
+                //  unwind block, function type, callee, argument*]
+                let mut ops = vec![
+                    Op::Lit(attrs),
+                    Op::Lit(1 << 13),
+                    s.block(c.blocks[0])?,
+                    s.block(c.blocks[1])?,
+                    Op::Ty(fn_ty),
+                ];
+                ops.extend(callee);
+                (fc::INST_INVOKE, ops)
+            }
+            // A cleanup without clauses
+            Operand::LandingPad(l) => (
+                fc::INST_LANDINGPAD,
+                vec![Op::Ty(l.ty), Op::Lit(1), Op::Lit(0)],
+            ),
+            Operand::Resume(r) => (fc::INST_RESUME, vec![self.rel_ty(s, r.arg, r.ty)?]),
+            Operand::Br(b) => (fc::INST_BR, vec![s.block(b.block)?]),
+            Operand::CondBr(b) => (
+                fc::INST_BR,
+                vec![
+                    s.block(b.blocks[0])?,
+                    s.block(b.blocks[1])?,
+                    self.rel(s, b.arg, i1)?,
+                ],
+            ),
+            Operand::Switch(sw) => {
+                let ty = sw.tys[0];
+                let mut ops = vec![
+                    Op::Ty(ty),
+                    self.rel(s, sw.args[0], ty)?,
+                    s.block(sw.blocks[0])?,
+                ];
+                for (arg, block) in sw.args[1..].iter().zip(&sw.blocks[1..]) {
+                    ops.push(Op::Value(self.value(s, *arg, ty)?));
+                    ops.push(s.block(*block)?);
+                }
+                (fc::INST_SWITCH, ops)
+            }
+            Operand::Ret(r) => match r.val {
+                Some(val) => (fc::INST_RET, vec![self.rel_ty(s, val, r.ty)?]),
+                None => (fc::INST_RET, vec![]),
+            },
+            Operand::Unreachable => (fc::INST_UNREACHABLE, vec![]),
+            Operand::Invalid => return err("invalid instruction"),
+        };
+        Ok(Rec::new(code, ops))
+    }
+
+    /// Returns the type of the callee, and the callee and the arguments as operands.
+    fn callee_and_args(
+        &mut self,
+        s: &mut State,
+        args: &[ValueId],
+        tys: &[TypeId],
+    ) -> Result<(TypeId, Vec<Op>)> {
+        let (_, fn_ty) = self.fn_type(tys);
+        let num_params = match &*self.types.get(fn_ty) {
+            Type::Function(f) => f.params.len(),
+            _ => unreachable!(),
+        };
+        let ptr = self.types.base_mut().pointer(fn_ty);
+        let callee = match s.func.data.value_ref(args[0]) {
+            Value::InlineAsm(asm) => self.inline_asm(fn_ty, asm),
+            _ => self.value(s, args[0], ptr)?,
+        };
+        let mut ops = vec![Op::RelTy(callee, ptr)];
+        for (i, (arg, ty)) in args[1..].iter().zip(&tys[1..]).enumerate() {
+            if i >= num_params {
+                ops.push(self.rel_ty(s, *arg, *ty)?);
+                continue;
+            }
+            let op = match s.func.data.value_ref(*arg) {
+                Value::Metadata(meta) => Op::RelMd(self.metadata_arg(s, meta)?),
+                _ => self.rel(s, *arg, *ty)?,
+            };
+            ops.push(op);
+        }
+        Ok((fn_ty, ops))
+    }
+
+    /// Returns the entry of a metadata argument, which may refer to values of the function.
+    fn metadata_arg(&mut self, s: &mut State, meta: &Metadata) -> Result<MdRef> {
+        let local = match meta {
+            Metadata::Value(ty, value) => {
+                let value = match &**value {
+                    Value::Instruction(id) => match s.locals.get(id) {
+                        Some(n) => ValueRef::Local(*n),
+                        None => return err("use of an instruction without a result"),
+                    },
+                    Value::Argument(i) => ValueRef::Local(*i),
+                    _ => return self.module_metadata_arg(meta),
+                };
+                Rec::new(mdc::VALUE, vec![Op::Ty(*ty), Op::Value(value)])
+            }
+            Metadata::Specialized(node) if node.kind == SpecializedKind::DIArgList => {
+                let mut ops = vec![];
+                for (_, field) in &node.fields {
+                    match field {
+                        Field::Metadata(meta) => ops.push(Op::Md(self.metadata_arg(s, meta)?)),
+                        _ => return err("invalid element of !DIArgList"),
+                    }
+                }
+                Rec::new(mdc::ARG_LIST, ops)
+            }
+            _ => return self.module_metadata_arg(meta),
+        };
+        s.body.metadata.push(local);
+        Ok(MdRef::Local(s.body.metadata.len() - 1))
+    }
+
+    fn module_metadata_arg(&mut self, meta: &Metadata) -> Result<MdRef> {
+        self.metadata(meta)?
+            .ok_or_else(|| BitcodeError::new("null metadata argument"))
+    }
+}
+
+/// The constant index of `extractvalue` or `insertvalue`.
+fn index(s: &State, v: ValueId) -> Result<Op> {
+    match s.func.data.value_ref(v) {
+        Value::Constant(ConstantData::Int(i)) => Ok(Op::Lit(i.zext_value().unwrap_or(0))),
+        _ => err("aggregate index is not a constant"),
+    }
+}
+
+fn push_fast_math_flags(ops: &mut Vec<Op>, flags: &FastMathFlags) {
+    let FastMathFlags {
+        nnan,
+        ninf,
+        nsz,
+        arcp,
+        contract,
+        afn,
+        reassoc,
+    } = *flags;
+    let bits = if nnan && ninf && nsz && arcp && contract && afn && reassoc {
+        // fast
+        1
+    } else {
+        (nnan as u64) << 1
+            | (ninf as u64) << 2
+            | (nsz as u64) << 3
+            | (arcp as u64) << 4
+            | (contract as u64) << 5
+            | (afn as u64) << 6
+            | (reassoc as u64) << 7
+    };
+    if bits != 0 {
+        ops.push(Op::Lit(bits));
+    }
+}
+
+fn ordering(ordering: AtomicOrdering) -> u64 {
+    match ordering {
+        AtomicOrdering::NotAtomic => 0,
+        AtomicOrdering::Unordered => 1,
+        AtomicOrdering::Monotonic => 2,
+        AtomicOrdering::Acquire => 3,
+        AtomicOrdering::Release => 4,
+        AtomicOrdering::AcqRel => 5,
+        AtomicOrdering::SeqCst => 6,
+    }
+}
+
+fn rmw_op(op: AtomicRmwBinOp) -> u64 {
+    match op {
+        AtomicRmwBinOp::Xchg => 0,
+        AtomicRmwBinOp::Add => 1,
+        AtomicRmwBinOp::Sub => 2,
+        AtomicRmwBinOp::And => 3,
+        AtomicRmwBinOp::Nand => 4,
+        AtomicRmwBinOp::Or => 5,
+        AtomicRmwBinOp::Xor => 6,
+        AtomicRmwBinOp::Max => 7,
+        AtomicRmwBinOp::Min => 8,
+        AtomicRmwBinOp::UMax => 9,
+        AtomicRmwBinOp::UMin => 10,
+        AtomicRmwBinOp::FAdd => 11,
+        AtomicRmwBinOp::FSub => 12,
+        AtomicRmwBinOp::FMax => 13,
+        AtomicRmwBinOp::FMin => 14,
+    }
+}
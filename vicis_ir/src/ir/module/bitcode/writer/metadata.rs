@@ -0,0 +1,668 @@
+use super::{
+    super::{
+        bitstream::{encode_signed, write_vbr6s, BitWriter, Record},
+        codes::{block, function as fc, metadata as mdc},
+        dwarf,
+        reader::{err, Result},
+        BitcodeError,
+    },
+    chars, Ids, MdRef, Op, Rec, ValueRef, Writer,
+};
+use crate::ir::{
+    module::{
+        metadata::{Field, Metadata, SpecializedKind, SpecializedNode},
+        name::Name,
+    },
+    types::TypeId,
+    value::{ConstantData, ConstantInt, Value},
+};
+use rustc_hash::FxHashMap;
+
+const CHECKSUM_KINDS: &[(u64, &str)] = &[(1, "CSK_MD5"), (2, "CSK_SHA1"), (3, "CSK_SHA256")];
+
+const EMISSION_KINDS: &[(u64, &str)] = &[
+    (0, "NoDebug"),
+    (1, "FullDebug"),
+    (2, "LineTablesOnly"),
+    (3, "DebugDirectivesOnly"),
+];
+
+const NAME_TABLE_KINDS: &[(u64, &str)] = &[(0, "Default"), (1, "GNU"), (2, "None"), (3, "Apple")];
+
+/// Module-level metadata: strings, then nodes in the order they were first used.
+#[derive(Default)]
+pub(super) struct MetadataTable {
+    strings: Vec<String>,
+    string_ids: FxHashMap<String, usize>,
+    nodes: Vec<Rec>,
+    /// Entries of `!N` references
+    named: FxHashMap<Name, Option<MdRef>>,
+    values: FxHashMap<(TypeId, ValueRef), usize>,
+    /// Names of attachment kinds. `dbg` is always the first one
+    kinds: Vec<String>,
+    named_metas: Vec<(String, Vec<MdRef>)>,
+    decl_attachments: Vec<Rec>,
+}
+
+impl MetadataTable {
+    pub fn num_strings(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len() + self.nodes.len()
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &Rec> {
+        self.nodes.iter().chain(self.decl_attachments.iter())
+    }
+
+    pub fn kind(&mut self, name: &str) -> u64 {
+        if self.kinds.is_empty() {
+            self.kinds.push("dbg".to_string());
+        }
+        match self.kinds.iter().position(|k| k == name) {
+            Some(i) => i as u64,
+            None => {
+                self.kinds.push(name.to_string());
+                self.kinds.len() as u64 - 1
+            }
+        }
+    }
+
+    fn string(&mut self, s: &str) -> MdRef {
+        if let Some(i) = self.string_ids.get(s) {
+            return MdRef::String(*i);
+        }
+        self.strings.push(s.to_string());
+        self.string_ids
+            .insert(s.to_string(), self.strings.len() - 1);
+        MdRef::String(self.strings.len() - 1)
+    }
+
+    /// Reserves a node so that nodes it refers to, which may refer back to it, can be built.
+    fn reserve(&mut self) -> usize {
+        self.nodes.push(Rec::new(mdc::NODE, vec![]));
+        self.nodes.len() - 1
+    }
+
+    pub fn emit_kinds(&self, w: &mut BitWriter) {
+        if self.kinds.is_empty() {
+            return;
+        }
+        w.enter_block(block::METADATA_KIND, 3);
+        for (i, kind) in self.kinds.iter().enumerate() {
+            let mut ops = vec![i as u64];
+            ops.extend(kind.bytes().map(u64::from));
+            w.record(&Record {
+                code: mdc::KIND,
+                ops,
+                blob: None,
+            });
+        }
+        w.end_block();
+    }
+
+    pub fn emit(&self, w: &mut BitWriter, ids: &Ids) {
+        if self.len() == 0 && self.named_metas.is_empty() && self.decl_attachments.is_empty() {
+            return;
+        }
+        w.enter_block(block::METADATA, 4);
+        if !self.strings.is_empty() {
+            // [count, offset to the characters] with the lengths and the characters as a blob
+            let lengths: Vec<u64> = self.strings.iter().map(|s| s.len() as u64).collect();
+            let mut blob = write_vbr6s(&lengths);
+            let offset = blob.len() as u64;
+            for s in &self.strings {
+                blob.extend_from_slice(s.as_bytes());
+            }
+            w.record(&Record {
+                code: mdc::STRINGS,
+                ops: vec![self.strings.len() as u64, offset],
+                blob: Some(blob),
+            });
+        }
+        for rec in &self.nodes {
+            w.record(&ids.resolve(rec, 0));
+        }
+        for (name, mds) in &self.named_metas {
+            w.record(&ids.resolve(&Rec::new(mdc::NAME, chars(name).collect()), 0));
+            let ops = mds.iter().map(|md| Op::Md(*md)).collect();
+            w.record(&ids.resolve(&Rec::new(mdc::NAMED_NODE, ops), 0));
+        }
+        for rec in &self.decl_attachments {
+            w.record(&ids.resolve(rec, 0));
+        }
+        w.end_block();
+    }
+}
+
+impl<'a> Writer<'a> {
+    /// Returns the entry of `meta`, or `None` for null.
+    pub(super) fn metadata(&mut self, meta: &Metadata) -> Result<Option<MdRef>> {
+        Ok(Some(match meta {
+            Metadata::Null => return Ok(None),
+            Metadata::String(s) => self.md.string(s),
+            Metadata::Name(name) => {
+                if let Some(md) = self.md.named.get(name) {
+                    return Ok(*md);
+                }
+                let def =
+                    self.module.metas.get(name).ok_or_else(|| {
+                        BitcodeError::new(format!("undefined metadata !{}", name))
+                    })?;
+                if !is_node(def) {
+                    let md = self.metadata(def)?;
+                    self.md.named.insert(name.clone(), md);
+                    return Ok(md);
+                }
+                let idx = self.md.reserve();
+                self.md.named.insert(name.clone(), Some(MdRef::Node(idx)));
+                self.md.nodes[idx] = self.node(def, false)?;
+                MdRef::Node(idx)
+            }
+            Metadata::Int(i) => {
+                let ty = self.types.base_mut().int(i.bits());
+                self.md_value(ty, &ConstantData::Int(i.clone()))?
+            }
+            Metadata::Value(ty, value) => match &**value {
+                Value::Constant(konst) => self.md_value(*ty, konst)?,
+                _ => return err("function-local metadata outside of an argument"),
+            },
+            Metadata::Node(_) | Metadata::Distinct(_) | Metadata::Specialized(_) => {
+                let idx = self.md.reserve();
+                self.md.nodes[idx] = self.node(meta, false)?;
+                MdRef::Node(idx)
+            }
+        }))
+    }
+
+    fn md_value(&mut self, ty: TypeId, konst: &ConstantData) -> Result<MdRef> {
+        let value = self.constant(ty, konst)?;
+        if let Some(i) = self.md.values.get(&(ty, value)) {
+            return Ok(MdRef::Node(*i));
+        }
+        let idx = self.md.nodes.len();
+        self.md
+            .nodes
+            .push(Rec::new(mdc::VALUE, vec![Op::Ty(ty), Op::Value(value)]));
+        self.md.values.insert((ty, value), idx);
+        Ok(MdRef::Node(idx))
+    }
+
+    fn node(&mut self, meta: &Metadata, distinct: bool) -> Result<Rec> {
+        match meta {
+            Metadata::Distinct(inner) => self.node(inner, true),
+            Metadata::Node(elems) => {
+                let mut ops = vec![];
+                for elem in elems {
+                    ops.push(Op::OptMd(self.metadata(elem)?));
+                }
+                let code = if distinct {
+                    mdc::DISTINCT_NODE
+                } else {
+                    mdc::NODE
+                };
+                Ok(Rec::new(code, ops))
+            }
+            Metadata::Specialized(node) => self.di_node(node, distinct),
+            _ => err("metadata is not a node"),
+        }
+    }
+
+    /// Returns the `(kind, metadata)*` operands of attachments sorted by kind. `!dbg` of
+    /// instructions is left out as it's written as a debug location.
+    pub(super) fn attachments<'b>(
+        &mut self,
+        attached: impl IntoIterator<Item = (&'b String, &'b Metadata)>,
+        with_dbg: bool,
+    ) -> Result<Vec<Op>> {
+        let mut kinds: Vec<_> = attached
+            .into_iter()
+            .filter(|(kind, _)| with_dbg || kind.as_str() != "dbg")
+            .map(|(kind, meta)| (self.md.kind(kind), meta))
+            .collect();
+        kinds.sort_by_key(|(kind, _)| *kind);
+        let mut ops = vec![];
+        for (kind, meta) in kinds {
+            if let Some(md) = self.metadata(meta)? {
+                ops.push(Op::Lit(kind));
+                ops.push(Op::Md(md));
+            }
+        }
+        Ok(ops)
+    }
+
+    pub(super) fn attach_to_declaration(
+        &mut self,
+        value: ValueRef,
+        attached: &[(String, Metadata)],
+    ) -> Result<()> {
+        let pairs = self.attachments(attached.iter().map(|(kind, meta)| (kind, meta)), true)?;
+        if !pairs.is_empty() {
+            let mut ops = vec![Op::Value(value)];
+            ops.extend(pairs);
+            self.md
+                .decl_attachments
+                .push(Rec::new(mdc::GLOBAL_DECL_ATTACHMENT, ops));
+        }
+        Ok(())
+    }
+
+    pub(super) fn named_metadata(&mut self) -> Result<()> {
+        let module = self.module;
+        for (name, elems) in &module.named_metas {
+            let mut mds = vec![];
+            for elem in elems {
+                mds.push(
+                    self.metadata(elem)?
+                        .ok_or_else(|| BitcodeError::new(format!("null operand of !{}", name)))?,
+                );
+            }
+            self.md.named_metas.push((name.clone(), mds));
+        }
+        Ok(())
+    }
+
+    /// Returns the DEBUG_LOC record of a `!dbg` attachment.
+    pub(super) fn debug_loc(&mut self, meta: &Metadata) -> Result<Rec> {
+        let module = self.module;
+        let loc = match module.resolve_metadata(meta).as_specialized() {
+            Some(node) if node.kind == SpecializedKind::DILocation => node,
+            _ => return err("!dbg of an instruction is not a location"),
+        };
+        let scope = match loc.metadata_field("scope") {
+            Some(scope) => self.metadata(scope)?,
+            None => None,
+        };
+        let inlined_at = match loc.metadata_field("inlinedAt") {
+            Some(inlined_at) => self.metadata(inlined_at)?,
+            None => None,
+        };
+        // [line, column, scope, inlined at, is implicit code]
+        Ok(Rec::new(
+            fc::DEBUG_LOC,
+            vec![
+                field_int(loc, "line")?,
+                field_int(loc, "column")?,
+                Op::OptMd(scope),
+                Op::OptMd(inlined_at),
+                field_int(loc, "isImplicitCode")?,
+            ],
+        ))
+    }
+
+    fn field_md(&mut self, node: &SpecializedNode, key: &str) -> Result<Op> {
+        Ok(Op::OptMd(match node.field(key) {
+            None => None,
+            Some(Field::Metadata(meta)) => self.metadata(meta)?,
+            Some(Field::String(s)) if s.is_empty() => None,
+            Some(Field::String(s)) => Some(self.md.string(s)),
+            Some(_) => return err(format!("invalid field {}", key)),
+        }))
+    }
+
+    /// A subrange bound, which is a constant if it's an integer.
+    fn field_bound(&mut self, node: &SpecializedNode, key: &str) -> Result<Op> {
+        match node.field(key) {
+            Some(Field::Int(v)) => {
+                let i64 = self.types.base().i64();
+                let md = self.md_value(
+                    i64,
+                    &ConstantData::Int(ConstantInt::from_i64(64, *v as i64)),
+                )?;
+                Ok(Op::OptMd(Some(md)))
+            }
+            _ => self.field_md(node, key),
+        }
+    }
+
+    fn di_node(&mut self, node: &SpecializedNode, distinct: bool) -> Result<Rec> {
+        use SpecializedKind::*;
+        let d = distinct as u64;
+        let (code, ops) = match node.kind {
+            DISubrange => (
+                mdc::SUBRANGE,
+                vec![
+                    Op::Lit(d | 2 << 1),
+                    self.field_bound(node, "count")?,
+                    self.field_bound(node, "lowerBound")?,
+                    self.field_bound(node, "upperBound")?,
+                    self.field_bound(node, "stride")?,
+                ],
+            ),
+            DIEnumerator => {
+                let is_unsigned = field_int(node, "isUnsigned")? == Op::Lit(1);
+                let value = match node.field("value") {
+                    Some(Field::Int(v)) => *v as i64,
+                    _ => 0,
+                };
+                (
+                    mdc::ENUMERATOR,
+                    vec![
+                        Op::Lit(d | (is_unsigned as u64) << 1 | 1 << 2),
+                        Op::Lit(64),
+                        self.field_md(node, "name")?,
+                        Op::Lit(encode_signed(value)),
+                    ],
+                )
+            }
+            DIBasicType => (
+                mdc::BASIC_TYPE,
+                vec![
+                    Op::Lit(d),
+                    field_enum(node, "tag", dwarf::TAGS, 0x24)?,
+                    self.field_md(node, "name")?,
+                    field_int(node, "size")?,
+                    field_int(node, "align")?,
+                    field_enum(node, "encoding", dwarf::ATES, 0)?,
+                    field_flags(node, "flags", dwarf::di_flag)?,
+                ],
+            ),
+            DIFile => {
+                let mut ops = vec![
+                    Op::Lit(d),
+                    self.field_md(node, "filename")?,
+                    self.field_md(node, "directory")?,
+                    field_enum(node, "checksumkind", CHECKSUM_KINDS, 0)?,
+                    self.field_md(node, "checksum")?,
+                ];
+                if node.field("source").is_some() {
+                    ops.push(self.field_md(node, "source")?);
+                }
+                (mdc::FILE, ops)
+            }
+            DIDerivedType => (
+                mdc::DERIVED_TYPE,
+                vec![
+                    Op::Lit(d),
+                    field_enum(node, "tag", dwarf::TAGS, 0)?,
+                    self.field_md(node, "name")?,
+                    self.field_md(node, "file")?,
+                    field_int(node, "line")?,
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "baseType")?,
+                    field_int(node, "size")?,
+                    field_int(node, "align")?,
+                    field_int(node, "offset")?,
+                    field_flags(node, "flags", dwarf::di_flag)?,
+                    self.field_md(node, "extraData")?,
+                    match node.field("dwarfAddressSpace") {
+                        Some(Field::Int(v)) => Op::Lit(*v as u64 + 1),
+                        _ => Op::Lit(0),
+                    },
+                    self.field_md(node, "annotations")?,
+                ],
+            ),
+            DICompositeType => (
+                mdc::COMPOSITE_TYPE,
+                vec![
+                    Op::Lit(d),
+                    field_enum(node, "tag", dwarf::TAGS, 0)?,
+                    self.field_md(node, "name")?,
+                    self.field_md(node, "file")?,
+                    field_int(node, "line")?,
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "baseType")?,
+                    field_int(node, "size")?,
+                    field_int(node, "align")?,
+                    field_int(node, "offset")?,
+                    field_flags(node, "flags", dwarf::di_flag)?,
+                    self.field_md(node, "elements")?,
+                    field_enum(node, "runtimeLang", dwarf::LANGS, 0)?,
+                    self.field_md(node, "vtableHolder")?,
+                    self.field_md(node, "templateParams")?,
+                    self.field_md(node, "identifier")?,
+                    self.field_md(node, "discriminator")?,
+                    self.field_md(node, "dataLocation")?,
+                    self.field_md(node, "associated")?,
+                    self.field_md(node, "allocated")?,
+                    self.field_bound(node, "rank")?,
+                    self.field_md(node, "annotations")?,
+                ],
+            ),
+            DISubroutineType => (
+                mdc::SUBROUTINE_TYPE,
+                vec![
+                    Op::Lit(d),
+                    field_flags(node, "flags", dwarf::di_flag)?,
+                    self.field_md(node, "types")?,
+                    field_enum(node, "cc", dwarf::CCS, 0)?,
+                ],
+            ),
+            DICompileUnit => (
+                mdc::COMPILE_UNIT,
+                vec![
+                    Op::Lit(1),
+                    field_enum(node, "language", dwarf::LANGS, 0)?,
+                    self.field_md(node, "file")?,
+                    self.field_md(node, "producer")?,
+                    field_int(node, "isOptimized")?,
+                    self.field_md(node, "flags")?,
+                    field_int(node, "runtimeVersion")?,
+                    self.field_md(node, "splitDebugFilename")?,
+                    field_enum(node, "emissionKind", EMISSION_KINDS, 0)?,
+                    self.field_md(node, "enums")?,
+                    self.field_md(node, "retainedTypes")?,
+                    Op::Lit(0),
+                    self.field_md(node, "globals")?,
+                    self.field_md(node, "imports")?,
+                    field_int(node, "dwoId")?,
+                    self.field_md(node, "macros")?,
+                    match node.field("splitDebugInlining") {
+                        Some(Field::Bool(false)) => Op::Lit(0),
+                        _ => Op::Lit(1),
+                    },
+                    field_int(node, "debugInfoForProfiling")?,
+                    field_enum(node, "nameTableKind", NAME_TABLE_KINDS, 0)?,
+                    field_int(node, "rangesBaseAddress")?,
+                    self.field_md(node, "sysroot")?,
+                    self.field_md(node, "sdk")?,
+                ],
+            ),
+            DISubprogram => (
+                mdc::SUBPROGRAM,
+                vec![
+                    Op::Lit(d | 1 << 1 | 1 << 2),
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "name")?,
+                    self.field_md(node, "linkageName")?,
+                    self.field_md(node, "file")?,
+                    field_int(node, "line")?,
+                    self.field_md(node, "type")?,
+                    field_int(node, "scopeLine")?,
+                    self.field_md(node, "containingType")?,
+                    field_flags(node, "spFlags", dwarf::di_sp_flag)?,
+                    field_int(node, "virtualIndex")?,
+                    field_flags(node, "flags", dwarf::di_flag)?,
+                    self.field_md(node, "unit")?,
+                    self.field_md(node, "templateParams")?,
+                    self.field_md(node, "declaration")?,
+                    self.field_md(node, "retainedNodes")?,
+                    field_int(node, "thisAdjustment")?,
+                    self.field_md(node, "thrownTypes")?,
+                    self.field_md(node, "annotations")?,
+                ],
+            ),
+            DILexicalBlock => (
+                mdc::LEXICAL_BLOCK,
+                vec![
+                    Op::Lit(d),
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "file")?,
+                    field_int(node, "line")?,
+                    field_int(node, "column")?,
+                ],
+            ),
+            DILexicalBlockFile => (
+                mdc::LEXICAL_BLOCK_FILE,
+                vec![
+                    Op::Lit(d),
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "file")?,
+                    field_int(node, "discriminator")?,
+                ],
+            ),
+            DINamespace => {
+                let export_symbols = field_int(node, "exportSymbols")? == Op::Lit(1);
+                (
+                    mdc::NAMESPACE,
+                    vec![
+                        Op::Lit(d | (export_symbols as u64) << 1),
+                        self.field_md(node, "scope")?,
+                        self.field_md(node, "name")?,
+                    ],
+                )
+            }
+            DIGlobalVariable => (
+                mdc::GLOBAL_VAR,
+                vec![
+                    Op::Lit(d | 2 << 1),
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "name")?,
+                    self.field_md(node, "linkageName")?,
+                    self.field_md(node, "file")?,
+                    field_int(node, "line")?,
+                    self.field_md(node, "type")?,
+                    field_int(node, "isLocal")?,
+                    field_int(node, "isDefinition")?,
+                    self.field_md(node, "declaration")?,
+                    self.field_md(node, "templateParams")?,
+                    field_int(node, "align")?,
+                    self.field_md(node, "annotations")?,
+                ],
+            ),
+            DILocalVariable => (
+                mdc::LOCAL_VAR,
+                vec![
+                    Op::Lit(d | 1 << 1),
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "name")?,
+                    self.field_md(node, "file")?,
+                    field_int(node, "line")?,
+                    self.field_md(node, "type")?,
+                    field_int(node, "arg")?,
+                    field_flags(node, "flags", dwarf::di_flag)?,
+                    field_int(node, "align")?,
+                    self.field_md(node, "annotations")?,
+                ],
+            ),
+            DILabel => (
+                mdc::LABEL,
+                vec![
+                    Op::Lit(d),
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "name")?,
+                    self.field_md(node, "file")?,
+                    field_int(node, "line")?,
+                ],
+            ),
+            DIExpression => {
+                let mut ops = vec![Op::Lit(d | 3 << 1)];
+                for (_, field) in &node.fields {
+                    ops.push(Op::Lit(match field {
+                        Field::Ident(name) => dwarf::op_code(name)
+                            .map(|(code, _)| code)
+                            .or_else(|| dwarf::value(dwarf::ATES, name))
+                            .ok_or_else(|| {
+                                BitcodeError::new(format!("unknown DWARF operation {}", name))
+                            })?,
+                        Field::Int(v) => *v as u64,
+                        _ => return err("invalid element of !DIExpression"),
+                    }));
+                }
+                (mdc::EXPRESSION, ops)
+            }
+            DIGlobalVariableExpression => (
+                mdc::GLOBAL_VAR_EXPR,
+                vec![
+                    Op::Lit(d),
+                    self.field_md(node, "var")?,
+                    self.field_md(node, "expr")?,
+                ],
+            ),
+            DIImportedEntity => (
+                mdc::IMPORTED_ENTITY,
+                vec![
+                    Op::Lit(d),
+                    field_enum(node, "tag", dwarf::TAGS, 0)?,
+                    self.field_md(node, "scope")?,
+                    self.field_md(node, "entity")?,
+                    field_int(node, "line")?,
+                    self.field_md(node, "name")?,
+                    self.field_md(node, "file")?,
+                    self.field_md(node, "elements")?,
+                ],
+            ),
+            DILocation => {
+                let scope = match node.metadata_field("scope") {
+                    Some(scope) => self.metadata(scope)?,
+                    None => None,
+                };
+                let scope =
+                    scope.ok_or_else(|| BitcodeError::new("!DILocation without a scope"))?;
+                (
+                    mdc::LOCATION,
+                    vec![
+                        Op::Lit(d),
+                        field_int(node, "line")?,
+                        field_int(node, "column")?,
+                        Op::Md(scope),
+                        self.field_md(node, "inlinedAt")?,
+                        field_int(node, "isImplicitCode")?,
+                    ],
+                )
+            }
+            kind => return err(format!("writing {:?} is not supported", kind)),
+        };
+        Ok(Rec::new(code, ops))
+    }
+}
+
+/// Whether `meta` is a node, which gets an entry of its own when referred to by `!N`.
+fn is_node(meta: &Metadata) -> bool {
+    matches!(
+        meta,
+        Metadata::Node(_) | Metadata::Distinct(_) | Metadata::Specialized(_)
+    )
+}
+
+fn field_int(node: &SpecializedNode, key: &str) -> Result<Op> {
+    Ok(Op::Lit(match node.field(key) {
+        None => 0,
+        Some(Field::Int(v)) => *v as u64,
+        Some(Field::Bool(b)) => *b as u64,
+        Some(_) => return err(format!("invalid field {}", key)),
+    }))
+}
+
+/// A field that is a name in `table` or a number.
+fn field_enum(
+    node: &SpecializedNode,
+    key: &str,
+    table: &[(u64, &str)],
+    default: u64,
+) -> Result<Op> {
+    Ok(Op::Lit(match node.field(key) {
+        None => default,
+        Some(Field::Int(v)) => *v as u64,
+        Some(Field::Ident(name)) => dwarf::value(table, name)
+            .ok_or_else(|| BitcodeError::new(format!("unknown {} {}", key, name)))?,
+        Some(_) => return err(format!("invalid field {}", key)),
+    }))
+}
+
+fn field_flags(node: &SpecializedNode, key: &str, flag: fn(&str) -> Option<u64>) -> Result<Op> {
+    let names = match node.field(key) {
+        None => return Ok(Op::Lit(0)),
+        Some(Field::Int(v)) => return Ok(Op::Lit(*v as u64)),
+        Some(Field::Ident(name)) => vec![name.as_str()],
+        Some(Field::Flags(names)) => names.iter().map(String::as_str).collect(),
+        Some(_) => return err(format!("invalid field {}", key)),
+    };
+    let mut bits = 0;
+    for name in names {
+        bits |= flag(name).ok_or_else(|| BitcodeError::new(format!("unknown flag {}", name)))?;
+    }
+    Ok(Op::Lit(bits))
+}
@@ -0,0 +1,1309 @@
+//! Writes a `Module` as LLVM bitcode.
+//!
+//! Records are built first with operands that refer to types, values and metadata
+//! symbolically, since their IDs are known only once every record has been built.
+//! The operands are turned into IDs as the records are written out.
+
+mod function;
+mod metadata;
+
+use super::{
+    bitstream::{encode_signed, BitWriter, Record},
+    codes::{
+        block, constants as cst, identification, module as modc, paramattr, strtab, sync_scope,
+        types as ty,
+    },
+    reader::{err, Result},
+    BitcodeError,
+};
+use crate::ir::{
+    function::{
+        instruction::{FCmpCond, ICmpCond, Opcode, SyncScope},
+        param_attrs::ParameterAttribute,
+    },
+    module::{
+        attributes::Attribute,
+        comdat::SelectionKind,
+        global_alias::GlobalAlias,
+        global_ifunc::GlobalIFunc,
+        global_variable::{GlobalVariable, ThreadLocalMode},
+        linkage::Linkage,
+        name::Name,
+        preemption_specifier::PreemptionSpecifier,
+        unnamed_addr::UnnamedAddr,
+        visibility::Visibility,
+        Module,
+    },
+    types::{Type, TypeId, Types},
+    value::{ConstantData, ConstantExpr, ConstantFloat, InlineAsm},
+};
+use function::Body;
+use metadata::MetadataTable;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// A value, numbered once the number of globals and constants is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum ValueRef {
+    Global(usize),
+    Const(usize),
+    /// An argument or an instruction, numbered from the first argument of the function
+    Local(usize),
+}
+
+/// A metadata entry. Strings come first, then nodes, then function-level entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum MdRef {
+    String(usize),
+    Node(usize),
+    Local(usize),
+}
+
+/// An operand of a record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Op {
+    Lit(u64),
+    Ty(TypeId),
+    Value(ValueRef),
+    /// A value ID plus one, or 0 for none
+    OptValue(Option<ValueRef>),
+    /// A value ID relative to the instruction
+    Rel(ValueRef),
+    /// A relative value ID followed by the type of the value if it is defined later
+    RelTy(ValueRef, TypeId),
+    /// A relative value ID as a signed number, as `phi` has it
+    Signed(ValueRef),
+    Md(MdRef),
+    /// A metadata ID plus one, or 0 for null
+    OptMd(Option<MdRef>),
+    /// A metadata ID relative to the instruction, as metadata arguments of calls have it
+    RelMd(MdRef),
+}
+
+/// A record whose operands are resolved when it is written.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct Rec {
+    pub code: u32,
+    pub ops: Vec<Op>,
+}
+
+impl Rec {
+    pub fn new(code: u32, ops: Vec<Op>) -> Self {
+        Self { code, ops }
+    }
+}
+
+/// Operands for the characters of `s`.
+pub(super) fn chars(s: &str) -> impl Iterator<Item = Op> + '_ {
+    s.bytes().map(|b| Op::Lit(b as u64))
+}
+
+pub(super) struct Writer<'a> {
+    module: &'a Module,
+    types: Types,
+    strtab: Vec<u8>,
+    /// Value IDs of global variables, functions, aliases and ifuncs
+    globals: FxHashMap<Name, usize>,
+    /// COMDAT records, then records that define globals in the order of their values
+    module_records: Vec<Rec>,
+    sections: Vec<String>,
+    comdats: Vec<Name>,
+    /// Constants with their types in the order of their values
+    consts: Vec<(TypeId, Rec)>,
+    const_ids: FxHashMap<(TypeId, String), usize>,
+    /// Attribute groups: the index they apply to and their encoded attributes
+    attr_groups: Vec<(u64, Vec<Op>)>,
+    /// Attribute lists as IDs of groups
+    attr_lists: Vec<Vec<u64>>,
+    /// Target-specific sync scopes, which come after `singlethread` and the system scope
+    sync_scopes: Vec<String>,
+    md: MetadataTable,
+    bodies: Vec<Body>,
+}
+
+/// IDs of what records refer to, known once every record has been built.
+pub(super) struct Ids {
+    types: FxHashMap<TypeId, u64>,
+    num_globals: u64,
+    num_module_values: u64,
+    num_strings: u64,
+    num_module_md: u64,
+}
+
+impl Ids {
+    fn value(&self, v: ValueRef) -> u64 {
+        match v {
+            ValueRef::Global(i) => i as u64,
+            ValueRef::Const(i) => self.num_globals + i as u64,
+            ValueRef::Local(i) => self.num_module_values + i as u64,
+        }
+    }
+
+    fn md(&self, md: MdRef, num_nodes: u64) -> u64 {
+        match md {
+            MdRef::String(i) => i as u64,
+            MdRef::Node(i) => self.num_strings + i as u64,
+            MdRef::Local(i) => self.num_strings + num_nodes + i as u64,
+        }
+    }
+
+    /// Resolves `rec` for an instruction that comes after `inst_num` values.
+    pub fn resolve(&self, rec: &Rec, inst_num: u64) -> Record {
+        let num_nodes = self.num_module_md - self.num_strings;
+        let relative = |id: u64| (inst_num as u32).wrapping_sub(id as u32) as u64;
+        let mut ops = vec![];
+        for op in &rec.ops {
+            match op {
+                Op::Lit(v) => ops.push(*v),
+                Op::Ty(ty) => ops.push(self.types[ty]),
+                Op::Value(v) => ops.push(self.value(*v)),
+                Op::OptValue(v) => ops.push(v.map_or(0, |v| self.value(v) + 1)),
+                Op::Rel(v) => ops.push(relative(self.value(*v))),
+                Op::RelTy(v, ty) => {
+                    let id = self.value(*v);
+                    ops.push(relative(id));
+                    if id >= inst_num {
+                        ops.push(self.types[ty]);
+                    }
+                }
+                Op::Signed(v) => ops.push(encode_signed(inst_num as i64 - self.value(*v) as i64)),
+                Op::Md(md) => ops.push(self.md(*md, num_nodes)),
+                Op::OptMd(md) => ops.push(md.map_or(0, |md| self.md(md, num_nodes) + 1)),
+                Op::RelMd(md) => ops.push(relative(self.md(*md, num_nodes))),
+            }
+        }
+        Record {
+            code: rec.code,
+            ops,
+            blob: None,
+        }
+    }
+}
+
+fn record(code: u32, ops: Vec<u64>) -> Record {
+    Record {
+        code,
+        ops,
+        blob: None,
+    }
+}
+
+fn string_record(code: u32, s: &str) -> Record {
+    record(code, s.bytes().map(u64::from).collect())
+}
+
+pub fn write_module(module: &Module) -> Result<Vec<u8>> {
+    let mut writer = Writer {
+        module,
+        types: module.types.clone(),
+        strtab: vec![],
+        globals: FxHashMap::default(),
+        module_records: vec![],
+        sections: vec![],
+        comdats: vec![],
+        consts: vec![],
+        const_ids: FxHashMap::default(),
+        attr_groups: vec![],
+        attr_lists: vec![],
+        sync_scopes: vec![],
+        md: MetadataTable::default(),
+        bodies: vec![],
+    };
+    writer.build()?;
+    Ok(writer.emit())
+}
+
+impl<'a> Writer<'a> {
+    fn build(&mut self) -> Result<()> {
+        let module = self.module;
+        let gvars: Vec<_> = module
+            .global_order
+            .iter()
+            .filter_map(|name| module.global_variables.get(name))
+            .collect();
+        let aliases: Vec<_> = module
+            .global_order
+            .iter()
+            .filter_map(|name| module.global_aliases.get(name))
+            .collect();
+        let ifuncs: Vec<_> = module
+            .global_order
+            .iter()
+            .filter_map(|name| module.global_ifuncs.get(name))
+            .collect();
+
+        // Number globals first since anything may refer to them.
+        let names = gvars
+            .iter()
+            .map(|gv| gv.name.clone())
+            .chain(
+                module
+                    .functions
+                    .iter()
+                    .map(|(_, f)| Name::Name(f.name.clone())),
+            )
+            .chain(aliases.iter().map(|a| a.name.clone()))
+            .chain(ifuncs.iter().map(|i| i.name.clone()));
+        for (i, name) in names.enumerate() {
+            self.globals.insert(name, i);
+        }
+
+        let mut comdats: Vec<_> = module.comdats.values().collect();
+        comdats.sort_by(|a, b| a.name.to_string().cmp(&b.name.to_string()));
+        for comdat in comdats {
+            // [strtab offset, strtab size, selection kind]
+            let mut ops = self.strtab_name(&comdat.name);
+            ops.push(Op::Lit(match comdat.kind {
+                SelectionKind::Any => 1,
+                SelectionKind::ExactMatch => 2,
+                SelectionKind::Largest => 3,
+                SelectionKind::NoDeduplicate => 4,
+                SelectionKind::SameSize => 5,
+            }));
+            self.module_records.push(Rec::new(modc::COMDAT, ops));
+            self.comdats.push(comdat.name.clone());
+        }
+
+        for gv in &gvars {
+            let rec = self.global_variable(gv)?;
+            self.module_records.push(rec);
+        }
+        for (_, func) in module.functions.iter() {
+            let rec = self.function_record(func)?;
+            self.module_records.push(rec);
+        }
+        for alias in &aliases {
+            let rec = self.alias(alias)?;
+            self.module_records.push(rec);
+        }
+        for ifunc in &ifuncs {
+            let rec = self.ifunc(ifunc)?;
+            self.module_records.push(rec);
+        }
+
+        for gv in &gvars {
+            let value = self.global(&gv.name)?;
+            self.attach_to_declaration(value, &gv.metadata)?;
+        }
+        self.named_metadata()?;
+        for (_, func) in module.functions.iter() {
+            if func.is_prototype() {
+                let value = self.global(&Name::Name(func.name.clone()))?;
+                self.attach_to_declaration(value, &func.metadata)?;
+            } else {
+                let body = self.function_body(func)?;
+                self.bodies.push(body);
+            }
+        }
+        Ok(())
+    }
+
+    fn strtab_name(&mut self, name: &Name) -> Vec<Op> {
+        let name = name.to_string().cloned().unwrap_or_default();
+        self.strtab_string(&name)
+    }
+
+    fn strtab_string(&mut self, s: &str) -> Vec<Op> {
+        let offset = self.strtab.len();
+        self.strtab.extend_from_slice(s.as_bytes());
+        vec![Op::Lit(offset as u64), Op::Lit(s.len() as u64)]
+    }
+
+    pub fn global(&self, name: &Name) -> Result<ValueRef> {
+        match self.globals.get(name) {
+            Some(i) => Ok(ValueRef::Global(*i)),
+            None => err(format!("unknown global @{}", name)),
+        }
+    }
+
+    fn comdat(&self, comdat: &Option<Name>) -> u64 {
+        comdat
+            .as_ref()
+            .and_then(|c| self.comdats.iter().position(|n| n == c))
+            .map_or(0, |i| i as u64 + 1)
+    }
+
+    // [strtab offset, strtab size, type, isconst | explicit type << 1 | addrspace << 2,
+    //  initid, linkage, alignment, section, visibility, threadlocal, unnamed_addr,
+    //  externally_initialized, dllstorageclass, comdat, attributes, dso_local,
+    //  partition offset, partition size]
+    fn global_variable(&mut self, gv: &GlobalVariable) -> Result<Rec> {
+        let mut ops = self.strtab_name(&gv.name);
+        ops.push(Op::Ty(gv.ty));
+        ops.push(Op::Lit(
+            gv.is_constant as u64 | 2 | (gv.addr_space as u64) << 2,
+        ));
+        ops.push(Op::OptValue(match &gv.init {
+            Some(init) => Some(self.constant(gv.ty, init)?),
+            None => None,
+        }));
+        let linkage = gv.linkage.unwrap_or(Linkage::External);
+        let visibility = gv.visibility.unwrap_or(Visibility::Default);
+        let section = match &gv.section {
+            Some(section) => match self.sections.iter().position(|s| s == section) {
+                Some(i) => i + 1,
+                None => {
+                    self.sections.push(section.clone());
+                    self.sections.len()
+                }
+            },
+            None => 0,
+        };
+        ops.extend(
+            [
+                linkage_code(linkage),
+                encode_align(gv.align),
+                section as u64,
+                visibility_code(visibility),
+                match gv.thread_local {
+                    None => 0,
+                    Some(ThreadLocalMode::GeneralDynamic) => 1,
+                    Some(ThreadLocalMode::LocalDynamic) => 2,
+                    Some(ThreadLocalMode::InitialExec) => 3,
+                    Some(ThreadLocalMode::LocalExec) => 4,
+                },
+                unnamed_addr_code(gv.unnamed_addr),
+                gv.externally_initialized as u64,
+                0,
+                self.comdat(&gv.comdat),
+                0,
+                dso_local(gv.preemption_specifier, linkage, visibility),
+            ]
+            .map(Op::Lit),
+        );
+        match &gv.partition {
+            Some(partition) => ops.extend(self.strtab_string(partition)),
+            None => ops.extend([Op::Lit(0), Op::Lit(0)]),
+        }
+        Ok(Rec::new(modc::GLOBALVAR, ops))
+    }
+
+    // [strtab offset, strtab size, type, callingconv, isproto, linkage, paramattr,
+    //  alignment, section, visibility, gc, unnamed_addr, prologuedata, dllstorageclass,
+    //  comdat, prefixdata, personalityfn, dso_local, addrspace]
+    fn function_record(&mut self, func: &crate::ir::function::Function) -> Result<Rec> {
+        let mut ops = self.strtab_name(&Name::Name(func.name.clone()));
+        let param_tys = func.params.iter().map(|p| p.ty).collect();
+        let fn_ty = self
+            .types
+            .base_mut()
+            .function(func.result_ty, param_tys, func.is_var_arg);
+        ops.push(Op::Ty(fn_ty));
+        let param_attrs: Vec<_> = func.params.iter().map(|p| p.attrs.clone()).collect();
+        let attrs = self.attr_list(&func.func_attrs, &func.ret_attrs, &param_attrs)?;
+        let personality = match &func.personality {
+            Some((ty, konst)) => Some(self.constant(*ty, konst)?),
+            None => None,
+        };
+        ops.extend(
+            [
+                0,
+                func.is_prototype() as u64,
+                linkage_code(func.linkage),
+                attrs,
+                0,
+                0,
+                visibility_code(func.visibility),
+                0,
+                unnamed_addr_code(func.unnamed_addr),
+                0,
+                0,
+                self.comdat(&func.comdat),
+                0,
+            ]
+            .map(Op::Lit),
+        );
+        ops.push(Op::OptValue(personality));
+        ops.push(Op::Lit(dso_local(
+            Some(func.preemption_specifier),
+            func.linkage,
+            func.visibility,
+        )));
+        ops.push(Op::Lit(0));
+        Ok(Rec::new(modc::FUNCTION, ops))
+    }
+
+    // [strtab offset, strtab size, value type, addrspace, aliasee, linkage, visibility,
+    //  dllstorageclass, threadlocal, unnamed_addr, dso_local, partition offset, partition size]
+    fn alias(&mut self, alias: &GlobalAlias) -> Result<Rec> {
+        let mut ops = self.strtab_name(&alias.name);
+        let aliasee = self.constant(alias.aliasee_ty, &alias.aliasee)?;
+        let linkage = alias.linkage.unwrap_or(Linkage::External);
+        let visibility = alias.visibility.unwrap_or(Visibility::Default);
+        ops.extend([Op::Ty(alias.ty), Op::Lit(0), Op::Value(aliasee)]);
+        ops.extend(
+            [
+                linkage_code(linkage),
+                visibility_code(visibility),
+                0,
+                0,
+                unnamed_addr_code(alias.unnamed_addr),
+                dso_local(alias.preemption_specifier, linkage, visibility),
+                0,
+                0,
+            ]
+            .map(Op::Lit),
+        );
+        Ok(Rec::new(modc::ALIAS, ops))
+    }
+
+    // [strtab offset, strtab size, value type, addrspace, resolver, linkage, visibility,
+    //  dso_local]
+    fn ifunc(&mut self, ifunc: &GlobalIFunc) -> Result<Rec> {
+        let mut ops = self.strtab_name(&ifunc.name);
+        let resolver = self.constant(ifunc.resolver_ty, &ifunc.resolver)?;
+        let linkage = ifunc.linkage.unwrap_or(Linkage::External);
+        let visibility = ifunc.visibility.unwrap_or(Visibility::Default);
+        ops.extend([Op::Ty(ifunc.ty), Op::Lit(0), Op::Value(resolver)]);
+        ops.extend(
+            [
+                linkage_code(linkage),
+                visibility_code(visibility),
+                dso_local(ifunc.preemption_specifier, linkage, visibility),
+            ]
+            .map(Op::Lit),
+        );
+        Ok(Rec::new(modc::IFUNC, ops))
+    }
+
+    pub fn sync_scope(&mut self, scope: &SyncScope) -> u64 {
+        match scope {
+            SyncScope::SingleThread => 0,
+            SyncScope::System => 1,
+            SyncScope::Target(name) => {
+                let i = match self.sync_scopes.iter().position(|s| s == name) {
+                    Some(i) => i,
+                    None => {
+                        self.sync_scopes.push(name.clone());
+                        self.sync_scopes.len() - 1
+                    }
+                };
+                i as u64 + 2
+            }
+        }
+    }
+
+    // Attributes
+
+    /// Returns the ID of the attribute list for a function or a call site, or 0 if it has
+    /// no attributes.
+    pub fn attr_list(
+        &mut self,
+        func: &[Attribute],
+        ret: &[ParameterAttribute],
+        params: &[Vec<ParameterAttribute>],
+    ) -> Result<u64> {
+        let mut groups = vec![];
+        let mut func_attrs = vec![];
+        for attr in func {
+            match attr {
+                Attribute::Ref(n) => match self.module.attributes.get(n) {
+                    Some(attrs) => func_attrs.extend(attrs.iter().flat_map(func_attr)),
+                    None => return err(format!("unknown attribute group #{}", n)),
+                },
+                attr => func_attrs.extend(func_attr(attr)),
+            }
+        }
+        if !func_attrs.is_empty() {
+            groups.push(self.attr_group(u32::MAX as u64, func_attrs.concat()));
+        }
+        let ret: Vec<_> = ret.iter().flat_map(param_attr).collect();
+        if !ret.is_empty() {
+            groups.push(self.attr_group(0, ret.concat()));
+        }
+        for (i, attrs) in params.iter().enumerate() {
+            let attrs: Vec<_> = attrs.iter().flat_map(param_attr).collect();
+            if !attrs.is_empty() {
+                groups.push(self.attr_group(i as u64 + 1, attrs.concat()));
+            }
+        }
+        if groups.is_empty() {
+            return Ok(0);
+        }
+        let id = match self.attr_lists.iter().position(|l| *l == groups) {
+            Some(id) => id,
+            None => {
+                self.attr_lists.push(groups);
+                self.attr_lists.len() - 1
+            }
+        };
+        Ok(id as u64 + 1)
+    }
+
+    fn attr_group(&mut self, index: u64, attrs: Vec<Op>) -> u64 {
+        let group = (index, attrs);
+        let id = match self.attr_groups.iter().position(|g| *g == group) {
+            Some(id) => id,
+            None => {
+                self.attr_groups.push(group);
+                self.attr_groups.len() - 1
+            }
+        };
+        id as u64 + 1
+    }
+
+    // Constants
+
+    /// Returns the value of a constant of type `ty`, sharing equal constants.
+    pub fn constant(&mut self, ty: TypeId, konst: &ConstantData) -> Result<ValueRef> {
+        if let ConstantData::GlobalRef(name) = konst {
+            return self.global(name);
+        }
+        let key = (ty, format!("{:?}", konst));
+        if let Some(id) = self.const_ids.get(&key) {
+            return Ok(ValueRef::Const(*id));
+        }
+        let rec = self.constant_record(ty, konst)?;
+        Ok(self.push_constant(key, ty, rec))
+    }
+
+    fn push_constant(&mut self, key: (TypeId, String), ty: TypeId, rec: Rec) -> ValueRef {
+        let id = self.consts.len();
+        self.consts.push((ty, rec));
+        self.const_ids.insert(key, id);
+        ValueRef::Const(id)
+    }
+
+    /// Returns the value of an inline assembly callee of the function type `fn_ty`.
+    pub fn inline_asm(&mut self, fn_ty: TypeId, asm: &InlineAsm) -> ValueRef {
+        let ptr = self.types.base_mut().pointer(fn_ty);
+        let key = (ptr, format!("{:?}", asm));
+        if let Some(id) = self.const_ids.get(&key) {
+            return ValueRef::Const(*id);
+        }
+        // [fnty, flags, asmstrsize, asmstr..., constraintsize, constraints...]
+        // The text parser keeps the asm string in `constraints` and the constraint string
+        // in `body`.
+        let mut ops = vec![
+            Op::Ty(fn_ty),
+            Op::Lit(asm.sideeffect as u64),
+            Op::Lit(asm.constraints.len() as u64),
+        ];
+        ops.extend(chars(&asm.constraints));
+        ops.push(Op::Lit(asm.body.len() as u64));
+        ops.extend(chars(&asm.body));
+        self.push_constant(key, ptr, Rec::new(cst::INLINEASM, ops))
+    }
+
+    fn constant_record(&mut self, ty: TypeId, konst: &ConstantData) -> Result<Rec> {
+        let types = self.types.clone();
+        Ok(match konst {
+            ConstantData::Undef => Rec::new(cst::UNDEF, vec![]),
+            ConstantData::Poison => Rec::new(cst::POISON, vec![]),
+            ConstantData::AggregateZero | ConstantData::Null => Rec::new(cst::NULL, vec![]),
+            ConstantData::Int(i) if i.bits() <= 64 => Rec::new(
+                cst::INTEGER,
+                vec![Op::Lit(encode_signed(i.sext_value().unwrap_or(0)))],
+            ),
+            ConstantData::Int(i) => Rec::new(
+                cst::WIDE_INTEGER,
+                i.words()
+                    .iter()
+                    .map(|w| Op::Lit(encode_signed(*w as i64)))
+                    .collect(),
+            ),
+            ConstantData::Float(f) => {
+                Rec::new(cst::FLOAT, float(f).into_iter().map(Op::Lit).collect())
+            }
+            ConstantData::Array(a) => {
+                let is_i8 = matches!(&*types.get(a.elem_ty), Type::Int(8));
+                let bytes: Option<Vec<u64>> = a
+                    .elems
+                    .iter()
+                    .map(|e| match e {
+                        ConstantData::Int(i) => i.zext_value(),
+                        _ => None,
+                    })
+                    .collect();
+                match bytes {
+                    Some(mut bytes) if is_i8 && !bytes.is_empty() => {
+                        let code = if bytes.last() == Some(&0)
+                            && bytes[..bytes.len() - 1].iter().all(|b| *b != 0)
+                        {
+                            bytes.pop();
+                            cst::CSTRING
+                        } else {
+                            cst::STRING
+                        };
+                        Rec::new(code, bytes.into_iter().map(Op::Lit).collect())
+                    }
+                    _ => self.sequence(a.elem_ty, &a.elems)?,
+                }
+            }
+            ConstantData::Vector(v) => {
+                let elems = if v.is_splat {
+                    let n = match &*types.get(ty) {
+                        Type::Vector(vt) if !vt.is_scalable => vt.num_elements as usize,
+                        _ => return err("splat of a scalable vector is not supported"),
+                    };
+                    vec![v.elems[0].clone(); n]
+                } else {
+                    v.elems.clone()
+                };
+                self.sequence(v.elem_ty, &elems)?
+            }
+            ConstantData::Struct(s) => {
+                let mut ops = vec![];
+                for (ty, elem) in s.elems_ty.iter().zip(s.elems.iter()) {
+                    ops.push(Op::Value(self.constant(*ty, elem)?));
+                }
+                Rec::new(cst::AGGREGATE, ops)
+            }
+            ConstantData::Expr(e) => self.constant_expr(e)?,
+            ConstantData::GlobalRef(_) => unreachable!(),
+            ConstantData::BlockAddress(b) => {
+                let func = self
+                    .module
+                    .functions
+                    .iter()
+                    .find(|(_, f)| Name::Name(f.name.clone()) == b.func)
+                    .map(|(_, f)| f)
+                    .ok_or_else(|| BitcodeError::new("blockaddress of an unknown function"))?;
+                let index = func
+                    .layout
+                    .block_iter()
+                    .position(|block| func.data.block_ref(block).name.as_ref() == Some(&b.block))
+                    .ok_or_else(|| BitcodeError::new("blockaddress of an unknown block"))?;
+                let param_tys = func.params.iter().map(|p| p.ty).collect();
+                let fn_ty =
+                    self.types
+                        .base_mut()
+                        .function(func.result_ty, param_tys, func.is_var_arg);
+                let fn_ptr = self.types.base_mut().pointer(fn_ty);
+                // [function type, function, block index]
+                Rec::new(
+                    cst::BLOCKADDRESS,
+                    vec![
+                        Op::Ty(fn_ptr),
+                        Op::Value(self.global(&b.func)?),
+                        Op::Lit(index as u64),
+                    ],
+                )
+            }
+        })
+    }
+
+    /// Builds an array or a vector. Elements that are all simple numbers are packed into
+    /// a DATA record.
+    fn sequence(&mut self, elem_ty: TypeId, elems: &[ConstantData]) -> Result<Rec> {
+        let data: Option<Vec<u64>> = {
+            let elem = self.types.get(elem_ty);
+            elems
+                .iter()
+                .map(|e| match (&*elem, e) {
+                    (Type::Int(bits), ConstantData::Int(i)) if *bits <= 64 => i.zext_value(),
+                    (Type::Half | Type::Float | Type::Double, ConstantData::Float(f)) => {
+                        Some(f.to_bits() as u64)
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+        match data {
+            Some(data) if !data.is_empty() => {
+                Ok(Rec::new(cst::DATA, data.into_iter().map(Op::Lit).collect()))
+            }
+            _ => {
+                let mut ops = vec![];
+                for elem in elems {
+                    ops.push(Op::Value(self.constant(elem_ty, elem)?));
+                }
+                Ok(Rec::new(cst::AGGREGATE, ops))
+            }
+        }
+    }
+
+    fn constant_expr(&mut self, expr: &ConstantExpr) -> Result<Rec> {
+        Ok(match expr {
+            ConstantExpr::GetElementPtr {
+                inbounds,
+                tys,
+                args,
+            } => {
+                // [source element type, (type, value)*]
+                let mut ops = vec![Op::Ty(tys[0])];
+                for (ty, arg) in tys[1..].iter().zip(args.iter()) {
+                    ops.push(Op::Ty(*ty));
+                    ops.push(Op::Value(self.constant(*ty, arg)?));
+                }
+                let code = if *inbounds {
+                    cst::CE_INBOUNDS_GEP
+                } else {
+                    cst::CE_GEP
+                };
+                Rec::new(code, ops)
+            }
+            ConstantExpr::Cast { opcode, tys, arg } => Rec::new(
+                cst::CE_CAST,
+                vec![
+                    Op::Lit(cast(*opcode)?),
+                    Op::Ty(tys[0]),
+                    Op::Value(self.constant(tys[0], arg)?),
+                ],
+            ),
+            ConstantExpr::IntBinary {
+                opcode,
+                ty,
+                nuw,
+                nsw,
+                exact,
+                args,
+            } => {
+                let mut ops = vec![
+                    Op::Lit(binop(*opcode)?),
+                    Op::Value(self.constant(*ty, &args[0])?),
+                    Op::Value(self.constant(*ty, &args[1])?),
+                ];
+                let flags = binop_flags(*opcode, *nuw, *nsw, *exact);
+                if flags != 0 {
+                    ops.push(Op::Lit(flags));
+                }
+                Rec::new(cst::CE_BINOP, ops)
+            }
+            ConstantExpr::FloatBinary { opcode, ty, args } => Rec::new(
+                cst::CE_BINOP,
+                vec![
+                    Op::Lit(binop(*opcode)?),
+                    Op::Value(self.constant(*ty, &args[0])?),
+                    Op::Value(self.constant(*ty, &args[1])?),
+                ],
+            ),
+            ConstantExpr::FNeg { ty, arg } => Rec::new(
+                cst::CE_UNOP,
+                vec![Op::Lit(0), Op::Value(self.constant(*ty, arg)?)],
+            ),
+            ConstantExpr::ICmp { cond, ty, args } => Rec::new(
+                cst::CE_CMP,
+                vec![
+                    Op::Ty(*ty),
+                    Op::Value(self.constant(*ty, &args[0])?),
+                    Op::Value(self.constant(*ty, &args[1])?),
+                    Op::Lit(icmp_cond(*cond)),
+                ],
+            ),
+            ConstantExpr::FCmp { cond, ty, args } => Rec::new(
+                cst::CE_CMP,
+                vec![
+                    Op::Ty(*ty),
+                    Op::Value(self.constant(*ty, &args[0])?),
+                    Op::Value(self.constant(*ty, &args[1])?),
+                    Op::Lit(fcmp_cond(*cond)),
+                ],
+            ),
+            ConstantExpr::Select { tys, args } => Rec::new(
+                cst::CE_SELECT,
+                vec![
+                    Op::Value(self.constant(tys[0], &args[0])?),
+                    Op::Value(self.constant(tys[1], &args[1])?),
+                    Op::Value(self.constant(tys[1], &args[2])?),
+                ],
+            ),
+            ConstantExpr::ExtractElement { tys, args } => Rec::new(
+                cst::CE_EXTRACTELT,
+                vec![
+                    Op::Ty(tys[0]),
+                    Op::Value(self.constant(tys[0], &args[0])?),
+                    Op::Ty(tys[1]),
+                    Op::Value(self.constant(tys[1], &args[1])?),
+                ],
+            ),
+            ConstantExpr::InsertElement { tys, args } => Rec::new(
+                cst::CE_INSERTELT,
+                vec![
+                    Op::Value(self.constant(tys[0], &args[0])?),
+                    Op::Value(self.constant(tys[1], &args[1])?),
+                    Op::Ty(tys[2]),
+                    Op::Value(self.constant(tys[2], &args[2])?),
+                ],
+            ),
+            ConstantExpr::ShuffleVector { tys, args } => Rec::new(
+                cst::CE_SHUFFLEVEC,
+                vec![
+                    Op::Value(self.constant(tys[0], &args[0])?),
+                    Op::Value(self.constant(tys[0], &args[1])?),
+                    Op::Value(self.constant(tys[1], &args[2])?),
+                ],
+            ),
+            ConstantExpr::ExtractValue { .. } | ConstantExpr::InsertValue { .. } => {
+                return err("extractvalue and insertvalue constant expressions are not supported")
+            }
+        })
+    }
+
+    // Writing
+
+    /// Numbers types: named structs in the order of their names, each after the types it
+    /// needs except other named structs, then every other type used.
+    fn type_ids(&self) -> (Vec<TypeId>, FxHashMap<TypeId, Name>) {
+        let named: FxHashMap<TypeId, Name> = self
+            .types
+            .base()
+            .named_types()
+            .into_iter()
+            .map(|(name, id)| (id, name))
+            .collect();
+        let mut order = vec![];
+        let mut seen = FxHashSet::default();
+        for (_, id) in self.types.base().named_types() {
+            self.visit_type(id, true, &named, &mut seen, &mut order);
+        }
+        let mut used = vec![];
+        let mut collect = |rec: &Rec| {
+            for op in &rec.ops {
+                match op {
+                    Op::Ty(ty) | Op::RelTy(_, ty) => used.push(*ty),
+                    _ => {}
+                }
+            }
+        };
+        for rec in &self.module_records {
+            collect(rec)
+        }
+        for (ty, rec) in &self.consts {
+            collect(&Rec::new(0, vec![Op::Ty(*ty)]));
+            collect(rec)
+        }
+        for (_, ops) in &self.attr_groups {
+            collect(&Rec::new(0, ops.clone()));
+        }
+        self.md.records().for_each(&mut collect);
+        for body in &self.bodies {
+            body.records().for_each(&mut collect);
+        }
+        for ty in used {
+            self.visit_type(ty, true, &named, &mut seen, &mut order);
+        }
+        (order, named)
+    }
+
+    fn visit_type(
+        &self,
+        ty: TypeId,
+        top: bool,
+        named: &FxHashMap<TypeId, Name>,
+        seen: &mut FxHashSet<TypeId>,
+        order: &mut Vec<TypeId>,
+    ) {
+        // Named structs may be referred to before they are defined.
+        if seen.contains(&ty) || (!top && named.contains_key(&ty)) {
+            return;
+        }
+        seen.insert(ty);
+        let children = match &*self.types.get(ty) {
+            Type::Pointer(p) => vec![p.inner],
+            Type::Array(a) => vec![a.inner],
+            Type::Vector(v) => vec![v.inner],
+            Type::Function(f) => [f.ret].iter().chain(f.params.iter()).copied().collect(),
+            Type::Struct(s) => s.elems.clone(),
+            _ => vec![],
+        };
+        for child in children {
+            self.visit_type(child, false, named, seen, order);
+        }
+        order.push(ty);
+    }
+
+    fn emit(self) -> Vec<u8> {
+        let (type_order, named) = self.type_ids();
+        let num_globals = self.globals.len() as u64;
+        let ids = Ids {
+            types: type_order
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| (*ty, i as u64))
+                .collect(),
+            num_globals,
+            num_module_values: num_globals + self.consts.len() as u64,
+            num_strings: self.md.num_strings() as u64,
+            num_module_md: self.md.len() as u64,
+        };
+
+        let mut w = BitWriter::new();
+        w.enter_block(block::IDENTIFICATION, 5);
+        w.record(&string_record(identification::STRING, "vicis"));
+        w.record(&record(identification::EPOCH, vec![0]));
+        w.end_block();
+
+        w.enter_block(block::MODULE, 3);
+        w.record(&record(modc::VERSION, vec![2]));
+        self.emit_types(&mut w, &ids, &type_order, &named);
+        if !self.attr_groups.is_empty() {
+            w.enter_block(block::PARAMATTR_GROUP, 4);
+            for (i, (index, attrs)) in self.attr_groups.iter().enumerate() {
+                let mut r = ids.resolve(&Rec::new(paramattr::GRP_ENTRY, attrs.clone()), 0);
+                r.ops.splice(0..0, [i as u64 + 1, *index]);
+                w.record(&r);
+            }
+            w.end_block();
+            w.enter_block(block::PARAMATTR, 4);
+            for groups in &self.attr_lists {
+                w.record(&record(paramattr::ENTRY, groups.clone()));
+            }
+            w.end_block();
+        }
+        let target = self.module.target();
+        if !target.triple().is_empty() {
+            w.record(&string_record(modc::TRIPLE, target.triple()));
+        }
+        if !target.datalayout().is_empty() {
+            w.record(&string_record(modc::DATALAYOUT, target.datalayout()));
+        }
+        for section in &self.sections {
+            w.record(&string_record(modc::SECTIONNAME, section));
+        }
+        for rec in &self.module_records {
+            w.record(&ids.resolve(rec, 0));
+        }
+        w.record(&string_record(
+            modc::SOURCE_FILENAME,
+            self.module.source_filename(),
+        ));
+
+        if !self.consts.is_empty() {
+            w.enter_block(block::CONSTANTS, 4);
+            let mut cur_ty = None;
+            for (ty, rec) in &self.consts {
+                if cur_ty != Some(*ty) {
+                    w.record(&record(cst::SETTYPE, vec![ids.types[ty]]));
+                    cur_ty = Some(*ty);
+                }
+                w.record(&ids.resolve(rec, 0));
+            }
+            w.end_block();
+        }
+
+        self.md.emit_kinds(&mut w);
+        self.md.emit(&mut w, &ids);
+
+        w.enter_block(block::SYNC_SCOPE_NAMES, 2);
+        for name in ["singlethread", ""]
+            .iter()
+            .copied()
+            .chain(self.sync_scopes.iter().map(String::as_str))
+        {
+            w.record(&string_record(sync_scope::NAME, name));
+        }
+        w.end_block();
+
+        for body in &self.bodies {
+            body.emit(&mut w, &ids);
+        }
+        w.end_block();
+
+        w.enter_block(block::STRTAB, 3);
+        w.record(&Record {
+            code: strtab::BLOB,
+            ops: vec![],
+            blob: Some(self.strtab),
+        });
+        w.end_block();
+        w.into_bytes()
+    }
+
+    fn emit_types(
+        &self,
+        w: &mut BitWriter,
+        ids: &Ids,
+        order: &[TypeId],
+        named: &FxHashMap<TypeId, Name>,
+    ) {
+        w.enter_block(block::TYPE, 4);
+        w.record(&record(ty::NUMENTRY, vec![order.len() as u64]));
+        for id in order {
+            let t = |id: &TypeId| ids.types[id];
+            let r = match &*self.types.get(*id) {
+                Type::Void if named.contains_key(id) => record(ty::OPAQUE, vec![]),
+                Type::Void => record(ty::VOID, vec![]),
+                Type::Int(bits) => record(ty::INTEGER, vec![*bits as u64]),
+                Type::Half => record(ty::HALF, vec![]),
+                Type::Float => record(ty::FLOAT, vec![]),
+                Type::Double => record(ty::DOUBLE, vec![]),
+                Type::X86Fp80 => record(ty::X86_FP80, vec![]),
+                Type::Fp128 => record(ty::FP128, vec![]),
+                Type::Metadata => record(ty::METADATA, vec![]),
+                Type::Pointer(p) => record(ty::POINTER, vec![t(&p.inner), p.addr_space as u64]),
+                Type::Array(a) => record(ty::ARRAY, vec![a.num_elements as u64, t(&a.inner)]),
+                Type::Vector(v) => {
+                    let mut ops = vec![v.num_elements as u64, t(&v.inner)];
+                    if v.is_scalable {
+                        ops.push(1);
+                    }
+                    record(ty::VECTOR, ops)
+                }
+                Type::Function(f) => {
+                    let mut ops = vec![f.is_var_arg as u64, t(&f.ret)];
+                    ops.extend(f.params.iter().map(t));
+                    record(ty::FUNCTION, ops)
+                }
+                Type::Struct(s) => {
+                    let mut ops = vec![s.is_packed as u64];
+                    ops.extend(s.elems.iter().map(t));
+                    let code = if named.contains_key(id) {
+                        ty::STRUCT_NAMED
+                    } else {
+                        ty::STRUCT_ANON
+                    };
+                    record(code, ops)
+                }
+            };
+            // The reader gives a pending name to the next struct, so only structs get one
+            if let Some(Name::Name(name)) = named.get(id) {
+                if r.code == ty::STRUCT_NAMED || r.code == ty::OPAQUE {
+                    w.record(&string_record(ty::STRUCT_NAME, name));
+                }
+            }
+            w.record(&r);
+        }
+        w.end_block();
+    }
+}
+
+/// Encodes a function attribute, dropping references to attribute groups.
+fn func_attr(attr: &Attribute) -> Option<Vec<Op>> {
+    let int = |kind: u64, val: u64| Some(vec![Op::Lit(1), Op::Lit(kind), Op::Lit(val)]);
+    let kind = match attr {
+        Attribute::StringAttribute { kind, value } => return Some(string_attr(kind, value)),
+        Attribute::AlignStack(n) => return int(25, *n),
+        Attribute::AllocSize { elt_size, num_elts } => {
+            return int(
+                51,
+                (*elt_size as u64) << 32 | num_elts.unwrap_or(u32::MAX) as u64,
+            )
+        }
+        Attribute::Ref(_) => return None,
+        Attribute::AlwaysInline => 2,
+        Attribute::InlineHint => 4,
+        Attribute::MinimizeSize => 6,
+        Attribute::Naked => 7,
+        Attribute::NoBuiltin => 10,
+        Attribute::NoDuplicate => 12,
+        Attribute::NoImplicitFloat => 13,
+        Attribute::NoInline => 14,
+        Attribute::NonLazyBind => 15,
+        Attribute::NoRedZone => 16,
+        Attribute::NoReturn => 17,
+        Attribute::NoUnwind => 18,
+        Attribute::OptSize => 19,
+        Attribute::ReadNone => 20,
+        Attribute::ReadOnly => 21,
+        Attribute::ReturnsTwice => 23,
+        Attribute::StackProtect => 26,
+        Attribute::StackProtectReq => 27,
+        Attribute::StackProtectStrong => 28,
+        Attribute::SanitizeAddress => 30,
+        Attribute::SanitizeThread => 31,
+        Attribute::SanitizeMemory => 32,
+        Attribute::UWTable => 33,
+        Attribute::Builtin => 35,
+        Attribute::Cold => 36,
+        Attribute::OptNone => 37,
+        Attribute::JumpTable => 40,
+        Attribute::Convergent => 43,
+        Attribute::SafeStack => 44,
+        Attribute::ArgMemOnly => 45,
+        Attribute::NoRecurse => 48,
+        Attribute::InaccessibleMemOnly => 49,
+        Attribute::InaccessibleMemOrArgMemOnly => 50,
+        Attribute::WriteOnly => 52,
+        Attribute::Speculatable => 53,
+        Attribute::StrictFP => 54,
+        Attribute::SanitizeHWAddress => 55,
+        Attribute::NoCFCheck => 56,
+        Attribute::OptForFuzzing => 57,
+        Attribute::ShadowCallStack => 58,
+        Attribute::SpeculativeLoadHardening => 59,
+        Attribute::WillReturn => 61,
+        Attribute::NoFree => 62,
+        Attribute::NoSync => 63,
+        Attribute::SanitizeMemTag => 64,
+    };
+    Some(vec![Op::Lit(0), Op::Lit(kind)])
+}
+
+/// Encodes a parameter attribute, dropping ones that can't be written.
+fn param_attr(attr: &ParameterAttribute) -> Option<Vec<Op>> {
+    let int = |kind: u64, val: u64| Some(vec![Op::Lit(1), Op::Lit(kind), Op::Lit(val)]);
+    let kind = match attr {
+        ParameterAttribute::StringAttribute { kind, value } => {
+            return Some(string_attr(kind, value))
+        }
+        ParameterAttribute::Alignment(n) => return int(1, *n),
+        ParameterAttribute::Dereferenceable(n) => return int(41, *n),
+        ParameterAttribute::DereferenceableOrNull(n) => return int(42, *n),
+        ParameterAttribute::ByVal => return Some(vec![Op::Lit(5), Op::Lit(3)]),
+        ParameterAttribute::SRet(Some(ty)) => {
+            return Some(vec![Op::Lit(6), Op::Lit(29), Op::Ty(*ty)])
+        }
+        ParameterAttribute::SRet(None) => return Some(vec![Op::Lit(5), Op::Lit(29)]),
+        ParameterAttribute::Ref(_) | ParameterAttribute::UnknownAttribute => return None,
+        ParameterAttribute::InReg => 5,
+        ParameterAttribute::Nest => 8,
+        ParameterAttribute::NoAlias => 9,
+        ParameterAttribute::NoCapture => 11,
+        ParameterAttribute::ReadOnly => 21,
+        ParameterAttribute::Returned => 22,
+        ParameterAttribute::SignExt => 24,
+        ParameterAttribute::ZeroExt => 34,
+        ParameterAttribute::InAlloca => 38,
+        ParameterAttribute::NonNull => 39,
+        ParameterAttribute::SwiftSelf => 46,
+        ParameterAttribute::SwiftError => 47,
+        ParameterAttribute::ImmArg => 60,
+        ParameterAttribute::NoFree => 62,
+    };
+    Some(vec![Op::Lit(0), Op::Lit(kind)])
+}
+
+/// `"key"` or `"key"="value"`, each string terminated by NUL.
+fn string_attr(kind: &str, value: &str) -> Vec<Op> {
+    let mut ops = vec![Op::Lit(if value.is_empty() { 3 } else { 4 })];
+    ops.extend(chars(kind));
+    ops.push(Op::Lit(0));
+    if !value.is_empty() {
+        ops.extend(chars(value));
+        ops.push(Op::Lit(0));
+    }
+    ops
+}
+
+/// The operands of a FLOAT record.
+fn float(f: &ConstantFloat) -> Vec<u64> {
+    match f {
+        ConstantFloat::X86Fp80(bits) => {
+            // The 16-bit exponent and sign come first, followed by the 64-bit significand.
+            let (lo, hi) = (*bits as u64, (*bits >> 64) as u64 & 0xffff);
+            vec![(hi << 48) | (lo >> 16), lo & 0xffff]
+        }
+        ConstantFloat::Fp128(bits) => vec![*bits as u64, (*bits >> 64) as u64],
+        f => vec![f.to_bits() as u64],
+    }
+}
+
+pub(super) fn binop(opcode: Opcode) -> Result<u64> {
+    Ok(match opcode {
+        Opcode::Add | Opcode::FAdd => 0,
+        Opcode::Sub | Opcode::FSub => 1,
+        Opcode::Mul | Opcode::FMul => 2,
+        Opcode::UDiv => 3,
+        Opcode::SDiv | Opcode::FDiv => 4,
+        Opcode::URem => 5,
+        Opcode::SRem | Opcode::FRem => 6,
+        Opcode::Shl => 7,
+        Opcode::LShr => 8,
+        Opcode::AShr => 9,
+        Opcode::And => 10,
+        Opcode::Or => 11,
+        Opcode::Xor => 12,
+        _ => return err("not a binary operator"),
+    })
+}
+
+/// `nuw` and `nsw`, or `exact`, as a BINOP record has them.
+pub(super) fn binop_flags(opcode: Opcode, nuw: bool, nsw: bool, exact: bool) -> u64 {
+    if opcode.has_exact_flag() {
+        exact as u64
+    } else {
+        nuw as u64 | (nsw as u64) << 1
+    }
+}
+
+pub(super) fn cast(opcode: Opcode) -> Result<u64> {
+    Ok(match opcode {
+        Opcode::Trunc => 0,
+        Opcode::Zext => 1,
+        Opcode::Sext => 2,
+        Opcode::FPToUI => 3,
+        Opcode::FPToSI => 4,
+        Opcode::UIToFP => 5,
+        Opcode::SIToFP => 6,
+        Opcode::FPTrunc => 7,
+        Opcode::FPExt => 8,
+        Opcode::PtrToInt => 9,
+        Opcode::IntToPtr => 10,
+        Opcode::Bitcast => 11,
+        Opcode::AddrSpaceCast => 12,
+        _ => return err("not a cast"),
+    })
+}
+
+pub(super) fn fcmp_cond(cond: FCmpCond) -> u64 {
+    match cond {
+        FCmpCond::False => 0,
+        FCmpCond::Oeq => 1,
+        FCmpCond::Ogt => 2,
+        FCmpCond::Oge => 3,
+        FCmpCond::Olt => 4,
+        FCmpCond::Ole => 5,
+        FCmpCond::One => 6,
+        FCmpCond::Ord => 7,
+        FCmpCond::Uno => 8,
+        FCmpCond::Ueq => 9,
+        FCmpCond::Ugt => 10,
+        FCmpCond::Uge => 11,
+        FCmpCond::Ult => 12,
+        FCmpCond::Ule => 13,
+        FCmpCond::Une => 14,
+        FCmpCond::True => 15,
+    }
+}
+
+pub(super) fn icmp_cond(cond: ICmpCond) -> u64 {
+    match cond {
+        ICmpCond::Eq => 32,
+        ICmpCond::Ne => 33,
+        ICmpCond::Ugt => 34,
+        ICmpCond::Uge => 35,
+        ICmpCond::Ult => 36,
+        ICmpCond::Ule => 37,
+        ICmpCond::Sgt => 38,
+        ICmpCond::Sge => 39,
+        ICmpCond::Slt => 40,
+        ICmpCond::Sle => 41,
+    }
+}
+
+pub(super) fn encode_align(align: u32) -> u64 {
+    match align {
+        0 => 0,
+        n => n.trailing_zeros() as u64 + 1,
+    }
+}
+
+fn linkage_code(linkage: Linkage) -> u64 {
+    match linkage {
+        Linkage::External | Linkage::DLLImport | Linkage::DLLExport | Linkage::Ghost => 0,
+        Linkage::Appending => 2,
+        Linkage::Internal => 3,
+        Linkage::ExternalWeak => 7,
+        Linkage::Common => 8,
+        Linkage::Private | Linkage::LinkerPrivate | Linkage::LinkerPrivateWeak => 9,
+        Linkage::AvailableExternally => 12,
+        Linkage::WeakAny => 16,
+        Linkage::WeakODR => 17,
+        Linkage::LinkOnceAny => 18,
+        Linkage::LinkOnceODR | Linkage::LinkOnceODRAutoHide => 19,
+    }
+}
+
+/// The `dso_local` flag, which is set as well when local linkage or non-default visibility
+/// implies it.
+fn dso_local(ps: Option<PreemptionSpecifier>, linkage: Linkage, visibility: Visibility) -> u64 {
+    let implicit = matches!(linkage, Linkage::Private | Linkage::Internal)
+        || (visibility != Visibility::Default && linkage != Linkage::ExternalWeak);
+    (ps == Some(PreemptionSpecifier::DsoLocal) || implicit) as u64
+}
+
+fn visibility_code(visibility: Visibility) -> u64 {
+    match visibility {
+        Visibility::Default => 0,
+        Visibility::Hidden => 1,
+        Visibility::Protected => 2,
+    }
+}
+
+fn unnamed_addr_code(unnamed_addr: Option<UnnamedAddr>) -> u64 {
+    match unnamed_addr {
+        None => 0,
+        Some(UnnamedAddr::Global) => 1,
+        Some(UnnamedAddr::Local) => 2,
+    }
+}
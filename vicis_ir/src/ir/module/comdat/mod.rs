@@ -0,0 +1,40 @@
+pub mod parser;
+
+pub use parser::parse;
+
+use super::name::Name;
+use std::fmt;
+
+/// `$<Name> = comdat <SelectionKind>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comdat {
+    pub name: Name,
+    pub kind: SelectionKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SelectionKind {
+    Any,
+    ExactMatch,
+    Largest,
+    NoDeduplicate,
+    SameSize,
+}
+
+impl fmt::Display for Comdat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${} = comdat {:?}", self.name, self.kind)
+    }
+}
+
+impl fmt::Debug for SelectionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => write!(f, "any"),
+            Self::ExactMatch => write!(f, "exactmatch"),
+            Self::Largest => write!(f, "largest"),
+            Self::NoDeduplicate => write!(f, "nodeduplicate"),
+            Self::SameSize => write!(f, "samesize"),
+        }
+    }
+}
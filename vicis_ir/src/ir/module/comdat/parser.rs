@@ -0,0 +1,54 @@
+use super::{Comdat, SelectionKind};
+use crate::ir::{
+    module::name::{self, Name},
+    util::spaces,
+};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{map, opt},
+    error::VerboseError,
+    sequence::{delimited, preceded},
+    IResult,
+};
+
+// $<Name> = comdat SelectionKind
+
+pub fn parse(source: &str) -> IResult<&str, Comdat, VerboseError<&str>> {
+    let (source, name) = preceded(spaces, preceded(char('$'), name::parse))(source)?;
+    let (source, _) = preceded(spaces, char('='))(source)?;
+    let (source, _) = preceded(spaces, tag("comdat"))(source)?;
+    let (source, kind) = preceded(spaces, parse_selection_kind)(source)?;
+    Ok((source, Comdat { name, kind }))
+}
+
+pub fn parse_selection_kind(source: &str) -> IResult<&str, SelectionKind, VerboseError<&str>> {
+    alt((
+        map(tag("any"), |_| SelectionKind::Any),
+        map(tag("exactmatch"), |_| SelectionKind::ExactMatch),
+        map(tag("largest"), |_| SelectionKind::Largest),
+        map(tag("nodeduplicate"), |_| SelectionKind::NoDeduplicate),
+        // The old spelling of `nodeduplicate`
+        map(tag("noduplicates"), |_| SelectionKind::NoDeduplicate),
+        map(tag("samesize"), |_| SelectionKind::SameSize),
+    ))(source)
+}
+
+/// Parses `comdat` or `comdat($name)` attached to a global object. A bare `comdat` refers to
+/// the comdat named after the object, so `Name` of the object itself is returned for it.
+pub fn parse_comdat_ref<'a>(
+    source: &'a str,
+    object: &Name,
+) -> IResult<&'a str, Name, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("comdat"))(source)?;
+    let (source, name) = opt(preceded(
+        spaces,
+        delimited(
+            char('('),
+            preceded(spaces, preceded(char('$'), name::parse)),
+            preceded(spaces, char(')')),
+        ),
+    ))(source)?;
+    Ok((source, name.unwrap_or_else(|| object.clone())))
+}
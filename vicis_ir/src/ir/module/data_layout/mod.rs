@@ -0,0 +1,304 @@
+pub mod parser;
+
+pub use parser::parse;
+
+use crate::ir::types::{AddrSpace, ArrayType, StructType, Type, TypeId, Types, VectorType};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// ABI and preferred alignments in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Align {
+    pub abi: u32,
+    pub pref: u32,
+}
+
+/// Size, alignment and GEP index size (all in bytes) of pointers in an address space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointerLayout {
+    pub size: u32,
+    pub align: Align,
+    pub index_size: u32,
+}
+
+/// Sizes and offsets (in bytes) of a struct type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout {
+    pub size: u64,
+    pub align: u32,
+    pub offsets: Vec<u64>,
+}
+
+/// The parsed form of `target datalayout = "..."`.
+/// Integer, float and vector alignments are keyed by their bit width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataLayout {
+    pub endianness: Endianness,
+    pub stack_align: Option<u32>,
+    pub program_addr_space: AddrSpace,
+    pub globals_addr_space: AddrSpace,
+    pub alloca_addr_space: AddrSpace,
+    pub mangling: Option<char>,
+    pub function_ptr_align: Option<u32>,
+    pub pointers: BTreeMap<AddrSpace, PointerLayout>,
+    pub ints: BTreeMap<u32, Align>,
+    pub floats: BTreeMap<u32, Align>,
+    pub vectors: BTreeMap<u32, Align>,
+    pub aggregate: Align,
+    pub native_ints: Vec<u32>,
+    pub non_integral_addr_spaces: Vec<AddrSpace>,
+}
+
+impl Default for DataLayout {
+    /// Returns LLVM's default layout, which an empty `datalayout` string describes.
+    fn default() -> Self {
+        let align = |abi, pref| Align { abi, pref };
+        Self {
+            endianness: Endianness::Little,
+            stack_align: None,
+            program_addr_space: 0,
+            globals_addr_space: 0,
+            alloca_addr_space: 0,
+            mangling: None,
+            function_ptr_align: None,
+            pointers: vec![(
+                0,
+                PointerLayout {
+                    size: 8,
+                    align: align(8, 8),
+                    index_size: 8,
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ints: vec![
+                (1, align(1, 1)),
+                (8, align(1, 1)),
+                (16, align(2, 2)),
+                (32, align(4, 4)),
+                (64, align(4, 8)),
+            ]
+            .into_iter()
+            .collect(),
+            floats: vec![
+                (16, align(2, 2)),
+                (32, align(4, 4)),
+                (64, align(8, 8)),
+                (128, align(16, 16)),
+            ]
+            .into_iter()
+            .collect(),
+            vectors: vec![(64, align(8, 8)), (128, align(16, 16))]
+                .into_iter()
+                .collect(),
+            aggregate: align(1, 8),
+            native_ints: vec![],
+            non_integral_addr_spaces: vec![],
+        }
+    }
+}
+
+impl DataLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_little_endian(&self) -> bool {
+        self.endianness == Endianness::Little
+    }
+
+    /// Returns the pointer layout of `addr_space`. Address spaces without their own
+    /// specification use that of address space 0.
+    pub fn pointer_layout(&self, addr_space: AddrSpace) -> PointerLayout {
+        self.pointers
+            .get(&addr_space)
+            .or_else(|| self.pointers.get(&0))
+            .copied()
+            .unwrap_or(PointerLayout {
+                size: 8,
+                align: Align { abi: 8, pref: 8 },
+                index_size: 8,
+            })
+    }
+
+    pub fn pointer_size(&self, addr_space: AddrSpace) -> u64 {
+        self.pointer_layout(addr_space).size as u64
+    }
+
+    /// Returns the number of bits needed to hold a value of `ty`.
+    /// Scalable vectors are measured with `vscale` being 1.
+    pub fn size_in_bits(&self, types: &Types, ty: TypeId) -> u64 {
+        match &*types.get(ty) {
+            Type::Void | Type::Function(_) | Type::Metadata => 0,
+            Type::Int(bits) => *bits as u64,
+            Type::Half => 16,
+            Type::Float => 32,
+            Type::Double => 64,
+            Type::X86Fp80 => 80,
+            Type::Fp128 => 128,
+            Type::Pointer(p) => self.pointer_size(p.addr_space) * 8,
+            Type::Array(ArrayType {
+                inner,
+                num_elements,
+            }) => self.alloc_size_of(types, *inner) * *num_elements as u64 * 8,
+            Type::Vector(VectorType {
+                inner,
+                num_elements,
+                ..
+            }) => self.size_in_bits(types, *inner) * *num_elements as u64,
+            Type::Struct(s) => self.struct_layout(types, s).size * 8,
+        }
+    }
+
+    /// Returns the number of bytes a store of `ty` may overwrite.
+    pub fn size_of(&self, types: &Types, ty: TypeId) -> u64 {
+        self.size_in_bits(types, ty).div_ceil(8)
+    }
+
+    /// Returns the distance in bytes between successive elements of `ty` in an array,
+    /// i.e. `size_of` rounded up to `abi_align_of`.
+    pub fn alloc_size_of(&self, types: &Types, ty: TypeId) -> u64 {
+        align_to(self.size_of(types, ty), self.abi_align_of(types, ty))
+    }
+
+    /// Returns the ABI alignment of `ty` in bytes.
+    pub fn abi_align_of(&self, types: &Types, ty: TypeId) -> u32 {
+        match &*types.get(ty) {
+            Type::Void | Type::Function(_) | Type::Metadata => 1,
+            Type::Int(bits) => self.int_align(*bits).abi,
+            Type::Half => self.float_align(16).abi,
+            Type::Float => self.float_align(32).abi,
+            Type::Double => self.float_align(64).abi,
+            Type::X86Fp80 => self.float_align(80).abi,
+            Type::Fp128 => self.float_align(128).abi,
+            Type::Pointer(p) => self.pointer_layout(p.addr_space).align.abi,
+            Type::Array(ArrayType { inner, .. }) => self.abi_align_of(types, *inner),
+            Type::Vector(_) => {
+                let bits = self.size_in_bits(types, ty);
+                match self.vectors.get(&(bits as u32)) {
+                    Some(align) => align.abi,
+                    // Vectors are naturally aligned by default
+                    None => bits.div_ceil(8).next_power_of_two() as u32,
+                }
+            }
+            Type::Struct(s) => self.struct_layout(types, s).align,
+        }
+    }
+
+    /// Returns the layout of `strukt`. Fields of a packed struct are not padded.
+    pub fn struct_layout(&self, types: &Types, strukt: &StructType) -> StructLayout {
+        let mut offsets = Vec::with_capacity(strukt.elems.len());
+        let mut size = 0;
+        let mut align = if strukt.is_packed {
+            1
+        } else {
+            self.aggregate.abi
+        };
+        for &elem in &strukt.elems {
+            let elem_align = if strukt.is_packed {
+                1
+            } else {
+                self.abi_align_of(types, elem)
+            };
+            size = align_to(size, elem_align);
+            offsets.push(size);
+            size += self.alloc_size_of(types, elem);
+            align = align.max(elem_align);
+        }
+        StructLayout {
+            size: align_to(size, align),
+            align,
+            offsets,
+        }
+    }
+
+    /// Returns the alignment of `iN`. Widths without their own specification use the
+    /// smallest wider one, or the widest one if there is none.
+    fn int_align(&self, bits: u32) -> Align {
+        self.ints
+            .range(bits..)
+            .next()
+            .or_else(|| self.ints.iter().next_back())
+            .map_or(Align { abi: 1, pref: 1 }, |(_, align)| *align)
+    }
+
+    /// Returns the alignment of a floating-point type `bits` wide, which is natural if
+    /// not specified.
+    fn float_align(&self, bits: u32) -> Align {
+        self.floats.get(&bits).copied().unwrap_or_else(|| {
+            let natural = bits.div_ceil(8).next_power_of_two();
+            Align {
+                abi: natural,
+                pref: natural,
+            }
+        })
+    }
+}
+
+fn align_to(size: u64, align: u32) -> u64 {
+    let align = align.max(1) as u64;
+    size.div_ceil(align) * align
+}
+
+#[test]
+fn test_data_layout() {
+    let dl = parse("e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128")
+        .unwrap()
+        .1;
+    let types = Types::new();
+    let i1 = types.base().i1();
+    let i8 = types.base().i8();
+    let i16 = types.base().i16();
+    let i32 = types.base().i32();
+    let i64 = types.base().i64();
+    let fp80 = types.base().x86_fp80();
+    let i8ptr = types.base_mut().pointer(i8);
+    let i8ptr32 = types.base_mut().pointer_in_addr_space(i8, 270);
+    let i128 = types.base_mut().int(128);
+    let i24 = types.base_mut().int(24);
+    let ary = types.base_mut().array(i16, 3);
+    let v3i32 = types.base_mut().vector(i32, 3, false);
+    let strukt = types.base_mut().anonymous_struct(vec![i8, i64, i16], false);
+    let packed = types.base_mut().anonymous_struct(vec![i8, i64, i16], true);
+    for (ty, size, alloc_size, align) in [
+        (i1, 1, 1, 1),
+        (i24, 3, 4, 4),
+        (i64, 8, 8, 8),
+        (i128, 16, 16, 8),
+        (fp80, 10, 16, 16),
+        (i8ptr, 8, 8, 8),
+        (i8ptr32, 4, 4, 4),
+        (ary, 6, 6, 2),
+        (v3i32, 12, 16, 16),
+        (strukt, 24, 24, 8),
+        (packed, 11, 11, 1),
+    ] {
+        assert_eq!(dl.size_of(&types, ty), size, "{}", types.to_string(ty));
+        assert_eq!(dl.alloc_size_of(&types, ty), alloc_size);
+        assert_eq!(dl.abi_align_of(&types, ty), align);
+    }
+    let strukt = types.get(strukt).as_struct().clone();
+    assert_eq!(dl.struct_layout(&types, &strukt).offsets, vec![0, 8, 16]);
+    let packed = types.get(packed).as_struct().clone();
+    assert_eq!(dl.struct_layout(&types, &packed).offsets, vec![0, 1, 9]);
+    assert_eq!(dl.native_ints, vec![8, 16, 32, 64]);
+    assert_eq!(dl.stack_align, Some(16));
+    assert_eq!(dl.mangling, Some('e'));
+
+    let dl = parse("E-p:32:32-i64:32").unwrap().1;
+    assert!(!dl.is_little_endian());
+    assert_eq!(dl.pointer_size(1), 4);
+    assert_eq!(dl.abi_align_of(&types, i64), 4);
+    assert_eq!(parse("").unwrap().1, DataLayout::default());
+
+    // Specs LLVM added later are skipped
+    let (rest, dl) = parse("e-Fn8-z:16-p:32:32:32:32:8-i64:64\"").unwrap();
+    assert_eq!(rest, "\"");
+    assert_eq!(dl.pointer_size(0), 8);
+    assert_eq!(dl.abi_align_of(&types, i64), 8);
+}
@@ -0,0 +1,138 @@
+use super::{Align, DataLayout, Endianness, PointerLayout};
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag},
+    character::complete::{anychar, char, digit1, none_of, one_of},
+    combinator::{map, map_res, not, opt, peek},
+    error::VerboseError,
+    multi::{separated_list0, separated_list1},
+    sequence::{preceded, terminated, tuple},
+    IResult,
+};
+
+/// One `-` separated item of a data layout string. Sizes are in bits.
+enum Spec {
+    Endianness(Endianness),
+    StackAlign(u32),
+    ProgramAddrSpace(u32),
+    GlobalsAddrSpace(u32),
+    AllocaAddrSpace(u32),
+    Mangling(char),
+    FunctionPtrAlign(u32),
+    Pointer(u32, u32, u32, Option<u32>, Option<u32>), // addrspace, size, abi, pref, index size
+    Align(char, Option<u32>, u32, Option<u32>),       // kind, size, abi, pref
+    NativeInts(Vec<u32>),
+    NonIntegral(Vec<u32>),
+    /// A spec this parser doesn't know, e.g. one added by a newer LLVM
+    Unknown,
+}
+
+/// Parses the contents of `target datalayout = "..."`. Unspecified items keep their
+/// defaults.
+pub fn parse(source: &str) -> IResult<&str, DataLayout, VerboseError<&str>> {
+    let (source, specs) = separated_list0(char('-'), parse_spec)(source)?;
+    let mut dl = DataLayout::default();
+    let bytes = |bits: u32| bits / 8;
+    for spec in specs {
+        match spec {
+            Spec::Endianness(e) => dl.endianness = e,
+            Spec::StackAlign(a) => dl.stack_align = Some(bytes(a)),
+            Spec::ProgramAddrSpace(a) => dl.program_addr_space = a,
+            Spec::GlobalsAddrSpace(a) => dl.globals_addr_space = a,
+            Spec::AllocaAddrSpace(a) => dl.alloca_addr_space = a,
+            Spec::Mangling(m) => dl.mangling = Some(m),
+            Spec::FunctionPtrAlign(a) => dl.function_ptr_align = Some(bytes(a)),
+            Spec::Pointer(addr_space, size, abi, pref, idx) => {
+                dl.pointers.insert(
+                    addr_space,
+                    PointerLayout {
+                        size: bytes(size),
+                        align: Align {
+                            abi: bytes(abi),
+                            pref: bytes(pref.unwrap_or(abi)),
+                        },
+                        index_size: bytes(idx.unwrap_or(size)),
+                    },
+                );
+            }
+            Spec::Align(kind, size, abi, pref) => {
+                let align = Align {
+                    abi: bytes(abi).max(1),
+                    pref: bytes(pref.unwrap_or(abi)).max(1),
+                };
+                let aligns = match (kind, size) {
+                    ('i', Some(_)) => &mut dl.ints,
+                    ('f', Some(_)) => &mut dl.floats,
+                    ('v', Some(_)) => &mut dl.vectors,
+                    _ => {
+                        dl.aggregate = align;
+                        continue;
+                    }
+                };
+                aligns.insert(size.unwrap(), align);
+            }
+            Spec::NativeInts(n) => dl.native_ints = n,
+            Spec::NonIntegral(n) => dl.non_integral_addr_spaces = n,
+            Spec::Unknown => {}
+        }
+    }
+    Ok((source, dl))
+}
+
+/// Parses one spec up to the next `-` or the end of the string. Specs that aren't understood
+/// are skipped rather than failing the whole module.
+fn parse_spec(source: &str) -> IResult<&str, Spec, VerboseError<&str>> {
+    alt((
+        terminated(parse_known_spec, peek(not(none_of("-\"")))),
+        map(is_not("-\""), |_| Spec::Unknown),
+    ))(source)
+}
+
+fn parse_known_spec(source: &str) -> IResult<&str, Spec, VerboseError<&str>> {
+    alt((
+        map(char('e'), |_| Spec::Endianness(Endianness::Little)),
+        map(char('E'), |_| Spec::Endianness(Endianness::Big)),
+        map(preceded(char('S'), uint), Spec::StackAlign),
+        map(preceded(char('P'), uint), Spec::ProgramAddrSpace),
+        map(preceded(char('G'), uint), Spec::GlobalsAddrSpace),
+        map(preceded(char('A'), uint), Spec::AllocaAddrSpace),
+        map(preceded(tag("m:"), anychar), Spec::Mangling),
+        map(
+            preceded(tuple((char('F'), one_of("in"))), uint),
+            Spec::FunctionPtrAlign,
+        ),
+        map(
+            tuple((
+                preceded(char('p'), opt(uint)),
+                preceded(char(':'), uint),
+                preceded(char(':'), uint),
+                opt(preceded(char(':'), uint)),
+                opt(preceded(char(':'), uint)),
+            )),
+            |(addr_space, size, abi, pref, idx)| {
+                Spec::Pointer(addr_space.unwrap_or(0), size, abi, pref, idx)
+            },
+        ),
+        map(
+            preceded(tag("ni:"), separated_list1(char(':'), uint)),
+            Spec::NonIntegral,
+        ),
+        map(
+            preceded(char('n'), separated_list1(char(':'), uint)),
+            Spec::NativeInts,
+        ),
+        map(
+            tuple((
+                one_of("ifva"),
+                opt(uint),
+                preceded(char(':'), uint),
+                opt(preceded(char(':'), uint)),
+            )),
+            |(kind, size, abi, pref)| Spec::Align(kind, size, abi, pref),
+        ),
+    ))(source)
+}
+
+fn uint(source: &str) -> IResult<&str, u32, VerboseError<&str>> {
+    map_res(digit1, |n: &str| n.parse())(source)
+}
@@ -0,0 +1,46 @@
+mod parser;
+
+pub use parser::parse;
+
+use crate::ir::{
+    module::{
+        linkage::Linkage, name::Name, preemption_specifier::PreemptionSpecifier,
+        unnamed_addr::UnnamedAddr, visibility::Visibility,
+    },
+    types::{TypeId, Types},
+    value::ConstantData,
+};
+
+/// `@<Name> = [Linkage] [PreemptionSpecifier] [Visibility] [(unnamed_addr|local_unnamed_addr)]
+/// alias <ValueTy>, <AliaseeTy> <Aliasee>`
+#[derive(Clone)]
+pub struct GlobalAlias {
+    pub name: Name,
+    pub linkage: Option<Linkage>,
+    pub preemption_specifier: Option<PreemptionSpecifier>,
+    pub visibility: Option<Visibility>,
+    pub unnamed_addr: Option<UnnamedAddr>,
+    pub ty: TypeId,
+    pub aliasee_ty: TypeId,
+    pub aliasee: ConstantData,
+}
+
+impl GlobalAlias {
+    pub fn to_string(&self, types: &Types) -> String {
+        format!(
+            "@{} = {}{}{}{}alias {}, {} {}",
+            self.name,
+            self.linkage
+                .map_or("".to_string(), |linkage| format!("{:?} ", linkage)),
+            self.preemption_specifier
+                .map_or("".to_string(), |p| format!("{:?} ", p)),
+            self.visibility
+                .map_or("".to_string(), |v| format!("{:?} ", v)),
+            self.unnamed_addr
+                .map_or("".to_string(), |u| format!("{:?} ", u)),
+            types.to_string(self.ty),
+            types.to_string(self.aliasee_ty),
+            self.aliasee.to_string(types)
+        )
+    }
+}
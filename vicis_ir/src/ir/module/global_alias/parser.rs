@@ -0,0 +1,48 @@
+use crate::ir::{
+    module::{
+        global_alias::GlobalAlias, linkage, name, preemption_specifier, unnamed_addr, visibility,
+    },
+    types::{self, Types},
+    util::spaces,
+    value,
+};
+use nom::{
+    bytes::complete::tag, character::complete::char, combinator::opt, error::VerboseError,
+    sequence::preceded, IResult,
+};
+
+// @<Name> = [Linkage] [PreemptionSpecifier] [Visibility]
+//           [DLLStorageClass] [ThreadLocal] [(unnamed_addr|local_unnamed_addr)]
+//           alias <AliaseeTy>, <AliaseeTy>* @<Aliasee>
+//           [, partition "name"]
+
+pub fn parse<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, GlobalAlias, VerboseError<&'a str>> {
+    let (source, name) = preceded(spaces, preceded(char('@'), name::parse))(source)?;
+    let (source, _) = preceded(spaces, char('='))(source)?;
+    let (source, linkage) = opt(preceded(spaces, linkage::parse))(source)?;
+    let (source, preemption_specifier) =
+        opt(preceded(spaces, preemption_specifier::parse))(source)?;
+    let (source, visibility) = opt(preceded(spaces, visibility::parse))(source)?;
+    let (source, unnamed_addr) = opt(preceded(spaces, unnamed_addr::parse))(source)?;
+    let (source, _) = preceded(spaces, tag("alias"))(source)?;
+    let (source, ty) = types::parse(source, types)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, aliasee_ty) = types::parse(source, types)?;
+    let (source, aliasee) = value::parser::parse_constant(source, types, aliasee_ty)?;
+    Ok((
+        source,
+        GlobalAlias {
+            name,
+            linkage,
+            preemption_specifier,
+            visibility,
+            unnamed_addr,
+            ty,
+            aliasee_ty,
+            aliasee,
+        },
+    ))
+}
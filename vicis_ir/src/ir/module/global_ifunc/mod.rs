@@ -0,0 +1,42 @@
+mod parser;
+
+pub use parser::parse;
+
+use crate::ir::{
+    module::{
+        linkage::Linkage, name::Name, preemption_specifier::PreemptionSpecifier,
+        visibility::Visibility,
+    },
+    types::{TypeId, Types},
+    value::ConstantData,
+};
+
+/// `@<Name> = [Linkage] [PreemptionSpecifier] [Visibility] ifunc <IFuncTy>, <ResolverTy> <Resolver>`
+#[derive(Clone)]
+pub struct GlobalIFunc {
+    pub name: Name,
+    pub linkage: Option<Linkage>,
+    pub preemption_specifier: Option<PreemptionSpecifier>,
+    pub visibility: Option<Visibility>,
+    pub ty: TypeId,
+    pub resolver_ty: TypeId,
+    pub resolver: ConstantData,
+}
+
+impl GlobalIFunc {
+    pub fn to_string(&self, types: &Types) -> String {
+        format!(
+            "@{} = {}{}{}ifunc {}, {} {}",
+            self.name,
+            self.linkage
+                .map_or("".to_string(), |linkage| format!("{:?} ", linkage)),
+            self.preemption_specifier
+                .map_or("".to_string(), |p| format!("{:?} ", p)),
+            self.visibility
+                .map_or("".to_string(), |v| format!("{:?} ", v)),
+            types.to_string(self.ty),
+            types.to_string(self.resolver_ty),
+            self.resolver.to_string(types)
+        )
+    }
+}
@@ -0,0 +1,43 @@
+use crate::ir::{
+    module::{global_ifunc::GlobalIFunc, linkage, name, preemption_specifier, visibility},
+    types::{self, Types},
+    util::spaces,
+    value,
+};
+use nom::{
+    bytes::complete::tag, character::complete::char, combinator::opt, error::VerboseError,
+    sequence::preceded, IResult,
+};
+
+// @<Name> = [Linkage] [PreemptionSpecifier] [Visibility]
+//           ifunc <IFuncTy>, <ResolverTy>* @<Resolver>
+//           [, partition "name"]
+
+pub fn parse<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, GlobalIFunc, VerboseError<&'a str>> {
+    let (source, name) = preceded(spaces, preceded(char('@'), name::parse))(source)?;
+    let (source, _) = preceded(spaces, char('='))(source)?;
+    let (source, linkage) = opt(preceded(spaces, linkage::parse))(source)?;
+    let (source, preemption_specifier) =
+        opt(preceded(spaces, preemption_specifier::parse))(source)?;
+    let (source, visibility) = opt(preceded(spaces, visibility::parse))(source)?;
+    let (source, _) = preceded(spaces, tag("ifunc"))(source)?;
+    let (source, ty) = types::parse(source, types)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, resolver_ty) = types::parse(source, types)?;
+    let (source, resolver) = value::parser::parse_constant(source, types, resolver_ty)?;
+    Ok((
+        source,
+        GlobalIFunc {
+            name,
+            linkage,
+            preemption_specifier,
+            visibility,
+            ty,
+            resolver_ty,
+            resolver,
+        },
+    ))
+}
@@ -3,31 +3,72 @@ mod parser;
 pub use parser::{parse, parse_global_type_and_const};
 
 use crate::ir::{
-    module::{linkage::Linkage, name::Name, unnamed_addr::UnnamedAddr},
-    types::{TypeId, Types},
+    module::{
+        linkage::Linkage, metadata::Metadata, name::Name,
+        preemption_specifier::PreemptionSpecifier, unnamed_addr::UnnamedAddr,
+        visibility::Visibility,
+    },
+    types::{AddrSpace, TypeId, Types},
+    util::escape,
     value::ConstantData,
 };
+use std::fmt;
 
 #[derive(Clone)]
 pub struct GlobalVariable {
     pub name: Name,
     pub linkage: Option<Linkage>,
+    pub preemption_specifier: Option<PreemptionSpecifier>,
+    pub visibility: Option<Visibility>,
+    pub thread_local: Option<ThreadLocalMode>,
     pub unnamed_addr: Option<UnnamedAddr>,
+    pub addr_space: AddrSpace,
+    pub externally_initialized: bool,
     pub is_constant: bool,
     pub ty: TypeId,
     pub init: Option<ConstantData>,
+    pub section: Option<String>,
+    pub partition: Option<String>,
+    pub comdat: Option<Name>,
     pub align: u32,
+    /// Attachments in the order they were written. A kind such as `!type` may repeat.
+    pub metadata: Vec<(String, Metadata)>,
+}
+
+/// `thread_local` is the same as `thread_local(generaldynamic)`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ThreadLocalMode {
+    GeneralDynamic,
+    LocalDynamic,
+    InitialExec,
+    LocalExec,
 }
 
 impl GlobalVariable {
     pub fn to_string(&self, types: &Types) -> String {
-        format!(
-            "@{} = {}{}{}{} {}{}",
+        let mut s = format!(
+            "@{} = {}{}{}{}{}{}{}{}{}{}",
             self.name,
             self.linkage
                 .map_or("".to_string(), |linkage| format!("{:?} ", linkage)),
+            self.preemption_specifier
+                .map_or("".to_string(), |p| format!("{:?} ", p)),
+            self.visibility
+                .map_or("".to_string(), |v| format!("{:?} ", v)),
+            self.thread_local
+                .map_or("".to_string(), |t| format!("{:?} ", t)),
             self.unnamed_addr
                 .map_or("".to_string(), |u| format!("{:?} ", u)),
+            if self.addr_space == 0 {
+                "".to_string()
+            } else {
+                format!("addrspace({}) ", self.addr_space)
+            },
+            if self.externally_initialized {
+                "externally_initialized "
+            } else {
+                ""
+            },
             if self.is_constant {
                 "constant "
             } else {
@@ -36,16 +77,40 @@ impl GlobalVariable {
             types.to_string(self.ty),
             self.init.as_ref().map_or("".to_string(), |init| {
                 if matches!(init, ConstantData::AggregateZero) {
-                    "zeroinitializer".to_string()
+                    " zeroinitializer".to_string()
                 } else {
-                    init.to_string(types)
+                    format!(" {}", init.to_string(types))
                 }
             }),
-            if self.align == 0 {
-                "".to_string()
-            } else {
-                format!(", align {}", self.align)
-            }
-        )
+        );
+        if let Some(section) = &self.section {
+            s.push_str(&format!(", section \"{}\"", escape(section)));
+        }
+        if let Some(partition) = &self.partition {
+            s.push_str(&format!(", partition \"{}\"", escape(partition)));
+        }
+        match &self.comdat {
+            Some(comdat) if comdat == &self.name => s.push_str(", comdat"),
+            Some(comdat) => s.push_str(&format!(", comdat(${})", comdat)),
+            None => {}
+        }
+        if self.align > 0 {
+            s.push_str(&format!(", align {}", self.align));
+        }
+        for (kind, meta) in &self.metadata {
+            s.push_str(&format!(", !{} {}", kind, meta.to_string(types)));
+        }
+        s
+    }
+}
+
+impl fmt::Debug for ThreadLocalMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GeneralDynamic => write!(f, "thread_local"),
+            Self::LocalDynamic => write!(f, "thread_local(localdynamic)"),
+            Self::InitialExec => write!(f, "thread_local(initialexec)"),
+            Self::LocalExec => write!(f, "thread_local(localexec)"),
+        }
     }
 }
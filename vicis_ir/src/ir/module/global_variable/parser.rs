@@ -1,17 +1,21 @@
+use super::ThreadLocalMode;
 use crate::ir::{
-    module::{global_variable::GlobalVariable, linkage, name, unnamed_addr},
+    module::{
+        comdat::parser::parse_comdat_ref, global_variable::GlobalVariable, linkage, metadata, name,
+        preemption_specifier, unnamed_addr, visibility,
+    },
     types,
     types::Types,
-    util::spaces,
+    util::{spaces, string_literal},
     value,
 };
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{char, digit1},
-    combinator::opt,
+    combinator::{map, map_res, opt},
     error::VerboseError,
-    sequence::preceded,
+    sequence::{delimited, preceded},
     IResult,
 };
 
@@ -20,8 +24,8 @@ use nom::{
 //                    [(unnamed_addr|local_unnamed_addr)] [AddrSpace]
 //                    [ExternallyInitialized]
 //                    <global | constant> <Type> [<InitializerConstant>]
-//                    [, section "name"] [, comdat [($name)]]
-//                    [, align <Alignment>] (, !name !N)*
+//                    [, section "name"] [, partition "name"]
+//                    [, comdat [($name)]] [, align <Alignment>] (, !name !N)*
 
 pub fn parse<'a>(
     source: &'a str,
@@ -30,29 +34,102 @@ pub fn parse<'a>(
     let (source, name) = preceded(spaces, preceded(char('@'), name::parse))(source)?;
     let (source, _) = preceded(spaces, char('='))(source)?;
     let (source, linkage) = opt(preceded(spaces, linkage::parse))(source)?;
+    let (source, preemption_specifier) =
+        opt(preceded(spaces, preemption_specifier::parse))(source)?;
+    let (source, visibility) = opt(preceded(spaces, visibility::parse))(source)?;
+    let (source, thread_local) = opt(preceded(spaces, parse_thread_local))(source)?;
     let (source, unnamed_addr) = opt(preceded(spaces, unnamed_addr::parse))(source)?;
+    let (source, addr_space) = opt(preceded(
+        spaces,
+        preceded(
+            tag("addrspace"),
+            delimited(
+                preceded(spaces, char('(')),
+                preceded(spaces, uint),
+                preceded(spaces, char(')')),
+            ),
+        ),
+    ))(source)?;
+    let (source, externally_initialized) =
+        opt(preceded(spaces, tag("externally_initialized")))(source)?;
     let (source, kind) = preceded(spaces, alt((tag("global"), tag("constant"))))(source)?;
     let (source, ty) = types::parse(source, types)?;
-    let (source, init) = parse_init(source, types, ty)?;
-    let (source, align) = opt(preceded(
-        spaces,
+    let (mut source, init) = parse_init(source, types, ty)?;
+    let mut gv = GlobalVariable {
+        name,
+        linkage,
+        preemption_specifier,
+        visibility,
+        thread_local,
+        unnamed_addr,
+        addr_space: addr_space.unwrap_or(0),
+        externally_initialized: externally_initialized.is_some(),
+        is_constant: kind == "constant",
+        ty,
+        init,
+        section: None,
+        partition: None,
+        comdat: None,
+        align: 0,
+        metadata: vec![],
+    };
+
+    // Trailing `, <attribute>`s
+    while let Ok((source_, _)) = preceded(spaces, char(','))(source) {
+        if let Ok((source_, section)) = preceded(
+            spaces,
+            preceded(tag("section"), preceded(spaces, string_literal)),
+        )(source_)
+        {
+            gv.section = Some(section);
+            source = source_;
+        } else if let Ok((source_, partition)) = preceded(
+            spaces,
+            preceded(tag("partition"), preceded(spaces, string_literal)),
+        )(source_)
+        {
+            gv.partition = Some(partition);
+            source = source_;
+        } else if let Ok((source_, comdat)) = parse_comdat_ref(source_, &gv.name) {
+            gv.comdat = Some(comdat);
+            source = source_;
+        } else if let Ok((source_, align)) =
+            preceded(spaces, preceded(tag("align"), preceded(spaces, uint)))(source_)
+        {
+            gv.align = align;
+            source = source_;
+        } else {
+            let (source_, kind) =
+                preceded(spaces, preceded(char('!'), name::parser::identifier))(source_)?;
+            let (source_, meta) = metadata::parse_operand(types)(source_)?;
+            gv.metadata.push((kind.to_owned(), meta));
+            source = source_;
+        }
+    }
+
+    Ok((source, gv))
+}
+
+/// Parses a decimal that fits in `u32`.
+fn uint(source: &str) -> IResult<&str, u32, VerboseError<&str>> {
+    map_res(digit1, |n: &str| n.parse())(source)
+}
+
+fn parse_thread_local(source: &str) -> IResult<&str, ThreadLocalMode, VerboseError<&str>> {
+    let (source, _) = tag("thread_local")(source)?;
+    let (source, mode) = opt(delimited(
+        preceded(spaces, char('(')),
         preceded(
-            char(','),
-            preceded(spaces, preceded(tag("align"), preceded(spaces, digit1))),
+            spaces,
+            alt((
+                map(tag("localdynamic"), |_| ThreadLocalMode::LocalDynamic),
+                map(tag("initialexec"), |_| ThreadLocalMode::InitialExec),
+                map(tag("localexec"), |_| ThreadLocalMode::LocalExec),
+            )),
         ),
+        preceded(spaces, char(')')),
     ))(source)?;
-    Ok((
-        source,
-        GlobalVariable {
-            name,
-            linkage,
-            unnamed_addr,
-            is_constant: kind == "constant",
-            ty,
-            init,
-            align: align.map_or(0, |align| align.parse::<u32>().unwrap()),
-        },
-    ))
+    Ok((source, mode.unwrap_or(ThreadLocalMode::GeneralDynamic)))
 }
 
 pub fn parse_init<'a>(
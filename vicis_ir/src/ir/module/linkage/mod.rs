@@ -4,7 +4,7 @@ pub use parser::parse;
 
 use std::fmt;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Linkage {
     Private,
     Internal,
@@ -31,20 +31,20 @@ impl fmt::Debug for Linkage {
             Self::Private => write!(f, "private"),
             Self::Internal => write!(f, "internal"),
             Self::External => write!(f, "external"),
-            Self::ExternalWeak => write!(f, "externalweak"),
-            Self::AvailableExternally => write!(f, "availableexternally"),
-            Self::LinkOnceAny => write!(f, "linkonceany"),
-            Self::LinkOnceODR => write!(f, "linkonceodr"),
-            Self::LinkOnceODRAutoHide => write!(f, "linkonceodrautohide"),
-            Self::WeakAny => write!(f, "weakany"),
-            Self::WeakODR => write!(f, "weakodr"),
+            Self::ExternalWeak => write!(f, "extern_weak"),
+            Self::AvailableExternally => write!(f, "available_externally"),
+            Self::LinkOnceAny => write!(f, "linkonce"),
+            Self::LinkOnceODR => write!(f, "linkonce_odr"),
+            Self::LinkOnceODRAutoHide => write!(f, "linkonce_odr_auto_hide"),
+            Self::WeakAny => write!(f, "weak"),
+            Self::WeakODR => write!(f, "weak_odr"),
             Self::Common => write!(f, "common"),
             Self::Appending => write!(f, "appending"),
             Self::DLLImport => write!(f, "dllimport"),
             Self::DLLExport => write!(f, "dllexport"),
             Self::Ghost => write!(f, "ghost"),
-            Self::LinkerPrivate => write!(f, "linkerprivate"),
-            Self::LinkerPrivateWeak => write!(f, "linkerprivateweak"),
+            Self::LinkerPrivate => write!(f, "linker_private"),
+            Self::LinkerPrivateWeak => write!(f, "linker_private_weak"),
         }
     }
 }
@@ -1,24 +1,41 @@
 use super::Linkage;
-use nom::{branch::alt, bytes::complete::tag, combinator::map, error::VerboseError, IResult};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::satisfy,
+    combinator::{map, not},
+    error::VerboseError,
+    sequence::terminated,
+    IResult,
+};
 
 pub fn parse(source: &str) -> IResult<&str, Linkage, VerboseError<&str>> {
-    alt((
-        map(tag("private"), |_| Linkage::Private),
-        map(tag("internal"), |_| Linkage::Internal),
-        map(tag("external"), |_| Linkage::External),
-        map(tag("externalweak"), |_| Linkage::ExternalWeak),
-        map(tag("availableexternally"), |_| Linkage::AvailableExternally),
-        map(tag("linkonceany"), |_| Linkage::LinkOnceAny),
-        map(tag("linkonceodr"), |_| Linkage::LinkOnceODR),
-        map(tag("linkonceodrautohide"), |_| Linkage::LinkOnceODRAutoHide),
-        map(tag("weakany"), |_| Linkage::WeakAny),
-        map(tag("weakodr"), |_| Linkage::WeakODR),
-        map(tag("common"), |_| Linkage::Common),
-        map(tag("appending"), |_| Linkage::Appending),
-        map(tag("dllimport"), |_| Linkage::DLLImport),
-        map(tag("dllexport"), |_| Linkage::DLLExport),
-        map(tag("ghost"), |_| Linkage::Ghost),
-        map(tag("linkerprivate"), |_| Linkage::LinkerPrivate),
-        map(tag("linkerprivateweak"), |_| Linkage::LinkerPrivateWeak),
-    ))(source)
+    // Longer keywords come first so that e.g. `linkonce_odr` is not taken for `linkonce`
+    terminated(
+        alt((
+            map(tag("private"), |_| Linkage::Private),
+            map(tag("internal"), |_| Linkage::Internal),
+            map(tag("external"), |_| Linkage::External),
+            map(tag("extern_weak"), |_| Linkage::ExternalWeak),
+            map(tag("available_externally"), |_| {
+                Linkage::AvailableExternally
+            }),
+            map(tag("linkonce_odr_auto_hide"), |_| {
+                Linkage::LinkOnceODRAutoHide
+            }),
+            map(tag("linkonce_odr"), |_| Linkage::LinkOnceODR),
+            map(tag("linkonce"), |_| Linkage::LinkOnceAny),
+            map(tag("weak_odr"), |_| Linkage::WeakODR),
+            map(tag("weak"), |_| Linkage::WeakAny),
+            map(tag("common"), |_| Linkage::Common),
+            map(tag("appending"), |_| Linkage::Appending),
+            map(tag("dllimport"), |_| Linkage::DLLImport),
+            map(tag("dllexport"), |_| Linkage::DLLExport),
+            map(tag("ghost"), |_| Linkage::Ghost),
+            map(tag("linker_private_weak"), |_| Linkage::LinkerPrivateWeak),
+            map(tag("linker_private"), |_| Linkage::LinkerPrivate),
+        )),
+        // Don't take the prefix of another keyword (e.g. `externally_initialized`)
+        not(satisfy(|c| c.is_alphanumeric() || c == '_')),
+    )(source)
 }
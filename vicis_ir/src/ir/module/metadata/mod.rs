@@ -3,7 +3,12 @@ pub mod parser;
 pub use parser::operand as parse_operand;
 pub use parser::parse;
 
-use crate::ir::{module::name::Name, value::ConstantInt};
+use crate::ir::{
+    module::name::Name,
+    types::{TypeId, Types},
+    util::escape,
+    value::{ConstantInt, Value},
+};
 use std::fmt;
 
 #[derive(PartialEq, Clone)]
@@ -12,27 +17,303 @@ pub enum Metadata {
     Name(Name),
     Int(ConstantInt),
     Node(Vec<Self>),
+    Null,
+    /// `distinct !{...}` or `distinct !DI...(...)`
+    Distinct(Box<Self>),
+    /// `!DILocation(line: 3, column: 5, scope: !7)` etc.
+    Specialized(SpecializedNode),
+    /// A value wrapped as metadata (e.g. `i32* %1` in `metadata i32* %1`)
+    Value(TypeId, Box<Value>),
 }
 
-// struct MetadataNode(Vec<Metadata>);
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpecializedNode {
+    pub kind: SpecializedKind,
+    /// Fields in the order they appear. Operands of `!DIExpression` and `!DIArgList` have no key.
+    pub fields: Vec<(Option<String>, Field)>,
+}
+
+#[derive(PartialEq, Clone)]
+pub enum Field {
+    Int(i128),
+    Bool(bool),
+    String(String),
+    /// Enumerators such as `DW_TAG_member`, `FullDebug` or `DW_OP_plus_uconst`
+    Ident(String),
+    /// `DIFlagPrototyped | DIFlagAllCallsDescribed`
+    Flags(Vec<String>),
+    Metadata(Metadata),
+}
+
+macro_rules! specialized_kinds {
+    ($($kind:ident),* $(,)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum SpecializedKind {
+            $($kind),*
+        }
+
+        impl SpecializedKind {
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($kind) => Some(Self::$kind),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+specialized_kinds!(
+    DILocation,
+    DIExpression,
+    DIGlobalVariableExpression,
+    DIFile,
+    DICompileUnit,
+    DISubprogram,
+    DILexicalBlock,
+    DILexicalBlockFile,
+    DINamespace,
+    DIModule,
+    DICommonBlock,
+    DISubrange,
+    DIGenericSubrange,
+    DIEnumerator,
+    DIBasicType,
+    DIStringType,
+    DIDerivedType,
+    DICompositeType,
+    DISubroutineType,
+    DITemplateTypeParameter,
+    DITemplateValueParameter,
+    DIGlobalVariable,
+    DILocalVariable,
+    DILabel,
+    DIObjCProperty,
+    DIImportedEntity,
+    DIMacro,
+    DIMacroFile,
+    DIArgList,
+    DIAssignID,
+    GenericDINode,
+);
+
+/// The typed form of `!DILocation(...)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DILocation {
+    pub line: u32,
+    pub column: u32,
+    pub scope: Metadata,
+    pub inlined_at: Option<Metadata>,
+}
+
+/// The typed form of `!DIFile(...)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DIFile {
+    pub filename: String,
+    pub directory: String,
+}
+
+/// A source location resolved from a `!dbg` attachment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugLocation {
+    pub filename: String,
+    pub directory: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// An operand of `!llvm.module.flags`: `!{i32 <behavior>, !"<key>", <value>}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleFlag {
+    pub behavior: ModuleFlagBehavior,
+    pub key: String,
+    pub value: Metadata,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleFlagBehavior {
+    Error = 1,
+    Warning = 2,
+    Require = 3,
+    Override = 4,
+    Append = 5,
+    AppendUnique = 6,
+    Max = 7,
+    Min = 8,
+}
+
+impl Metadata {
+    /// Returns the node itself, looking through `distinct`.
+    pub fn strip_distinct(&self) -> &Self {
+        match self {
+            Self::Distinct(node) => node.strip_distinct(),
+            node => node,
+        }
+    }
+
+    pub fn as_specialized(&self) -> Option<&SpecializedNode> {
+        match self.strip_distinct() {
+            Self::Specialized(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    pub fn as_node(&self) -> Option<&[Self]> {
+        match self.strip_distinct() {
+            Self::Node(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Calls `f` on each value wrapped in this metadata, including the operands of
+    /// `!DIArgList`.
+    pub fn for_each_value_mut(&mut self, f: &mut impl FnMut(&mut Value)) {
+        match self {
+            Self::Value(_, val) => f(val),
+            Self::Node(list) => list.iter_mut().for_each(|m| m.for_each_value_mut(f)),
+            Self::Distinct(node) => node.for_each_value_mut(f),
+            Self::Specialized(node) => {
+                for (_, field) in &mut node.fields {
+                    if let Field::Metadata(m) = field {
+                        m.for_each_value_mut(f)
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn to_string(&self, types: &Types) -> String {
+        match self {
+            Self::Node(list) => format!(
+                "!{{{}}}",
+                list.iter()
+                    .map(|m| m.to_string(types))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Distinct(node) => format!("distinct {}", node.to_string(types)),
+            Self::Specialized(node) => node.to_string(types),
+            Self::Value(ty, val) => match val.as_ref() {
+                Value::Constant(konst) => {
+                    format!("{} {}", types.to_string(*ty), konst.to_string(types))
+                }
+                val => format!("{} {:?}", types.to_string(*ty), val),
+            },
+            m => format!("{:?}", m),
+        }
+    }
+}
+
+impl SpecializedNode {
+    pub fn field(&self, key: &str) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k.as_deref() == Some(key))
+            .map(|(_, f)| f)
+    }
+
+    pub fn int_field(&self, key: &str) -> Option<i128> {
+        match self.field(key)? {
+            Field::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn string_field(&self, key: &str) -> Option<&str> {
+        match self.field(key)? {
+            Field::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns a metadata field unless it is `null`.
+    pub fn metadata_field(&self, key: &str) -> Option<&Metadata> {
+        match self.field(key)? {
+            Field::Metadata(Metadata::Null) => None,
+            Field::Metadata(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    pub fn as_location(&self) -> Option<DILocation> {
+        if self.kind != SpecializedKind::DILocation {
+            return None;
+        }
+        Some(DILocation {
+            line: self.int_field("line").unwrap_or(0) as u32,
+            column: self.int_field("column").unwrap_or(0) as u32,
+            scope: self.metadata_field("scope")?.clone(),
+            inlined_at: self.metadata_field("inlinedAt").cloned(),
+        })
+    }
+
+    pub fn as_file(&self) -> Option<DIFile> {
+        if self.kind != SpecializedKind::DIFile {
+            return None;
+        }
+        Some(DIFile {
+            filename: self.string_field("filename")?.to_owned(),
+            directory: self.string_field("directory").unwrap_or("").to_owned(),
+        })
+    }
+
+    pub fn to_string(&self, types: &Types) -> String {
+        format!(
+            "!{:?}({})",
+            self.kind,
+            self.fields
+                .iter()
+                .map(|(key, field)| match key {
+                    Some(key) => format!("{}: {}", key, field.to_string(types)),
+                    None => field.to_string(types),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl Field {
+    pub fn to_string(&self, types: &Types) -> String {
+        match self {
+            Self::Int(i) => i.to_string(),
+            Self::Bool(b) => b.to_string(),
+            Self::String(s) => format!("\"{}\"", escape(s)),
+            Self::Ident(i) => i.clone(),
+            Self::Flags(flags) => flags.join(" | "),
+            Self::Metadata(Metadata::Null) => "null".to_string(),
+            Self::Metadata(m) => m.to_string(types),
+        }
+    }
+}
+
+impl ModuleFlagBehavior {
+    pub fn from_u64(behavior: u64) -> Option<Self> {
+        Some(match behavior {
+            1 => Self::Error,
+            2 => Self::Warning,
+            3 => Self::Require,
+            4 => Self::Override,
+            5 => Self::Append,
+            6 => Self::AppendUnique,
+            7 => Self::Max,
+            8 => Self::Min,
+            _ => return None,
+        })
+    }
+}
 
 // Metadata Node
 
 impl fmt::Debug for Metadata {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fn fmt_int(i: &ConstantInt, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            // TODO: Find another simpler way
-            match i {
-                ConstantInt::Int1(_) => write!(f, "i1 "),
-                ConstantInt::Int8(_) => write!(f, "i8 "),
-                ConstantInt::Int32(_) => write!(f, "i32 "),
-                ConstantInt::Int64(_) => write!(f, "i64 "),
-            }?;
-            write!(f, "{}", i)
+            write!(f, "i{} {}", i.bits(), i)
         }
 
         match self {
-            Self::String(s) => write!(f, "!\"{}\"", s),
+            Self::String(s) => write!(f, "!\"{}\"", escape(s)),
             Self::Name(n) => write!(f, "!{}", n),
             Self::Int(i) => fmt_int(i, f),
             Self::Node(list) => {
@@ -46,6 +327,23 @@ impl fmt::Debug for Metadata {
                 }
                 write!(f, "}}")
             }
+            Self::Null => write!(f, "null"),
+            Self::Distinct(node) => write!(f, "distinct {:?}", node),
+            Self::Specialized(node) => write!(f, "{:?}", node),
+            Self::Value(_, val) => write!(f, "{:?}", val),
+        }
+    }
+}
+
+impl fmt::Debug for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(i) => write!(f, "{}", i),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::String(s) => write!(f, "\"{}\"", escape(s)),
+            Self::Ident(i) => write!(f, "{}", i),
+            Self::Flags(flags) => write!(f, "{}", flags.join(" | ")),
+            Self::Metadata(m) => write!(f, "{:?}", m),
         }
     }
 }
@@ -1,17 +1,25 @@
-use crate::ir::module::{metadata::Metadata, name, name::Name};
+use crate::ir::module::{
+    metadata::{Field, Metadata, SpecializedKind, SpecializedNode},
+    name,
+    name::Name,
+};
 use crate::ir::{
     types,
     util::{spaces, string_literal},
-    value::parser::parse_constant_int,
+    value::{
+        parser::{parse_constant, parse_constant_int},
+        Value,
+    },
 };
 use nom;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::char,
-    error::VerboseError,
-    multi::separated_list0,
-    sequence::{preceded, separated_pair, tuple},
+    character::complete::{char, digit1},
+    combinator::{map, map_res, opt, recognize},
+    error::{ErrorKind, ParseError, VerboseError},
+    multi::{separated_list0, separated_list1},
+    sequence::{preceded, separated_pair, terminated, tuple},
     IResult,
 };
 
@@ -22,7 +30,7 @@ pub fn parse(
         separated_pair(
             preceded(exclamation, name::parse),
             preceded(spaces, tag("=")),
-            node(types),
+            alt((distinct(types), node(types), specialized(types))),
         )(source)
     }
 }
@@ -40,10 +48,21 @@ fn name(source: &str) -> IResult<&str, Metadata, VerboseError<&str>> {
     preceded(exclamation, name::parse)(source).map(|(i, source)| (i, Metadata::Name(source)))
 }
 
+fn null(source: &str) -> IResult<&str, Metadata, VerboseError<&str>> {
+    map(preceded(spaces, tag("null")), |_| Metadata::Null)(source)
+}
+
 fn int(types: &types::Types) -> impl Fn(&str) -> IResult<&str, Metadata, VerboseError<&str>> + '_ {
     move |source| {
         let (source, ty) = types::parse(source, types)?;
-        parse_constant_int(source, &types, ty).map(|(source, v)| (source, Metadata::Int(v)))
+        if let Ok((source, i)) = parse_constant_int(source, &types, ty) {
+            return Ok((source, Metadata::Int(i)));
+        }
+        let (source, konst) = parse_constant(source, types, ty)?;
+        Ok((
+            source,
+            Metadata::Value(ty, Box::new(Value::Constant(konst))),
+        ))
     }
 }
 
@@ -61,10 +80,95 @@ fn node(types: &types::Types) -> impl Fn(&str) -> IResult<&str, Metadata, Verbos
     }
 }
 
+fn distinct(
+    types: &types::Types,
+) -> impl Fn(&str) -> IResult<&str, Metadata, VerboseError<&str>> + '_ {
+    move |source| {
+        let (source, _) = preceded(spaces, tag("distinct"))(source)?;
+        let (source, node) = alt((node(types), specialized(types)))(source)?;
+        Ok((source, Metadata::Distinct(Box::new(node))))
+    }
+}
+
+// !DIKind(key: value, ...)
+fn specialized(
+    types: &types::Types,
+) -> impl Fn(&str) -> IResult<&str, Metadata, VerboseError<&str>> + '_ {
+    move |source| {
+        let (source, kind) = preceded(exclamation, name::parser::identifier)(source)?;
+        let kind = SpecializedKind::from_name(kind).ok_or_else(|| {
+            nom::Err::Error(VerboseError::from_error_kind(source, ErrorKind::Tag))
+        })?;
+        let (source, _) = preceded(spaces, char('('))(source)?;
+        let (source, fields) = separated_list0(
+            preceded(spaces, char(',')),
+            tuple((
+                opt(terminated(
+                    preceded(spaces, name::parser::identifier),
+                    preceded(spaces, char(':')),
+                )),
+                field(types),
+            )),
+        )(source)?;
+        let (source, _) = preceded(spaces, char(')'))(source)?;
+        Ok((
+            source,
+            Metadata::Specialized(SpecializedNode {
+                kind,
+                fields: fields
+                    .into_iter()
+                    .map(|(key, field)| (key.map(|k| k.to_owned()), field))
+                    .collect(),
+            }),
+        ))
+    }
+}
+
+fn field(types: &types::Types) -> impl Fn(&str) -> IResult<&str, Field, VerboseError<&str>> + '_ {
+    move |source| {
+        preceded(
+            spaces,
+            alt((
+                map(
+                    map_res(recognize(tuple((opt(char('-')), digit1))), |i: &str| {
+                        i.parse::<i128>()
+                    }),
+                    Field::Int,
+                ),
+                map(string_literal, Field::String),
+                map(tag("true"), |_| Field::Bool(true)),
+                map(tag("false"), |_| Field::Bool(false)),
+                map(
+                    separated_list1(
+                        preceded(spaces, char('|')),
+                        preceded(spaces, name::parser::identifier),
+                    ),
+                    |mut flags| {
+                        if flags.len() == 1 && !flags[0].starts_with("DIFlag") {
+                            return Field::Ident(flags.remove(0).to_owned());
+                        }
+                        Field::Flags(flags.into_iter().map(|f| f.to_owned()).collect())
+                    },
+                ),
+                map(operand(types), Field::Metadata),
+            )),
+        )(source)
+    }
+}
+
 pub fn operand(
     types: &types::Types,
 ) -> impl Fn(&str) -> IResult<&str, Metadata, VerboseError<&str>> + '_ {
-    move |source| alt((string, name, node(types), int(types)))(source)
+    move |source| {
+        alt((
+            string,
+            specialized(types),
+            name,
+            node(types),
+            null,
+            int(types),
+        ))(source)
+    }
 }
 
 #[test]
@@ -1,4 +1,9 @@
 pub mod attributes;
+pub mod bitcode;
+pub mod comdat;
+pub mod data_layout;
+pub mod global_alias;
+pub mod global_ifunc;
 pub mod global_variable;
 pub mod linkage;
 pub mod metadata;
@@ -8,24 +13,32 @@ pub mod preemption_specifier;
 pub mod unnamed_addr;
 pub mod visibility;
 
+pub use crate::ir::util::error::ParseError;
+pub use bitcode::{parse as parse_bitcode, write as write_bitcode};
 pub use parser::parse as parse_assembly;
 
 use super::{
-    function::{Function, FunctionId, Parameter},
+    function::{instruction::Instruction, Function, FunctionId, Parameter},
     types::{TypeId, Types},
+    util::escape,
 };
 use attributes::Attribute;
+use comdat::Comdat;
+use data_layout::DataLayout;
+use global_alias::GlobalAlias;
+use global_ifunc::GlobalIFunc;
 use global_variable::GlobalVariable;
 use id_arena::{Arena, Id};
-use metadata::Metadata;
+use metadata::{DebugLocation, Metadata, ModuleFlag, ModuleFlagBehavior};
 use name::Name;
 use rustc_hash::FxHashMap;
-use std::fmt;
+use std::{cmp::Ordering, fmt};
 
 #[derive(Debug, Clone)]
 pub struct Target {
     triple: String,
     datalayout: String,
+    data_layout: DataLayout,
 }
 
 pub struct Module {
@@ -35,8 +48,15 @@ pub struct Module {
     pub(crate) functions: Arena<Function>,
     pub(crate) attributes: FxHashMap<u32, Vec<Attribute>>,
     pub(crate) global_variables: FxHashMap<Name, GlobalVariable>,
+    pub(crate) global_aliases: FxHashMap<Name, GlobalAlias>,
+    pub(crate) global_ifuncs: FxHashMap<Name, GlobalIFunc>,
+    pub(crate) comdats: FxHashMap<Name, Comdat>,
+    /// Names of global variables, aliases and ifuncs in the order they were declared
+    pub(crate) global_order: Vec<Name>,
     pub types: Types,
     pub metas: FxHashMap<Name, Metadata>,
+    /// Named metadata in the order it was declared
+    pub named_metas: Vec<(String, Vec<Metadata>)>,
 }
 
 impl Default for Module {
@@ -48,8 +68,13 @@ impl Default for Module {
             functions: Arena::new(),
             attributes: FxHashMap::default(),
             global_variables: FxHashMap::default(),
+            global_aliases: FxHashMap::default(),
+            global_ifuncs: FxHashMap::default(),
+            comdats: FxHashMap::default(),
+            global_order: vec![],
             types: Types::new(),
             metas: FxHashMap::default(),
+            named_metas: vec![],
         }
     }
 }
@@ -71,6 +96,10 @@ impl Module {
         &self.target
     }
 
+    pub fn data_layout(&self) -> &DataLayout {
+        &self.target.data_layout
+    }
+
     pub fn functions(&self) -> &Arena<Function> {
         &self.functions
     }
@@ -87,6 +116,88 @@ impl Module {
         &self.global_variables
     }
 
+    pub fn global_aliases(&self) -> &FxHashMap<Name, GlobalAlias> {
+        &self.global_aliases
+    }
+
+    pub fn global_ifuncs(&self) -> &FxHashMap<Name, GlobalIFunc> {
+        &self.global_ifuncs
+    }
+
+    pub fn comdats(&self) -> &FxHashMap<Name, Comdat> {
+        &self.comdats
+    }
+
+    /// Returns the operands of named metadata such as `!llvm.dbg.cu`.
+    pub fn named_metadata(&self, name: &str) -> Option<&[Metadata]> {
+        self.named_metas
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, list)| list.as_slice())
+    }
+
+    /// Follows references like `!7` to the node they name.
+    pub fn resolve_metadata<'a>(&'a self, mut meta: &'a Metadata) -> &'a Metadata {
+        while let Metadata::Name(name) = meta {
+            match self.metas.get(name) {
+                Some(m) => meta = m,
+                None => break,
+            }
+        }
+        meta.strip_distinct()
+    }
+
+    /// Returns the well-formed entries of `!llvm.module.flags`.
+    pub fn module_flags(&self) -> Vec<ModuleFlag> {
+        let mut flags = vec![];
+        for flag in self.named_metadata("llvm.module.flags").unwrap_or(&[]) {
+            match self.resolve_metadata(flag).as_node() {
+                Some([Metadata::Int(behavior), Metadata::String(key), value]) => {
+                    if let Some(behavior) =
+                        behavior.zext_value().and_then(ModuleFlagBehavior::from_u64)
+                    {
+                        flags.push(ModuleFlag {
+                            behavior,
+                            key: key.clone(),
+                            value: value.clone(),
+                        })
+                    }
+                }
+                _ => continue,
+            }
+        }
+        flags
+    }
+
+    /// Resolves the `!dbg` attachment of `inst` to a source location. The file is that of
+    /// the innermost enclosing scope which has one.
+    pub fn debug_location(&self, inst: &Instruction) -> Option<DebugLocation> {
+        let loc = self
+            .resolve_metadata(inst.metadata.get("dbg")?)
+            .as_specialized()?
+            .as_location()?;
+        let mut scope = self.resolve_metadata(&loc.scope).as_specialized()?;
+        // Scopes form a chain ending at a subprogram or compile unit, so this terminates
+        // on well-formed input. Bound it anyway to guard against cycles.
+        for _ in 0..64 {
+            if let Some(file) = scope.as_file() {
+                return Some(DebugLocation {
+                    filename: file.filename,
+                    directory: file.directory,
+                    line: loc.line,
+                    column: loc.column,
+                });
+            }
+            scope = match scope.metadata_field("file") {
+                Some(file) => self.resolve_metadata(file).as_specialized()?,
+                None => self
+                    .resolve_metadata(scope.metadata_field("scope")?)
+                    .as_specialized()?,
+            };
+        }
+        None
+    }
+
     pub fn add_function(&mut self, f: Function) -> Id<Function> {
         self.functions.alloc(f)
     }
@@ -122,6 +233,7 @@ impl Default for Target {
         Self {
             triple: "".to_string(),
             datalayout: "".to_string(),
+            data_layout: DataLayout::default(),
         }
     }
 }
@@ -138,32 +250,88 @@ impl Target {
     pub fn datalayout(&self) -> &str {
         self.datalayout.as_str()
     }
+
+    pub fn data_layout(&self) -> &DataLayout {
+        &self.data_layout
+    }
 }
 
-impl fmt::Debug for Module {
+impl fmt::Display for Module {
+    /// Prints the module as LLVM Assembly. Globals and named metadata keep their declaration
+    /// order, and numbered entities are sorted by number.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "source_filename = \"{}\"", self.source_filename)?;
-        writeln!(f, "target datalayout = \"{}\"", self.target.datalayout)?;
-        writeln!(f, "target triple = \"{}\"", self.target.triple)?;
+        writeln!(f, "source_filename = \"{}\"", escape(&self.source_filename))?;
+        if !self.target.datalayout.is_empty() {
+            writeln!(f, "target datalayout = \"{}\"", self.target.datalayout)?;
+        }
+        if !self.target.triple.is_empty() {
+            writeln!(f, "target triple = \"{}\"", self.target.triple)?;
+        }
         writeln!(f)?;
         write!(f, "{:?}", self.types)?;
-        for gv in self.global_variables.values() {
+        let mut comdats = self.comdats.values().collect::<Vec<_>>();
+        comdats.sort_by_key(|c| c.name.to_string().cloned());
+        for comdat in comdats {
+            writeln!(f, "{}", comdat)?;
+        }
+        for gv in self
+            .global_order
+            .iter()
+            .filter_map(|n| self.global_variables.get(n))
+        {
             writeln!(f, "{}", gv.to_string(&self.types))?;
         }
+        for alias in self
+            .global_order
+            .iter()
+            .filter_map(|n| self.global_aliases.get(n))
+        {
+            writeln!(f, "{}", alias.to_string(&self.types))?;
+        }
+        for ifunc in self
+            .global_order
+            .iter()
+            .filter_map(|n| self.global_ifuncs.get(n))
+        {
+            writeln!(f, "{}", ifunc.to_string(&self.types))?;
+        }
         writeln!(f)?;
         for (_, func) in &self.functions {
             writeln!(f, "{:?}", func)?;
         }
-        for (id, attrs) in &self.attributes {
+        let mut attributes = self.attributes.iter().collect::<Vec<_>>();
+        attributes.sort_by_key(|(id, _)| **id);
+        for (id, attrs) in attributes {
             write!(f, "attributes #{} = {{ ", id)?;
             for attr in attrs {
                 write!(f, "{:?} ", attr)?;
             }
             writeln!(f, "}}")?
         }
-        for (n, meta) in &self.metas {
-            writeln!(f, "!{} = {:?}", n, meta)?;
+        for (name, list) in &self.named_metas {
+            writeln!(
+                f,
+                "!{} = {}",
+                Name::Name(name.clone()),
+                Metadata::Node(list.clone()).to_string(&self.types)
+            )?;
+        }
+        let mut metas = self.metas.iter().collect::<Vec<_>>();
+        metas.sort_by(|(x, _), (y, _)| match (x, y) {
+            (Name::Number(x), Name::Number(y)) => x.cmp(y),
+            (Name::Number(_), Name::Name(_)) => Ordering::Less,
+            (Name::Name(_), Name::Number(_)) => Ordering::Greater,
+            (Name::Name(x), Name::Name(y)) => x.cmp(y),
+        });
+        for (n, meta) in metas {
+            writeln!(f, "!{} = {}", n, meta.to_string(&self.types))?;
         }
         Ok(())
     }
 }
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -53,8 +53,9 @@ impl fmt::Display for Name {
 }
 
 fn to_escaped(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
-    if s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
-        .is_some()
+    if s.is_empty()
+        || s.starts_with(|c: char| c.is_ascii_digit())
+        || s.contains(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '$' | '-')))
     {
         write!(f, r#""{}""#, escape(s))
     } else {
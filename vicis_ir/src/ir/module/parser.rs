@@ -2,20 +2,28 @@ use super::super::function;
 use super::Module;
 use super::{
     attributes::{parser::parse_attributes, Attribute},
-    global_variable, metadata, name,
+    comdat,
+    data_layout::{self, DataLayout},
+    global_alias, global_ifunc, global_variable,
+    metadata::{self, Metadata},
+    name::{self, Name},
 };
 use crate::ir::{
     types,
-    util::{spaces, string_literal},
+    util::{
+        error::{furthest, ParseError},
+        spaces, string_literal,
+    },
 };
 use nom;
 use nom::{
     bytes::complete::tag,
     character::complete::{char, digit1},
-    error::VerboseError,
+    error::{ContextError, ErrorKind, ParseError as _, VerboseError},
     sequence::{preceded, tuple},
     IResult,
 };
+use std::mem;
 
 fn parse_source_filename(source: &str) -> IResult<&str, String, VerboseError<&str>> {
     tuple((
@@ -26,14 +34,19 @@ fn parse_source_filename(source: &str) -> IResult<&str, String, VerboseError<&st
     .map(|(i, (_, _, name))| (i, name))
 }
 
-fn parse_target_datalayout(source: &str) -> IResult<&str, String, VerboseError<&str>> {
-    tuple((
+fn parse_target_datalayout(
+    source: &str,
+) -> IResult<&str, (String, DataLayout), VerboseError<&str>> {
+    let (source, _) = tuple((
         tag("target"),
         preceded(spaces, tag("datalayout")),
         preceded(spaces, char('=')),
-        preceded(spaces, string_literal),
-    ))(source)
-    .map(|(i, (_, _, _, datalayout))| (i, datalayout))
+        preceded(spaces, char('"')),
+    ))(source)?;
+    let (rest, data_layout) = data_layout::parse(source)?;
+    let datalayout = source[..source.len() - rest.len()].to_string();
+    let (rest, _) = char('"')(rest)?;
+    Ok((rest, (datalayout, data_layout)))
 }
 
 fn parse_target_triple(source: &str) -> IResult<&str, String, VerboseError<&str>> {
@@ -71,7 +84,12 @@ fn parse_local_type<'a>(
     Ok((source, ()))
 }
 
-pub fn parse(mut source: &str) -> Result<Module, nom::Err<VerboseError<&str>>> {
+/// Parses LLVM Assembly.
+pub fn parse(source: &str) -> Result<Module, ParseError> {
+    parse_module(source).map_err(|e| ParseError::new(source, e))
+}
+
+fn parse_module(mut source: &str) -> Result<Module, nom::Err<VerboseError<&str>>> {
     let mut module = Module::new();
     loop {
         source = spaces(source)?.0;
@@ -80,86 +98,160 @@ pub fn parse(mut source: &str) -> Result<Module, nom::Err<VerboseError<&str>>> {
             break;
         }
 
-        if let Ok((source_, source_filename)) = parse_source_filename(source) {
+        let mut error = nom::Err::Error(VerboseError::from_error_kind(source, ErrorKind::Alt));
+
+        if let Some((source_, source_filename)) = record(parse_source_filename(source), &mut error)
+        {
             module.source_filename = source_filename;
             source = source_;
             continue;
         }
 
-        if let Ok((source_, target_datalayout)) = parse_target_datalayout(source) {
-            module.target.datalayout = target_datalayout.to_string();
+        if let Some((source_, (target_datalayout, data_layout))) =
+            record(parse_target_datalayout(source), &mut error)
+        {
+            module.target.datalayout = target_datalayout;
+            module.target.data_layout = data_layout;
             source = source_;
             continue;
         }
 
-        if let Ok((source_, target_triple)) = parse_target_triple(source) {
+        if let Some((source_, target_triple)) = record(parse_target_triple(source), &mut error) {
             module.target.triple = target_triple.to_string();
             source = source_;
             continue;
         }
 
-        if let Ok((source_, (id, attrs))) = parse_attribute_group(source) {
+        if let Some((source_, (id, attrs))) = record(parse_attribute_group(source), &mut error) {
             module.attributes.insert(id, attrs);
             source = source_;
             continue;
         }
 
-        if let Ok((source_, _)) = parse_local_type(source, &module.types) {
+        if let Some((source_, _)) = record(parse_local_type(source, &module.types), &mut error) {
             source = source_;
             continue;
         }
 
-        if let Ok((source_, gv)) = global_variable::parse(source, &module.types) {
+        if let Some((source_, comdat)) = record(comdat::parse(source), &mut error) {
+            module.comdats.insert(comdat.name.clone(), comdat);
+            source = source_;
+            continue;
+        }
+
+        if let Some((source_, gv)) =
+            record(global_variable::parse(source, &module.types), &mut error)
+        {
+            module.global_order.push(gv.name.clone());
             module.global_variables.insert(gv.name.clone(), gv);
             source = source_;
             continue;
         }
 
-        if let Ok((source_, func)) = function::parse(source, module.types.clone()) {
+        if let Some((source_, alias)) =
+            record(global_alias::parse(source, &module.types), &mut error)
+        {
+            module.global_order.push(alias.name.clone());
+            module.global_aliases.insert(alias.name.clone(), alias);
+            source = source_;
+            continue;
+        }
+
+        if let Some((source_, ifunc)) =
+            record(global_ifunc::parse(source, &module.types), &mut error)
+        {
+            module.global_order.push(ifunc.name.clone());
+            module.global_ifuncs.insert(ifunc.name.clone(), ifunc);
+            source = source_;
+            continue;
+        }
+
+        if let Some((source_, func)) =
+            record(function::parse(source, module.types.clone()), &mut error)
+        {
             module.functions.alloc(func);
             source = source_;
             continue;
         }
-        if let Ok((source_, (name_, meta))) = metadata::parse(&module.types)(source) {
-            module.metas.insert(name_, meta);
+        if let Some((source_, (name_, meta))) =
+            record(metadata::parse(&module.types)(source), &mut error)
+        {
+            match (name_, meta) {
+                (Name::Name(name_), Metadata::Node(list)) => {
+                    // Repeated named metadata accumulates operands
+                    match module.named_metas.iter_mut().find(|(n, _)| n == &name_) {
+                        Some((_, operands)) => operands.extend(list),
+                        None => module.named_metas.push((name_, list)),
+                    }
+                }
+                (name_, meta) => {
+                    module.metas.insert(name_, meta);
+                }
+            }
             source = source_;
             continue;
         }
 
-        todo!("unsupported syntax at {:?}", source)
+        return Err(error.map(|e| VerboseError::add_context(source, "top-level entity", e)));
     }
 
     Ok(module)
 }
 
+/// Returns the output of `result` if it succeeded. Otherwise keeps the error in `error`
+/// if it got further than the one already there.
+fn record<'a, T>(
+    result: IResult<&'a str, T, VerboseError<&'a str>>,
+    error: &mut nom::Err<VerboseError<&'a str>>,
+) -> Option<(&'a str, T)> {
+    match result {
+        Ok(ok) => Some(ok),
+        Err(e) => {
+            let prev = mem::replace(error, nom::Err::Incomplete(nom::Needed::Unknown));
+            *error = furthest(prev, e);
+            None
+        }
+    }
+}
+
 macro_rules! generate_test {
     ($fname:ident, $name:literal) => {
         #[test]
         fn $fname() {
-            use nom::error::convert_error;
             use std::fs;
             let source = fs::read_to_string(concat!("./examples/", $name)).unwrap();
             let module = match parse(&source) {
                 Ok(ok) => ok,
-                Err(nom::Err::Error(e)) => {
-                    println!("{}", convert_error(source.as_str(), e));
-                    panic!()
-                }
-                Err(e) => panic!("{:?}", e),
+                Err(e) => panic!("{}", e),
             };
             insta::assert_debug_snapshot!(module);
+
+            // Printing and parsing again must give the same module
+            let printed = module.to_string();
+            let reparsed = match parse(&printed) {
+                Ok(ok) => ok,
+                Err(e) => panic!("{}", e),
+            };
+            assert_eq!(reparsed.to_string(), printed);
         }
     };
 }
 
 generate_test!(parse_example_addsubmul, "addsubmul.ll");
+generate_test!(parse_example_alias, "alias.ll");
 generate_test!(parse_example_ary, "ary.ll");
+generate_test!(parse_example_bitops, "bitops.ll");
 generate_test!(parse_example_br, "br.ll");
 generate_test!(parse_example_call, "call.ll");
 generate_test!(parse_example_cast, "cast.ll");
 generate_test!(parse_example_cgep, "cgep.ll");
+generate_test!(parse_example_constexpr, "constexpr.ll");
 generate_test!(parse_example_dce, "dce.ll");
+generate_test!(parse_example_debug_info, "debug_info.ll");
+generate_test!(parse_example_float, "float.ll");
+generate_test!(parse_example_fp_arith, "fp_arith.ll");
 generate_test!(parse_example_gblvar, "gblvar.ll");
+generate_test!(parse_example_global_attrs, "global_attrs.ll");
 generate_test!(parse_example_icmp, "icmp.ll");
 generate_test!(parse_example_load, "load.ll");
 generate_test!(parse_example_loop, "loop.ll");
@@ -176,6 +268,12 @@ generate_test!(parse_example_rustc_fn_call, "rustc_fn_call.ll");
 generate_test!(parse_example_rustc_hello, "rustc_hello.ll");
 generate_test!(parse_example_rustc_minimum, "rustc_minimum.ll");
 generate_test!(parse_example_struct, "struct.ll");
+generate_test!(parse_example_switch, "switch.ll");
+generate_test!(parse_example_vector, "vector.ll");
+generate_test!(parse_example_atomic, "atomic.ll");
+generate_test!(parse_example_wide_int, "wide_int.ll");
+generate_test!(parse_example_poison, "poison.ll");
+generate_test!(parse_example_vararg, "vararg.ll");
 
 #[test]
 fn parse_module1() {
@@ -250,3 +348,71 @@ fn parse_module2() {
     }
     println!("{:?}", result);
 }
+
+#[test]
+fn parse_debug_info() {
+    use super::metadata::{DebugLocation, ModuleFlagBehavior};
+    use std::fs;
+
+    let source = fs::read_to_string("./examples/debug_info.ll").unwrap();
+    let module = parse(&source).unwrap();
+    assert_eq!(
+        module.named_metadata("llvm.dbg.cu").map(|cu| cu.len()),
+        Some(1)
+    );
+
+    let flags = module.module_flags();
+    assert_eq!(flags.len(), 3);
+    assert_eq!(flags[0].behavior, ModuleFlagBehavior::Max);
+    assert_eq!(flags[1].key, "Debug Info Version");
+
+    let func = &module.functions()[module.find_function_by_name("square").unwrap()];
+    let locs = func
+        .layout
+        .block_iter()
+        .flat_map(|block| func.layout.inst_iter(block))
+        .filter_map(|inst| module.debug_location(func.data.inst_ref(inst)))
+        .map(|loc| (loc.line, loc.column))
+        .collect::<Vec<_>>();
+    assert_eq!(locs, vec![(1, 16), (2, 10), (2, 12), (2, 3)]);
+
+    let ret = func
+        .layout
+        .inst_iter(func.layout.block_iter().next().unwrap())
+        .next_back()
+        .unwrap();
+    assert_eq!(
+        module.debug_location(func.data.inst_ref(ret)),
+        Some(DebugLocation {
+            filename: "dbg.c".to_string(),
+            directory: "/home/user/src".to_string(),
+            line: 2,
+            column: 3,
+        })
+    );
+}
+
+#[test]
+fn parse_error() {
+    let err = parse("define void @f() {\n  %1 = alloca foo\n  ret void\n}").unwrap_err();
+    assert_eq!((err.line, err.column), (2, 15));
+    assert_eq!(err.message, "expected type after 'alloca'");
+    assert_eq!(
+        err.to_string(),
+        "error: expected type after 'alloca'\n --> line 2, column 15\n  |\n2 |   %1 = alloca foo\n  |               ^"
+    );
+
+    let err = parse("define void @f() {\n  store i32 1 i32* %1\n  ret void\n}").unwrap_err();
+    assert_eq!((err.line, err.column), (2, 15));
+    assert_eq!(err.message, "expected ',' after '1'");
+
+    let err = parse("define void @f() {\n  %1 = bogus\n}").unwrap_err();
+    assert_eq!(err.message, "expected instruction after '='");
+
+    let err = parse("@g = addrspace(4294967296) global i32 0").unwrap_err();
+    assert_eq!(err.line, 1);
+
+    let err = parse("define i32 @f() {\n  ret i32\n}").unwrap_err();
+    assert_eq!((err.line, err.column), (3, 1));
+    assert_eq!(err.message, "expected value after 'i32'");
+}
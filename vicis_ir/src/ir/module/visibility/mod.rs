@@ -4,6 +4,7 @@ pub use parser::parse_visibility as parse;
 
 use std::fmt;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Visibility {
     Default,
     Hidden,
@@ -1,7 +1,7 @@
 use crate::ir::{
     function::{
         data::Data,
-        instruction::{InstructionId, Opcode},
+        instruction::{AtomicOrdering, InstructionId, Operand},
         Function,
     },
     module::Module,
@@ -44,8 +44,11 @@ fn check_if_elimination_possible(
 ) {
     let no_users = data.users_of(inst).is_empty();
     let inst = data.inst_ref(inst);
-    let do_not_eliminate = matches!(inst.opcode, Opcode::Alloca | Opcode::Store | Opcode::Call)
-        || inst.opcode.is_terminator();
+    // Plain loads and phis are removable once unused
+    let do_not_eliminate = match &inst.operand {
+        Operand::Load(load) => load.volatile || load.ordering != AtomicOrdering::NotAtomic,
+        _ => inst.opcode.has_side_effects() && !inst.opcode.is_phi(),
+    };
     if do_not_eliminate {
         return;
     }
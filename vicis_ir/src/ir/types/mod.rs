@@ -15,6 +15,9 @@ pub type AddrSpace = u32;
 pub type Cache<T> = FxHashMap<T, TypeId>;
 pub type TypeId = Id<Type>;
 
+/// The widest integer type LLVM allows
+pub const MAX_INT_BITS: u32 = (1 << 23) - 1;
+
 #[derive(Clone)]
 pub struct Types(Arc<RefCell<TypesBase>>);
 
@@ -23,8 +26,14 @@ pub struct TypesBase {
     named_types: Cache<Name>,
     void: TypeId,
     int: Cache<u32>,
+    half: TypeId,
+    float: TypeId,
+    double: TypeId,
+    x86_fp80: TypeId,
+    fp128: TypeId,
     pointer: Cache<(TypeId, u32)>,
     array: Cache<(TypeId, u32)>,
+    vector: Cache<(TypeId, u32, bool)>,
     structs: Cache<String>,
     metadata: TypeId,
 }
@@ -33,8 +42,14 @@ pub struct TypesBase {
 pub enum Type {
     Void,
     Int(u32),
+    Half,
+    Float,
+    Double,
+    X86Fp80,
+    Fp128,
     Pointer(PointerType),
     Array(ArrayType),
+    Vector(VectorType),
     Function(FunctionType),
     Struct(StructType),
     Metadata,
@@ -53,6 +68,15 @@ pub struct ArrayType {
     pub num_elements: u32,
 }
 
+/// `<N x T>`, or `<vscale x N x T>` if `is_scalable`.
+/// A scalable vector holds a runtime multiple of `num_elements` elements.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct VectorType {
+    pub inner: TypeId,
+    pub num_elements: u32,
+    pub is_scalable: bool,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct FunctionType {
     pub ret: TypeId,
@@ -103,9 +127,21 @@ impl Types {
         self.base().is_atomic(id)
     }
 
+    pub fn is_float(&self, id: TypeId) -> bool {
+        self.base().is_float(id)
+    }
+
+    pub fn is_vector(&self, id: TypeId) -> bool {
+        self.base().is_vector(id)
+    }
+
     pub fn to_string(&self, ty: TypeId) -> String {
         self.base().to_string(ty)
     }
+
+    pub fn is_same(&self, x: TypeId, y: TypeId) -> bool {
+        self.base().is_same(x, y)
+    }
 }
 
 impl Default for TypesBase {
@@ -122,14 +158,25 @@ impl Default for TypesBase {
         .into_iter()
         .map(|(bits, ty)| (bits, arena.alloc(ty)))
         .collect();
+        let half = arena.alloc(Type::Half);
+        let float = arena.alloc(Type::Float);
+        let double = arena.alloc(Type::Double);
+        let x86_fp80 = arena.alloc(Type::X86Fp80);
+        let fp128 = arena.alloc(Type::Fp128);
         let metadata = arena.alloc(Type::Metadata);
         Self {
             arena,
             named_types: Cache::default(),
             void,
             int,
+            half,
+            float,
+            double,
+            x86_fp80,
+            fp128,
             pointer: Cache::default(),
             array: Cache::default(),
+            vector: Cache::default(),
             structs: Cache::default(),
             metadata,
         }
@@ -183,6 +230,26 @@ impl TypesBase {
         self.int[&64]
     }
 
+    pub fn half(&self) -> TypeId {
+        self.half
+    }
+
+    pub fn float(&self) -> TypeId {
+        self.float
+    }
+
+    pub fn double(&self) -> TypeId {
+        self.double
+    }
+
+    pub fn x86_fp80(&self) -> TypeId {
+        self.x86_fp80
+    }
+
+    pub fn fp128(&self) -> TypeId {
+        self.fp128
+    }
+
     pub fn metadata(&self) -> TypeId {
         self.metadata
     }
@@ -222,6 +289,19 @@ impl TypesBase {
         id
     }
 
+    pub fn vector(&mut self, inner: TypeId, num_elements: u32, is_scalable: bool) -> TypeId {
+        if let Some(vector) = self.vector.get(&(inner, num_elements, is_scalable)) {
+            return *vector;
+        }
+        let id = self.arena.alloc(Type::Vector(VectorType {
+            inner,
+            num_elements,
+            is_scalable,
+        }));
+        self.vector.insert((inner, num_elements, is_scalable), id);
+        id
+    }
+
     pub fn function(&mut self, ret: TypeId, params: Vec<TypeId>, is_var_arg: bool) -> TypeId {
         // TODO: FIXME: Should cache function type?
         self.arena.alloc(Type::Function(FunctionType {
@@ -252,6 +332,17 @@ impl TypesBase {
         }))
     }
 
+    /// Returns the named types in the order they were first referred to.
+    pub fn named_types(&self) -> Vec<(Name, TypeId)> {
+        let mut named_types = self
+            .named_types
+            .iter()
+            .map(|(name, &id)| (name.clone(), id))
+            .collect::<Vec<_>>();
+        named_types.sort_by_key(|(_, id)| id.index());
+        named_types
+    }
+
     pub fn get_struct(&self, name: &str) -> Option<TypeId> {
         self.structs.get(name).copied()
     }
@@ -277,8 +368,10 @@ impl TypesBase {
         match self.arena[ty] {
             Type::Void => None,
             Type::Int(_) => None,
+            Type::Half | Type::Float | Type::Double | Type::X86Fp80 | Type::Fp128 => None,
             Type::Pointer(PointerType { inner, .. }) => Some(inner),
             Type::Array(ArrayType { inner, .. }) => Some(inner),
+            Type::Vector(VectorType { inner, .. }) => Some(inner),
             Type::Function(_) => None,
             Type::Struct(_) => None,
             Type::Metadata => None,
@@ -289,8 +382,10 @@ impl TypesBase {
         match self.arena[ty] {
             Type::Void => None,
             Type::Int(_) => None,
+            Type::Half | Type::Float | Type::Double | Type::X86Fp80 | Type::Fp128 => None,
             Type::Pointer(_) => None,
             Type::Array(ArrayType { inner, .. }) => Some(inner),
+            Type::Vector(VectorType { inner, .. }) => Some(inner),
             Type::Function(_) => None,
             Type::Struct(StructType { ref elems, .. }) => elems.get(i).copied(),
             Type::Metadata => None,
@@ -302,6 +397,11 @@ impl TypesBase {
         match ty {
             Type::Void => "void".to_string(),
             Type::Int(bits) => format!("i{}", bits),
+            Type::Half => "half".to_string(),
+            Type::Float => "float".to_string(),
+            Type::Double => "double".to_string(),
+            Type::X86Fp80 => "x86_fp80".to_string(),
+            Type::Fp128 => "fp128".to_string(),
             Type::Pointer(PointerType { inner, addr_space }) if *addr_space == 0 => {
                 format!("{}*", self.to_string(*inner))
             }
@@ -314,34 +414,31 @@ impl TypesBase {
             }) => {
                 format!("[{} x {}]", num_elements, self.to_string(*inner))
             }
+            Type::Vector(VectorType {
+                inner,
+                num_elements,
+                is_scalable,
+            }) => {
+                format!(
+                    "<{}{} x {}>",
+                    if *is_scalable { "vscale x " } else { "" },
+                    num_elements,
+                    self.to_string(*inner)
+                )
+            }
             Type::Function(FunctionType {
                 ret,
                 params,
                 is_var_arg,
             }) => {
-                format!(
-                    "{} ({})",
-                    self.to_string(*ret),
-                    params
-                        .iter()
-                        .enumerate()
-                        .fold("".to_string(), |acc, (i, &param)| {
-                            format!(
-                                "{}{}{}",
-                                acc,
-                                self.to_string(param),
-                                if i == params.len() - 1 {
-                                    if *is_var_arg {
-                                        ", ..."
-                                    } else {
-                                        ""
-                                    }
-                                } else {
-                                    ", "
-                                }
-                            )
-                        })
-                )
+                let mut params = params
+                    .iter()
+                    .map(|&param| self.to_string(param))
+                    .collect::<Vec<_>>();
+                if *is_var_arg {
+                    params.push("...".to_string());
+                }
+                format!("{} ({})", self.to_string(*ret), params.join(", "))
             }
             Type::Struct(ty) => {
                 if let Some(name) = ty.name.as_ref() {
@@ -373,13 +470,69 @@ impl TypesBase {
         matches!(self.arena[ty], Type::Struct(_))
     }
 
+    pub fn is_float(&self, ty: TypeId) -> bool {
+        self.arena[ty].is_float()
+    }
+
+    pub fn is_vector(&self, ty: TypeId) -> bool {
+        matches!(self.arena[ty], Type::Vector(_))
+    }
+
     pub fn is_atomic(&self, ty: TypeId) -> bool {
         let ty = &self.arena[ty];
-        matches!(ty, Type::Void | Type::Pointer(_) | Type::Int(_))
+        matches!(ty, Type::Void | Type::Pointer(_) | Type::Int(_)) || ty.is_float()
+    }
+
+    /// Returns true if `x` and `y` are the same type. Function types and literal structs
+    /// are not uniqued, so they are compared structurally.
+    pub fn is_same(&self, x: TypeId, y: TypeId) -> bool {
+        if x == y {
+            return true;
+        }
+        let all_same = |xs: &[TypeId], ys: &[TypeId]| {
+            xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| self.is_same(*x, *y))
+        };
+        match (&self.arena[x], &self.arena[y]) {
+            (Type::Pointer(x), Type::Pointer(y)) => {
+                x.addr_space == y.addr_space && self.is_same(x.inner, y.inner)
+            }
+            (Type::Array(x), Type::Array(y)) => {
+                x.num_elements == y.num_elements && self.is_same(x.inner, y.inner)
+            }
+            (Type::Vector(x), Type::Vector(y)) => {
+                x.num_elements == y.num_elements
+                    && x.is_scalable == y.is_scalable
+                    && self.is_same(x.inner, y.inner)
+            }
+            (Type::Function(x), Type::Function(y)) => {
+                x.is_var_arg == y.is_var_arg
+                    && self.is_same(x.ret, y.ret)
+                    && all_same(&x.params, &y.params)
+            }
+            // Named structs are the same only if they are the same id.
+            (Type::Struct(x), Type::Struct(y)) if x.name.is_none() && y.name.is_none() => {
+                x.is_packed == y.is_packed && all_same(&x.elems, &y.elems)
+            }
+            (x, y) => !matches!(x, Type::Struct(_)) && x == y,
+        }
     }
 }
 
 impl Type {
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            Self::Half | Self::Float | Self::Double | Self::X86Fp80 | Self::Fp128
+        )
+    }
+
+    pub fn as_vector(&self) -> &VectorType {
+        match self {
+            Self::Vector(vector) => vector,
+            _ => panic!(),
+        }
+    }
+
     pub fn as_struct(&self) -> &StructType {
         match self {
             Self::Struct(strct) => strct,
@@ -397,7 +550,8 @@ impl Type {
 
 impl fmt::Debug for Types {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (name, &id) in &self.base().named_types {
+        let named_types = self.base().named_types();
+        for (name, id) in named_types {
             writeln!(
                 f,
                 "%{} = type {}",
@@ -1,12 +1,15 @@
-use super::super::types::{TypeId, Types};
-use crate::ir::{module::name, util::spaces};
+use super::super::types::{TypeId, Types, MAX_INT_BITS};
+use crate::ir::{
+    module::name,
+    util::{error::expected, spaces},
+};
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{char, digit1},
-    combinator::map,
+    combinator::{map, map_res, opt, verify},
     error::VerboseError,
-    sequence::preceded,
+    sequence::{preceded, tuple},
     IResult,
 };
 
@@ -20,20 +23,33 @@ pub fn parse<'a>(
         parse_struct(source, types, false)?
     } else if let Ok((source, _)) = preceded(spaces, tag("<{"))(source) {
         parse_struct(source, types, true)?
+    } else if let Ok((source, _)) = preceded(spaces, char('<'))(source) {
+        parse_vector(source, types)?
     } else if let Ok((source, name)) = preceded(spaces, preceded(char('%'), name::parse))(source) {
         (source, types.base_mut().named_type(name))
     } else {
-        preceded(
+        let result = preceded(
             spaces,
             alt((
                 map(tag("void"), |_| types.base().void()),
-                map(tag("i1"), |_| types.base().i1()),
-                map(tag("i8"), |_| types.base().i8()),
-                map(tag("i32"), |_| types.base().i32()),
-                map(tag("i64"), |_| types.base().i64()),
+                map(
+                    verify(
+                        map_res(preceded(char('i'), digit1), |bits: &str| {
+                            bits.parse::<u32>()
+                        }),
+                        |bits| (1..=MAX_INT_BITS).contains(bits),
+                    ),
+                    |bits| types.base_mut().int(bits),
+                ),
+                map(tag("half"), |_| types.base().half()),
+                map(tag("float"), |_| types.base().float()),
+                map(tag("double"), |_| types.base().double()),
+                map(tag("x86_fp80"), |_| types.base().x86_fp80()),
+                map(tag("fp128"), |_| types.base().fp128()),
                 map(tag("metadata"), |_| types.base().metadata()),
             )),
-        )(source)?
+        )(source);
+        expected(source, "type", result)?
     };
 
     loop {
@@ -68,6 +84,22 @@ fn parse_array<'a>(
     Ok((source, ary_ty))
 }
 
+fn parse_vector<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, TypeId, VerboseError<&'a str>> {
+    let (source, vscale) =
+        opt(preceded(spaces, tuple((tag("vscale"), spaces, char('x')))))(source)?;
+    let (source, n) = preceded(spaces, digit1)(source)?;
+    let (source, _) = preceded(spaces, char('x'))(source)?;
+    let (source, ty) = parse(source, types)?;
+    let (source, _) = preceded(spaces, char('>'))(source)?;
+    let vec_ty = types
+        .base_mut()
+        .vector(ty, n.parse::<u32>().unwrap(), vscale.is_some());
+    Ok((source, vec_ty))
+}
+
 fn parse_struct<'a>(
     mut source: &'a str,
     types: &Types,
@@ -134,3 +166,45 @@ fn test_metadata() {
     let (_, ty) = parse(source, &types).unwrap();
     assert!(types.base().metadata() == ty)
 }
+
+#[test]
+fn test_float_types() {
+    let types = Types::default();
+    for (source, expected) in [
+        ("half", types.base().half()),
+        ("float", types.base().float()),
+        ("double", types.base().double()),
+        ("x86_fp80", types.base().x86_fp80()),
+        ("fp128", types.base().fp128()),
+    ] {
+        let (_, ty) = parse(source, &types).unwrap();
+        assert!(ty == expected);
+        assert_eq!(types.to_string(ty), source);
+    }
+}
+
+#[test]
+fn test_int_types() {
+    let types = Types::default();
+    for source in ["i1", "i3", "i16", "i24", "i32", "i128"] {
+        let (_, ty) = parse(source, &types).unwrap();
+        assert_eq!(types.to_string(ty), source);
+    }
+    assert!(parse("i32", &types).unwrap().1 == types.base().i32());
+    for source in ["i0", "i8388608", "i4294967296"] {
+        assert!(parse(source, &types).is_err());
+    }
+}
+
+#[test]
+fn test_vector_types() {
+    let types = Types::default();
+    for source in ["<4 x i32>", "<vscale x 4 x float>", "<2 x i8*>"] {
+        let (_, ty) = parse(source, &types).unwrap();
+        assert_eq!(types.to_string(ty), source);
+    }
+    let (_, x) = parse("<vscale x 2 x i64>", &types).unwrap();
+    let (_, y) = parse("<2 x i64>", &types).unwrap();
+    assert!(x != y);
+    assert!(types.get(x).as_vector().is_scalable);
+}
@@ -0,0 +1,116 @@
+use nom::error::{ContextError, VerboseError, VerboseErrorKind};
+use nom::{Err, IResult};
+use std::{error::Error, fmt};
+
+/// An error from parsing LLVM Assembly, located by line and column (both 1-based).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    /// The source line the error is on
+    pub source_line: String,
+    /// What went wrong, e.g. `expected type after 'alloca'`
+    pub message: String,
+}
+
+impl ParseError {
+    /// Locates `err` in `source`, which must be the whole input given to the parser.
+    pub fn new(source: &str, err: Err<VerboseError<&str>>) -> Self {
+        let errors = match err {
+            Err::Error(e) | Err::Failure(e) => e.errors,
+            Err::Incomplete(_) => vec![],
+        };
+        let offset_of = |input: &str| source.len() - input.len();
+        let (offset, message) = match errors.first() {
+            Some((input, kind)) => {
+                let input = input.trim_start();
+                // The innermost context wins if it failed at the same place
+                let context = errors.iter().find_map(|(i, kind)| match kind {
+                    VerboseErrorKind::Context(ctx) if i.trim_start().len() == input.len() => {
+                        Some(*ctx)
+                    }
+                    _ => None,
+                });
+                let message = match (context, kind) {
+                    (Some(ctx), _) => format!("expected {}", ctx),
+                    (None, VerboseErrorKind::Char(c)) => format!("expected '{}'", c),
+                    (None, _) => match input.split_whitespace().next() {
+                        Some(token) => format!("unexpected '{}'", token),
+                        None => "unexpected end of input".to_string(),
+                    },
+                };
+                (offset_of(input), message)
+            }
+            None => (source.len(), "unexpected end of input".to_string()),
+        };
+
+        let before = &source[..offset];
+        let message = match before.split_whitespace().last() {
+            Some(prev) if message.starts_with("expected") => {
+                format!("{} after '{}'", message, prev)
+            }
+            _ => message,
+        };
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+            source_line: source[line_start..line_end].to_string(),
+            message,
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(
+            f,
+            "{}--> line {}, column {}",
+            gutter, self.line, self.column
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(
+            f,
+            "{} | {}^",
+            gutter,
+            " ".repeat(self.column.saturating_sub(1))
+        )
+    }
+}
+
+/// Tags the error of `result`, if any, with what `source` was expected to start with.
+pub fn expected<'a, T>(
+    source: &'a str,
+    what: &'static str,
+    result: IResult<&'a str, T, VerboseError<&'a str>>,
+) -> IResult<&'a str, T, VerboseError<&'a str>> {
+    result.map_err(|e| e.map(|e| VerboseError::add_context(source, what, e)))
+}
+
+/// Returns whichever error got further into the input.
+pub fn furthest<'a>(
+    x: Err<VerboseError<&'a str>>,
+    y: Err<VerboseError<&'a str>>,
+) -> Err<VerboseError<&'a str>> {
+    fn remaining(e: &Err<VerboseError<&str>>) -> usize {
+        match e {
+            Err::Error(e) | Err::Failure(e) => {
+                e.errors.first().map_or(usize::MAX, |(i, _)| i.len())
+            }
+            Err::Incomplete(_) => usize::MAX,
+        }
+    }
+    if remaining(&y) < remaining(&x) {
+        y
+    } else {
+        x
+    }
+}
@@ -1,3 +1,5 @@
+pub mod error;
+
 use nom::{
     branch::alt,
     bytes::complete::take_until,
@@ -31,6 +33,40 @@ pub fn string_literal(source: &str) -> IResult<&str, String, VerboseError<&str>>
     )(source)
 }
 
+/// Parses a quoted name. LLVM escapes each byte of a non-ASCII name (`%"\E3\81\82"`), so
+/// the escaped bytes are read as UTF-8 where they form it.
+pub fn name_literal(source: &str) -> IResult<&str, String, VerboseError<&str>> {
+    map(
+        preceded(char('\"'), cut(terminated(take_until("\""), char('\"')))),
+        |s| {
+            unescape_bytes(s)
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .unwrap_or_else(|| unescape(s).unwrap())
+        },
+    )(source)
+}
+
+/// Like `unescape` but gives the bytes, with each escape as one byte.
+pub fn unescape_bytes(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => bytes.push(b'\\'),
+            Some(c) if c.is_ascii_hexdigit() => {
+                let cc = chars.next()?;
+                bytes.push(u8::from_str_radix(&format!("{}{}", c, cc), 16).ok()?)
+            }
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
+
 pub fn unescape(s: &str) -> Option<String> {
     let mut queue: VecDeque<_> = String::from(s).chars().collect();
     let mut s = String::new();
@@ -64,7 +100,7 @@ pub fn escape(s: &str) -> String {
         &s.as_bytes()
             .iter()
             .flat_map(|&c| {
-                if c.is_ascii_control() {
+                if c.is_ascii_control() || c == b'"' || c == b'\\' {
                     vec![b'\\', hexify(c >> 4), hexify(c & 0xf)]
                 } else {
                     vec![c]
@@ -0,0 +1,606 @@
+use super::{
+    ConstantArray, ConstantData, ConstantExpr, ConstantFloat, ConstantInt, ConstantStruct,
+    ConstantVector,
+};
+use crate::ir::{
+    function::instruction::{FCmpCond, ICmpCond, Opcode},
+    types::{Type, TypeId, Types},
+};
+use std::cmp::Ordering;
+
+impl ConstantData {
+    /// Returns `self` with every constant expression in it evaluated, or `None` if some
+    /// expression cannot be folded (e.g. it depends on the address of a global).
+    pub fn fold(&self, types: &Types) -> Option<Self> {
+        match self {
+            Self::Expr(e) => e.fold(types),
+            konst => Some(konst.clone()),
+        }
+    }
+}
+
+impl ConstantExpr {
+    /// Evaluates the expression if all its operands are known.
+    pub fn fold(&self, types: &Types) -> Option<ConstantData> {
+        match self {
+            Self::GetElementPtr { .. } => None,
+            Self::Cast { opcode, tys, arg } => fold_cast(*opcode, &arg.fold(types)?, tys[1], types),
+            Self::IntBinary { opcode, args, .. } => {
+                fold_int_binary(*opcode, &args[0].fold(types)?, &args[1].fold(types)?)
+            }
+            Self::FloatBinary { opcode, args, .. } => {
+                fold_float_binary(*opcode, &args[0].fold(types)?, &args[1].fold(types)?)
+            }
+            Self::FNeg { arg, .. } => match arg.fold(types)? {
+                ConstantData::Float(f) => Some(ConstantData::Float(f.with_f64(-f.to_f64()))),
+                ConstantData::Poison => Some(ConstantData::Poison),
+                _ => None,
+            },
+            Self::ICmp { cond, args, .. } => fold_icmp(
+                *cond,
+                &args[0].fold(types)?,
+                &args[1].fold(types)?,
+                types.base().i1(),
+            ),
+            Self::FCmp { cond, args, .. } => {
+                fold_fcmp(*cond, &args[0].fold(types)?, &args[1].fold(types)?)
+            }
+            Self::Select { args, .. } => match args[0].fold(types)? {
+                ConstantData::Int(cond) => args[if cond.is_zero() { 2 } else { 1 }].fold(types),
+                ConstantData::Poison => Some(ConstantData::Poison),
+                _ => None,
+            },
+            Self::ExtractElement { tys, args } => {
+                fold_extractelement(&args[0].fold(types)?, &args[1].fold(types)?, tys[0], types)
+            }
+            Self::InsertElement { tys, args } => {
+                let mut elems = vector_elems(&args[0].fold(types)?, tys[0], types)?;
+                let idx = match args[2].fold(types)? {
+                    ConstantData::Int(i) => i.zext_value(),
+                    ConstantData::Poison => None,
+                    _ => return None,
+                };
+                // An out of range index yields poison
+                match idx.and_then(|idx| elems.get_mut(idx as usize)) {
+                    Some(elem) => *elem = args[1].fold(types)?,
+                    None => return Some(ConstantData::Poison),
+                }
+                Some(ConstantData::Vector(ConstantVector::new(
+                    types.get_element(tys[0])?,
+                    elems,
+                )))
+            }
+            Self::ShuffleVector { tys, args } => {
+                let v1 = vector_elems(&args[0].fold(types)?, tys[0], types)?;
+                let v2 = vector_elems(&args[1].fold(types)?, tys[0], types)?;
+                let mask = vector_elems(&args[2].fold(types)?, tys[1], types)?;
+                let elems = mask
+                    .iter()
+                    .map(|m| match m {
+                        ConstantData::Int(i) => {
+                            let i = i.zext_value()? as usize;
+                            v1.get(i).or_else(|| v2.get(i - v1.len())).cloned()
+                        }
+                        ConstantData::Undef => Some(ConstantData::Undef),
+                        ConstantData::Poison => Some(ConstantData::Poison),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(ConstantData::Vector(ConstantVector::new(
+                    types.get_element(tys[0])?,
+                    elems,
+                )))
+            }
+            Self::ExtractValue { arg, indices, .. } => {
+                let mut konst = arg.fold(types)?;
+                for &idx in indices {
+                    konst = aggregate_elems(&konst)?.get(idx as usize)?.clone();
+                }
+                Some(konst)
+            }
+            Self::InsertValue { args, indices, .. } => {
+                let mut aggre = args[0].fold(types)?;
+                let elem = args[1].fold(types)?;
+                let mut konst = &mut aggre;
+                for &idx in indices {
+                    konst = match konst {
+                        ConstantData::Array(a) => a.elems.get_mut(idx as usize)?,
+                        ConstantData::Struct(s) => s.elems.get_mut(idx as usize)?,
+                        _ => return None,
+                    };
+                }
+                *konst = elem;
+                Some(aggre)
+            }
+        }
+    }
+}
+
+/// Returns the elements of a constant array or struct.
+fn aggregate_elems(konst: &ConstantData) -> Option<&[ConstantData]> {
+    match konst {
+        ConstantData::Array(a) => Some(&a.elems),
+        ConstantData::Struct(s) => Some(&s.elems),
+        _ => None,
+    }
+}
+
+/// Returns every lane of a constant vector of type `ty`, expanding splats and `zeroinitializer`.
+fn vector_elems(konst: &ConstantData, ty: TypeId, types: &Types) -> Option<Vec<ConstantData>> {
+    let len = match &*types.get(ty) {
+        Type::Vector(v) if !v.is_scalable => v.num_elements as usize,
+        _ => return None,
+    };
+    match konst {
+        ConstantData::Vector(v) => (0..len).map(|i| v.get(i).cloned()).collect(),
+        ConstantData::AggregateZero => match &*types.get(types.get_element(ty)?) {
+            Type::Int(bits) => Some(vec![ConstantData::Int(ConstantInt::zero(*bits)); len]),
+            _ => None,
+        },
+        ConstantData::Undef => Some(vec![ConstantData::Undef; len]),
+        ConstantData::Poison => Some(vec![ConstantData::Poison; len]),
+        _ => None,
+    }
+}
+
+pub(crate) fn fold_int_binary(
+    opcode: Opcode,
+    x: &ConstantData,
+    y: &ConstantData,
+) -> Option<ConstantData> {
+    match (x, y) {
+        // Division by poison is UB, so it isn't folded
+        (_, ConstantData::Poison) if opcode.is_division() => None,
+        (ConstantData::Poison, _) | (_, ConstantData::Poison) => Some(ConstantData::Poison),
+        (ConstantData::Int(x), ConstantData::Int(y)) => {
+            let val = match opcode {
+                Opcode::Add => x.add(y),
+                Opcode::Sub => x.sub(y),
+                Opcode::Mul => x.mul(y),
+                Opcode::SDiv => x.sdiv(y)?,
+                Opcode::UDiv => x.udiv(y)?,
+                Opcode::SRem => x.srem(y)?,
+                Opcode::URem => x.urem(y)?,
+                Opcode::And => x.and(y),
+                Opcode::Or => x.or(y),
+                Opcode::Xor => x.xor(y),
+                // Shifting by the bit width or more yields poison
+                Opcode::Shl => {
+                    return Some(x.shl(y).map_or(ConstantData::Poison, ConstantData::Int))
+                }
+                Opcode::LShr => {
+                    return Some(x.lshr(y).map_or(ConstantData::Poison, ConstantData::Int))
+                }
+                Opcode::AShr => {
+                    return Some(x.ashr(y).map_or(ConstantData::Poison, ConstantData::Int))
+                }
+                _ => return None,
+            };
+            Some(ConstantData::Int(val))
+        }
+        // Vectors are folded element-wise
+        (ConstantData::Vector(x), ConstantData::Vector(y)) => x
+            .zip_with(y, x.elem_ty, |x, y| fold_int_binary(opcode, x, y))
+            .map(ConstantData::Vector),
+        (ConstantData::Vector(v), ConstantData::AggregateZero) => {
+            fold_int_binary(opcode, x, &zero_vector_like(v)?)
+        }
+        (ConstantData::AggregateZero, ConstantData::Vector(v)) => {
+            fold_int_binary(opcode, &zero_vector_like(v)?, y)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn fold_icmp(
+    cond: ICmpCond,
+    x: &ConstantData,
+    y: &ConstantData,
+    i1: TypeId,
+) -> Option<ConstantData> {
+    match (x, y) {
+        (ConstantData::Poison, _) | (_, ConstantData::Poison) => Some(ConstantData::Poison),
+        (ConstantData::Int(x), ConstantData::Int(y)) => {
+            let (u, s) = (x.ucmp(y), x.scmp(y));
+            let val = match cond {
+                ICmpCond::Eq => x == y,
+                ICmpCond::Ne => x != y,
+                ICmpCond::Ugt => u == Ordering::Greater,
+                ICmpCond::Uge => u != Ordering::Less,
+                ICmpCond::Ult => u == Ordering::Less,
+                ICmpCond::Ule => u != Ordering::Greater,
+                ICmpCond::Sgt => s == Ordering::Greater,
+                ICmpCond::Sge => s != Ordering::Less,
+                ICmpCond::Slt => s == Ordering::Less,
+                ICmpCond::Sle => s != Ordering::Greater,
+            };
+            Some(ConstantData::Int(ConstantInt::from(val)))
+        }
+        (ConstantData::Null, ConstantData::Null) => match cond {
+            ICmpCond::Eq | ICmpCond::Uge | ICmpCond::Ule | ICmpCond::Sge | ICmpCond::Sle => {
+                Some(ConstantData::Int(ConstantInt::from(true)))
+            }
+            _ => Some(ConstantData::Int(ConstantInt::from(false))),
+        },
+        // Two references to the same global always compare equal
+        (ConstantData::GlobalRef(x), ConstantData::GlobalRef(y)) if x == y => {
+            fold_icmp(cond, &ConstantData::Null, &ConstantData::Null, i1)
+        }
+        (ConstantData::Vector(x), ConstantData::Vector(y)) => x
+            .zip_with(y, i1, |x, y| fold_icmp(cond, x, y, i1))
+            .map(ConstantData::Vector),
+        (ConstantData::Vector(v), ConstantData::AggregateZero) => {
+            fold_icmp(cond, x, &zero_vector_like(v)?, i1)
+        }
+        (ConstantData::AggregateZero, ConstantData::Vector(v)) => {
+            fold_icmp(cond, &zero_vector_like(v)?, y, i1)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn fold_float_binary(
+    opcode: Opcode,
+    x: &ConstantData,
+    y: &ConstantData,
+) -> Option<ConstantData> {
+    match (x, y) {
+        (ConstantData::Poison, _) | (_, ConstantData::Poison) => Some(ConstantData::Poison),
+        // Computing in `f64` and rounding the result is exact for `half` and `float`
+        (ConstantData::Float(x), ConstantData::Float(y)) if is_f64_exact(x) => {
+            let (a, b) = (x.to_f64(), y.to_f64());
+            let val = match opcode {
+                Opcode::FAdd => a + b,
+                Opcode::FSub => a - b,
+                Opcode::FMul => a * b,
+                Opcode::FDiv => a / b,
+                Opcode::FRem => a % b,
+                _ => return None,
+            };
+            Some(ConstantData::Float(x.with_f64(val)))
+        }
+        (ConstantData::Vector(x), ConstantData::Vector(y)) => x
+            .zip_with(y, x.elem_ty, |x, y| fold_float_binary(opcode, x, y))
+            .map(ConstantData::Vector),
+        _ => None,
+    }
+}
+
+pub(crate) fn fold_fcmp(
+    cond: FCmpCond,
+    x: &ConstantData,
+    y: &ConstantData,
+) -> Option<ConstantData> {
+    let (x, y) = match (x, y) {
+        (ConstantData::Float(x), ConstantData::Float(y)) => (x.to_f64(), y.to_f64()),
+        (ConstantData::Poison, _) | (_, ConstantData::Poison) => return Some(ConstantData::Poison),
+        _ => return None,
+    };
+    let unordered = x.is_nan() || y.is_nan();
+    let ord = x.partial_cmp(&y);
+    let val = match cond {
+        FCmpCond::False => false,
+        FCmpCond::True => true,
+        FCmpCond::Ord => !unordered,
+        FCmpCond::Uno => unordered,
+        FCmpCond::Oeq => ord == Some(Ordering::Equal),
+        FCmpCond::Ogt => ord == Some(Ordering::Greater),
+        FCmpCond::Oge => matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+        FCmpCond::Olt => ord == Some(Ordering::Less),
+        FCmpCond::Ole => matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+        FCmpCond::One => matches!(ord, Some(Ordering::Less | Ordering::Greater)),
+        FCmpCond::Ueq => unordered || ord == Some(Ordering::Equal),
+        FCmpCond::Ugt => unordered || ord == Some(Ordering::Greater),
+        FCmpCond::Uge => unordered || matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+        FCmpCond::Ult => unordered || ord == Some(Ordering::Less),
+        FCmpCond::Ule => unordered || matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+        FCmpCond::Une => unordered || ord != Some(Ordering::Equal),
+    };
+    Some(ConstantData::Int(ConstantInt::from(val)))
+}
+
+/// Folds a cast of `arg` to the type `to`.
+pub(crate) fn fold_cast(
+    opcode: Opcode,
+    arg: &ConstantData,
+    to: TypeId,
+    types: &Types,
+) -> Option<ConstantData> {
+    if let ConstantData::Vector(v) = arg {
+        let inner = types.get_element(to)?;
+        let elems = v
+            .elems
+            .iter()
+            .map(|e| fold_cast(opcode, e, inner, types))
+            .collect::<Option<Vec<_>>>()?;
+        return Some(ConstantData::Vector(ConstantVector {
+            elem_ty: inner,
+            elems,
+            is_splat: v.is_splat,
+        }));
+    }
+    let to_ty = types.get(to);
+    match (opcode, arg, &*to_ty) {
+        (Opcode::Trunc, ConstantData::Int(i), Type::Int(bits)) => {
+            Some(ConstantData::Int(i.trunc(*bits)))
+        }
+        (Opcode::Zext, ConstantData::Int(i), Type::Int(bits)) => {
+            Some(ConstantData::Int(i.zext(*bits)))
+        }
+        (Opcode::Sext, ConstantData::Int(i), Type::Int(bits)) => {
+            Some(ConstantData::Int(i.sext(*bits)))
+        }
+        (Opcode::PtrToInt, ConstantData::Null, Type::Int(bits)) => {
+            Some(ConstantData::Int(ConstantInt::zero(*bits)))
+        }
+        (Opcode::IntToPtr, ConstantData::Int(i), Type::Pointer(_)) if i.is_zero() => {
+            Some(ConstantData::Null)
+        }
+        (Opcode::Bitcast | Opcode::AddrSpaceCast, ConstantData::Null, Type::Pointer(_)) => {
+            Some(ConstantData::Null)
+        }
+        (Opcode::Bitcast, ConstantData::Int(i), _) => {
+            let bits = i.zext_value()?;
+            Some(ConstantData::Float(match &*to_ty {
+                Type::Half if i.bits() == 16 => ConstantFloat::Half(bits as u16),
+                Type::Float if i.bits() == 32 => ConstantFloat::Float(f32::from_bits(bits as u32)),
+                Type::Double if i.bits() == 64 => ConstantFloat::Double(f64::from_bits(bits)),
+                Type::Int(n) if *n == i.bits() => return Some(arg.clone()),
+                _ => return None,
+            }))
+        }
+        (Opcode::Bitcast, ConstantData::Float(f), Type::Int(bits)) => match f {
+            ConstantFloat::Half(_) | ConstantFloat::Float(_) | ConstantFloat::Double(_)
+                if float_bits(f) == *bits =>
+            {
+                Some(ConstantData::Int(ConstantInt::new(
+                    *bits,
+                    f.to_bits() as u64,
+                )))
+            }
+            _ => None,
+        },
+        (Opcode::FPToSI, ConstantData::Float(f), Type::Int(bits)) if *bits <= 64 => {
+            let val = f.to_f64().trunc();
+            let (min, max) = (-(2f64.powi(*bits as i32 - 1)), 2f64.powi(*bits as i32 - 1));
+            // Out of range values yield poison
+            if !(min <= val && val < max) {
+                return Some(ConstantData::Poison);
+            }
+            Some(ConstantData::Int(ConstantInt::from_i64(*bits, val as i64)))
+        }
+        (Opcode::FPToUI, ConstantData::Float(f), Type::Int(bits)) if *bits <= 64 => {
+            let val = f.to_f64().trunc();
+            if !(0.0 <= val && val < 2f64.powi(*bits as i32)) {
+                return Some(ConstantData::Poison);
+            }
+            Some(ConstantData::Int(ConstantInt::new(*bits, val as u64)))
+        }
+        (Opcode::SIToFP, ConstantData::Int(i), _) => {
+            let val = i.sext_value()?;
+            float_of_type(&to_ty, val as f32, val as f64)
+        }
+        (Opcode::UIToFP, ConstantData::Int(i), _) => {
+            let val = i.zext_value()?;
+            float_of_type(&to_ty, val as f32, val as f64)
+        }
+        (Opcode::FPTrunc | Opcode::FPExt, ConstantData::Float(f), _) if is_f64_exact(f) => {
+            float_of_type(&to_ty, f.to_f64() as f32, f.to_f64())
+        }
+        (_, ConstantData::Undef, _) => Some(ConstantData::Undef),
+        (_, ConstantData::Poison, _) => Some(ConstantData::Poison),
+        _ => None,
+    }
+}
+
+/// Folds `extractelement` of the vector `vec` of type `ty`. An out of range index yields poison.
+pub(crate) fn fold_extractelement(
+    vec: &ConstantData,
+    idx: &ConstantData,
+    ty: TypeId,
+    types: &Types,
+) -> Option<ConstantData> {
+    match idx {
+        ConstantData::Int(i) => {
+            let elems = vector_elems(vec, ty, types)?;
+            Some(
+                i.zext_value()
+                    .and_then(|i| elems.get(i as usize).cloned())
+                    .unwrap_or(ConstantData::Poison),
+            )
+        }
+        ConstantData::Poison => Some(ConstantData::Poison),
+        _ => None,
+    }
+}
+
+/// Folds `freeze` of `arg`. Poison and undef are frozen to zero, also inside vectors, arrays
+/// and structs. Constant expressions are left unfolded since they may turn out poison.
+pub(crate) fn fold_freeze(arg: &ConstantData, ty: TypeId, types: &Types) -> Option<ConstantData> {
+    match arg {
+        ConstantData::Poison | ConstantData::Undef => {}
+        ConstantData::Vector(v) => {
+            let elems = v
+                .elems
+                .iter()
+                .map(|e| fold_freeze(e, v.elem_ty, types))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(ConstantData::Vector(ConstantVector { elems, ..v.clone() }));
+        }
+        ConstantData::Array(a) => {
+            let elems = a
+                .elems
+                .iter()
+                .map(|e| fold_freeze(e, a.elem_ty, types))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(ConstantData::Array(ConstantArray { elems, ..a.clone() }));
+        }
+        ConstantData::Struct(s) => {
+            let elems = s
+                .elems
+                .iter()
+                .zip(s.elems_ty.iter())
+                .map(|(e, &ty)| fold_freeze(e, ty, types))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(ConstantData::Struct(ConstantStruct { elems, ..s.clone() }));
+        }
+        ConstantData::Expr(_) => return None,
+        _ => return Some(arg.clone()),
+    }
+    Some(match &*types.get(ty) {
+        Type::Int(bits) => ConstantData::Int(ConstantInt::zero(*bits)),
+        Type::Pointer(_) => ConstantData::Null,
+        Type::Vector(_) | Type::Array(_) | Type::Struct(_) => ConstantData::AggregateZero,
+        ty @ (Type::Half | Type::Float | Type::Double) => float_of_type(ty, 0.0, 0.0)?,
+        _ => return None,
+    })
+}
+
+/// Returns a vector of integer zeros that has the same type as `v`.
+pub(crate) fn zero_vector_like(v: &ConstantVector) -> Option<ConstantData> {
+    let zero = match v.get(0)? {
+        ConstantData::Int(i) => ConstantData::Int(ConstantInt::zero(i.bits())),
+        _ => return None,
+    };
+    Some(ConstantData::Vector(if v.is_splat {
+        ConstantVector::splat(v.elem_ty, zero)
+    } else {
+        ConstantVector::new(v.elem_ty, vec![zero; v.elems.len()])
+    }))
+}
+
+/// Returns true if `f` is exactly representable as `f64`.
+fn is_f64_exact(f: &ConstantFloat) -> bool {
+    matches!(
+        f,
+        ConstantFloat::Half(_) | ConstantFloat::Float(_) | ConstantFloat::Double(_)
+    )
+}
+
+fn float_bits(f: &ConstantFloat) -> u32 {
+    match f {
+        ConstantFloat::Half(_) => 16,
+        ConstantFloat::Float(_) => 32,
+        ConstantFloat::Double(_) => 64,
+        ConstantFloat::X86Fp80(_) => 80,
+        ConstantFloat::Fp128(_) => 128,
+    }
+}
+
+/// Makes a floating-point constant of type `ty`. `f` is used for `float` to avoid
+/// rounding twice.
+fn float_of_type(ty: &Type, f: f32, d: f64) -> Option<ConstantData> {
+    Some(ConstantData::Float(match ty {
+        Type::Half => ConstantFloat::Half(0).with_f64(d),
+        Type::Float => ConstantFloat::Float(f),
+        Type::Double => ConstantFloat::Double(d),
+        _ => return None,
+    }))
+}
+
+#[test]
+fn test_fold_constant_expr() {
+    use super::parser::parse_constant;
+
+    let types = Types::new();
+    let i32 = types.base().i32();
+    let i64 = types.base().i64();
+    let i8 = types.base().i8();
+    let i8ptr = types.base_mut().pointer(i8);
+    let double = types.base().double();
+    let v4i32 = types.base_mut().vector(i32, 4, false);
+    for (ty, source, expected) in [
+        (i32, "add (i32 1, i32 2)", "3"),
+        (i32, "sub nsw (i32 1, i32 2)", "-1"),
+        (i32, "shl (i32 1, i32 4)", "16"),
+        (i32, "trunc (i64 4294967297 to i32)", "1"),
+        (i64, "sext (i32 -1 to i64)", "-1"),
+        (i64, "zext (i32 -1 to i64)", "4294967295"),
+        (i64, "ptrtoint (i8* null to i64)", "0"),
+        (i8ptr, "inttoptr (i64 0 to i8*)", "null"),
+        (i64, "bitcast (double 1.0 to i64)", "4607182418800017408"),
+        (i32, "fptosi (double -2.5 to i32)", "-2"),
+        (double, "sitofp (i32 -3 to double)", "-3.000000e+00"),
+        (double, "fadd (double 1.5, double 2.0)", "3.500000e+00"),
+        (double, "fneg (double 1.5)", "-1.500000e+00"),
+        (i32, "select (i1 icmp slt (i32 1, i32 2), i32 10, i32 20)", "10"),
+        (i32, "select (i1 fcmp uno (double 1.0, double 2.0), i32 10, i32 20)", "20"),
+        (
+            i32,
+            "extractelement (<4 x i32> <i32 1, i32 2, i32 3, i32 4>, i32 2)",
+            "3",
+        ),
+        (
+            v4i32,
+            "insertelement (<4 x i32> zeroinitializer, i32 7, i32 1)",
+            "<i32 0, i32 7, i32 0, i32 0>",
+        ),
+        (
+            v4i32,
+            "shufflevector (<4 x i32> <i32 1, i32 2, i32 3, i32 4>, <4 x i32> splat (i32 9), <4 x i32> <i32 0, i32 4, i32 3, i32 7>)",
+            "<i32 1, i32 9, i32 4, i32 9>",
+        ),
+        (
+            i32,
+            "extractvalue ({ i32, [2 x i32] } { i32 1, [2 x i32] [i32 2, i32 3] }, 1, 0)",
+            "2",
+        ),
+        (
+            i32,
+            "extractvalue ({ i32, i32 } insertvalue ({ i32, i32 } { i32 1, i32 2 }, i32 5, 1), 1)",
+            "5",
+        ),
+        (i32, "add (i32 ptrtoint (i8* @g to i32), i32 1)", ""),
+        (i32, "sdiv (i32 1, i32 0)", ""),
+        (i32, "add (i32 poison, i32 1)", "poison"),
+        (i32, "shl (i32 1, i32 32)", "poison"),
+        (i32, "fptosi (double 1.0e10 to i32)", "poison"),
+        (i32, "select (i1 poison, i32 1, i32 2)", "poison"),
+        (i32, "extractelement (<4 x i32> zeroinitializer, i32 4)", "poison"),
+        (
+            v4i32,
+            "shufflevector (<4 x i32> splat (i32 1), <4 x i32> poison, <4 x i32> <i32 0, i32 poison, i32 5, i32 undef>)",
+            "<i32 1, i32 poison, i32 poison, i32 undef>",
+        ),
+    ] {
+        let (rest, konst) = parse_constant(source, &types, ty).expect(source);
+        assert!(rest.is_empty());
+        // The printed expression must parse back to the same one
+        let printed = konst.to_string(&types);
+        let (_, reparsed) = parse_constant(&printed, &types, ty).unwrap();
+        assert_eq!(reparsed.to_string(&types), printed);
+        assert_eq!(
+            konst.fold(&types).map_or("".to_string(), |k| k.to_string(&types)),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_fold_freeze() {
+    use super::parser::parse_constant;
+
+    let types = Types::new();
+    let i32 = types.base().i32();
+    let v2i32 = types.base_mut().vector(i32, 2, false);
+    let a2i32 = types.base_mut().array(i32, 2);
+    let s = types.base_mut().anonymous_struct(vec![i32, v2i32], false);
+    for (ty, source, expected) in [
+        (i32, "poison", "0"),
+        (i32, "7", "7"),
+        (v2i32, "<i32 1, i32 poison>", "<i32 1, i32 0>"),
+        (a2i32, "[i32 undef, i32 2]", "[i32 0, i32 2]"),
+        (
+            s,
+            "{ i32 poison, <2 x i32> <i32 undef, i32 3> }",
+            "{ i32 0, <2 x i32> <i32 0, i32 3> }",
+        ),
+        (i32, "add (i32 ptrtoint (i32* @g to i32), i32 1)", ""),
+    ] {
+        let (_, konst) = parse_constant(source, &types, ty).expect(source);
+        assert_eq!(
+            fold_freeze(&konst, ty, &types).map_or("".to_string(), |k| k.to_string(&types)),
+            expected
+        );
+    }
+}
@@ -0,0 +1,564 @@
+use std::{cmp::Ordering, fmt};
+
+/// An arbitrary-width integer constant, like `APInt` in LLVM.
+/// The value is stored as little-endian 64-bit words and always kept truncated to `bits`,
+/// so every operation wraps around as the LLVM integer type of the same width does.
+/// Whether the value is signed or not depends on the operation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConstantInt {
+    bits: u32,
+    words: Vec<u64>,
+}
+
+fn num_words(bits: u32) -> usize {
+    (bits as usize).div_ceil(64).max(1)
+}
+
+impl ConstantInt {
+    /// Creates a `bits`-wide integer from `val`, truncating it if needed.
+    pub fn new(bits: u32, val: u64) -> Self {
+        assert!(bits > 0, "integer width must be non-zero");
+        let mut words = vec![0; num_words(bits)];
+        words[0] = val;
+        Self { bits, words }.clear_unused_bits()
+    }
+
+    /// Creates a `bits`-wide integer from `val`, sign-extending or truncating it if needed.
+    pub fn from_i64(bits: u32, val: i64) -> Self {
+        assert!(bits > 0, "integer width must be non-zero");
+        let fill = if val < 0 { u64::MAX } else { 0 };
+        let mut words = vec![fill; num_words(bits)];
+        words[0] = val as u64;
+        Self { bits, words }.clear_unused_bits()
+    }
+
+    /// Creates a `bits`-wide integer from little-endian `words`, truncating it if needed.
+    pub fn from_words(bits: u32, words: &[u64]) -> Self {
+        assert!(bits > 0, "integer width must be non-zero");
+        let mut new = vec![0; num_words(bits)];
+        for (n, w) in new.iter_mut().zip(words.iter()) {
+            *n = *w;
+        }
+        Self { bits, words: new }.clear_unused_bits()
+    }
+
+    pub fn zero(bits: u32) -> Self {
+        Self::new(bits, 0)
+    }
+
+    pub fn all_ones(bits: u32) -> Self {
+        Self::from_i64(bits, -1)
+    }
+
+    /// Parses a decimal literal such as `123` or `-42`. Out of range values wrap around.
+    pub fn from_str_decimal(bits: u32, s: &str) -> Option<Self> {
+        let (neg, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        if digits.is_empty() {
+            return None;
+        }
+        let ten = Self::new(bits, 10);
+        let mut val = Self::zero(bits);
+        for c in digits.chars() {
+            let d = c.to_digit(10)?;
+            val = val.mul(&ten).add(&Self::new(bits, d as u64));
+        }
+        Some(if neg { val.neg() } else { val })
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    fn clear_unused_bits(mut self) -> Self {
+        let rem = self.bits % 64;
+        if rem != 0 {
+            *self.words.last_mut().unwrap() &= (1u64 << rem) - 1;
+        }
+        self
+    }
+
+    fn bit(&self, i: u32) -> bool {
+        self.words[i as usize / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn set_bit(&mut self, i: u32) {
+        self.words[i as usize / 64] |= 1 << (i % 64);
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.words[0] == 1 && self.words[1..].iter().all(|w| *w == 0)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.bit(self.bits - 1)
+    }
+
+    pub fn is_min_signed(&self) -> bool {
+        self.is_negative() && self.neg() == *self
+    }
+
+    /// Returns the zero-extended value if it fits in `u64`.
+    pub fn zext_value(&self) -> Option<u64> {
+        if self.words[1..].iter().all(|w| *w == 0) {
+            Some(self.words[0])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the sign-extended value if it fits in `i64`.
+    pub fn sext_value(&self) -> Option<i64> {
+        let ext = self.sext(self.bits.max(64));
+        let fill = if self.is_negative() { u64::MAX } else { 0 };
+        if ext.words[1..].iter().all(|w| *w == fill) && (ext.words[0] >> 63 == fill & 1) {
+            Some(ext.words[0] as i64)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        (self.bits == 1).then(|| self.words[0] == 1)
+    }
+
+    pub fn as_i8(&self) -> Option<i8> {
+        (self.bits == 8).then(|| self.words[0] as i8)
+    }
+
+    pub fn as_i16(&self) -> Option<i16> {
+        (self.bits == 16).then(|| self.words[0] as i16)
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        (self.bits == 32).then(|| self.words[0] as i32)
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        (self.bits == 64).then(|| self.words[0] as i64)
+    }
+
+    /// Returns the lowest bits of the zero-extended value as `usize`.
+    pub fn cast_to_usize(&self) -> usize {
+        self.words[0] as usize
+    }
+
+    pub fn trunc(&self, bits: u32) -> Self {
+        Self::from_words(bits, &self.words)
+    }
+
+    pub fn zext(&self, bits: u32) -> Self {
+        Self::from_words(bits, &self.words)
+    }
+
+    pub fn sext(&self, bits: u32) -> Self {
+        if bits <= self.bits || !self.is_negative() {
+            return Self::from_words(bits, &self.words);
+        }
+        let mut ext = Self::all_ones(bits);
+        for (i, w) in self.words.iter().enumerate() {
+            ext.words[i] = *w;
+        }
+        let rem = self.bits % 64;
+        if rem != 0 {
+            ext.words[self.words.len() - 1] |= !((1u64 << rem) - 1);
+        }
+        ext.clear_unused_bits()
+    }
+
+    pub fn not(&self) -> Self {
+        Self {
+            bits: self.bits,
+            words: self.words.iter().map(|w| !w).collect(),
+        }
+        .clear_unused_bits()
+    }
+
+    pub fn neg(&self) -> Self {
+        self.not().add(&Self::new(self.bits, 1))
+    }
+
+    pub fn add(&self, rhs: &Self) -> Self {
+        debug_assert_eq!(self.bits, rhs.bits);
+        let mut carry = false;
+        let words = self
+            .words
+            .iter()
+            .zip(rhs.words.iter())
+            .map(|(x, y)| {
+                let (s, c1) = x.overflowing_add(*y);
+                let (s, c2) = s.overflowing_add(carry as u64);
+                carry = c1 || c2;
+                s
+            })
+            .collect();
+        Self {
+            bits: self.bits,
+            words,
+        }
+        .clear_unused_bits()
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Self {
+        self.add(&rhs.neg())
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Self {
+        debug_assert_eq!(self.bits, rhs.bits);
+        let n = self.words.len();
+        let mut words = vec![0u64; n];
+        for i in 0..n {
+            let mut carry = 0u128;
+            for j in 0..n - i {
+                let t = self.words[i] as u128 * rhs.words[j] as u128 + words[i + j] as u128 + carry;
+                words[i + j] = t as u64;
+                carry = t >> 64;
+            }
+        }
+        Self {
+            bits: self.bits,
+            words,
+        }
+        .clear_unused_bits()
+    }
+
+    /// Returns the unsigned quotient and remainder, or `None` if `rhs` is zero.
+    fn udivrem(&self, rhs: &Self) -> Option<(Self, Self)> {
+        debug_assert_eq!(self.bits, rhs.bits);
+        if rhs.is_zero() {
+            return None;
+        }
+        let mut quo = Self::zero(self.bits);
+        let mut rem = Self::zero(self.bits);
+        for i in (0..self.bits).rev() {
+            // `rem` may temporarily need one more bit than `bits`
+            let overflow = rem.is_negative();
+            rem = rem.shl_by(1);
+            if self.bit(i) {
+                rem.set_bit(0);
+            }
+            if overflow || rem.ucmp(rhs) != Ordering::Less {
+                rem = rem.sub(rhs);
+                quo.set_bit(i);
+            }
+        }
+        Some((quo, rem))
+    }
+
+    pub fn udiv(&self, rhs: &Self) -> Option<Self> {
+        self.udivrem(rhs).map(|(q, _)| q)
+    }
+
+    pub fn urem(&self, rhs: &Self) -> Option<Self> {
+        self.udivrem(rhs).map(|(_, r)| r)
+    }
+
+    /// Returns `None` on division by zero or when the quotient overflows (`MIN / -1`).
+    pub fn sdiv(&self, rhs: &Self) -> Option<Self> {
+        if self.is_min_signed() && *rhs == Self::all_ones(self.bits) {
+            return None;
+        }
+        let (q, _) = self.abs().udivrem(&rhs.abs())?;
+        Some(if self.is_negative() != rhs.is_negative() {
+            q.neg()
+        } else {
+            q
+        })
+    }
+
+    /// Returns `None` on division by zero or when the quotient overflows (`MIN % -1`).
+    pub fn srem(&self, rhs: &Self) -> Option<Self> {
+        if self.is_min_signed() && *rhs == Self::all_ones(self.bits) {
+            return None;
+        }
+        let (_, r) = self.abs().udivrem(&rhs.abs())?;
+        Some(if self.is_negative() { r.neg() } else { r })
+    }
+
+    fn abs(&self) -> Self {
+        if self.is_negative() {
+            self.neg()
+        } else {
+            self.clone()
+        }
+    }
+
+    pub fn and(&self, rhs: &Self) -> Self {
+        self.zip_words(rhs, |x, y| x & y)
+    }
+
+    pub fn or(&self, rhs: &Self) -> Self {
+        self.zip_words(rhs, |x, y| x | y)
+    }
+
+    pub fn xor(&self, rhs: &Self) -> Self {
+        self.zip_words(rhs, |x, y| x ^ y)
+    }
+
+    fn zip_words(&self, rhs: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        debug_assert_eq!(self.bits, rhs.bits);
+        Self {
+            bits: self.bits,
+            words: self
+                .words
+                .iter()
+                .zip(rhs.words.iter())
+                .map(|(x, y)| f(*x, *y))
+                .collect(),
+        }
+    }
+
+    /// Returns the shift amount held by `rhs`, or `None` if it is not less than the bit width
+    /// (such shifts produce poison).
+    fn shift_amount(&self, rhs: &Self) -> Option<u32> {
+        rhs.zext_value()
+            .filter(|amt| *amt < self.bits as u64)
+            .map(|amt| amt as u32)
+    }
+
+    pub fn shl(&self, rhs: &Self) -> Option<Self> {
+        Some(self.shl_by(self.shift_amount(rhs)?))
+    }
+
+    pub fn lshr(&self, rhs: &Self) -> Option<Self> {
+        Some(self.lshr_by(self.shift_amount(rhs)?))
+    }
+
+    pub fn ashr(&self, rhs: &Self) -> Option<Self> {
+        let amt = self.shift_amount(rhs)?;
+        let shifted = self.lshr_by(amt);
+        if !self.is_negative() || amt == 0 {
+            return Some(shifted);
+        }
+        let fill = Self::all_ones(self.bits).shl_by(self.bits - amt);
+        Some(shifted.or(&fill))
+    }
+
+    fn shl_by(&self, amt: u32) -> Self {
+        let (word_shift, bit_shift) = (amt as usize / 64, amt % 64);
+        let n = self.words.len();
+        let mut words = vec![0u64; n];
+        for (i, w) in words.iter_mut().enumerate().skip(word_shift) {
+            let src = i - word_shift;
+            *w = self.words[src] << bit_shift;
+            if bit_shift != 0 && src > 0 {
+                *w |= self.words[src - 1] >> (64 - bit_shift);
+            }
+        }
+        Self {
+            bits: self.bits,
+            words,
+        }
+        .clear_unused_bits()
+    }
+
+    fn lshr_by(&self, amt: u32) -> Self {
+        let (word_shift, bit_shift) = (amt as usize / 64, amt % 64);
+        let n = self.words.len();
+        let mut words = vec![0u64; n];
+        for (i, w) in words
+            .iter_mut()
+            .take(n.saturating_sub(word_shift))
+            .enumerate()
+        {
+            let src = i + word_shift;
+            *w = self.words[src] >> bit_shift;
+            if bit_shift != 0 && src + 1 < n {
+                *w |= self.words[src + 1] << (64 - bit_shift);
+            }
+        }
+        Self {
+            bits: self.bits,
+            words,
+        }
+    }
+
+    /// Compares two integers as unsigned values.
+    pub fn ucmp(&self, rhs: &Self) -> Ordering {
+        debug_assert_eq!(self.bits, rhs.bits);
+        self.words.iter().rev().cmp(rhs.words.iter().rev())
+    }
+
+    /// Compares two integers as signed values.
+    pub fn scmp(&self, rhs: &Self) -> Ordering {
+        match (self.is_negative(), rhs.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => self.ucmp(rhs),
+        }
+    }
+
+    /// Divides the value as unsigned by a small `divisor`, returning the quotient and remainder.
+    fn divrem_u64(&self, divisor: u64) -> (Self, u64) {
+        let mut rem = 0u128;
+        let mut words = vec![0u64; self.words.len()];
+        for (i, w) in self.words.iter().enumerate().rev() {
+            let cur = rem << 64 | *w as u128;
+            words[i] = (cur / divisor as u128) as u64;
+            rem = cur % divisor as u128;
+        }
+        (
+            Self {
+                bits: self.bits,
+                words,
+            },
+            rem as u64,
+        )
+    }
+
+    /// Returns the value as an unsigned decimal string.
+    pub fn to_string_unsigned(&self) -> String {
+        let mut digits = vec![];
+        let mut val = self.clone();
+        loop {
+            let (quo, rem) = val.divrem_u64(10);
+            digits.push(std::char::from_digit(rem as u32, 10).unwrap());
+            if quo.is_zero() {
+                break;
+            }
+            val = quo;
+        }
+        digits.iter().rev().collect()
+    }
+}
+
+impl From<bool> for ConstantInt {
+    fn from(b: bool) -> Self {
+        Self::new(1, b as u64)
+    }
+}
+
+impl From<i8> for ConstantInt {
+    fn from(i: i8) -> Self {
+        Self::from_i64(8, i as i64)
+    }
+}
+
+impl From<i16> for ConstantInt {
+    fn from(i: i16) -> Self {
+        Self::from_i64(16, i as i64)
+    }
+}
+
+impl From<i32> for ConstantInt {
+    fn from(i: i32) -> Self {
+        Self::from_i64(32, i as i64)
+    }
+}
+
+impl From<i64> for ConstantInt {
+    fn from(i: i64) -> Self {
+        Self::from_i64(64, i)
+    }
+}
+
+impl From<i128> for ConstantInt {
+    fn from(i: i128) -> Self {
+        Self::from_words(128, &[i as u64, (i >> 64) as u64])
+    }
+}
+
+impl fmt::Display for ConstantInt {
+    /// Prints the value as a signed decimal (or `true`/`false` for `i1`), as LLVM does.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(b) = self.as_bool() {
+            return write!(f, "{}", b);
+        }
+        if self.is_negative() {
+            write!(f, "-{}", self.neg().to_string_unsigned())
+        } else {
+            write!(f, "{}", self.to_string_unsigned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConstantInt;
+    use std::cmp::Ordering;
+
+    fn int(bits: u32, s: &str) -> ConstantInt {
+        ConstantInt::from_str_decimal(bits, s).unwrap()
+    }
+
+    #[test]
+    fn parse_and_print() {
+        for (bits, s) in [
+            (3, "-4"),
+            (16, "-32768"),
+            (24, "8388607"),
+            (64, "-9223372036854775808"),
+            (128, "170141183460469231731687303715884105727"),
+            (128, "-170141183460469231731687303715884105728"),
+            (200, "-1"),
+        ] {
+            assert_eq!(int(bits, s).to_string(), s);
+        }
+        assert_eq!(int(8, "255").to_string(), "-1");
+        assert_eq!(int(3, "9").to_string(), "1");
+        assert_eq!(ConstantInt::from(true).to_string(), "true");
+    }
+
+    #[test]
+    fn wrap_around() {
+        assert_eq!(int(3, "3").add(&int(3, "1")), int(3, "-4"));
+        assert_eq!(int(16, "-32768").sub(&int(16, "1")), int(16, "32767"));
+        assert_eq!(int(24, "4096").mul(&int(24, "4096")), int(24, "0"));
+        assert_eq!(
+            int(128, "18446744073709551615").add(&int(128, "1")),
+            int(128, "18446744073709551616")
+        );
+        assert_eq!(
+            int(128, "18446744073709551616").mul(&int(128, "18446744073709551616")),
+            int(128, "0")
+        );
+        assert_eq!(int(16, "-32768").neg(), int(16, "-32768"));
+    }
+
+    #[test]
+    fn div_rem() {
+        assert_eq!(int(16, "-7").sdiv(&int(16, "2")), Some(int(16, "-3")));
+        assert_eq!(int(16, "-7").srem(&int(16, "2")), Some(int(16, "-1")));
+        assert_eq!(int(16, "-1").udiv(&int(16, "2")), Some(int(16, "32767")));
+        assert_eq!(int(16, "-1").urem(&int(16, "-2")), Some(int(16, "1")));
+        assert_eq!(int(8, "-128").sdiv(&int(8, "-1")), None);
+        assert_eq!(int(8, "1").udiv(&int(8, "0")), None);
+        assert_eq!(
+            int(128, "-1").udiv(&int(128, "18446744073709551616")),
+            Some(int(128, "18446744073709551615"))
+        );
+        assert_eq!(
+            int(128, "100000000000000000000000").srem(&int(128, "-7")),
+            Some(int(128, "5"))
+        );
+    }
+
+    #[test]
+    fn shift_and_cmp() {
+        assert_eq!(int(24, "1").shl(&int(24, "23")), Some(int(24, "-8388608")));
+        assert_eq!(int(24, "1").shl(&int(24, "24")), None);
+        assert_eq!(
+            int(128, "-1").lshr(&int(128, "64")),
+            Some(int(128, "18446744073709551615"))
+        );
+        assert_eq!(int(128, "-256").ashr(&int(128, "70")), Some(int(128, "-1")));
+        assert_eq!(int(3, "-1").ashr(&int(3, "1")), Some(int(3, "-1")));
+        assert_eq!(int(16, "-1").ucmp(&int(16, "1")), Ordering::Greater);
+        assert_eq!(int(16, "-1").scmp(&int(16, "1")), Ordering::Less);
+        assert_eq!(int(8, "-1").sext(128), int(128, "-1"));
+        assert_eq!(int(8, "-1").zext(128), int(128, "255"));
+        assert_eq!(int(128, "-2").trunc(3), int(3, "-2"));
+        assert_eq!(int(128, "-2").sext_value(), Some(-2));
+        assert_eq!(int(128, "18446744073709551616").sext_value(), None);
+    }
+}
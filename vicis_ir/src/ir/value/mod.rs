@@ -1,15 +1,21 @@
+pub(crate) mod fold;
+mod int;
 pub mod parser;
 
+pub use int::ConstantInt;
 pub use parser::parse;
 
 use super::{
-    function::{data::Data, instruction::InstructionId},
-    module::name::Name,
+    function::{
+        data::Data,
+        instruction::{FCmpCond, ICmpCond, InstructionId, Opcode},
+    },
+    module::{metadata::Metadata, name::Name},
     types::{TypeId, Types},
     util::escape,
 };
 use id_arena::Id;
-use std::{fmt, str};
+use std::{fmt, slice, str};
 
 pub type ValueId = Id<Value>;
 
@@ -19,26 +25,42 @@ pub enum Value {
     Argument(usize),
     Constant(ConstantData),
     InlineAsm(InlineAsm),
+    /// An operand of type `metadata`, e.g. `metadata !12` or `metadata i32* %1`
+    Metadata(Metadata),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConstantData {
     Undef,
+    Poison,
     AggregateZero,
     Null,
     Int(ConstantInt),
+    Float(ConstantFloat),
     Array(ConstantArray),
+    Vector(ConstantVector),
     Struct(ConstantStruct),
     Expr(ConstantExpr), // TODO: Boxing?
     GlobalRef(Name),
+    BlockAddress(BlockAddress),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ConstantInt {
-    Int1(bool),
-    Int8(i8),
-    Int32(i32),
-    Int64(i64),
+/// `blockaddress(@func, %block)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockAddress {
+    pub func: Name,
+    pub block: Name,
+}
+
+/// A floating-point constant. `Half`, `X86Fp80` and `Fp128` hold their raw bit patterns
+/// since Rust has no native types for them.
+#[derive(Debug, Clone, Copy)]
+pub enum ConstantFloat {
+    Half(u16),
+    Float(f32),
+    Double(f64),
+    X86Fp80(u128),
+    Fp128(u128),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -48,6 +70,16 @@ pub struct ConstantArray {
     pub is_string: bool,
 }
 
+/// A vector constant. A splat constant holds a single element repeated in every lane,
+/// which is the only way to write a non-zero constant of a scalable vector type. Constants
+/// of fixed vector types are never splats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstantVector {
+    pub elem_ty: TypeId,
+    pub elems: Vec<ConstantData>,
+    pub is_splat: bool,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ConstantStruct {
     pub elems_ty: Vec<TypeId>,
@@ -62,10 +94,64 @@ pub enum ConstantExpr {
         tys: Vec<TypeId>,
         args: Vec<ConstantData>,
     },
-    Bitcast {
-        tys: [TypeId; 2],
+    Cast {
+        opcode: Opcode,
+        tys: [TypeId; 2], // from, to
+        arg: Box<ConstantData>,
+    },
+    IntBinary {
+        opcode: Opcode,
+        ty: TypeId,
+        nuw: bool,
+        nsw: bool,
+        exact: bool,
+        args: Box<[ConstantData; 2]>,
+    },
+    FloatBinary {
+        opcode: Opcode,
+        ty: TypeId,
+        args: Box<[ConstantData; 2]>,
+    },
+    FNeg {
+        ty: TypeId,
         arg: Box<ConstantData>,
     },
+    ICmp {
+        cond: ICmpCond,
+        ty: TypeId,
+        args: Box<[ConstantData; 2]>,
+    },
+    FCmp {
+        cond: FCmpCond,
+        ty: TypeId,
+        args: Box<[ConstantData; 2]>,
+    },
+    Select {
+        tys: [TypeId; 2],             // condition type, value type
+        args: Box<[ConstantData; 3]>, // condition, true value, false value
+    },
+    ExtractElement {
+        tys: [TypeId; 2],             // vector type, index type
+        args: Box<[ConstantData; 2]>, // vector, index
+    },
+    InsertElement {
+        tys: [TypeId; 3],             // vector type, element type, index type
+        args: Box<[ConstantData; 3]>, // vector, element, index
+    },
+    ShuffleVector {
+        tys: [TypeId; 2],             // operand vector type, mask type
+        args: Box<[ConstantData; 3]>, // first vector, second vector, mask
+    },
+    ExtractValue {
+        ty: TypeId,
+        arg: Box<ConstantData>,
+        indices: Vec<u32>,
+    },
+    InsertValue {
+        tys: [TypeId; 2],             // aggregate type, element type
+        args: Box<[ConstantData; 2]>, // aggregate, element
+        indices: Vec<u32>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -80,6 +166,10 @@ impl Value {
         Self::Constant(ConstantData::Undef)
     }
 
+    pub fn poison() -> Self {
+        Self::Constant(ConstantData::Poison)
+    }
+
     pub fn as_inst(&self) -> &InstructionId {
         match self {
             Self::Instruction(id) => id,
@@ -106,6 +196,7 @@ impl Value {
                     body
                 )
             }
+            Self::Metadata(meta) => meta.to_string(types),
         }
     }
 }
@@ -114,16 +205,26 @@ impl ConstantData {
     pub fn to_string(&self, types: &Types) -> String {
         match self {
             Self::Undef => "undef".to_string(),
-            Self::AggregateZero => "AggregateZero".to_string(),
+            Self::Poison => "poison".to_string(),
+            Self::AggregateZero => "zeroinitializer".to_string(),
             Self::Null => "null".to_string(),
             Self::Int(i) => i.to_string(),
+            Self::Float(f) => f.to_string(),
             Self::Array(a) => a.to_string(types),
+            Self::Vector(v) => v.to_string(types),
             Self::Struct(s) => s.to_string(types),
             Self::Expr(e) => e.to_string(types),
             Self::GlobalRef(name) => format!("@{:?}", name),
+            Self::BlockAddress(BlockAddress { func, block }) => {
+                format!("blockaddress(@{:?}, %{:?})", func, block)
+            }
         }
     }
 
+    pub fn is_poison(&self) -> bool {
+        matches!(self, Self::Poison)
+    }
+
     pub fn as_int(&self) -> &ConstantInt {
         match self {
             Self::Int(i) => i,
@@ -131,6 +232,13 @@ impl ConstantData {
         }
     }
 
+    pub fn as_float(&self) -> &ConstantFloat {
+        match self {
+            Self::Float(f) => f,
+            _ => panic!(),
+        }
+    }
+
     pub fn as_global_ref(&self) -> &Name {
         match self {
             Self::GlobalRef(name) => name,
@@ -138,6 +246,27 @@ impl ConstantData {
         }
     }
 
+    /// Returns whether this is `@name` or a constant made with it.
+    pub fn refers_to_global(&self, name: &Name) -> bool {
+        match self {
+            Self::GlobalRef(n) => n == name,
+            Self::Array(ConstantArray { elems, .. })
+            | Self::Vector(ConstantVector { elems, .. })
+            | Self::Struct(ConstantStruct { elems, .. }) => {
+                elems.iter().any(|e| e.refers_to_global(name))
+            }
+            Self::Expr(e) => e.args().any(|arg| arg.refers_to_global(name)),
+            _ => false,
+        }
+    }
+
+    pub fn as_vector(&self) -> &ConstantVector {
+        match self {
+            Self::Vector(v) => v,
+            _ => panic!(),
+        }
+    }
+
     pub fn as_array(&self) -> &ConstantArray {
         match self {
             Self::Array(a) => a,
@@ -146,25 +275,183 @@ impl ConstantData {
     }
 }
 
-impl ConstantInt {
-    pub fn as_i8(&self) -> &i8 {
+impl ConstantFloat {
+    /// Converts `val` into a constant of the same kind as `self`, rounding to nearest if needed.
+    pub fn with_f64(&self, val: f64) -> Self {
         match self {
-            Self::Int8(i) => i,
-            _ => panic!(),
+            Self::Half(_) => Self::Half(f64_to_half(val)),
+            Self::Float(_) => Self::Float(val as f32),
+            Self::Double(_) => Self::Double(val),
+            Self::X86Fp80(_) => Self::X86Fp80(f64_to_x86_fp80(val)),
+            Self::Fp128(_) => Self::Fp128(f64_to_fp128(val)),
+        }
+    }
+
+    /// Returns the value as `f64`. `X86Fp80` and `Fp128` may lose precision.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Self::Half(bits) => half_to_f64(*bits),
+            Self::Float(f) => *f as f64,
+            Self::Double(f) => *f,
+            Self::X86Fp80(bits) => x86_fp80_to_f64(*bits),
+            Self::Fp128(bits) => fp128_to_f64(*bits),
         }
     }
 
-    // TODO: Generics?
-    pub fn cast_to_usize(self) -> usize {
+    pub fn to_bits(&self) -> u128 {
         match self {
-            Self::Int1(i) => i as usize,
-            Self::Int8(i) => i as usize,
-            Self::Int32(i) => i as usize,
-            Self::Int64(i) => i as usize,
+            Self::Half(bits) => *bits as u128,
+            Self::Float(f) => f.to_bits() as u128,
+            Self::Double(f) => f.to_bits() as u128,
+            Self::X86Fp80(bits) | Self::Fp128(bits) => *bits,
         }
     }
 }
 
+fn f64_parts(val: f64) -> (bool, i32, u64) {
+    // Returns (sign, unbiased exponent, 53-bit significand with the leading one) for finite
+    // non-zero `val`. Subnormals are normalized.
+    let bits = val.to_bits();
+    let sign = bits >> 63 == 1;
+    let exp = ((bits >> 52) & 0x7ff) as i32;
+    let mant = bits & ((1 << 52) - 1);
+    if exp == 0 {
+        let shift = mant.leading_zeros() as i32 - 11;
+        (sign, -1022 - shift, mant << shift)
+    } else {
+        (sign, exp - 1023, mant | (1 << 52))
+    }
+}
+
+fn f64_to_half(val: f64) -> u16 {
+    let sign = ((val.to_bits() >> 63) as u16) << 15;
+    if val.is_nan() {
+        return sign | 0x7e00 | ((val.to_bits() >> 42) as u16 & 0x1ff);
+    }
+    if val.is_infinite() {
+        return sign | 0x7c00;
+    }
+    if val == 0.0 {
+        return sign;
+    }
+    let (_, exp, mant) = f64_parts(val);
+    // Number of low bits of `mant` to drop
+    let shift = if exp < -14 { 42 + (-14 - exp) } else { 42 } as u32;
+    if shift > 53 {
+        return sign;
+    }
+    let kept = mant >> shift;
+    let rest = mant & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    let rounded = if rest > half || (rest == half && kept & 1 == 1) {
+        kept + 1
+    } else {
+        kept
+    };
+    let bits = if exp < -14 {
+        // Subnormal. Rounding may carry into the smallest normal, which the encoding handles.
+        rounded
+    } else {
+        (((exp + 15) as u64) << 10) + (rounded - (1 << 10))
+    };
+    if bits >= 0x7c00 {
+        return sign | 0x7c00;
+    }
+    sign | bits as u16
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits >> 15 == 1 { -1.0 } else { 1.0 };
+    let exp = ((bits >> 10) & 0x1f) as i32;
+    let mant = (bits & 0x3ff) as f64;
+    match exp {
+        0 => sign * mant * 2f64.powi(-24),
+        0x1f if mant == 0.0 => sign * f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => sign * (1.0 + mant / 1024.0) * 2f64.powi(exp - 15),
+    }
+}
+
+fn f64_to_x86_fp80(val: f64) -> u128 {
+    let sign = (val.to_bits() >> 63) as u128;
+    let (exp, significand) = if val.is_nan() {
+        (
+            0x7fff,
+            (1 << 63) | ((val.to_bits() & ((1 << 52) - 1)) << 11),
+        )
+    } else if val.is_infinite() {
+        (0x7fff, 1 << 63)
+    } else if val == 0.0 {
+        (0, 0)
+    } else {
+        let (_, exp, mant) = f64_parts(val);
+        ((exp + 16383) as u128, mant << 11)
+    };
+    (sign << 79) | (exp << 64) | significand as u128
+}
+
+fn x86_fp80_to_f64(bits: u128) -> f64 {
+    let sign = if (bits >> 79) & 1 == 1 { -1.0 } else { 1.0 };
+    let exp = ((bits >> 64) & 0x7fff) as i32;
+    let significand = bits as u64;
+    if exp == 0x7fff {
+        return if significand << 1 == 0 {
+            sign * f64::INFINITY
+        } else {
+            f64::NAN
+        };
+    }
+    let exp = if exp == 0 { -16382 } else { exp - 16383 };
+    sign * scale(significand as f64, exp - 63)
+}
+
+fn f64_to_fp128(val: f64) -> u128 {
+    let sign = (val.to_bits() >> 63) as u128;
+    let (exp, mant) = if val.is_nan() || val.is_infinite() {
+        (0x7fff, (val.to_bits() & ((1 << 52) - 1)) as u128)
+    } else if val == 0.0 {
+        (0, 0)
+    } else {
+        let (_, exp, mant) = f64_parts(val);
+        ((exp + 16383) as u128, (mant & ((1 << 52) - 1)) as u128)
+    };
+    (sign << 127) | (exp << 112) | (mant << 60)
+}
+
+fn fp128_to_f64(bits: u128) -> f64 {
+    let sign = if bits >> 127 == 1 { -1.0 } else { 1.0 };
+    let exp = ((bits >> 112) & 0x7fff) as i32;
+    let mant = bits & ((1 << 112) - 1);
+    if exp == 0x7fff {
+        return if mant == 0 {
+            sign * f64::INFINITY
+        } else {
+            f64::NAN
+        };
+    }
+    let (exp, significand) = if exp == 0 {
+        (-16382, mant)
+    } else {
+        (exp - 16383, mant | (1 << 112))
+    };
+    // Keep the upper 64 bits (plus a sticky bit) so that `as f64` rounds correctly.
+    let sticky = (significand & ((1 << 49) - 1) != 0) as u64;
+    sign * scale(((significand >> 49) as u64 | sticky) as f64, exp - 63)
+}
+
+fn scale(mut val: f64, mut exp: i32) -> f64 {
+    // `powi` overflows for large exponents, so scale in steps.
+    while exp > 1000 {
+        val *= 2f64.powi(1000);
+        exp -= 1000;
+    }
+    while exp < -1000 {
+        val *= 2f64.powi(-1000);
+        exp += 1000;
+    }
+    val * 2f64.powi(exp)
+}
+
 impl ConstantArray {
     pub fn to_string(&self, types: &Types) -> String {
         if self.is_string {
@@ -174,7 +461,7 @@ impl ConstantArray {
                     str::from_utf8(
                         self.elems
                             .iter()
-                            .map(|i| *i.as_int().as_i8() as u8)
+                            .map(|i| i.as_int().as_i8().unwrap() as u8)
                             .collect::<Vec<u8>>()
                             .as_slice(),
                     )
@@ -200,6 +487,69 @@ impl ConstantArray {
     }
 }
 
+impl ConstantVector {
+    pub fn new(elem_ty: TypeId, elems: Vec<ConstantData>) -> Self {
+        Self {
+            elem_ty,
+            elems,
+            is_splat: false,
+        }
+    }
+
+    pub fn splat(elem_ty: TypeId, elem: ConstantData) -> Self {
+        Self {
+            elem_ty,
+            elems: vec![elem],
+            is_splat: true,
+        }
+    }
+
+    /// Returns the element in the `i`-th lane.
+    pub fn get(&self, i: usize) -> Option<&ConstantData> {
+        if self.is_splat {
+            return self.elems.first();
+        }
+        self.elems.get(i)
+    }
+
+    /// Applies `f` lane by lane. Returns a splat if both `self` and `other` are splats.
+    pub fn zip_with(
+        &self,
+        other: &Self,
+        elem_ty: TypeId,
+        mut f: impl FnMut(&ConstantData, &ConstantData) -> Option<ConstantData>,
+    ) -> Option<Self> {
+        if self.is_splat && other.is_splat {
+            return Some(Self::splat(elem_ty, f(&self.elems[0], &other.elems[0])?));
+        }
+        let len = if self.is_splat {
+            other.elems.len()
+        } else {
+            self.elems.len()
+        };
+        let elems = (0..len)
+            .map(|i| f(self.get(i)?, other.get(i)?))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self::new(elem_ty, elems))
+    }
+
+    pub fn to_string(&self, types: &Types) -> String {
+        let elem_ty = types.to_string(self.elem_ty);
+        if self.is_splat {
+            return format!("splat ({} {})", elem_ty, self.elems[0].to_string(types));
+        }
+        format!(
+            "<{}>",
+            self.elems
+                .iter()
+                .fold("".to_string(), |acc, e| {
+                    format!("{}{} {}, ", acc, elem_ty, e.to_string(types))
+                })
+                .trim_end_matches(", ")
+        )
+    }
+}
+
 impl ConstantStruct {
     pub fn to_string(&self, types: &Types) -> String {
         format!(
@@ -218,7 +568,34 @@ impl ConstantStruct {
 }
 
 impl ConstantExpr {
+    pub fn args(&self) -> impl Iterator<Item = &ConstantData> {
+        let args: &[ConstantData] = match self {
+            Self::GetElementPtr { args, .. } => args,
+            Self::Cast { arg, .. } | Self::FNeg { arg, .. } | Self::ExtractValue { arg, .. } => {
+                slice::from_ref(arg.as_ref())
+            }
+            Self::IntBinary { args, .. }
+            | Self::FloatBinary { args, .. }
+            | Self::ICmp { args, .. }
+            | Self::FCmp { args, .. }
+            | Self::ExtractElement { args, .. }
+            | Self::InsertValue { args, .. } => args.as_ref(),
+            Self::Select { args, .. }
+            | Self::InsertElement { args, .. }
+            | Self::ShuffleVector { args, .. } => args.as_ref(),
+        };
+        args.iter()
+    }
+
     pub fn to_string(&self, types: &Types) -> String {
+        let typed = |ty: TypeId, arg: &ConstantData| {
+            format!("{} {}", types.to_string(ty), arg.to_string(types))
+        };
+        let indices = |indices: &[u32]| {
+            indices
+                .iter()
+                .fold("".to_string(), |acc, idx| format!("{}, {}", acc, idx))
+        };
         match self {
             Self::GetElementPtr {
                 inbounds,
@@ -238,27 +615,148 @@ impl ConstantExpr {
                         .trim_end_matches(", ")
                 )
             }
-            Self::Bitcast { tys, arg } => {
+            Self::Cast { opcode, tys, arg } => {
                 format!(
-                    "bitcast ({} {} to {})",
-                    types.to_string(tys[0]),
-                    arg.to_string(types),
+                    "{:?} ({} to {})",
+                    opcode,
+                    typed(tys[0], arg),
                     types.to_string(tys[1]),
                 )
             }
+            Self::IntBinary {
+                opcode,
+                ty,
+                nuw,
+                nsw,
+                exact,
+                args,
+            } => {
+                format!(
+                    "{:?}{}{}{} ({}, {})",
+                    opcode,
+                    if *nuw { " nuw" } else { "" },
+                    if *nsw { " nsw" } else { "" },
+                    if *exact { " exact" } else { "" },
+                    typed(*ty, &args[0]),
+                    typed(*ty, &args[1]),
+                )
+            }
+            Self::FloatBinary { opcode, ty, args } => {
+                format!(
+                    "{:?} ({}, {})",
+                    opcode,
+                    typed(*ty, &args[0]),
+                    typed(*ty, &args[1]),
+                )
+            }
+            Self::FNeg { ty, arg } => format!("fneg ({})", typed(*ty, arg)),
+            Self::ICmp { cond, ty, args } => {
+                format!(
+                    "icmp {:?} ({}, {})",
+                    cond,
+                    typed(*ty, &args[0]),
+                    typed(*ty, &args[1]),
+                )
+            }
+            Self::FCmp { cond, ty, args } => {
+                format!(
+                    "fcmp {:?} ({}, {})",
+                    cond,
+                    typed(*ty, &args[0]),
+                    typed(*ty, &args[1]),
+                )
+            }
+            Self::Select { tys, args } => {
+                format!(
+                    "select ({}, {}, {})",
+                    typed(tys[0], &args[0]),
+                    typed(tys[1], &args[1]),
+                    typed(tys[1], &args[2]),
+                )
+            }
+            Self::ExtractElement { tys, args } => {
+                format!(
+                    "extractelement ({}, {})",
+                    typed(tys[0], &args[0]),
+                    typed(tys[1], &args[1]),
+                )
+            }
+            Self::InsertElement { tys, args } => {
+                format!(
+                    "insertelement ({}, {}, {})",
+                    typed(tys[0], &args[0]),
+                    typed(tys[1], &args[1]),
+                    typed(tys[2], &args[2]),
+                )
+            }
+            Self::ShuffleVector { tys, args } => {
+                format!(
+                    "shufflevector ({}, {}, {})",
+                    typed(tys[0], &args[0]),
+                    typed(tys[0], &args[1]),
+                    typed(tys[1], &args[2]),
+                )
+            }
+            Self::ExtractValue {
+                ty,
+                arg,
+                indices: idx,
+            } => format!("extractvalue ({}{})", typed(*ty, arg), indices(idx)),
+            Self::InsertValue {
+                tys,
+                args,
+                indices: idx,
+            } => {
+                format!(
+                    "insertvalue ({}, {}{})",
+                    typed(tys[0], &args[0]),
+                    typed(tys[1], &args[1]),
+                    indices(idx)
+                )
+            }
         }
     }
 }
 
+impl From<InstructionId> for Value {
+    fn from(id: InstructionId) -> Self {
+        Self::Instruction(id)
+    }
+}
+
 impl From<i32> for Value {
     fn from(i: i32) -> Self {
-        Self::Constant(ConstantInt::Int32(i).into())
+        Self::Constant(ConstantInt::from(i).into())
     }
 }
 
 impl From<i64> for Value {
     fn from(i: i64) -> Self {
-        Self::Constant(ConstantInt::Int64(i).into())
+        Self::Constant(ConstantInt::from(i).into())
+    }
+}
+
+impl From<f32> for Value {
+    fn from(f: f32) -> Self {
+        Self::Constant(ConstantFloat::Float(f).into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Self::Constant(ConstantFloat::Double(f).into())
+    }
+}
+
+impl From<ConstantFloat> for ConstantData {
+    fn from(f: ConstantFloat) -> Self {
+        Self::Float(f)
+    }
+}
+
+impl From<ConstantFloat> for Value {
+    fn from(f: ConstantFloat) -> Self {
+        Self::Constant(f.into())
     }
 }
 
@@ -274,13 +772,38 @@ impl From<ConstantInt> for Value {
     }
 }
 
-impl fmt::Display for ConstantInt {
+impl PartialEq for ConstantFloat {
+    fn eq(&self, other: &Self) -> bool {
+        // Compare bit patterns so that NaNs with the same payload are equal.
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+            && self.to_bits() == other.to_bits()
+    }
+}
+
+impl fmt::Display for ConstantFloat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Int1(i) => write!(f, "{}", i),
-            Self::Int8(i) => write!(f, "{}", i),
-            Self::Int32(i) => write!(f, "{}", i),
-            Self::Int64(i) => write!(f, "{}", i),
+            Self::Float(_) | Self::Double(_) => {
+                // Same as LLVM: print in decimal if it round-trips, otherwise as hex double.
+                let val = self.to_f64();
+                let dec = format!("{:.6e}", val);
+                if let Some((mantissa, exp)) = dec.split_once('e') {
+                    let exp = exp.parse::<i32>().unwrap();
+                    let dec = format!(
+                        "{}e{}{:02}",
+                        mantissa,
+                        if exp < 0 { '-' } else { '+' },
+                        exp.abs()
+                    );
+                    if matches!(dec.parse::<f64>(), Ok(v) if v.to_bits() == val.to_bits()) {
+                        return write!(f, "{}", dec);
+                    }
+                }
+                write!(f, "0x{:016X}", val.to_bits())
+            }
+            Self::Half(bits) => write!(f, "0xH{:04X}", bits),
+            Self::X86Fp80(bits) => write!(f, "0xK{:04X}{:016X}", bits >> 64, *bits as u64),
+            Self::Fp128(bits) => write!(f, "0xL{:016X}{:016X}", *bits as u64, bits >> 64),
         }
     }
 }
@@ -1,21 +1,31 @@
 use crate::ir::{
-    function::parser::ParserContext,
-    module::name,
+    function::{
+        instruction::parser::{
+            parse_cast_opcode, parse_fcmp_cond, parse_float_binary_opcode, parse_icmp_cond,
+            parse_int_binary_flags, parse_int_binary_opcode,
+        },
+        parser::ParserContext,
+    },
+    module::{metadata, metadata::Metadata, name},
     types::{self, Type, TypeId, Types},
-    util::{spaces, string_literal},
+    util::{error::expected, spaces, string_literal},
     value::{
-        ConstantArray, ConstantData, ConstantExpr, ConstantInt, ConstantStruct, Value, ValueId,
+        BlockAddress, ConstantArray, ConstantData, ConstantExpr, ConstantFloat, ConstantInt,
+        ConstantStruct, ConstantVector, Value, ValueId,
     },
 };
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{char, digit1},
-    combinator::{opt, recognize},
+    character::complete::{char, digit0, digit1, hex_digit1, one_of},
+    combinator::{map_res, opt, recognize},
     error::VerboseError,
+    multi::many1,
     sequence::{preceded, tuple},
+    Err::Error,
     IResult,
 };
+use std::convert::TryInto;
 
 pub fn parse_constant<'a>(
     source: &'a str,
@@ -25,10 +35,25 @@ pub fn parse_constant<'a>(
     if let Ok((source, _)) = preceded(spaces, tag("undef"))(source) {
         return Ok((source, ConstantData::Undef));
     }
+    if let Ok((source, _)) = preceded(spaces, tag("poison"))(source) {
+        return Ok((source, ConstantData::Poison));
+    }
     if let Ok((source, _)) = preceded(spaces, tag("null"))(source) {
         return Ok((source, ConstantData::Null));
     }
-    if let Ok((source, id)) = parse_constant_int(source, types, ty) {
+    if let Ok((source, _)) = preceded(spaces, tag("zeroinitializer"))(source) {
+        return Ok((source, ConstantData::AggregateZero));
+    }
+    if types.is_vector(ty) {
+        if let Ok((source, v)) = parse_constant_vector(source, types, ty) {
+            return Ok((source, v));
+        }
+    }
+    if types.is_float(ty) {
+        if let Ok((source, f)) = parse_constant_float(source, types, ty) {
+            return Ok((source, f.into()));
+        }
+    } else if let Ok((source, id)) = parse_constant_int(source, types, ty) {
         return Ok((source, id.into()));
     }
     if let Ok((source, id)) = parse_constant_array(source, types) {
@@ -37,6 +62,9 @@ pub fn parse_constant<'a>(
     if let Ok((source, id)) = parse_constant_global_ref(source) {
         return Ok((source, id));
     }
+    if let Ok((source, id)) = parse_constant_block_address(source) {
+        return Ok((source, id));
+    }
     if let Ok((source, id)) = parse_constant_struct(source, types) {
         return Ok((source, id));
     }
@@ -55,59 +83,190 @@ pub fn parse_constant_int<'a>(
             alt((digit1, tag("true"), tag("false"))),
         ))),
     )(source)?;
-    let val = match &*types.get(ty) {
-        Type::Int(1) => ConstantInt::Int1(num == "true"),
-        Type::Int(8) => ConstantInt::Int8(num.parse::<i8>().unwrap()),
-        Type::Int(32) => ConstantInt::Int32(num.parse::<i32>().unwrap()),
-        Type::Int(64) => ConstantInt::Int64(num.parse::<i64>().unwrap()),
-        _ => todo!(),
+    let bits = match &*types.get(ty) {
+        Type::Int(bits) => *bits,
+        _ => return Err(Error(VerboseError { errors: vec![] })),
+    };
+    let val = match num {
+        "true" => ConstantInt::from(true),
+        "false" => ConstantInt::from(false),
+        num => ConstantInt::from_str_decimal(bits, num)
+            .ok_or(Error(VerboseError { errors: vec![] }))?,
     };
     Ok((source, val))
 }
 
+pub fn parse_constant_float<'a>(
+    source: &'a str,
+    types: &Types,
+    ty: TypeId,
+) -> IResult<&'a str, ConstantFloat, VerboseError<&'a str>> {
+    let kind = match &*types.get(ty) {
+        Type::Half => ConstantFloat::Half(0),
+        Type::Float => ConstantFloat::Float(0.0),
+        Type::Double => ConstantFloat::Double(0.0),
+        Type::X86Fp80 => ConstantFloat::X86Fp80(0),
+        Type::Fp128 => ConstantFloat::Fp128(0),
+        _ => return Err(Error(VerboseError { errors: vec![] })),
+    };
+
+    // 0xK (x86_fp80), 0xL (fp128) and 0xH (half) are followed by the raw bits
+    if let Ok((source, (prefix, digits))) = preceded(
+        spaces,
+        preceded(tag("0x"), tuple((one_of("KLH"), hex_digit1))),
+    )(source)
+    {
+        let val = match (prefix, kind) {
+            ('K', ConstantFloat::X86Fp80(_)) if digits.len() == 20 => ConstantFloat::X86Fp80(
+                u128::from_str_radix(&digits[..4], 16).unwrap() << 64
+                    | u128::from_str_radix(&digits[4..], 16).unwrap(),
+            ),
+            ('L', ConstantFloat::Fp128(_)) if digits.len() == 32 => ConstantFloat::Fp128(
+                u128::from_str_radix(&digits[16..], 16).unwrap() << 64
+                    | u128::from_str_radix(&digits[..16], 16).unwrap(),
+            ),
+            ('H', ConstantFloat::Half(_)) if digits.len() == 4 => {
+                ConstantFloat::Half(u16::from_str_radix(digits, 16).unwrap())
+            }
+            _ => return Err(Error(VerboseError { errors: vec![] })),
+        };
+        return Ok((source, val));
+    }
+
+    // Otherwise, hexadecimal literals are IEEE double bit patterns
+    if let Ok((source, digits)) = preceded(spaces, preceded(tag("0x"), hex_digit1))(source) {
+        let bits =
+            u64::from_str_radix(digits, 16).map_err(|_| Error(VerboseError { errors: vec![] }))?;
+        let val = match kind {
+            // Keep NaN payloads as they are
+            ConstantFloat::Double(_) => ConstantFloat::Double(f64::from_bits(bits)),
+            kind => kind.with_f64(f64::from_bits(bits)),
+        };
+        return Ok((source, val));
+    }
+
+    let (source, num) = preceded(
+        spaces,
+        recognize(tuple((
+            opt(one_of("+-")),
+            digit1,
+            char('.'),
+            digit0,
+            opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+        ))),
+    )(source)?;
+    Ok((source, kind.with_f64(num.parse::<f64>().unwrap())))
+}
+
 pub fn parse_constant_array<'a>(
     source: &'a str,
     types: &Types,
-    // ty: TypeId,
-) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
-    // TODO: Support arrays in the form of [a, b, c]
-    let (source, _) = preceded(spaces, char('c'))(source)?;
-    let (source, s) = preceded(spaces, string_literal)(source)?;
-    let val = ConstantData::Array(ConstantArray {
-        elem_ty: types.base().i8(),
-        elems: s
-            .as_bytes()
-            .iter()
-            .map(|c| ConstantData::Int(ConstantInt::Int8(*c as i8)))
-            .collect(),
-        is_string: true,
-    });
-    Ok((source, val))
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    if let Ok((source, s)) = preceded(
+        spaces,
+        preceded(char('c'), preceded(spaces, string_literal)),
+    )(source)
+    {
+        let val = ConstantData::Array(ConstantArray {
+            elem_ty: types.base().i8(),
+            elems: s
+                .as_bytes()
+                .iter()
+                .map(|c| ConstantData::Int(ConstantInt::from(*c as i8)))
+                .collect(),
+            is_string: true,
+        });
+        return Ok((source, val));
+    }
+
+    let (mut source, _) = preceded(spaces, char('['))(source)?;
+    let mut elem_ty = None;
+    let mut elems = vec![];
+    loop {
+        let (source_, ty) = types::parse(source, types)?;
+        let (source_, elem) = parse_constant(source_, types, ty)?;
+        elem_ty = elem_ty.or(Some(ty));
+        elems.push(elem);
+        if let Ok((source_, _)) = preceded(spaces, char(','))(source_) {
+            source = source_;
+            continue;
+        }
+        let (source_, _) = preceded(spaces, char(']'))(source_)?;
+        return Ok((
+            source_,
+            ConstantData::Array(ConstantArray {
+                elem_ty: elem_ty.unwrap(),
+                elems,
+                is_string: false,
+            }),
+        ));
+    }
+}
 
-    // let (mut source, _) = preceded(spaces, char('['))(source)?;
-    // loop {
-    //     let (source_, ty) = types::parse(source, ctx.types)?;
-    //
-    // }
+pub fn parse_constant_vector<'a>(
+    source: &'a str,
+    types: &Types,
+    ty: TypeId,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let elem_ty = types.get_element(ty).unwrap();
 
-    // let (source, num) = preceded(spaces, digit1)(source)?;
-    // let val = match &*ctx.types.get(ty) {
-    //     Type::Int(32) => Value::Constant(ConstantData::Int(ConstantInt::Int32(
-    //         num.parse::<i32>().unwrap(),
-    //     ))),
-    //     _ => todo!(),
-    // };
-    // Ok((source, ctx.data.create_value(val)))
+    if let Ok((source, _)) = preceded(spaces, tag("splat"))(source) {
+        let (source, _) = preceded(spaces, char('('))(source)?;
+        let (source, _) = types::parse(source, types)?;
+        let (source, elem) = parse_constant(source, types, elem_ty)?;
+        let (source, _) = preceded(spaces, char(')'))(source)?;
+        // Only scalable vectors are kept as splats, since older LLVM can't read `splat`.
+        let vector = match &*types.get(ty) {
+            Type::Vector(v) if !v.is_scalable => {
+                ConstantVector::new(elem_ty, vec![elem; v.num_elements as usize])
+            }
+            _ => ConstantVector::splat(elem_ty, elem),
+        };
+        return Ok((source, ConstantData::Vector(vector)));
+    }
+
+    let (mut source, _) = preceded(spaces, char('<'))(source)?;
+    let mut elems = vec![];
+    loop {
+        let (source_, _) = types::parse(source, types)?;
+        let (source_, elem) = parse_constant(source_, types, elem_ty)?;
+        elems.push(elem);
+        if let Ok((source_, _)) = preceded(spaces, char(','))(source_) {
+            source = source_;
+            continue;
+        }
+        let (source_, _) = preceded(spaces, char('>'))(source_)?;
+        return Ok((
+            source_,
+            ConstantData::Vector(ConstantVector::new(elem_ty, elems)),
+        ));
+    }
 }
 
 pub fn parse_constant_expr<'a>(
     source: &'a str,
     types: &Types,
 ) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
-    if let Ok((source, konst)) = parse_constant_getelementptr(source, types) {
-        return Ok((source, konst));
+    for f in [
+        parse_constant_getelementptr,
+        parse_constant_cast,
+        parse_constant_int_binary,
+        parse_constant_float_binary,
+        parse_constant_fneg,
+        parse_constant_icmp,
+        parse_constant_fcmp,
+        parse_constant_select,
+        parse_constant_extractelement,
+        parse_constant_insertelement,
+        parse_constant_shufflevector,
+        parse_constant_extractvalue,
+        parse_constant_insertvalue,
+    ] {
+        if let Ok((source, konst)) = f(source, types) {
+            return Ok((source, konst));
+        }
     }
-    parse_constant_bitcast(source, types)
+    Err(Error(VerboseError { errors: vec![] }))
 }
 
 pub fn parse_constant_getelementptr<'a>(
@@ -143,26 +302,265 @@ pub fn parse_constant_getelementptr<'a>(
     }
 }
 
-pub fn parse_constant_bitcast<'a>(
+pub fn parse_constant_cast<'a>(
     source: &'a str,
     types: &Types,
 ) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
-    let (source, _) = preceded(spaces, tag("bitcast"))(source)?;
+    let (source, opcode) = preceded(spaces, parse_cast_opcode)(source)?;
     let (source, _) = preceded(spaces, char('('))(source)?;
-    let (source, from) = types::parse(source, types)?;
-    let (source, arg) = parse_constant(source, types, from)?;
+    let (source, (from, arg)) = parse_typed_constant(source, types)?;
     let (source, _) = preceded(spaces, tag("to"))(source)?;
     let (source, to) = types::parse(source, types)?;
     let (source, _) = preceded(spaces, char(')'))(source)?;
     Ok((
         source,
-        ConstantData::Expr(ConstantExpr::Bitcast {
+        ConstantData::Expr(ConstantExpr::Cast {
+            opcode,
             tys: [from, to],
             arg: Box::new(arg),
         }),
     ))
 }
 
+pub fn parse_constant_int_binary<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, opcode) = preceded(spaces, parse_int_binary_opcode)(source)?;
+    let (source, (nuw, nsw, exact)) = parse_int_binary_flags(source, opcode)?;
+    let (source, (ty, [lhs, rhs])) = parse_constant_operands(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::IntBinary {
+            opcode,
+            ty: ty[0],
+            nuw,
+            nsw,
+            exact,
+            args: Box::new([lhs, rhs]),
+        }),
+    ))
+}
+
+pub fn parse_constant_float_binary<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, opcode) = preceded(spaces, parse_float_binary_opcode)(source)?;
+    let (source, (ty, [lhs, rhs])) = parse_constant_operands(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::FloatBinary {
+            opcode,
+            ty: ty[0],
+            args: Box::new([lhs, rhs]),
+        }),
+    ))
+}
+
+pub fn parse_constant_fneg<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("fneg"))(source)?;
+    let (source, (ty, [arg])) = parse_constant_operands(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::FNeg {
+            ty: ty[0],
+            arg: Box::new(arg),
+        }),
+    ))
+}
+
+pub fn parse_constant_icmp<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("icmp"))(source)?;
+    let (source, cond) = preceded(spaces, parse_icmp_cond)(source)?;
+    let (source, (ty, [lhs, rhs])) = parse_constant_operands(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::ICmp {
+            cond,
+            ty: ty[0],
+            args: Box::new([lhs, rhs]),
+        }),
+    ))
+}
+
+pub fn parse_constant_fcmp<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("fcmp"))(source)?;
+    let (source, cond) = preceded(spaces, parse_fcmp_cond)(source)?;
+    let (source, (ty, [lhs, rhs])) = parse_constant_operands(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::FCmp {
+            cond,
+            ty: ty[0],
+            args: Box::new([lhs, rhs]),
+        }),
+    ))
+}
+
+pub fn parse_constant_select<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("select"))(source)?;
+    let (source, (tys, args)) = parse_constant_operands::<3>(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::Select {
+            tys: [tys[0], tys[1]],
+            args: Box::new(args),
+        }),
+    ))
+}
+
+pub fn parse_constant_extractelement<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("extractelement"))(source)?;
+    let (source, (tys, args)) = parse_constant_operands(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::ExtractElement {
+            tys,
+            args: Box::new(args),
+        }),
+    ))
+}
+
+pub fn parse_constant_insertelement<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("insertelement"))(source)?;
+    let (source, (tys, args)) = parse_constant_operands(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::InsertElement {
+            tys,
+            args: Box::new(args),
+        }),
+    ))
+}
+
+pub fn parse_constant_shufflevector<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("shufflevector"))(source)?;
+    let (source, (tys, args)) = parse_constant_operands::<3>(source, types)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::ShuffleVector {
+            tys: [tys[0], tys[2]],
+            args: Box::new(args),
+        }),
+    ))
+}
+
+pub fn parse_constant_extractvalue<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("extractvalue"))(source)?;
+    let (source, _) = preceded(spaces, char('('))(source)?;
+    let (source, (ty, arg)) = parse_typed_constant(source, types)?;
+    let (source, indices) = parse_constant_indices(source)?;
+    let (source, _) = preceded(spaces, char(')'))(source)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::ExtractValue {
+            ty,
+            arg: Box::new(arg),
+            indices,
+        }),
+    ))
+}
+
+pub fn parse_constant_insertvalue<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ConstantData, VerboseError<&'a str>> {
+    let (source, _) = preceded(spaces, tag("insertvalue"))(source)?;
+    let (source, _) = preceded(spaces, char('('))(source)?;
+    let (source, (aggre_ty, aggre)) = parse_typed_constant(source, types)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, (elem_ty, elem)) = parse_typed_constant(source, types)?;
+    let (source, indices) = parse_constant_indices(source)?;
+    let (source, _) = preceded(spaces, char(')'))(source)?;
+    Ok((
+        source,
+        ConstantData::Expr(ConstantExpr::InsertValue {
+            tys: [aggre_ty, elem_ty],
+            args: Box::new([aggre, elem]),
+            indices,
+        }),
+    ))
+}
+
+/// Parses `<ty> <constant>`.
+fn parse_typed_constant<'a>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, (TypeId, ConstantData), VerboseError<&'a str>> {
+    let (source, ty) = types::parse(source, types)?;
+    let (source, konst) = parse_constant(source, types, ty)?;
+    Ok((source, (ty, konst)))
+}
+
+/// Parses a parenthesized list of `N` typed constants such as `(i32 1, i32 2)`.
+fn parse_constant_operands<'a, const N: usize>(
+    source: &'a str,
+    types: &Types,
+) -> IResult<&'a str, ([TypeId; N], [ConstantData; N]), VerboseError<&'a str>> {
+    let (mut source, _) = preceded(spaces, char('('))(source)?;
+    let mut tys = [types.base().void(); N];
+    let mut args = vec![];
+    for (i, ty) in tys.iter_mut().enumerate() {
+        if i > 0 {
+            source = preceded(spaces, char(','))(source)?.0;
+        }
+        let (source_, (ty_, arg)) = parse_typed_constant(source, types)?;
+        *ty = ty_;
+        args.push(arg);
+        source = source_;
+    }
+    let (source, _) = preceded(spaces, char(')'))(source)?;
+    Ok((source, (tys, args.try_into().unwrap())))
+}
+
+/// Parses the indices of `extractvalue` and `insertvalue`, each preceded by a comma.
+fn parse_constant_indices(source: &str) -> IResult<&str, Vec<u32>, VerboseError<&str>> {
+    many1(map_res(
+        preceded(spaces, preceded(char(','), preceded(spaces, digit1))),
+        |idx: &str| idx.parse::<u32>(),
+    ))(source)
+}
+
+pub fn parse_constant_block_address(
+    source: &str,
+) -> IResult<&str, ConstantData, VerboseError<&str>> {
+    let (source, _) = preceded(spaces, tag("blockaddress"))(source)?;
+    let (source, _) = preceded(spaces, char('('))(source)?;
+    let (source, func) = preceded(spaces, preceded(char('@'), name::parse))(source)?;
+    let (source, _) = preceded(spaces, char(','))(source)?;
+    let (source, block) = preceded(spaces, preceded(char('%'), name::parse))(source)?;
+    let (source, _) = preceded(spaces, char(')'))(source)?;
+    Ok((
+        source,
+        ConstantData::BlockAddress(BlockAddress { func, block }),
+    ))
+}
+
 pub fn parse_constant_global_ref(source: &str) -> IResult<&str, ConstantData, VerboseError<&str>> {
     let (source, name) = preceded(spaces, preceded(char('@'), name::parse))(source)?;
     Ok((source, ConstantData::GlobalRef(name)))
@@ -211,10 +609,100 @@ pub fn parse<'a, 'b>(
     ctx: &mut ParserContext<'b>,
     ty: TypeId,
 ) -> IResult<&'a str, ValueId, VerboseError<&'a str>> {
+    if matches!(&*ctx.types.get(ty), Type::Metadata) {
+        return parse_metadata(source, ctx);
+    }
+
     if let Ok((source, konst)) = parse_constant(source, ctx.types, ty) {
         let id = ctx.data.create_value(Value::Constant(konst));
         return Ok((source, id));
     }
 
-    parse_local(source, ctx, ty)
+    expected(source, "value", parse_local(source, ctx, ty))
+}
+
+// `!12`, `!DIExpression()` or a wrapped value like `i32* %1`
+fn parse_metadata<'a, 'b>(
+    source: &'a str,
+    ctx: &mut ParserContext<'b>,
+) -> IResult<&'a str, ValueId, VerboseError<&'a str>> {
+    if let Ok((source, meta)) = metadata::parse_operand(ctx.types)(source) {
+        let id = ctx.data.create_value(Value::Metadata(meta));
+        return Ok((source, id));
+    }
+
+    let (source, ty) = types::parse(source, ctx.types)?;
+    let (source, val) = parse(source, ctx, ty)?;
+    let val = ctx.data.value_ref(val).clone();
+    let id = ctx
+        .data
+        .create_value(Value::Metadata(Metadata::Value(ty, Box::new(val))));
+    Ok((source, id))
+}
+
+#[test]
+fn test_parse_constant_float() {
+    let types = Types::new();
+    for (ty, source, expected) in [
+        (types.base().double(), "1.000000e+00", "1.000000e+00"),
+        (types.base().double(), "-2.5", "-2.500000e+00"),
+        (types.base().double(), "0.1", "1.000000e-01"),
+        (types.base().double(), "0x3FB999999999999A", "1.000000e-01"),
+        (
+            types.base().double(),
+            "0x7FF8000000000000",
+            "0x7FF8000000000000",
+        ),
+        (
+            types.base().double(),
+            "0x3FD5555555555555",
+            "0x3FD5555555555555",
+        ),
+        (
+            types.base().float(),
+            "0x3FB99999A0000000",
+            "0x3FB99999A0000000",
+        ),
+        (types.base().float(), "3.000000e+00", "3.000000e+00"),
+        (types.base().half(), "0xH3C00", "0xH3C00"),
+        (types.base().half(), "1.5", "0xH3E00"),
+        (
+            types.base().x86_fp80(),
+            "0xK3FFF8000000000000000",
+            "0xK3FFF8000000000000000",
+        ),
+        (types.base().x86_fp80(), "1.0", "0xK3FFF8000000000000000"),
+        (
+            types.base().fp128(),
+            "0xL00000000000000003FFF000000000000",
+            "0xL00000000000000003FFF000000000000",
+        ),
+        (
+            types.base().fp128(),
+            "1.0",
+            "0xL00000000000000003FFF000000000000",
+        ),
+    ] {
+        let (rest, konst) = parse_constant(source, &types, ty).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(konst.to_string(&types), expected);
+        let (_, konst2) = parse_constant(expected, &types, ty).unwrap();
+        assert_eq!(konst, konst2);
+    }
+}
+
+#[test]
+fn test_parse_constant_vector_splat() {
+    let types = Types::new();
+    let i32 = types.base().i32();
+    let fixed = types.base_mut().vector(i32, 3, false);
+    let scalable = types.base_mut().vector(i32, 4, true);
+    for (ty, source, expected) in [
+        (fixed, "splat (i32 7)", "<i32 7, i32 7, i32 7>"),
+        (scalable, "splat (i32 7)", "splat (i32 7)"),
+    ] {
+        let (rest, konst) = parse_constant(source, &types, ty).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(konst.to_string(&types), expected);
+    }
 }
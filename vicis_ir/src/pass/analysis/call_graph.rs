@@ -0,0 +1,259 @@
+use crate::{
+    ir::{
+        function::{
+            instruction::{Call, InstructionId, Invoke, Operand},
+            FunctionId,
+        },
+        module::{linkage::Linkage, name::Name, Module},
+        value::{ConstantArray, ConstantData, ConstantStruct, ConstantVector, Value},
+    },
+    pass::{Analyses, AnalysisPass},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Which functions of a module call which. Calls whose callee isn't known, such as calls
+/// through pointers, go to the `External` node. So do calls made by functions only declared
+/// in the module, since they may call anything. In turn, `External` calls every function
+/// that is visible outside the module or whose address is taken.
+#[derive(Debug)]
+pub struct CallGraph {
+    calls: FxHashMap<CallGraphNode, Vec<CallSite>>,
+    callers: FxHashMap<CallGraphNode, Vec<(CallGraphNode, Option<InstructionId>)>>,
+    /// Strongly connected components, callees before their callers
+    sccs: Vec<Vec<FunctionId>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CallGraphNode {
+    Function(FunctionId),
+    External,
+}
+
+/// A call in a function. `inst` is the `call` or `invoke` instruction, or `None` for the
+/// call to `External` from a function that's only declared and for the calls `External`
+/// makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallSite {
+    pub inst: Option<InstructionId>,
+    pub callee: CallGraphNode,
+}
+
+/// Computes the `CallGraph` of a `Module`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CallGraphAnalysis;
+
+impl AnalysisPass<Module> for CallGraphAnalysis {
+    type Result = CallGraph;
+
+    fn run_on(&self, module: &Module, _: &mut Analyses<Module>) -> Self::Result {
+        CallGraph::new(module)
+    }
+}
+
+impl CallGraph {
+    pub fn new(module: &Module) -> Self {
+        let ids: FxHashMap<&str, FunctionId> = module
+            .functions()
+            .iter()
+            .map(|(id, func)| (func.name().as_str(), id))
+            .collect();
+        let mut graph = Self {
+            calls: FxHashMap::default(),
+            callers: FxHashMap::default(),
+            sccs: vec![],
+        };
+
+        let mut address_taken = FxHashSet::default();
+        for gv in module.global_variables().values() {
+            if let Some(init) = &gv.init {
+                global_refs(init, &mut address_taken);
+            }
+        }
+
+        for (caller, func) in module.functions() {
+            if let Some((_, personality)) = &func.personality {
+                global_refs(personality, &mut address_taken);
+            }
+            let mut calls = vec![];
+            if func.is_prototype() {
+                calls.push(CallSite {
+                    inst: None,
+                    callee: CallGraphNode::External,
+                });
+            }
+            for block in func.layout.block_iter() {
+                for inst in func.layout.inst_iter(block) {
+                    let operand = &func.data.inst_ref(inst).operand;
+                    let is_call = matches!(operand, Operand::Call(_) | Operand::Invoke(_));
+                    for (i, &arg) in operand.args().iter().enumerate() {
+                        match func.data.value_ref(arg) {
+                            // A direct callee doesn't take the address
+                            Value::Constant(ConstantData::GlobalRef(_)) if is_call && i == 0 => {}
+                            Value::Constant(konst) => global_refs(konst, &mut address_taken),
+                            _ => {}
+                        }
+                    }
+                    let callee = match operand {
+                        Operand::Call(Call { args, .. }) | Operand::Invoke(Invoke { args, .. }) => {
+                            args[0]
+                        }
+                        _ => continue,
+                    };
+                    let callee = match func.data.value_ref(callee) {
+                        Value::Constant(ConstantData::GlobalRef(Name::Name(name))) => ids
+                            .get(name.as_str())
+                            .map_or(CallGraphNode::External, |&id| CallGraphNode::Function(id)),
+                        _ => CallGraphNode::External,
+                    };
+                    calls.push(CallSite {
+                        inst: Some(inst),
+                        callee,
+                    });
+                }
+            }
+            for call in &calls {
+                graph
+                    .callers
+                    .entry(call.callee)
+                    .or_default()
+                    .push((CallGraphNode::Function(caller), call.inst));
+            }
+            graph.calls.insert(CallGraphNode::Function(caller), calls);
+        }
+
+        let mut external = vec![];
+        for (id, func) in module.functions() {
+            let is_local = matches!(func.linkage, Linkage::Internal | Linkage::Private);
+            if is_local && !address_taken.contains(func.name().as_str()) {
+                continue;
+            }
+            let callee = CallGraphNode::Function(id);
+            external.push(CallSite { inst: None, callee });
+            graph
+                .callers
+                .entry(callee)
+                .or_default()
+                .push((CallGraphNode::External, None));
+        }
+        graph.calls.insert(CallGraphNode::External, external);
+
+        graph.sccs = Tarjan::new(&graph).run(module);
+        graph
+    }
+
+    /// Returns the calls `node` makes, in layout order.
+    pub fn calls_of(&self, node: CallGraphNode) -> &[CallSite] {
+        self.calls.get(&node).map_or(&[], |calls| calls.as_slice())
+    }
+
+    /// Returns the nodes that call `node`, with the instructions that do.
+    pub fn callers_of(&self, node: CallGraphNode) -> &[(CallGraphNode, Option<InstructionId>)] {
+        self.callers
+            .get(&node)
+            .map_or(&[], |callers| callers.as_slice())
+    }
+
+    /// Returns the strongly connected components of the graph bottom-up: every function comes
+    /// after the components of the functions it calls, unless they call it back.
+    pub fn sccs(&self) -> &[Vec<FunctionId>] {
+        &self.sccs
+    }
+
+    /// Returns whether `func` can end up calling itself through known callees.
+    pub fn is_recursive(&self, func: FunctionId) -> bool {
+        let scc = match self.sccs.iter().find(|scc| scc.contains(&func)) {
+            Some(scc) => scc,
+            None => return false,
+        };
+        scc.len() > 1
+            || self
+                .calls_of(CallGraphNode::Function(func))
+                .iter()
+                .any(|call| call.callee == CallGraphNode::Function(func))
+    }
+}
+
+/// Adds the names of the globals `konst` refers to to `refs`.
+fn global_refs<'a>(konst: &'a ConstantData, refs: &mut FxHashSet<&'a str>) {
+    match konst {
+        ConstantData::GlobalRef(Name::Name(name)) => {
+            refs.insert(name.as_str());
+        }
+        ConstantData::Array(ConstantArray { elems, .. })
+        | ConstantData::Vector(ConstantVector { elems, .. })
+        | ConstantData::Struct(ConstantStruct { elems, .. }) => {
+            elems.iter().for_each(|elem| global_refs(elem, refs))
+        }
+        ConstantData::Expr(expr) => expr.args().for_each(|arg| global_refs(arg, refs)),
+        _ => {}
+    }
+}
+
+/// Tarjan's algorithm for strongly connected components, which finds them callees first.
+struct Tarjan<'a> {
+    graph: &'a CallGraph,
+    index: FxHashMap<FunctionId, usize>,
+    lowlink: FxHashMap<FunctionId, usize>,
+    stack: Vec<FunctionId>,
+    on_stack: FxHashSet<FunctionId>,
+    sccs: Vec<Vec<FunctionId>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn new(graph: &'a CallGraph) -> Self {
+        Self {
+            graph,
+            index: FxHashMap::default(),
+            lowlink: FxHashMap::default(),
+            stack: vec![],
+            on_stack: FxHashSet::default(),
+            sccs: vec![],
+        }
+    }
+
+    fn run(mut self, module: &Module) -> Vec<Vec<FunctionId>> {
+        for (id, _) in module.functions() {
+            if !self.index.contains_key(&id) {
+                self.visit(id)
+            }
+        }
+        self.sccs
+    }
+
+    fn visit(&mut self, func: FunctionId) {
+        let index = self.index.len();
+        self.index.insert(func, index);
+        self.lowlink.insert(func, index);
+        self.stack.push(func);
+        self.on_stack.insert(func);
+
+        for call in self.graph.calls_of(CallGraphNode::Function(func)) {
+            let callee = match call.callee {
+                CallGraphNode::Function(callee) => callee,
+                CallGraphNode::External => continue,
+            };
+            if !self.index.contains_key(&callee) {
+                self.visit(callee);
+                let low = self.lowlink[&func].min(self.lowlink[&callee]);
+                self.lowlink.insert(func, low);
+            } else if self.on_stack.contains(&callee) {
+                let low = self.lowlink[&func].min(self.index[&callee]);
+                self.lowlink.insert(func, low);
+            }
+        }
+
+        if self.lowlink[&func] == self.index[&func] {
+            let mut scc = vec![];
+            loop {
+                let f = self.stack.pop().unwrap();
+                self.on_stack.remove(&f);
+                scc.push(f);
+                if f == func {
+                    break;
+                }
+            }
+            scc.reverse();
+            self.sccs.push(scc);
+        }
+    }
+}
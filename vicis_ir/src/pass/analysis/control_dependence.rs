@@ -0,0 +1,99 @@
+use crate::{
+    ir::function::{basic_block::BasicBlock as IrBasicBlock, Function},
+    pass::{
+        analysis::post_dom_tree::{PostDominatorTree, PostDominatorTreeAnalysis},
+        Analyses, AnalysisPass,
+    },
+    traits::basic_block::{BasicBlock, BasicBlockData, BasicBlockLayout},
+};
+use id_arena::Id;
+use rustc_hash::FxHashMap;
+
+/// The control-dependence graph of a function. A block `y` is control dependent on a block
+/// `x` if `x` decides whether `y` runs: `x` has a successor that `y` post-dominates, and `y`
+/// doesn't post-dominate `x` itself.
+#[derive(Debug)]
+pub struct ControlDependenceGraph<BB: BasicBlock> {
+    /// The blocks each block is control dependent on
+    deps: FxHashMap<Id<BB>, Vec<Id<BB>>>,
+    /// The blocks control dependent on each block
+    dependents: FxHashMap<Id<BB>, Vec<Id<BB>>>,
+}
+
+/// Computes the `ControlDependenceGraph` of a `Function`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ControlDependenceAnalysis;
+
+impl AnalysisPass<Function> for ControlDependenceAnalysis {
+    type Result = ControlDependenceGraph<IrBasicBlock>;
+
+    fn run_on(&self, func: &Function, analyses: &mut Analyses<Function>) -> Self::Result {
+        ControlDependenceGraph::new(func, &analyses.get::<PostDominatorTreeAnalysis>(func))
+    }
+}
+
+impl<BB: BasicBlock> ControlDependenceGraph<BB> {
+    pub fn new<F: BasicBlockData<BB> + BasicBlockLayout<BB>>(
+        f: &F,
+        post_dom_tree: &PostDominatorTree<BB>,
+    ) -> Self {
+        let order: Vec<Id<BB>> = f.order().collect();
+        let index: FxHashMap<Id<BB>, usize> =
+            order.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+        let mut graph = Self {
+            deps: FxHashMap::default(),
+            dependents: FxHashMap::default(),
+        };
+
+        // For every edge x -> s, the blocks from s up to (not including) the immediate
+        // post-dominator of x in the post-dominator tree are control dependent on x. A block
+        // with one successor decides nothing, even one the virtual exit follows.
+        for &x in &order {
+            if f.get(x).succs().len() < 2 {
+                continue;
+            }
+            let end = post_dom_tree.ipdom_of(x);
+            let mut succs: Vec<Id<BB>> = f
+                .get(x)
+                .succs()
+                .iter()
+                .copied()
+                .filter(|s| index.contains_key(s))
+                .collect();
+            succs.sort_by_key(|s| index[s]);
+            for succ in succs {
+                let mut cur = Some(succ);
+                while let Some(y) = cur {
+                    if Some(y) == end {
+                        break;
+                    }
+                    let deps = graph.deps.entry(y).or_default();
+                    if !deps.contains(&x) {
+                        deps.push(x);
+                        graph.dependents.entry(x).or_default().push(y);
+                    }
+                    cur = post_dom_tree.ipdom_of(y);
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Returns the blocks whose terminators decide whether `block` runs.
+    pub fn dependences_of(&self, block: Id<BB>) -> &[Id<BB>] {
+        self.deps.get(&block).map_or(&[], |deps| deps.as_slice())
+    }
+
+    /// Returns the blocks that `block`'s terminator decides whether to run.
+    pub fn dependents_of(&self, block: Id<BB>) -> &[Id<BB>] {
+        self.dependents
+            .get(&block)
+            .map_or(&[], |dependents| dependents.as_slice())
+    }
+
+    /// Returns whether `y` is control dependent on `x`.
+    pub fn is_dependent(&self, y: Id<BB>, x: Id<BB>) -> bool {
+        self.dependences_of(y).contains(&x)
+    }
+}
@@ -1,4 +1,8 @@
-use crate::traits::basic_block::{BasicBlock, BasicBlockData, BasicBlockLayout};
+use crate::{
+    ir::function::{basic_block::BasicBlock as IrBasicBlock, Function},
+    pass::{Analyses, AnalysisPass},
+    traits::basic_block::{BasicBlock, BasicBlockData, BasicBlockLayout},
+};
 use id_arena::Id;
 use rustc_hash::{FxHashMap, FxHashSet};
 
@@ -10,6 +14,10 @@ pub struct DominatorTree<BB: BasicBlock> {
     root: Id<BB>,
 }
 
+/// Computes the `DominatorTree` of a `Function`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DominatorTreeAnalysis;
+
 type DomTree<BB> = FxHashMap<Id<BB>, FxHashSet<Id<BB>>>;
 
 type Map<T> = FxHashMap<T, T>;
@@ -26,6 +34,14 @@ struct Context<'a, BB: BasicBlock, F: BasicBlockData<BB> + BasicBlockLayout<BB>>
     best: Map<Id<BB>>,
 }
 
+impl AnalysisPass<Function> for DominatorTreeAnalysis {
+    type Result = DominatorTree<IrBasicBlock>;
+
+    fn run_on(&self, func: &Function, _: &mut Analyses<Function>) -> Self::Result {
+        DominatorTree::new(func)
+    }
+}
+
 impl<BB: BasicBlock> DominatorTree<BB> {
     pub fn new<F: BasicBlockData<BB> + BasicBlockLayout<BB>>(f: &F) -> Self {
         let mut dom = FxHashMap::default();
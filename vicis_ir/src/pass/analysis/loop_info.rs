@@ -0,0 +1,275 @@
+use crate::{
+    ir::function::{basic_block::BasicBlock as IrBasicBlock, Function},
+    pass::{
+        analysis::dom_tree::{DominatorTree, DominatorTreeAnalysis},
+        Analyses, AnalysisPass,
+    },
+    traits::basic_block::{BasicBlock, BasicBlockData, BasicBlockLayout},
+};
+use id_arena::{Arena, Id};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// The natural loops of a function and how they nest. Loops are found from back edges,
+/// edges to a block that dominates the source. Loops sharing a header are one loop.
+#[derive(Debug)]
+pub struct LoopInfo<BB: BasicBlock> {
+    loops: Arena<Loop<BB>>,
+    top_level: Vec<LoopId<BB>>,
+    /// The innermost loop containing each block
+    innermost: FxHashMap<Id<BB>, LoopId<BB>>,
+}
+
+pub type LoopId<BB> = Id<Loop<BB>>;
+
+#[derive(Debug)]
+pub struct Loop<BB: BasicBlock> {
+    header: Id<BB>,
+    latches: Vec<Id<BB>>,
+    /// All the blocks in the loop, including those of inner loops, in layout order
+    blocks: Vec<Id<BB>>,
+    block_set: FxHashSet<Id<BB>>,
+    exiting: Vec<Id<BB>>,
+    exits: Vec<Id<BB>>,
+    preheader: Option<Id<BB>>,
+    parent: Option<LoopId<BB>>,
+    children: Vec<LoopId<BB>>,
+    depth: usize,
+}
+
+/// Computes the `LoopInfo` of a `Function`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LoopInfoAnalysis;
+
+impl AnalysisPass<Function> for LoopInfoAnalysis {
+    type Result = LoopInfo<IrBasicBlock>;
+
+    fn run_on(&self, func: &Function, analyses: &mut Analyses<Function>) -> Self::Result {
+        LoopInfo::new(func, &analyses.get::<DominatorTreeAnalysis>(func))
+    }
+}
+
+impl<BB: BasicBlock> LoopInfo<BB> {
+    pub fn new<F: BasicBlockData<BB> + BasicBlockLayout<BB>>(
+        f: &F,
+        dom_tree: &DominatorTree<BB>,
+    ) -> Self {
+        let order: Vec<Id<BB>> = f.order().collect();
+        let is_reachable = |block: Id<BB>| dom_tree.level_of(block).is_some();
+
+        // Find the loops in layout order of their headers.
+        let mut loops = vec![];
+        for &header in order.iter().filter(|&&b| is_reachable(b)) {
+            let latches: Vec<Id<BB>> = order
+                .iter()
+                .copied()
+                .filter(|&pred| {
+                    f.get(header).preds().contains(&pred)
+                        && is_reachable(pred)
+                        && dom_tree.dominates(header, pred)
+                })
+                .collect();
+            if latches.is_empty() {
+                continue;
+            }
+
+            // The loop is the header and whatever reaches a latch without going through it.
+            let mut block_set = FxHashSet::default();
+            block_set.insert(header);
+            let mut worklist = latches.clone();
+            while let Some(block) = worklist.pop() {
+                if !block_set.insert(block) {
+                    continue;
+                }
+                worklist.extend(f.get(block).preds().iter().filter(|&&p| is_reachable(p)));
+            }
+            loops.push((header, latches, block_set));
+        }
+
+        let mut arena = Arena::new();
+        let ids: Vec<LoopId<BB>> = loops
+            .into_iter()
+            .map(|(header, latches, block_set)| {
+                arena.alloc(Loop::new(f, &order, header, latches, block_set))
+            })
+            .collect();
+
+        // A loop is nested in the smallest other loop that contains its header.
+        let mut top_level = vec![];
+        for &id in &ids {
+            let header = arena[id].header;
+            let parent = ids
+                .iter()
+                .copied()
+                .filter(|&other| other != id && arena[other].contains(header))
+                .min_by_key(|&other| arena[other].blocks.len());
+            arena[id].parent = parent;
+            match parent {
+                Some(parent) => arena[parent].children.push(id),
+                None => top_level.push(id),
+            }
+        }
+        for &id in &ids {
+            let mut depth = 1;
+            let mut cur = arena[id].parent;
+            while let Some(parent) = cur {
+                depth += 1;
+                cur = arena[parent].parent;
+            }
+            arena[id].depth = depth;
+        }
+
+        let mut innermost = FxHashMap::default();
+        for &id in &ids {
+            for &block in &arena[id].blocks {
+                let inner = innermost.entry(block).or_insert(id);
+                if arena[id].depth > arena[*inner].depth {
+                    *inner = id;
+                }
+            }
+        }
+
+        Self {
+            loops: arena,
+            top_level,
+            innermost,
+        }
+    }
+
+    pub fn get(&self, id: LoopId<BB>) -> &Loop<BB> {
+        &self.loops[id]
+    }
+
+    /// Returns all the loops in layout order of their headers.
+    pub fn loops(&self) -> impl Iterator<Item = LoopId<BB>> + '_ {
+        self.loops.iter().map(|(id, _)| id)
+    }
+
+    /// Returns all the loops, inner loops before the loops containing them.
+    pub fn loops_innermost_first(&self) -> Vec<LoopId<BB>> {
+        fn visit<BB: BasicBlock>(info: &LoopInfo<BB>, id: LoopId<BB>, order: &mut Vec<LoopId<BB>>) {
+            for &child in &info.loops[id].children {
+                visit(info, child, order)
+            }
+            order.push(id)
+        }
+        let mut order = vec![];
+        for &id in &self.top_level {
+            visit(self, id, &mut order)
+        }
+        order
+    }
+
+    pub fn top_level_loops(&self) -> &[LoopId<BB>] {
+        &self.top_level
+    }
+
+    /// Returns the innermost loop that contains `block`.
+    pub fn loop_of(&self, block: Id<BB>) -> Option<LoopId<BB>> {
+        self.innermost.get(&block).copied()
+    }
+
+    /// Returns how many loops contain `block`.
+    pub fn depth_of(&self, block: Id<BB>) -> usize {
+        self.loop_of(block).map_or(0, |id| self.loops[id].depth)
+    }
+
+    pub fn is_loop_header(&self, block: Id<BB>) -> bool {
+        matches!(self.loop_of(block), Some(id) if self.loops[id].header == block)
+    }
+}
+
+impl<BB: BasicBlock> Loop<BB> {
+    fn new<F: BasicBlockData<BB>>(
+        f: &F,
+        order: &[Id<BB>],
+        header: Id<BB>,
+        latches: Vec<Id<BB>>,
+        block_set: FxHashSet<Id<BB>>,
+    ) -> Self {
+        let blocks: Vec<Id<BB>> = order
+            .iter()
+            .copied()
+            .filter(|b| block_set.contains(b))
+            .collect();
+        let exiting = blocks
+            .iter()
+            .copied()
+            .filter(|&b| f.get(b).succs().iter().any(|s| !block_set.contains(s)))
+            .collect();
+        let exits = order
+            .iter()
+            .copied()
+            .filter(|&b| {
+                !block_set.contains(&b) && f.get(b).preds().iter().any(|p| block_set.contains(p))
+            })
+            .collect();
+        // The only block out of the loop that enters it, if it goes nowhere else.
+        let mut outside_preds = f
+            .get(header)
+            .preds()
+            .iter()
+            .copied()
+            .filter(|p| !block_set.contains(p));
+        let preheader = match (outside_preds.next(), outside_preds.next()) {
+            (Some(pred), None) if f.get(pred).succs().len() == 1 => Some(pred),
+            _ => None,
+        };
+        Self {
+            header,
+            latches,
+            blocks,
+            block_set,
+            exiting,
+            exits,
+            preheader,
+            parent: None,
+            children: vec![],
+            depth: 0,
+        }
+    }
+
+    pub fn header(&self) -> Id<BB> {
+        self.header
+    }
+
+    /// Returns the blocks that branch back to the header.
+    pub fn latches(&self) -> &[Id<BB>] {
+        &self.latches
+    }
+
+    /// Returns the blocks in the loop, including those of inner loops, in layout order.
+    pub fn blocks(&self) -> &[Id<BB>] {
+        &self.blocks
+    }
+
+    pub fn contains(&self, block: Id<BB>) -> bool {
+        self.block_set.contains(&block)
+    }
+
+    /// Returns the blocks in the loop that branch out of it.
+    pub fn exiting_blocks(&self) -> &[Id<BB>] {
+        &self.exiting
+    }
+
+    /// Returns the blocks out of the loop that it branches to.
+    pub fn exit_blocks(&self) -> &[Id<BB>] {
+        &self.exits
+    }
+
+    pub fn preheader(&self) -> Option<Id<BB>> {
+        self.preheader
+    }
+
+    pub fn parent(&self) -> Option<LoopId<BB>> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[LoopId<BB>] {
+        &self.children
+    }
+
+    /// Returns how many loops contain this one, counting itself.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
@@ -1 +1,6 @@
+pub mod call_graph;
+pub mod control_dependence;
 pub mod dom_tree;
+pub mod loop_info;
+pub mod post_dom_tree;
+pub mod verifier;
@@ -0,0 +1,189 @@
+use crate::{
+    ir::function::{basic_block::BasicBlock as IrBasicBlock, Function},
+    pass::{Analyses, AnalysisPass},
+    traits::basic_block::{BasicBlock, BasicBlockData, BasicBlockLayout},
+};
+use id_arena::Id;
+use rustc_hash::FxHashMap;
+
+/// The post-dominator tree of a function. Its root is a virtual exit node that every block
+/// without successors branches to, so that a function with several exits has one tree.
+/// Infinite loops never reach an exit, so one block of each such loop is made to branch to
+/// the virtual exit as well. The virtual exit is `None` wherever a node is an `Option`.
+#[derive(Debug)]
+pub struct PostDominatorTree<BB: BasicBlock> {
+    ipdom: FxHashMap<Id<BB>, Option<Id<BB>>>,
+    children: FxHashMap<Option<Id<BB>>, Vec<Id<BB>>>,
+    /// The blocks the virtual exit is a successor of
+    exits: Vec<Id<BB>>,
+}
+
+/// Computes the `PostDominatorTree` of a `Function`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PostDominatorTreeAnalysis;
+
+impl AnalysisPass<Function> for PostDominatorTreeAnalysis {
+    type Result = PostDominatorTree<IrBasicBlock>;
+
+    fn run_on(&self, func: &Function, _: &mut Analyses<Function>) -> Self::Result {
+        PostDominatorTree::new(func)
+    }
+}
+
+impl<BB: BasicBlock> PostDominatorTree<BB> {
+    pub fn new<F: BasicBlockData<BB> + BasicBlockLayout<BB>>(f: &F) -> Self {
+        let order: Vec<Id<BB>> = f.order().collect();
+        let index: FxHashMap<Id<BB>, usize> =
+            order.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+        // Nodes are blocks by their position in the layout, and the virtual exit after them.
+        let exit = order.len();
+        let mut succs: Vec<Vec<usize>> = (0..exit)
+            .map(|node| {
+                f.get(order[node])
+                    .succs()
+                    .iter()
+                    .filter_map(|s| index.get(s).copied())
+                    .collect()
+            })
+            .collect();
+        let preds = |node: usize| -> Vec<usize> {
+            f.get(order[node])
+                .preds()
+                .iter()
+                .filter_map(|p| index.get(p).copied())
+                .collect()
+        };
+        fn visit(
+            node: usize,
+            preds: &dyn Fn(usize) -> Vec<usize>,
+            visited: &mut [bool],
+            postorder: &mut Vec<usize>,
+        ) {
+            if visited[node] {
+                return;
+            }
+            visited[node] = true;
+            for pred in preds(node) {
+                visit(pred, preds, visited, postorder)
+            }
+            postorder.push(node)
+        }
+        /// Follows successors from `node` until one has no successor not seen yet. The block
+        /// it stops at is on a cycle, if every block on the way has a successor.
+        fn last_unseen(node: usize, succs: &[Vec<usize>], seen: &mut [bool]) -> usize {
+            seen[node] = true;
+            match succs[node].iter().find(|&&succ| !seen[succ]) {
+                Some(&succ) => last_unseen(succ, succs, seen),
+                None => node,
+            }
+        }
+
+        // Walk the reversed CFG from the virtual exit. Whatever it doesn't reach can't reach
+        // an exit, so is in or leads to an infinite loop. A block of such a loop is connected
+        // to the virtual exit and the walk goes on from there, until every block is reached.
+        let mut exits: Vec<usize> = (0..exit).filter(|&node| succs[node].is_empty()).collect();
+        let mut postorder = vec![];
+        let mut visited = vec![false; exit + 1];
+        visited[exit] = true;
+        let mut next_exit = 0;
+        loop {
+            for &start in &exits[next_exit..] {
+                visit(start, &preds, &mut visited, &mut postorder)
+            }
+            next_exit = exits.len();
+            match (0..exit).find(|&node| !visited[node]) {
+                Some(node) => exits.push(last_unseen(node, &succs, &mut vec![false; exit])),
+                None => break,
+            }
+        }
+        postorder.push(exit);
+
+        // Find immediate post-dominators the way of "A Simple, Fast Dominance Algorithm" by
+        // Cooper, Harvey and Kennedy, on the reversed CFG.
+        let mut po_num = vec![0; exit + 1];
+        for (i, &node) in postorder.iter().enumerate() {
+            po_num[node] = i;
+        }
+        for &node in &exits {
+            succs[node].push(exit);
+        }
+        let mut ipdom: Vec<Option<usize>> = vec![None; exit + 1];
+        ipdom[exit] = Some(exit);
+        let intersect = |ipdom: &[Option<usize>], mut x: usize, mut y: usize| {
+            while x != y {
+                while po_num[x] < po_num[y] {
+                    x = ipdom[x].unwrap();
+                }
+                while po_num[y] < po_num[x] {
+                    y = ipdom[y].unwrap();
+                }
+            }
+            x
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in postorder.iter().rev().skip(1) {
+                let new_ipdom = succs[node]
+                    .iter()
+                    .copied()
+                    .filter(|&s| ipdom[s].is_some())
+                    .reduce(|x, y| intersect(&ipdom, x, y));
+                if new_ipdom.is_some() && ipdom[node] != new_ipdom {
+                    ipdom[node] = new_ipdom;
+                    changed = true;
+                }
+            }
+        }
+
+        let to_block = |node: usize| {
+            if node == exit {
+                None
+            } else {
+                Some(order[node])
+            }
+        };
+        let mut tree = Self {
+            ipdom: FxHashMap::default(),
+            children: FxHashMap::default(),
+            exits: exits.into_iter().map(|node| order[node]).collect(),
+        };
+        for (node, &block) in order.iter().enumerate() {
+            let parent = to_block(ipdom[node].unwrap());
+            tree.ipdom.insert(block, parent);
+            tree.children.entry(parent).or_default().push(block);
+        }
+        tree
+    }
+
+    /// Returns whether every path from `y` to the exit goes through `x`.
+    pub fn post_dominates(&self, x: Id<BB>, y: Id<BB>) -> bool {
+        let mut cur = Some(y);
+        while let Some(block) = cur {
+            if block == x {
+                return true;
+            }
+            cur = self.ipdom_of(block);
+        }
+        false
+    }
+
+    /// Returns the immediate post-dominator of `x`, or `None` if it's the virtual exit.
+    pub fn ipdom_of(&self, x: Id<BB>) -> Option<Id<BB>> {
+        self.ipdom.get(&x).copied().flatten()
+    }
+
+    /// Returns the blocks `x` immediately post-dominates, in layout order. `None` stands for
+    /// the virtual exit.
+    pub fn children_of(&self, x: Option<Id<BB>>) -> &[Id<BB>] {
+        self.children
+            .get(&x)
+            .map_or(&[], |children| children.as_slice())
+    }
+
+    /// Returns the blocks connected to the virtual exit: blocks without successors, and then
+    /// a block of each infinite loop.
+    pub fn exits(&self) -> &[Id<BB>] {
+        &self.exits
+    }
+}
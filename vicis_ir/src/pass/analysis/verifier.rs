@@ -0,0 +1,552 @@
+//! Checks that functions are well-formed, so that a bad transform or a bad `Builder` call
+//! is reported where it happens instead of as a panic in a later pass.
+
+use crate::{
+    ir::{
+        function::{
+            basic_block::{BasicBlock, BasicBlockId},
+            data::Use,
+            instruction::{Call, Instruction, InstructionId, Invoke, Opcode, Operand},
+            Function,
+        },
+        module::{name::Name, Module},
+        types::{Type, TypeId},
+        value::{ConstantData, Value, ValueId},
+    },
+    pass::analysis::dom_tree::DominatorTree,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt;
+
+/// A problem found by the verifier. `block` and `inst` locate it in `function` if it's
+/// about a block or an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub function: String,
+    pub block: Option<BasicBlockId>,
+    pub inst: Option<InstructionId>,
+    pub message: String,
+    /// Names of the function, the block and the instruction, e.g. `@main, %entry, %3`
+    location: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Verifies every function defined in `module`.
+pub fn verify_module(module: &Module) -> Vec<Diagnostic> {
+    module
+        .functions()
+        .iter()
+        .flat_map(|(_, func)| verify_function(module, func))
+        .collect()
+}
+
+/// Verifies `func`, which is in `module`. Returns no diagnostics if it's well-formed.
+pub fn verify_function(module: &Module, func: &Function) -> Vec<Diagnostic> {
+    if func.is_prototype() {
+        return vec![];
+    }
+    let mut verifier = Verifier::new(module, func);
+    verifier.verify();
+    verifier.diags
+}
+
+struct Verifier<'a> {
+    module: &'a Module,
+    func: &'a Function,
+    /// The block of each instruction in the layout and its index in the block
+    positions: FxHashMap<InstructionId, (BasicBlockId, usize)>,
+    /// Predecessors as the terminators say, rather than as the blocks record them, with the
+    /// number of edges from each
+    preds: FxHashMap<BasicBlockId, FxHashMap<BasicBlockId, usize>>,
+    diags: Vec<Diagnostic>,
+}
+
+impl<'a> Verifier<'a> {
+    fn new(module: &'a Module, func: &'a Function) -> Self {
+        let mut positions = FxHashMap::default();
+        let mut preds: FxHashMap<_, FxHashMap<_, _>> = func
+            .layout
+            .block_iter()
+            .map(|block| (block, FxHashMap::default()))
+            .collect();
+        for block in func.layout.block_iter() {
+            for (i, id) in func.layout.inst_iter(block).enumerate() {
+                positions.insert(id, (block, i));
+                let inst = func.data.inst_ref(id);
+                if !inst.opcode.is_terminator() {
+                    continue;
+                }
+                for succ in inst.operand.blocks() {
+                    if let Some(preds) = preds.get_mut(succ) {
+                        *preds.entry(block).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        Self {
+            module,
+            func,
+            positions,
+            preds,
+            diags: vec![],
+        }
+    }
+
+    fn verify(&mut self) {
+        let func = self.func;
+        for block in func.layout.block_iter() {
+            self.verify_block(block);
+        }
+        let dom_tree = DominatorTree::<BasicBlock>::new(func);
+        for block in func.layout.block_iter() {
+            for id in func.layout.inst_iter(block) {
+                let inst = func.data.inst_ref(id);
+                self.verify_uses(&dom_tree, block, id, inst);
+                self.verify_types(id, inst);
+                self.verify_call(id, inst);
+            }
+        }
+        self.verify_users_map();
+        self.verify_use_lists();
+    }
+
+    /// Checks that `block` ends in its only terminator, and that its phis come first and
+    /// have an incoming value for each predecessor.
+    fn verify_block(&mut self, block: BasicBlockId) {
+        let func = self.func;
+        let insts: Vec<_> = func.layout.inst_iter(block).collect();
+        match insts.last() {
+            None => self.block_error(block, "block is empty"),
+            Some(last) if !func.data.inst_ref(*last).opcode.is_terminator() => {
+                self.block_error(block, "block does not end in a terminator")
+            }
+            Some(_) => {}
+        }
+
+        let mut seen_non_phi = false;
+        for (i, &id) in insts.iter().enumerate() {
+            let inst = func.data.inst_ref(id);
+            if inst.opcode.is_terminator() && i + 1 != insts.len() {
+                self.inst_error(id, "terminator in the middle of a block");
+            }
+            if inst.opcode.is_terminator() {
+                for succ in inst.operand.blocks() {
+                    if !self.preds.contains_key(succ) {
+                        self.inst_error(id, "branch to a block that is not in the function");
+                    }
+                }
+            }
+            if inst.parent != block {
+                self.inst_error(id, "instruction's parent is not the block it is in");
+            }
+            let phi = match &inst.operand {
+                Operand::Phi(phi) => phi,
+                _ => {
+                    seen_non_phi = true;
+                    continue;
+                }
+            };
+            if seen_non_phi {
+                self.inst_error(id, "phi is not at the start of the block");
+            }
+            if phi.args.len() != phi.blocks.len() {
+                self.inst_error(id, "phi has a different number of values and blocks");
+            }
+            // Like a terminator, a phi has an entry for each edge, even from the same block.
+            let mut incoming: FxHashMap<_, usize> = FxHashMap::default();
+            for pred in &phi.blocks {
+                *incoming.entry(*pred).or_insert(0) += 1;
+            }
+            if incoming != self.preds[&block] {
+                self.inst_error(
+                    id,
+                    "incoming blocks of phi do not match the predecessors of the block",
+                );
+            }
+        }
+    }
+
+    /// Checks that every operand is defined, and that definitions dominate their uses.
+    fn verify_uses(
+        &mut self,
+        dom_tree: &DominatorTree<BasicBlock>,
+        block: BasicBlockId,
+        id: InstructionId,
+        inst: &Instruction,
+    ) {
+        let func = self.func;
+        // Anything dominates a block that can't be reached.
+        if dom_tree.level_of(block).is_none() {
+            return;
+        }
+        for (i, &arg) in inst.operand.args().iter().enumerate() {
+            let def = match func.data.value_ref(arg) {
+                Value::Instruction(def) => *def,
+                Value::Argument(n) if *n >= func.params.len() => {
+                    self.inst_error(id, format!("use of argument {} out of range", n));
+                    continue;
+                }
+                _ => continue,
+            };
+            let (def_block, def_idx) = match self.positions.get(&def) {
+                Some(pos) => *pos,
+                None => {
+                    self.inst_error(id, "use of an instruction that is not in the function");
+                    continue;
+                }
+            };
+            let dominates = match &inst.operand {
+                // An incoming value must be available at the end of its block.
+                Operand::Phi(phi) => match phi.blocks.get(i) {
+                    Some(pred) => {
+                        dom_tree.level_of(*pred).is_none() || dom_tree.dominates(def_block, *pred)
+                    }
+                    None => true,
+                },
+                _ if def_block == block => def_idx < self.positions[&id].1,
+                _ => dom_tree.dominates(def_block, block),
+            };
+            if !dominates {
+                let name = self.value_name(arg);
+                self.inst_error(id, format!("{} does not dominate this use", name));
+            }
+        }
+    }
+
+    /// Checks that operands have the types the opcode needs.
+    fn verify_types(&mut self, id: InstructionId, inst: &Instruction) {
+        let types = &self.func.types;
+        match &inst.operand {
+            Operand::IntBinary(b) => {
+                self.expect(id, self.is_int(b.ty), "operands must be integers");
+                self.expect_args(id, &b.args, b.ty);
+            }
+            Operand::FloatBinary(b) => {
+                self.expect(id, self.is_float(b.ty), "operands must be floats");
+                self.expect_args(id, &b.args, b.ty);
+            }
+            Operand::FloatUnary(u) => {
+                self.expect(id, self.is_float(u.ty), "operand must be a float");
+                self.expect_args(id, &[u.arg], u.ty);
+            }
+            Operand::ICmp(c) => {
+                let ok = self.is_int(c.ty) || self.is_pointer(self.scalar(c.ty));
+                self.expect(id, ok, "operands must be integers or pointers");
+                self.expect_args(id, &c.args, c.ty);
+            }
+            Operand::FCmp(c) => {
+                self.expect(id, self.is_float(c.ty), "operands must be floats");
+                self.expect_args(id, &c.args, c.ty);
+            }
+            Operand::Cast(c) => {
+                self.expect_args(id, &[c.arg], c.tys[0]);
+                self.verify_cast(id, inst.opcode, c.tys[0], c.tys[1]);
+            }
+            Operand::Load(l) => {
+                self.expect(id, self.is_pointer(l.tys[1]), "address must be a pointer");
+                self.expect_args(id, &[l.addr], l.tys[1]);
+            }
+            Operand::Store(s) => {
+                self.expect(id, self.is_pointer(s.tys[1]), "address must be a pointer");
+                self.expect_args(id, &s.args[..1], s.tys[0]);
+                self.expect_args(id, &s.args[1..], s.tys[1]);
+            }
+            Operand::Alloca(a) => {
+                let ok = matches!(&*types.get(a.tys[1]), Type::Int(_));
+                self.expect(id, ok, "number of elements must be an integer");
+            }
+            Operand::Phi(p) => self.expect_args(id, &p.args, p.ty),
+            Operand::Select(s) => {
+                let i1 = types.base().i1();
+                self.expect(id, self.scalar(s.tys[0]) == i1, "condition must be i1");
+                self.expect_args(id, &s.args[..1], s.tys[0]);
+                self.expect_args(id, &s.args[1..], s.tys[1]);
+            }
+            Operand::GetElementPtr(g) => {
+                let ok = g.tys.len() > 1 && self.is_pointer(self.scalar(g.tys[1]));
+                self.expect(id, ok, "base must be a pointer");
+                for (arg, ty) in g.args.iter().zip(&g.tys[1..]) {
+                    self.expect_args(id, &[*arg], *ty);
+                }
+                for ty in g.tys.iter().skip(2) {
+                    self.expect(id, self.is_int(*ty), "indices must be integers");
+                }
+            }
+            Operand::CondBr(b) => {
+                let i1 = types.base().i1();
+                self.expect_args(id, &[b.arg], i1);
+            }
+            Operand::Switch(s) => {
+                let ty = s.cond_ty();
+                let ok = matches!(&*types.get(ty), Type::Int(_));
+                self.expect(id, ok, "condition must be an integer");
+                self.expect_args(id, &s.args[..1], ty);
+            }
+            Operand::Ret(r) => {
+                let void = types.base().void();
+                if !types.is_same(r.ty, self.func.result_ty) {
+                    self.inst_error(
+                        id,
+                        format!(
+                            "returns {} from a function returning {}",
+                            types.to_string(r.ty),
+                            types.to_string(self.func.result_ty)
+                        ),
+                    );
+                } else if r.val.is_none() != (r.ty == void) {
+                    self.inst_error(id, "return value does not match the return type");
+                }
+                if let Some(val) = r.val {
+                    self.expect_args(id, &[val], r.ty);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn verify_cast(&mut self, id: InstructionId, opcode: Opcode, from: TypeId, to: TypeId) {
+        let types = &self.func.types;
+        let ok = opcode.is_valid_cast(from, to, types);
+        if !ok {
+            let message = format!(
+                "invalid cast from {} to {}",
+                types.to_string(from),
+                types.to_string(to)
+            );
+            self.inst_error(id, message);
+        }
+    }
+
+    /// Checks that a call to a function of this module passes what its signature says.
+    fn verify_call(&mut self, id: InstructionId, inst: &Instruction) {
+        let (args, tys) = match &inst.operand {
+            Operand::Call(Call { args, tys, .. }) | Operand::Invoke(Invoke { args, tys, .. }) => {
+                (args, tys)
+            }
+            _ => return,
+        };
+        let name = match self.func.data.value_ref(args[0]) {
+            Value::Constant(ConstantData::GlobalRef(Name::Name(name))) => name,
+            _ => return,
+        };
+        let callee = match self.module.find_function_by_name(name) {
+            Some(callee) => &self.module.functions()[callee],
+            None => return,
+        };
+        let types = &self.func.types;
+        let (ret, params, is_var_arg) = match &*types.get(tys[0]) {
+            Type::Function(f) => (f.ret, f.params.clone(), f.is_var_arg),
+            _ => (tys[0], tys[1..].to_vec(), false),
+        };
+        if !types.is_same(ret, callee.result_ty) {
+            self.inst_error(
+                id,
+                format!(
+                    "call of @{} returning {} expects {}",
+                    name,
+                    types.to_string(callee.result_ty),
+                    types.to_string(ret)
+                ),
+            );
+        }
+        let num_args = args.len() - 1;
+        let num_params = callee.params.len();
+        if is_var_arg != callee.is_var_arg
+            || num_args < num_params
+            || (!callee.is_var_arg && num_args != num_params)
+        {
+            self.inst_error(
+                id,
+                format!(
+                    "call of @{} with {} arguments does not match its signature",
+                    name, num_args
+                ),
+            );
+            return;
+        }
+        for (i, param) in callee.params.iter().enumerate() {
+            let ty = if is_var_arg {
+                params.get(i)
+            } else {
+                tys.get(i + 1)
+            };
+            if matches!(ty, Some(ty) if !types.is_same(*ty, param.ty)) {
+                self.inst_error(
+                    id,
+                    format!(
+                        "argument {} of @{} must be {}",
+                        i,
+                        name,
+                        types.to_string(param.ty)
+                    ),
+                );
+            }
+        }
+        for (arg, ty) in args[1..].iter().zip(&tys[1..]) {
+            self.expect_args(id, &[*arg], *ty);
+        }
+    }
+
+    /// Checks that `users_map` records exactly the uses of each instruction in the layout.
+    fn verify_users_map(&mut self) {
+        let func = self.func;
+        let mut users: FxHashMap<InstructionId, FxHashSet<InstructionId>> = FxHashMap::default();
+        for block in func.layout.block_iter() {
+            for id in func.layout.inst_iter(block) {
+                for arg in func.data.inst_ref(id).operand.args() {
+                    if let Value::Instruction(def) = func.data.value_ref(*arg) {
+                        users.entry(*def).or_default().insert(id);
+                    }
+                }
+            }
+        }
+        for block in func.layout.block_iter() {
+            for id in func.layout.inst_iter(block) {
+                let actual = users.remove(&id).unwrap_or_default();
+                let recorded = func.data.users_map.get(&id).cloned().unwrap_or_default();
+                for user in actual.difference(&recorded) {
+                    let message = format!("{} is not recorded as a user", self.inst_name(*user));
+                    self.inst_error(id, message);
+                }
+                for user in recorded.difference(&actual) {
+                    // Removed instructions are left in the arena, but not in the map.
+                    let message = format!(
+                        "{} is recorded as a user but does not use this",
+                        self.inst_name(*user)
+                    );
+                    self.inst_error(id, message);
+                }
+            }
+        }
+    }
+
+    /// Checks that `uses` records exactly the operands of each instruction in the layout.
+    fn verify_use_lists(&mut self) {
+        let func = self.func;
+        for block in func.layout.block_iter() {
+            for id in func.layout.inst_iter(block) {
+                for (index, arg) in func.data.inst_ref(id).operand.args().iter().enumerate() {
+                    if !func
+                        .data
+                        .uses_of(*arg)
+                        .any(|u| u == Use { user: id, index })
+                    {
+                        let message = format!("operand {} is not recorded as a use", index);
+                        self.inst_error(id, message);
+                    }
+                }
+            }
+        }
+        for (&val, uses) in &func.data.uses {
+            for &Use { user, index } in uses {
+                if func.layout.block_of(user).is_none() {
+                    continue;
+                }
+                let args = func.data.inst_ref(user).operand.args();
+                if args.get(index) != Some(&val) {
+                    let message =
+                        format!("operand {} is recorded as a use of another value", index);
+                    self.inst_error(user, message);
+                }
+            }
+        }
+    }
+
+    fn expect(&mut self, id: InstructionId, ok: bool, message: &str) {
+        if !ok {
+            self.inst_error(id, message)
+        }
+    }
+
+    /// Checks that `args` whose types are known are of `ty`.
+    fn expect_args(&mut self, id: InstructionId, args: &[ValueId], ty: TypeId) {
+        for &arg in args {
+            match self.func.value_ty(arg) {
+                Some(actual) if !self.func.types.is_same(actual, ty) => {
+                    let types = &self.func.types;
+                    let message = format!(
+                        "{} is {} but {} is expected",
+                        self.value_name(arg),
+                        types.to_string(actual),
+                        types.to_string(ty)
+                    );
+                    self.inst_error(id, message);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn scalar(&self, ty: TypeId) -> TypeId {
+        match &*self.func.types.get(ty) {
+            Type::Vector(v) => v.inner,
+            _ => ty,
+        }
+    }
+
+    fn is_int(&self, ty: TypeId) -> bool {
+        matches!(&*self.func.types.get(self.scalar(ty)), Type::Int(_))
+    }
+
+    fn is_float(&self, ty: TypeId) -> bool {
+        self.func.types.is_float(self.scalar(ty))
+    }
+
+    fn is_pointer(&self, ty: TypeId) -> bool {
+        matches!(&*self.func.types.get(ty), Type::Pointer(_))
+    }
+
+    fn value_name(&self, v: ValueId) -> String {
+        match self.func.data.value_ref(v) {
+            Value::Instruction(id) => self.inst_name(*id),
+            Value::Argument(i) => match self.func.params.get(*i) {
+                Some(param) => format!("%{}", param.name),
+                None => format!("argument {}", i),
+            },
+            _ => "operand".to_string(),
+        }
+    }
+
+    fn inst_name(&self, id: InstructionId) -> String {
+        let inst = self.func.data.inst_ref(id);
+        match &inst.dest {
+            Some(dest) => format!("%{}", dest),
+            None => format!("`{:?}`", inst.opcode),
+        }
+    }
+
+    fn block_error(&mut self, block: BasicBlockId, message: impl Into<String>) {
+        let mut location = format!("@{}", self.func.name);
+        if let Some(name) = &self.func.data.block_ref(block).name {
+            location.push_str(&format!(", %{}", name));
+        }
+        self.diags.push(Diagnostic {
+            function: self.func.name.clone(),
+            block: Some(block),
+            inst: None,
+            message: message.into(),
+            location,
+        })
+    }
+
+    fn inst_error(&mut self, id: InstructionId, message: impl Into<String>) {
+        let block = self.positions.get(&id).map(|(block, _)| *block);
+        let mut location = format!("@{}", self.func.name);
+        if let Some(name) = block.and_then(|b| self.func.data.block_ref(b).name.as_ref()) {
+            location.push_str(&format!(", %{}", name));
+        }
+        location.push_str(&format!(", {}", self.inst_name(id)));
+        self.diags.push(Diagnostic {
+            function: self.func.name.clone(),
+            block,
+            inst: Some(id),
+            message: message.into(),
+            location,
+        })
+    }
+}
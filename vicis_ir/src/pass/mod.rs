@@ -1,173 +1,554 @@
+//! Passes and the managers that run them.
+//!
+//! Transforms run on a `Module`, a `Function` or a loop in a function, and nest with
+//! adaptors: a `ModuleToFunctionAdaptor` runs a function pass on every defined function,
+//! and a `FunctionToLoopAdaptor` runs a loop pass on every loop. Analyses are computed on
+//! demand through an `AnalysisManager`, which caches them per function until a transform
+//! reports that it didn't preserve them.
+
 pub mod analysis;
 pub mod transform;
 
-use rustc_hash::FxHashMap;
-use std::any::{Any, TypeId};
-
+use crate::ir::{
+    function::{basic_block::BasicBlockId, Function, FunctionId},
+    module::Module,
+};
+use analysis::loop_info::LoopInfoAnalysis;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+    rc::Rc,
+};
+
+/// An analysis of `T`. The result is cached by `AnalysisManager`, keyed by the type of
+/// the pass, so analyses that other passes request must implement `Default`.
 pub trait AnalysisPass<T> {
-    fn run_on(&self, _: &T, _: &mut Box<dyn Any>) {}
+    type Result: 'static;
+
+    /// Computes the result. Analyses this one requests through `analyses` are its
+    /// dependencies: invalidating any of them invalidates this one too.
+    fn run_on(&self, target: &T, analyses: &mut Analyses<T>) -> Self::Result;
 }
 
+/// A transform of `T`. Returns which analyses are still valid after it.
 pub trait TransformPass<T> {
-    fn run_on(&self, _: &mut T, _: &mut Box<dyn Any>) {}
+    fn run_on(&self, target: &mut T, analyses: &mut Analyses<T>) -> PreservedAnalyses;
+}
+
+/// A transform of the loop whose header is `header`.
+pub trait LoopPass {
+    fn run_on(
+        &self,
+        func: &mut Function,
+        header: BasicBlockId,
+        analyses: &mut Analyses<Function>,
+    ) -> PreservedAnalyses;
+}
+
+/// Analyses that a transform left valid.
+#[derive(Debug, Clone)]
+pub struct PreservedAnalyses {
+    all: bool,
+    /// Whether analyses of functions have been taken care of. A module transform that runs
+    /// function passes invalidates what they changed as it goes.
+    all_on_functions: bool,
+    preserved: FxHashSet<TypeId>,
+}
+
+/// Where a cached result comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Unit {
+    Module,
+    Function(FunctionId),
+}
+
+type Key = (Unit, TypeId);
+
+struct Cached {
+    result: Rc<dyn Any>,
+    deps: FxHashSet<Key>,
 }
 
-pub enum Pass<T> {
-    Analysis(Box<dyn AnalysisPass<T>>),
-    Transform(Box<dyn TransformPass<T>>),
+/// Caches the results of analyses of a module and its functions.
+#[derive(Default)]
+pub struct AnalysisManager {
+    results: FxHashMap<Key, Cached>,
+    /// Analyses being computed, with the analyses each of them has requested so far
+    computing: Vec<FxHashSet<Key>>,
 }
 
+/// The analyses of one module or function.
+pub struct Analyses<'a, T> {
+    manager: &'a mut AnalysisManager,
+    unit: Unit,
+    _target: PhantomData<fn(&T)>,
+}
+
+/// Runs transforms of `T` in order, invalidating analyses after each of them.
 pub struct PassManager<T> {
-    passes: Vec<Pass<T>>,
-    results: FxHashMap<TypeId, Box<dyn Any>>,
+    passes: Vec<Box<dyn TransformPass<T>>>,
 }
 
-impl<T> Default for PassManager<T> {
-    fn default() -> Self {
+/// Runs a function pass on every function with a body.
+pub struct ModuleToFunctionAdaptor<P>(pub P);
+
+/// Runs a loop pass on every loop, inner loops first.
+pub struct FunctionToLoopAdaptor<P>(pub P);
+
+/// Computes `A` so that it's cached for later passes.
+pub struct RequireAnalysis<A>(PhantomData<A>);
+
+impl PreservedAnalyses {
+    pub fn all() -> Self {
+        Self {
+            all: true,
+            all_on_functions: true,
+            preserved: FxHashSet::default(),
+        }
+    }
+
+    pub fn none() -> Self {
         Self {
-            passes: vec![],
-            results: FxHashMap::default(),
+            all: false,
+            all_on_functions: false,
+            preserved: FxHashSet::default(),
+        }
+    }
+
+    pub fn preserve<A: 'static>(mut self) -> Self {
+        self.preserved.insert(TypeId::of::<A>());
+        self
+    }
+
+    pub fn preserve_all_on_functions(mut self) -> Self {
+        self.all_on_functions = true;
+        self
+    }
+
+    pub fn is_preserved<A: 'static>(&self) -> bool {
+        self.is_preserved_id(TypeId::of::<A>())
+    }
+
+    pub fn are_all_preserved(&self) -> bool {
+        self.all
+    }
+
+    /// Keeps only what both `self` and `other` preserve.
+    pub fn intersect(&mut self, other: &Self) {
+        if other.all {
+            return;
         }
+        if self.all {
+            *self = other.clone();
+            return;
+        }
+        self.all_on_functions &= other.all_on_functions;
+        self.preserved.retain(|id| other.preserved.contains(id));
+    }
+
+    fn is_preserved_id(&self, id: TypeId) -> bool {
+        self.all || self.preserved.contains(&id)
     }
 }
 
-impl<T> PassManager<T> {
+impl AnalysisManager {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn add(&mut self, pass: Pass<T>) {
-        self.passes.push(pass)
+    pub fn module(&mut self) -> Analyses<'_, Module> {
+        Analyses::new(self, Unit::Module)
     }
 
-    pub fn add_analysis<P: 'static + AnalysisPass<T>>(&mut self, pass: P) {
-        self.passes.push(Pass::Analysis(Box::new(pass)))
+    pub fn function(&mut self, id: FunctionId) -> Analyses<'_, Function> {
+        Analyses::new(self, Unit::Function(id))
     }
 
-    pub fn add_transform<P: 'static + TransformPass<T>>(&mut self, pass: P) {
-        self.passes.push(Pass::Transform(Box::new(pass)))
+    pub fn clear(&mut self) {
+        self.results.clear()
     }
 
-    pub fn run_on(&mut self, target: &mut T) {
-        self.results.clear();
-
-        for pass in &self.passes {
-            let mut result: Box<dyn Any> = Box::new(());
-            match pass {
-                Pass::Analysis(analysis) => analysis.run_on(target, &mut result),
-                Pass::Transform(transform) => transform.run_on(target, &mut result),
+    /// Drops the results of `unit` that `preserved` doesn't keep, and then everything that
+    /// depends on what has been dropped. The module's results are about its functions too,
+    /// so they are dropped along with the results of a function.
+    fn invalidate(&mut self, unit: Unit, preserved: &PreservedAnalyses) {
+        if preserved.all {
+            return;
+        }
+        let mut invalidated: FxHashSet<Key> = self
+            .results
+            .keys()
+            .filter(|(u, id)| {
+                let affected = match unit {
+                    Unit::Module => *u == Unit::Module || !preserved.all_on_functions,
+                    Unit::Function(_) => *u == unit || *u == Unit::Module,
+                };
+                affected && !preserved.is_preserved_id(*id)
+            })
+            .copied()
+            .collect();
+        loop {
+            let dependents: Vec<Key> = self
+                .results
+                .iter()
+                .filter(|(key, cached)| {
+                    !invalidated.contains(key) && !cached.deps.is_disjoint(&invalidated)
+                })
+                .map(|(key, _)| *key)
+                .collect();
+            if dependents.is_empty() {
+                break;
             }
-            self.results.insert((*result).type_id(), result);
+            invalidated.extend(dependents);
+        }
+        for key in invalidated {
+            self.results.remove(&key);
         }
     }
+}
 
-    pub fn run_analyses_on(&mut self, target: &T) {
-        self.results.clear();
+impl<'a, T: 'static> Analyses<'a, T> {
+    fn new(manager: &'a mut AnalysisManager, unit: Unit) -> Self {
+        Self {
+            manager,
+            unit,
+            _target: PhantomData,
+        }
+    }
 
-        for pass in &self.passes {
-            let mut result: Box<dyn Any> = Box::new(());
-            match pass {
-                Pass::Analysis(analysis) => analysis.run_on(target, &mut result),
-                Pass::Transform(_) => {}
-            }
-            self.results.insert((*result).type_id(), result);
+    /// Returns the result of `A` on `target`, computing it if it's not cached.
+    pub fn get<A: AnalysisPass<T> + Default + 'static>(&mut self, target: &T) -> Rc<A::Result> {
+        self.get_with(&A::default(), target)
+    }
+
+    /// Returns the result of `pass` on `target`, computing it if it's not cached.
+    pub fn get_with<A: AnalysisPass<T> + 'static>(
+        &mut self,
+        pass: &A,
+        target: &T,
+    ) -> Rc<A::Result> {
+        let key = (self.unit, TypeId::of::<A>());
+        if let Some(deps) = self.manager.computing.last_mut() {
+            deps.insert(key);
         }
+        if let Some(result) = self.get_cached::<A>() {
+            return result;
+        }
+        self.manager.computing.push(FxHashSet::default());
+        let result = Rc::new(pass.run_on(target, self));
+        let deps = self.manager.computing.pop().unwrap();
+        self.manager.results.insert(
+            key,
+            Cached {
+                result: result.clone(),
+                deps,
+            },
+        );
+        result
+    }
+
+    /// Returns the result of `A` if it's cached.
+    pub fn get_cached<A: AnalysisPass<T> + 'static>(&self) -> Option<Rc<A::Result>> {
+        let cached = self.manager.results.get(&(self.unit, TypeId::of::<A>()))?;
+        cached.result.clone().downcast().ok()
+    }
+
+    /// Drops the results that `preserved` doesn't keep.
+    pub fn invalidate(&mut self, preserved: &PreservedAnalyses) {
+        self.manager.invalidate(self.unit, preserved)
     }
+}
 
-    pub fn get_result<P: 'static>(&self) -> Option<&P> {
-        self.results
-            .get(&TypeId::of::<P>())
-            .and_then(|result| result.downcast_ref())
+impl<'a> Analyses<'a, Module> {
+    /// Returns the analyses of the function `id`. Module analyses can depend on them.
+    pub fn function(&mut self, id: FunctionId) -> Analyses<'_, Function> {
+        Analyses::new(self.manager, Unit::Function(id))
     }
 }
 
-use crate::ir::{function::Function, module::Module};
+impl<T> Default for PassManager<T> {
+    fn default() -> Self {
+        Self { passes: vec![] }
+    }
+}
+
+impl<T: 'static> PassManager<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_transform<P: 'static + TransformPass<T>>(&mut self, pass: P) {
+        self.passes.push(Box::new(pass))
+    }
+
+    /// Adds a pass that computes `A`, so that it's ready for the passes after it.
+    pub fn add_analysis<A: 'static + AnalysisPass<T> + Default>(&mut self) {
+        self.passes
+            .push(Box::new(RequireAnalysis::<A>(PhantomData)))
+    }
+}
+
+impl PassManager<Module> {
+    /// Adds a function pass to run on every function.
+    pub fn add_function_transform<P: 'static + TransformPass<Function>>(&mut self, pass: P) {
+        self.add_transform(ModuleToFunctionAdaptor(pass))
+    }
+
+    pub fn run_on_module(&self, module: &mut Module, analyses: &mut AnalysisManager) {
+        self.run_on(module, &mut analyses.module());
+    }
+}
 
 impl PassManager<Function> {
-    pub fn run_analyses_on_module(&mut self, module: &Module) {
-        for (_, func) in &module.functions {
-            self.run_analyses_on(func)
+    /// Adds a loop pass to run on every loop.
+    pub fn add_loop_transform<P: 'static + LoopPass>(&mut self, pass: P) {
+        self.add_transform(FunctionToLoopAdaptor(pass))
+    }
+}
+
+impl<T: 'static> TransformPass<T> for PassManager<T> {
+    fn run_on(&self, target: &mut T, analyses: &mut Analyses<T>) -> PreservedAnalyses {
+        let mut preserved = PreservedAnalyses::all();
+        for pass in &self.passes {
+            let pass_preserved = pass.run_on(target, analyses);
+            analyses.invalidate(&pass_preserved);
+            preserved.intersect(&pass_preserved);
         }
+        preserved
     }
+}
+
+impl<P: TransformPass<Function>> TransformPass<Module> for ModuleToFunctionAdaptor<P> {
+    fn run_on(&self, module: &mut Module, analyses: &mut Analyses<Module>) -> PreservedAnalyses {
+        let mut preserved = PreservedAnalyses::all();
+        let ids: Vec<FunctionId> = module.functions().iter().map(|(id, _)| id).collect();
+        for id in ids {
+            let func = &mut module.functions_mut()[id];
+            if func.is_prototype() {
+                continue;
+            }
+            let mut func_analyses = analyses.function(id);
+            let func_preserved = self.0.run_on(func, &mut func_analyses);
+            func_analyses.invalidate(&func_preserved);
+            preserved.intersect(&func_preserved);
+        }
+        preserved.preserve_all_on_functions()
+    }
+}
 
-    pub fn run_on_module(&mut self, module: &mut Module) {
-        for (_, func) in &mut module.functions {
-            self.run_on(func)
+impl<P: LoopPass> TransformPass<Function> for FunctionToLoopAdaptor<P> {
+    fn run_on(&self, func: &mut Function, analyses: &mut Analyses<Function>) -> PreservedAnalyses {
+        let loop_info = analyses.get::<LoopInfoAnalysis>(func);
+        let headers: Vec<BasicBlockId> = loop_info
+            .loops_innermost_first()
+            .into_iter()
+            .map(|id| loop_info.get(id).header())
+            .collect();
+        let mut preserved = PreservedAnalyses::all();
+        for header in headers {
+            let loop_preserved = self.0.run_on(func, header, analyses);
+            analyses.invalidate(&loop_preserved);
+            preserved.intersect(&loop_preserved);
         }
+        preserved
     }
 }
 
-impl<T> Pass<T> {
-    pub fn analysis<P: 'static + AnalysisPass<T>>(pass: P) -> Self {
-        Self::Analysis(Box::new(pass))
+impl<T: 'static, A: AnalysisPass<T> + Default + 'static> TransformPass<T> for RequireAnalysis<A> {
+    fn run_on(&self, target: &mut T, analyses: &mut Analyses<T>) -> PreservedAnalyses {
+        analyses.get::<A>(target);
+        PreservedAnalyses::all()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::ir::{
-        function::Function,
-        module::{parse_assembly, Module},
-    };
+    use crate::{ir::module::parse_assembly, pass::analysis::dom_tree::DominatorTreeAnalysis};
+    use std::cell::{Cell, RefCell};
+
+    thread_local! {
+        static RUNS: Cell<usize> = const { Cell::new(0) };
+        static VISITED: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    }
 
-    pub struct TestFunctionAnalysisPass {}
-    pub struct TestFunctionAnalysisResult(String);
+    #[derive(Default)]
+    struct NameAnalysis;
 
-    impl AnalysisPass<Function> for TestFunctionAnalysisPass {
-        fn run_on(&self, func: &Function, result: &mut Box<dyn Any>) {
-            *result = Box::new(TestFunctionAnalysisResult(func.name.to_owned()));
+    impl AnalysisPass<Function> for NameAnalysis {
+        type Result = String;
+
+        fn run_on(&self, func: &Function, _: &mut Analyses<Function>) -> String {
+            RUNS.with(|runs| runs.set(runs.get() + 1));
+            func.name.to_owned()
         }
     }
 
-    pub struct TestFunctionTransformPass {}
+    /// Depends on `NameAnalysis`.
+    #[derive(Default)]
+    struct NameLenAnalysis;
+
+    impl AnalysisPass<Function> for NameLenAnalysis {
+        type Result = usize;
+
+        fn run_on(&self, func: &Function, analyses: &mut Analyses<Function>) -> usize {
+            analyses.get::<NameAnalysis>(func).len()
+        }
+    }
 
-    impl TransformPass<Function> for TestFunctionTransformPass {
-        fn run_on(&self, _func: &mut Function, _result: &mut Box<dyn Any>) {}
+    struct Rename(PreservedAnalyses);
+
+    impl TransformPass<Function> for Rename {
+        fn run_on(
+            &self,
+            func: &mut Function,
+            analyses: &mut Analyses<Function>,
+        ) -> PreservedAnalyses {
+            let name = analyses.get::<NameAnalysis>(func);
+            func.name = format!("{}_", name);
+            self.0.clone()
+        }
+    }
+
+    struct VisitLoops;
+
+    impl LoopPass for VisitLoops {
+        fn run_on(
+            &self,
+            func: &mut Function,
+            header: BasicBlockId,
+            _: &mut Analyses<Function>,
+        ) -> PreservedAnalyses {
+            let name = format!("{:?}", func.data.block_ref(header).name.as_ref().unwrap());
+            VISITED.with(|visited| visited.borrow_mut().push(name));
+            PreservedAnalyses::all()
+        }
     }
 
     fn test_module() -> Module {
         parse_assembly(
             r#"
-source_filename = "sample"                                                                            
-target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"       
-target triple = "x86_64-pc-linux-gnu"                                                              
-         
-define dso_local i32 @main() {                                            
+define dso_local i32 @main() {
   ret i32 0
-}                                                                            
+}
+
+define dso_local i32 @f() {
+  ret i32 1
+}
+
+declare i32 @g()
         "#,
-        ).expect("failed to parse IR")
+        )
+        .expect("failed to parse IR")
     }
 
     #[test]
-    fn analysis() {
+    fn analysis_per_function() {
         let module = test_module();
-
-        let mut pm = PassManager::new();
-        pm.add_analysis(TestFunctionAnalysisPass {});
-
-        pm.run_analyses_on_module(&module);
-
+        let mut am = AnalysisManager::new();
+        for (id, func) in module.functions() {
+            if !func.is_prototype() {
+                am.function(id).get::<NameAnalysis>(func);
+            }
+        }
+        let main = module.find_function_by_name("main").unwrap();
+        let f = module.find_function_by_name("f").unwrap();
         assert_eq!(
-            pm.get_result::<TestFunctionAnalysisResult>().unwrap().0,
+            *am.function(main).get_cached::<NameAnalysis>().unwrap(),
             "main"
         );
+        assert_eq!(*am.function(f).get_cached::<NameAnalysis>().unwrap(), "f");
     }
 
     #[test]
-    fn analysis_transform() {
+    fn cache_and_invalidate() {
         let mut module = test_module();
+        let mut am = AnalysisManager::new();
+        RUNS.with(|runs| runs.set(0));
 
+        // `Rename` preserves `NameAnalysis` (wrongly, but on purpose) so it's computed once
+        // per function, and it's still cached afterwards.
         let mut pm = PassManager::new();
-        pm.add_analysis(TestFunctionAnalysisPass {});
-        pm.add_transform(TestFunctionTransformPass {});
+        pm.add_function_transform(Rename(PreservedAnalyses::none().preserve::<NameAnalysis>()));
+        pm.add_function_transform(Rename(PreservedAnalyses::all()));
+        pm.run_on_module(&mut module, &mut am);
+        assert_eq!(RUNS.with(Cell::get), 2);
+        let main = module.find_function_by_name("main_").unwrap();
+        assert_eq!(
+            *am.function(main).get_cached::<NameAnalysis>().unwrap(),
+            "main"
+        );
 
-        pm.run_on_module(&mut module);
+        // Now the first `Rename` uses the cached result, and the second recomputes it.
+        let mut pm = PassManager::new();
+        pm.add_function_transform(Rename(PreservedAnalyses::none()));
+        pm.add_function_transform(Rename(PreservedAnalyses::none()));
+        pm.run_on_module(&mut module, &mut am);
+        assert_eq!(RUNS.with(Cell::get), 4);
+        assert!(am.function(main).get_cached::<NameAnalysis>().is_none());
+    }
 
+    #[test]
+    fn invalidate_dependents() {
+        let module = test_module();
+        let main = module.find_function_by_name("main").unwrap();
+        let func = &module.functions()[main];
+        let mut am = AnalysisManager::new();
+        let mut analyses = am.function(main);
+        assert_eq!(*analyses.get::<NameLenAnalysis>(func), 4);
+        assert!(analyses.get_cached::<NameAnalysis>().is_some());
+
+        // Preserving `NameLenAnalysis` isn't enough if what it depends on is not preserved.
+        analyses.invalidate(&PreservedAnalyses::none().preserve::<NameLenAnalysis>());
+        assert!(analyses.get_cached::<NameAnalysis>().is_none());
+        assert!(analyses.get_cached::<NameLenAnalysis>().is_none());
+    }
+
+    #[test]
+    fn require_analysis() {
+        let mut module = test_module();
+        let mut am = AnalysisManager::new();
+        let mut fpm = PassManager::new();
+        fpm.add_analysis::<DominatorTreeAnalysis>();
+        let mut pm = PassManager::new();
+        pm.add_function_transform(fpm);
+        pm.run_on_module(&mut module, &mut am);
+        let f = module.find_function_by_name("f").unwrap();
+        assert!(am
+            .function(f)
+            .get_cached::<DominatorTreeAnalysis>()
+            .is_some());
+    }
+
+    #[test]
+    fn loop_passes() {
+        let mut module = parse_assembly(
+            r#"
+define void @f(i1 %c) {
+entry:
+  br label %outer
+outer:
+  br label %inner
+inner:
+  br i1 %c, label %inner, label %latch
+latch:
+  br i1 %c, label %outer, label %exit
+exit:
+  ret void
+}
+        "#,
+        )
+        .unwrap();
+        VISITED.with(|visited| visited.borrow_mut().clear());
+        let mut fpm = PassManager::new();
+        fpm.add_loop_transform(VisitLoops);
+        let mut pm = PassManager::new();
+        pm.add_function_transform(fpm);
+        pm.run_on_module(&mut module, &mut AnalysisManager::new());
         assert_eq!(
-            pm.get_result::<TestFunctionAnalysisResult>().unwrap().0,
-            "main"
+            VISITED.with(|visited| visited.borrow().clone()),
+            vec!["inner", "outer"]
         );
     }
 }
@@ -7,15 +7,33 @@ use crate::{
         },
         value::{Value, ValueId},
     },
-    pass::{analysis::dom_tree, transform::sccp::SCCP},
+    pass::{
+        analysis::dom_tree::{self, DominatorTreeAnalysis},
+        transform::sccp::SCCP,
+        Analyses, PreservedAnalyses, TransformPass,
+    },
 };
 use rustc_hash::{FxHashMap, FxHashSet};
-use std::{cmp::Ordering, collections::BinaryHeap};
+use std::{cmp::Ordering, collections::BinaryHeap, rc::Rc};
+
+/// Runs `Mem2Reg` as a function pass.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Mem2RegPass;
+
+impl TransformPass<Function> for Mem2RegPass {
+    fn run_on(&self, func: &mut Function, analyses: &mut Analyses<Function>) -> PreservedAnalyses {
+        let dom_tree = analyses.get::<DominatorTreeAnalysis>(func);
+        Mem2Reg::with_dom_tree(func, dom_tree).run();
+        PreservedAnalyses::none()
+    }
+}
 
 pub struct Mem2Reg<'a> {
     func: &'a mut Function,
-    dom_tree: dom_tree::DominatorTree<BasicBlock>,
+    dom_tree: Rc<dom_tree::DominatorTree<BasicBlock>>,
     inst_indexes: InstructionIndexes,
+    /// The value that refers to each added phi
+    phi_values: FxHashMap<InstructionId, ValueId>,
 }
 
 type InstructionIndex = usize;
@@ -33,9 +51,19 @@ struct RenameData {
 
 impl<'a> Mem2Reg<'a> {
     pub fn new(func: &'a mut Function) -> Self {
+        let dom_tree = Rc::new(dom_tree::DominatorTree::new(func));
+        Self::with_dom_tree(func, dom_tree)
+    }
+
+    /// Uses `dom_tree`, which must be the dominator tree of `func`, instead of computing it.
+    pub fn with_dom_tree(
+        func: &'a mut Function,
+        dom_tree: Rc<dom_tree::DominatorTree<BasicBlock>>,
+    ) -> Self {
         Self {
-            dom_tree: dom_tree::DominatorTree::new(func),
+            dom_tree,
             inst_indexes: InstructionIndexes::default(),
+            phi_values: FxHashMap::default(),
             func,
         }
     }
@@ -141,10 +169,8 @@ impl<'a> Mem2Reg<'a> {
         }
 
         for load_id in loads_to_remove {
+            self.func.data.replace_all_uses(load_id, src);
             self.func.remove_inst(load_id);
-            for user_id in self.func.data.users_of(load_id).clone() {
-                self.func.data.replace_inst_arg(user_id, load_id, src);
-            }
         }
     }
 
@@ -195,10 +221,8 @@ impl<'a> Mem2Reg<'a> {
 
             stores_to_remove.push(nearest_store_id);
 
+            self.func.data.replace_all_uses(load_id, src);
             self.func.remove_inst(load_id);
-            for user_id in self.func.data.users_of(load_id).clone() {
-                self.func.data.replace_inst_arg(user_id, load_id, src);
-            }
         }
 
         if remove_all_access {
@@ -362,7 +386,14 @@ impl<'a> Mem2Reg<'a> {
                 phi.args_mut().push(*incoming_id);
                 phi.blocks_mut().push(data.pred.unwrap());
                 self.func.data.validate_inst_uses(*phi_id);
-                *incoming_id = self.func.data.create_value(Value::Instruction(*phi_id));
+                *incoming_id = match self.phi_values.get(phi_id) {
+                    Some(&val) => val,
+                    None => {
+                        let val = self.func.data.create_value(Value::Instruction(*phi_id));
+                        self.phi_values.insert(*phi_id, val);
+                        val
+                    }
+                };
             }
 
             if !visited.insert(data.cur) {
@@ -430,11 +461,12 @@ impl<'a> Mem2Reg<'a> {
         self.func.types.is_atomic(ty)
             && self.func.data.users_of(alloca_id).iter().all(|&user_id| {
                 let user = self.func.data.inst_ref(user_id);
-                user.opcode.is_load()
+                // Volatile accesses must be kept as they are
+                (user.opcode.is_load() && !user.operand.as_load().unwrap().volatile)
                     || (user.opcode.is_store() && {
-                        let dst_id = user.operand.as_store().unwrap().dst_val();
-                        let dst = self.func.data.value_ref(dst_id);
-                        matches!(dst, Value::Instruction(id) if id == &alloca_id)
+                        let store = user.operand.as_store().unwrap();
+                        let dst = self.func.data.value_ref(store.dst_val());
+                        !store.volatile && matches!(dst, Value::Instruction(id) if id == &alloca_id)
                     })
             })
     }
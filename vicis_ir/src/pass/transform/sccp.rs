@@ -1,15 +1,30 @@
 // Sparse Conditional Constant Propagation
 
-use crate::ir::{
-    function::{
-        basic_block::BasicBlockId,
-        instruction::{Br, Instruction, Opcode, Operand},
-        Function,
+use crate::{
+    ir::{
+        function::{
+            basic_block::BasicBlockId,
+            instruction::{Br, Instruction, Opcode, Operand},
+            Function,
+        },
+        value::{ConstantData, Value},
     },
-    value::{ConstantData, Value},
+    pass::{Analyses, PreservedAnalyses, TransformPass},
 };
+use rustc_hash::FxHashSet;
 use std::collections::VecDeque;
 
+/// Runs `SCCP` as a function pass.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SCCPPass;
+
+impl TransformPass<Function> for SCCPPass {
+    fn run_on(&self, func: &mut Function, _: &mut Analyses<Function>) -> PreservedAnalyses {
+        SCCP::new(func).run();
+        PreservedAnalyses::none()
+    }
+}
+
 pub struct SCCP<'a> {
     func: &'a mut Function,
 }
@@ -22,6 +37,8 @@ impl<'a> SCCP<'a> {
     pub fn run(&mut self) {
         let mut foldable = VecDeque::new();
         let mut foldable_condbr = VecDeque::new();
+        let mut foldable_switch = VecDeque::new();
+        let mut foldable_select = VecDeque::new();
         let mut changed = false;
 
         for block_id in self.func.layout.block_iter() {
@@ -35,6 +52,14 @@ impl<'a> SCCP<'a> {
                 if self.is_foldable_condbr(inst) {
                     foldable_condbr.push_back(inst_id);
                 }
+
+                if self.is_foldable_switch(inst) {
+                    foldable_switch.push_back(inst_id);
+                }
+
+                if self.is_foldable_select(inst) {
+                    foldable_select.push_back(inst_id);
+                }
             }
         }
 
@@ -51,9 +76,54 @@ impl<'a> SCCP<'a> {
             // Append Br and remove CondBr
             self.func.layout.append_inst(br, block);
             self.func.remove_inst(inst_id);
-            // Remove successor and predecessor from blocks properly
-            self.func.data.remove_block_succ(block, not_dst);
-            self.func.data.remove_block_pred(not_dst, block);
+            // Remove successor and predecessor from blocks properly, and what phis take from
+            // the edge that is gone
+            if not_dst == dst {
+                for phi in self.func.phis_of(dst) {
+                    self.func.data.dedup_phi_incoming(phi, block);
+                }
+            } else {
+                self.func.data.remove_block_succ(block, not_dst);
+                self.func.data.remove_block_pred(not_dst, block);
+                self.remove_phi_incoming(not_dst, block);
+            }
+            changed |= true;
+        }
+
+        while let Some(inst_id) = foldable_switch.pop_front() {
+            let switch = &self.func.data.inst_ref(inst_id);
+            let block = switch.parent;
+            let switch = switch.operand.as_switch().unwrap();
+            let cond = self.func.data.value_ref(switch.cond());
+            let dst = switch
+                .cases()
+                .find(|&(val, _)| self.func.data.value_ref(val) == cond)
+                .map_or(switch.default_block(), |(_, dst)| dst);
+            let not_dsts: FxHashSet<BasicBlockId> = switch
+                .blocks
+                .iter()
+                .copied()
+                .filter(|&b| b != dst)
+                .collect();
+            // Create a new Br instruction to replace Switch with.
+            let br = Opcode::Br
+                .with_block(block)
+                .with_operand(Operand::Br(Br { block: dst }));
+            let br = self.func.data.create_inst(br);
+            // Append Br and remove Switch
+            self.func.layout.append_inst(br, block);
+            self.func.remove_inst(inst_id);
+            // Remove successors and predecessors from blocks properly, and what phis take
+            // from the edges that are gone. Several cases may have gone to `dst`, whose
+            // phis now need an entry for the one `br`.
+            for not_dst in not_dsts {
+                self.func.data.remove_block_succ(block, not_dst);
+                self.func.data.remove_block_pred(not_dst, block);
+                self.remove_phi_incoming(not_dst, block);
+            }
+            for phi in self.func.phis_of(dst) {
+                self.func.data.dedup_phi_incoming(phi, block);
+            }
             changed |= true;
         }
 
@@ -61,9 +131,30 @@ impl<'a> SCCP<'a> {
             self.fold_phi();
         }
 
+        while let Some(inst_id) = foldable_select.pop_front() {
+            let select = self
+                .func
+                .data
+                .inst_ref(inst_id)
+                .operand
+                .as_select()
+                .unwrap();
+            let chosen = match self.func.data.value_ref(select.cond()) {
+                // Selecting on poison yields poison
+                Value::Constant(ConstantData::Poison) => {
+                    self.func.data.create_value(Value::poison())
+                }
+                Value::Constant(cond) if cond.as_int().is_zero() => select.args[2],
+                _ => select.args[1],
+            };
+            self.func.data.replace_all_uses(inst_id, chosen);
+            self.func.remove_inst(inst_id);
+            changed |= true;
+        }
+
         while let Some(inst_id) = foldable.pop_front() {
             let inst = &self.func.data.inst_ref(inst_id);
-            let folded = match inst.fold_consts(&self.func.data) {
+            let folded = match inst.fold_consts(&self.func.data, &self.func.types) {
                 Some(folded) => folded,
                 None => continue,
             };
@@ -81,13 +172,38 @@ impl<'a> SCCP<'a> {
     fn is_foldable(&self, inst: &Instruction) -> bool {
         matches!(
             inst.opcode,
-            Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::ICmp | Opcode::Zext
+            Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::SDiv
+                | Opcode::UDiv
+                | Opcode::SRem
+                | Opcode::URem
+                | Opcode::And
+                | Opcode::Or
+                | Opcode::Xor
+                | Opcode::Shl
+                | Opcode::LShr
+                | Opcode::AShr
+                | Opcode::ICmp
+                | Opcode::ExtractElement
+                | Opcode::Zext
+                | Opcode::Freeze
         ) && inst
             .operand
             .args()
             .iter()
             .map(|arg| self.func.data.value_ref(*arg))
-            .all(|arg| matches!(arg, Value::Constant(ConstantData::Int(_))))
+            .all(|arg| {
+                matches!(
+                    arg,
+                    Value::Constant(ConstantData::Int(_))
+                        | Value::Constant(ConstantData::Vector(_))
+                        | Value::Constant(ConstantData::AggregateZero)
+                        | Value::Constant(ConstantData::Poison)
+                        | Value::Constant(ConstantData::Undef)
+                )
+            })
     }
 
     fn is_foldable_condbr(&self, inst: &Instruction) -> bool {
@@ -100,6 +216,36 @@ impl<'a> SCCP<'a> {
                 .all(|arg| matches!(arg, Value::Constant(ConstantData::Int(_))))
     }
 
+    fn is_foldable_switch(&self, inst: &Instruction) -> bool {
+        // Case values are always constants, so only the condition matters
+        matches!(inst.opcode, Opcode::Switch)
+            && matches!(
+                self.func.data.value_ref(inst.operand.args()[0]),
+                Value::Constant(ConstantData::Int(_))
+            )
+    }
+
+    fn is_foldable_select(&self, inst: &Instruction) -> bool {
+        matches!(inst.opcode, Opcode::Select)
+            && matches!(
+                self.func.data.value_ref(inst.operand.args()[0]),
+                Value::Constant(ConstantData::Int(_)) | Value::Constant(ConstantData::Poison)
+            )
+    }
+
+    /// Removes what the phis in `block` take from `pred`. A phi left with no entries is
+    /// removed, and its uses become `undef`.
+    fn remove_phi_incoming(&mut self, block: BasicBlockId, pred: BasicBlockId) {
+        for phi in self.func.phis_of(block) {
+            self.func.data.remove_phi_incoming(phi, pred);
+            if self.func.data.inst_ref(phi).operand.args().is_empty() {
+                let undef = self.func.data.create_value(Value::undef());
+                self.func.data.replace_all_uses(phi, undef);
+                self.func.remove_inst(phi);
+            }
+        }
+    }
+
     fn fold_phi(&mut self) {
         let mut remove_list = vec![];
 
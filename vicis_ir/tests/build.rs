@@ -1,4 +1,11 @@
-use vicis_ir::ir::{function, module};
+use vicis_ir::{
+    ir::{
+        function::{self, instruction::ICmpCond, Parameter},
+        module::{self, name::Name},
+        value::{ConstantData, Value},
+    },
+    pass::analysis::verifier::verify_module,
+};
 
 #[test]
 fn build() {
@@ -14,3 +21,158 @@ fn build() {
 
     insta::assert_debug_snapshot!(module);
 }
+
+#[test]
+fn build_loop() {
+    let mut module = module::Module::default();
+    let i32 = module.types.base().i32();
+    let param = |ty| Parameter {
+        name: Name::Name("n".to_string()),
+        ty,
+        attrs: vec![],
+    };
+    let func = module.create_function("sum", i32, vec![param(i32)], false);
+    let func = &mut module.functions_mut()[func];
+    let mut builder = function::builder::Builder::new(func);
+    let entry = builder.create_block();
+    let header = builder.create_block();
+    let body = builder.create_block();
+    let exit = builder.create_block();
+    let (n, zero, one) = (
+        builder.value(Value::Argument(0)),
+        builder.value(0i32),
+        builder.value(1i32),
+    );
+
+    builder.switch_to_block(entry);
+    builder.inst().br(header);
+
+    builder.switch_to_block(header);
+    let i = builder.inst().phi(i32);
+    let sum = builder.inst().phi(i32);
+    let (i_val, sum_val) = (builder.value(i), builder.value(sum));
+    let cond = builder.inst().icmp(ICmpCond::Slt, i32, i_val, n);
+    let cond = builder.value(cond);
+    builder.inst().cond_br(cond, body, exit);
+
+    builder.switch_to_block(body);
+    let next_sum = builder.inst().add(i32, sum_val, i_val);
+    let next_sum = builder.value(next_sum);
+    let next_i = builder.inst().add(i32, i_val, one);
+    let next_i = builder.value(next_i);
+    builder.inst().br(header);
+
+    builder.switch_to_block(exit);
+    builder.inst().ret(sum_val);
+
+    builder.inst().add_incoming(i, zero, entry);
+    builder.inst().add_incoming(i, next_i, body);
+    builder.inst().add_incoming(sum, zero, entry);
+    builder.inst().add_incoming(sum, next_sum, body);
+
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn build_memory_and_calls() {
+    let mut module = module::Module::default();
+    let (i32, i64, void) = (
+        module.types.base().i32(),
+        module.types.base().i64(),
+        module.types.base().void(),
+    );
+    let (pair, array, fn_ty) = {
+        let mut types = module.types.base_mut();
+        let pair = types.anonymous_struct(vec![i32, i64], false);
+        let array = types.array(pair, 4);
+        let fn_ty = types.function(i64, vec![i64], false);
+        (pair, array, fn_ty)
+    };
+    let param = |ty| Parameter {
+        name: Name::Name("x".to_string()),
+        ty,
+        attrs: vec![],
+    };
+    module.create_function("id", i64, vec![param(i64)], false);
+    let func = module.create_function("func", void, vec![], false);
+    let func = &mut module.functions_mut()[func];
+    let mut builder = function::builder::Builder::new(func);
+    let entry = builder.create_block();
+    builder.switch_to_block(entry);
+    let (zero, two) = (builder.value(0i32), builder.value(2i32));
+    let callee = builder.value(Value::Constant(ConstantData::GlobalRef(Name::Name(
+        "id".to_string(),
+    ))));
+
+    let alloca = builder.inst().alloca(array);
+    let ary = builder.value(alloca);
+    let elem = builder
+        .inst()
+        .gep(array, ary, &[(i32, zero), (i32, two)], true);
+    let elem = builder.value(elem);
+    let loaded = builder.inst().load(pair, elem);
+    let loaded = builder.value(loaded);
+    let field = builder.inst().extract_value(pair, loaded, &[1]);
+    let field = builder.value(field);
+    let called = builder.inst().call(fn_ty, callee, &[field]);
+    let called = builder.value(called);
+    let truncated = builder.inst().trunc(i64, called, i32);
+    let truncated = builder.value(truncated);
+    let updated = builder
+        .inst()
+        .insert_value(pair, loaded, i32, truncated, &[0]);
+    let updated = builder.value(updated);
+    let store = builder.inst().store(pair, updated, elem);
+    builder.inst().ret_void();
+
+    // Instructions built after the insertion point is moved go in order before `store`.
+    builder.set_insert_point_before(store);
+    let mul = builder.inst().mul(i32, truncated, two);
+    let mul = builder.value(mul);
+    builder.inst().sext(i32, mul, i64);
+    // ...and after `ary`.
+    builder.set_insert_point_after(alloca);
+    builder.inst().alloca(i32);
+    builder.inst().alloca(i64);
+
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+#[should_panic(expected = "expected a value of i32, but got i64")]
+fn build_mismatched_operand() {
+    let mut module = module::Module::default();
+    let (i32, i64) = (module.types.base().i32(), module.types.base().i64());
+    let param = Parameter {
+        name: Name::Name("x".to_string()),
+        ty: i64,
+        attrs: vec![],
+    };
+    let func = module.create_function("func", i32, vec![param], false);
+    let func = &mut module.functions_mut()[func];
+    let mut builder = function::builder::Builder::new(func);
+    let entry = builder.create_block();
+    builder.switch_to_block(entry);
+    let x = builder.value(Value::Argument(0));
+    builder.inst().add(i32, x, x);
+}
+
+#[test]
+#[should_panic(expected = "call with 2 arguments doesn't match the callee's type")]
+fn build_mismatched_call() {
+    let mut module = module::Module::default();
+    let i32 = module.types.base().i32();
+    let fn_ty = module.types.base_mut().function(i32, vec![i32], false);
+    let func = module.create_function("func", i32, vec![], false);
+    let func = &mut module.functions_mut()[func];
+    let mut builder = function::builder::Builder::new(func);
+    let entry = builder.create_block();
+    builder.switch_to_block(entry);
+    let one = builder.value(1i32);
+    let callee = builder.value(Value::Constant(ConstantData::GlobalRef(Name::Name(
+        "func".to_string(),
+    ))));
+    builder.inst().call(fn_ty, callee, &[one, one]);
+}
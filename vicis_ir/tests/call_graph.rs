@@ -0,0 +1,232 @@
+use vicis_ir::{
+    ir::{
+        function::FunctionId,
+        module::{self, Module},
+    },
+    pass::{
+        analysis::call_graph::{CallGraph, CallGraphAnalysis, CallGraphNode, CallSite},
+        AnalysisManager,
+    },
+};
+
+const SRC: &str = r#"
+declare i32 @ext(i32)
+
+define internal i32 @leaf(i32 %x) {
+entry:
+  ret i32 %x
+}
+
+define internal i32 @even(i32 %n) {
+entry:
+  %c = icmp eq i32 %n, 0
+  br i1 %c, label %done, label %rec
+
+rec:
+  %m = sub i32 %n, 1
+  %r = call i32 @odd(i32 %m)
+  ret i32 %r
+
+done:
+  ret i32 1
+}
+
+define internal i32 @odd(i32 %n) {
+entry:
+  %r = call i32 @even(i32 %n)
+  %l = call i32 @leaf(i32 %r)
+  ret i32 %l
+}
+
+define internal i32 @fact(i32 %n) {
+entry:
+  %m = sub i32 %n, 1
+  %r = call i32 @fact(i32 %m)
+  ret i32 %r
+}
+
+define i32 @main(i32 (i32)* %fp) {
+entry:
+  %a = call i32 @odd(i32 3)
+  %b = call i32 %fp(i32 %a)
+  %c = call i32 @ext(i32 %b)
+  %d = call i32 @fact(i32 %c)
+  ret i32 %d
+}
+"#;
+
+fn id(module: &Module, name: &str) -> FunctionId {
+    module.find_function_by_name(name).unwrap()
+}
+
+fn names(module: &Module, sccs: &[Vec<FunctionId>]) -> Vec<Vec<String>> {
+    sccs.iter()
+        .map(|scc| {
+            scc.iter()
+                .map(|&f| module.functions()[f].name().clone())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn calls_and_callers() {
+    let module = module::parse_assembly(SRC).unwrap();
+    let graph = CallGraph::new(&module);
+    let (ext, leaf, odd, fact, main) = (
+        id(&module, "ext"),
+        id(&module, "leaf"),
+        id(&module, "odd"),
+        id(&module, "fact"),
+        id(&module, "main"),
+    );
+
+    let callees: Vec<CallGraphNode> = graph
+        .calls_of(CallGraphNode::Function(main))
+        .iter()
+        .map(|c| c.callee)
+        .collect();
+    assert_eq!(
+        callees,
+        vec![
+            CallGraphNode::Function(odd),
+            CallGraphNode::External,
+            CallGraphNode::Function(ext),
+            CallGraphNode::Function(fact),
+        ]
+    );
+    assert!(graph.calls_of(CallGraphNode::Function(leaf)).is_empty());
+    // A declared function may call anything.
+    assert_eq!(
+        graph.calls_of(CallGraphNode::Function(ext)),
+        &[CallSite {
+            inst: None,
+            callee: CallGraphNode::External
+        }]
+    );
+
+    let callers: Vec<CallGraphNode> = graph
+        .callers_of(CallGraphNode::External)
+        .iter()
+        .map(|&(f, _)| f)
+        .collect();
+    assert_eq!(
+        callers,
+        vec![CallGraphNode::Function(ext), CallGraphNode::Function(main)]
+    );
+    assert_eq!(graph.callers_of(CallGraphNode::Function(leaf)).len(), 1);
+    // Only `External` calls the functions visible outside the module.
+    assert_eq!(
+        graph.callers_of(CallGraphNode::Function(main)),
+        &[(CallGraphNode::External, None)]
+    );
+    assert_eq!(
+        graph.calls_of(CallGraphNode::External),
+        &[
+            CallSite {
+                inst: None,
+                callee: CallGraphNode::Function(ext)
+            },
+            CallSite {
+                inst: None,
+                callee: CallGraphNode::Function(main)
+            },
+        ]
+    );
+}
+
+#[test]
+fn address_taken() {
+    let module = module::parse_assembly(
+        r#"
+@table = internal constant [1 x i32 (i32)*] [i32 (i32)* @in_table]
+
+define internal i32 @in_table(i32 %x) {
+entry:
+  ret i32 %x
+}
+
+define internal i32 @stored(i32 %x) {
+entry:
+  ret i32 %x
+}
+
+define internal i32 @called(i32 %x) {
+entry:
+  ret i32 %x
+}
+
+define internal void @f(i32 (i32)** %p) {
+entry:
+  store i32 (i32)* @stored, i32 (i32)** %p
+  %r = call i32 @called(i32 1)
+  ret void
+}
+"#,
+    )
+    .unwrap();
+    let graph = CallGraph::new(&module);
+    let external = |name| {
+        graph
+            .callers_of(CallGraphNode::Function(id(&module, name)))
+            .contains(&(CallGraphNode::External, None))
+    };
+    assert!(external("in_table"));
+    assert!(external("stored"));
+    assert!(!external("called"));
+    assert!(!external("f"));
+}
+
+#[test]
+fn address_taken_vtable() {
+    let module =
+        module::parse_assembly(&std::fs::read_to_string("./examples/rustc_minimum.ll").unwrap())
+            .unwrap();
+    let graph = CallGraph::new(&module);
+    let external = |name| {
+        graph
+            .callers_of(CallGraphNode::Function(id(&module, name)))
+            .contains(&(CallGraphNode::External, None))
+    };
+    // Entries of `@vtable.0`
+    assert!(external(
+        "_ZN4core3ops8function6FnOnce40call_once$u7b$$u7b$vtable.shim$u7d$$u7d$17h725d331cbdf52b97E"
+    ));
+    assert!(external(
+        "_ZN3std2rt10lang_start28_$u7b$$u7b$closure$u7d$$u7d$17hc67b0eba1738ef88E"
+    ));
+    // The personality function and `main` are visible outside the module
+    assert!(external("rust_eh_personality"));
+    assert!(external("main"));
+    assert!(!external("_ZN4core4hint9black_box17h9ef81826e68d7cfcE"));
+}
+
+#[test]
+fn bottom_up_sccs() {
+    let module = module::parse_assembly(SRC).unwrap();
+    let graph = CallGraph::new(&module);
+
+    assert_eq!(
+        names(&module, graph.sccs()),
+        vec![
+            vec!["ext"],
+            vec!["leaf"],
+            vec!["even", "odd"],
+            vec!["fact"],
+            vec!["main"],
+        ]
+    );
+    assert!(graph.is_recursive(id(&module, "even")));
+    assert!(graph.is_recursive(id(&module, "fact")));
+    assert!(!graph.is_recursive(id(&module, "leaf")));
+    assert!(!graph.is_recursive(id(&module, "main")));
+}
+
+#[test]
+fn module_analysis() {
+    let module = module::parse_assembly(SRC).unwrap();
+    let mut am = AnalysisManager::new();
+    let graph = am.module().get::<CallGraphAnalysis>(&module);
+    assert_eq!(graph.sccs().len(), 5);
+    assert!(am.module().get_cached::<CallGraphAnalysis>().is_some());
+}
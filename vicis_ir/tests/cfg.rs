@@ -0,0 +1,265 @@
+use vicis_ir::{
+    ir::{
+        function::{basic_block::BasicBlockId, instruction::InstructionId, Function},
+        module::{self, Module},
+    },
+    pass::analysis::verifier::verify_module,
+};
+
+fn parse(src: &str) -> Module {
+    module::parse_assembly(src).unwrap()
+}
+
+fn blocks(func: &Function) -> Vec<BasicBlockId> {
+    func.layout.block_iter().collect()
+}
+
+fn insts(func: &Function, block: BasicBlockId) -> Vec<InstructionId> {
+    func.layout.inst_iter(block).collect()
+}
+
+fn main_of(module: &mut Module) -> &mut Function {
+    let id = module.find_function_by_name("main").unwrap();
+    &mut module.functions_mut()[id]
+}
+
+#[test]
+fn split_block_at() {
+    let mut module = parse(
+        r#"
+define dso_local i32 @main(i32 %n) {
+entry:
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %loop ]
+  %i.next = add nsw i32 %i, 1
+  %c = icmp slt i32 %i.next, %n
+  br i1 %c, label %loop, label %exit
+
+exit:
+  ret i32 %i.next
+}
+"#,
+    );
+    let func = main_of(&mut module);
+    let loop_ = blocks(func)[1];
+    let c = insts(func, loop_)[2];
+    let tail = func.split_block_at(c);
+
+    assert_eq!(blocks(func)[2], tail);
+    assert_eq!(func.layout.block_of(c), Some(tail));
+    assert_eq!(func.data.inst_ref(c).parent, tail);
+    assert!(func.data.block_ref(tail).preds().contains(&loop_));
+    assert!(func.data.block_ref(loop_).preds().contains(&tail));
+    assert_eq!(func.data.block_ref(loop_).succs().len(), 1);
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn split_critical_edges() {
+    let mut module = parse(
+        r#"
+define dso_local i32 @main(i1 %c, i32 %x) {
+entry:
+  br i1 %c, label %then, label %merge
+
+then:
+  br label %merge
+
+merge:
+  %p = phi i32 [ 0, %entry ], [ %x, %then ]
+  switch i32 %p, label %merge2 [
+    i32 1, label %merge2
+    i32 2, label %exit
+  ]
+
+merge2:
+  br label %exit
+
+exit:
+  ret i32 %p
+}
+"#,
+    );
+    let func = main_of(&mut module);
+    let new_blocks = func.split_critical_edges();
+
+    // entry -> merge and merge -> exit. Both cases of the switch to `merge2` are fine since
+    // `merge2` has one predecessor.
+    assert_eq!(new_blocks.len(), 2);
+    for block in new_blocks {
+        assert_eq!(func.data.block_ref(block).preds().len(), 1);
+        assert_eq!(func.data.block_ref(block).succs().len(), 1);
+    }
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn split_critical_edges_duplicate() {
+    let mut module = parse(
+        r#"
+define dso_local i32 @main(i32 %x) {
+entry:
+  switch i32 %x, label %a [
+    i32 0, label %b
+    i32 1, label %b
+  ]
+
+a:
+  br label %b
+
+b:
+  %p = phi i32 [ 1, %entry ], [ 1, %entry ], [ 2, %a ]
+  ret i32 %p
+}
+"#,
+    );
+    let func = main_of(&mut module);
+    let b = blocks(func)[2];
+    let new_blocks = func.split_critical_edges();
+
+    // Both cases go to the one new block, so `%p` takes a value from it once.
+    assert_eq!(new_blocks.len(), 1);
+    let p = insts(func, b)[0];
+    assert_eq!(
+        func.data.inst_ref(p).operand.as_phi().unwrap().blocks.len(),
+        2
+    );
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn split_critical_edges_landing_pad() {
+    let mut module = parse(
+        r#"
+define dso_local i32 @main() personality i32 (...)* @__gxx_personality_v0 {
+entry:
+  invoke void @f()
+          to label %next unwind label %lpad
+
+next:
+  invoke void @f()
+          to label %exit unwind label %lpad
+
+exit:
+  ret i32 0
+
+lpad:
+  %p = phi i32 [ 1, %entry ], [ 2, %next ]
+  %l = landingpad { i8*, i32 } cleanup
+  ret i32 %p
+}
+
+declare void @f()
+
+declare i32 @__gxx_personality_v0(...)
+"#,
+    );
+    let func = main_of(&mut module);
+    assert!(func.split_critical_edges().is_empty());
+    assert!(verify_module(&module).is_empty());
+}
+
+#[test]
+fn merge_into_single_pred() {
+    let mut module = parse(
+        r#"
+define dso_local i32 @main(i1 %c, i32 %x) {
+entry:
+  %a = add i32 %x, 1
+  br label %next
+
+next:
+  %p = phi i32 [ %a, %entry ]
+  %b = add i32 %p, 2
+  br i1 %c, label %next, label %exit
+
+exit:
+  ret i32 %b
+}
+"#,
+    );
+    let func = main_of(&mut module);
+    let (entry, next) = (blocks(func)[0], blocks(func)[1]);
+
+    // `next` has two predecessors, itself and `entry`.
+    assert!(!func.merge_into_single_pred(next));
+    let exit = blocks(func)[2];
+    assert!(!func.merge_into_single_pred(exit));
+
+    func.redirect_edge(next, next, exit);
+    assert!(func.merge_into_single_pred(next));
+    assert_eq!(blocks(func), vec![entry, exit]);
+    assert!(func.data.block_ref(exit).preds().contains(&entry));
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn redirect_edge() {
+    let mut module = parse(
+        r#"
+define dso_local i32 @main(i1 %c) {
+entry:
+  br i1 %c, label %a, label %b
+
+a:
+  br label %b
+
+b:
+  %p = phi i32 [ 1, %entry ], [ 2, %a ]
+  ret i32 %p
+}
+"#,
+    );
+    let func = main_of(&mut module);
+    let (entry, a, b) = (blocks(func)[0], blocks(func)[1], blocks(func)[2]);
+    func.redirect_edge(entry, b, a);
+
+    assert!(!func.data.block_ref(b).preds().contains(&entry));
+    assert!(func.data.block_ref(a).preds().contains(&entry));
+    assert_eq!(func.data.block_ref(entry).succs().len(), 1);
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn remove_unreachable_blocks() {
+    let mut module = parse(
+        r#"
+define dso_local i32 @main() {
+entry:
+  br label %exit
+
+dead:
+  %a = add i32 1, 2
+  br label %dead2
+
+dead2:
+  %b = add i32 %a, 1
+  br i1 true, label %dead, label %exit
+
+exit:
+  %p = phi i32 [ 0, %entry ], [ %b, %dead2 ]
+  ret i32 %p
+}
+"#,
+    );
+    let func = main_of(&mut module);
+    let (entry, dead, dead2, exit) = {
+        let b = blocks(func);
+        (b[0], b[1], b[2], b[3])
+    };
+    let b = insts(func, dead2)[0];
+
+    assert_eq!(func.remove_unreachable_blocks(), vec![dead, dead2]);
+    assert_eq!(blocks(func), vec![entry, exit]);
+    assert_eq!(func.data.block_ref(exit).preds().len(), 1);
+    assert!(func.data.users_of(b).is_empty());
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
@@ -0,0 +1,59 @@
+use vicis_ir::ir::{module, pass::dce};
+
+#[test]
+fn dce_switch_select() {
+    let ir = r#"
+define dso_local i32 @f(i32 %0, i1 %1) {
+  %3 = select i1 %1, i32 %0, i32 1
+  %4 = add i32 %3, 2
+  switch i32 %0, label %6 [
+    i32 0, label %5
+  ]
+
+5:                                                ; preds = %2
+  ret i32 %0
+
+6:                                                ; preds = %2
+  unreachable
+}
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    dce::run_on_module(&mut module);
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn dce_debug_value() {
+    let ir = r#"
+define dso_local i32 @f(i32 %0) {
+  %2 = add i32 %0, 2
+  call void @llvm.dbg.value(metadata i32 %2, metadata !{}, metadata !DIExpression())
+  ret i32 %0
+}
+
+declare void @llvm.dbg.value(metadata, metadata, metadata)
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    dce::run_on_module(&mut module);
+    let printed = format!("{:?}", module);
+    assert!(!printed.contains("add"));
+    assert!(printed.contains("@llvm.dbg.value(metadata i32 undef"));
+}
+
+#[test]
+fn dce_atomic_volatile() {
+    let ir = r#"
+define dso_local i32 @f(i32* %0) {
+  fence seq_cst
+  %2 = atomicrmw add i32* %0, i32 1 seq_cst
+  %3 = cmpxchg i32* %0, i32 0, i32 1 acq_rel monotonic
+  %4 = load volatile i32, i32* %0, align 4
+  %5 = load atomic i32, i32* %0 acquire, align 4
+  %6 = load i32, i32* %0, align 4
+  ret i32 0
+}
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    dce::run_on_module(&mut module);
+    insta::assert_debug_snapshot!(module);
+}
@@ -0,0 +1,214 @@
+use vicis_ir::{
+    ir::{
+        function::{self, data::Use, instruction::InstructionId, Function, Parameter},
+        module::{self, name::Name, Module},
+        value::{ConstantData, ConstantInt, Value},
+    },
+    pass::analysis::verifier::verify_module,
+};
+
+const SRC: &str = r#"
+@g = global i32 0
+
+define i32 @f(i32 %x, i32 %y) {
+entry:
+  %a = add i32 %x, %y
+  %b = mul i32 %x, %a
+  store i32 %b, i32* @g
+  %c = load i32, i32* getelementptr (i32, i32* @g, i64 0)
+  ret i32 %c
+}
+"#;
+
+fn insts(func: &Function) -> Vec<InstructionId> {
+    let entry = func.layout.block_iter().next().unwrap();
+    func.layout.inst_iter(entry).collect()
+}
+
+fn sorted(mut uses: Vec<Use>) -> Vec<Use> {
+    uses.sort_by_key(|u| (u.user.index(), u.index));
+    uses
+}
+
+fn parse() -> Module {
+    module::parse_assembly(SRC).unwrap()
+}
+
+#[test]
+fn uses_of_arguments() {
+    let module = parse();
+    let func = &module.functions()[module.find_function_by_name("f").unwrap()];
+    let insts = insts(func);
+    let x = func.data.inst_ref(insts[0]).operand.args()[0];
+    let y = func.data.inst_ref(insts[0]).operand.args()[1];
+    assert_eq!(func.data.value_ref(x), &Value::Argument(0));
+
+    assert_eq!(
+        sorted(func.data.uses_of(x).collect()),
+        vec![
+            Use {
+                user: insts[0],
+                index: 0
+            },
+            Use {
+                user: insts[1],
+                index: 0
+            }
+        ]
+    );
+    assert_eq!(
+        sorted(func.data.uses_of(y).collect()),
+        vec![Use {
+            user: insts[0],
+            index: 1
+        }]
+    );
+}
+
+#[test]
+fn uses_of_global() {
+    let module = parse();
+    let func = &module.functions()[module.find_function_by_name("f").unwrap()];
+    let insts = insts(func);
+
+    // `@g` itself and through a constant expression
+    assert_eq!(
+        sorted(
+            func.data
+                .uses_of_global(&Name::Name("g".to_string()))
+                .collect()
+        ),
+        vec![
+            Use {
+                user: insts[2],
+                index: 1
+            },
+            Use {
+                user: insts[3],
+                index: 0
+            }
+        ]
+    );
+    assert_eq!(
+        func.data
+            .uses_of_global(&Name::Name("h".to_string()))
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn replace_all_uses_with_argument() {
+    let mut module = parse();
+    let id = module.find_function_by_name("f").unwrap();
+    let func = &mut module.functions_mut()[id];
+    let insts = insts(func);
+    let x = func.data.inst_ref(insts[0]).operand.args()[0];
+    let y = func.data.inst_ref(insts[0]).operand.args()[1];
+
+    func.data.replace_all_uses_with(x, y);
+    assert_eq!(func.data.uses_of(x).count(), 0);
+    assert_eq!(func.data.uses_of(y).count(), 3);
+    assert_eq!(func.data.inst_ref(insts[1]).operand.args()[0], y);
+    assert!(verify_module(&module).is_empty());
+}
+
+#[test]
+fn replace_all_uses_with_constant() {
+    let mut module = parse();
+    let id = module.find_function_by_name("f").unwrap();
+    let func = &mut module.functions_mut()[id];
+    let insts = insts(func);
+    let a = func.data.inst_ref(insts[1]).operand.args()[1];
+    let one = func
+        .data
+        .create_value(Value::Constant(ConstantData::Int(ConstantInt::new(32, 1))));
+
+    // Instructions keep their users in `users_map` too.
+    func.data.replace_all_uses_with(a, one);
+    assert!(func.data.users_of(insts[0]).is_empty());
+    assert_eq!(
+        func.data.uses_of(one).collect::<Vec<_>>(),
+        vec![Use {
+            user: insts[1],
+            index: 1
+        }]
+    );
+    func.remove_inst(insts[0]);
+    assert!(verify_module(&module).is_empty());
+}
+
+#[test]
+fn remove_inst() {
+    let mut module = parse();
+    let id = module.find_function_by_name("f").unwrap();
+    let func = &mut module.functions_mut()[id];
+    let insts = insts(func);
+    let x = func.data.inst_ref(insts[0]).operand.args()[0];
+
+    func.data.replace_all_uses(insts[1], x);
+    func.remove_inst(insts[1]);
+    assert_eq!(
+        sorted(func.data.uses_of(x).collect()),
+        vec![
+            Use {
+                user: insts[0],
+                index: 0
+            },
+            Use {
+                user: insts[2],
+                index: 0
+            }
+        ]
+    );
+    assert!(verify_module(&module).is_empty());
+}
+
+#[test]
+fn uses_of_built_values() {
+    let mut module = Module::default();
+    let i32 = module.types.base().i32();
+    let param = Parameter {
+        name: Name::Name("x".to_string()),
+        ty: i32,
+        attrs: vec![],
+    };
+    let id = module.create_function("f", i32, vec![param], false);
+    let func = &mut module.functions_mut()[id];
+    let mut builder = function::builder::Builder::new(func);
+    let entry = builder.create_block();
+    builder.switch_to_block(entry);
+    // Every operand gets its own `value` call, as in a frontend.
+    let (x0, x1) = (
+        builder.value(Value::Argument(0)),
+        builder.value(Value::Argument(0)),
+    );
+    let a = builder.inst().add(i32, x0, x1);
+    let (a0, a1) = (builder.value(a), builder.value(a));
+    let b = builder.inst().mul(i32, a0, a1);
+    let b = builder.value(b);
+    builder.inst().ret(b);
+
+    let insts = insts(func);
+    assert_eq!(x0, x1);
+    assert_eq!(func.data.uses_of(x0).count(), 2);
+    assert_eq!(
+        sorted(func.data.uses_of(a0).collect()),
+        vec![
+            Use {
+                user: insts[1],
+                index: 0
+            },
+            Use {
+                user: insts[1],
+                index: 1
+            }
+        ]
+    );
+
+    func.data.replace_all_uses_with(a0, x0);
+    assert_eq!(func.data.inst_ref(insts[1]).operand.args(), &[x0, x0]);
+    assert!(func.data.users_of(insts[0]).is_empty());
+    func.remove_inst(insts[0]);
+    assert!(verify_module(&module).is_empty());
+}
@@ -2,7 +2,7 @@ use rustc_hash::FxHashSet;
 use vicis_ir::{
     // codegen::{isa::x86_64::X86_64, lower::compile_module},
     // exec::{generic_value::GenericValue, interpreter::Interpreter},
-    ir::{function::basic_block::BasicBlockId, module},
+    ir::{function::basic_block::BasicBlockId, module, module::name::Name},
     pass::analysis::dom_tree::DominatorTree,
 };
 
@@ -84,3 +84,56 @@ define dso_local i32 @main() {
         );
     }
 }
+
+#[test]
+fn dom_switch() {
+    let src = r#"
+define dso_local i32 @f(i32 %0) {
+  switch i32 %0, label %4 [
+    i32 0, label %2
+    i32 1, label %3
+    i32 2, label %3
+  ]
+
+2:                                                ; preds = %1
+  br label %5
+
+3:                                                ; preds = %1, %1
+  br label %5
+
+4:                                                ; preds = %1
+  br label %5
+
+5:                                                ; preds = %4, %3, %2
+  ret i32 0
+}
+        "#;
+
+    let module = module::parse_assembly(src).unwrap();
+
+    for (_, func) in module.functions() {
+        let block = |n: usize| {
+            func.data
+                .basic_blocks
+                .iter()
+                .find(|(_, b)| b.name == Some(Name::Number(n)))
+                .unwrap()
+                .0
+        };
+        let entry = func.layout.first_block.unwrap();
+        let dom_tree = DominatorTree::new(func);
+
+        for n in 2..=5 {
+            assert!(dom_tree.dominates(entry, block(n)));
+        }
+        for (x, y) in [(2, 3), (3, 4), (4, 5), (2, 5), (3, 5)] {
+            assert!(!dom_tree.dominates(block(x), block(y)));
+        }
+        for n in 2..=4 {
+            assert!(
+                dom_tree.dominance_frontier_of(block(n))
+                    == Some(&vec![block(5)].into_iter().collect::<FxHashSet<_>>())
+            );
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use vicis_ir::{
+    ir::{function::basic_block::BasicBlockId, module},
+    pass::{
+        analysis::{
+            dom_tree::DominatorTree,
+            loop_info::{LoopInfo, LoopInfoAnalysis},
+        },
+        AnalysisManager,
+    },
+};
+
+#[test]
+fn nested_loops() {
+    let src = r#"
+define dso_local i32 @main(i32 %n) {
+entry:
+  br label %outer
+
+outer:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %outer.latch ]
+  br label %inner
+
+inner:
+  %j = phi i32 [ 0, %outer ], [ %j.next, %inner ]
+  %j.next = add nsw i32 %j, 1
+  %c1 = icmp slt i32 %j.next, %n
+  br i1 %c1, label %inner, label %outer.latch
+
+outer.latch:
+  %i.next = add nsw i32 %i, 1
+  %c2 = icmp slt i32 %i.next, %n
+  br i1 %c2, label %outer, label %exit
+
+exit:
+  ret i32 %i.next
+}
+        "#;
+    let module = module::parse_assembly(src).unwrap();
+    let func = &module.functions()[module.find_function_by_name("main").unwrap()];
+    let blocks: Vec<BasicBlockId> = func.layout.block_iter().collect();
+    let (entry, outer, inner, outer_latch, exit) =
+        (blocks[0], blocks[1], blocks[2], blocks[3], blocks[4]);
+    let loop_info = LoopInfo::new(func, &DominatorTree::new(func));
+
+    assert_eq!(loop_info.loops().count(), 2);
+    assert_eq!(loop_info.top_level_loops().len(), 1);
+
+    let outer_loop = loop_info.loop_of(outer).unwrap();
+    let l = loop_info.get(outer_loop);
+    assert_eq!(l.header(), outer);
+    assert_eq!(l.latches(), &[outer_latch]);
+    assert_eq!(l.blocks(), &[outer, inner, outer_latch]);
+    assert_eq!(l.exiting_blocks(), &[outer_latch]);
+    assert_eq!(l.exit_blocks(), &[exit]);
+    assert_eq!(l.preheader(), Some(entry));
+    assert_eq!(l.parent(), None);
+    assert_eq!(l.depth(), 1);
+
+    let inner_loop = loop_info.loop_of(inner).unwrap();
+    let l = loop_info.get(inner_loop);
+    assert_eq!(l.header(), inner);
+    assert_eq!(l.latches(), &[inner]);
+    assert_eq!(l.blocks(), &[inner]);
+    assert_eq!(l.exiting_blocks(), &[inner]);
+    assert_eq!(l.exit_blocks(), &[outer_latch]);
+    // `outer` is the only way in and goes nowhere else.
+    assert_eq!(l.preheader(), Some(outer));
+    assert_eq!(l.parent(), Some(outer_loop));
+    assert_eq!(loop_info.get(outer_loop).children(), &[inner_loop]);
+    assert_eq!(l.depth(), 2);
+
+    assert_eq!(
+        loop_info.loops_innermost_first(),
+        vec![inner_loop, outer_loop]
+    );
+    assert_eq!(loop_info.depth_of(entry), 0);
+    assert_eq!(loop_info.depth_of(outer), 1);
+    assert_eq!(loop_info.depth_of(inner), 2);
+    assert_eq!(loop_info.depth_of(outer_latch), 1);
+    assert_eq!(loop_info.depth_of(exit), 0);
+    assert!(loop_info.is_loop_header(inner));
+    assert!(!loop_info.is_loop_header(outer_latch));
+}
+
+#[test]
+fn multiple_latches_and_exits() {
+    let src = r#"
+define dso_local i32 @main(i32 %n) {
+entry:
+  %c0 = icmp slt i32 0, %n
+  br i1 %c0, label %header, label %exit1
+
+header:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %body ], [ %i.next, %latch ]
+  %i.next = add nsw i32 %i, 1
+  %c1 = icmp eq i32 %i, 10
+  br i1 %c1, label %exit2, label %body
+
+body:
+  %c2 = icmp slt i32 %i.next, %n
+  br i1 %c2, label %header, label %latch
+
+latch:
+  %c3 = icmp slt i32 %i.next, 100
+  br i1 %c3, label %header, label %exit1
+
+exit1:
+  ret i32 0
+
+exit2:
+  ret i32 1
+}
+        "#;
+    let module = module::parse_assembly(src).unwrap();
+    let func = &module.functions()[module.find_function_by_name("main").unwrap()];
+    let blocks: Vec<BasicBlockId> = func.layout.block_iter().collect();
+    let (header, body, latch, exit1, exit2) =
+        (blocks[1], blocks[2], blocks[3], blocks[4], blocks[5]);
+    let loop_info = LoopInfo::new(func, &DominatorTree::new(func));
+
+    assert_eq!(loop_info.loops().count(), 1);
+    let l = loop_info.get(loop_info.loop_of(header).unwrap());
+    assert_eq!(l.latches(), &[body, latch]);
+    assert_eq!(l.blocks(), &[header, body, latch]);
+    assert_eq!(l.exiting_blocks(), &[header, latch]);
+    assert_eq!(l.exit_blocks(), &[exit1, exit2]);
+    // `entry` also branches to `exit1`.
+    assert_eq!(l.preheader(), None);
+}
+
+#[test]
+fn loop_info_analysis() {
+    let src = r#"
+define dso_local void @f() {
+entry:
+  br label %loop
+
+loop:
+  br label %loop
+
+dead:
+  br label %dead
+}
+        "#;
+    let module = module::parse_assembly(src).unwrap();
+    let id = module.find_function_by_name("f").unwrap();
+    let func = &module.functions()[id];
+    let blocks: Vec<BasicBlockId> = func.layout.block_iter().collect();
+    let mut am = AnalysisManager::new();
+    let loop_info = am.function(id).get::<LoopInfoAnalysis>(func);
+
+    // Unreachable blocks are in no loop.
+    assert_eq!(loop_info.loops().count(), 1);
+    let l = loop_info.get(loop_info.loop_of(blocks[1]).unwrap());
+    assert_eq!(l.latches(), &[blocks[1]]);
+    assert!(l.exit_blocks().is_empty());
+    assert_eq!(loop_info.loop_of(blocks[2]), None);
+}
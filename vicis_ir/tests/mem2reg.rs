@@ -1,4 +1,11 @@
-use vicis_ir::{ir::module, pass::transform::mem2reg::Mem2Reg};
+use vicis_ir::{
+    ir::module,
+    pass::{
+        analysis::{dom_tree::DominatorTreeAnalysis, verifier::verify_module},
+        transform::mem2reg::{Mem2Reg, Mem2RegPass},
+        AnalysisManager, PassManager, TransformPass,
+    },
+};
 
 #[test]
 fn mem2reg_1() {
@@ -179,3 +186,127 @@ define dso_local i32 @main() {
     }
     insta::assert_debug_snapshot!(module);
 }
+
+#[test]
+fn mem2reg_switch() {
+    let ir = r#"
+define dso_local i32 @main() {
+  %1 = alloca i32, align 4
+  %2 = alloca i32, align 4
+  store i32 1, i32* %2, align 4
+  %3 = load i32, i32* %2, align 4
+  switch i32 %3, label %6 [
+    i32 0, label %4
+    i32 1, label %5
+  ]
+
+4:                                                ; preds = %0
+  store i32 10, i32* %1, align 4
+  br label %7
+
+5:                                                ; preds = %0
+  store i32 20, i32* %1, align 4
+  br label %7
+
+6:                                                ; preds = %0
+  unreachable
+
+7:                                                ; preds = %5, %4
+  %8 = load i32, i32* %1, align 4
+  %9 = icmp eq i32 %8, 20
+  %10 = select i1 %9, i32 %8, i32 0
+  ret i32 %10
+}
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    for (_, func) in module.functions_mut() {
+        Mem2Reg::new(func).run();
+    }
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn mem2reg_pass() {
+    let ir = r#"
+define dso_local i32 @main(i32 %n) {
+  %1 = alloca i32, align 4
+  store i32 %n, i32* %1, align 4
+  %2 = icmp sgt i32 %n, 0
+  br i1 %2, label %3, label %4
+
+3:                                                ; preds = %0
+  store i32 1, i32* %1, align 4
+  br label %4
+
+4:                                                ; preds = %3, %0
+  %5 = load i32, i32* %1, align 4
+  ret i32 %5
+}
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    let mut pm = PassManager::new();
+    pm.add_function_transform(Mem2RegPass);
+    pm.run_on_module(&mut module, &mut AnalysisManager::new());
+    assert!(verify_module(&module).is_empty());
+    let printed = format!("{:?}", module);
+    assert!(!printed.contains("alloca") && printed.contains("phi i32"));
+}
+
+#[test]
+fn mem2reg_pass_uses_dom_tree_analysis() {
+    let ir = r#"
+define dso_local i32 @main(i32 %n) {
+  %1 = alloca i32, align 4
+  store i32 0, i32* %1, align 4
+  %2 = icmp sgt i32 %n, 0
+  br i1 %2, label %3, label %4
+
+3:
+  store i32 %n, i32* %1, align 4
+  br label %4
+
+4:
+  %5 = load i32, i32* %1, align 4
+  ret i32 %5
+}
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    let id = module.find_function_by_name("main").unwrap();
+    let mut am = AnalysisManager::new();
+    Mem2RegPass.run_on(&mut module.functions_mut()[id], &mut am.function(id));
+    assert!(am
+        .function(id)
+        .get_cached::<DominatorTreeAnalysis>()
+        .is_some());
+    assert!(verify_module(&module).is_empty());
+    assert!(format!("{:?}", module).contains("phi i32"));
+}
+
+#[test]
+fn mem2reg_debug_value() {
+    let ir = r#"
+define dso_local i32 @main(i32 %n) {
+  %1 = alloca i32, align 4
+  call void @llvm.dbg.declare(metadata i32* %1, metadata !{}, metadata !DIExpression())
+  store i32 %n, i32* %1, align 4
+  %2 = load i32, i32* %1, align 4
+  call void @llvm.dbg.value(metadata i32 %2, metadata !{}, metadata !DIExpression())
+  ret i32 %2
+}
+
+declare void @llvm.dbg.declare(metadata, metadata, metadata)
+
+declare void @llvm.dbg.value(metadata, metadata, metadata)
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    for (_, func) in module.functions_mut() {
+        if func.is_prototype() {
+            continue;
+        }
+        Mem2Reg::new(func).run();
+    }
+    let printed = format!("{:?}", module);
+    assert!(!printed.contains("alloca"));
+    assert!(printed.contains("@llvm.dbg.declare(metadata i32* undef"));
+    assert!(printed.contains("@llvm.dbg.value(metadata i32 %n"));
+}
@@ -0,0 +1,200 @@
+use std::fs;
+use vicis_ir::{
+    ir::{function::basic_block::BasicBlockId, module},
+    pass::{
+        analysis::{
+            control_dependence::{ControlDependenceAnalysis, ControlDependenceGraph},
+            post_dom_tree::PostDominatorTree,
+        },
+        AnalysisManager,
+    },
+};
+
+#[test]
+fn examples() {
+    for entry in fs::read_dir("./examples").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "ll") {
+            continue;
+        }
+        let module = module::parse_assembly(&fs::read_to_string(&path).unwrap()).unwrap();
+        for (_, func) in module.functions() {
+            if func.is_prototype() {
+                continue;
+            }
+            let post_dom_tree = PostDominatorTree::new(func);
+            for block in func.layout.block_iter() {
+                let succs = func.data.block_ref(block).succs();
+                // A block that can only go to one place is post-dominated by it first.
+                if succs.len() == 1 && !post_dom_tree.exits().contains(&block) {
+                    let succ = succs.iter().next().copied();
+                    assert_eq!(post_dom_tree.ipdom_of(block), succ, "{}", path.display());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn if_else() {
+    let src = r#"
+define dso_local i32 @main(i1 %c) {
+entry:
+  br i1 %c, label %then, label %else
+
+then:
+  br label %merge
+
+else:
+  br label %merge
+
+merge:
+  ret i32 0
+}
+        "#;
+    let module = module::parse_assembly(src).unwrap();
+    let func = &module.functions()[module.find_function_by_name("main").unwrap()];
+    let blocks: Vec<BasicBlockId> = func.layout.block_iter().collect();
+    let (entry, then, else_, merge) = (blocks[0], blocks[1], blocks[2], blocks[3]);
+    let post_dom_tree = PostDominatorTree::new(func);
+
+    assert_eq!(post_dom_tree.exits(), &[merge]);
+    assert_eq!(post_dom_tree.ipdom_of(merge), None);
+    assert_eq!(post_dom_tree.ipdom_of(entry), Some(merge));
+    assert_eq!(post_dom_tree.ipdom_of(then), Some(merge));
+    assert_eq!(post_dom_tree.ipdom_of(else_), Some(merge));
+    assert_eq!(post_dom_tree.children_of(None), &[merge]);
+    assert_eq!(
+        post_dom_tree.children_of(Some(merge)),
+        &[entry, then, else_]
+    );
+    assert!(post_dom_tree.post_dominates(merge, entry));
+    assert!(post_dom_tree.post_dominates(entry, entry));
+    assert!(!post_dom_tree.post_dominates(then, entry));
+
+    let cdg = ControlDependenceGraph::new(func, &post_dom_tree);
+    assert_eq!(cdg.dependences_of(then), &[entry]);
+    assert_eq!(cdg.dependences_of(else_), &[entry]);
+    assert_eq!(cdg.dependents_of(entry), &[then, else_]);
+    assert!(cdg.dependences_of(entry).is_empty());
+    assert!(cdg.dependences_of(merge).is_empty());
+}
+
+#[test]
+fn multiple_exits() {
+    let src = r#"
+define dso_local i32 @main(i1 %c, i1 %d) {
+entry:
+  br i1 %c, label %a, label %ret1
+
+a:
+  br i1 %d, label %ret1, label %ret2
+
+ret1:
+  ret i32 1
+
+ret2:
+  ret i32 2
+}
+        "#;
+    let module = module::parse_assembly(src).unwrap();
+    let func = &module.functions()[module.find_function_by_name("main").unwrap()];
+    let blocks: Vec<BasicBlockId> = func.layout.block_iter().collect();
+    let (entry, a, ret1, ret2) = (blocks[0], blocks[1], blocks[2], blocks[3]);
+    let post_dom_tree = PostDominatorTree::new(func);
+
+    assert_eq!(post_dom_tree.exits(), &[ret1, ret2]);
+    // Only the virtual exit post-dominates the blocks that can go either way.
+    assert_eq!(post_dom_tree.ipdom_of(entry), None);
+    assert_eq!(post_dom_tree.ipdom_of(a), None);
+    assert_eq!(post_dom_tree.children_of(None), &[entry, a, ret1, ret2]);
+    assert!(!post_dom_tree.post_dominates(ret1, entry));
+
+    let cdg = ControlDependenceGraph::new(func, &post_dom_tree);
+    assert_eq!(cdg.dependences_of(a), &[entry]);
+    assert_eq!(cdg.dependences_of(ret1), &[entry, a]);
+    assert_eq!(cdg.dependences_of(ret2), &[a]);
+}
+
+#[test]
+fn infinite_loop() {
+    let src = r#"
+define dso_local void @main(i1 %c) {
+entry:
+  br i1 %c, label %loop, label %exit
+
+loop:
+  br label %loop.body
+
+loop.body:
+  br label %loop
+
+exit:
+  ret void
+}
+        "#;
+    let module = module::parse_assembly(src).unwrap();
+    let func = &module.functions()[module.find_function_by_name("main").unwrap()];
+    let blocks: Vec<BasicBlockId> = func.layout.block_iter().collect();
+    let (entry, loop_, body, exit) = (blocks[0], blocks[1], blocks[2], blocks[3]);
+    let post_dom_tree = PostDominatorTree::new(func);
+
+    // The loop never reaches `exit`, so one of its blocks is connected to the virtual exit.
+    assert_eq!(post_dom_tree.exits(), &[exit, body]);
+    assert_eq!(post_dom_tree.ipdom_of(body), None);
+    assert_eq!(post_dom_tree.ipdom_of(loop_), Some(body));
+    assert_eq!(post_dom_tree.ipdom_of(entry), None);
+
+    let cdg = ControlDependenceGraph::new(func, &post_dom_tree);
+    assert_eq!(cdg.dependences_of(loop_), &[entry]);
+    assert_eq!(cdg.dependences_of(body), &[entry]);
+    assert_eq!(cdg.dependences_of(exit), &[entry]);
+}
+
+#[test]
+fn loop_control_dependence() {
+    let src = r#"
+define dso_local i32 @main(i32 %n) {
+entry:
+  br label %header
+
+header:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %latch ]
+  %c = icmp slt i32 %i, %n
+  br i1 %c, label %body, label %exit
+
+body:
+  %d = icmp eq i32 %i, 5
+  br i1 %d, label %skip, label %latch
+
+skip:
+  br label %latch
+
+latch:
+  %i.next = add nsw i32 %i, 1
+  br label %header
+
+exit:
+  ret i32 %i
+}
+        "#;
+    let module = module::parse_assembly(src).unwrap();
+    let id = module.find_function_by_name("main").unwrap();
+    let func = &module.functions()[id];
+    let blocks: Vec<BasicBlockId> = func.layout.block_iter().collect();
+    let (entry, header, body, skip, latch, exit) = (
+        blocks[0], blocks[1], blocks[2], blocks[3], blocks[4], blocks[5],
+    );
+    let mut am = AnalysisManager::new();
+    let cdg = am.function(id).get::<ControlDependenceAnalysis>(func);
+
+    assert!(cdg.dependences_of(entry).is_empty());
+    // The header runs again only if the loop's condition holds.
+    assert_eq!(cdg.dependences_of(header), &[header]);
+    assert_eq!(cdg.dependences_of(body), &[header]);
+    assert_eq!(cdg.dependences_of(skip), &[body]);
+    assert_eq!(cdg.dependences_of(latch), &[header]);
+    assert!(cdg.dependences_of(exit).is_empty());
+    assert!(cdg.is_dependent(skip, body));
+    assert!(!cdg.is_dependent(latch, body));
+}
@@ -0,0 +1,82 @@
+use vicis_ir::{
+    ir::module,
+    pass::{analysis::verifier::verify_module, transform::sccp::SCCP},
+};
+
+#[test]
+fn sccp_vector() {
+    let ir = r#"
+define dso_local i1 @f() {
+  %1 = add <4 x i32> <i32 1, i32 2, i32 3, i32 -1>, splat (i32 2147483647)
+  %2 = shl <4 x i32> %1, <i32 1, i32 1, i32 1, i32 1>
+  %3 = icmp slt <4 x i32> %2, zeroinitializer
+  %4 = icmp eq <4 x i32> %1, <i32 -2147483648, i32 0, i32 0, i32 0>
+  %5 = extractelement <4 x i1> %4, i32 0
+  ret i1 %5
+}
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    for (_, func) in module.functions_mut() {
+        SCCP::new(func).run();
+    }
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn sccp_poison() {
+    let ir = r#"
+@g = global i32 0
+
+define dso_local i32 @f(i32 %x) {
+  %1 = shl i32 1, 32
+  %2 = add i32 %1, 1
+  %3 = freeze i32 %2
+  %4 = freeze i32 poison
+  %5 = icmp eq i32 %4, 0
+  %6 = select i1 %5, i32 %3, i32 %x
+  %7 = select i1 poison, i32 %x, i32 1
+  %8 = add i32 %6, 1
+  %9 = icmp eq i32 %7, %8
+  %10 = zext i1 %9 to i32
+  %11 = add i32 %8, %x
+  store i32 %10, i32* @g
+  ret i32 %11
+}
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    for (_, func) in module.functions_mut() {
+        SCCP::new(func).run();
+    }
+    insta::assert_debug_snapshot!(module);
+}
+
+#[test]
+fn sccp_switch_phi() {
+    let ir = r#"
+define dso_local i32 @f() {
+entry:
+  switch i32 1, label %d [
+    i32 0, label %a
+    i32 1, label %b
+    i32 2, label %b
+  ]
+
+a:
+  br label %b
+
+b:
+  %p = phi i32 [ 1, %entry ], [ 1, %entry ], [ 2, %a ]
+  ret i32 %p
+
+d:
+  %q = phi i32 [ 5, %entry ]
+  ret i32 %q
+}
+"#;
+    let mut module = module::parse_assembly(ir).expect("failed to parse ir");
+    for (_, func) in module.functions_mut() {
+        SCCP::new(func).run();
+    }
+    assert!(verify_module(&module).is_empty());
+    insta::assert_debug_snapshot!(module);
+}
@@ -0,0 +1,275 @@
+use std::fs;
+use vicis_ir::{
+    ir::{function::data::Use, module},
+    pass::analysis::verifier::verify_module,
+};
+
+fn diagnostics(src: &str) -> Vec<String> {
+    let module = module::parse_assembly(src).unwrap();
+    verify_module(&module)
+        .into_iter()
+        .map(|d| d.to_string())
+        .collect()
+}
+
+#[test]
+fn verify_examples() {
+    for dir in &["./examples", "./examples/bitcode"] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "ll") {
+                continue;
+            }
+            let module = module::parse_assembly(&fs::read_to_string(&path).unwrap()).unwrap();
+            let diags = verify_module(&module);
+            assert!(diags.is_empty(), "{}: {:?}", path.display(), diags);
+        }
+    }
+}
+
+#[test]
+fn missing_terminator() {
+    let diags = diagnostics(
+        r#"
+define i32 @f() {
+entry:
+  %a = add i32 1, 2
+}
+"#,
+    );
+    assert_eq!(
+        diags,
+        vec!["@f, %entry: block does not end in a terminator"]
+    );
+}
+
+#[test]
+fn terminator_in_middle() {
+    let diags = diagnostics(
+        r#"
+define void @f() {
+entry:
+  ret void
+  ret void
+}
+"#,
+    );
+    assert_eq!(
+        diags,
+        vec!["@f, %entry, `ret`: terminator in the middle of a block"]
+    );
+}
+
+#[test]
+fn misplaced_phi() {
+    let diags = diagnostics(
+        r#"
+define i32 @f(i1 %c) {
+entry:
+  br i1 %c, label %a, label %b
+a:
+  br label %b
+b:
+  %x = add i32 1, 2
+  %p = phi i32 [ 1, %entry ], [ 2, %a ]
+  ret i32 %p
+}
+"#,
+    );
+    assert_eq!(
+        diags,
+        vec!["@f, %b, %p: phi is not at the start of the block"]
+    );
+}
+
+#[test]
+fn phi_predecessors() {
+    let diags = diagnostics(
+        r#"
+define i32 @f(i1 %c) {
+entry:
+  br i1 %c, label %a, label %b
+a:
+  br label %b
+b:
+  %p = phi i32 [ 1, %entry ]
+  ret i32 %p
+}
+"#,
+    );
+    assert_eq!(
+        diags,
+        vec!["@f, %b, %p: incoming blocks of phi do not match the predecessors of the block"]
+    );
+}
+
+#[test]
+fn phi_duplicate_edges() {
+    let diags = diagnostics(
+        r#"
+define i32 @f(i32 %x) {
+entry:
+  switch i32 %x, label %a [
+    i32 0, label %b
+    i32 1, label %b
+  ]
+a:
+  br label %b
+b:
+  %p = phi i32 [ 1, %entry ], [ 2, %a ]
+  ret i32 %p
+}
+"#,
+    );
+    assert_eq!(
+        diags,
+        vec!["@f, %b, %p: incoming blocks of phi do not match the predecessors of the block"]
+    );
+}
+
+#[test]
+fn def_does_not_dominate_use() {
+    let diags = diagnostics(
+        r#"
+define i32 @f(i1 %c) {
+entry:
+  br i1 %c, label %a, label %b
+a:
+  %x = add i32 1, 2
+  br label %b
+b:
+  %y = add i32 %x, 1
+  %z = add i32 %w, 1
+  %w = add i32 1, 1
+  ret i32 %y
+}
+"#,
+    );
+    assert_eq!(
+        diags,
+        vec![
+            "@f, %b, %y: %x does not dominate this use",
+            "@f, %b, %z: %w does not dominate this use"
+        ]
+    );
+}
+
+#[test]
+fn operand_types() {
+    let diags = diagnostics(
+        r#"
+define i32 @f(i64 %x, float %y) {
+entry:
+  %a = add i32 %x, 1
+  %b = fadd float %y, %y
+  %c = add float %b, %b
+  %d = trunc i64 %x to i128
+  ret float %b
+}
+"#,
+    );
+    assert_eq!(
+        diags,
+        vec![
+            "@f, %entry, %a: %x is i64 but i32 is expected",
+            "@f, %entry, %c: operands must be integers",
+            "@f, %entry, %d: invalid cast from i64 to i128",
+            "@f, %entry, `ret`: returns float from a function returning i32",
+        ]
+    );
+}
+
+#[test]
+fn call_signature() {
+    let diags = diagnostics(
+        r#"
+declare i32 @g(i32)
+
+define void @f() {
+entry:
+  %a = call i32 @g(i32 1, i32 2)
+  %b = call i64 @g(i32 1)
+  %c = call i32 @g(i64 1)
+  ret void
+}
+"#,
+    );
+    assert_eq!(
+        diags,
+        vec![
+            "@f, %entry, %a: call of @g with 2 arguments does not match its signature",
+            "@f, %entry, %b: call of @g returning i32 expects i64",
+            "@f, %entry, %c: argument 0 of @g must be i32",
+        ]
+    );
+}
+
+#[test]
+fn users_map() {
+    let src = r#"
+define i32 @f() {
+entry:
+  %a = add i32 1, 2
+  %b = add i32 %a, 1
+  ret i32 %b
+}
+"#;
+    let mut module = module::parse_assembly(src).unwrap();
+    let id = module.find_function_by_name("f").unwrap();
+    let func = &mut module.functions_mut()[id];
+    let (a, b) = {
+        let mut insts = func
+            .layout
+            .inst_iter(func.layout.block_iter().next().unwrap());
+        (insts.next().unwrap(), insts.next().unwrap())
+    };
+    func.data.users_map.get_mut(&a).unwrap().remove(&b);
+    func.data.users_map.entry(b).or_default().insert(a);
+    let diags: Vec<_> = verify_module(&module)
+        .into_iter()
+        .map(|d| d.to_string())
+        .collect();
+    assert_eq!(
+        diags,
+        vec![
+            "@f, %entry, %a: %b is not recorded as a user",
+            "@f, %entry, %b: %a is recorded as a user but does not use this",
+        ]
+    );
+}
+
+#[test]
+fn use_lists() {
+    let src = r#"
+define i32 @f(i32 %x) {
+entry:
+  %a = add i32 %x, 1
+  %b = add i32 %x, %a
+  ret i32 %b
+}
+"#;
+    let mut module = module::parse_assembly(src).unwrap();
+    let id = module.find_function_by_name("f").unwrap();
+    let func = &mut module.functions_mut()[id];
+    let (a, b) = {
+        let mut insts = func
+            .layout
+            .inst_iter(func.layout.block_iter().next().unwrap());
+        (insts.next().unwrap(), insts.next().unwrap())
+    };
+    let x = func.data.inst_ref(a).operand.args()[0];
+    let uses = func.data.uses.get_mut(&x).unwrap();
+    uses.remove(&Use { user: a, index: 0 });
+    uses.insert(Use { user: b, index: 1 });
+    let diags: Vec<_> = verify_module(&module)
+        .into_iter()
+        .map(|d| d.to_string())
+        .collect();
+    assert_eq!(
+        diags,
+        vec![
+            "@f, %entry, %a: operand 0 is not recorded as a use",
+            "@f, %entry, %b: operand 1 is recorded as a use of another value",
+        ]
+    );
+}